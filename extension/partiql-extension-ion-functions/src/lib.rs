@@ -155,6 +155,7 @@ fn parse_ion_buff<'a, I: 'a + ToIonDataSource>(input: I) -> BaseTableExprResult<
 mod tests {
     use super::*;
 
+    use partiql_ast::arena::Arena;
     use partiql_catalog::{Catalog, Extension, PartiqlCatalog};
     use partiql_eval::env::basic::MapBindings;
     use partiql_eval::plan::EvaluationMode;
@@ -163,8 +164,8 @@ mod tests {
 
     #[track_caller]
     #[inline]
-    pub(crate) fn parse(statement: &str) -> ParserResult {
-        partiql_parser::Parser::default().parse(statement)
+    pub(crate) fn parse<'a>(statement: &'a str, arena: &'a Arena) -> ParserResult<'a> {
+        partiql_parser::Parser::default().parse(statement, arena)
     }
 
     #[track_caller]
@@ -205,7 +206,8 @@ mod tests {
         ext.load(&mut catalog)
             .expect("ion extension load to succeed");
 
-        let parsed = parse(statement);
+        let arena = Arena::new();
+        let parsed = parse(statement, &arena);
         let lowered = lower(&catalog, &parsed.expect("parse"));
         let bindings = env
             .as_ref()
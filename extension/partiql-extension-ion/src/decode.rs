@@ -1,7 +1,7 @@
 use delegate::delegate;
 use ion_rs::{Decimal, Int, IonError, IonReader, IonType, StreamItem, Symbol};
 use once_cell::sync::Lazy;
-use partiql_value::{Bag, DateTime, List, Tuple, Value};
+use partiql_value::{Bag, BindingsName, DateTime, List, Tuple, Value};
 use regex::RegexSet;
 use rust_decimal::prelude::ToPrimitive;
 
@@ -56,9 +56,29 @@ impl From<rust_decimal::Error> for IonDecodeError {
 /// Result of attempts to decode a [`Value`] from Ion.
 pub type IonDecodeResult = Result<Value, IonDecodeError>;
 
+/// How the fields of decoded structs that are absent or explicitly `null` are decoded.
+///
+/// JSON and Ion do not distinguish absent fields from `null` ones the way PartiQL distinguishes
+/// `MISSING` from `NULL`, yet the distinction changes the results of queries (e.g., of
+/// `IS MISSING` or of `COUNT(t.a)` against `COUNT(*)`).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum NullFields {
+    /// Absent fields are `MISSING` and `null` fields are `NULL`.
+    #[default]
+    Distinct,
+    /// `null` fields are dropped, so that both absent and `null` fields are `MISSING`.
+    NullAsMissing,
+    /// Fields absent from a struct of a list, yet present in other structs of that list, are
+    /// added as `NULL`, so that both absent and `null` fields are `NULL`. Top-level values of
+    /// the stream are decoded independently of one another, so this applies to lists only, e.g.,
+    /// to a JSON array of records.
+    MissingAsNull,
+}
+
 /// Config for construction an Ion decoder.
 pub struct IonDecoderConfig {
     mode: Encoding,
+    null_fields: NullFields,
 }
 
 impl IonDecoderConfig {
@@ -67,12 +87,19 @@ impl IonDecoderConfig {
         self.mode = mode;
         self
     }
+
+    /// Set the decoding of absent & `null` struct fields to `null_fields`
+    pub fn with_null_fields(mut self, null_fields: NullFields) -> Self {
+        self.null_fields = null_fields;
+        self
+    }
 }
 
 impl Default for IonDecoderConfig {
     fn default() -> Self {
         IonDecoderConfig {
             mode: crate::Encoding::Ion,
+            null_fields: NullFields::default(),
         }
     }
 }
@@ -93,7 +120,9 @@ impl IonDecoderBuilder {
         self,
         reader: impl 'a + IonReader<Item = StreamItem, Symbol = Symbol>,
     ) -> Result<IonValueIter<'a>, IonDecodeError> {
-        let decoder = SimpleIonValueDecoder {};
+        let decoder = SimpleIonValueDecoder {
+            null_fields: self.config.null_fields,
+        };
         let inner: Box<dyn Iterator<Item = IonDecodeResult>> = match self.config.mode {
             crate::Encoding::Ion => Box::new(IonValueIterInner { reader, decoder }),
             crate::Encoding::PartiqlEncodedAsIon => {
@@ -198,7 +227,9 @@ fn ion_decimal_to_decimal(ion_dec: Decimal) -> Result<rust_decimal::Decimal, rus
         .or_else(|_| rust_decimal::Decimal::from_scientific(&ion_dec_str))
 }
 
-struct SimpleIonValueDecoder {}
+struct SimpleIonValueDecoder {
+    null_fields: NullFields,
+}
 
 impl<R> IonValueDecoder<R> for SimpleIonValueDecoder
 where
@@ -278,7 +309,7 @@ where
 
     #[inline]
     fn decode_list(&self, reader: &mut R) -> IonDecodeResult {
-        decode_list(self, reader)
+        decode_list(self, self.null_fields, reader)
     }
 
     #[inline]
@@ -288,12 +319,16 @@ where
 
     #[inline]
     fn decode_struct(&self, reader: &mut R) -> IonDecodeResult {
-        decode_struct(self, reader)
+        decode_struct(self, self.null_fields, reader)
     }
 }
 
 #[inline]
-fn decode_list<R>(decoder: &impl IonValueDecoder<R>, reader: &mut R) -> IonDecodeResult
+fn decode_list<R>(
+    decoder: &impl IonValueDecoder<R>,
+    null_fields: NullFields,
+    reader: &mut R,
+) -> IonDecodeResult
 where
     R: IonReader<Item = StreamItem, Symbol = Symbol>,
 {
@@ -309,11 +344,44 @@ where
         values.push(val);
     }
     reader.step_out()?;
+    if null_fields == NullFields::MissingAsNull {
+        add_absent_fields(&mut values);
+    }
     Ok(List::from(values).into())
 }
 
+/// Adds the fields absent from some of the structs of `values` but present in others as `NULL`.
+fn add_absent_fields(values: &mut [Value]) {
+    let mut attrs: Vec<String> = vec![];
+    for value in values.iter() {
+        if let Value::Tuple(tuple) = value {
+            for attr in tuple.attrs() {
+                if !attrs.iter().any(|a| a == attr) {
+                    attrs.push(attr.to_string());
+                }
+            }
+        }
+    }
+    for value in values.iter_mut() {
+        if let Value::Tuple(tuple) = value {
+            for attr in &attrs {
+                if tuple
+                    .get(&BindingsName::CaseSensitive(attr.clone()))
+                    .is_none()
+                {
+                    tuple.insert(attr, Value::Null);
+                }
+            }
+        }
+    }
+}
+
 #[inline]
-fn decode_struct<R>(decoder: &impl IonValueDecoder<R>, reader: &mut R) -> IonDecodeResult
+fn decode_struct<R>(
+    decoder: &impl IonValueDecoder<R>,
+    null_fields: NullFields,
+    reader: &mut R,
+) -> IonDecodeResult
 where
     R: IonReader<Item = StreamItem, Symbol = Symbol>,
 {
@@ -329,6 +397,9 @@ where
             StreamItem::Null(_) => (reader.field_name()?, decoder.decode_null(reader)?),
             StreamItem::Nothing => break 'kv,
         };
+        if null_fields == NullFields::NullAsMissing && matches!(value, Value::Null) {
+            continue;
+        }
         tuple.insert(key.text_or_error()?, value);
     }
     reader.step_out()?;
@@ -517,7 +588,7 @@ where
     #[inline]
     fn decode_list(&self, reader: &mut R) -> IonDecodeResult {
         let is_bag = has_annotation(reader, BAG_ANNOT);
-        let list = decode_list(self, reader);
+        let list = decode_list(self, self.inner.null_fields, reader);
         if is_bag {
             Ok(Bag::from(list?.coerce_to_list()).into())
         } else {
@@ -530,7 +601,7 @@ where
         if has_annotation(reader, TIME_ANNOT) {
             self.decode_time(reader)
         } else {
-            decode_struct(self, reader)
+            decode_struct(self, self.inner.null_fields, reader)
         }
     }
 
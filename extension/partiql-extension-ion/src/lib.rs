@@ -7,7 +7,7 @@ pub use common::Encoding;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::decode::{IonDecodeResult, IonDecoderBuilder, IonDecoderConfig};
+    use crate::decode::{IonDecodeResult, IonDecoderBuilder, IonDecoderConfig, NullFields};
     use crate::encode::{IonEncodeError, IonEncoderBuilder, IonEncoderConfig};
     use itertools::Itertools;
 
@@ -414,4 +414,36 @@ mod tests {
             tuple![("k", list![1, 2, 3])],
         );
     }
+
+    #[test]
+    fn partiql_value_from_json_null_fields() {
+        fn decode_json(contents: &str, null_fields: NullFields) -> Value {
+            let reader = ion_rs::ReaderBuilder::new()
+                .build(contents)
+                .expect("reader");
+            let mut iter =
+                IonDecoderBuilder::new(IonDecoderConfig::default().with_null_fields(null_fields))
+                    .build(reader)
+                    .expect("decoder");
+            iter.next().expect("value").expect("decoded value")
+        }
+
+        let json = r#"[{"a": 1, "b": null}, {"a": 2}]"#;
+        assert_eq!(
+            decode_json(json, NullFields::Distinct),
+            list![tuple![("a", 1), ("b", Value::Null)], tuple![("a", 2)]].into()
+        );
+        assert_eq!(
+            decode_json(json, NullFields::NullAsMissing),
+            list![tuple![("a", 1)], tuple![("a", 2)]].into()
+        );
+        assert_eq!(
+            decode_json(json, NullFields::MissingAsNull),
+            list![
+                tuple![("a", 1), ("b", Value::Null)],
+                tuple![("a", 2), ("b", Value::Null)]
+            ]
+            .into()
+        );
+    }
 }
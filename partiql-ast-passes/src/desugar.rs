@@ -0,0 +1,206 @@
+//! Desugaring of queries into the core of PartiQL.
+//!
+//! [`desugar`] rewrites a query in place such that later passes only need to handle its core
+//! forms:
+//!
+//! - the implicit aliases of `FROM` items, `GROUP BY` keys, and `SELECT`-list items that are
+//!   variable references or paths are made explicit, e.g., `FROM t.items` becomes
+//!   `FROM t.items AS items`
+//! - other `SELECT`-list items are given the aliases `_1`, `_2`, ... by their (1-based) position
+//!   in the list, e.g., `SELECT a + 1` becomes `SELECT a + 1 AS _1`
+//! - `SELECT <items>` becomes `SELECT VALUE` of a tuple constructor, e.g., `SELECT a, b + 1 AS c`
+//!   becomes `SELECT VALUE {'a': a, 'c': b + 1}`
+//!
+//! A `SELECT` list including an `<expr>.*` item keeps its form, as the attributes of such an item
+//! are only known when the query is evaluated, as do `SELECT *` and `PIVOT`. `FROM` items and
+//! `GROUP BY` keys whose aliases cannot be inferred are left without one; they cannot be
+//! referenced by the query.
+//!
+//! ```
+//! let arena = partiql_ast::arena::Arena::new();
+//! let mut parsed = partiql_parser::Parser::default()
+//!     .parse("SELECT t.a, t.b * 2 FROM t", &arena)
+//!     .expect("parse");
+//! partiql_ast_passes::desugar::desugar(&mut parsed.ast, &arena);
+//! assert_eq!(
+//!     parsed.ast.node.to_string(),
+//!     "SELECT VALUE {'a': t.a, '_2': t.b * 2} FROM t AS t"
+//! );
+//! ```
+
+use crate::name_resolver::infer_alias;
+use partiql_ast::arena::{self, Arena};
+use partiql_ast::ast;
+use partiql_ast::visit::{Traverse, Visit, VisitMut, Visitor, VisitorMut};
+
+/// Desugars `query` in place, allocating the nodes it adds in `arena`; see the
+/// [module documentation](self).
+pub fn desugar<'a>(query: &mut ast::AstNode<ast::TopLevelQuery<'a>>, arena: &'a Arena) {
+    let mut max_id = MaxNodeId::default();
+    query.visit(&mut max_id);
+    let mut desugarer = Desugarer {
+        arena,
+        next_id: max_id.0 + 1,
+    };
+    query.visit_mut(&mut desugarer);
+}
+
+/// Finds the greatest [`ast::NodeId`] of an AST, so that nodes added to it get unique ids.
+#[derive(Default)]
+struct MaxNodeId(u32);
+
+impl<'ast> Visitor<'ast> for MaxNodeId {
+    fn enter_ast_node(&mut self, id: ast::NodeId) -> Traverse {
+        self.0 = self.0.max(id.0);
+        Traverse::Continue
+    }
+}
+
+struct Desugarer<'a> {
+    arena: &'a Arena,
+    next_id: u32,
+}
+
+impl<'a> Desugarer<'a> {
+    fn node<T>(&mut self, node: T) -> ast::AstNode<T> {
+        let id = ast::NodeId(self.next_id);
+        self.next_id += 1;
+        ast::AstNode { id, node }
+    }
+}
+
+impl<'a> VisitorMut<'a> for Desugarer<'a> {
+    fn enter_from_let(&mut self, from_let: &mut ast::FromLet<'a>) -> Traverse {
+        if from_let.as_alias.is_none() {
+            from_let.as_alias = infer_alias(&from_let.expr);
+        }
+        Traverse::Continue
+    }
+
+    fn enter_group_key(&mut self, group_key: &mut ast::GroupKey<'a>) -> Traverse {
+        if group_key.as_alias.is_none() {
+            group_key.as_alias = infer_alias(&group_key.expr);
+        }
+        Traverse::Continue
+    }
+
+    fn exit_projection(&mut self, projection: &mut ast::Projection<'a>) -> Traverse {
+        let ast::ProjectionKind::ProjectList(items) = &mut projection.kind else {
+            return Traverse::Continue;
+        };
+        for (position, item) in items.iter_mut().enumerate() {
+            if let ast::ProjectItem::ProjectExpr(ast::ProjectExpr { expr, as_alias }) =
+                &mut item.node
+            {
+                if as_alias.is_none() {
+                    *as_alias = Some(infer_alias(expr).unwrap_or_else(|| ast::SymbolPrimitive {
+                        value: self.arena.alloc_str(&format!("_{}", position + 1)),
+                        case: ast::CaseSensitivity::CaseInsensitive,
+                    }));
+                }
+            }
+        }
+
+        if items
+            .iter()
+            .all(|item| matches!(item.node, ast::ProjectItem::ProjectExpr(_)))
+        {
+            let arena = self.arena;
+            let items = std::mem::replace(items, arena::Vec::new_in(arena));
+            let fields = items
+                .into_iter()
+                .filter_map(|item| match item.node {
+                    ast::ProjectItem::ProjectExpr(ast::ProjectExpr { expr, as_alias }) => {
+                        Some((expr, as_alias?))
+                    }
+                    ast::ProjectItem::ProjectAll(_) => None,
+                })
+                .map(|(expr, as_alias)| ast::ExprPair {
+                    first: arena::Box::new_in(
+                        ast::Expr::Lit(self.node(ast::Lit::CharStringLit(as_alias.value))),
+                        arena,
+                    ),
+                    second: expr,
+                });
+            let fields = arena::Vec::from_iter_in(fields, arena);
+            let tuple = ast::Expr::Struct(self.node(ast::Struct { fields }));
+            projection.kind = ast::ProjectionKind::ProjectValue(arena::Box::new_in(tuple, arena));
+        }
+        Traverse::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn desugared(query: &str) -> String {
+        let arena = Arena::new();
+        let mut parsed = partiql_parser::Parser::default()
+            .parse(query, &arena)
+            .expect("Expect successful parse");
+        desugar(&mut parsed.ast, &arena);
+        parsed.ast.node.to_string()
+    }
+
+    #[test]
+    fn select_list() {
+        assert_eq!(
+            desugared("SELECT a, t.b, t['c'], d + 1, e AS x FROM t"),
+            "SELECT VALUE {'a': a, 'b': t.b, '_3': t['c'], '_4': d + 1, 'x': e} FROM t AS t"
+        );
+        assert_eq!(
+            desugared("SELECT DISTINCT \"A\" FROM t"),
+            "SELECT DISTINCT VALUE {'A': \"A\"} FROM t AS t"
+        );
+        // the attributes of `<expr>.*` items are not known until evaluation
+        assert_eq!(
+            desugared("SELECT t.*, a + 1 FROM t"),
+            "SELECT t.*, a + 1 AS _2 FROM t AS t"
+        );
+        assert_eq!(desugared("SELECT * FROM t"), "SELECT * FROM t AS t");
+    }
+
+    #[test]
+    fn implicit_aliases() {
+        assert_eq!(
+            desugared("SELECT VALUE i FROM t, t.items AT n, @x.y[0] GROUP BY t.a, a + 1"),
+            "SELECT VALUE i FROM t AS t CROSS JOIN t.items AS items AT n CROSS JOIN @x.y[0] \
+             GROUP BY t.a AS a, a + 1"
+        );
+    }
+
+    #[test]
+    fn subqueries() {
+        assert_eq!(
+            desugared("SELECT (SELECT b FROM u) AS s FROM (SELECT a FROM t)"),
+            "SELECT VALUE {'s': (SELECT VALUE {'b': b} FROM u AS u)} \
+             FROM (SELECT VALUE {'a': a} FROM t AS t)"
+        );
+    }
+
+    #[test]
+    fn unique_node_ids() {
+        let arena = Arena::new();
+        let mut parsed = partiql_parser::Parser::default()
+            .parse("SELECT a, b FROM t", &arena)
+            .expect("Expect successful parse");
+        desugar(&mut parsed.ast, &arena);
+
+        #[derive(Default)]
+        struct NodeIds(Vec<u32>);
+        impl<'ast> Visitor<'ast> for NodeIds {
+            fn enter_ast_node(&mut self, id: ast::NodeId) -> Traverse {
+                self.0.push(id.0);
+                Traverse::Continue
+            }
+        }
+        let mut ids = NodeIds::default();
+        parsed.ast.visit(&mut ids);
+        let count = ids.0.len();
+        ids.0.sort();
+        ids.0.dedup();
+        assert_eq!(ids.0.len(), count);
+    }
+}
@@ -35,6 +35,21 @@ pub enum AstTransformError {
     #[error("Unsupported aggregation function: {0}")]
     UnsupportedAggregationFunction(String),
 
+    /// Indicates that an aggregation or window function is called where aggregation is not
+    /// allowed (e.g., outside of a query).
+    #[error("Illegal aggregation: {0}")]
+    IllegalAggregation(String),
+
+    /// Indicates that a `FROM` item references a variable of a preceding item where that is not
+    /// allowed (e.g., the right side of a `RIGHT` or `FULL` join referencing its left side).
+    #[error("Illegal lateral reference: {0}")]
+    IllegalLateralReference(String),
+
+    /// Indicates that a name is declared more than once where names must be unique (e.g., the
+    /// common table expressions of a `WITH` clause).
+    #[error("Duplicate name: {0}")]
+    DuplicateName(String),
+
     /// Any other lowering error.
     #[error("Lowering error: {0}")]
     Unknown(String),
@@ -4,6 +4,8 @@
 //!
 //! This API is currently unstable and subject to change.
 
+pub mod desugar;
 pub mod error;
+pub mod lint;
 pub mod name_resolver;
 pub mod partiql_typer;
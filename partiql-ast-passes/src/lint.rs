@@ -0,0 +1,352 @@
+//! A lint framework for PartiQL ASTs.
+//!
+//! A [`Linter`] runs a set of [`LintRule`]s over a query, collecting [`LintWarning`]s that point
+//! at the AST node (and, if known, the source location) they concern. Custom rules are written
+//! as [`Visitor`]s:
+//!
+//! ```
+//! use partiql_ast::ast;
+//! use partiql_ast::visit::{Traverse, Visit, Visitor};
+//! use partiql_ast_passes::lint::{LintRule, Linter};
+//!
+//! /// Warns on every `SELECT *`
+//! #[derive(Default)]
+//! struct NoSelectStar {
+//!     id_stack: Vec<ast::NodeId>,
+//!     found: Vec<(ast::NodeId, String)>,
+//! }
+//!
+//! impl<'ast> Visitor<'ast> for NoSelectStar {
+//!     fn enter_ast_node(&mut self, id: ast::NodeId) -> Traverse {
+//!         self.id_stack.push(id);
+//!         Traverse::Continue
+//!     }
+//!     fn exit_ast_node(&mut self, _id: ast::NodeId) -> Traverse {
+//!         self.id_stack.pop();
+//!         Traverse::Continue
+//!     }
+//!     fn enter_projection(&mut self, projection: &'ast ast::Projection) -> Traverse {
+//!         if let ast::ProjectionKind::ProjectStar = projection.kind {
+//!             let id = *self.id_stack.last().unwrap();
+//!             self.found.push((id, "avoid `SELECT *`".to_string()));
+//!         }
+//!         Traverse::Continue
+//!     }
+//! }
+//!
+//! impl LintRule for NoSelectStar {
+//!     fn name(&self) -> &'static str {
+//!         "no-select-star"
+//!     }
+//!     fn check(&mut self, query: &ast::AstNode<ast::TopLevelQuery>) -> Vec<(ast::NodeId, String)> {
+//!         query.visit(self);
+//!         std::mem::take(&mut self.found)
+//!     }
+//! }
+//!
+//! let arena = partiql_ast::arena::Arena::new();
+//! let parsed = partiql_parser::Parser::default()
+//!     .parse("SELECT * FROM t", &arena)
+//!     .expect("parse");
+//! let warnings = Linter::empty()
+//!     .with_rule(NoSelectStar::default())
+//!     .lint(&parsed.ast, &parsed.locations);
+//! assert_eq!(warnings.len(), 1);
+//! assert_eq!(warnings[0].rule, "no-select-star");
+//! ```
+
+use partiql_ast::ast;
+use partiql_ast::visit::{Traverse, Visit, Visitor};
+use partiql_source_map::location::{BytePosition, Location};
+use partiql_source_map::metadata::LocationMap;
+use std::fmt;
+
+/// A warning reported by a [`LintRule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// The name of the rule which reported the warning.
+    pub rule: &'static str,
+    pub message: String,
+    /// The AST node the warning concerns.
+    pub node: ast::NodeId,
+    /// The source location of [`node`](Self::node), if known.
+    pub location: Option<Location<BytePosition>>,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} at `{}`: {}", self.rule, location, self.message),
+            None => write!(f, "{}: {}", self.rule, self.message),
+        }
+    }
+}
+
+/// A single lint check over a query.
+pub trait LintRule {
+    /// The name by which warnings from this rule are reported.
+    fn name(&self) -> &'static str;
+
+    /// Check `query`, returning the offending nodes along with a message for each.
+    fn check(&mut self, query: &ast::AstNode<ast::TopLevelQuery>) -> Vec<(ast::NodeId, String)>;
+}
+
+/// Runs a set of [`LintRule`]s over queries.
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl Default for Linter {
+    /// Creates a [`Linter`] with all built-in rules.
+    fn default() -> Self {
+        Linter::empty()
+            .with_rule(CartesianProduct::default())
+            .with_rule(OrderByWithoutLimit::default())
+            .with_rule(NonDeterministicGroupBy::default())
+    }
+}
+
+impl Linter {
+    /// Creates a [`Linter`] with no rules.
+    pub fn empty() -> Self {
+        Linter { rules: vec![] }
+    }
+
+    /// Adds `rule` to the rules this [`Linter`] checks.
+    pub fn with_rule(mut self, rule: impl LintRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Check `query` against all rules, using `locations` to attach source locations to warnings.
+    pub fn lint(
+        &mut self,
+        query: &ast::AstNode<ast::TopLevelQuery>,
+        locations: &LocationMap,
+    ) -> Vec<LintWarning> {
+        self.rules
+            .iter_mut()
+            .flat_map(|rule| {
+                let name = rule.name();
+                rule.check(query)
+                    .into_iter()
+                    .map(move |(node, message)| LintWarning {
+                        rule: name,
+                        message,
+                        node,
+                        location: locations.get(&node).cloned(),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Warns on a `FROM` clause that is the cartesian product of independent sources (e.g.,
+/// `FROM a, b` or `FROM a CROSS JOIN b`) with no `WHERE` clause to restrict it.
+#[derive(Debug, Default)]
+pub struct CartesianProduct {
+    found: Vec<(ast::NodeId, String)>,
+}
+
+impl CartesianProduct {
+    /// `true` if `source` is a join without predicate whose right side doesn't depend on its left
+    /// (i.e., is a simple variable reference rather than a path such as `t.items`).
+    fn is_cartesian(source: &ast::FromSource) -> bool {
+        fn is_independent(source: &ast::FromSource) -> bool {
+            match source {
+                ast::FromSource::FromLet(from_let) => {
+                    matches!(*from_let.node.expr, ast::Expr::VarRef(_))
+                }
+                ast::FromSource::Join(_) => false,
+            }
+        }
+
+        match source {
+            ast::FromSource::FromLet(_) => false,
+            ast::FromSource::Join(join) => {
+                let join = &join.node;
+                let unrestricted = join.predicate.is_none()
+                    && matches!(join.kind, ast::JoinKind::Cross | ast::JoinKind::Inner);
+                (unrestricted && is_independent(&join.right))
+                    || Self::is_cartesian(&join.left)
+                    || Self::is_cartesian(&join.right)
+            }
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for CartesianProduct {
+    fn enter_select(&mut self, select: &'ast ast::Select) -> Traverse {
+        if let (Some(from), None) = (&select.from, &select.where_clause) {
+            if Self::is_cartesian(&from.node.source) {
+                self.found.push((
+                    from.id,
+                    "cartesian product of FROM sources without a WHERE clause".to_string(),
+                ));
+            }
+        }
+        Traverse::Continue
+    }
+}
+
+impl LintRule for CartesianProduct {
+    fn name(&self) -> &'static str {
+        "cartesian-product"
+    }
+
+    fn check(&mut self, query: &ast::AstNode<ast::TopLevelQuery>) -> Vec<(ast::NodeId, String)> {
+        query.visit(self);
+        std::mem::take(&mut self.found)
+    }
+}
+
+/// Warns on an `ORDER BY` without a `LIMIT` in a subquery; as the result of a subquery is
+/// generally consumed as a bag, the ordering is likely to be lost.
+#[derive(Debug, Default)]
+pub struct OrderByWithoutLimit {
+    query_depth: usize,
+    found: Vec<(ast::NodeId, String)>,
+}
+
+impl<'ast> Visitor<'ast> for OrderByWithoutLimit {
+    fn enter_query(&mut self, query: &'ast ast::Query) -> Traverse {
+        self.query_depth += 1;
+        if self.query_depth > 1 && query.limit_offset.is_none() {
+            if let Some(order_by) = &query.order_by {
+                self.found.push((
+                    order_by.id,
+                    "ORDER BY without LIMIT in a subquery".to_string(),
+                ));
+            }
+        }
+        Traverse::Continue
+    }
+
+    fn exit_query(&mut self, _query: &'ast ast::Query) -> Traverse {
+        self.query_depth -= 1;
+        Traverse::Continue
+    }
+}
+
+impl LintRule for OrderByWithoutLimit {
+    fn name(&self) -> &'static str {
+        "order-by-without-limit"
+    }
+
+    fn check(&mut self, query: &ast::AstNode<ast::TopLevelQuery>) -> Vec<(ast::NodeId, String)> {
+        query.visit(self);
+        std::mem::take(&mut self.found)
+    }
+}
+
+/// Functions whose result may differ between calls with the same arguments.
+const NON_DETERMINISTIC_FNS: [&str; 4] = ["utcnow", "rand", "random", "uuid"];
+
+/// Warns on calls to non-deterministic functions (e.g., `utcnow()`) in `GROUP BY` keys.
+#[derive(Debug, Default)]
+pub struct NonDeterministicGroupBy {
+    id_stack: Vec<ast::NodeId>,
+    group_key_depth: usize,
+    found: Vec<(ast::NodeId, String)>,
+}
+
+impl<'ast> Visitor<'ast> for NonDeterministicGroupBy {
+    fn enter_ast_node(&mut self, id: ast::NodeId) -> Traverse {
+        self.id_stack.push(id);
+        Traverse::Continue
+    }
+
+    fn exit_ast_node(&mut self, _id: ast::NodeId) -> Traverse {
+        self.id_stack.pop();
+        Traverse::Continue
+    }
+
+    fn enter_group_key(&mut self, _group_key: &'ast ast::GroupKey) -> Traverse {
+        self.group_key_depth += 1;
+        Traverse::Continue
+    }
+
+    fn exit_group_key(&mut self, _group_key: &'ast ast::GroupKey) -> Traverse {
+        self.group_key_depth -= 1;
+        Traverse::Continue
+    }
+
+    fn enter_call(&mut self, call: &'ast ast::Call) -> Traverse {
+        let name = &call.func_name.value;
+        let is_non_deterministic = NON_DETERMINISTIC_FNS
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(name));
+        if self.group_key_depth > 0 && is_non_deterministic {
+            if let Some(id) = self.id_stack.last() {
+                self.found.push((
+                    *id,
+                    format!("non-deterministic function `{name}` in GROUP BY"),
+                ));
+            }
+        }
+        Traverse::Continue
+    }
+}
+
+impl LintRule for NonDeterministicGroupBy {
+    fn name(&self) -> &'static str {
+        "non-deterministic-group-by"
+    }
+
+    fn check(&mut self, query: &ast::AstNode<ast::TopLevelQuery>) -> Vec<(ast::NodeId, String)> {
+        query.visit(self);
+        std::mem::take(&mut self.found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn lint(query: &str) -> Vec<&'static str> {
+        let arena = partiql_ast::arena::Arena::new();
+        let parsed = partiql_parser::Parser::default()
+            .parse(query, &arena)
+            .expect("Expect successful parse");
+        Linter::default()
+            .lint(&parsed.ast, &parsed.locations)
+            .into_iter()
+            .map(|w| {
+                assert!(w.location.is_some());
+                w.rule
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cartesian_product() {
+        assert_eq!(lint("SELECT * FROM a, b"), vec!["cartesian-product"]);
+        assert_eq!(
+            lint("SELECT * FROM a CROSS JOIN b"),
+            vec!["cartesian-product"]
+        );
+        assert!(lint("SELECT * FROM a, b WHERE a.id = b.id").is_empty());
+        assert!(lint("SELECT * FROM a INNER JOIN b ON a.id = b.id").is_empty());
+        assert!(lint("SELECT * FROM a, a.items AS i").is_empty());
+    }
+
+    #[test]
+    fn order_by_without_limit() {
+        assert!(lint("SELECT a FROM t ORDER BY a").is_empty());
+        assert_eq!(
+            lint("SELECT a FROM (SELECT a FROM t ORDER BY a) AS s"),
+            vec!["order-by-without-limit"]
+        );
+        assert!(lint("SELECT a FROM (SELECT a FROM t ORDER BY a LIMIT 1) AS s").is_empty());
+    }
+
+    #[test]
+    fn non_deterministic_group_by() {
+        assert_eq!(
+            lint("SELECT k FROM t GROUP BY UTCNOW() AS k"),
+            vec!["non-deterministic-group-by"]
+        );
+        assert!(lint("SELECT k FROM t GROUP BY upper(t.a) AS k").is_empty());
+    }
+}
@@ -11,8 +11,8 @@ type FnvIndexSet<T> = IndexSet<T, FnvBuildHasher>;
 type FnvIndexMap<K, V> = IndexMap<K, V, FnvBuildHasher>;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub struct NameRef {
-    pub sym: ast::SymbolPrimitive,
+pub struct NameRef<'a> {
+    pub sym: ast::SymbolPrimitive<'a>,
     pub lookup: Vec<NameLookup>,
 }
 
@@ -23,25 +23,28 @@ pub enum NameLookup {
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub enum Symbol {
-    Known(ast::SymbolPrimitive),
+pub enum Symbol<'a> {
+    Known(ast::SymbolPrimitive<'a>),
     Unknown(u32),
 }
 
-type NameRefs = FnvIndexSet<NameRef>;
-type Names = FnvIndexSet<Symbol>;
+type NameRefs<'a> = FnvIndexSet<NameRef<'a>>;
+type Names<'a> = FnvIndexSet<Symbol<'a>>;
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
-pub struct KeySchema {
-    pub consume: NameRefs,
-    pub produce: Names,
+pub struct KeySchema<'a> {
+    pub consume: NameRefs<'a>,
+    pub produce: Names<'a>,
 }
 
 #[derive(Default, Debug, Clone)]
-pub struct KeyRegistry {
+pub struct KeyRegistry<'a> {
     pub in_scope: FnvIndexMap<ast::NodeId, Vec<ast::NodeId>>,
-    pub schema: FnvIndexMap<ast::NodeId, KeySchema>,
-    pub aliases: FnvIndexMap<ast::NodeId, Symbol>,
+    pub schema: FnvIndexMap<ast::NodeId, KeySchema<'a>>,
+    pub aliases: FnvIndexMap<ast::NodeId, Symbol<'a>>,
+    /// `FROM` items mapped to the preceding `FROM` items whose variables they reference
+    /// (e.g., `o.items` references `o` in `FROM orders AS o, o.items AS i`).
+    pub correlated: FnvIndexMap<ast::NodeId, Vec<ast::NodeId>>,
 }
 
 #[derive(Debug)]
@@ -63,13 +66,13 @@ impl IdGenerator {
     }
 }
 
-type NameOptions = FnvIndexSet<Option<Symbol>>;
+type NameOptions<'a> = FnvIndexSet<Option<Symbol<'a>>>;
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
-struct KeyRefs {
-    pub consume: NameRefs,
-    pub produce_required: Names,
-    pub produce_optional: NameOptions,
+struct KeyRefs<'a> {
+    pub consume: NameRefs<'a>,
+    pub produce_required: Names<'a>,
+    pub produce_optional: NameOptions<'a>,
 }
 
 // The enclosing clause; used, in part, to track whether a name is a 'from path' reference
@@ -83,29 +86,30 @@ enum EnclosingClause {
 /// AST and collecting variable references. Also partially infers alias if no `AS` alias
 /// was provided in the query.
 #[derive(Default, Debug)]
-pub struct NameResolver {
+pub struct NameResolver<'a> {
     // environment stack tracking
     id_path_to_root: Vec<ast::NodeId>,
     id_child_stack: Vec<Vec<ast::NodeId>>,
-    keyref_stack: Vec<KeyRefs>,
+    keyref_stack: Vec<KeyRefs<'a>>,
     lateral_stack: Vec<Vec<ast::NodeId>>,
     id_gen: IdGenerator,
 
     // data flow tracking
     enclosing_clause: FnvIndexMap<EnclosingClause, Vec<ast::NodeId>>,
     in_scope: FnvIndexMap<ast::NodeId, Vec<ast::NodeId>>,
-    schema: FnvIndexMap<ast::NodeId, KeySchema>,
-    aliases: FnvIndexMap<ast::NodeId, Symbol>,
+    schema: FnvIndexMap<ast::NodeId, KeySchema<'a>>,
+    aliases: FnvIndexMap<ast::NodeId, Symbol<'a>>,
+    correlated: FnvIndexMap<ast::NodeId, Vec<ast::NodeId>>,
 
     // errors that occur during name resolution
     errors: Vec<AstTransformError>,
 }
 
-impl NameResolver {
+impl<'a> NameResolver<'a> {
     pub fn resolve(
         &mut self,
-        query: &ast::AstNode<ast::TopLevelQuery>,
-    ) -> Result<KeyRegistry, AstTransformationError> {
+        query: &'a ast::AstNode<ast::TopLevelQuery>,
+    ) -> Result<KeyRegistry<'a>, AstTransformationError> {
         query.visit(self);
         if !self.errors.is_empty() {
             return Err(AstTransformationError {
@@ -116,10 +120,12 @@ impl NameResolver {
         let in_scope = std::mem::take(&mut self.in_scope);
         let schema = std::mem::take(&mut self.schema);
         let aliases = std::mem::take(&mut self.aliases);
+        let correlated = std::mem::take(&mut self.correlated);
         Ok(KeyRegistry {
             in_scope,
             schema,
             aliases,
+            correlated,
         })
     }
 
@@ -176,19 +182,19 @@ impl NameResolver {
     }
 
     #[inline]
-    fn exit_keyref(&mut self) -> Result<KeyRefs, AstTransformError> {
+    fn exit_keyref(&mut self) -> Result<KeyRefs<'a>, AstTransformError> {
         self.keyref_stack.pop().ok_or_else(|| {
             AstTransformError::IllegalState("Expected non-empty keyrefs".to_string())
         })
     }
 
     #[inline]
-    fn push_consume_name(&mut self, name: NameRef) {
+    fn push_consume_name(&mut self, name: NameRef<'a>) {
         self.keyref_stack.last_mut().unwrap().consume.insert(name);
     }
 }
 
-impl<'ast> Visitor<'ast> for NameResolver {
+impl<'ast> Visitor<'ast> for NameResolver<'ast> {
     fn enter_ast_node(&mut self, id: ast::NodeId) -> Traverse {
         self.id_path_to_root.push(id);
         if let Some(children) = self.id_child_stack.last_mut() {
@@ -331,6 +337,72 @@ impl<'ast> Visitor<'ast> for NameResolver {
             self.aliases.insert(id, alias.clone());
         }
 
+        // Record the preceding items in this `FROM` whose variables this item references
+        let correlated: Vec<_> = self
+            .lateral_stack
+            .last()
+            .unwrap()
+            .iter()
+            .filter(|preceding| **preceding != id)
+            .filter(|preceding| {
+                self.schema.get(*preceding).is_some_and(|schema| {
+                    schema.produce.iter().any(|produced| {
+                        consume
+                            .iter()
+                            .any(|name_ref| symbol_matches(produced, &name_ref.sym))
+                    })
+                })
+            })
+            .copied()
+            .collect();
+        if !correlated.is_empty() {
+            self.correlated.insert(id, correlated);
+        }
+
+        self.schema.insert(id, KeySchema { consume, produce });
+        Traverse::Continue
+    }
+
+    fn enter_let(&mut self, _let: &'ast ast::Let) -> Traverse {
+        let id = *self.current_node();
+        self.enter_keyref();
+
+        // This `LET` is in-scope of variables defined by the `FROM` of its enclosing query
+        let in_scope = self
+            .id_path_to_root
+            .iter()
+            .rev()
+            .skip(1)
+            .find_map(|id| self.in_scope.get(id))
+            .cloned()
+            .unwrap_or_default();
+        self.in_scope.insert(id, in_scope);
+        Traverse::Continue
+    }
+
+    fn exit_let(&mut self, let_clause: &'ast ast::Let) -> Traverse {
+        let id = *self.current_node();
+        let KeyRefs { consume, .. } = match self.exit_keyref() {
+            Ok(kr) => kr,
+            Err(e) => {
+                self.errors.push(e);
+                return Traverse::Stop;
+            }
+        };
+
+        // Scopes above this `LET` in the AST are in-scope to use variables defined by this `LET`
+        for in_scope in self.id_path_to_root.iter().rev().skip(1) {
+            self.in_scope
+                .entry(*in_scope)
+                .or_insert_with(Vec::new)
+                .push(id);
+        }
+
+        let produce: Names = let_clause
+            .let_bindings
+            .iter()
+            .map(|binding| Symbol::Known(binding.as_alias.clone()))
+            .collect();
         self.schema.insert(id, KeySchema { consume, produce });
         Traverse::Continue
     }
@@ -416,9 +488,22 @@ impl<'ast> Visitor<'ast> for NameResolver {
     }
 }
 
+/// Whether a reference to `sym` may refer to the `produced` name.
+fn symbol_matches(produced: &Symbol<'_>, sym: &ast::SymbolPrimitive<'_>) -> bool {
+    match produced {
+        Symbol::Known(produced) => match (&produced.case, &sym.case) {
+            (ast::CaseSensitivity::CaseSensitive, ast::CaseSensitivity::CaseSensitive) => {
+                produced.value == sym.value
+            }
+            _ => produced.value.eq_ignore_ascii_case(sym.value),
+        },
+        Symbol::Unknown(_) => false,
+    }
+}
+
 /// Attempt to infer an alias for a simple variable reference expression.
 /// For example infer such that  `SELECT a, b.c.d.e ...` <=> `SELECT a as a, b.c.d.e as e`  
-fn infer_alias(expr: &ast::Expr) -> Option<ast::SymbolPrimitive> {
+pub(crate) fn infer_alias<'a>(expr: &ast::Expr<'a>) -> Option<ast::SymbolPrimitive<'a>> {
     match expr {
         ast::Expr::VarRef(ast::AstNode { node, .. }) => Some(node.name.clone()),
         ast::Expr::Path(ast::AstNode { node, .. }) => match node.steps.last() {
@@ -120,6 +120,8 @@ impl<'c, 'ast> Visitor<'ast> for AstPartiqlTyper<'c> {
             Lit::ListLit(_) => TypeKind::Array(ArrayType::new_any()),
             Lit::BagLit(_) => TypeKind::Bag(BagType::new_any()),
             Lit::TypedLit(_, _) => todo!(),
+            // intervals have no type of their own yet
+            Lit::IntervalLit(_) => TypeKind::Any,
         };
 
         let ty = PartiqlType::new(kind);
@@ -239,6 +241,7 @@ mod tests {
             run_literal_test("{'a': 1, 'b': 3, 'c': [1, 2]}"),
             TypeKind::Struct(_)
         );
+        assert_matches!(run_literal_test("INTERVAL '2' DAY"), TypeKind::Any);
     }
 
     #[test]
@@ -256,8 +259,9 @@ mod tests {
         q: &str,
         catalog: &dyn Catalog,
     ) -> Result<AstTypeMap<PartiqlType>, AstTransformationError> {
+        let arena = partiql_ast::arena::Arena::new();
         let parsed = partiql_parser::Parser::default()
-            .parse(q)
+            .parse(q, &arena)
             .expect("Expect successful parse");
 
         let typer = AstPartiqlTyper::new(catalog);
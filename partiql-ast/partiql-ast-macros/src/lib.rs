@@ -12,6 +12,13 @@ pub fn visit_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     gen.into()
 }
 
+#[proc_macro_derive(VisitMut, attributes(visit))]
+pub fn visit_mut_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    let gen = impl_visit_mut(&ast);
+    gen.into()
+}
+
 #[derive(FromDeriveInput)]
 #[darling(attributes(visit))]
 struct VisitItemOptions {
@@ -47,7 +54,7 @@ fn should_skip_variant(variant: &syn::Variant) -> bool {
         .unwrap_or(false)
 }
 
-fn impl_visit(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
+fn enter_exit_fn_names(ast: &syn::DeriveInput) -> (Ident, Ident) {
     let visit_fn_name = &ast.ident.to_string().to_snake_case();
     let enter_fn_name = Ident::new(
         &format!("enter_{visit_fn_name}"),
@@ -57,12 +64,20 @@ fn impl_visit(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
         &format!("exit_{visit_fn_name}"),
         proc_macro2::Span::call_site(),
     );
+    (enter_fn_name, exit_fn_name)
+}
 
-    let visit_children = (!should_skip_recurse(ast)).then(|| impl_visit_children(&ast));
+fn impl_visit(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
+    let (enter_fn_name, exit_fn_name) = enter_exit_fn_names(ast);
+
+    let visit_fn = format_ident!("visit");
+    let visit_children =
+        (!should_skip_recurse(ast)).then(|| impl_visit_children(&ast, &visit_fn, quote!(&self)));
 
     let ast_name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
     quote! {
-        impl crate::visit::Visit for #ast_name {
+        impl #impl_generics crate::visit::Visit for #ast_name #ty_generics #where_clause {
             fn visit<'v, V>(&'v self, v: &mut V) -> crate::visit::Traverse
             where
                 V: crate::visit::Visitor<'v>,
@@ -77,7 +92,47 @@ fn impl_visit(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
     }
 }
 
-fn impl_visit_children(ast: &&DeriveInput) -> TokenStream {
+fn impl_visit_mut(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
+    let (enter_fn_name, exit_fn_name) = enter_exit_fn_names(ast);
+
+    let visit_fn = format_ident!("visit_mut");
+    let visit_children =
+        (!should_skip_recurse(ast)).then(|| impl_visit_children(&ast, &visit_fn, quote!(self)));
+
+    let ast_name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    // nodes are rewritten with nodes allocated in the arena of their AST, whose lifetime is that
+    // of the node's type, if any
+    let (impl_generics, lifetime) = match ast.generics.lifetimes().next() {
+        Some(lifetime) => {
+            let lifetime = &lifetime.lifetime;
+            (quote!(#impl_generics), quote!(#lifetime))
+        }
+        None => (quote!(<'a>), quote!('a)),
+    };
+    quote! {
+        impl #impl_generics crate::visit::VisitMut<#lifetime> for #ast_name #ty_generics #where_clause {
+            fn visit_mut<V>(&mut self, v: &mut V) -> crate::visit::Traverse
+            where
+                V: crate::visit::VisitorMut<#lifetime>,
+            {
+                if v.#enter_fn_name(self) == crate::visit::Traverse::Stop {
+                    return crate::visit::Traverse::Stop
+                }
+                #visit_children
+                v.#exit_fn_name(self)
+            }
+        }
+    }
+}
+
+/// Generates the traversal of the children of `ast`, calling `visit_fn` on each child of the
+/// enum variant `scrutinee` or on each field of `self`.
+fn impl_visit_children(
+    ast: &&DeriveInput,
+    visit_fn: &Ident,
+    scrutinee: TokenStream,
+) -> TokenStream {
     match &ast.data {
         Data::Enum(e) => {
             let enum_name = std::iter::repeat(&ast.ident);
@@ -96,8 +151,8 @@ fn impl_visit_children(ast: &&DeriveInput) -> TokenStream {
             });
 
             quote! {
-                if match &self {
-                    #(#enum_name::#variants(child) => child.visit(v),)*
+                if match #scrutinee {
+                    #(#enum_name::#variants(child) => child.#visit_fn(v),)*
                     #else_clause
                 } == crate::visit::Traverse::Stop {
                     return crate::visit::Traverse::Stop
@@ -132,7 +187,7 @@ fn impl_visit_children(ast: &&DeriveInput) -> TokenStream {
                 Fields::Unit => vec![],
             };
             quote! {
-                #(if self.#fields.visit(v) == crate::visit::Traverse::Stop {
+                #(if self.#fields.#visit_fn(v) == crate::visit::Traverse::Stop {
                     return crate::visit::Traverse::Stop
                 })*
             }
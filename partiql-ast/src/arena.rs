@@ -0,0 +1,21 @@
+//! The arena in which the nodes of ASTs are allocated.
+//!
+//! An AST borrows its [`Arena`] for its lifetime `'a`: its children are held by the arena's
+//! [`Box`]es and [`Vec`]s rather than by the global allocator's, so that building (and dropping)
+//! the AST of a large query does not make an allocation for each of its nodes. Likewise, the text
+//! of its identifiers and string literals is borrowed from the query it was parsed from, or, when
+//! decoding changes it (e.g., `'it''s'`), from a copy in the arena.
+//!
+//! ```
+//! use partiql_ast::arena::{Arena, Box};
+//! use partiql_ast::ast::{AstNode, Expr, Lit, NodeId, UniOp, UniOpKind};
+//!
+//! let arena = Arena::new();
+//! let one = Expr::Lit(AstNode { id: NodeId(1), node: Lit::Int64Lit(1) });
+//! let neg = UniOp { kind: UniOpKind::Neg, expr: Box::new_in(one, &arena) };
+//! assert_eq!(Expr::UniOp(AstNode { id: NodeId(2), node: neg }).to_string(), "-1");
+//! ```
+
+pub use bumpalo::boxed::Box;
+pub use bumpalo::collections::Vec;
+pub use bumpalo::Bump as Arena;
@@ -4,6 +4,9 @@
 //! Two main entities in the module are [`Item`] and [`AstNode`]. `AstNode` represents an AST node
 //! and `Item` represents a PartiQL statement type, e.g. query, data definition language (DDL)
 //! data manipulation language (DML).
+//!
+//! The nodes of an AST are allocated in an [`Arena`](crate::arena::Arena), which the AST borrows
+//! for its lifetime `'a`.
 
 // As more changes to this AST are expected, unless explicitly advised, using the structures exposed
 // in this crate directly is not recommended.
@@ -11,148 +14,197 @@
 use indexmap::IndexMap;
 use rust_decimal::Decimal as RustDecimal;
 
-use std::fmt;
-
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use partiql_ast_macros::Visit;
+use partiql_ast_macros::{Visit, VisitMut};
+
+use crate::arena::{Box, Vec};
 
+/// A side table of metadata about AST nodes keyed by their [`NodeId`]s, e.g., their locations in
+/// the text of a statement, their types, or the comments attached to them.
 pub type AstTypeMap<T> = IndexMap<NodeId, T>;
 
+/// The identifier of an [`AstNode`], unique among the nodes of the AST it belongs to.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NodeId(pub u32);
 
+/// Whether the ASTs `lhs` and `rhs` are equal disregarding the [`NodeId`]s of their nodes, e.g.,
+/// whether the ASTs of a statement and of its pretty-printed text have the same structure.
+///
+/// Since the locations of nodes and other metadata are kept in side tables (see [`AstTypeMap`]),
+/// they are disregarded as well.
+pub fn ast_eq<T>(lhs: &T, rhs: &T) -> bool
+where
+    T: PartialEq + ?Sized,
+{
+    let _ignoring = IgnoringNodeIds::new();
+    lhs == rhs
+}
+
+std::thread_local! {
+    /// Whether [`AstNode`]s are compared disregarding their [`NodeId`]s, i.e., by [`ast_eq`].
+    static IGNORE_NODE_IDS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Makes the [`AstNode`]s compared on this thread disregard their [`NodeId`]s until dropped.
+struct IgnoringNodeIds(bool);
+
+impl IgnoringNodeIds {
+    fn new() -> Self {
+        IgnoringNodeIds(IGNORE_NODE_IDS.with(|ignore| ignore.replace(true)))
+    }
+}
+
+impl Drop for IgnoringNodeIds {
+    fn drop(&mut self) {
+        IGNORE_NODE_IDS.with(|ignore| ignore.set(self.0));
+    }
+}
+
 /// Represents an AST node.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AstNode<T> {
     pub id: NodeId,
     pub node: T,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum Item {
+impl<T: PartialEq> PartialEq for AstNode<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.id == other.id || IGNORE_NODE_IDS.with(std::cell::Cell::get))
+            && self.node == other.node
+    }
+}
+
+impl<T: Eq> Eq for AstNode<T> {}
+
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Item<'a> {
     // Data Definition Language statements
-    Ddl(Ddl),
+    Ddl(AstNode<Ddl<'a>>),
     // Data Modification Language statements
-    Dml(Dml),
+    Dml(AstNode<Dml<'a>>),
     // Data retrieval statements
-    Query(Query),
+    Query(AstNode<TopLevelQuery<'a>>),
+    // Stored procedure calls
+    Exec(AstNode<Exec<'a>>),
 }
 
-impl fmt::Display for Item {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Use Debug formatting for now
-        write!(f, "{self:?}")
-    }
+/// `EXEC <symbol> [<expr> [, <expr>]...]`
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Exec<'a> {
+    #[visit(skip)]
+    pub procedure_name: SymbolPrimitive<'a>,
+    pub args: Vec<'a, Box<'a, Expr<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Ddl {
-    pub op: DdlOp,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Ddl<'a> {
+    pub op: DdlOp<'a>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum DdlOp {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum DdlOp<'a> {
     /// `CREATE TABLE <symbol>`
-    CreateTable(CreateTable),
+    CreateTable(CreateTable<'a>),
     /// `DROP TABLE <Ident>`
-    DropTable(DropTable),
+    DropTable(DropTable<'a>),
     /// `CREATE INDEX ON <Ident> (<expr> [, <expr>]...)`
-    CreateIndex(CreateIndex),
+    CreateIndex(CreateIndex<'a>),
     /// DROP INDEX <Ident> ON <Ident>
     /// In Statement, first <Ident> represents keys, second represents table
-    DropIndex(DropIndex),
+    DropIndex(DropIndex<'a>),
 }
 
-#[derive(Visit, Clone, Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct CreateTable {
+#[derive(Visit, VisitMut, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CreateTable<'a> {
     #[visit(skip)]
-    pub table_name: SymbolPrimitive,
+    pub table_name: SymbolPrimitive<'a>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct DropTable {
+#[derive(Visit, VisitMut, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DropTable<'a> {
     #[visit(skip)]
-    pub table_name: SymbolPrimitive,
+    pub table_name: SymbolPrimitive<'a>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct CreateIndex {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CreateIndex<'a> {
     #[visit(skip)]
-    pub index_name: SymbolPrimitive,
-    pub fields: Vec<Box<Expr>>,
+    pub index_name: SymbolPrimitive<'a>,
+    pub fields: Vec<'a, Box<'a, Expr<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct DropIndex {
+#[derive(Visit, VisitMut, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DropIndex<'a> {
     #[visit(skip)]
-    pub table: SymbolPrimitive,
+    pub table: SymbolPrimitive<'a>,
     #[visit(skip)]
-    pub keys: SymbolPrimitive,
+    pub keys: SymbolPrimitive<'a>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Dml {
-    pub op: DmlOp,
-    pub from_clause: Option<FromClause>,
-    pub where_clause: Option<Box<Expr>>,
-    pub returning: Option<ReturningExpr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Dml<'a> {
+    pub op: DmlOp<'a>,
+    pub from_clause: Option<FromClause<'a>>,
+    pub where_clause: Option<Box<'a, Expr<'a>>>,
+    pub returning: Option<ReturningExpr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum DmlOp {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum DmlOp<'a> {
     /// `INSERT INTO <expr> <expr>`
-    Insert(Insert),
+    Insert(Insert<'a>),
     /// `INSERT INTO <expr> VALUE <expr> [AT <expr>]` [ON CONFLICT WHERE <expr> DO NOTHING]`
-    InsertValue(InsertValue),
-    /// `SET <assignment>...`
-    Set(Set),
+    InsertValue(InsertValue<'a>),
+    /// `SET <assignment> [, <assignment>]...`
+    Set(Set<'a>),
     /// `REMOVE <expr>`
-    Remove(Remove),
+    Remove(Remove<'a>),
     /// DELETE
     Delete(Delete),
 }
 
 /// `RETURNING (<returning_elem> [, <returning_elem>]...)`
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct ReturningExpr {
-    pub elems: Vec<ReturningElem>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ReturningExpr<'a> {
+    pub elems: Vec<'a, ReturningElem<'a>>,
 }
 
 /// `<returning mapping> (<expr> [, <expr>]...)`
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct ReturningElem {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ReturningElem<'a> {
     #[visit(skip)]
     pub mapping: ReturningMapping,
     #[visit(skip)]
-    pub column: ColumnComponent,
+    pub column: ColumnComponent<'a>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum ColumnComponent {
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ColumnComponent<'a> {
     ReturningWildcard,
-    ReturningColumn(ReturningColumn),
+    ReturningColumn(ReturningColumn<'a>),
 }
 
-#[derive(Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct ReturningColumn {
-    pub expr: Box<Expr>,
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ReturningColumn<'a> {
+    pub expr: Box<'a, Expr<'a>>,
 }
 
 /// ( MODIFIED | ALL ) ( NEW | OLD )
@@ -165,52 +217,52 @@ pub enum ReturningMapping {
     AllOld,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Insert {
-    pub target: Box<Expr>,
-    pub values: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Insert<'a> {
+    pub target: Box<'a, Expr<'a>>,
+    pub values: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct InsertValue {
-    pub target: Box<Expr>,
-    pub value: Box<Expr>,
-    pub index: Option<Box<Expr>>,
-    pub on_conflict: Option<OnConflict>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct InsertValue<'a> {
+    pub target: Box<'a, Expr<'a>>,
+    pub value: Box<'a, Expr<'a>>,
+    pub index: Option<Box<'a, Expr<'a>>>,
+    pub on_conflict: Option<OnConflict<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Set {
-    pub assignment: Assignment,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Set<'a> {
+    pub assignments: Vec<'a, Assignment<'a>>,
 }
 
 /// Represents `<expr> = <expr>` in a DML SET operation.  Note that in this case, `=` is representing
 /// an assignment operation and *not* the equality operator.
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Assignment {
-    pub target: Box<Expr>,
-    pub value: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Assignment<'a> {
+    pub target: Box<'a, Expr<'a>>,
+    pub value: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Remove {
-    pub target: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Remove<'a> {
+    pub target: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq, Eq)]
+#[derive(Visit, VisitMut, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Delete {}
 
 /// `ON CONFLICT <expr> <conflict_action>`
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct OnConflict {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct OnConflict<'a> {
+    pub expr: Box<'a, Expr<'a>>,
     #[visit(skip)]
     pub conflict_action: ConflictAction,
 }
@@ -234,58 +286,58 @@ pub enum ConflictAction {
 // LIMIT / OFFSET
 // SELECT (or SELECT VALUE or PIVOT, which are both special to ion PartiQL).
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct TopLevelQuery {
-    pub with: Option<AstNode<WithClause>>,
-    pub query: AstNode<Query>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TopLevelQuery<'a> {
+    pub with: Option<AstNode<WithClause<'a>>>,
+    pub query: AstNode<Query<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Query {
-    pub set: AstNode<QuerySet>,
-    pub order_by: Option<Box<AstNode<OrderByExpr>>>,
-    pub limit_offset: Option<Box<AstNode<LimitOffsetClause>>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Query<'a> {
+    pub set: AstNode<QuerySet<'a>>,
+    pub order_by: Option<Box<'a, AstNode<OrderByExpr<'a>>>>,
+    pub limit_offset: Option<Box<'a, AstNode<LimitOffsetClause<'a>>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct WithClause {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct WithClause<'a> {
     #[visit(skip)]
     pub recursive: bool,
-    pub withs: Vec<AstNode<WithElement>>,
+    pub withs: Vec<'a, AstNode<WithElement<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct WithElement {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct WithElement<'a> {
     #[visit(skip)]
-    pub query_name: SymbolPrimitive,
+    pub query_name: SymbolPrimitive<'a>,
     #[visit(skip)]
-    pub columns: Option<Vec<SymbolPrimitive>>,
-    pub subquery: AstNode<Expr>,
+    pub columns: Option<Vec<'a, SymbolPrimitive<'a>>>,
+    pub subquery: AstNode<Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum QuerySet {
-    BagOp(Box<AstNode<BagOpExpr>>),
-    Select(Box<AstNode<Select>>),
-    Expr(Box<Expr>),
-    Values(Vec<Box<Expr>>),
-    Table(QueryTable),
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum QuerySet<'a> {
+    BagOp(Box<'a, AstNode<BagOpExpr<'a>>>),
+    Select(Box<'a, AstNode<Select<'a>>>),
+    Expr(Box<'a, Expr<'a>>),
+    Values(Vec<'a, Box<'a, Expr<'a>>>),
+    Table(QueryTable<'a>),
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct BagOpExpr {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct BagOpExpr<'a> {
     #[visit(skip)]
     pub bag_op: BagOperator,
     #[visit(skip)]
     pub setq: SetQuantifier,
-    pub lhs: Box<AstNode<Query>>,
-    pub rhs: Box<AstNode<Query>>,
+    pub lhs: Box<'a, AstNode<Query<'a>>>,
+    pub rhs: Box<'a, AstNode<Query<'a>>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -307,102 +359,112 @@ pub enum SetQuantifier {
     Distinct,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Select {
-    pub project: AstNode<Projection>,
-    pub from: Option<AstNode<FromClause>>,
-    pub from_let: Option<AstNode<Let>>,
-    pub where_clause: Option<Box<AstNode<WhereClause>>>,
-    pub group_by: Option<Box<AstNode<GroupByExpr>>>,
-    pub having: Option<Box<AstNode<HavingClause>>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Select<'a> {
+    pub project: AstNode<Projection<'a>>,
+    pub from: Option<AstNode<FromClause<'a>>>,
+    pub from_let: Option<AstNode<Let<'a>>>,
+    pub where_clause: Option<Box<'a, AstNode<WhereClause<'a>>>>,
+    pub group_by: Option<Box<'a, AstNode<GroupByExpr<'a>>>>,
+    pub having: Option<Box<'a, AstNode<HavingClause<'a>>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct QueryTable {
+#[derive(Visit, VisitMut, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct QueryTable<'a> {
     #[visit(skip)]
-    pub table_name: SymbolPrimitive,
+    pub table_name: SymbolPrimitive<'a>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Projection {
-    pub kind: ProjectionKind,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Projection<'a> {
+    pub kind: ProjectionKind<'a>,
     #[visit(skip)]
     pub setq: Option<SetQuantifier>,
 }
 
 /// Indicates the type of projection in a SFW query.
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum ProjectionKind {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ProjectionKind<'a> {
     #[visit(skip)]
     ProjectStar,
-    ProjectList(Vec<AstNode<ProjectItem>>),
-    ProjectPivot(ProjectPivot),
-    ProjectValue(Box<Expr>),
+    ProjectList(Vec<'a, AstNode<ProjectItem<'a>>>),
+    ProjectPivot(ProjectPivot<'a>),
+    ProjectValue(Box<'a, Expr<'a>>),
 }
 
 /// An item to be projected in a `SELECT`-list.
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum ProjectItem {
-    /// For `.*` in SELECT list
-    ProjectAll(ProjectAll), // TODO remove this?
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ProjectItem<'a> {
+    /// For `<expr>.*` in SELECT list
+    ProjectAll(ProjectAll<'a>),
     /// For `<expr> [AS <id>]`
-    ProjectExpr(ProjectExpr),
+    ProjectExpr(ProjectExpr<'a>),
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct ProjectPivot {
-    pub key: Box<Expr>,
-    pub value: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ProjectPivot<'a> {
+    pub key: Box<'a, Expr<'a>>,
+    pub value: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct ProjectAll {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ProjectAll<'a> {
+    pub expr: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct ProjectExpr {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ProjectExpr<'a> {
+    pub expr: Box<'a, Expr<'a>>,
     #[visit(skip)]
-    pub as_alias: Option<SymbolPrimitive>,
+    pub as_alias: Option<SymbolPrimitive<'a>>,
 }
 
 /// The expressions that can result in values.
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum Expr {
-    Lit(AstNode<Lit>),
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Expr<'a> {
+    Lit(AstNode<Lit<'a>>),
     /// Variable reference
-    VarRef(AstNode<VarRef>),
+    VarRef(AstNode<VarRef<'a>>),
+    /// Query parameter, e.g. `?` or `:name`
+    Parameter(AstNode<Parameter<'a>>),
     /// Binary operator
-    BinOp(AstNode<BinOp>),
+    BinOp(AstNode<BinOp<'a>>),
     /// Unary operators
-    UniOp(AstNode<UniOp>),
+    UniOp(AstNode<UniOp<'a>>),
     /// Comparison operators
-    Like(AstNode<Like>),
-    Between(AstNode<Between>),
-    In(AstNode<In>),
-    Case(AstNode<Case>),
+    Like(AstNode<Like<'a>>),
+    Between(AstNode<Between<'a>>),
+    In(AstNode<In<'a>>),
+    Exists(AstNode<Exists<'a>>),
+    Case(AstNode<Case<'a>>),
     /// Constructors
-    Struct(AstNode<Struct>),
-    Bag(AstNode<Bag>),
-    List(AstNode<List>),
-    Sexp(AstNode<Sexp>),
+    Struct(AstNode<Struct<'a>>),
+    Bag(AstNode<Bag<'a>>),
+    List(AstNode<List<'a>>),
+    Sexp(AstNode<Sexp<'a>>),
     /// Other expression types
-    Path(AstNode<Path>),
-    Call(AstNode<Call>),
-    CallAgg(AstNode<CallAgg>),
+    Path(AstNode<Path<'a>>),
+    Call(AstNode<Call<'a>>),
+    CallAgg(AstNode<CallAgg<'a>>),
+    /// Boxed, as window calls are rare and by far the largest of the expressions, which are all
+    /// allocated at the size of the largest.
+    CallWindow(AstNode<Box<'a, CallWindow<'a>>>),
 
     /// Query, e.g. `UNION` | `EXCEPT` | `INTERSECT` | `SELECT` and their parts.
-    Query(AstNode<Query>),
+    Query(AstNode<Query<'a>>),
+    /// A `SELECT` subquery in a scalar context (e.g., an operand of a comparison), whose result is
+    /// coerced to the single value of its single tuple, e.g. `(SELECT MAX(a) FROM t)` in
+    /// `x > (SELECT MAX(a) FROM t)`. Subqueries in other contexts are [`Expr::Query`]s.
+    ScalarSubQuery(AstNode<Query<'a>>),
 
     /// Indicates an error occurred during query processing; The exact error details are out of band of the AST
     #[visit(skip)]
@@ -414,10 +476,10 @@ pub enum Expr {
 /// <https://www.contrib.andrew.cmu.edu/~shadow/sql/sql1992.txt>
 /// and Section 2 of the following (Figure 1: BNF Grammar for PartiQL Values):
 /// <https://partiql.org/assets/PartiQL-Specification.pdf>
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 #[visit(skip_recurse)]
-pub enum Lit {
+pub enum Lit<'a> {
     #[visit(skip)]
     Null,
     #[visit(skip)]
@@ -443,31 +505,83 @@ pub enum Lit {
     #[visit(skip)]
     BoolLit(bool),
     #[visit(skip)]
-    IonStringLit(String),
+    IonStringLit(&'a str),
     #[visit(skip)]
-    CharStringLit(String),
+    CharStringLit(&'a str),
     #[visit(skip)]
-    NationalCharStringLit(String),
+    NationalCharStringLit(&'a str),
     #[visit(skip)]
-    BitStringLit(String),
+    BitStringLit(&'a str),
     #[visit(skip)]
-    HexStringLit(String),
+    HexStringLit(&'a str),
     #[visit(skip)]
-    StructLit(AstNode<Struct>),
+    StructLit(AstNode<Struct<'a>>),
     #[visit(skip)]
-    BagLit(AstNode<Bag>),
+    BagLit(AstNode<Bag<'a>>),
     #[visit(skip)]
-    ListLit(AstNode<List>),
+    ListLit(AstNode<List<'a>>),
     /// E.g. `TIME WITH TIME ZONE` in `SELECT TIME WITH TIME ZONE '12:00' FROM ...`
     #[visit(skip)]
-    TypedLit(String, Type),
+    TypedLit(&'a str, Type<'a>),
+    /// E.g. `INTERVAL '1:30' HOUR TO MINUTE` in `SELECT t + INTERVAL '1:30' HOUR TO MINUTE FROM ...`
+    #[visit(skip)]
+    IntervalLit(IntervalLit<'a>),
+}
+
+/// An interval literal, e.g., `INTERVAL '3' DAY` or `INTERVAL '1-6' YEAR TO MONTH`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct IntervalLit<'a> {
+    /// The text of the interval's value, e.g., `1-6` in `INTERVAL '1-6' YEAR TO MONTH`.
+    pub value: &'a str,
+    pub qualifier: IntervalQualifier,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq, Eq)]
+/// The fields of an interval, e.g., `HOUR TO SECOND(3)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IntervalQualifier {
+    /// The leading field, e.g., `HOUR` in `HOUR TO SECOND`.
+    pub start: IntervalField,
+    /// The precision of the leading field, e.g., `2` in `DAY(2)`.
+    pub precision: Option<u32>,
+    /// The trailing field, if any, e.g., `SECOND` in `HOUR TO SECOND`.
+    pub end: Option<IntervalField>,
+    /// The precision of fractional seconds, e.g., `3` in `SECOND(2, 3)` or `HOUR TO SECOND(3)`.
+    pub fractional_precision: Option<u32>,
+}
+
+/// The fields of an [`IntervalQualifier`], from the most to the least significant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct VarRef {
+pub enum IntervalField {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+/// A parameter of a query, whose value is bound when the query is evaluated.
+#[derive(Visit, VisitMut, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[visit(skip_recurse)]
+pub enum Parameter<'a> {
+    /// A positional parameter `?`, with its zero-based position among the query's positional
+    /// parameters.
     #[visit(skip)]
-    pub name: SymbolPrimitive,
+    Positional(usize),
+    /// A named parameter, e.g. `:name`.
+    #[visit(skip)]
+    Named(SymbolPrimitive<'a>),
+}
+
+#[derive(Visit, VisitMut, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct VarRef<'a> {
+    #[visit(skip)]
+    pub name: SymbolPrimitive<'a>,
     #[visit(skip)]
     pub qualifier: ScopeQualifier,
 }
@@ -485,13 +599,13 @@ pub enum ScopeQualifier {
     Qualified,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct BinOp {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct BinOp<'a> {
     #[visit(skip)]
     pub kind: BinOpKind,
-    pub lhs: Box<Expr>,
-    pub rhs: Box<Expr>,
+    pub lhs: Box<'a, Expr<'a>>,
+    pub rhs: Box<'a, Expr<'a>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -517,14 +631,16 @@ pub enum BinOpKind {
     Lte,
     Ne,
     Is,
+    /// The null-safe comparison `IS DISTINCT FROM`; `IS NOT DISTINCT FROM` is its negation.
+    IsDistinctFrom,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct UniOp {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct UniOp<'a> {
     #[visit(skip)]
     pub kind: UniOpKind,
-    pub expr: Box<Expr>,
+    pub expr: Box<'a, Expr<'a>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -535,214 +651,267 @@ pub enum UniOpKind {
     Not,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Like {
-    pub value: Box<Expr>,
-    pub pattern: Box<Expr>,
-    pub escape: Option<Box<Expr>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Like<'a> {
+    pub value: Box<'a, Expr<'a>>,
+    pub pattern: Box<'a, Expr<'a>>,
+    pub escape: Option<Box<'a, Expr<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Between {
-    pub value: Box<Expr>,
-    pub from: Box<Expr>,
-    pub to: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Between<'a> {
+    pub value: Box<'a, Expr<'a>>,
+    pub from: Box<'a, Expr<'a>>,
+    pub to: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct In {
-    pub lhs: Box<Expr>,
-    pub rhs: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct In<'a> {
+    pub lhs: Box<'a, Expr<'a>>,
+    pub rhs: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum Case {
+/// `EXISTS (<expr>)`, which tests whether the collection `expr` (typically a subquery) is
+/// non-empty, e.g. `EXISTS (SELECT * FROM t WHERE t.a = 1)`.
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Exists<'a> {
+    pub expr: Box<'a, Expr<'a>>,
+}
+
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Case<'a> {
     /// CASE <expr> [ WHEN <expr> THEN <expr> ]... [ ELSE <expr> ] END
-    SimpleCase(SimpleCase),
+    SimpleCase(SimpleCase<'a>),
     /// CASE [ WHEN <expr> THEN <expr> ]... [ ELSE <expr> ] END
-    SearchedCase(SearchedCase),
+    SearchedCase(SearchedCase<'a>),
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SimpleCase {
-    pub expr: Box<Expr>,
-    pub cases: Vec<ExprPair>,
-    pub default: Option<Box<Expr>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SimpleCase<'a> {
+    pub expr: Box<'a, Expr<'a>>,
+    pub cases: Vec<'a, ExprPair<'a>>,
+    pub default: Option<Box<'a, Expr<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SearchedCase {
-    pub cases: Vec<ExprPair>,
-    pub default: Option<Box<Expr>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SearchedCase<'a> {
+    pub cases: Vec<'a, ExprPair<'a>>,
+    pub default: Option<Box<'a, Expr<'a>>>,
 }
 
 /// A generic pair of expressions. Used in the `pub struct`, `searched_case`
 /// and `simple_case` expr variants above.
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct ExprPair {
-    pub first: Box<Expr>,
-    pub second: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ExprPair<'a> {
+    pub first: Box<'a, Expr<'a>>,
+    pub second: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Struct {
-    pub fields: Vec<ExprPair>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Struct<'a> {
+    pub fields: Vec<'a, ExprPair<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Bag {
-    pub values: Vec<Box<Expr>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Bag<'a> {
+    pub values: Vec<'a, Box<'a, Expr<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct List {
-    pub values: Vec<Box<Expr>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct List<'a> {
+    pub values: Vec<'a, Box<'a, Expr<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Sexp<'a> {
+    pub values: Vec<'a, Box<'a, Expr<'a>>>,
+}
+
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CallAgg<'a> {
+    #[visit(skip)]
+    pub func_name: SymbolPrimitive<'a>,
+    /// The set quantifier of the aggregation, e.g. `DISTINCT` in `COUNT(DISTINCT x)`; `None` if
+    /// omitted (i.e., equivalent to `ALL`).
+    #[visit(skip)]
+    pub setq: Option<SetQuantifier>,
+    pub args: Vec<'a, AstNode<CallArg<'a>>>,
+}
+
+/// A call to a window function, e.g.,
+/// `RANK() OVER (PARTITION BY a ORDER BY b ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW)`.
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CallWindow<'a> {
+    #[visit(skip)]
+    pub func_name: SymbolPrimitive<'a>,
+    pub args: Vec<'a, AstNode<CallArg<'a>>>,
+    pub partition_by: Vec<'a, Box<'a, Expr<'a>>>,
+    pub order_by: Vec<'a, AstNode<SortSpec<'a>>>,
+    #[visit(skip)]
+    pub frame: Option<WindowFrame>,
+}
+
+/// The frame of a window, e.g., `ROWS BETWEEN 1 PRECEDING AND CURRENT ROW`.
+#[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Sexp {
-    pub values: Vec<Box<Expr>>,
+pub struct WindowFrame {
+    pub units: WindowFrameUnits,
+    pub start: WindowFrameBound,
+    /// `CURRENT ROW` if omitted, e.g., in `ROWS UNBOUNDED PRECEDING`.
+    pub end: WindowFrameBound,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct CallAgg {
-    #[visit(skip)]
-    pub func_name: SymbolPrimitive,
-    pub args: Vec<AstNode<CallArg>>,
+pub enum WindowFrameUnits {
+    Rows,
+    Range,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Call {
+pub enum WindowFrameBound {
+    UnboundedPreceding,
+    Preceding(u64),
+    CurrentRow,
+    Following(u64),
+    UnboundedFollowing,
+}
+
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Call<'a> {
     #[visit(skip)]
-    pub func_name: SymbolPrimitive,
-    pub args: Vec<AstNode<CallArg>>,
+    pub func_name: SymbolPrimitive<'a>,
+    pub args: Vec<'a, AstNode<CallArg<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum CallArg {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum CallArg<'a> {
     /// `*` used as an argument to a function call (e.g., in `count(*)`)
     #[visit(skip)]
     Star(),
     /// positional argument to a function call (e.g., all arguments in `foo(1, 'a', 3)`)
-    Positional(Box<Expr>),
+    Positional(Box<'a, Expr<'a>>),
     /// E.g. `INT` in `foo(INT)`
     #[visit(skip)]
-    PositionalType(Type),
+    PositionalType(Type<'a>),
     /// named argument to a function call (e.g., the `"from" : 2` in `substring(a, "from":2)`
-    Named(CallArgNamed),
+    Named(CallArgNamed<'a>),
     /// E.g. `AS: VARCHAR` in `CAST('abc' AS VARCHAR`
-    NamedType(CallArgNamedType),
+    NamedType(CallArgNamedType<'a>),
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct CallArgNamed {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CallArgNamed<'a> {
     #[visit(skip)]
-    pub name: SymbolPrimitive,
-    pub value: Box<Expr>,
+    pub name: SymbolPrimitive<'a>,
+    pub value: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct CallArgNamedType {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CallArgNamedType<'a> {
     #[visit(skip)]
-    pub name: SymbolPrimitive,
+    pub name: SymbolPrimitive<'a>,
     #[visit(skip)]
-    pub ty: Type,
+    pub ty: Type<'a>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Path {
-    pub root: Box<Expr>,
-    pub steps: Vec<PathStep>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Path<'a> {
+    pub root: Box<'a, Expr<'a>>,
+    pub steps: Vec<'a, PathStep<'a>>,
 }
 
 /// A "step" within a path expression; that is the components of the expression following the root.
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum PathStep {
-    PathExpr(PathExpr),
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum PathStep<'a> {
+    PathExpr(PathExpr<'a>),
     #[visit(skip)]
     PathWildCard,
     #[visit(skip)]
     PathUnpivot,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct PathExpr {
-    pub index: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PathExpr<'a> {
+    pub index: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Let {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Let<'a> {
     /// A list of LET bindings
-    pub let_bindings: Vec<LetBinding>,
+    pub let_bindings: Vec<'a, LetBinding<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct LetBinding {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LetBinding<'a> {
+    pub expr: Box<'a, Expr<'a>>,
     #[visit(skip)]
-    pub as_alias: SymbolPrimitive,
+    pub as_alias: SymbolPrimitive<'a>,
 }
 
 /// FROM clause of an SFW query
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct FromClause {
-    pub source: FromSource,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FromClause<'a> {
+    pub source: FromSource<'a>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum FromSource {
-    FromLet(AstNode<FromLet>),
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum FromSource<'a> {
+    FromLet(AstNode<FromLet<'a>>),
     /// <from_source> JOIN \[INNER | LEFT | RIGHT | FULL\] <from_source> ON <expr>
-    Join(AstNode<Join>),
+    Join(AstNode<Join<'a>>),
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct WhereClause {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct WhereClause<'a> {
+    pub expr: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct HavingClause {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HavingClause<'a> {
+    pub expr: Box<'a, Expr<'a>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct FromLet {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FromLet<'a> {
+    pub expr: Box<'a, Expr<'a>>,
     #[visit(skip)]
     pub kind: FromLetKind,
     #[visit(skip)]
-    pub as_alias: Option<SymbolPrimitive>,
+    pub as_alias: Option<SymbolPrimitive<'a>>,
     #[visit(skip)]
-    pub at_alias: Option<SymbolPrimitive>,
+    pub at_alias: Option<SymbolPrimitive<'a>>,
     #[visit(skip)]
-    pub by_alias: Option<SymbolPrimitive>,
+    pub by_alias: Option<SymbolPrimitive<'a>>,
 }
 
 /// Indicates the type of FromLet, see the following for more details:
@@ -754,14 +923,14 @@ pub enum FromLetKind {
     Unpivot,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Join {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Join<'a> {
     #[visit(skip)]
     pub kind: JoinKind,
-    pub left: Box<FromSource>,
-    pub right: Box<FromSource>,
-    pub predicate: Option<AstNode<JoinSpec>>,
+    pub left: Box<'a, FromSource<'a>>,
+    pub right: Box<'a, FromSource<'a>>,
+    pub predicate: Option<AstNode<JoinSpec<'a>>>,
 }
 
 /// Indicates the logical type of join.
@@ -775,24 +944,24 @@ pub enum JoinKind {
     Cross,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum JoinSpec {
-    On(Box<Expr>),
-    Using(Vec<Path>),
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum JoinSpec<'a> {
+    On(Box<'a, Expr<'a>>),
+    Using(Vec<'a, Path<'a>>),
     #[visit(skip)]
     Natural,
 }
 
 /// GROUP BY <grouping_strategy> <group_key>[, <group_key>]... \[AS <symbol>\]
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct GroupByExpr {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GroupByExpr<'a> {
     #[visit(skip)]
     pub strategy: GroupingStrategy,
-    pub keys: Vec<AstNode<GroupKey>>,
+    pub keys: Vec<'a, AstNode<GroupKey<'a>>>,
     #[visit(skip)]
-    pub group_as_alias: Option<SymbolPrimitive>,
+    pub group_as_alias: Option<SymbolPrimitive<'a>>,
 }
 
 /// Desired grouping qualifier:  ALL or PARTIAL.  Note: the `group_` prefix is
@@ -805,33 +974,33 @@ pub enum GroupingStrategy {
 }
 
 /// <expr> [AS <symbol>]
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct GroupKey {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GroupKey<'a> {
+    pub expr: Box<'a, Expr<'a>>,
     #[visit(skip)]
-    pub as_alias: Option<SymbolPrimitive>,
+    pub as_alias: Option<SymbolPrimitive<'a>>,
 }
 
 /// ORDER BY <sort_spec>...
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct OrderByExpr {
-    pub sort_specs: Vec<AstNode<SortSpec>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct OrderByExpr<'a> {
+    pub sort_specs: Vec<'a, AstNode<SortSpec<'a>>>,
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct LimitOffsetClause {
-    pub limit: Option<Box<Expr>>,
-    pub offset: Option<Box<Expr>>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LimitOffsetClause<'a> {
+    pub limit: Option<Box<'a, Expr<'a>>>,
+    pub offset: Option<Box<'a, Expr<'a>>>,
 }
 
 /// <expr> [ASC | DESC] ?
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SortSpec {
-    pub expr: Box<Expr>,
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SortSpec<'a> {
+    pub expr: Box<'a, Expr<'a>>,
     #[visit(skip)]
     pub ordering_spec: Option<OrderingSpec>,
     #[visit(skip)]
@@ -853,9 +1022,9 @@ pub enum NullOrderingSpec {
 }
 
 /// Represents all possible PartiQL data types.
-#[derive(Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum Type {
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Type<'a> {
     NullType,
     BooleanType,
     Integer2Type,
@@ -883,38 +1052,38 @@ pub enum Type {
     BagType,
     AnyType,
 
-    CustomType(CustomType),
+    CustomType(CustomType<'a>),
 }
 
-#[derive(Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum CustomTypeParam {
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum CustomTypeParam<'a> {
     /// E.g. `2` in `VARCHAR(2)`
-    Lit(Lit),
+    Lit(Lit<'a>),
     /// E.g. `INT` in `FooType(INT)`
-    Type(Type),
+    Type(Type<'a>),
 }
 
-#[derive(Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum CustomTypePart {
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum CustomTypePart<'a> {
     /// E.g. any of `WITH`, `TIME`, and`ZONE` in `TIME(20) WITH TIME ZONE`
-    Name(SymbolPrimitive),
+    Name(SymbolPrimitive<'a>),
     /// E.g. `TIME(20) in `TIME(20) WITH TIME ZONE`
-    Parameterized(SymbolPrimitive, Vec<CustomTypeParam>),
+    Parameterized(SymbolPrimitive<'a>, Vec<'a, CustomTypeParam<'a>>),
 }
 
-#[derive(Visit, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct CustomType {
+#[derive(Visit, VisitMut, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CustomType<'a> {
     #[visit(skip)]
-    pub parts: Vec<CustomTypePart>,
+    pub parts: Vec<'a, CustomTypePart<'a>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SymbolPrimitive {
-    pub value: String,
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SymbolPrimitive<'a> {
+    pub value: &'a str,
     pub case: CaseSensitivity,
 }
 
@@ -925,3 +1094,51 @@ pub enum CaseSensitivity {
     CaseSensitive,
     CaseInsensitive,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::Arena;
+
+    fn var_ref(id: u32, name: &str) -> Expr<'_> {
+        Expr::VarRef(AstNode {
+            id: NodeId(id),
+            node: VarRef {
+                name: SymbolPrimitive {
+                    value: name,
+                    case: CaseSensitivity::CaseInsensitive,
+                },
+                qualifier: ScopeQualifier::Unqualified,
+            },
+        })
+    }
+
+    fn add<'a>(arena: &'a Arena, id: u32, lhs: Expr<'a>, rhs: Expr<'a>) -> Expr<'a> {
+        Expr::BinOp(AstNode {
+            id: NodeId(id),
+            node: BinOp {
+                kind: BinOpKind::Add,
+                lhs: Box::new_in(lhs, arena),
+                rhs: Box::new_in(rhs, arena),
+            },
+        })
+    }
+
+    #[test]
+    fn ast_eq_disregards_node_ids() {
+        let arena = Arena::new();
+        let lhs = add(&arena, 3, var_ref(1, "a"), var_ref(2, "b"));
+        let rhs = add(&arena, 1, var_ref(2, "a"), var_ref(3, "b"));
+        assert_ne!(lhs, rhs);
+        assert!(ast_eq(&lhs, &rhs));
+        // the ids are left as they are, and compared again afterwards
+        assert!(matches!(&lhs, Expr::BinOp(AstNode { id: NodeId(3), .. })));
+        assert!(matches!(&rhs, Expr::BinOp(AstNode { id: NodeId(1), .. })));
+        assert_ne!(lhs, rhs);
+
+        let other = add(&arena, 3, var_ref(1, "a"), var_ref(2, "c"));
+        assert!(!ast_eq(&lhs, &other));
+        let swapped = add(&arena, 3, var_ref(1, "b"), var_ref(2, "a"));
+        assert!(!ast_eq(&lhs, &swapped));
+    }
+}
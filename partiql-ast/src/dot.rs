@@ -0,0 +1,321 @@
+//! Rendering of ASTs as [Graphviz](https://graphviz.org/) DOT graphs for debugging.
+//!
+//! [`to_dot`] renders each [`AstNode`] of an AST as a graph node labeled by its [`NodeId`], its
+//! type, and, where it has one, a short description (e.g., the operator of a [`BinOp`] or the
+//! value of a [`Lit`]). Edges lead from each node to its children, in order.
+//!
+//! ```
+//! use partiql_ast::ast::{AstNode, Lit, NodeId};
+//!
+//! let lit = AstNode { id: NodeId(1), node: Lit::Int64Lit(42) };
+//! assert_eq!(
+//!     partiql_ast::dot::to_dot(&lit),
+//!     "digraph ast {\n  ordering=out;\n  node [shape=box];\n  n0 [label=\"#1 Lit\\n42\"];\n}\n"
+//! );
+//! ```
+//!
+//! The output can be rendered with, e.g., `dot -Tsvg ast.dot -o ast.svg`.
+
+use crate::ast::*;
+use crate::visit::{Traverse, Visit, Visitor};
+use std::fmt::Write;
+
+/// Renders the AST `ast` as a DOT graph; see the [module documentation](self).
+pub fn to_dot<T>(ast: &T) -> String
+where
+    T: Visit,
+{
+    let mut writer = DotWriter::default();
+    ast.visit(&mut writer);
+
+    let mut dot = String::from("digraph ast {\n  ordering=out;\n  node [shape=box];\n");
+    for (idx, (id, label)) in writer.nodes.iter().enumerate() {
+        let label = label.as_deref().unwrap_or("AstNode");
+        writeln!(dot, "  n{idx} [label=\"#{} {}\"];", id.0, escape(label)).unwrap();
+    }
+    for (parent, child) in writer.edges {
+        writeln!(dot, "  n{parent} -> n{child};").unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Escapes `label` for a double-quoted DOT string; new lines start new lines of the label.
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Collects the nodes of an AST and the edges between them.
+///
+/// Graph nodes are numbered by the order they are visited in rather than by their [`NodeId`]s, as
+/// the ids of an AST need not be unique (e.g., those of an AST built by hand).
+#[derive(Default)]
+struct DotWriter {
+    nodes: Vec<(NodeId, Option<String>)>,
+    edges: Vec<(usize, usize)>,
+    /// The graph nodes of the [`AstNode`]s currently being visited.
+    stack: Vec<usize>,
+}
+
+impl DotWriter {
+    /// Labels the innermost [`AstNode`] being visited, unless it already is; the first type entered
+    /// after an [`AstNode`] is that of the node itself, while later ones are nested in it.
+    fn label(&mut self, ty: &str, detail: Option<String>) -> Traverse {
+        if let Some(&idx) = self.stack.last() {
+            let label = &mut self.nodes[idx].1;
+            if label.is_none() {
+                *label = Some(match detail {
+                    Some(detail) => format!("{ty}\n{detail}"),
+                    None => ty.to_string(),
+                });
+            }
+        }
+        Traverse::Continue
+    }
+}
+
+/// Labels the types named only by their type names.
+macro_rules! label_types {
+    ($($enter:ident($ty:ident)),* $(,)?) => {
+        $(
+            fn $enter(&mut self, _node: &'ast $ty) -> Traverse {
+                self.label(stringify!($ty), None)
+            }
+        )*
+    };
+}
+
+fn aliases(
+    as_alias: &Option<SymbolPrimitive>,
+    at_alias: &Option<SymbolPrimitive>,
+) -> Option<String> {
+    let as_alias = as_alias.as_ref().map(|alias| format!("AS {alias}"));
+    let at_alias = at_alias.as_ref().map(|alias| format!("AT {alias}"));
+    match (as_alias, at_alias) {
+        (Some(as_alias), Some(at_alias)) => Some(format!("{as_alias} {at_alias}")),
+        (as_alias, at_alias) => as_alias.or(at_alias),
+    }
+}
+
+impl<'ast> Visitor<'ast> for DotWriter {
+    fn enter_ast_node(&mut self, id: NodeId) -> Traverse {
+        let idx = self.nodes.len();
+        self.nodes.push((id, None));
+        if let Some(&parent) = self.stack.last() {
+            self.edges.push((parent, idx));
+        }
+        self.stack.push(idx);
+        Traverse::Continue
+    }
+
+    fn exit_ast_node(&mut self, _id: NodeId) -> Traverse {
+        self.stack.pop();
+        Traverse::Continue
+    }
+
+    label_types!(
+        enter_top_level_query(TopLevelQuery),
+        enter_query(Query),
+        enter_with_clause(WithClause),
+        enter_with_element(WithElement),
+        enter_query_set(QuerySet),
+        enter_select(Select),
+        enter_let(Let),
+        enter_from_clause(FromClause),
+        enter_where_clause(WhereClause),
+        enter_having_clause(HavingClause),
+        enter_join_spec(JoinSpec),
+        enter_group_by_expr(GroupByExpr),
+        enter_order_by_expr(OrderByExpr),
+        enter_limit_offset_clause(LimitOffsetClause),
+        enter_ddl(Ddl),
+        enter_dml(Dml),
+        enter_expr(Expr),
+        enter_like(Like),
+        enter_between(Between),
+        enter_in(In),
+        enter_exists(Exists),
+        enter_case(Case),
+        enter_struct(Struct),
+        enter_bag(Bag),
+        enter_list(List),
+        enter_sexp(Sexp),
+        enter_path(Path),
+        enter_call_arg(CallArg),
+    );
+
+    fn enter_exec(&mut self, exec: &'ast Exec) -> Traverse {
+        self.label("Exec", Some(exec.procedure_name.to_string()))
+    }
+
+    fn enter_bag_op_expr(&mut self, bag_op: &'ast BagOpExpr) -> Traverse {
+        let detail = format!("{:?} {:?}", bag_op.bag_op, bag_op.setq);
+        self.label("BagOpExpr", Some(detail))
+    }
+
+    fn enter_projection(&mut self, projection: &'ast Projection) -> Traverse {
+        let kind = match projection.kind {
+            ProjectionKind::ProjectStar => "*",
+            ProjectionKind::ProjectList(_) => "list",
+            ProjectionKind::ProjectPivot(_) => "PIVOT",
+            ProjectionKind::ProjectValue(_) => "VALUE",
+        };
+        let detail = match &projection.setq {
+            Some(setq) => format!("{setq:?} {kind}"),
+            None => kind.to_string(),
+        };
+        self.label("Projection", Some(detail))
+    }
+
+    fn enter_project_item(&mut self, item: &'ast ProjectItem) -> Traverse {
+        let detail = match item {
+            ProjectItem::ProjectAll(_) => Some(".*".to_string()),
+            ProjectItem::ProjectExpr(ProjectExpr { as_alias, .. }) => aliases(as_alias, &None),
+        };
+        self.label("ProjectItem", detail)
+    }
+
+    fn enter_lit(&mut self, lit: &'ast Lit) -> Traverse {
+        self.label("Lit", Some(lit.to_string()))
+    }
+
+    fn enter_parameter(&mut self, parameter: &'ast Parameter) -> Traverse {
+        let detail = match parameter {
+            Parameter::Positional(position) => format!("?{position}"),
+            Parameter::Named(name) => format!(":{name}"),
+        };
+        self.label("Parameter", Some(detail))
+    }
+
+    fn enter_var_ref(&mut self, var_ref: &'ast VarRef) -> Traverse {
+        let detail = match var_ref.qualifier {
+            ScopeQualifier::Unqualified => var_ref.name.to_string(),
+            ScopeQualifier::Qualified => format!("@{}", var_ref.name),
+        };
+        self.label("VarRef", Some(detail))
+    }
+
+    fn enter_bin_op(&mut self, bin_op: &'ast BinOp) -> Traverse {
+        self.label("BinOp", Some(format!("{:?}", bin_op.kind)))
+    }
+
+    fn enter_uni_op(&mut self, uni_op: &'ast UniOp) -> Traverse {
+        self.label("UniOp", Some(format!("{:?}", uni_op.kind)))
+    }
+
+    fn enter_call(&mut self, call: &'ast Call) -> Traverse {
+        self.label("Call", Some(call.func_name.to_string()))
+    }
+
+    fn enter_call_agg(&mut self, call_agg: &'ast CallAgg) -> Traverse {
+        let detail = match &call_agg.setq {
+            Some(setq) => format!("{} {setq:?}", call_agg.func_name),
+            None => call_agg.func_name.to_string(),
+        };
+        self.label("CallAgg", Some(detail))
+    }
+
+    fn enter_call_window(&mut self, call_window: &'ast CallWindow) -> Traverse {
+        self.label("CallWindow", Some(call_window.func_name.to_string()))
+    }
+
+    fn enter_from_let(&mut self, from_let: &'ast FromLet) -> Traverse {
+        let detail = format!("{:?}", from_let.kind);
+        let detail = match aliases(&from_let.as_alias, &from_let.at_alias) {
+            Some(aliases) => format!("{detail} {aliases}"),
+            None => detail,
+        };
+        self.label("FromLet", Some(detail))
+    }
+
+    fn enter_join(&mut self, join: &'ast Join) -> Traverse {
+        self.label("Join", Some(format!("{:?}", join.kind)))
+    }
+
+    fn enter_group_key(&mut self, group_key: &'ast GroupKey) -> Traverse {
+        self.label("GroupKey", aliases(&group_key.as_alias, &None))
+    }
+
+    fn enter_sort_spec(&mut self, sort_spec: &'ast SortSpec) -> Traverse {
+        let detail = [
+            sort_spec.ordering_spec.as_ref().map(|o| format!("{o:?}")),
+            sort_spec
+                .null_ordering_spec
+                .as_ref()
+                .map(|o| format!("NULLS {o:?}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+        self.label("SortSpec", (!detail.is_empty()).then_some(detail))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::{Arena, Box};
+
+    fn var_ref(id: u32, name: &str) -> Expr<'_> {
+        Expr::VarRef(AstNode {
+            id: NodeId(id),
+            node: VarRef {
+                name: SymbolPrimitive {
+                    value: name,
+                    case: CaseSensitivity::CaseSensitive,
+                },
+                qualifier: ScopeQualifier::Unqualified,
+            },
+        })
+    }
+
+    #[test]
+    fn nodes_and_edges() {
+        let arena = Arena::new();
+        let expr = Expr::BinOp(AstNode {
+            id: NodeId(3),
+            node: BinOp {
+                kind: BinOpKind::Add,
+                lhs: Box::new_in(var_ref(1, "a"), &arena),
+                rhs: Box::new_in(
+                    Expr::Lit(AstNode {
+                        id: NodeId(2),
+                        node: Lit::CharStringLit("b\"c"),
+                    }),
+                    &arena,
+                ),
+            },
+        });
+        assert_eq!(
+            to_dot(&expr),
+            r##"digraph ast {
+  ordering=out;
+  node [shape=box];
+  n0 [label="#3 BinOp\nAdd"];
+  n1 [label="#1 VarRef\n\"a\""];
+  n2 [label="#2 Lit\n'b\"c'"];
+  n0 -> n1;
+  n0 -> n2;
+}
+"##
+        );
+    }
+
+    #[test]
+    fn repeated_node_ids() {
+        let arena = Arena::new();
+        let values = [var_ref(0, "a"), var_ref(0, "a")].map(|value| Box::new_in(value, &arena));
+        let expr = Expr::List(AstNode {
+            id: NodeId(0),
+            node: List {
+                values: crate::arena::Vec::from_iter_in(values, &arena),
+            },
+        });
+        let dot = to_dot(&expr);
+        assert!(dot.contains("n0 -> n1;\n  n0 -> n2;"), "{dot}");
+    }
+}
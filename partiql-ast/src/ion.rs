@@ -0,0 +1,2187 @@
+//! Serialization of the AST to and from the Ion s-expressions of the `partiql_ast` domain of
+//! [partiql-lang-kotlin](https://github.com/partiql/partiql-lang-kotlin), e.g.,
+//!
+//! ```text
+//! (query (select (project (project_star)) (from (scan (id t (case_insensitive) (unqualified)) null null null))))
+//! ```
+//!
+//! for `SELECT * FROM t`, which allows comparing the parse results of the two implementations.
+//!
+//! Reading what [`to_ion`] writes for a parsed query yields the same AST, up to its [`NodeId`]s,
+//! which are numbered from 1. To that end, the format extends partiql-lang-kotlin's:
+//!
+//! - Function names are written as in the query rather than lowercased, and the symbols of
+//!   case-sensitive names are annotated, e.g., `case_sensitive::T` for the alias of `AS "T"`.
+//! - Parts of the AST without a counterpart in partiql-lang-kotlin have s-expressions of their own,
+//!   e.g., `(call_arg_named FROM (id a (case_insensitive) (unqualified)))` for the `FROM a` of
+//!   `TRIM(FROM a)`, `(call_arg_star)` for the `*` of `COUNT(*)`, or `(scalar_subquery ...)`,
+//!   `(with ...)`, `(call_window ...)`, `(typed_lit ...)`, `(interval_lit ...)`, and
+//!   `(is_distinct_from ...)`, or `(list_expr ...)` for the rows of `VALUES (1, 2)`, which,
+//!   unlike `[1, 2]`, are not literals.
+//! - partiql-lang-kotlin's s-expressions are only written for the ASTs they are read as; e.g.,
+//!   `cast(a AS INT)` is written as `(cast (id a ...) (integer_type))` but `CAST(a AS INT)` as a
+//!   `(call CAST ...)`, and `a IS INTEGER` is written with the type `(id INTEGER ...)`, as
+//!   `(integer_type)` is read as `INT`.
+//!
+//! ```
+//! use ion_rs::element::Element;
+//! use partiql_ast::arena::Arena;
+//! use partiql_ast::ion::{from_ion, to_ion};
+//!
+//! let ion = Element::read_one(
+//!     "(query (select (project (project_star)) \
+//!         (from (scan (id t (case_insensitive) (unqualified)) null null null))))",
+//! )
+//! .unwrap();
+//! let arena = Arena::new();
+//! let query = from_ion(&ion, &arena).unwrap();
+//! assert_eq!(to_ion(&query).unwrap(), ion);
+//! ```
+
+use crate::arena::{self, Arena};
+use crate::ast;
+use crate::ast::{AstNode, CaseSensitivity, NodeId, SymbolPrimitive};
+use ion_rs::element::{Element, SExp, Sequence, Value};
+use ion_rs::types::IntAccess;
+use ion_rs::{Decimal, IonType};
+use rust_decimal::Decimal as RustDecimal;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An error converting between the AST and its Ion representation.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IonAstError {
+    /// A part of the AST has no counterpart in the Ion format, or vice versa.
+    #[error("{0} cannot be converted between the AST and Ion")]
+    Unsupported(String),
+    /// An Ion literal of the AST is not valid Ion.
+    #[error("invalid Ion literal `{0}`")]
+    InvalidIon(String),
+    /// The Ion being read is not an AST.
+    #[error("malformed Ion AST `{0}`")]
+    Malformed(String),
+}
+
+/// The names of types, as written in queries, along with the tags of their Ion representation and
+/// the number of their (optional) parameters. The first name of each type is the one its Ion
+/// representation is read as.
+const TYPES: &[(&str, &str, usize)] = &[
+    ("BOOLEAN", "boolean_type", 0),
+    ("BOOL", "boolean_type", 0),
+    ("SMALLINT", "smallint_type", 0),
+    ("INT4", "integer4_type", 0),
+    ("INT8", "integer8_type", 0),
+    ("BIGINT", "integer8_type", 0),
+    ("INT", "integer_type", 0),
+    ("INTEGER", "integer_type", 0),
+    ("FLOAT", "float_type", 1),
+    ("REAL", "real_type", 0),
+    ("DECIMAL", "decimal_type", 2),
+    ("NUMERIC", "numeric_type", 2),
+    ("TIMESTAMP", "timestamp_type", 0),
+    ("CHAR", "character_type", 1),
+    ("CHARACTER", "character_type", 1),
+    ("VARCHAR", "character_varying_type", 1),
+    ("STRING", "string_type", 0),
+    ("SYMBOL", "symbol_type", 0),
+    ("BLOB", "blob_type", 0),
+    ("CLOB", "clob_type", 0),
+    ("DATE", "date_type", 0),
+    ("TIME", "time_type", 1),
+    ("STRUCT", "struct_type", 0),
+    ("TUPLE", "tuple_type", 0),
+    ("LIST", "list_type", 0),
+    ("SEXP", "sexp_type", 0),
+    ("BAG", "bag_type", 0),
+    ("ANY", "any_type", 0),
+];
+
+/// The constructor of an [`ast::Type`].
+type TypeOf = fn() -> ast::Type<'static>;
+
+/// The tags of the [`ast::Type`]s other than custom types, e.g., of the `INT` of `foo(INT)`.
+const AST_TYPES: &[(&str, TypeOf)] = &[
+    ("null_type", || ast::Type::NullType),
+    ("boolean_type", || ast::Type::BooleanType),
+    ("smallint_type", || ast::Type::Integer2Type),
+    ("integer4_type", || ast::Type::Integer4Type),
+    ("integer8_type", || ast::Type::Integer8Type),
+    ("decimal_type", || ast::Type::DecimalType),
+    ("numeric_type", || ast::Type::NumericType),
+    ("real_type", || ast::Type::RealType),
+    ("double_precision_type", || ast::Type::DoublePrecisionType),
+    ("timestamp_type", || ast::Type::TimestampType),
+    ("character_type", || ast::Type::CharacterType),
+    ("character_varying_type", || ast::Type::CharacterVaryingType),
+    ("missing_type", || ast::Type::MissingType),
+    ("string_type", || ast::Type::StringType),
+    ("symbol_type", || ast::Type::SymbolType),
+    ("blob_type", || ast::Type::BlobType),
+    ("clob_type", || ast::Type::ClobType),
+    ("date_type", || ast::Type::DateType),
+    ("time_type", || ast::Type::TimeType),
+    ("timestamp_with_time_zone_type", || {
+        ast::Type::ZonedTimestampType
+    }),
+    ("struct_type", || ast::Type::StructType),
+    ("tuple_type", || ast::Type::TupleType),
+    ("list_type", || ast::Type::ListType),
+    ("sexp_type", || ast::Type::SexpType),
+    ("bag_type", || ast::Type::BagType),
+    ("any_type", || ast::Type::AnyType),
+];
+
+/// The functions whose calls are written as `(<name> <value> <type>)`, e.g., `cast(a AS INT)`.
+const CAST_FUNCTIONS: &[&str] = &["cast", "can_cast", "can_lossless_cast"];
+
+/// The annotation of the symbols of case-sensitive names, e.g., of `case_sensitive::T`.
+const CASE_SENSITIVE: &str = "case_sensitive";
+
+/// Writes `query` as an Ion `(query ...)` statement.
+pub fn to_ion(query: &AstNode<ast::TopLevelQuery>) -> Result<Element, IonAstError> {
+    let ast::TopLevelQuery { with, query } = &query.node;
+    let query = query_to_ion(&query.node)?;
+    match with {
+        None => Ok(sexp("query", [query])),
+        Some(with) => Ok(sexp("query", [with_to_ion(&with.node)?, query])),
+    }
+}
+
+/// Reads a query from an Ion `(query ...)` statement.
+pub fn from_ion<'a>(
+    element: &Element,
+    arena: &'a Arena,
+) -> Result<AstNode<ast::TopLevelQuery<'a>>, IonAstError> {
+    let mut reader = Reader::new(arena);
+    let (with, expr) = match tagged(element)?.parts() {
+        ("query", [expr]) => (None, *expr),
+        ("query", [with, expr]) => (Some(reader.with(with)?), *expr),
+        _ => return Err(malformed(element)),
+    };
+    let expr = reader.expr(expr)?;
+    let query = reader.query(expr);
+    Ok(reader.node(ast::TopLevelQuery { with, query }))
+}
+
+fn malformed(element: &Element) -> IonAstError {
+    IonAstError::Malformed(element.to_string())
+}
+
+fn sexp(tag: &str, args: impl IntoIterator<Item = Element>) -> Element {
+    let elements = std::iter::once(Element::symbol(tag)).chain(args);
+    SExp(Sequence::new(elements)).into()
+}
+
+fn tag(tag: &str) -> Element {
+    sexp(tag, [])
+}
+
+fn null() -> Element {
+    Element::null(IonType::Null)
+}
+
+fn symbol(symbol: &SymbolPrimitive) -> Element {
+    let element = Element::symbol(symbol.value);
+    match symbol.case {
+        CaseSensitivity::CaseSensitive => element.with_annotations([CASE_SENSITIVE]),
+        CaseSensitivity::CaseInsensitive => element,
+    }
+}
+
+fn opt_symbol(symbol: &Option<SymbolPrimitive>) -> Element {
+    symbol.as_ref().map_or_else(null, self::symbol)
+}
+
+fn opt_expr(expr: &Option<arena::Box<ast::Expr>>) -> Result<Element, IonAstError> {
+    expr.as_deref().map_or_else(|| Ok(null()), expr_to_ion)
+}
+
+fn opt_integer(integer: Option<u32>) -> Element {
+    integer.map_or_else(null, Element::integer)
+}
+
+fn exprs<'e, 'a: 'e>(
+    tag: &str,
+    exprs: impl IntoIterator<Item = &'e ast::Expr<'a>>,
+) -> Result<Element, IonAstError> {
+    let exprs = exprs
+        .into_iter()
+        .map(expr_to_ion)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sexp(tag, exprs))
+}
+
+fn set_quantifier(setq: &ast::SetQuantifier) -> Element {
+    match setq {
+        ast::SetQuantifier::All => tag("all"),
+        ast::SetQuantifier::Distinct => tag("distinct"),
+    }
+}
+
+fn case_sensitivity(case: &CaseSensitivity) -> Element {
+    match case {
+        CaseSensitivity::CaseSensitive => tag("case_sensitive"),
+        CaseSensitivity::CaseInsensitive => tag("case_insensitive"),
+    }
+}
+
+fn decimal(d: &RustDecimal) -> Element {
+    Element::decimal(Decimal::new(d.mantissa(), -(d.scale() as i64)))
+}
+
+fn with_to_ion(with: &ast::WithClause) -> Result<Element, IonAstError> {
+    let elements = with
+        .withs
+        .iter()
+        .map(|element| {
+            let ast::WithElement {
+                query_name,
+                columns,
+                subquery,
+            } = &element.node;
+            let columns = columns.as_ref().map_or_else(null, |columns| {
+                sexp("column_list", columns.iter().map(symbol))
+            });
+            Ok(sexp(
+                "with_element",
+                [symbol(query_name), columns, expr_to_ion(&subquery.node)?],
+            ))
+        })
+        .collect::<Result<Vec<_>, IonAstError>>()?;
+    let tag = match with.recursive {
+        true => "with_recursive",
+        false => "with",
+    };
+    Ok(sexp(tag, elements))
+}
+
+fn query_to_ion(query: &ast::Query) -> Result<Element, IonAstError> {
+    let ast::Query {
+        set,
+        order_by,
+        limit_offset,
+    } = query;
+    let clauses = query_clauses_to_ion(order_by.as_deref(), limit_offset.as_deref())?;
+    let set = match &set.node {
+        ast::QuerySet::Select(select) => return select_to_ion(&select.node, clauses),
+        ast::QuerySet::BagOp(bag_op) => {
+            let ast::BagOpExpr {
+                bag_op,
+                setq,
+                lhs,
+                rhs,
+            } = &bag_op.node;
+            let op = match bag_op {
+                ast::BagOperator::Union => "union",
+                ast::BagOperator::Except => "except",
+                ast::BagOperator::Intersect => "intersect",
+                ast::BagOperator::OuterUnion => "outer_union",
+                ast::BagOperator::OuterExcept => "outer_except",
+                ast::BagOperator::OuterIntersect => "outer_intersect",
+            };
+            sexp(
+                "bag_op",
+                [
+                    tag(op),
+                    set_quantifier(setq),
+                    query_to_ion(&lhs.node)?,
+                    query_to_ion(&rhs.node)?,
+                ],
+            )
+        }
+        ast::QuerySet::Expr(expr) => expr_to_ion(expr)?,
+        ast::QuerySet::Values(rows) => exprs("values", rows.iter().map(AsRef::as_ref))?,
+        ast::QuerySet::Table(table) => sexp("table", [symbol(&table.table_name)]),
+    };
+    // the clauses of queries other than `SELECT`s are written along with them
+    match clauses.is_empty() {
+        true => Ok(set),
+        false => Ok(sexp("query_set", std::iter::once(set).chain(clauses))),
+    }
+}
+
+/// Writes the `ORDER BY`, `LIMIT`, and `OFFSET` clauses of a query.
+fn query_clauses_to_ion(
+    order_by: Option<&AstNode<ast::OrderByExpr>>,
+    limit_offset: Option<&AstNode<ast::LimitOffsetClause>>,
+) -> Result<Vec<Element>, IonAstError> {
+    let mut clauses = vec![];
+    if let Some(order_by) = order_by {
+        let specs = sort_specs_to_ion("order_by", &order_by.node.sort_specs)?;
+        clauses.push(sexp("order", [specs]));
+    }
+    if let Some(limit_offset) = limit_offset {
+        if let Some(limit) = &limit_offset.node.limit {
+            clauses.push(sexp("limit", [expr_to_ion(limit)?]));
+        }
+        if let Some(offset) = &limit_offset.node.offset {
+            clauses.push(sexp("offset", [expr_to_ion(offset)?]));
+        }
+    }
+    Ok(clauses)
+}
+
+fn select_to_ion(select: &ast::Select, clauses: Vec<Element>) -> Result<Element, IonAstError> {
+    let mut fields = vec![];
+    if let Some(ast::SetQuantifier::Distinct) = select.project.node.setq {
+        fields.push(sexp("setq", [tag("distinct")]));
+    }
+    fields.push(sexp(
+        "project",
+        [projection_to_ion(&select.project.node.kind)?],
+    ));
+    if let Some(from) = &select.from {
+        fields.push(sexp("from", [from_source_to_ion(&from.node.source)?]));
+    }
+    if let Some(from_let) = &select.from_let {
+        let bindings = from_let
+            .node
+            .let_bindings
+            .iter()
+            .map(|binding| {
+                Ok(sexp(
+                    "let_binding",
+                    [expr_to_ion(&binding.expr)?, symbol(&binding.as_alias)],
+                ))
+            })
+            .collect::<Result<Vec<_>, IonAstError>>()?;
+        fields.push(sexp("from_let", [sexp("let", bindings)]));
+    }
+    if let Some(where_clause) = &select.where_clause {
+        fields.push(sexp("where", [expr_to_ion(&where_clause.node.expr)?]));
+    }
+    if let Some(group_by) = &select.group_by {
+        fields.push(sexp("group", [group_by_to_ion(&group_by.node)?]));
+    }
+    if let Some(having) = &select.having {
+        fields.push(sexp("having", [expr_to_ion(&having.node.expr)?]));
+    }
+    fields.extend(clauses);
+    Ok(sexp("select", fields))
+}
+
+fn projection_to_ion(kind: &ast::ProjectionKind) -> Result<Element, IonAstError> {
+    Ok(match kind {
+        ast::ProjectionKind::ProjectStar => tag("project_star"),
+        ast::ProjectionKind::ProjectList(items) => {
+            let items = items
+                .iter()
+                .map(|item| match &item.node {
+                    ast::ProjectItem::ProjectAll(all) => {
+                        Ok(sexp("project_all", [expr_to_ion(&all.expr)?]))
+                    }
+                    ast::ProjectItem::ProjectExpr(item) => Ok(sexp(
+                        "project_expr",
+                        [expr_to_ion(&item.expr)?, opt_symbol(&item.as_alias)],
+                    )),
+                })
+                .collect::<Result<Vec<_>, IonAstError>>()?;
+            sexp("project_list", items)
+        }
+        ast::ProjectionKind::ProjectPivot(pivot) => sexp(
+            "project_pivot",
+            [expr_to_ion(&pivot.value)?, expr_to_ion(&pivot.key)?],
+        ),
+        ast::ProjectionKind::ProjectValue(expr) => sexp("project_value", [expr_to_ion(expr)?]),
+    })
+}
+
+fn from_source_to_ion(source: &ast::FromSource) -> Result<Element, IonAstError> {
+    match source {
+        ast::FromSource::FromLet(from_let) => {
+            let ast::FromLet {
+                expr,
+                kind,
+                as_alias,
+                at_alias,
+                by_alias,
+            } = &from_let.node;
+            let kind = match kind {
+                ast::FromLetKind::Scan => "scan",
+                ast::FromLetKind::Unpivot => "unpivot",
+            };
+            Ok(sexp(
+                kind,
+                [
+                    expr_to_ion(expr)?,
+                    opt_symbol(as_alias),
+                    opt_symbol(at_alias),
+                    opt_symbol(by_alias),
+                ],
+            ))
+        }
+        ast::FromSource::Join(join) => {
+            let ast::Join {
+                kind,
+                left,
+                right,
+                predicate,
+            } = &join.node;
+            let kind = match kind {
+                ast::JoinKind::Inner | ast::JoinKind::Cross => "inner",
+                ast::JoinKind::Left => "left",
+                ast::JoinKind::Right => "right",
+                ast::JoinKind::Full => "full",
+            };
+            let predicate = match predicate.as_ref().map(|p| &p.node) {
+                None => null(),
+                Some(ast::JoinSpec::On(expr)) => expr_to_ion(expr)?,
+                Some(ast::JoinSpec::Using(paths)) => sexp(
+                    "using",
+                    paths
+                        .iter()
+                        .map(path_to_ion)
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                Some(ast::JoinSpec::Natural) => tag("natural"),
+            };
+            Ok(sexp(
+                "join",
+                [
+                    tag(kind),
+                    from_source_to_ion(left)?,
+                    from_source_to_ion(right)?,
+                    predicate,
+                ],
+            ))
+        }
+    }
+}
+
+fn group_by_to_ion(group_by: &ast::GroupByExpr) -> Result<Element, IonAstError> {
+    let strategy = match group_by.strategy {
+        ast::GroupingStrategy::GroupFull => "group_full",
+        ast::GroupingStrategy::GroupPartial => "group_partial",
+    };
+    let keys = group_by
+        .keys
+        .iter()
+        .map(|key| {
+            Ok(sexp(
+                "group_key",
+                [expr_to_ion(&key.node.expr)?, opt_symbol(&key.node.as_alias)],
+            ))
+        })
+        .collect::<Result<Vec<_>, IonAstError>>()?;
+    Ok(sexp(
+        "group_by",
+        [
+            tag(strategy),
+            sexp("group_key_list", keys),
+            opt_symbol(&group_by.group_as_alias),
+        ],
+    ))
+}
+
+fn sort_specs_to_ion(tag: &str, specs: &[AstNode<ast::SortSpec>]) -> Result<Element, IonAstError> {
+    let specs = specs
+        .iter()
+        .map(|spec| sort_spec_to_ion(&spec.node))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sexp(tag, specs))
+}
+
+fn sort_spec_to_ion(spec: &ast::SortSpec) -> Result<Element, IonAstError> {
+    let ordering = match spec.ordering_spec {
+        None => null(),
+        Some(ast::OrderingSpec::Asc) => tag("asc"),
+        Some(ast::OrderingSpec::Desc) => tag("desc"),
+    };
+    let nulls = match spec.null_ordering_spec {
+        None => null(),
+        Some(ast::NullOrderingSpec::First) => tag("nulls_first"),
+        Some(ast::NullOrderingSpec::Last) => tag("nulls_last"),
+    };
+    Ok(sexp(
+        "sort_spec",
+        [expr_to_ion(&spec.expr)?, ordering, nulls],
+    ))
+}
+
+fn expr_to_ion(expr: &ast::Expr) -> Result<Element, IonAstError> {
+    match expr {
+        ast::Expr::Lit(lit) => lit_to_ion(&lit.node),
+        ast::Expr::VarRef(var_ref) => {
+            let ast::VarRef { name, qualifier } = &var_ref.node;
+            let qualifier = match qualifier {
+                ast::ScopeQualifier::Unqualified => tag("unqualified"),
+                ast::ScopeQualifier::Qualified => tag("locals_first"),
+            };
+            Ok(sexp(
+                "id",
+                [
+                    Element::symbol(name.value),
+                    case_sensitivity(&name.case),
+                    qualifier,
+                ],
+            ))
+        }
+        ast::Expr::Parameter(parameter) => match &parameter.node {
+            ast::Parameter::Positional(index) => {
+                Ok(sexp("parameter", [Element::integer(*index as i64 + 1)]))
+            }
+            ast::Parameter::Named(name) => Ok(sexp("named_parameter", [symbol(name)])),
+        },
+        ast::Expr::BinOp(bin_op) => bin_op_to_ion(&bin_op.node),
+        ast::Expr::UniOp(uni_op) => {
+            let op = match uni_op.node.kind {
+                ast::UniOpKind::Pos => "pos",
+                ast::UniOpKind::Neg => "neg",
+                ast::UniOpKind::Not => "not",
+            };
+            Ok(sexp(op, [expr_to_ion(&uni_op.node.expr)?]))
+        }
+        ast::Expr::Like(like) => Ok(sexp(
+            "like",
+            [
+                expr_to_ion(&like.node.value)?,
+                expr_to_ion(&like.node.pattern)?,
+                opt_expr(&like.node.escape)?,
+            ],
+        )),
+        ast::Expr::Between(between) => exprs(
+            "between",
+            [
+                between.node.value.as_ref(),
+                &between.node.from,
+                &between.node.to,
+            ],
+        ),
+        ast::Expr::In(in_expr) => exprs(
+            "in_collection",
+            [in_expr.node.lhs.as_ref(), &in_expr.node.rhs],
+        ),
+        ast::Expr::Exists(exists) => Ok(sexp(
+            "call",
+            [Element::symbol("exists"), expr_to_ion(&exists.node.expr)?],
+        )),
+        ast::Expr::Case(case) => match &case.node {
+            ast::Case::SimpleCase(case) => Ok(sexp(
+                "simple_case",
+                [
+                    expr_to_ion(&case.expr)?,
+                    expr_pairs_to_ion("expr_pair_list", &case.cases)?,
+                    opt_expr(&case.default)?,
+                ],
+            )),
+            ast::Case::SearchedCase(case) => Ok(sexp(
+                "searched_case",
+                [
+                    expr_pairs_to_ion("expr_pair_list", &case.cases)?,
+                    opt_expr(&case.default)?,
+                ],
+            )),
+        },
+        // collections of literals are read as literals, unless written as expressions, e.g., the
+        // rows of `VALUES (1, 2)`
+        ast::Expr::Struct(tuple) => {
+            let fields = &tuple.node.fields;
+            match fields
+                .iter()
+                .all(|pair| is_lit(&pair.first) && is_lit(&pair.second))
+            {
+                true => expr_pairs_to_ion("struct_expr", fields),
+                false => expr_pairs_to_ion("struct", fields),
+            }
+        }
+        ast::Expr::Bag(bag) => match bag.node.values.iter().all(|value| is_lit(value)) {
+            true => exprs("bag_expr", bag.node.values.iter().map(AsRef::as_ref)),
+            false => exprs("bag", bag.node.values.iter().map(AsRef::as_ref)),
+        },
+        ast::Expr::List(list) => match list.node.values.iter().all(|value| is_lit(value)) {
+            true => exprs("list_expr", list.node.values.iter().map(AsRef::as_ref)),
+            false => exprs("list", list.node.values.iter().map(AsRef::as_ref)),
+        },
+        ast::Expr::Sexp(sexp) => exprs("sexp", sexp.node.values.iter().map(AsRef::as_ref)),
+        ast::Expr::Path(path) => path_to_ion(&path.node),
+        ast::Expr::Call(call) => call_to_ion(&call.node),
+        ast::Expr::CallAgg(call) => {
+            let ast::CallAgg {
+                func_name,
+                setq,
+                args,
+            } = &call.node;
+            let mut elements = vec![
+                setq.as_ref().map_or_else(null, set_quantifier),
+                symbol(func_name),
+            ];
+            elements.extend(call_args_to_ion(args)?);
+            Ok(sexp("call_agg", elements))
+        }
+        ast::Expr::CallWindow(call) => {
+            let ast::CallWindow {
+                func_name,
+                args,
+                partition_by,
+                order_by,
+                frame,
+            } = &*call.node;
+            let mut over = vec![
+                match partition_by.is_empty() {
+                    true => null(),
+                    false => exprs(
+                        "window_partition_list",
+                        partition_by.iter().map(AsRef::as_ref),
+                    )?,
+                },
+                match order_by.is_empty() {
+                    true => null(),
+                    false => sort_specs_to_ion("window_sort_spec_list", order_by)?,
+                },
+            ];
+            over.extend(frame.as_ref().map(window_frame_to_ion));
+            let mut elements = vec![symbol(func_name), sexp("over", over)];
+            elements.extend(call_args_to_ion(args)?);
+            Ok(sexp("call_window", elements))
+        }
+        ast::Expr::Query(query) => query_to_ion(&query.node),
+        ast::Expr::ScalarSubQuery(query) => {
+            Ok(sexp("scalar_subquery", [query_to_ion(&query.node)?]))
+        }
+        ast::Expr::Error => Err(IonAstError::Unsupported(
+            "erroneous expressions".to_string(),
+        )),
+    }
+}
+
+fn expr_pairs_to_ion(tag: &str, pairs: &[ast::ExprPair]) -> Result<Element, IonAstError> {
+    let pairs = pairs
+        .iter()
+        .map(|pair| exprs("expr_pair", [pair.first.as_ref(), &pair.second]))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sexp(tag, pairs))
+}
+
+fn path_to_ion(path: &ast::Path) -> Result<Element, IonAstError> {
+    let mut elements = vec![expr_to_ion(&path.root)?];
+    for step in &path.steps {
+        elements.push(match step {
+            ast::PathStep::PathExpr(ast::PathExpr { index }) => match index.as_ref() {
+                ast::Expr::VarRef(var_ref) => sexp(
+                    "path_expr",
+                    [
+                        sexp("lit", [Element::string(var_ref.node.name.value)]),
+                        case_sensitivity(&var_ref.node.name.case),
+                    ],
+                ),
+                // unlike `t."a"`, `t['a']` is not a name
+                ast::Expr::Lit(lit) if matches!(lit.node, ast::Lit::CharStringLit(_)) => {
+                    sexp("path_expr", [lit_to_ion(&lit.node)?])
+                }
+                index => sexp("path_expr", [expr_to_ion(index)?, tag("case_sensitive")]),
+            },
+            ast::PathStep::PathWildCard => tag("path_wildcard"),
+            ast::PathStep::PathUnpivot => tag("path_unpivot"),
+        });
+    }
+    Ok(sexp("path", elements))
+}
+
+fn window_frame_to_ion(frame: &ast::WindowFrame) -> Element {
+    let units = match frame.units {
+        ast::WindowFrameUnits::Rows => tag("rows"),
+        ast::WindowFrameUnits::Range => tag("range"),
+    };
+    let bound = |bound: &ast::WindowFrameBound| match bound {
+        ast::WindowFrameBound::UnboundedPreceding => tag("unbounded_preceding"),
+        ast::WindowFrameBound::Preceding(offset) => sexp("preceding", [Element::integer(*offset)]),
+        ast::WindowFrameBound::CurrentRow => tag("current_row"),
+        ast::WindowFrameBound::Following(offset) => sexp("following", [Element::integer(*offset)]),
+        ast::WindowFrameBound::UnboundedFollowing => tag("unbounded_following"),
+    };
+    sexp(
+        "window_frame",
+        [units, bound(&frame.start), bound(&frame.end)],
+    )
+}
+
+fn bin_op_to_ion(bin_op: &ast::BinOp) -> Result<Element, IonAstError> {
+    let ast::BinOp { kind, lhs, rhs } = bin_op;
+    let op = match kind {
+        ast::BinOpKind::Add => "plus",
+        ast::BinOpKind::Sub => "minus",
+        ast::BinOpKind::Mul => "times",
+        ast::BinOpKind::Div => "divide",
+        ast::BinOpKind::Mod => "modulo",
+        ast::BinOpKind::Exp => "power",
+        ast::BinOpKind::Concat => "concat",
+        ast::BinOpKind::And => "and",
+        ast::BinOpKind::Or => "or",
+        ast::BinOpKind::Eq => "eq",
+        ast::BinOpKind::Ne => "ne",
+        ast::BinOpKind::Gt => "gt",
+        ast::BinOpKind::Gte => "gte",
+        ast::BinOpKind::Lt => "lt",
+        ast::BinOpKind::Lte => "lte",
+        ast::BinOpKind::IsDistinctFrom => "is_distinct_from",
+        ast::BinOpKind::Is => {
+            return Ok(sexp("is_type", [expr_to_ion(lhs)?, type_to_ion(rhs)?]));
+        }
+    };
+    exprs(op, [lhs.as_ref(), rhs])
+}
+
+/// Writes the type named by `ty`, e.g., the `INT` of `a IS INT` or `CAST(a AS INT)`, as the Ion
+/// type it is read from, if any, or as an expression, e.g., for `VARCHAR(2)`.
+fn type_to_ion(ty: &ast::Expr) -> Result<Element, IonAstError> {
+    match ty {
+        ast::Expr::Lit(lit) if lit.node == ast::Lit::Null => Ok(tag("null_type")),
+        ast::Expr::Lit(lit) if lit.node == ast::Lit::Missing => Ok(tag("missing_type")),
+        ast::Expr::VarRef(var_ref)
+            if var_ref.node.name.case == CaseSensitivity::CaseInsensitive
+                && matches!(var_ref.node.qualifier, ast::ScopeQualifier::Unqualified) =>
+        {
+            let name = var_ref.node.name.value;
+            let ty_of_name = TYPES
+                .iter()
+                .find(|(type_name, ..)| *type_name == name)
+                .and_then(|(_, type_tag, _)| type_of_tag(type_tag))
+                .filter(|(canonical, ..)| *canonical == name);
+            match ty_of_name {
+                Some((_, type_tag, params)) => {
+                    Ok(sexp(type_tag, std::iter::repeat_with(null).take(*params)))
+                }
+                None => expr_to_ion(ty),
+            }
+        }
+        ty => expr_to_ion(ty),
+    }
+}
+
+/// The name, tag, and number of parameters of the type whose Ion representation is tagged `tag`.
+fn type_of_tag(tag: &str) -> Option<&'static (&'static str, &'static str, usize)> {
+    TYPES.iter().find(|(_, type_tag, _)| *type_tag == tag)
+}
+
+fn ast_type_to_ion(ty: &ast::Type) -> Result<Element, IonAstError> {
+    let parts = match ty {
+        ast::Type::CustomType(ty) => &ty.parts,
+        ty => {
+            let (type_tag, _) = AST_TYPES
+                .iter()
+                .find(|(_, type_of)| type_of() == *ty)
+                .expect("a type other than custom types");
+            return Ok(tag(type_tag));
+        }
+    };
+    let parts = parts
+        .iter()
+        .map(|part| match part {
+            ast::CustomTypePart::Name(name) => Ok(symbol(name)),
+            ast::CustomTypePart::Parameterized(name, params) => {
+                let mut elements = vec![symbol(name)];
+                for param in params {
+                    elements.push(match param {
+                        ast::CustomTypeParam::Lit(lit) => lit_to_ion(lit)?,
+                        ast::CustomTypeParam::Type(ty) => ast_type_to_ion(ty)?,
+                    });
+                }
+                Ok(sexp("parameterized", elements))
+            }
+        })
+        .collect::<Result<Vec<_>, IonAstError>>()?;
+    Ok(sexp("custom_type", parts))
+}
+
+fn call_to_ion(call: &ast::Call) -> Result<Element, IonAstError> {
+    let ast::Call { func_name, args } = call;
+    // partiql-lang-kotlin's s-expressions of calls are read as calls of lowercase names
+    let name = match func_name.case {
+        CaseSensitivity::CaseInsensitive => Some(func_name.value),
+        CaseSensitivity::CaseSensitive => None,
+    };
+    let positional = args
+        .iter()
+        .all(|arg| matches!(arg.node, ast::CallArg::Positional(_)));
+    match (name, args.as_slice()) {
+        (Some(cast), [value, ty]) if CAST_FUNCTIONS.contains(&cast) => {
+            if let (
+                ast::CallArg::Positional(value),
+                ast::CallArg::Named(ast::CallArgNamed { name, value: ty }),
+            ) = (&value.node, &ty.node)
+            {
+                if name.value == "AS" && name.case == CaseSensitivity::CaseSensitive {
+                    return Ok(sexp(cast, [expr_to_ion(value)?, type_to_ion(ty)?]));
+                }
+            }
+        }
+        (Some("nullif"), [_, _]) if positional => {
+            return Ok(sexp("null_if", call_args_to_ion(args)?));
+        }
+        (Some("coalesce"), _) if positional => {
+            return Ok(sexp("coalesce", call_args_to_ion(args)?));
+        }
+        _ => {}
+    }
+    let mut elements = vec![symbol(func_name)];
+    elements.extend(call_args_to_ion(args)?);
+    Ok(sexp("call", elements))
+}
+
+fn call_args_to_ion(args: &[AstNode<ast::CallArg>]) -> Result<Vec<Element>, IonAstError> {
+    args.iter()
+        .map(|arg| {
+            Ok(match &arg.node {
+                ast::CallArg::Star() => tag("call_arg_star"),
+                ast::CallArg::Positional(expr) => expr_to_ion(expr)?,
+                ast::CallArg::PositionalType(ty) => sexp("call_arg_type", [ast_type_to_ion(ty)?]),
+                ast::CallArg::Named(ast::CallArgNamed { name, value }) => {
+                    sexp("call_arg_named", [symbol(name), expr_to_ion(value)?])
+                }
+                ast::CallArg::NamedType(ast::CallArgNamedType { name, ty }) => {
+                    sexp("call_arg_named_type", [symbol(name), ast_type_to_ion(ty)?])
+                }
+            })
+        })
+        .collect()
+}
+
+fn lit_to_ion(lit: &ast::Lit) -> Result<Element, IonAstError> {
+    let value = match lit {
+        ast::Lit::Null => null(),
+        ast::Lit::Missing => return Ok(tag("missing")),
+        ast::Lit::Int8Lit(i) => Element::integer(*i as i64),
+        ast::Lit::Int16Lit(i) => Element::integer(*i as i64),
+        ast::Lit::Int32Lit(i) => Element::integer(*i as i64),
+        ast::Lit::Int64Lit(i) => Element::integer(*i),
+        ast::Lit::DecimalLit(d) => decimal(d),
+        ast::Lit::RealLit(f) | ast::Lit::FloatLit(f) => Element::float(*f as f64),
+        ast::Lit::DoubleLit(f) => Element::float(*f),
+        ast::Lit::BoolLit(b) => Element::boolean(*b),
+        ast::Lit::CharStringLit(s) => Element::string(*s),
+        ast::Lit::IonStringLit(ion) => {
+            let value =
+                Element::read_one(ion).map_err(|_| IonAstError::InvalidIon(ion.to_string()))?;
+            // Ion literals read as other literals, or as other text, are written as text
+            if lit_of(&value, &Arena::new()) != ast::Lit::IonStringLit(ion) {
+                return Ok(sexp("ion_lit", [Element::string(*ion)]));
+            }
+            value
+        }
+        ast::Lit::StructLit(tuple) => return expr_pairs_to_ion("struct", &tuple.node.fields),
+        ast::Lit::BagLit(bag) => return exprs("bag", bag.node.values.iter().map(AsRef::as_ref)),
+        ast::Lit::ListLit(list) => {
+            return exprs("list", list.node.values.iter().map(AsRef::as_ref))
+        }
+        ast::Lit::NumericLit(d) => return Ok(sexp("numeric_lit", [decimal(d)])),
+        ast::Lit::NationalCharStringLit(s) => {
+            return Ok(sexp("national_char_string_lit", [Element::string(*s)]))
+        }
+        ast::Lit::BitStringLit(s) => return Ok(sexp("bit_string_lit", [Element::string(*s)])),
+        ast::Lit::HexStringLit(s) => return Ok(sexp("hex_string_lit", [Element::string(*s)])),
+        ast::Lit::TypedLit(s, ty) => {
+            return Ok(sexp(
+                "typed_lit",
+                [Element::string(*s), ast_type_to_ion(ty)?],
+            ))
+        }
+        ast::Lit::IntervalLit(ast::IntervalLit { value, qualifier }) => {
+            let ast::IntervalQualifier {
+                start,
+                precision,
+                end,
+                fractional_precision,
+            } = qualifier;
+            return Ok(sexp(
+                "interval_lit",
+                [
+                    Element::string(*value),
+                    interval_field_to_ion(start),
+                    opt_integer(*precision),
+                    end.as_ref().map_or_else(null, interval_field_to_ion),
+                    opt_integer(*fractional_precision),
+                ],
+            ));
+        }
+    };
+    Ok(sexp("lit", [value]))
+}
+
+fn interval_field_to_ion(field: &ast::IntervalField) -> Element {
+    tag(match field {
+        ast::IntervalField::Year => "year",
+        ast::IntervalField::Month => "month",
+        ast::IntervalField::Day => "day",
+        ast::IntervalField::Hour => "hour",
+        ast::IntervalField::Minute => "minute",
+        ast::IntervalField::Second => "second",
+    })
+}
+
+/// An s-expression, e.g., `(plus a b)`.
+struct Tagged<'a> {
+    tag: &'a str,
+    args: Vec<&'a Element>,
+}
+
+impl<'a> Tagged<'a> {
+    /// The tag and the arguments of the s-expression, e.g., `plus` and `[a, b]` for `(plus a b)`.
+    fn parts(&self) -> (&'a str, &[&'a Element]) {
+        (self.tag, &self.args)
+    }
+}
+
+fn tagged(element: &Element) -> Result<Tagged<'_>, IonAstError> {
+    let elements = match element.value() {
+        Value::SExp(elements) => elements,
+        _ => return Err(malformed(element)),
+    };
+    let mut elements = elements.elements();
+    let tag = elements
+        .next()
+        .and_then(Element::as_symbol)
+        .and_then(|tag| tag.text())
+        .ok_or_else(|| malformed(element))?;
+    Ok(Tagged {
+        tag,
+        args: elements.collect(),
+    })
+}
+
+/// The tag of the s-expression `element` without arguments, e.g., `asc` for `(asc)`.
+fn tag_of(element: &Element) -> Result<&str, IonAstError> {
+    match tagged(element)?.parts() {
+        (tag, []) => Ok(tag),
+        _ => Err(malformed(element)),
+    }
+}
+
+fn symbol_of<'a>(element: &Element, arena: &'a Arena) -> Result<SymbolPrimitive<'a>, IonAstError> {
+    let value = element
+        .as_symbol()
+        .and_then(|symbol| symbol.text())
+        .ok_or_else(|| malformed(element))?;
+    let case = match element.annotations().contains(CASE_SENSITIVE) {
+        true => CaseSensitivity::CaseSensitive,
+        false => CaseSensitivity::CaseInsensitive,
+    };
+    Ok(SymbolPrimitive {
+        value: arena.alloc_str(value),
+        case,
+    })
+}
+
+fn opt_symbol_of<'a>(
+    element: &Element,
+    arena: &'a Arena,
+) -> Result<Option<SymbolPrimitive<'a>>, IonAstError> {
+    (!element.is_null())
+        .then(|| symbol_of(element, arena))
+        .transpose()
+}
+
+fn string_of<'a>(element: &Element, arena: &'a Arena) -> Result<&'a str, IonAstError> {
+    let string = element.as_string().ok_or_else(|| malformed(element))?;
+    Ok(arena.alloc_str(string))
+}
+
+fn opt_integer_of(element: &Element) -> Result<Option<u32>, IonAstError> {
+    (!element.is_null())
+        .then(|| {
+            element
+                .as_int()
+                .and_then(IntAccess::as_i64)
+                .and_then(|integer| u32::try_from(integer).ok())
+                .ok_or_else(|| malformed(element))
+        })
+        .transpose()
+}
+
+fn set_quantifier_of(element: &Element) -> Result<ast::SetQuantifier, IonAstError> {
+    match tag_of(element)? {
+        "all" => Ok(ast::SetQuantifier::All),
+        "distinct" => Ok(ast::SetQuantifier::Distinct),
+        _ => Err(malformed(element)),
+    }
+}
+
+fn case_sensitivity_of(element: &Element) -> Result<CaseSensitivity, IonAstError> {
+    match tag_of(element)? {
+        "case_sensitive" => Ok(CaseSensitivity::CaseSensitive),
+        "case_insensitive" => Ok(CaseSensitivity::CaseInsensitive),
+        _ => Err(malformed(element)),
+    }
+}
+
+fn interval_field_of(element: &Element) -> Result<ast::IntervalField, IonAstError> {
+    Ok(match tag_of(element)? {
+        "year" => ast::IntervalField::Year,
+        "month" => ast::IntervalField::Month,
+        "day" => ast::IntervalField::Day,
+        "hour" => ast::IntervalField::Hour,
+        "minute" => ast::IntervalField::Minute,
+        "second" => ast::IntervalField::Second,
+        _ => return Err(malformed(element)),
+    })
+}
+
+fn window_frame_of(element: &Element) -> Result<ast::WindowFrame, IonAstError> {
+    let bound = |bound: &Element| -> Result<_, IonAstError> {
+        let offset = |offset: &Element| {
+            offset
+                .as_int()
+                .and_then(IntAccess::as_i64)
+                .and_then(|offset| u64::try_from(offset).ok())
+                .ok_or_else(|| malformed(offset))
+        };
+        Ok(match tagged(bound)?.parts() {
+            ("unbounded_preceding", []) => ast::WindowFrameBound::UnboundedPreceding,
+            ("preceding", [n]) => ast::WindowFrameBound::Preceding(offset(n)?),
+            ("current_row", []) => ast::WindowFrameBound::CurrentRow,
+            ("following", [n]) => ast::WindowFrameBound::Following(offset(n)?),
+            ("unbounded_following", []) => ast::WindowFrameBound::UnboundedFollowing,
+            _ => return Err(malformed(bound)),
+        })
+    };
+    match tagged(element)?.parts() {
+        ("window_frame", [units, start, end]) => Ok(ast::WindowFrame {
+            units: match tag_of(units)? {
+                "rows" => ast::WindowFrameUnits::Rows,
+                "range" => ast::WindowFrameUnits::Range,
+                _ => return Err(malformed(units)),
+            },
+            start: bound(start)?,
+            end: bound(end)?,
+        }),
+        _ => Err(malformed(element)),
+    }
+}
+
+fn bin_op_kind(op: &str) -> Option<ast::BinOpKind> {
+    Some(match op {
+        "plus" => ast::BinOpKind::Add,
+        "minus" => ast::BinOpKind::Sub,
+        "times" => ast::BinOpKind::Mul,
+        "divide" => ast::BinOpKind::Div,
+        "modulo" => ast::BinOpKind::Mod,
+        "power" => ast::BinOpKind::Exp,
+        "concat" => ast::BinOpKind::Concat,
+        "and" => ast::BinOpKind::And,
+        "or" => ast::BinOpKind::Or,
+        "eq" => ast::BinOpKind::Eq,
+        "ne" => ast::BinOpKind::Ne,
+        "gt" => ast::BinOpKind::Gt,
+        "gte" => ast::BinOpKind::Gte,
+        "lt" => ast::BinOpKind::Lt,
+        "lte" => ast::BinOpKind::Lte,
+        "is_distinct_from" => ast::BinOpKind::IsDistinctFrom,
+        _ => return None,
+    })
+}
+
+fn decimal_of(d: &Decimal) -> Option<RustDecimal> {
+    let text = d.to_string().replace('d', "e");
+    RustDecimal::from_str(&text)
+        .or_else(|_| RustDecimal::from_scientific(&text))
+        .ok()
+}
+
+fn lit_of<'a>(value: &Element, arena: &'a Arena) -> ast::Lit<'a> {
+    if value.annotations().is_empty() {
+        match value.value() {
+            Value::Null(IonType::Null) => return ast::Lit::Null,
+            Value::Bool(b) => return ast::Lit::BoolLit(*b),
+            Value::Int(i) => {
+                if let Some(i) = i.as_i64() {
+                    return ast::Lit::Int64Lit(i);
+                }
+            }
+            Value::Float(f) => return ast::Lit::DoubleLit(*f),
+            Value::Decimal(d) => {
+                if let Some(d) = decimal_of(d) {
+                    return ast::Lit::DecimalLit(d);
+                }
+            }
+            Value::String(s) => return ast::Lit::CharStringLit(arena.alloc_str(s.text())),
+            _ => {}
+        }
+    }
+    ast::Lit::IonStringLit(arena.alloc_str(&value.to_string()))
+}
+
+fn is_lit(expr: &ast::Expr) -> bool {
+    matches!(expr, ast::Expr::Lit(_))
+}
+
+/// Whether `element` is one of the `ORDER BY`, `LIMIT`, and `OFFSET` clauses of a query.
+fn is_query_clause(element: &Element) -> bool {
+    tagged(element).is_ok_and(|clause| matches!(clause.tag, "order" | "limit" | "offset"))
+}
+
+/// Collects the results of `iter` into a vector allocated in `arena`, or the first error.
+fn collect_in<'a, T>(
+    arena: &'a Arena,
+    iter: impl IntoIterator<Item = Result<T, IonAstError>>,
+) -> Result<arena::Vec<'a, T>, IonAstError> {
+    let mut vec = arena::Vec::new_in(arena);
+    for item in iter {
+        vec.push(item?);
+    }
+    Ok(vec)
+}
+
+/// The `ORDER BY`, and `LIMIT` and `OFFSET` clauses of a query.
+type QueryClauses<'a> = (
+    Option<arena::Box<'a, AstNode<ast::OrderByExpr<'a>>>>,
+    Option<arena::Box<'a, AstNode<ast::LimitOffsetClause<'a>>>>,
+);
+
+/// Reads the AST from Ion, numbering its nodes.
+struct Reader<'a> {
+    arena: &'a Arena,
+    next_id: u32,
+}
+
+impl<'a> Reader<'a> {
+    fn new(arena: &'a Arena) -> Self {
+        Reader { arena, next_id: 0 }
+    }
+
+    fn node<T>(&mut self, node: T) -> AstNode<T> {
+        self.next_id += 1;
+        AstNode {
+            id: NodeId(self.next_id),
+            node,
+        }
+    }
+
+    /// The query `expr`, if it is a query, or a query of `expr` otherwise.
+    fn query(&mut self, expr: ast::Expr<'a>) -> AstNode<ast::Query<'a>> {
+        match expr {
+            ast::Expr::Query(query) => query,
+            expr => {
+                let set = self.node(ast::QuerySet::Expr(arena::Box::new_in(expr, self.arena)));
+                self.query_of(set)
+            }
+        }
+    }
+
+    /// A query of `set`, without clauses.
+    fn query_of(&mut self, set: AstNode<ast::QuerySet<'a>>) -> AstNode<ast::Query<'a>> {
+        self.node(ast::Query {
+            set,
+            order_by: None,
+            limit_offset: None,
+        })
+    }
+
+    fn boxed(&mut self, element: &Element) -> Result<arena::Box<'a, ast::Expr<'a>>, IonAstError> {
+        let expr = self.expr(element)?;
+        Ok(arena::Box::new_in(expr, self.arena))
+    }
+
+    fn opt_expr(
+        &mut self,
+        element: &Element,
+    ) -> Result<Option<arena::Box<'a, ast::Expr<'a>>>, IonAstError> {
+        (!element.is_null())
+            .then(|| self.boxed(element))
+            .transpose()
+    }
+
+    fn exprs(
+        &mut self,
+        elements: &[&Element],
+    ) -> Result<arena::Vec<'a, arena::Box<'a, ast::Expr<'a>>>, IonAstError> {
+        collect_in(
+            self.arena,
+            elements.iter().map(|element| self.boxed(element)),
+        )
+    }
+
+    fn expr_pairs(
+        &mut self,
+        elements: &[&Element],
+    ) -> Result<arena::Vec<'a, ast::ExprPair<'a>>, IonAstError> {
+        collect_in(
+            self.arena,
+            elements
+                .iter()
+                .map(|element| match tagged(element)?.parts() {
+                    ("expr_pair", [first, second]) => Ok(ast::ExprPair {
+                        first: self.boxed(first)?,
+                        second: self.boxed(second)?,
+                    }),
+                    _ => Err(malformed(element)),
+                }),
+        )
+    }
+
+    fn positional_args(
+        &mut self,
+        elements: &[&Element],
+    ) -> Result<arena::Vec<'a, AstNode<ast::CallArg<'a>>>, IonAstError> {
+        collect_in(
+            self.arena,
+            elements.iter().map(|element| {
+                let arg = ast::CallArg::Positional(self.boxed(element)?);
+                Ok(self.node(arg))
+            }),
+        )
+    }
+
+    fn call_args(
+        &mut self,
+        elements: &[&Element],
+    ) -> Result<arena::Vec<'a, AstNode<ast::CallArg<'a>>>, IonAstError> {
+        collect_in(
+            self.arena,
+            elements.iter().map(|element| {
+                let arg = match tagged(element)?.parts() {
+                    ("call_arg_star", []) => ast::CallArg::Star(),
+                    ("call_arg_type", [ty]) => ast::CallArg::PositionalType(self.ast_type(ty)?),
+                    ("call_arg_named", [name, value]) => ast::CallArg::Named(ast::CallArgNamed {
+                        name: symbol_of(name, self.arena)?,
+                        value: self.boxed(value)?,
+                    }),
+                    ("call_arg_named_type", [name, ty]) => {
+                        ast::CallArg::NamedType(ast::CallArgNamedType {
+                            name: symbol_of(name, self.arena)?,
+                            ty: self.ast_type(ty)?,
+                        })
+                    }
+                    _ => ast::CallArg::Positional(self.boxed(element)?),
+                };
+                Ok(self.node(arg))
+            }),
+        )
+    }
+
+    fn call(
+        &mut self,
+        name: &str,
+        args: arena::Vec<'a, AstNode<ast::CallArg<'a>>>,
+    ) -> ast::Expr<'a> {
+        let func_name = SymbolPrimitive {
+            value: self.arena.alloc_str(name),
+            case: CaseSensitivity::CaseInsensitive,
+        };
+        ast::Expr::Call(self.node(ast::Call { func_name, args }))
+    }
+
+    /// Reads the type `element` as the expression naming it, e.g., the `INT` of `a IS INT`, or
+    /// the expression `element` written instead of a type, e.g., the `VARCHAR(2)` of `a IS
+    /// VARCHAR(2)`.
+    fn type_expr(&mut self, element: &Element) -> Result<ast::Expr<'a>, IonAstError> {
+        let ty = tagged(element)?;
+        let (tag, params) = ty.parts();
+        let (name, _, params_len) = match tag {
+            "null_type" => return Ok(ast::Expr::Lit(self.node(ast::Lit::Null))),
+            "missing_type" => return Ok(ast::Expr::Lit(self.node(ast::Lit::Missing))),
+            _ => match type_of_tag(tag) {
+                Some(ty) => ty,
+                None => return self.expr(element),
+            },
+        };
+        if params.len() != *params_len {
+            return Err(malformed(element));
+        }
+        if params.iter().any(|param| !param.is_null()) {
+            return Err(IonAstError::Unsupported(format!(
+                "the parameterized type `{element}`"
+            )));
+        }
+        let var_ref = ast::VarRef {
+            name: SymbolPrimitive {
+                value: name,
+                case: CaseSensitivity::CaseInsensitive,
+            },
+            qualifier: ast::ScopeQualifier::Unqualified,
+        };
+        Ok(ast::Expr::VarRef(self.node(var_ref)))
+    }
+
+    fn ast_type(&mut self, element: &Element) -> Result<ast::Type<'a>, IonAstError> {
+        match tagged(element)?.parts() {
+            ("custom_type", parts) if !parts.is_empty() => {
+                let parts = collect_in(
+                    self.arena,
+                    parts.iter().map(|part| self.custom_type_part(part)),
+                )?;
+                Ok(ast::Type::CustomType(ast::CustomType { parts }))
+            }
+            (tag, []) => AST_TYPES
+                .iter()
+                .find(|(type_tag, _)| *type_tag == tag)
+                .map(|(_, type_of)| type_of())
+                .ok_or_else(|| malformed(element)),
+            _ => Err(malformed(element)),
+        }
+    }
+
+    fn custom_type_part(
+        &mut self,
+        element: &Element,
+    ) -> Result<ast::CustomTypePart<'a>, IonAstError> {
+        if element.as_symbol().is_some() {
+            return Ok(ast::CustomTypePart::Name(symbol_of(element, self.arena)?));
+        }
+        match tagged(element)?.parts() {
+            ("parameterized", [name, params @ ..]) if !params.is_empty() => {
+                let name = symbol_of(name, self.arena)?;
+                let params = collect_in(
+                    self.arena,
+                    params.iter().map(|param| {
+                        let tag = tagged(param)?.tag;
+                        if tag == "custom_type" || AST_TYPES.iter().any(|(t, _)| *t == tag) {
+                            return Ok(ast::CustomTypeParam::Type(self.ast_type(param)?));
+                        }
+                        match self.expr(param)? {
+                            ast::Expr::Lit(lit) => Ok(ast::CustomTypeParam::Lit(lit.node)),
+                            _ => Err(malformed(param)),
+                        }
+                    }),
+                )?;
+                Ok(ast::CustomTypePart::Parameterized(name, params))
+            }
+            _ => Err(malformed(element)),
+        }
+    }
+
+    fn expr(&mut self, element: &Element) -> Result<ast::Expr<'a>, IonAstError> {
+        let expr = tagged(element)?;
+        let (tag, args) = expr.parts();
+        Ok(match (tag, args) {
+            ("lit", [value]) => ast::Expr::Lit(self.node(lit_of(value, self.arena))),
+            ("missing", []) => ast::Expr::Lit(self.node(ast::Lit::Missing)),
+            ("ion_lit", [text]) => {
+                let lit = ast::Lit::IonStringLit(string_of(text, self.arena)?);
+                ast::Expr::Lit(self.node(lit))
+            }
+            ("numeric_lit", [value]) => {
+                let d = value
+                    .as_decimal()
+                    .and_then(decimal_of)
+                    .ok_or_else(|| malformed(value))?;
+                ast::Expr::Lit(self.node(ast::Lit::NumericLit(d)))
+            }
+            ("national_char_string_lit" | "bit_string_lit" | "hex_string_lit", [value]) => {
+                let value = string_of(value, self.arena)?;
+                let lit = match tag {
+                    "national_char_string_lit" => ast::Lit::NationalCharStringLit(value),
+                    "bit_string_lit" => ast::Lit::BitStringLit(value),
+                    _ => ast::Lit::HexStringLit(value),
+                };
+                ast::Expr::Lit(self.node(lit))
+            }
+            ("typed_lit", [value, ty]) => {
+                let lit = ast::Lit::TypedLit(string_of(value, self.arena)?, self.ast_type(ty)?);
+                ast::Expr::Lit(self.node(lit))
+            }
+            ("interval_lit", [value, start, precision, end, fractional_precision]) => {
+                let qualifier = ast::IntervalQualifier {
+                    start: interval_field_of(start)?,
+                    precision: opt_integer_of(precision)?,
+                    end: (!end.is_null())
+                        .then(|| interval_field_of(end))
+                        .transpose()?,
+                    fractional_precision: opt_integer_of(fractional_precision)?,
+                };
+                let lit = ast::Lit::IntervalLit(ast::IntervalLit {
+                    value: string_of(value, self.arena)?,
+                    qualifier,
+                });
+                ast::Expr::Lit(self.node(lit))
+            }
+            ("id", [name, case, qualifier]) => {
+                let mut name = symbol_of(name, self.arena)?;
+                name.case = case_sensitivity_of(case)?;
+                let qualifier = match tag_of(qualifier)? {
+                    "unqualified" => ast::ScopeQualifier::Unqualified,
+                    "locals_first" => ast::ScopeQualifier::Qualified,
+                    _ => return Err(malformed(qualifier)),
+                };
+                ast::Expr::VarRef(self.node(ast::VarRef { name, qualifier }))
+            }
+            ("parameter", [index]) => {
+                let index = index
+                    .as_int()
+                    .and_then(IntAccess::as_i64)
+                    .and_then(|index| usize::try_from(index).ok())
+                    .filter(|index| *index > 0)
+                    .ok_or_else(|| malformed(element))?;
+                ast::Expr::Parameter(self.node(ast::Parameter::Positional(index - 1)))
+            }
+            ("named_parameter", [name]) => {
+                let name = symbol_of(name, self.arena)?;
+                ast::Expr::Parameter(self.node(ast::Parameter::Named(name)))
+            }
+            ("pos" | "neg" | "not", [expr]) => {
+                let kind = match tag {
+                    "pos" => ast::UniOpKind::Pos,
+                    "neg" => ast::UniOpKind::Neg,
+                    _ => ast::UniOpKind::Not,
+                };
+                let expr = self.boxed(expr)?;
+                ast::Expr::UniOp(self.node(ast::UniOp { kind, expr }))
+            }
+            (op, [first, rest @ ..]) if !rest.is_empty() && bin_op_kind(op).is_some() => {
+                let mut lhs = self.expr(first)?;
+                for operand in rest {
+                    let bin_op = ast::BinOp {
+                        kind: bin_op_kind(op).expect("binary operator"),
+                        lhs: arena::Box::new_in(lhs, self.arena),
+                        rhs: self.boxed(operand)?,
+                    };
+                    lhs = ast::Expr::BinOp(self.node(bin_op));
+                }
+                lhs
+            }
+            ("is_type", [value, ty]) => {
+                let bin_op = ast::BinOp {
+                    kind: ast::BinOpKind::Is,
+                    lhs: self.boxed(value)?,
+                    rhs: arena::Box::new_in(self.type_expr(ty)?, self.arena),
+                };
+                ast::Expr::BinOp(self.node(bin_op))
+            }
+            ("like", [value, pattern, escape]) => {
+                let like = ast::Like {
+                    value: self.boxed(value)?,
+                    pattern: self.boxed(pattern)?,
+                    escape: self.opt_expr(escape)?,
+                };
+                ast::Expr::Like(self.node(like))
+            }
+            ("between", [value, from, to]) => {
+                let between = ast::Between {
+                    value: self.boxed(value)?,
+                    from: self.boxed(from)?,
+                    to: self.boxed(to)?,
+                };
+                ast::Expr::Between(self.node(between))
+            }
+            ("in_collection", [lhs, rhs]) => {
+                let in_expr = ast::In {
+                    lhs: self.boxed(lhs)?,
+                    rhs: self.boxed(rhs)?,
+                };
+                ast::Expr::In(self.node(in_expr))
+            }
+            ("call", [name, expr]) if symbol_of(name, self.arena)?.value == "exists" => {
+                let expr = self.boxed(expr)?;
+                ast::Expr::Exists(self.node(ast::Exists { expr }))
+            }
+            ("call", [name, args @ ..]) => {
+                let func_name = symbol_of(name, self.arena)?;
+                let args = self.call_args(args)?;
+                ast::Expr::Call(self.node(ast::Call { func_name, args }))
+            }
+            ("null_if", [lhs, rhs]) => {
+                let args = self.positional_args(&[lhs, rhs])?;
+                self.call("nullif", args)
+            }
+            ("coalesce", args) => {
+                let args = self.positional_args(args)?;
+                self.call("coalesce", args)
+            }
+            (cast, [value, ty]) if CAST_FUNCTIONS.contains(&cast) => {
+                let value = self.positional_args(&[value])?;
+                let ty = ast::CallArg::Named(ast::CallArgNamed {
+                    name: SymbolPrimitive {
+                        value: "AS",
+                        case: CaseSensitivity::CaseSensitive,
+                    },
+                    value: arena::Box::new_in(self.type_expr(ty)?, self.arena),
+                });
+                let ty = self.node(ty);
+                let args = arena::Vec::from_iter_in(value.into_iter().chain([ty]), self.arena);
+                self.call(cast, args)
+            }
+            ("call_agg", [setq, name, args @ ..]) => {
+                let call_agg = ast::CallAgg {
+                    func_name: symbol_of(name, self.arena)?,
+                    setq: (!setq.is_null())
+                        .then(|| set_quantifier_of(setq))
+                        .transpose()?,
+                    args: self.call_args(args)?,
+                };
+                ast::Expr::CallAgg(self.node(call_agg))
+            }
+            ("call_window", [name, over, args @ ..]) => {
+                let (partition_by, order_by, frame) = match tagged(over)?.parts() {
+                    ("over", [partition_by, order_by]) => (*partition_by, *order_by, None),
+                    ("over", [partition_by, order_by, frame]) => {
+                        (*partition_by, *order_by, Some(window_frame_of(frame)?))
+                    }
+                    _ => return Err(malformed(over)),
+                };
+                let partition_by = match partition_by.is_null() {
+                    true => arena::Vec::new_in(self.arena),
+                    false => match tagged(partition_by)?.parts() {
+                        ("window_partition_list", exprs) if !exprs.is_empty() => {
+                            self.exprs(exprs)?
+                        }
+                        _ => return Err(malformed(partition_by)),
+                    },
+                };
+                let order_by = match order_by.is_null() {
+                    true => arena::Vec::new_in(self.arena),
+                    false => match tagged(order_by)?.parts() {
+                        ("window_sort_spec_list", specs) if !specs.is_empty() => {
+                            self.sort_specs(specs)?
+                        }
+                        _ => return Err(malformed(order_by)),
+                    },
+                };
+                let call_window = ast::CallWindow {
+                    func_name: symbol_of(name, self.arena)?,
+                    args: self.call_args(args)?,
+                    partition_by,
+                    order_by,
+                    frame,
+                };
+                let call_window = arena::Box::new_in(call_window, self.arena);
+                ast::Expr::CallWindow(self.node(call_window))
+            }
+            ("simple_case", [expr, cases, default]) => {
+                let case = ast::SimpleCase {
+                    expr: self.boxed(expr)?,
+                    cases: self.expr_pair_list(cases)?,
+                    default: self.opt_expr(default)?,
+                };
+                ast::Expr::Case(self.node(ast::Case::SimpleCase(case)))
+            }
+            ("searched_case", [cases, default]) => {
+                let case = ast::SearchedCase {
+                    cases: self.expr_pair_list(cases)?,
+                    default: self.opt_expr(default)?,
+                };
+                ast::Expr::Case(self.node(ast::Case::SearchedCase(case)))
+            }
+            ("struct" | "struct_expr", fields) => {
+                let fields = self.expr_pairs(fields)?;
+                let tuple = self.node(ast::Struct { fields });
+                // constant collections are literals, as the parser reads them
+                if tag == "struct"
+                    && tuple
+                        .node
+                        .fields
+                        .iter()
+                        .all(|pair| is_lit(&pair.first) && is_lit(&pair.second))
+                {
+                    ast::Expr::Lit(self.node(ast::Lit::StructLit(tuple)))
+                } else {
+                    ast::Expr::Struct(tuple)
+                }
+            }
+            ("bag" | "bag_expr", values) => {
+                let values = self.exprs(values)?;
+                let bag = self.node(ast::Bag { values });
+                if tag == "bag" && bag.node.values.iter().all(|value| is_lit(value)) {
+                    ast::Expr::Lit(self.node(ast::Lit::BagLit(bag)))
+                } else {
+                    ast::Expr::Bag(bag)
+                }
+            }
+            ("list" | "list_expr", values) => {
+                let values = self.exprs(values)?;
+                let list = self.node(ast::List { values });
+                if tag == "list" && list.node.values.iter().all(|value| is_lit(value)) {
+                    ast::Expr::Lit(self.node(ast::Lit::ListLit(list)))
+                } else {
+                    ast::Expr::List(list)
+                }
+            }
+            ("sexp", values) => {
+                let values = self.exprs(values)?;
+                ast::Expr::Sexp(self.node(ast::Sexp { values }))
+            }
+            ("path", [_, steps @ ..]) if !steps.is_empty() => {
+                let path = self.path(element)?;
+                ast::Expr::Path(self.node(path))
+            }
+            ("select", fields) => ast::Expr::Query(self.select(element, fields)?),
+            ("scalar_subquery", [query]) => {
+                let query = self.expr(query)?;
+                ast::Expr::ScalarSubQuery(self.query(query))
+            }
+            ("bag_op", [op, setq, first, rest @ ..]) if !rest.is_empty() => {
+                let bag_op = match tag_of(op)? {
+                    "union" => ast::BagOperator::Union,
+                    "except" => ast::BagOperator::Except,
+                    "intersect" => ast::BagOperator::Intersect,
+                    "outer_union" => ast::BagOperator::OuterUnion,
+                    "outer_except" => ast::BagOperator::OuterExcept,
+                    "outer_intersect" => ast::BagOperator::OuterIntersect,
+                    _ => return Err(malformed(op)),
+                };
+                let setq = set_quantifier_of(setq)?;
+                let lhs = self.expr(first)?;
+                let mut lhs = self.query(lhs);
+                for operand in rest {
+                    let rhs = self.expr(operand)?;
+                    let rhs = self.query(rhs);
+                    let bag_op = ast::BagOpExpr {
+                        bag_op: bag_op.clone(),
+                        setq: setq.clone(),
+                        lhs: arena::Box::new_in(lhs, self.arena),
+                        rhs: arena::Box::new_in(rhs, self.arena),
+                    };
+                    let bag_op = self.node(bag_op);
+                    let set =
+                        self.node(ast::QuerySet::BagOp(arena::Box::new_in(bag_op, self.arena)));
+                    lhs = self.query_of(set);
+                }
+                ast::Expr::Query(lhs)
+            }
+            ("values", rows) if !rows.is_empty() => {
+                let rows = self.exprs(rows)?;
+                let set = self.node(ast::QuerySet::Values(rows));
+                ast::Expr::Query(self.query_of(set))
+            }
+            ("table", [name]) => {
+                let table_name = symbol_of(name, self.arena)?;
+                let set = self.node(ast::QuerySet::Table(ast::QueryTable { table_name }));
+                ast::Expr::Query(self.query_of(set))
+            }
+            ("query_set", [set, clauses @ ..]) if !clauses.is_empty() => {
+                let set = self.expr(set)?;
+                let mut query = self.query(set);
+                if query.node.order_by.is_some() || query.node.limit_offset.is_some() {
+                    return Err(malformed(element));
+                }
+                (query.node.order_by, query.node.limit_offset) = self.query_clauses(clauses)?;
+                ast::Expr::Query(query)
+            }
+            _ => return Err(malformed(element)),
+        })
+    }
+
+    fn expr_pair_list(
+        &mut self,
+        element: &Element,
+    ) -> Result<arena::Vec<'a, ast::ExprPair<'a>>, IonAstError> {
+        match tagged(element)?.parts() {
+            ("expr_pair_list", pairs) => self.expr_pairs(pairs),
+            _ => Err(malformed(element)),
+        }
+    }
+
+    fn path(&mut self, element: &Element) -> Result<ast::Path<'a>, IonAstError> {
+        match tagged(element)?.parts() {
+            ("path", [root, steps @ ..]) => {
+                let root = self.boxed(root)?;
+                let steps = collect_in(self.arena, steps.iter().map(|step| self.path_step(step)))?;
+                Ok(ast::Path { root, steps })
+            }
+            _ => Err(malformed(element)),
+        }
+    }
+
+    fn path_step(&mut self, element: &Element) -> Result<ast::PathStep<'a>, IonAstError> {
+        Ok(match tagged(element)?.parts() {
+            ("path_expr", [index, case]) => {
+                let case = case_sensitivity_of(case)?;
+                let index = match lit_of_path(index) {
+                    Some(name) => {
+                        let var_ref = ast::VarRef {
+                            name: SymbolPrimitive {
+                                value: self.arena.alloc_str(name),
+                                case,
+                            },
+                            qualifier: ast::ScopeQualifier::Unqualified,
+                        };
+                        ast::Expr::VarRef(self.node(var_ref))
+                    }
+                    None => self.expr(index)?,
+                };
+                ast::PathStep::PathExpr(ast::PathExpr {
+                    index: arena::Box::new_in(index, self.arena),
+                })
+            }
+            ("path_expr", [index]) => ast::PathStep::PathExpr(ast::PathExpr {
+                index: self.boxed(index)?,
+            }),
+            ("path_wildcard", []) => ast::PathStep::PathWildCard,
+            ("path_unpivot", []) => ast::PathStep::PathUnpivot,
+            _ => return Err(malformed(element)),
+        })
+    }
+
+    fn with(&mut self, element: &Element) -> Result<AstNode<ast::WithClause<'a>>, IonAstError> {
+        let with = tagged(element)?;
+        let (recursive, elements) = match with.parts() {
+            ("with", elements) if !elements.is_empty() => (false, elements),
+            ("with_recursive", elements) if !elements.is_empty() => (true, elements),
+            _ => return Err(malformed(element)),
+        };
+        let withs = collect_in(
+            self.arena,
+            elements
+                .iter()
+                .map(|element| match tagged(element)?.parts() {
+                    ("with_element", [name, columns, subquery]) => {
+                        let columns = match columns.is_null() {
+                            true => None,
+                            false => match tagged(columns)?.parts() {
+                                ("column_list", names) if !names.is_empty() => Some(collect_in(
+                                    self.arena,
+                                    names.iter().map(|name| symbol_of(name, self.arena)),
+                                )?),
+                                _ => return Err(malformed(columns)),
+                            },
+                        };
+                        let subquery = self.expr(subquery)?;
+                        let with_element = ast::WithElement {
+                            query_name: symbol_of(name, self.arena)?,
+                            columns,
+                            subquery: self.node(subquery),
+                        };
+                        Ok(self.node(with_element))
+                    }
+                    _ => Err(malformed(element)),
+                }),
+        )?;
+        Ok(self.node(ast::WithClause { recursive, withs }))
+    }
+
+    fn select(
+        &mut self,
+        element: &Element,
+        fields: &[&Element],
+    ) -> Result<AstNode<ast::Query<'a>>, IonAstError> {
+        let clauses = fields
+            .iter()
+            .position(|field| is_query_clause(field))
+            .unwrap_or(fields.len());
+        let (fields, clauses) = fields.split_at(clauses);
+        let mut setq = ast::SetQuantifier::All;
+        let mut project = None;
+        let mut from = None;
+        let mut from_let = None;
+        let mut where_clause = None;
+        let mut group_by = None;
+        let mut having = None;
+        for field in fields {
+            match tagged(field)?.parts() {
+                ("setq", [quantifier]) => setq = set_quantifier_of(quantifier)?,
+                ("project", [projection]) => project = Some(self.projection(projection)?),
+                ("from", [source]) => {
+                    let source = self.source(source)?;
+                    from = Some(self.node(ast::FromClause { source }));
+                }
+                ("from_let", [bindings]) => from_let = Some(self.let_bindings(bindings)?),
+                ("where", [expr]) => {
+                    let expr = self.boxed(expr)?;
+                    where_clause = Some(arena::Box::new_in(
+                        self.node(ast::WhereClause { expr }),
+                        self.arena,
+                    ));
+                }
+                ("group", [group]) => {
+                    group_by = Some(arena::Box::new_in(self.group_by(group)?, self.arena))
+                }
+                ("having", [expr]) => {
+                    let expr = self.boxed(expr)?;
+                    having = Some(arena::Box::new_in(
+                        self.node(ast::HavingClause { expr }),
+                        self.arena,
+                    ));
+                }
+                _ => return Err(malformed(field)),
+            }
+        }
+        let (order_by, limit_offset) = self.query_clauses(clauses)?;
+        let kind = project.ok_or_else(|| malformed(element))?;
+        // as in the parser, only `SELECT` projections are quantified
+        let setq = (!matches!(kind, ast::ProjectionKind::ProjectPivot(_))).then_some(setq);
+        let project = self.node(ast::Projection { kind, setq });
+        let select = self.node(ast::Select {
+            project,
+            from,
+            from_let,
+            where_clause,
+            group_by,
+            having,
+        });
+        let set = self.node(ast::QuerySet::Select(arena::Box::new_in(
+            select, self.arena,
+        )));
+        Ok(self.node(ast::Query {
+            set,
+            order_by,
+            limit_offset,
+        }))
+    }
+
+    /// Reads the `ORDER BY`, `LIMIT`, and `OFFSET` clauses of a query.
+    fn query_clauses(&mut self, clauses: &[&Element]) -> Result<QueryClauses<'a>, IonAstError> {
+        let mut order_by = None;
+        let mut limit = None;
+        let mut offset = None;
+        for clause in clauses {
+            match tagged(clause)?.parts() {
+                ("order", [order]) => {
+                    order_by = Some(arena::Box::new_in(self.order_by(order)?, self.arena))
+                }
+                ("limit", [expr]) => limit = Some(self.boxed(expr)?),
+                ("offset", [expr]) => offset = Some(self.boxed(expr)?),
+                _ => return Err(malformed(clause)),
+            }
+        }
+        let limit_offset = (limit.is_some() || offset.is_some()).then(|| {
+            arena::Box::new_in(
+                self.node(ast::LimitOffsetClause { limit, offset }),
+                self.arena,
+            )
+        });
+        Ok((order_by, limit_offset))
+    }
+
+    fn projection(&mut self, element: &Element) -> Result<ast::ProjectionKind<'a>, IonAstError> {
+        Ok(match tagged(element)?.parts() {
+            ("project_star", []) => ast::ProjectionKind::ProjectStar,
+            ("project_list", items) if !items.is_empty() => {
+                let items = collect_in(
+                    self.arena,
+                    items.iter().map(|item| {
+                        let item = match tagged(item)?.parts() {
+                            ("project_all", [expr]) => {
+                                ast::ProjectItem::ProjectAll(ast::ProjectAll {
+                                    expr: self.boxed(expr)?,
+                                })
+                            }
+                            ("project_expr", [expr, as_alias]) => {
+                                ast::ProjectItem::ProjectExpr(ast::ProjectExpr {
+                                    expr: self.boxed(expr)?,
+                                    as_alias: opt_symbol_of(as_alias, self.arena)?,
+                                })
+                            }
+                            _ => return Err(malformed(item)),
+                        };
+                        Ok(self.node(item))
+                    }),
+                )?;
+                ast::ProjectionKind::ProjectList(items)
+            }
+            ("project_value", [expr]) => ast::ProjectionKind::ProjectValue(self.boxed(expr)?),
+            ("project_pivot", [value, key]) => {
+                let value = self.boxed(value)?;
+                let key = self.boxed(key)?;
+                ast::ProjectionKind::ProjectPivot(ast::ProjectPivot { key, value })
+            }
+            _ => return Err(malformed(element)),
+        })
+    }
+
+    fn source(&mut self, element: &Element) -> Result<ast::FromSource<'a>, IonAstError> {
+        Ok(match tagged(element)?.parts() {
+            (kind @ ("scan" | "unpivot"), [expr, as_alias, at_alias, by_alias]) => {
+                let from_let = ast::FromLet {
+                    expr: self.boxed(expr)?,
+                    kind: match kind {
+                        "scan" => ast::FromLetKind::Scan,
+                        _ => ast::FromLetKind::Unpivot,
+                    },
+                    as_alias: opt_symbol_of(as_alias, self.arena)?,
+                    at_alias: opt_symbol_of(at_alias, self.arena)?,
+                    by_alias: opt_symbol_of(by_alias, self.arena)?,
+                };
+                ast::FromSource::FromLet(self.node(from_let))
+            }
+            ("join", [kind, left, right, predicate]) => {
+                let left = arena::Box::new_in(self.source(left)?, self.arena);
+                let right = arena::Box::new_in(self.source(right)?, self.arena);
+                let kind = match (tag_of(kind)?, predicate.is_null()) {
+                    ("inner", true) => ast::JoinKind::Cross,
+                    ("inner", false) => ast::JoinKind::Inner,
+                    ("left", _) => ast::JoinKind::Left,
+                    ("right", _) => ast::JoinKind::Right,
+                    ("full", _) => ast::JoinKind::Full,
+                    _ => return Err(malformed(kind)),
+                };
+                let predicate = match predicate.is_null() {
+                    true => None,
+                    false => {
+                        let predicate = match tagged(predicate)?.parts() {
+                            ("using", paths) if !paths.is_empty() => ast::JoinSpec::Using(
+                                collect_in(self.arena, paths.iter().map(|path| self.path(path)))?,
+                            ),
+                            ("natural", []) => ast::JoinSpec::Natural,
+                            _ => ast::JoinSpec::On(self.boxed(predicate)?),
+                        };
+                        Some(self.node(predicate))
+                    }
+                };
+                let join = ast::Join {
+                    kind,
+                    left,
+                    right,
+                    predicate,
+                };
+                ast::FromSource::Join(self.node(join))
+            }
+            _ => return Err(malformed(element)),
+        })
+    }
+
+    fn let_bindings(&mut self, element: &Element) -> Result<AstNode<ast::Let<'a>>, IonAstError> {
+        match tagged(element)?.parts() {
+            ("let", bindings) if !bindings.is_empty() => {
+                let let_bindings = collect_in(
+                    self.arena,
+                    bindings
+                        .iter()
+                        .map(|binding| match tagged(binding)?.parts() {
+                            ("let_binding", [expr, name]) => Ok(ast::LetBinding {
+                                expr: self.boxed(expr)?,
+                                as_alias: symbol_of(name, self.arena)?,
+                            }),
+                            _ => Err(malformed(binding)),
+                        }),
+                )?;
+                Ok(self.node(ast::Let { let_bindings }))
+            }
+            _ => Err(malformed(element)),
+        }
+    }
+
+    fn group_by(
+        &mut self,
+        element: &Element,
+    ) -> Result<AstNode<ast::GroupByExpr<'a>>, IonAstError> {
+        let (strategy, keys, group_as) = match tagged(element)?.parts() {
+            ("group_by", [strategy, keys, group_as]) => (*strategy, *keys, *group_as),
+            _ => return Err(malformed(element)),
+        };
+        let strategy = match tag_of(strategy)? {
+            "group_full" => ast::GroupingStrategy::GroupFull,
+            "group_partial" => ast::GroupingStrategy::GroupPartial,
+            _ => return Err(malformed(strategy)),
+        };
+        let keys = match tagged(keys)?.parts() {
+            ("group_key_list", keys) if !keys.is_empty() => collect_in(
+                self.arena,
+                keys.iter().map(|key| match tagged(key)?.parts() {
+                    ("group_key", [expr, as_alias]) => {
+                        let key = ast::GroupKey {
+                            expr: self.boxed(expr)?,
+                            as_alias: opt_symbol_of(as_alias, self.arena)?,
+                        };
+                        Ok(self.node(key))
+                    }
+                    _ => Err(malformed(key)),
+                }),
+            )?,
+            _ => return Err(malformed(keys)),
+        };
+        let group_by = ast::GroupByExpr {
+            strategy,
+            keys,
+            group_as_alias: opt_symbol_of(group_as, self.arena)?,
+        };
+        Ok(self.node(group_by))
+    }
+
+    fn order_by(
+        &mut self,
+        element: &Element,
+    ) -> Result<AstNode<ast::OrderByExpr<'a>>, IonAstError> {
+        let sort_specs = match tagged(element)?.parts() {
+            ("order_by", specs) if !specs.is_empty() => self.sort_specs(specs)?,
+            _ => return Err(malformed(element)),
+        };
+        Ok(self.node(ast::OrderByExpr { sort_specs }))
+    }
+
+    fn sort_specs(
+        &mut self,
+        specs: &[&Element],
+    ) -> Result<arena::Vec<'a, AstNode<ast::SortSpec<'a>>>, IonAstError> {
+        collect_in(
+            self.arena,
+            specs.iter().map(|spec| match tagged(spec)?.parts() {
+                ("sort_spec", [expr, ordering, nulls]) => {
+                    let ordering_spec = match ordering.is_null() {
+                        true => None,
+                        false => Some(match tag_of(ordering)? {
+                            "asc" => ast::OrderingSpec::Asc,
+                            "desc" => ast::OrderingSpec::Desc,
+                            _ => return Err(malformed(ordering)),
+                        }),
+                    };
+                    let null_ordering_spec = match nulls.is_null() {
+                        true => None,
+                        false => Some(match tag_of(nulls)? {
+                            "nulls_first" => ast::NullOrderingSpec::First,
+                            "nulls_last" => ast::NullOrderingSpec::Last,
+                            _ => return Err(malformed(nulls)),
+                        }),
+                    };
+                    let spec = ast::SortSpec {
+                        expr: self.boxed(expr)?,
+                        ordering_spec,
+                        null_ordering_spec,
+                    };
+                    Ok(self.node(spec))
+                }
+                _ => Err(malformed(spec)),
+            }),
+        )
+    }
+}
+
+/// The name of the attribute that the index `(lit "<name>")` of a path step refers to.
+fn lit_of_path(index: &Element) -> Option<&str> {
+    match tagged(index).ok()?.parts() {
+        ("lit", [name]) => name.as_string(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn round_trip(ion: &str) {
+        let expected = Element::read_one(ion).unwrap();
+        let arena = Arena::new();
+        let query = from_ion(&expected, &arena).unwrap();
+        assert_eq!(to_ion(&query).unwrap(), expected, "{ion}");
+    }
+
+    #[test]
+    fn queries() {
+        round_trip(
+            "(query (select (setq (distinct)) \
+                (project (project_list \
+                    (project_expr (path (id c (case_insensitive) (unqualified)) \
+                        (path_expr (lit \"name\") (case_insensitive))) n) \
+                    (project_all (id o (case_insensitive) (locals_first))))) \
+                (from (join (left) \
+                    (scan (id customers (case_sensitive) (unqualified)) c null null) \
+                    (unpivot (id orders (case_insensitive) (unqualified)) o k null) \
+                    (eq (id c (case_insensitive) (unqualified)) (id o (case_insensitive) (unqualified))))) \
+                (from_let (let (let_binding (lit 1) one))) \
+                (where (and (gt (id a (case_insensitive) (unqualified)) (parameter 1)) \
+                    (not (is_type (id b (case_insensitive) (unqualified)) (null_type))))) \
+                (group (group_by (group_full) \
+                    (group_key_list (group_key (id a (case_insensitive) (unqualified)) k)) g)) \
+                (having (gt (call_agg (distinct) count (id b (case_insensitive) (unqualified))) (lit 1))) \
+                (order (order_by (sort_spec (id k (case_insensitive) (unqualified)) (desc) (nulls_last)))) \
+                (limit (lit 10)) \
+                (offset (lit 5))))",
+        );
+        round_trip(
+            "(query (bag_op (outer_union) (all) \
+                (select (project (project_value (lit 1.5)))) \
+                (select (project (project_pivot (lit \"v\") (lit \"k\"))) \
+                    (from (scan (id t (case_insensitive) (unqualified)) null null null)))))",
+        );
+        round_trip(
+            "(query (select (project (project_star)) \
+                (from (join (inner) \
+                    (scan (id t (case_insensitive) (unqualified)) null null null) \
+                    (scan (id u (case_insensitive) (unqualified)) null null null) \
+                    null))))",
+        );
+        round_trip(
+            "(query (with_recursive (with_element case_sensitive::X (column_list a) \
+                    (select (project (project_star)) \
+                        (from (scan (id t (case_insensitive) (unqualified)) null null null))))) \
+                (query_set (bag_op (union) (distinct) \
+                    (select (project (project_star)) \
+                        (from (join (inner) \
+                            (scan (id X (case_sensitive) (unqualified)) case_sensitive::T null null) \
+                            (scan (id u (case_insensitive) (unqualified)) null null null) \
+                            (using (path (id a (case_insensitive) (unqualified))))))) \
+                    (values (list (lit 1)))) \
+                    (order (order_by (sort_spec (lit 1) null null))) \
+                    (limit (lit 1))))",
+        );
+    }
+
+    #[test]
+    fn exprs() {
+        round_trip(
+            "(query (struct \
+                (expr_pair (lit \"a\") (plus (lit 1) (times (lit 2.5e0) (neg (lit 3))))) \
+                (expr_pair (lit \"b\") (list (lit true) (lit null) (missing) (lit {x: 1}))) \
+                (expr_pair (lit \"c\") (bag (sexp (lit 1)))) \
+                (expr_pair (lit \"d\") (like (lit \"abc\") (lit \"a%\") null)) \
+                (expr_pair (lit \"e\") (between (lit 2) (lit 1) (lit 3))) \
+                (expr_pair (lit \"f\") (in_collection (lit 1) (list (lit 1) (lit 2)))) \
+                (expr_pair (lit \"g\") (simple_case (lit 1) \
+                    (expr_pair_list (expr_pair (lit 1) (lit \"one\"))) null)) \
+                (expr_pair (lit \"h\") (searched_case \
+                    (expr_pair_list (expr_pair (lit true) (lit 1))) (lit 2))) \
+                (expr_pair (lit \"i\") (cast (lit 1) (decimal_type null null))) \
+                (expr_pair (lit \"j\") (coalesce (lit null) (null_if (lit 1) (lit 2)))) \
+                (expr_pair (lit \"k\") (call upper (lit \"a\"))) \
+                (expr_pair (lit \"l\") (call exists (select (project (project_star)) \
+                    (from (scan (id t (case_insensitive) (unqualified)) null null null))))) \
+                (expr_pair (lit \"m\") (path (id t (case_insensitive) (unqualified)) \
+                    (path_expr (lit \"a\") (case_sensitive)) (path_expr (lit 0) (case_sensitive)) \
+                    (path_expr (lit \"b\")) (path_wildcard) (path_unpivot)))))",
+        );
+        round_trip(
+            "(query (list \
+                (call TRIM (call_arg_named BOTH (lit \" \")) \
+                    (call_arg_named case_sensitive::FROM (id a (case_insensitive) (unqualified)))) \
+                (call CAST (id a (case_insensitive) (unqualified)) \
+                    (call_arg_named case_sensitive::AS (id INT (case_insensitive) (unqualified)))) \
+                (is_type (id a (case_insensitive) (unqualified)) \
+                    (call VARCHAR (lit 2))) \
+                (call_agg null COUNT (call_arg_star)) \
+                (call_window LAG (over (window_partition_list (id a (case_insensitive) (unqualified))) \
+                    (window_sort_spec_list (sort_spec (id b (case_insensitive) (unqualified)) null null)) \
+                    (window_frame (rows) (preceding 1) (current_row))) \
+                    (id x (case_insensitive) (unqualified))) \
+                (plus (lit 1) (scalar_subquery (select (project (project_star))))) \
+                (is_distinct_from (named_parameter p) (power (lit 2) (lit 3))) \
+                (typed_lit \"12:00\" (custom_type (parameterized TIME (lit 2)) WITH TIME ZONE)) \
+                (interval_lit \"1-6\" (year) null (month) null) \
+                (ion_lit \"{x:1}\") \
+                (ion_lit \"1\")))",
+        );
+    }
+
+    #[test]
+    fn to_ion_of_ast() {
+        let arena = Arena::new();
+        let mut reader = Reader::new(&arena);
+        let ty = reader.node(ast::CallArg::PositionalType(ast::Type::Integer4Type));
+        let foo = ast::Call {
+            func_name: SymbolPrimitive {
+                value: "foo",
+                case: CaseSensitivity::CaseSensitive,
+            },
+            args: arena::Vec::from_iter_in([ty], &arena),
+        };
+        let foo = ast::Expr::Call(reader.node(foo));
+        let query = reader.query(foo);
+        let query = reader.node(ast::TopLevelQuery { with: None, query });
+        let ion = to_ion(&query).unwrap();
+        assert_eq!(
+            ion.to_string(),
+            "(query (call case_sensitive::foo (call_arg_type (integer4_type))))"
+        );
+        let read = from_ion(&ion, &arena).unwrap();
+        assert!(ast::ast_eq(&query, &read));
+    }
+
+    #[test]
+    fn errors() {
+        let arena = Arena::new();
+        let read = |ion: &str| from_ion(&Element::read_one(ion).unwrap(), &arena);
+        assert!(matches!(
+            read("(query (plus (lit 1)))"),
+            Err(IonAstError::Malformed(_))
+        ));
+        assert!(matches!(
+            read("(query (select (from (scan (lit 1) null null null))))"),
+            Err(IonAstError::Malformed(_))
+        ));
+        assert!(matches!(
+            read("(dml (operations))"),
+            Err(IonAstError::Malformed(_))
+        ));
+        assert!(matches!(
+            read("(query (cast (lit 1) (character_type 2)))"),
+            Err(IonAstError::Unsupported(_))
+        ));
+    }
+}
@@ -4,5 +4,10 @@
 //!
 //! This API is currently unstable and subject to change.
 
+pub mod arena;
 pub mod ast;
+pub mod dot;
+#[cfg(feature = "ion")]
+pub mod ion;
+pub mod pretty;
 pub mod visit;
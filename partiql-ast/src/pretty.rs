@@ -0,0 +1,1050 @@
+//! Rendering of ASTs as PartiQL text.
+//!
+//! [`Item`]s, queries, and expressions implement [`Display`] by rendering themselves as canonical,
+//! single-line PartiQL text which parses back to the same AST. The rendering is stable: keywords are
+//! upper-cased, clauses & operators are separated by single spaces, and parentheses are only
+//! emitted where the precedence of operators requires them, e.g., `(a + b) * c`.
+//!
+//! Special forms, which the parser rewrites as function calls with named arguments, are rendered in
+//! their SQL syntax, e.g., `CAST(x AS INT)`, `EXTRACT(YEAR FROM x)`, `TRIM(BOTH ' ' FROM x)`, or
+//! `COLL_SUM(DISTINCT x)`, rather than `CAST(x, "AS": INT)`. As the parser keeps the keywords of
+//! special forms as the names of the arguments, they are rendered as written, e.g.,
+//! `cast(x as INT)`.
+
+use crate::ast::*;
+use std::fmt::{self, Display, Formatter};
+
+/// The precedence levels of expressions; an expression of a higher level binds less tightly.
+///
+/// See the precedence table in the PartiQL grammar.
+mod prec {
+    pub(super) const TERM: u8 = 0;
+    pub(super) const CALL: u8 = 1;
+    pub(super) const PATH: u8 = 2;
+    pub(super) const CASE: u8 = 3;
+    pub(super) const UNARY: u8 = 4;
+    pub(super) const EXP: u8 = 5;
+    pub(super) const MUL: u8 = 6;
+    pub(super) const ADD: u8 = 7;
+    pub(super) const CONCAT: u8 = 8;
+    pub(super) const PREDICATE: u8 = 9;
+    pub(super) const COMPARE: u8 = 10;
+    pub(super) const EQ: u8 = 11;
+    pub(super) const IS: u8 = 12;
+    pub(super) const NOT: u8 = 13;
+    pub(super) const AND: u8 = 14;
+    pub(super) const OR: u8 = 15;
+}
+
+impl Display for Item<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Ddl(ddl) => write_ddl(f, &ddl.node),
+            Item::Dml(dml) => write_dml(f, &dml.node),
+            Item::Query(query) => query.node.fmt(f),
+            Item::Exec(exec) => {
+                write!(f, "EXEC {}", exec.node.procedure_name)?;
+                if !exec.node.args.is_empty() {
+                    f.write_str(" ")?;
+                    comma_sep(f, &exec.node.args, |f, arg| arg.fmt(f))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for TopLevelQuery<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(with) = &self.with {
+            f.write_str("WITH ")?;
+            if with.node.recursive {
+                f.write_str("RECURSIVE ")?;
+            }
+            comma_sep(f, &with.node.withs, |f, elem| {
+                let elem = &elem.node;
+                write!(f, "{}", elem.query_name)?;
+                if let Some(columns) = &elem.columns {
+                    f.write_str(" (")?;
+                    comma_sep(f, columns, |f, column| column.fmt(f))?;
+                    f.write_str(")")?;
+                }
+                f.write_str(" AS ")?;
+                write_subquery(f, &elem.subquery.node)
+            })?;
+            f.write_str(" ")?;
+        }
+        self.query.node.fmt(f)
+    }
+}
+
+impl Display for Query<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_query_set(f, &self.set.node)?;
+        if let Some(order_by) = &self.order_by {
+            f.write_str(" ORDER BY ")?;
+            write_sort_specs(f, &order_by.node.sort_specs)?;
+        }
+        if let Some(limit_offset) = &self.limit_offset {
+            if let Some(limit) = &limit_offset.node.limit {
+                write!(f, " LIMIT {limit}")?;
+            }
+            if let Some(offset) = &limit_offset.node.offset {
+                write!(f, " OFFSET {offset}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Expr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_expr(f, self, prec::OR)
+    }
+}
+
+impl Display for Lit<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Lit::Null => f.write_str("NULL"),
+            Lit::Missing => f.write_str("MISSING"),
+            Lit::Int8Lit(n) => write!(f, "{n}"),
+            Lit::Int16Lit(n) => write!(f, "{n}"),
+            Lit::Int32Lit(n) => write!(f, "{n}"),
+            Lit::Int64Lit(n) => write!(f, "{n}"),
+            Lit::DecimalLit(d) | Lit::NumericLit(d) => {
+                // Keep the decimal point, lest the literal be read back as an integer.
+                let d = d.to_string();
+                if d.contains('.') {
+                    f.write_str(&d)
+                } else {
+                    write!(f, "{d}.0")
+                }
+            }
+            Lit::RealLit(r) | Lit::FloatLit(r) => write!(f, "{r:?}"),
+            Lit::DoubleLit(r) => write!(f, "{r:?}"),
+            Lit::BoolLit(true) => f.write_str("TRUE"),
+            Lit::BoolLit(false) => f.write_str("FALSE"),
+            Lit::IonStringLit(ion) => write!(f, "`{ion}`"),
+            Lit::CharStringLit(s) => write!(f, "'{}'", s.replace('\'', "''")),
+            Lit::NationalCharStringLit(s) => write!(f, "N'{}'", s.replace('\'', "''")),
+            Lit::BitStringLit(s) => write!(f, "B'{s}'"),
+            Lit::HexStringLit(s) => write!(f, "X'{s}'"),
+            Lit::StructLit(s) => write_struct(f, &s.node),
+            Lit::BagLit(b) => write_bag(f, &b.node),
+            Lit::ListLit(l) => write_list(f, &l.node),
+            Lit::TypedLit(s, ty) => write!(f, "{ty} '{}'", s.replace('\'', "''")),
+            Lit::IntervalLit(IntervalLit { value, qualifier }) => {
+                write!(f, "INTERVAL '{}' {qualifier}", value.replace('\'', "''"))
+            }
+        }
+    }
+}
+
+impl Display for IntervalQualifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let IntervalQualifier {
+            start,
+            precision,
+            end,
+            fractional_precision,
+        } = self;
+        write!(f, "{start}")?;
+        match (precision, end, fractional_precision) {
+            (Some(p), None, Some(fp)) => write!(f, "({p}, {fp})")?,
+            (Some(p), _, _) => write!(f, "({p})")?,
+            _ => {}
+        }
+        if let Some(end) = end {
+            write!(f, " TO {end}")?;
+            if let Some(fp) = fractional_precision {
+                write!(f, "({fp})")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for IntervalField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IntervalField::Year => "YEAR",
+            IntervalField::Month => "MONTH",
+            IntervalField::Day => "DAY",
+            IntervalField::Hour => "HOUR",
+            IntervalField::Minute => "MINUTE",
+            IntervalField::Second => "SECOND",
+        })
+    }
+}
+
+impl Display for Type<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::NullType => "NULL",
+            Type::BooleanType => "BOOL",
+            Type::Integer2Type => "SMALLINT",
+            Type::Integer4Type => "INT",
+            Type::Integer8Type => "BIGINT",
+            Type::DecimalType => "DECIMAL",
+            Type::NumericType => "NUMERIC",
+            Type::RealType => "REAL",
+            Type::DoublePrecisionType => "DOUBLE PRECISION",
+            Type::TimestampType => "TIMESTAMP",
+            Type::CharacterType => "CHAR",
+            Type::CharacterVaryingType => "VARCHAR",
+            Type::MissingType => "MISSING",
+            Type::StringType => "STRING",
+            Type::SymbolType => "SYMBOL",
+            Type::BlobType => "BLOB",
+            Type::ClobType => "CLOB",
+            Type::DateType => "DATE",
+            Type::TimeType => "TIME",
+            Type::ZonedTimestampType => "TIMESTAMP WITH TIME ZONE",
+            Type::StructType => "STRUCT",
+            Type::TupleType => "TUPLE",
+            Type::ListType => "LIST",
+            Type::SexpType => "SEXP",
+            Type::BagType => "BAG",
+            Type::AnyType => "ANY",
+            Type::CustomType(custom) => {
+                let mut parts = custom.parts.iter();
+                if let Some(part) = parts.next() {
+                    write_type_part(f, part)?;
+                }
+                for part in parts {
+                    f.write_str(" ")?;
+                    write_type_part(f, part)?;
+                }
+                return Ok(());
+            }
+        };
+        f.write_str(name)
+    }
+}
+
+impl Display for SymbolPrimitive<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.case {
+            CaseSensitivity::CaseSensitive => write!(f, "\"{}\"", self.value),
+            CaseSensitivity::CaseInsensitive => f.write_str(self.value),
+        }
+    }
+}
+
+/// Writes `items` separated by commas, writing each one by `write`.
+fn comma_sep<T>(
+    f: &mut Formatter<'_>,
+    items: impl IntoIterator<Item = T>,
+    mut write: impl FnMut(&mut Formatter<'_>, T) -> fmt::Result,
+) -> fmt::Result {
+    let mut items = items.into_iter();
+    if let Some(item) = items.next() {
+        write(f, item)?;
+    }
+    for item in items {
+        f.write_str(", ")?;
+        write(f, item)?;
+    }
+    Ok(())
+}
+
+fn write_type_part(f: &mut Formatter<'_>, part: &CustomTypePart) -> fmt::Result {
+    match part {
+        CustomTypePart::Name(name) => f.write_str(name.value),
+        CustomTypePart::Parameterized(name, params) => {
+            write!(f, "{}(", name.value)?;
+            comma_sep(f, params, |f, param| match param {
+                CustomTypeParam::Lit(lit) => lit.fmt(f),
+                CustomTypeParam::Type(ty) => ty.fmt(f),
+            })?;
+            f.write_str(")")
+        }
+    }
+}
+
+fn write_ddl(f: &mut Formatter<'_>, ddl: &Ddl) -> fmt::Result {
+    match &ddl.op {
+        DdlOp::CreateTable(create) => write!(f, "CREATE TABLE {}", create.table_name),
+        DdlOp::DropTable(drop) => write!(f, "DROP TABLE {}", drop.table_name),
+        DdlOp::CreateIndex(create) => {
+            write!(f, "CREATE INDEX ON {} (", create.index_name)?;
+            comma_sep(f, &create.fields, |f, field| field.fmt(f))?;
+            f.write_str(")")
+        }
+        DdlOp::DropIndex(drop) => write!(f, "DROP INDEX {} ON {}", drop.keys, drop.table),
+    }
+}
+
+fn write_dml(f: &mut Formatter<'_>, dml: &Dml) -> fmt::Result {
+    match &dml.op {
+        DmlOp::Insert(insert) => {
+            write!(f, "INSERT INTO {} ", insert.target)?;
+            match insert.values.as_ref() {
+                Expr::Query(query) => query.node.fmt(f)?,
+                values => values.fmt(f)?,
+            }
+        }
+        DmlOp::InsertValue(insert) => {
+            write!(f, "INSERT INTO {} VALUE {}", insert.target, insert.value)?;
+            if let Some(index) = &insert.index {
+                write!(f, " AT {index}")?;
+            }
+            if let Some(on_conflict) = &insert.on_conflict {
+                write!(f, " ON CONFLICT WHERE {}", on_conflict.expr)?;
+                match on_conflict.conflict_action {
+                    ConflictAction::DoNothing => f.write_str(" DO NOTHING")?,
+                }
+            }
+        }
+        DmlOp::Set(set) => {
+            f.write_str("UPDATE ")?;
+            write_dml_source(f, dml)?;
+            f.write_str(" SET ")?;
+            comma_sep(f, &set.assignments, |f, assignment| {
+                write!(f, "{} = {}", assignment.target, assignment.value)
+            })?;
+        }
+        DmlOp::Remove(remove) => {
+            f.write_str("UPDATE ")?;
+            write_dml_source(f, dml)?;
+            write!(f, " REMOVE {}", remove.target)?;
+        }
+        DmlOp::Delete(_) => {
+            f.write_str("DELETE FROM ")?;
+            write_dml_source(f, dml)?;
+        }
+    }
+    if let Some(where_clause) = &dml.where_clause {
+        write!(f, " WHERE {where_clause}")?;
+    }
+    if let Some(returning) = &dml.returning {
+        f.write_str(" RETURNING ")?;
+        comma_sep(f, &returning.elems, |f, elem| {
+            f.write_str(match elem.mapping {
+                ReturningMapping::ModifiedNew => "MODIFIED NEW ",
+                ReturningMapping::ModifiedOld => "MODIFIED OLD ",
+                ReturningMapping::AllNew => "ALL NEW ",
+                ReturningMapping::AllOld => "ALL OLD ",
+            })?;
+            match &elem.column {
+                ColumnComponent::ReturningWildcard => f.write_str("*"),
+                ColumnComponent::ReturningColumn(column) => column.expr.fmt(f),
+            }
+        })?;
+    }
+    Ok(())
+}
+
+fn write_dml_source(f: &mut Formatter<'_>, dml: &Dml) -> fmt::Result {
+    match &dml.from_clause {
+        Some(from) => write_from_source(f, &from.source),
+        None => Ok(()),
+    }
+}
+
+fn write_query_set(f: &mut Formatter<'_>, set: &QuerySet) -> fmt::Result {
+    match set {
+        QuerySet::BagOp(bag_op) => {
+            let BagOpExpr {
+                bag_op,
+                setq,
+                lhs,
+                rhs,
+            } = &bag_op.node;
+            // Set operations are left-associative; a nested operation on the right, as well as an
+            // ordered or limited operand, needs parentheses.
+            let nested = |query: &Query| query.order_by.is_some() || query.limit_offset.is_some();
+            if nested(&lhs.node) {
+                write!(f, "({})", lhs.node)?;
+            } else {
+                lhs.node.fmt(f)?;
+            }
+            f.write_str(match bag_op {
+                BagOperator::Union => " UNION ",
+                BagOperator::Except => " EXCEPT ",
+                BagOperator::Intersect => " INTERSECT ",
+                BagOperator::OuterUnion => " OUTER UNION ",
+                BagOperator::OuterExcept => " OUTER EXCEPT ",
+                BagOperator::OuterIntersect => " OUTER INTERSECT ",
+            })?;
+            if let SetQuantifier::All = setq {
+                f.write_str("ALL ")?;
+            }
+            if nested(&rhs.node) || matches!(rhs.node.set.node, QuerySet::BagOp(_)) {
+                write!(f, "({})", rhs.node)
+            } else {
+                rhs.node.fmt(f)
+            }
+        }
+        QuerySet::Select(select) => write_select(f, &select.node),
+        QuerySet::Expr(expr) => expr.fmt(f),
+        QuerySet::Values(rows) => {
+            f.write_str("VALUES ")?;
+            comma_sep(f, rows, |f, row| match row.as_ref() {
+                Expr::List(list) => write_list(f, &list.node),
+                row => write!(f, "({row})"),
+            })
+        }
+        QuerySet::Table(table) => write!(f, "TABLE {}", table.table_name),
+    }
+}
+
+fn write_select(f: &mut Formatter<'_>, select: &Select) -> fmt::Result {
+    let Select {
+        project,
+        from,
+        from_let,
+        where_clause,
+        group_by,
+        having,
+    } = select;
+
+    let project = &project.node;
+    match &project.kind {
+        ProjectionKind::ProjectPivot(pivot) => {
+            write!(f, "PIVOT {} AT {}", pivot.value, pivot.key)?;
+        }
+        kind => {
+            f.write_str("SELECT ")?;
+            if let Some(SetQuantifier::Distinct) = project.setq {
+                f.write_str("DISTINCT ")?;
+            }
+            match kind {
+                ProjectionKind::ProjectStar => f.write_str("*")?,
+                ProjectionKind::ProjectList(items) => {
+                    comma_sep(f, items, |f, item| match &item.node {
+                        ProjectItem::ProjectAll(all) => {
+                            write_expr(f, &all.expr, prec::CALL)?;
+                            f.write_str(".*")
+                        }
+                        ProjectItem::ProjectExpr(project) => {
+                            project.expr.fmt(f)?;
+                            if let Some(alias) = &project.as_alias {
+                                write!(f, " AS {alias}")?;
+                            }
+                            Ok(())
+                        }
+                    })?
+                }
+                ProjectionKind::ProjectValue(value) => write!(f, "VALUE {value}")?,
+                ProjectionKind::ProjectPivot(_) => unreachable!(),
+            }
+        }
+    }
+
+    if let Some(from) = from {
+        f.write_str(" FROM ")?;
+        write_from_source(f, &from.node.source)?;
+    }
+    if let Some(from_let) = from_let {
+        f.write_str(" LET ")?;
+        comma_sep(f, &from_let.node.let_bindings, |f, binding| {
+            write!(f, "{} AS {}", binding.expr, binding.as_alias)
+        })?;
+    }
+    if let Some(where_clause) = where_clause {
+        write!(f, " WHERE {}", where_clause.node.expr)?;
+    }
+    if let Some(group_by) = group_by {
+        let group_by = &group_by.node;
+        f.write_str(" GROUP")?;
+        if let GroupingStrategy::GroupPartial = group_by.strategy {
+            f.write_str(" PARTIAL")?;
+        }
+        if !group_by.keys.is_empty() {
+            f.write_str(" BY ")?;
+            comma_sep(f, &group_by.keys, |f, key| {
+                key.node.expr.fmt(f)?;
+                if let Some(alias) = &key.node.as_alias {
+                    write!(f, " AS {alias}")?;
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(alias) = &group_by.group_as_alias {
+            write!(f, " GROUP AS {alias}")?;
+        }
+    }
+    if let Some(having) = having {
+        write!(f, " HAVING {}", having.node.expr)?;
+    }
+    Ok(())
+}
+
+fn write_from_source(f: &mut Formatter<'_>, source: &FromSource) -> fmt::Result {
+    match source {
+        FromSource::FromLet(from_let) => {
+            let from_let = &from_let.node;
+            if let FromLetKind::Unpivot = from_let.kind {
+                f.write_str("UNPIVOT ")?;
+            }
+            from_let.expr.fmt(f)?;
+            if let Some(alias) = &from_let.as_alias {
+                write!(f, " AS {alias}")?;
+            }
+            if let Some(alias) = &from_let.at_alias {
+                write!(f, " AT {alias}")?;
+            }
+            if let Some(alias) = &from_let.by_alias {
+                write!(f, " BY {alias}")?;
+            }
+            Ok(())
+        }
+        FromSource::Join(join) => {
+            let Join {
+                kind,
+                left,
+                right,
+                predicate,
+            } = &join.node;
+            write_from_source(f, left)?;
+            let kind = match kind {
+                JoinKind::Inner => " INNER",
+                JoinKind::Left => " LEFT",
+                JoinKind::Right => " RIGHT",
+                JoinKind::Full => " FULL",
+                JoinKind::Cross => "",
+            };
+            match predicate.as_ref().map(|p| &p.node) {
+                None => write!(f, "{kind} CROSS JOIN ")?,
+                Some(JoinSpec::Natural) => write!(f, " NATURAL{kind} JOIN ")?,
+                Some(_) => write!(f, "{kind} JOIN ")?,
+            }
+            // Joins are left-associative; a nested join on the right needs parentheses.
+            if let FromSource::Join(_) = right.as_ref() {
+                f.write_str("(")?;
+                write_from_source(f, right)?;
+                f.write_str(")")?;
+            } else {
+                write_from_source(f, right)?;
+            }
+            match predicate.as_ref().map(|p| &p.node) {
+                Some(JoinSpec::On(on)) => write!(f, " ON {on}"),
+                Some(JoinSpec::Using(paths)) => {
+                    f.write_str(" USING (")?;
+                    comma_sep(f, paths, write_path)?;
+                    f.write_str(")")
+                }
+                Some(JoinSpec::Natural) | None => Ok(()),
+            }
+        }
+    }
+}
+
+fn write_sort_specs(f: &mut Formatter<'_>, sort_specs: &[AstNode<SortSpec>]) -> fmt::Result {
+    if sort_specs.is_empty() {
+        return f.write_str("PRESERVE");
+    }
+    comma_sep(f, sort_specs, |f, spec| {
+        let spec = &spec.node;
+        spec.expr.fmt(f)?;
+        match spec.ordering_spec {
+            Some(OrderingSpec::Asc) => f.write_str(" ASC")?,
+            Some(OrderingSpec::Desc) => f.write_str(" DESC")?,
+            None => {}
+        }
+        match spec.null_ordering_spec {
+            Some(NullOrderingSpec::First) => f.write_str(" NULLS FIRST"),
+            Some(NullOrderingSpec::Last) => f.write_str(" NULLS LAST"),
+            None => Ok(()),
+        }
+    })
+}
+
+/// The precedence level of `expr`, which is that of its outermost operator.
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::BinOp(bin_op) => match bin_op.node.kind {
+            BinOpKind::Or => prec::OR,
+            BinOpKind::And => prec::AND,
+            BinOpKind::Is | BinOpKind::IsDistinctFrom => prec::IS,
+            BinOpKind::Eq | BinOpKind::Ne => prec::EQ,
+            BinOpKind::Lt | BinOpKind::Gt | BinOpKind::Lte | BinOpKind::Gte => prec::COMPARE,
+            BinOpKind::Concat => prec::CONCAT,
+            BinOpKind::Add | BinOpKind::Sub => prec::ADD,
+            BinOpKind::Mul | BinOpKind::Div | BinOpKind::Mod => prec::MUL,
+            BinOpKind::Exp => prec::EXP,
+        },
+        Expr::UniOp(uni_op) => match (&uni_op.node.kind, uni_op.node.expr.as_ref()) {
+            (UniOpKind::Pos | UniOpKind::Neg, _) => prec::UNARY,
+            // `IS NOT`, `NOT LIKE`, `NOT BETWEEN`, & `NOT IN` are written at the level of their
+            // positive forms.
+            (UniOpKind::Not, Expr::BinOp(bin_op))
+                if matches!(bin_op.node.kind, BinOpKind::Is | BinOpKind::IsDistinctFrom) =>
+            {
+                prec::IS
+            }
+            (UniOpKind::Not, Expr::Like(_) | Expr::Between(_) | Expr::In(_)) => prec::PREDICATE,
+            (UniOpKind::Not, _) => prec::NOT,
+        },
+        Expr::Like(_) | Expr::Between(_) | Expr::In(_) => prec::PREDICATE,
+        Expr::Case(_) => prec::CASE,
+        Expr::Path(_) => prec::PATH,
+        Expr::Call(_) | Expr::CallAgg(_) | Expr::CallWindow(_) | Expr::Exists(_) => prec::CALL,
+        // A negative number is written with a leading `-`, like a negation.
+        Expr::Lit(lit) if is_negative(&lit.node) => prec::UNARY,
+        Expr::Lit(_)
+        | Expr::VarRef(_)
+        | Expr::Parameter(_)
+        | Expr::Struct(_)
+        | Expr::Bag(_)
+        | Expr::List(_)
+        | Expr::Sexp(_)
+        | Expr::Query(_)
+        | Expr::ScalarSubQuery(_)
+        | Expr::Error => prec::TERM,
+    }
+}
+
+fn is_negative(lit: &Lit) -> bool {
+    match lit {
+        Lit::Int8Lit(n) => *n < 0,
+        Lit::Int16Lit(n) => *n < 0,
+        Lit::Int32Lit(n) => *n < 0,
+        Lit::Int64Lit(n) => *n < 0,
+        Lit::DecimalLit(d) | Lit::NumericLit(d) => d.is_sign_negative(),
+        Lit::RealLit(r) | Lit::FloatLit(r) => r.is_sign_negative(),
+        Lit::DoubleLit(r) => r.is_sign_negative(),
+        _ => false,
+    }
+}
+
+/// Writes `expr`, parenthesized if its precedence level is above `max`.
+fn write_expr(f: &mut Formatter<'_>, expr: &Expr, max: u8) -> fmt::Result {
+    if precedence(expr) > max {
+        f.write_str("(")?;
+        write_expr(f, expr, prec::OR)?;
+        f.write_str(")")
+    } else {
+        write_expr_unparenthesized(f, expr)
+    }
+}
+
+fn write_expr_unparenthesized(f: &mut Formatter<'_>, expr: &Expr) -> fmt::Result {
+    match expr {
+        Expr::Lit(lit) => lit.node.fmt(f),
+        Expr::VarRef(var_ref) => write_var_ref(f, &var_ref.node),
+        Expr::Parameter(param) => match &param.node {
+            Parameter::Positional(_) => f.write_str("?"),
+            Parameter::Named(name) => write!(f, ":{name}"),
+        },
+        Expr::BinOp(bin_op) => {
+            let BinOp { kind, lhs, rhs } = &bin_op.node;
+            // Operators are left-associative, but for the non-associative comparisons.
+            let (op, lhs_max, rhs_max) = match kind {
+                BinOpKind::Or => ("OR", prec::OR, prec::AND),
+                BinOpKind::And => ("AND", prec::AND, prec::NOT),
+                BinOpKind::Is => ("IS", prec::IS, prec::EQ),
+                BinOpKind::IsDistinctFrom => ("IS DISTINCT FROM", prec::IS, prec::EQ),
+                BinOpKind::Eq => ("=", prec::EQ, prec::COMPARE),
+                BinOpKind::Ne => ("<>", prec::EQ, prec::COMPARE),
+                BinOpKind::Lt => ("<", prec::PREDICATE, prec::PREDICATE),
+                BinOpKind::Gt => (">", prec::PREDICATE, prec::PREDICATE),
+                BinOpKind::Lte => ("<=", prec::PREDICATE, prec::PREDICATE),
+                BinOpKind::Gte => (">=", prec::PREDICATE, prec::PREDICATE),
+                BinOpKind::Concat => ("||", prec::CONCAT, prec::ADD),
+                BinOpKind::Add => ("+", prec::ADD, prec::MUL),
+                BinOpKind::Sub => ("-", prec::ADD, prec::MUL),
+                BinOpKind::Mul => ("*", prec::MUL, prec::EXP),
+                BinOpKind::Div => ("/", prec::MUL, prec::EXP),
+                BinOpKind::Mod => ("%", prec::MUL, prec::EXP),
+                BinOpKind::Exp => ("^", prec::EXP, prec::UNARY),
+            };
+            write_expr(f, lhs, lhs_max)?;
+            write!(f, " {op} ")?;
+            write_expr(f, rhs, rhs_max)
+        }
+        Expr::UniOp(uni_op) => {
+            let UniOp { kind, expr } = &uni_op.node;
+            match (kind, expr.as_ref()) {
+                // A sign must not be followed by another one, lest `- -x` be written as the
+                // comment `--x`.
+                (UniOpKind::Pos, expr) => {
+                    f.write_str("+")?;
+                    write_expr(f, expr, prec::CASE)
+                }
+                (UniOpKind::Neg, expr) => {
+                    f.write_str("-")?;
+                    write_expr(f, expr, prec::CASE)
+                }
+                (UniOpKind::Not, Expr::BinOp(bin_op))
+                    if matches!(bin_op.node.kind, BinOpKind::Is | BinOpKind::IsDistinctFrom) =>
+                {
+                    let BinOp { kind, lhs, rhs } = &bin_op.node;
+                    write_expr(f, lhs, prec::IS)?;
+                    f.write_str(match kind {
+                        BinOpKind::Is => " IS NOT ",
+                        _ => " IS NOT DISTINCT FROM ",
+                    })?;
+                    write_expr(f, rhs, prec::IS - 1)
+                }
+                (UniOpKind::Not, Expr::Like(like)) => write_like(f, &like.node, true),
+                (UniOpKind::Not, Expr::Between(between)) => write_between(f, &between.node, true),
+                (UniOpKind::Not, Expr::In(in_expr)) => write_in(f, &in_expr.node, true),
+                (UniOpKind::Not, expr) => {
+                    f.write_str("NOT ")?;
+                    write_expr(f, expr, prec::NOT)
+                }
+            }
+        }
+        Expr::Like(like) => write_like(f, &like.node, false),
+        Expr::Between(between) => write_between(f, &between.node, false),
+        Expr::In(in_expr) => write_in(f, &in_expr.node, false),
+        Expr::Exists(exists) => {
+            f.write_str("EXISTS ")?;
+            write_subquery(f, &exists.node.expr)
+        }
+        Expr::Case(case) => {
+            f.write_str("CASE")?;
+            let (cases, default) = match &case.node {
+                Case::SimpleCase(SimpleCase {
+                    expr,
+                    cases,
+                    default,
+                }) => {
+                    write!(f, " {expr}")?;
+                    (cases, default)
+                }
+                Case::SearchedCase(SearchedCase { cases, default }) => (cases, default),
+            };
+            for case in cases {
+                write!(f, " WHEN {} THEN {}", case.first, case.second)?;
+            }
+            if let Some(default) = default {
+                write!(f, " ELSE {default}")?;
+            }
+            f.write_str(" END")
+        }
+        Expr::Struct(s) => write_struct(f, &s.node),
+        Expr::Bag(b) => write_bag(f, &b.node),
+        Expr::List(l) => write_list(f, &l.node),
+        Expr::Sexp(s) => {
+            f.write_str("SEXP(")?;
+            comma_sep(f, &s.node.values, |f, value| value.fmt(f))?;
+            f.write_str(")")
+        }
+        Expr::Path(path) => write_path(f, &path.node),
+        Expr::Call(call) => {
+            write!(f, "{}(", call.node.func_name)?;
+            if let Some((setq, value)) = coll_agg_set_quantified(&call.node) {
+                write!(f, "{setq} {value}")?;
+            } else if let Some(args) = special_form_args(&call.node) {
+                write_special_form_args(f, &args)?;
+            } else {
+                write_call_args(f, &call.node.args)?;
+            }
+            f.write_str(")")
+        }
+        Expr::CallAgg(call) => {
+            write!(f, "{}(", call.node.func_name)?;
+            match call.node.setq {
+                Some(SetQuantifier::All) => f.write_str("ALL ")?,
+                Some(SetQuantifier::Distinct) => f.write_str("DISTINCT ")?,
+                None => {}
+            }
+            write_call_args(f, &call.node.args)?;
+            f.write_str(")")
+        }
+        Expr::CallWindow(call) => {
+            let CallWindow {
+                func_name,
+                args,
+                partition_by,
+                order_by,
+                frame,
+            } = &*call.node;
+            write!(f, "{func_name}(")?;
+            write_call_args(f, args)?;
+            f.write_str(") OVER (")?;
+            let mut sep = "";
+            if !partition_by.is_empty() {
+                f.write_str("PARTITION BY ")?;
+                comma_sep(f, partition_by, |f, expr| expr.fmt(f))?;
+                sep = " ";
+            }
+            if !order_by.is_empty() {
+                write!(f, "{sep}ORDER BY ")?;
+                write_sort_specs(f, order_by)?;
+                sep = " ";
+            }
+            if let Some(frame) = frame {
+                f.write_str(sep)?;
+                write_window_frame(f, frame)?;
+            }
+            f.write_str(")")
+        }
+        Expr::Query(query) | Expr::ScalarSubQuery(query) => write!(f, "({})", query.node),
+        Expr::Error => f.write_str("<error>"),
+    }
+}
+
+fn write_var_ref(f: &mut Formatter<'_>, var_ref: &VarRef) -> fmt::Result {
+    if let ScopeQualifier::Qualified = var_ref.qualifier {
+        f.write_str("@")?;
+    }
+    var_ref.name.fmt(f)
+}
+
+/// Writes `expr` in parentheses, as required by `EXISTS` and `WITH`.
+fn write_subquery(f: &mut Formatter<'_>, expr: &Expr) -> fmt::Result {
+    match expr {
+        Expr::Query(query) | Expr::ScalarSubQuery(query) => write!(f, "({})", query.node),
+        expr => write!(f, "({expr})"),
+    }
+}
+
+fn write_like(f: &mut Formatter<'_>, like: &Like, not: bool) -> fmt::Result {
+    write_expr(f, &like.value, prec::PREDICATE)?;
+    f.write_str(if not { " NOT LIKE " } else { " LIKE " })?;
+    write_expr(f, &like.pattern, prec::CONCAT)?;
+    if let Some(escape) = &like.escape {
+        f.write_str(" ESCAPE ")?;
+        write_expr(f, escape, prec::ADD)?;
+    }
+    Ok(())
+}
+
+fn write_between(f: &mut Formatter<'_>, between: &Between, not: bool) -> fmt::Result {
+    write_expr(f, &between.value, prec::PREDICATE)?;
+    f.write_str(if not { " NOT BETWEEN " } else { " BETWEEN " })?;
+    write_expr(f, &between.from, prec::CONCAT)?;
+    f.write_str(" AND ")?;
+    write_expr(f, &between.to, prec::CONCAT)
+}
+
+fn write_in(f: &mut Formatter<'_>, in_expr: &In, not: bool) -> fmt::Result {
+    write_expr(f, &in_expr.lhs, prec::PREDICATE)?;
+    f.write_str(if not { " NOT IN " } else { " IN " })?;
+    write_expr(f, &in_expr.rhs, prec::CONCAT)
+}
+
+fn write_struct(f: &mut Formatter<'_>, s: &Struct) -> fmt::Result {
+    f.write_str("{")?;
+    comma_sep(f, &s.fields, |f, field| {
+        write!(f, "{}: {}", field.first, field.second)
+    })?;
+    f.write_str("}")
+}
+
+fn write_bag(f: &mut Formatter<'_>, b: &Bag) -> fmt::Result {
+    f.write_str("<<")?;
+    comma_sep(f, &b.values, |f, value| value.fmt(f))?;
+    f.write_str(">>")
+}
+
+fn write_list(f: &mut Formatter<'_>, l: &List) -> fmt::Result {
+    f.write_str("[")?;
+    comma_sep(f, &l.values, |f, value| value.fmt(f))?;
+    f.write_str("]")
+}
+
+fn write_path(f: &mut Formatter<'_>, path: &Path) -> fmt::Result {
+    write_expr(f, &path.root, prec::CALL)?;
+    for step in &path.steps {
+        match step {
+            PathStep::PathExpr(PathExpr { index }) => match index.as_ref() {
+                Expr::VarRef(var_ref) => {
+                    f.write_str(".")?;
+                    write_var_ref(f, &var_ref.node)?;
+                }
+                index => write!(f, "[{index}]")?,
+            },
+            PathStep::PathWildCard => f.write_str("[*]")?,
+            PathStep::PathUnpivot => f.write_str(".*")?,
+        }
+    }
+    Ok(())
+}
+
+/// The set quantifier and argument of a call to a collection aggregate whose argument is set
+/// quantified, e.g., `DISTINCT` and `x` of `COLL_SUM(DISTINCT x)`, which the parser rewrites as the
+/// named argument of `COLL_SUM(distinct: x)`.
+fn coll_agg_set_quantified<'c, 'a>(call: &'c Call<'a>) -> Option<(&'static str, &'c Expr<'a>)> {
+    if !call.func_name.value.to_lowercase().starts_with("coll_") {
+        return None;
+    }
+    let [AstNode {
+        node: CallArg::Named(CallArgNamed { name, value }),
+        ..
+    }] = call.args.as_slice()
+    else {
+        return None;
+    };
+    if !matches!(name.case, CaseSensitivity::CaseInsensitive) {
+        return None;
+    }
+    match name.value.to_lowercase().as_str() {
+        "distinct" => Some(("DISTINCT", value)),
+        "all" => Some(("ALL", value)),
+        _ => None,
+    }
+}
+
+/// The specifiers of `TRIM`, e.g., `BOTH` of `TRIM(BOTH ' ' FROM x)`.
+const TRIM_SPECIFIERS: &[&str] = &["leading", "trailing", "both"];
+
+/// The fields of `EXTRACT`, e.g., `YEAR` of `EXTRACT(YEAR FROM x)`.
+const EXTRACT_FIELDS: &[&str] = &[
+    "second",
+    "minute",
+    "hour",
+    "day",
+    "month",
+    "year",
+    "timezone_hour",
+    "timezone_minute",
+];
+
+/// A keyword or an argument of a special form, e.g., `FROM` or `x` of `TRIM(FROM x)`.
+enum SpecialFormArg<'c, 'a> {
+    Keyword(&'c str),
+    Expr(&'c Expr<'a>),
+}
+
+/// The keywords and arguments of a call to a special form, e.g., `x`, `AS`, and `INT` of
+/// `CAST(x AS INT)`, which the parser rewrites as `CAST(x, "AS": INT)`.
+fn special_form_args<'c, 'a>(call: &'c Call<'a>) -> Option<Vec<SpecialFormArg<'c, 'a>>> {
+    use SpecialFormArg::{Expr as E, Keyword as K};
+
+    if !matches!(call.func_name.case, CaseSensitivity::CaseInsensitive) {
+        return None;
+    }
+    let name = call.func_name.value.to_lowercase();
+    let args: Vec<_> = call.args.iter().map(|arg| &arg.node).collect();
+    let mut parts = vec![];
+    match (name.as_str(), args.as_slice()) {
+        ("cast" | "can_cast" | "can_lossless_cast", [value, ty]) => {
+            parts.push(E(positional_arg(value)?));
+            parts.extend(keyword_arg(ty, "as")?);
+        }
+        ("trim", [spec, from]) if ident_arg(spec, TRIM_SPECIFIERS).is_some() => {
+            let (spec, chars) = ident_arg(spec, TRIM_SPECIFIERS)?;
+            parts.extend([K(spec), E(chars)]);
+            parts.extend(keyword_arg(from, "from")?);
+        }
+        ("trim", [chars, from]) => {
+            parts.push(E(positional_arg(chars)?));
+            parts.extend(keyword_arg(from, "from")?);
+        }
+        ("trim", [from]) => parts.extend(keyword_arg(from, "from")?),
+        ("extract", [field, from]) => {
+            // the parser synthesizes the value of the field
+            let (field, value) = ident_arg(field, EXTRACT_FIELDS)?;
+            if !matches!(value, Expr::Lit(lit) if matches!(lit.node, Lit::BoolLit(true))) {
+                return None;
+            }
+            parts.push(K(field));
+            parts.extend(keyword_arg(from, "from")?);
+        }
+        ("position", [value, within]) => {
+            parts.push(E(positional_arg(value)?));
+            parts.extend(keyword_arg(within, "in")?);
+        }
+        ("overlay", [value, placing, from, rest @ ..]) if rest.len() <= 1 => {
+            parts.push(E(positional_arg(value)?));
+            let (keyword, placing) = ident_arg(placing, &["placing"])?;
+            parts.extend([K(keyword), E(placing)]);
+            parts.extend(keyword_arg(from, "from")?);
+            for length in rest {
+                parts.extend(keyword_arg(length, "for")?);
+            }
+        }
+        ("substring", [value, from, length]) => {
+            parts.push(E(positional_arg(value)?));
+            parts.extend(keyword_arg(from, "from")?);
+            parts.extend(keyword_arg(length, "for")?);
+        }
+        ("substring", [value, arg]) => {
+            parts.push(E(positional_arg(value)?));
+            parts.extend(keyword_arg(arg, "from").or_else(|| keyword_arg(arg, "for"))?);
+        }
+        _ => return None,
+    }
+    Some(parts)
+}
+
+fn positional_arg<'c, 'a>(arg: &'c CallArg<'a>) -> Option<&'c Expr<'a>> {
+    match arg {
+        CallArg::Positional(expr) => Some(expr),
+        _ => None,
+    }
+}
+
+/// The keyword and value of the argument that the parser rewrites a keyword of a special form as,
+/// e.g., of `"FROM": x` for `FROM x`.
+fn keyword_arg<'c, 'a>(arg: &'c CallArg<'a>, keyword: &str) -> Option<[SpecialFormArg<'c, 'a>; 2]> {
+    match arg {
+        CallArg::Named(CallArgNamed { name, value })
+            if matches!(name.case, CaseSensitivity::CaseSensitive)
+                && name.value.eq_ignore_ascii_case(keyword) =>
+        {
+            Some([
+                SpecialFormArg::Keyword(name.value),
+                SpecialFormArg::Expr(value),
+            ])
+        }
+        _ => None,
+    }
+}
+
+/// The identifier and value of the argument that the parser rewrites an identifier of a special
+/// form as, e.g., of `LEADING: 'a'` for `LEADING 'a'`.
+fn ident_arg<'c, 'a>(arg: &'c CallArg<'a>, idents: &[&str]) -> Option<(&'c str, &'c Expr<'a>)> {
+    match arg {
+        CallArg::Named(CallArgNamed { name, value })
+            if matches!(name.case, CaseSensitivity::CaseInsensitive)
+                && idents.contains(&name.value.to_lowercase().as_str()) =>
+        {
+            Some((name.value, value))
+        }
+        _ => None,
+    }
+}
+
+fn write_special_form_args(f: &mut Formatter<'_>, args: &[SpecialFormArg]) -> fmt::Result {
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            f.write_str(" ")?;
+        }
+        match arg {
+            SpecialFormArg::Keyword(keyword) => f.write_str(keyword)?,
+            // predicates are parenthesized, as their keywords would end the arguments
+            SpecialFormArg::Expr(expr) => write_expr(f, expr, prec::CONCAT)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_call_args(f: &mut Formatter<'_>, args: &[AstNode<CallArg>]) -> fmt::Result {
+    comma_sep(f, args, |f, arg| match &arg.node {
+        CallArg::Star() => f.write_str("*"),
+        CallArg::Positional(expr) => expr.fmt(f),
+        CallArg::PositionalType(ty) => ty.fmt(f),
+        CallArg::Named(CallArgNamed { name, value }) => write!(f, "{name}: {value}"),
+        CallArg::NamedType(CallArgNamedType { name, ty }) => write!(f, "{name}: {ty}"),
+    })
+}
+
+fn write_window_frame(f: &mut Formatter<'_>, frame: &WindowFrame) -> fmt::Result {
+    f.write_str(match frame.units {
+        WindowFrameUnits::Rows => "ROWS ",
+        WindowFrameUnits::Range => "RANGE ",
+    })?;
+    let write_bound = |f: &mut Formatter<'_>, bound: &WindowFrameBound| match bound {
+        WindowFrameBound::UnboundedPreceding => f.write_str("UNBOUNDED PRECEDING"),
+        WindowFrameBound::Preceding(n) => write!(f, "{n} PRECEDING"),
+        WindowFrameBound::CurrentRow => f.write_str("CURRENT ROW"),
+        WindowFrameBound::Following(n) => write!(f, "{n} FOLLOWING"),
+        WindowFrameBound::UnboundedFollowing => f.write_str("UNBOUNDED FOLLOWING"),
+    };
+    match frame.end {
+        WindowFrameBound::CurrentRow => write_bound(f, &frame.start),
+        _ => {
+            f.write_str("BETWEEN ")?;
+            write_bound(f, &frame.start)?;
+            f.write_str(" AND ")?;
+            write_bound(f, &frame.end)
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::arena;
 use crate::ast;
 use crate::ast::NodeId;
 
@@ -46,7 +47,7 @@ where
     }
 }
 
-impl<T> Visit for Box<T>
+impl<T> Visit for arena::Box<'_, T>
 where
     T: Visit,
 {
@@ -75,7 +76,7 @@ where
     }
 }
 
-impl<T> Visit for Vec<T>
+impl<T> Visit for arena::Vec<'_, T>
 where
     T: Visit,
 {
@@ -105,6 +106,12 @@ pub trait Visitor<'ast> {
     fn exit_item(&mut self, _item: &'ast ast::Item) -> Traverse {
         Traverse::Continue
     }
+    fn enter_exec(&mut self, _exec: &'ast ast::Exec) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_exec(&mut self, _exec: &'ast ast::Exec) -> Traverse {
+        Traverse::Continue
+    }
     fn enter_ddl(&mut self, _ddl: &'ast ast::Ddl) -> Traverse {
         Traverse::Continue
     }
@@ -303,6 +310,12 @@ pub trait Visitor<'ast> {
     fn exit_lit(&mut self, _lit: &'ast ast::Lit) -> Traverse {
         Traverse::Continue
     }
+    fn enter_parameter(&mut self, _parameter: &'ast ast::Parameter) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_parameter(&mut self, _parameter: &'ast ast::Parameter) -> Traverse {
+        Traverse::Continue
+    }
     fn enter_var_ref(&mut self, _var_ref: &'ast ast::VarRef) -> Traverse {
         Traverse::Continue
     }
@@ -339,6 +352,12 @@ pub trait Visitor<'ast> {
     fn exit_in(&mut self, _in: &'ast ast::In) -> Traverse {
         Traverse::Continue
     }
+    fn enter_exists(&mut self, _exists: &'ast ast::Exists) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_exists(&mut self, _exists: &'ast ast::Exists) -> Traverse {
+        Traverse::Continue
+    }
     fn enter_case(&mut self, _case: &'ast ast::Case) -> Traverse {
         Traverse::Continue
     }
@@ -423,6 +442,12 @@ pub trait Visitor<'ast> {
     fn exit_call_agg(&mut self, _call_agg: &'ast ast::CallAgg) -> Traverse {
         Traverse::Continue
     }
+    fn enter_call_window(&mut self, _call_window: &'ast ast::CallWindow) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_call_window(&mut self, _call_window: &'ast ast::CallWindow) -> Traverse {
+        Traverse::Continue
+    }
     fn enter_path(&mut self, _path: &'ast ast::Path) -> Traverse {
         Traverse::Continue
     }
@@ -539,10 +564,565 @@ pub trait Visitor<'ast> {
     }
 }
 
+/// A node of the AST that can be traversed by a [`VisitorMut`], which may rewrite it in place.
+pub trait VisitMut<'a> {
+    fn visit_mut<V>(&mut self, v: &mut V) -> Traverse
+    where
+        V: VisitorMut<'a>;
+}
+
+impl<'a, T> VisitMut<'a> for ast::AstNode<T>
+where
+    T: VisitMut<'a>,
+{
+    fn visit_mut<V>(&mut self, v: &mut V) -> Traverse
+    where
+        V: VisitorMut<'a>,
+    {
+        if v.enter_ast_node(&mut self.id) == Traverse::Stop {
+            return Traverse::Stop;
+        }
+        if self.node.visit_mut(v) == Traverse::Stop {
+            return Traverse::Stop;
+        }
+        v.exit_ast_node(&mut self.id)
+    }
+}
+
+impl<'a, T> VisitMut<'a> for arena::Box<'_, T>
+where
+    T: VisitMut<'a>,
+{
+    fn visit_mut<V>(&mut self, v: &mut V) -> Traverse
+    where
+        V: VisitorMut<'a>,
+    {
+        (**self).visit_mut(v)
+    }
+}
+
+impl<'a, T> VisitMut<'a> for Option<T>
+where
+    T: VisitMut<'a>,
+{
+    fn visit_mut<V>(&mut self, v: &mut V) -> Traverse
+    where
+        V: VisitorMut<'a>,
+    {
+        if let Some(inner) = self {
+            if inner.visit_mut(v) == Traverse::Stop {
+                return Traverse::Stop;
+            }
+        }
+        Traverse::Continue
+    }
+}
+
+impl<'a, T> VisitMut<'a> for arena::Vec<'_, T>
+where
+    T: VisitMut<'a>,
+{
+    fn visit_mut<V>(&mut self, v: &mut V) -> Traverse
+    where
+        V: VisitorMut<'a>,
+    {
+        for i in self {
+            if i.visit_mut(v) == Traverse::Stop {
+                return Traverse::Stop;
+            }
+        }
+        Traverse::Continue
+    }
+}
+
+/// A rewriter of the AST, the mutable counterpart of [`Visitor`].
+///
+/// Each `enter_` method is called with a node before its children are traversed, and each `exit_`
+/// method after; either may replace or modify the node. Since children are traversed after
+/// `enter_` returns, a node replaced on entry has its replacement's children traversed. The ids of
+/// nodes may also be changed, e.g., to renumber the nodes of a rewritten AST.
+///
+/// The nodes that a node is rewritten with are allocated in the [`Arena`](arena::Arena) of its AST,
+/// whose lifetime is `'a`.
+pub trait VisitorMut<'a> {
+    fn enter_ast_node(&mut self, _id: &mut NodeId) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_ast_node(&mut self, _id: &mut NodeId) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_item(&mut self, _item: &mut ast::Item<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_item(&mut self, _item: &mut ast::Item<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_exec(&mut self, _exec: &mut ast::Exec<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_exec(&mut self, _exec: &mut ast::Exec<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_ddl(&mut self, _ddl: &mut ast::Ddl<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_ddl(&mut self, _ddl: &mut ast::Ddl<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_ddl_op(&mut self, _ddl_op: &mut ast::DdlOp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_ddl_op(&mut self, _ddl_op: &mut ast::DdlOp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_create_table(&mut self, _create_table: &mut ast::CreateTable) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_create_table(&mut self, _create_table: &mut ast::CreateTable) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_drop_table(&mut self, _drop_table: &mut ast::DropTable) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_drop_table(&mut self, _drop_table: &mut ast::DropTable) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_create_index(&mut self, _create_index: &mut ast::CreateIndex<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_create_index(&mut self, _create_index: &mut ast::CreateIndex<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_drop_index(&mut self, _drop_index: &mut ast::DropIndex) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_drop_index(&mut self, _drop_index: &mut ast::DropIndex) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_dml(&mut self, _dml: &mut ast::Dml<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_dml(&mut self, _dml: &mut ast::Dml<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_dml_op(&mut self, _dml_op: &mut ast::DmlOp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_dml_op(&mut self, _dml_op: &mut ast::DmlOp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_returning_expr(&mut self, _returning_expr: &mut ast::ReturningExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_returning_expr(&mut self, _returning_expr: &mut ast::ReturningExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_returning_elem(&mut self, _returning_elem: &mut ast::ReturningElem<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_returning_elem(&mut self, _returning_elem: &mut ast::ReturningElem<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_insert(&mut self, _insert: &mut ast::Insert<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_insert(&mut self, _insert: &mut ast::Insert<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_insert_value(&mut self, _insert_value: &mut ast::InsertValue<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_insert_value(&mut self, _insert_value: &mut ast::InsertValue<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_set(&mut self, _set: &mut ast::Set<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_set(&mut self, _set: &mut ast::Set<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_assignment(&mut self, _assignment: &mut ast::Assignment<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_assignment(&mut self, _assignment: &mut ast::Assignment<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_remove(&mut self, _remove: &mut ast::Remove<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_remove(&mut self, _remove: &mut ast::Remove<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_delete(&mut self, _delete: &mut ast::Delete) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_delete(&mut self, _delete: &mut ast::Delete) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_on_conflict(&mut self, _on_conflict: &mut ast::OnConflict<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_on_conflict(&mut self, _on_conflict: &mut ast::OnConflict<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_top_level_query(&mut self, _query: &mut ast::TopLevelQuery<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_top_level_query(&mut self, _query: &mut ast::TopLevelQuery<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_query(&mut self, _query: &mut ast::Query<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_query(&mut self, _query: &mut ast::Query<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_with_clause(&mut self, _query: &mut ast::WithClause<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_with_clause(&mut self, _query: &mut ast::WithClause<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_with_element(&mut self, _query: &mut ast::WithElement<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_with_element(&mut self, _query: &mut ast::WithElement<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_query_set(&mut self, _query_set: &mut ast::QuerySet<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_query_set(&mut self, _query_set: &mut ast::QuerySet<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_bag_op_expr(&mut self, _set_expr: &mut ast::BagOpExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_bag_op_expr(&mut self, _set_expr: &mut ast::BagOpExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_select(&mut self, _select: &mut ast::Select<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_select(&mut self, _select: &mut ast::Select<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_query_table(&mut self, _table: &mut ast::QueryTable) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_query_table(&mut self, _table: &mut ast::QueryTable) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_projection(&mut self, _projection: &mut ast::Projection<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_projection(&mut self, _projection: &mut ast::Projection<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_projection_kind(
+        &mut self,
+        _projection_kind: &mut ast::ProjectionKind<'a>,
+    ) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_projection_kind(&mut self, _projection_kind: &mut ast::ProjectionKind<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_project_item(&mut self, _project_item: &mut ast::ProjectItem<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_project_item(&mut self, _project_item: &mut ast::ProjectItem<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_project_pivot(&mut self, _project_pivot: &mut ast::ProjectPivot<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_project_pivot(&mut self, _project_pivot: &mut ast::ProjectPivot<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_project_all(&mut self, _project_all: &mut ast::ProjectAll<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_project_all(&mut self, _project_all: &mut ast::ProjectAll<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_project_expr(&mut self, _project_expr: &mut ast::ProjectExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_project_expr(&mut self, _project_expr: &mut ast::ProjectExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_expr(&mut self, _expr: &mut ast::Expr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_expr(&mut self, _expr: &mut ast::Expr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_lit(&mut self, _lit: &mut ast::Lit<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_lit(&mut self, _lit: &mut ast::Lit<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_parameter(&mut self, _parameter: &mut ast::Parameter) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_parameter(&mut self, _parameter: &mut ast::Parameter) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_var_ref(&mut self, _var_ref: &mut ast::VarRef) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_var_ref(&mut self, _var_ref: &mut ast::VarRef) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_bin_op(&mut self, _bin_op: &mut ast::BinOp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_bin_op(&mut self, _bin_op: &mut ast::BinOp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_uni_op(&mut self, _uni_op: &mut ast::UniOp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_uni_op(&mut self, _uni_op: &mut ast::UniOp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_like(&mut self, _like: &mut ast::Like<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_like(&mut self, _like: &mut ast::Like<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_between(&mut self, _between: &mut ast::Between<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_between(&mut self, _between: &mut ast::Between<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_in(&mut self, _in: &mut ast::In<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_in(&mut self, _in: &mut ast::In<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_exists(&mut self, _exists: &mut ast::Exists<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_exists(&mut self, _exists: &mut ast::Exists<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_case(&mut self, _case: &mut ast::Case<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_case(&mut self, _case: &mut ast::Case<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_simple_case(&mut self, _simple_case: &mut ast::SimpleCase<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_simple_case(&mut self, _simple_case: &mut ast::SimpleCase<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_searched_case(&mut self, _searched_case: &mut ast::SearchedCase<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_searched_case(&mut self, _searched_case: &mut ast::SearchedCase<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_expr_pair(&mut self, _expr_pair: &mut ast::ExprPair<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_expr_pair(&mut self, _expr_pair: &mut ast::ExprPair<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_struct(&mut self, _struct: &mut ast::Struct<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_struct(&mut self, _struct: &mut ast::Struct<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_bag(&mut self, _bag: &mut ast::Bag<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_bag(&mut self, _bag: &mut ast::Bag<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_list(&mut self, _list: &mut ast::List<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_list(&mut self, _list: &mut ast::List<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_sexp(&mut self, _sexp: &mut ast::Sexp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_sexp(&mut self, _sexp: &mut ast::Sexp<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_call(&mut self, _call: &mut ast::Call<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_call(&mut self, _call: &mut ast::Call<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_call_arg(&mut self, _call_arg: &mut ast::CallArg<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_call_arg(&mut self, _call_arg: &mut ast::CallArg<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_call_arg_named(&mut self, _call_arg_named: &mut ast::CallArgNamed<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_call_arg_named(&mut self, _call_arg_named: &mut ast::CallArgNamed<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_call_arg_named_type(
+        &mut self,
+        _call_arg_named_type: &mut ast::CallArgNamedType<'a>,
+    ) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_call_arg_named_type(
+        &mut self,
+        _call_arg_named_type: &mut ast::CallArgNamedType<'a>,
+    ) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_call_agg(&mut self, _call_agg: &mut ast::CallAgg<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_call_agg(&mut self, _call_agg: &mut ast::CallAgg<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_call_window(&mut self, _call_window: &mut ast::CallWindow<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_call_window(&mut self, _call_window: &mut ast::CallWindow<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_path(&mut self, _path: &mut ast::Path<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_path(&mut self, _path: &mut ast::Path<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_path_step(&mut self, _path_step: &mut ast::PathStep<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_path_step(&mut self, _path_step: &mut ast::PathStep<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_path_expr(&mut self, _path_expr: &mut ast::PathExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_path_expr(&mut self, _path_expr: &mut ast::PathExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_let(&mut self, _let: &mut ast::Let<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_let(&mut self, _let: &mut ast::Let<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_let_binding(&mut self, _let_binding: &mut ast::LetBinding<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_let_binding(&mut self, _let_binding: &mut ast::LetBinding<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_from_clause(&mut self, _from_clause: &mut ast::FromClause<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_from_clause(&mut self, _from_clause: &mut ast::FromClause<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_from_source(&mut self, _from_clause: &mut ast::FromSource<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_from_source(&mut self, _from_clause: &mut ast::FromSource<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_where_clause(&mut self, _where_clause: &mut ast::WhereClause<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_where_clause(&mut self, _where_clause: &mut ast::WhereClause<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_having_clause(&mut self, _having_clause: &mut ast::HavingClause<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_having_clause(&mut self, _having_clause: &mut ast::HavingClause<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_from_let(&mut self, _from_let: &mut ast::FromLet<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_from_let(&mut self, _from_let: &mut ast::FromLet<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_join(&mut self, _join: &mut ast::Join<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_join(&mut self, _join: &mut ast::Join<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_join_spec(&mut self, _join_spec: &mut ast::JoinSpec<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_join_spec(&mut self, _join_spec: &mut ast::JoinSpec<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_group_by_expr(&mut self, _group_by_expr: &mut ast::GroupByExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_group_by_expr(&mut self, _group_by_expr: &mut ast::GroupByExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_group_key(&mut self, _group_key: &mut ast::GroupKey<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_group_key(&mut self, _group_key: &mut ast::GroupKey<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_order_by_expr(&mut self, _order_by_expr: &mut ast::OrderByExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_order_by_expr(&mut self, _order_by_expr: &mut ast::OrderByExpr<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_limit_offset_clause(
+        &mut self,
+        _limit_offset: &mut ast::LimitOffsetClause<'a>,
+    ) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_limit_offset_clause(
+        &mut self,
+        _limit_offset: &mut ast::LimitOffsetClause<'a>,
+    ) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_sort_spec(&mut self, _sort_spec: &mut ast::SortSpec<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_sort_spec(&mut self, _sort_spec: &mut ast::SortSpec<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_custom_type(&mut self, _custom_type: &mut ast::CustomType<'a>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_custom_type(&mut self, _custom_type: &mut ast::CustomType<'a>) -> Traverse {
+        Traverse::Continue
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::arena::{Arena, Box};
     use crate::ast;
-    use crate::visit::{Traverse, Visitor};
+    use crate::visit::{Traverse, VisitMut, Visitor, VisitorMut};
     use ast::{AstNode, BinOp, BinOpKind, Expr, Lit, NodeId};
     use std::ops::AddAssign;
 
@@ -566,18 +1146,28 @@ mod tests {
             }
         }
 
-        fn create_bin_op(op: BinOpKind, lhs: Expr, rhs: Expr) -> Expr {
+        fn create_bin_op<'a>(
+            arena: &'a Arena,
+            op: BinOpKind,
+            lhs: Expr<'a>,
+            rhs: Expr<'a>,
+        ) -> Expr<'a> {
             Expr::BinOp(AstNode {
                 id: NodeId(1),
                 node: BinOp {
                     kind: op,
-                    lhs: Box::new(lhs),
-                    rhs: Box::new(rhs),
+                    lhs: Box::new_in(lhs, arena),
+                    rhs: Box::new_in(rhs, arena),
                 },
             })
         }
 
-        fn create_bin_op_lit(op: BinOpKind, lhs: Lit, rhs: Lit) -> Expr {
+        fn create_bin_op_lit<'a>(
+            arena: &'a Arena,
+            op: BinOpKind,
+            lhs: Lit<'a>,
+            rhs: Lit<'a>,
+        ) -> Expr<'a> {
             let lhs = Expr::Lit(AstNode {
                 id: NodeId(1),
                 node: lhs,
@@ -586,12 +1176,18 @@ mod tests {
                 id: NodeId(1),
                 node: rhs,
             });
-            create_bin_op(op, lhs, rhs)
+            create_bin_op(arena, op, lhs, rhs)
         }
 
-        let lhs = create_bin_op_lit(BinOpKind::Add, Lit::Int32Lit(5), Lit::Int16Lit(4));
-        let rhs = create_bin_op_lit(BinOpKind::Mul, Lit::Int8Lit(-20), Lit::Int64Lit(3000));
-        let ast = create_bin_op(BinOpKind::Div, lhs, rhs);
+        let arena = Arena::new();
+        let lhs = create_bin_op_lit(&arena, BinOpKind::Add, Lit::Int32Lit(5), Lit::Int16Lit(4));
+        let rhs = create_bin_op_lit(
+            &arena,
+            BinOpKind::Mul,
+            Lit::Int8Lit(-20),
+            Lit::Int64Lit(3000),
+        );
+        let ast = create_bin_op(&arena, BinOpKind::Div, lhs, rhs);
 
         let mut acc = Accum::default();
 
@@ -601,4 +1197,79 @@ mod tests {
         let val = acc.val;
         assert!(matches!(val, Some(2989)));
     }
+
+    #[test]
+    fn visit_mut_fold() {
+        /// Folds additions of 64-bit integer literals.
+        struct FoldAdd;
+
+        impl<'a> VisitorMut<'a> for FoldAdd {
+            fn exit_expr(&mut self, expr: &mut Expr<'a>) -> Traverse {
+                if let Expr::BinOp(AstNode { id, node }) = expr {
+                    if let (BinOpKind::Add, Expr::Lit(lhs), Expr::Lit(rhs)) =
+                        (&node.kind, node.lhs.as_ref(), node.rhs.as_ref())
+                    {
+                        if let (Lit::Int64Lit(l), Lit::Int64Lit(r)) = (&lhs.node, &rhs.node) {
+                            *expr = Expr::Lit(AstNode {
+                                id: *id,
+                                node: Lit::Int64Lit(l + r),
+                            });
+                        }
+                    }
+                }
+                Traverse::Continue
+            }
+        }
+
+        fn lit<'a>(l: i64) -> Expr<'a> {
+            Expr::Lit(AstNode {
+                id: NodeId(1),
+                node: Lit::Int64Lit(l),
+            })
+        }
+
+        let arena = Arena::new();
+        let bin_op = |op, lhs, rhs| {
+            Expr::BinOp(AstNode {
+                id: NodeId(2),
+                node: BinOp {
+                    kind: op,
+                    lhs: Box::new_in(lhs, &arena),
+                    rhs: Box::new_in(rhs, &arena),
+                },
+            })
+        };
+
+        // (1 + 2) + (3 * 4) folds to 3 + (3 * 4)
+        let mut ast = bin_op(
+            BinOpKind::Add,
+            bin_op(BinOpKind::Add, lit(1), lit(2)),
+            bin_op(BinOpKind::Mul, lit(3), lit(4)),
+        );
+        assert_eq!(Traverse::Continue, ast.visit_mut(&mut FoldAdd));
+        let expected = bin_op(
+            BinOpKind::Add,
+            Expr::Lit(AstNode {
+                id: NodeId(2),
+                node: Lit::Int64Lit(3),
+            }),
+            bin_op(BinOpKind::Mul, lit(3), lit(4)),
+        );
+        assert_eq!(ast, expected);
+
+        // 1 + 2 + 3 folds entirely, as the children are folded before their parent
+        let mut ast = bin_op(
+            BinOpKind::Add,
+            bin_op(BinOpKind::Add, lit(1), lit(2)),
+            lit(3),
+        );
+        ast.visit_mut(&mut FoldAdd);
+        assert!(matches!(
+            ast,
+            Expr::Lit(AstNode {
+                node: Lit::Int64Lit(6),
+                ..
+            })
+        ));
+    }
 }
@@ -19,6 +19,8 @@ pub enum CallLookupError {
 pub enum CallArgument {
     Positional(ValueExpr),
     Named(String, ValueExpr),
+    /// `*` used as an argument to a function call (e.g., in `count(*)`)
+    Star(),
 }
 
 #[derive(Debug)]
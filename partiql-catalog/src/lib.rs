@@ -1,7 +1,7 @@
 use crate::call_defs::CallDef;
 
-use partiql_types::PartiqlType;
-use partiql_value::Value;
+use partiql_types::{PartiqlType, StructType};
+use partiql_value::{BindingsName, Value};
 use std::borrow::Cow;
 
 use std::collections::HashMap;
@@ -50,6 +50,40 @@ pub type BaseTableExprResult<'a> =
 
 pub trait BaseTableExpr: Debug {
     fn evaluate(&self, args: &[Cow<Value>]) -> BaseTableExprResult;
+
+    /// Evaluates the table as [`evaluate`](Self::evaluate) does, given the hint that its consumer
+    /// reads no more than [`ScanLimit::max_values`] of its values, e.g., for the query
+    /// `SELECT * FROM t LIMIT 10`. An implementation may stop reading its source after producing
+    /// that many values; the consumer skips the first `offset` values itself.
+    ///
+    /// The consumer stops pulling values from the returned iterator once it has read enough of
+    /// them, so lazy implementations of [`evaluate`](Self::evaluate) need not override this.
+    fn evaluate_limited(&self, args: &[Cow<Value>], _limit: ScanLimit) -> BaseTableExprResult<'_> {
+        self.evaluate(args)
+    }
+}
+
+/// A hint that only some of the values of a [`BaseTableExpr`] are consumed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScanLimit {
+    /// The number of values consumed after the skipped ones, if limited.
+    pub limit: Option<u64>,
+    /// The number of values skipped before the consumed ones.
+    pub offset: u64,
+}
+
+impl ScanLimit {
+    pub fn new(limit: u64, offset: u64) -> Self {
+        ScanLimit {
+            limit: Some(limit),
+            offset,
+        }
+    }
+
+    /// The number of values read by the consumer, including the skipped ones, if limited.
+    pub fn max_values(&self) -> Option<u64> {
+        self.limit.map(|limit| limit.saturating_add(self.offset))
+    }
 }
 
 pub trait BaseTableFunctionInfo: Debug {
@@ -66,6 +100,41 @@ impl TableFunction {
     pub fn new(info: Box<dyn BaseTableFunctionInfo>) -> Self {
         TableFunction { info }
     }
+
+    pub fn plan_eval(&self) -> Box<dyn BaseTableExpr> {
+        self.info.plan_eval()
+    }
+}
+
+pub type ScalarFnExprResultError = Box<dyn Error>;
+pub type ScalarFnExprResult = Result<Value, ScalarFnExprResultError>;
+
+/// The implementation of a scalar function, which evaluates to a single value per call, e.g.,
+/// `twice(x)` in `SELECT VALUE twice(x) FROM t AS x`.
+pub trait ScalarFnExpr: Debug {
+    fn evaluate(&self, args: &[Cow<Value>]) -> ScalarFnExprResult;
+}
+
+pub trait ScalarFunctionInfo: Debug {
+    fn call_def(&self) -> &CallDef;
+    fn plan_eval(&self) -> Box<dyn ScalarFnExpr>;
+}
+
+/// A scalar function registered in a [`Catalog`], which calls to are looked up by name as those
+/// of the built-in functions are.
+#[derive(Debug)]
+pub struct ScalarFunction {
+    info: Box<dyn ScalarFunctionInfo>,
+}
+
+impl ScalarFunction {
+    pub fn new(info: Box<dyn ScalarFunctionInfo>) -> Self {
+        ScalarFunction { info }
+    }
+
+    pub fn plan_eval(&self) -> Box<dyn ScalarFnExpr> {
+        self.info.plan_eval()
+    }
 }
 
 /// Contains the errors that occur during Catalog related operations
@@ -104,10 +173,23 @@ pub enum CatalogErrorKind {
 pub trait Catalog: Debug {
     fn add_table_function(&mut self, info: TableFunction) -> Result<ObjectId, CatalogError>;
 
+    /// Registers a scalar function; catalogs which do not support scalar functions reject it.
+    fn add_scalar_function(&mut self, info: ScalarFunction) -> Result<ObjectId, CatalogError> {
+        Err(CatalogError::new(vec![CatalogErrorKind::EntryError(
+            format!(
+                "scalar function `{}` not supported by this catalog",
+                info.info.call_def().names.join("`, `")
+            ),
+        )]))
+    }
+
     fn add_type_entry(&mut self, entry: TypeEnvEntry) -> Result<ObjectId, CatalogError>;
 
     fn get_function(&self, name: &str) -> Option<FunctionEntry>;
 
+    /// The functions registered in the catalog.
+    fn functions(&self) -> Vec<FunctionEntry<'_>>;
+
     fn resolve_type(&self, name: &str) -> Option<TypeEntry>;
 }
 
@@ -134,6 +216,41 @@ pub struct TypeEntry {
     ty: PartiqlType,
 }
 
+impl TypeEntry {
+    pub fn ty(&self) -> &PartiqlType {
+        &self.ty
+    }
+}
+
+/// Indicates that an attribute is accessed which a closed struct type does not declare.
+#[derive(Error, Debug, Clone, PartialEq, Eq, Hash)]
+#[error("`{0}` is not an attribute of a closed struct")]
+pub struct UndeclaredAttribute(pub String);
+
+/// The type of the attribute `attr` of values of the struct type `st`, `None` if `st` is open and
+/// does not declare it. Both the typing and the planning of plans check attributes of the struct
+/// types the catalog declares with it, so that they report the same errors.
+pub fn attribute_type<'t>(
+    st: &'t StructType,
+    attr: &BindingsName,
+) -> Result<Option<&'t PartiqlType>, UndeclaredAttribute> {
+    let declared = st.fields().find(|field| match attr {
+        BindingsName::CaseSensitive(attr) => field.name() == attr,
+        BindingsName::CaseInsensitive(attr) => {
+            UniCase::new(field.name()) == UniCase::new(attr.as_str())
+        }
+    });
+    match declared {
+        Some(field) => Ok(Some(field.ty())),
+        None if st.is_open() => Ok(None),
+        None => match attr {
+            BindingsName::CaseSensitive(attr) | BindingsName::CaseInsensitive(attr) => {
+                Err(UndeclaredAttribute(attr.clone()))
+            }
+        },
+    }
+}
+
 #[derive(Debug)]
 pub struct FunctionEntry<'a> {
     id: ObjectId,
@@ -143,7 +260,7 @@ pub struct FunctionEntry<'a> {
 #[derive(Debug)]
 pub enum FunctionEntryFunction {
     Table(TableFunction),
-    Scalar(),
+    Scalar(ScalarFunction),
     Aggregate(),
 }
 
@@ -151,17 +268,13 @@ impl<'a> FunctionEntry<'a> {
     pub fn call_def(&'a self) -> &'a CallDef {
         match &self.function {
             FunctionEntryFunction::Table(tf) => tf.info.call_def(),
-            FunctionEntryFunction::Scalar() => todo!(),
+            FunctionEntryFunction::Scalar(sf) => sf.info.call_def(),
             FunctionEntryFunction::Aggregate() => todo!(),
         }
     }
 
-    pub fn plan_eval(&'a self) -> Box<dyn BaseTableExpr> {
-        match &self.function {
-            FunctionEntryFunction::Table(tf) => tf.info.plan_eval(),
-            FunctionEntryFunction::Scalar() => todo!(),
-            FunctionEntryFunction::Aggregate() => todo!(),
-        }
+    pub fn function(&self) -> &'a FunctionEntryFunction {
+        self.function
     }
 }
 
@@ -182,16 +295,14 @@ impl Default for PartiqlCatalog {
     }
 }
 
-impl PartiqlCatalog {}
-
-impl Catalog for PartiqlCatalog {
-    fn add_table_function(&mut self, info: TableFunction) -> Result<ObjectId, CatalogError> {
-        let call_def = info.info.call_def();
-        let names = call_def.names.clone();
+impl PartiqlCatalog {
+    fn add_function(
+        &mut self,
+        names: &[&str],
+        function: FunctionEntryFunction,
+    ) -> Result<ObjectId, CatalogError> {
         if let Some((name, aliases)) = names.split_first() {
-            let id = self
-                .functions
-                .add(name, aliases, FunctionEntryFunction::Table(info))?;
+            let id = self.functions.add(name, aliases, function)?;
             Ok(ObjectId {
                 catalog_id: self.id,
                 entry_id: id,
@@ -202,6 +313,18 @@ impl Catalog for PartiqlCatalog {
             )]))
         }
     }
+}
+
+impl Catalog for PartiqlCatalog {
+    fn add_table_function(&mut self, info: TableFunction) -> Result<ObjectId, CatalogError> {
+        let names = info.info.call_def().names.clone();
+        self.add_function(&names, FunctionEntryFunction::Table(info))
+    }
+
+    fn add_scalar_function(&mut self, info: ScalarFunction) -> Result<ObjectId, CatalogError> {
+        let names = info.info.call_def().names.clone();
+        self.add_function(&names, FunctionEntryFunction::Scalar(info))
+    }
 
     fn add_type_entry(&mut self, entry: TypeEnvEntry) -> Result<ObjectId, CatalogError> {
         let id = self
@@ -229,6 +352,20 @@ impl Catalog for PartiqlCatalog {
             })
     }
 
+    fn functions(&self) -> Vec<FunctionEntry<'_>> {
+        self.functions
+            .entries
+            .iter()
+            .map(|(eid, entry)| FunctionEntry {
+                id: ObjectId {
+                    catalog_id: self.id,
+                    entry_id: *eid,
+                },
+                function: entry,
+            })
+            .collect()
+    }
+
     fn resolve_type(&self, name: &str) -> Option<TypeEntry> {
         self.types.find_by_name(name).map(|(eid, entry)| TypeEntry {
             id: ObjectId {
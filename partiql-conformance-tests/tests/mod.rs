@@ -1,3 +1,4 @@
+use partiql_ast::arena::Arena;
 use partiql_ast_passes::error::AstTransformationError;
 use partiql_catalog::{Catalog, PartiqlCatalog};
 use partiql_eval as eval;
@@ -29,8 +30,8 @@ impl From<EvaluationMode> for eval::plan::EvaluationMode {
 
 #[track_caller]
 #[inline]
-pub(crate) fn parse(statement: &str) -> ParserResult {
-    partiql_parser::Parser::default().parse(statement)
+pub(crate) fn parse<'a>(statement: &'a str, arena: &'a Arena) -> ParserResult<'a> {
+    partiql_parser::Parser::default().parse(statement, arena)
 }
 
 #[track_caller]
@@ -64,7 +65,8 @@ pub(crate) fn evaluate(mut plan: EvalPlan, bindings: MapBindings<Value>) -> Eval
 #[inline]
 #[allow(dead_code)]
 pub(crate) fn fail_syntax(statement: &str) {
-    let res = parse(statement);
+    let arena = Arena::new();
+    let res = parse(statement, &arena);
     assert!(
         res.is_err(),
         "When parsing `{statement}`, expected `Err(_)`, but was `{res:#?}`"
@@ -74,8 +76,15 @@ pub(crate) fn fail_syntax(statement: &str) {
 #[track_caller]
 #[inline]
 #[allow(dead_code)]
-pub(crate) fn pass_syntax(statement: &str) -> Parsed {
-    let res = parse(statement);
+pub(crate) fn pass_syntax(statement: &str) {
+    let arena = Arena::new();
+    parse_ok(statement, &arena);
+}
+
+#[track_caller]
+#[inline]
+fn parse_ok<'a>(statement: &'a str, arena: &'a Arena) -> Parsed<'a> {
+    let res = parse(statement, arena);
     assert!(
         res.is_ok(),
         "When parsing `{statement}`, expected `Ok(_)`, but was `{res:#?}`"
@@ -88,7 +97,8 @@ pub(crate) fn pass_syntax(statement: &str) -> Parsed {
 #[allow(dead_code)]
 pub(crate) fn fail_semantics(statement: &str) {
     let catalog = PartiqlCatalog::default();
-    if let Ok(parsed) = parse(statement) {
+    let arena = Arena::new();
+    if let Ok(parsed) = parse(statement, &arena) {
         let lowered = lower(&catalog, &parsed);
 
         assert!(
@@ -103,7 +113,8 @@ pub(crate) fn fail_semantics(statement: &str) {
 #[allow(dead_code)]
 pub(crate) fn pass_semantics(statement: &str) {
     let catalog = PartiqlCatalog::default();
-    let parsed = pass_syntax(statement);
+    let arena = Arena::new();
+    let parsed = parse_ok(statement, &arena);
     let lowered = lower(&catalog, &parsed);
     assert!(
         lowered.is_ok(),
@@ -117,7 +128,8 @@ pub(crate) fn pass_semantics(statement: &str) {
 pub(crate) fn fail_eval(statement: &str, mode: EvaluationMode, env: &Option<TestValue>) {
     let catalog = PartiqlCatalog::default();
 
-    let parsed = parse(statement);
+    let arena = Arena::new();
+    let parsed = parse(statement, &arena);
     let lowered_result = lower(&catalog, &parsed.expect("parse"));
     let lowered = lowered_result.expect("lower");
     let bindings = env
@@ -150,7 +162,8 @@ pub(crate) fn pass_eval(
 ) {
     let catalog = PartiqlCatalog::default();
 
-    let parsed = parse(statement);
+    let arena = Arena::new();
+    let parsed = parse(statement, &arena);
     let lowered_result = lower(&catalog, &parsed.expect("parse"));
     let lowered = lowered_result.expect("lower");
     let bindings = env
@@ -144,6 +144,13 @@ fn eval_bench(c: &mut Criterion) {
             evaluate(black_box(eval_plan), bindings)
         })
     });
+    c.bench_function("join-reexecute", |b| {
+        let mut eval_plan = eval_plan(&logical_plan);
+        b.iter(|| {
+            let bindings = join_data.clone();
+            black_box(eval_plan.execute_mut(bindings).map(|out| out.result))
+        })
+    });
 
     fn eval(eval: bool) {
         // logical plan for SELECT * FROM hr.employeesNestScalars
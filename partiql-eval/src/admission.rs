@@ -0,0 +1,316 @@
+//! Admission control of queries based on their estimated cost.
+//!
+//! An [`AdmissionControl`] registered with an [`EvaluatorPlanner`] is consulted each time a
+//! compiled plan is executed, before any of its operators are evaluated. It receives the logical
+//! plan along with a [`PlanEstimate`] of its output cardinality and cost, allowing an embedding
+//! service to reject expensive queries; a service wishing to queue queries may instead block
+//! within [`AdmissionControl::admit`] until resources become available.
+//!
+//! ```
+//! use partiql_catalog::PartiqlCatalog;
+//! use partiql_eval::admission::{Admission, PlanEstimate};
+//! use partiql_eval::env::basic::MapBindings;
+//! use partiql_eval::error::EvaluationError;
+//! use partiql_eval::plan::{EvaluationMode, EvaluatorPlanner};
+//! use partiql_logical::{BindingsOp, LogicalPlan, Scan, ValueExpr};
+//! use partiql_value::{bag, BindingsName, Value};
+//!
+//! let mut logical = LogicalPlan::new();
+//! let scan = logical.add_operator(BindingsOp::Scan(Scan {
+//!     expr: ValueExpr::VarRef(BindingsName::CaseInsensitive("data".to_string())),
+//!     as_key: "d".to_string(),
+//!     at_key: None,
+//! }));
+//! let sink = logical.add_operator(BindingsOp::Sink);
+//! logical.add_flow(scan, sink);
+//!
+//! let catalog = PartiqlCatalog::default();
+//! let mut plan = EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+//!     .with_admission_control(|_: &LogicalPlan<BindingsOp>, estimate: &PlanEstimate| {
+//!         if estimate.cardinality > 2.0 {
+//!             Admission::Reject(format!("too many rows: {}", estimate.cardinality))
+//!         } else {
+//!             Admission::Admit
+//!         }
+//!     })
+//!     .compile(&logical)
+//!     .expect("compile");
+//!
+//! let mut bindings = MapBindings::default();
+//! bindings.insert("data", Value::from(bag![1, 2, 3]));
+//! let err = plan.execute_mut(bindings).expect_err("rejected");
+//! assert!(matches!(err.errors[0], EvaluationError::AdmissionRejected(_)));
+//! ```
+//!
+//! [`EvaluatorPlanner`]: crate::plan::EvaluatorPlanner
+
+use crate::env::Bindings;
+use crate::error::{EvalErr, EvaluationError};
+use partiql_logical::{BindingsOp, JoinKind, LogicalPlan, OpId, ValueExpr};
+use partiql_value::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+/// The cardinality assumed for a source whose size is not known before evaluation (e.g., a path
+/// into another source).
+pub const UNKNOWN_CARDINALITY: f64 = 1000.0;
+/// The fraction of its input assumed to satisfy a predicate (i.e., `WHERE`, `HAVING` or `ON`).
+pub const PREDICATE_SELECTIVITY: f64 = 0.1;
+/// The number of groups assumed per input row of a `GROUP BY`.
+pub const GROUP_SELECTIVITY: f64 = 0.1;
+
+/// The estimated size & cost of evaluating a plan.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlanEstimate {
+    /// The estimated number of values output by the plan.
+    pub cardinality: f64,
+    /// The estimated cost of evaluating the plan, measured as the total number of values
+    /// processed by its operators.
+    pub cost: f64,
+}
+
+/// The decision of an [`AdmissionControl`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    /// Proceed with evaluation.
+    Admit,
+    /// Fail evaluation with [`EvaluationError::AdmissionRejected`] for the given reason.
+    Reject(String),
+}
+
+/// A pre-execution check of whether a plan may be evaluated.
+pub trait AdmissionControl {
+    /// Decide whether `plan`, estimated as `estimate`, may be evaluated.
+    fn admit(&self, plan: &LogicalPlan<BindingsOp>, estimate: &PlanEstimate) -> Admission;
+}
+
+impl<F> AdmissionControl for F
+where
+    F: Fn(&LogicalPlan<BindingsOp>, &PlanEstimate) -> Admission,
+{
+    fn admit(&self, plan: &LogicalPlan<BindingsOp>, estimate: &PlanEstimate) -> Admission {
+        self(plan, estimate)
+    }
+}
+
+/// An [`AdmissionControl`] along with the logical plan it checks.
+pub(crate) struct AdmissionCheck {
+    pub(crate) control: Rc<dyn AdmissionControl>,
+    pub(crate) plan: LogicalPlan<BindingsOp>,
+}
+
+impl Debug for AdmissionCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdmissionCheck")
+            .field("plan", &self.plan)
+            .finish()
+    }
+}
+
+impl AdmissionCheck {
+    /// Estimates the plan against `bindings` and consults the admission control.
+    pub(crate) fn check(&self, bindings: &dyn Bindings<Value>) -> Result<(), EvalErr> {
+        let estimate = estimate(&self.plan, bindings);
+        match self.control.admit(&self.plan, &estimate) {
+            Admission::Admit => Ok(()),
+            Admission::Reject(reason) => Err(EvalErr {
+                errors: vec![EvaluationError::AdmissionRejected(reason)],
+            }),
+        }
+    }
+}
+
+/// Estimates the output cardinality and cost of evaluating `plan` in the environment `bindings`.
+///
+/// The sizes of sources which are variables of `bindings` or literal collections are known
+/// exactly; the size of other sources is assumed to be [`UNKNOWN_CARDINALITY`].
+pub fn estimate(plan: &LogicalPlan<BindingsOp>, bindings: &dyn Bindings<Value>) -> PlanEstimate {
+    let mut inputs: HashMap<OpId, Vec<OpId>> = HashMap::new();
+    let mut sources: HashSet<OpId> = HashSet::new();
+    for (src, dst, _) in plan.flows() {
+        inputs.entry(dst).or_default().push(src);
+        sources.insert(src);
+    }
+    let sinks: Vec<OpId> = plan
+        .operators_by_id()
+        .map(|(id, _)| id)
+        .filter(|id| !sources.contains(id))
+        .collect();
+    let mut estimator = Estimator {
+        plan,
+        bindings,
+        inputs,
+        rows: HashMap::new(),
+        cost: 0.0,
+    };
+    let cardinality = sinks.into_iter().map(|id| estimator.op_rows(id)).sum();
+    PlanEstimate {
+        cardinality,
+        cost: estimator.cost,
+    }
+}
+
+struct Estimator<'a> {
+    plan: &'a LogicalPlan<BindingsOp>,
+    bindings: &'a dyn Bindings<Value>,
+    /// The operators flowing into each operator of the plan.
+    inputs: HashMap<OpId, Vec<OpId>>,
+    /// The estimated output cardinality of each operator already visited.
+    rows: HashMap<OpId, f64>,
+    cost: f64,
+}
+
+impl Estimator<'_> {
+    fn op_rows(&mut self, id: OpId) -> f64 {
+        if let Some(rows) = self.rows.get(&id) {
+            return *rows;
+        }
+        let sources = self.inputs.get(&id).cloned().unwrap_or_default();
+        let input = sources.into_iter().map(|src| self.op_rows(src)).sum();
+        let rows = match self.plan.operator(id) {
+            Some(op) => self.bindings_op_rows(op, input),
+            None => input,
+        };
+        self.rows.insert(id, rows);
+        rows
+    }
+
+    fn bindings_op_rows(&mut self, op: &BindingsOp, input: f64) -> f64 {
+        self.cost += input;
+        match op {
+            BindingsOp::Scan(scan) => {
+                let rows = match self.value(&scan.expr) {
+                    Some(Value::Bag(bag)) => bag.len() as f64,
+                    Some(Value::List(list)) => list.len() as f64,
+                    Some(_) => 1.0,
+                    None => self.expr_rows(&scan.expr),
+                };
+                self.cost += rows;
+                rows
+            }
+            BindingsOp::Unpivot(unpivot) => {
+                let rows = match self.value(&unpivot.expr) {
+                    Some(Value::Tuple(tuple)) => tuple.len() as f64,
+                    Some(_) => 1.0,
+                    None => UNKNOWN_CARDINALITY,
+                };
+                self.cost += rows;
+                rows
+            }
+            BindingsOp::Join(join) => {
+                let left = self.bindings_op_rows(&join.left, 0.0);
+                let right = self.bindings_op_rows(&join.right, 0.0);
+                self.cost += left * right;
+                let matched = match join.on {
+                    Some(_) => left * right * PREDICATE_SELECTIVITY,
+                    None => left * right,
+                };
+                match join.kind {
+                    JoinKind::Inner | JoinKind::Cross => matched,
+                    JoinKind::Left => matched.max(left),
+                    JoinKind::Right => matched.max(right),
+                    JoinKind::Full => matched.max(left + right),
+                }
+            }
+            BindingsOp::Filter(_) | BindingsOp::Having(_) => input * PREDICATE_SELECTIVITY,
+            BindingsOp::LimitOffset(limit_offset) => {
+                match limit_offset
+                    .limit
+                    .as_ref()
+                    .and_then(|limit| self.value(limit))
+                {
+                    Some(Value::Integer(limit)) => input.min(*limit as f64),
+                    _ => input,
+                }
+            }
+            BindingsOp::GroupBy(group_by) if group_by.exprs.is_empty() => 1.0,
+            BindingsOp::GroupBy(_) => (input * GROUP_SELECTIVITY).max(1.0),
+            BindingsOp::Pivot(_) | BindingsOp::ExprQuery(_) => 1.0,
+            BindingsOp::Let(_)
+            | BindingsOp::OrderBy(_)
+            | BindingsOp::Window(_)
+            | BindingsOp::BagOp(_)
+            | BindingsOp::Project(_)
+            | BindingsOp::ProjectAll
+            | BindingsOp::ProjectValue(_)
+            | BindingsOp::Distinct
+            | BindingsOp::Sink => input,
+        }
+    }
+
+    /// The value of `expr`, if it is known without evaluation.
+    fn value<'v>(&'v self, expr: &'v ValueExpr) -> Option<&'v Value> {
+        match expr {
+            ValueExpr::VarRef(name) | ValueExpr::GlobalVarRef(name) => self.bindings.get(name),
+            ValueExpr::Lit(lit) => Some(lit.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// The estimated number of values of the collection `expr`.
+    fn expr_rows(&mut self, expr: &ValueExpr) -> f64 {
+        match expr {
+            ValueExpr::BagExpr(bag) => bag.elements.len() as f64,
+            ValueExpr::ListExpr(list) => list.elements.len() as f64,
+            ValueExpr::SubQueryExpr(subquery) => {
+                let estimate = estimate(&subquery.plan, self.bindings);
+                self.cost += estimate.cost;
+                estimate.cardinality
+            }
+            _ => UNKNOWN_CARDINALITY,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::basic::MapBindings;
+    use partiql_logical::{Filter, LimitOffset, Scan};
+    use partiql_value::{bag, BindingsName};
+
+    fn scan_var(name: &str) -> BindingsOp {
+        BindingsOp::Scan(Scan {
+            expr: ValueExpr::VarRef(BindingsName::CaseInsensitive(name.to_string())),
+            as_key: name.to_string(),
+            at_key: None,
+        })
+    }
+
+    #[test]
+    fn estimates() {
+        let mut bindings = MapBindings::default();
+        bindings.insert("data", Value::from(bag![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+
+        let mut plan = LogicalPlan::new();
+        let scan = plan.add_operator(scan_var("data"));
+        let sink = plan.add_operator(BindingsOp::Sink);
+        plan.add_flow(scan, sink);
+        let estimated = estimate(&plan, &bindings);
+        assert_eq!(estimated.cardinality, 10.0);
+        assert_eq!(estimated.cost, 20.0);
+
+        let mut plan = LogicalPlan::new();
+        let scan = plan.add_operator(scan_var("data"));
+        let filter = plan.add_operator(BindingsOp::Filter(Filter {
+            expr: ValueExpr::Lit(Box::new(Value::Boolean(true))),
+        }));
+        let limit = plan.add_operator(BindingsOp::LimitOffset(LimitOffset {
+            limit: Some(ValueExpr::Lit(Box::new(Value::Integer(0)))),
+            offset: None,
+        }));
+        let sink = plan.add_operator(BindingsOp::Sink);
+        plan.extend_with_flows(&[(scan, filter), (filter, limit), (limit, sink)]);
+        let estimated = estimate(&plan, &bindings);
+        assert_eq!(estimated.cardinality, 0.0);
+
+        // unbound sources are of unknown size
+        let mut plan = LogicalPlan::new();
+        let scan = plan.add_operator(scan_var("unknown"));
+        let sink = plan.add_operator(BindingsOp::Sink);
+        plan.add_flow(scan, sink);
+        let estimated = estimate(&plan, &bindings);
+        assert_eq!(estimated.cardinality, UNKNOWN_CARDINALITY);
+    }
+}
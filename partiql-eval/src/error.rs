@@ -1,6 +1,7 @@
 use crate::eval::evaluable::Evaluable;
 use crate::eval::expr::EvalExpr;
 use crate::eval::EvalContext;
+use partiql_catalog::UndeclaredAttribute;
 use partiql_value::{Tuple, Value};
 use std::borrow::Cow;
 use thiserror::Error;
@@ -21,6 +22,9 @@ pub enum PlanningError {
     /// Internal error that was not due to user input or API violation.
     #[error("Illegal State: {0}")]
     IllegalState(String),
+    /// Reference to an attribute not declared by the (closed) struct type of a variable.
+    #[error(transparent)]
+    UndeclaredAttribute(#[from] UndeclaredAttribute),
 }
 
 /// All errors that occurred during evaluation.
@@ -42,6 +46,24 @@ pub enum EvaluationError {
     /// Feature has not yet been implemented.
     #[error("Not yet implemented: {0}")]
     NotYetImplemented(String),
+    /// Evaluation was rejected by an [`crate::admission::AdmissionControl`].
+    #[error("Evaluation Error: rejected by admission control: {0}")]
+    AdmissionRejected(String),
+    /// No value was bound to a parameter of the query.
+    #[error("Evaluation Error: no value bound to parameter {0}")]
+    UnboundParameter(String),
+    /// Spilling or merging the runs of an external sort failed.
+    #[error("Evaluation Error: external sort failed: {0}")]
+    ExternalSortFailed(String),
+    /// A function registered in the catalog failed.
+    #[error("Evaluation Error: function failed: {0}")]
+    FunctionFailed(String),
+    /// An argument of a function or operator is invalid (e.g., a negative `LAG` offset).
+    #[error("Evaluation Error: invalid argument: {0}")]
+    InvalidArgument(String),
+    /// A scalar subquery returned more than one row, or a row of other than one attribute.
+    #[error("Evaluation Error: cardinality violation: {0}")]
+    CardinalityViolation(String),
 }
 
 /// Used when an error occurs during the the logical to eval plan conversion. Allows the conversion
@@ -1,10 +1,10 @@
-use crate::env::basic::MapBindings;
 use crate::error::EvaluationError;
 use crate::eval::expr::EvalExpr;
-use crate::eval::{EvalContext, EvalPlan};
+use crate::eval::sort::{ExternalSortConfig, ExternalSortError, ExternalSorter};
+use crate::eval::{EvalContext, EvalPlan, NestedContext};
 use itertools::Itertools;
 use partiql_value::Value::{Boolean, Missing, Null};
-use partiql_value::{bag, tuple, Bag, List, Tuple, Value, ValueIntoIterator};
+use partiql_value::{bag, tuple, Bag, BindingsName, List, Tuple, Value, ValueIntoIterator};
 use std::borrow::{Borrow, Cow};
 use std::cell::RefCell;
 use std::cmp::{max, min, Ordering};
@@ -35,6 +35,11 @@ pub enum EvalType {
 
 /// `Evaluable` represents each evaluation operator in the evaluation plan as an evaluable entity.
 pub trait Evaluable: Debug {
+    /// The name of the operator, e.g., for display in plan explanations.
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
     fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value;
     fn update_input(&mut self, input: Value, branch_num: u8, ctx: &dyn EvalContext);
     fn get_vars(&self) -> Option<&[String]> {
@@ -167,18 +172,18 @@ impl EvalJoin {
     }
 }
 
+/// Creates a `Tuple` with attributes `attrs`, each with value `Null`
+#[inline]
+fn tuple_with_null_vals<I, S>(attrs: I) -> Tuple
+where
+    S: Into<String>,
+    I: IntoIterator<Item = S>,
+{
+    attrs.into_iter().map(|k| (k.into(), Null)).collect()
+}
+
 impl Evaluable for EvalJoin {
     fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
-        /// Creates a `Tuple` with attributes `attrs`, each with value `Null`
-        #[inline]
-        fn tuple_with_null_vals<I, S>(attrs: I) -> Tuple
-        where
-            S: Into<String>,
-            I: IntoIterator<Item = S>,
-        {
-            attrs.into_iter().map(|k| (k.into(), Null)).collect()
-        }
-
         let mut output_bag = bag![];
         let input_env = self.input.take().unwrap_or_else(|| Value::from(tuple![]));
         self.left.update_input(input_env.clone(), 0, ctx);
@@ -299,12 +304,406 @@ impl Evaluable for EvalJoin {
                 });
             }
             EvalJoinKind::Full | EvalJoinKind::Right => {
-                ctx.add_error(EvaluationError::NotYetImplemented(
-                    "FULL and RIGHT JOIN".to_string(),
+                // the rhs of a `RIGHT` or `FULL` join may not reference the lhs, so is evaluated once
+                self.right.update_input(input_env.clone(), 0, ctx);
+                let right_bindings = match self.right.evaluate(ctx) {
+                    Value::Bag(t) => *t,
+                    _ => bag![tuple![]],
+                };
+                let right_bindings: Vec<_> = right_bindings.into_iter().collect();
+                let mut right_matched = vec![false; right_bindings.len()];
+
+                for b_l in left_bindings.iter() {
+                    let mut left_matched = false;
+                    for (b_r, matched) in right_bindings.iter().zip(right_matched.iter_mut()) {
+                        let b_l_b_r = b_l
+                            .as_tuple_ref()
+                            .as_ref()
+                            .tuple_concat(b_r.as_tuple_ref().borrow());
+                        let cond = match &self.on {
+                            None => true,
+                            Some(condition) => {
+                                let env_b_l_b_r =
+                                    &input_env.as_tuple_ref().as_ref().tuple_concat(&b_l_b_r);
+                                condition.evaluate(env_b_l_b_r, ctx).as_ref()
+                                    == &Value::Boolean(true)
+                            }
+                        };
+                        if cond {
+                            left_matched = true;
+                            *matched = true;
+                            output_bag.push(Value::from(b_l_b_r));
+                        }
+                    }
+
+                    // for `FULL` joins, add b_l || <v_1_r: NULL, ..., v_n_r: NULL> for each
+                    // unmatched b_l
+                    if !left_matched && matches!(self.kind, EvalJoinKind::Full) {
+                        let attrs = self.right.get_vars().unwrap_or(&[]);
+                        let new_binding = b_l
+                            .as_tuple_ref()
+                            .as_ref()
+                            .tuple_concat(&tuple_with_null_vals(attrs));
+                        output_bag.push(Value::from(new_binding));
+                    }
+                }
+
+                // add <v_1_l: NULL, ..., v_n_l: NULL> || b_r for each unmatched b_r
+                let attrs = self.left.get_vars().unwrap_or(&[]);
+                for (b_r, matched) in right_bindings.iter().zip(right_matched) {
+                    if !matched {
+                        let new_binding =
+                            tuple_with_null_vals(attrs).tuple_concat(b_r.as_tuple_ref().borrow());
+                        output_bag.push(Value::from(new_binding));
+                    }
+                }
+            }
+        };
+        Value::Bag(Box::new(output_bag))
+    }
+
+    fn update_input(&mut self, input: Value, _branch_num: u8, _ctx: &dyn EvalContext) {
+        self.input = Some(input);
+    }
+
+    fn eval_type(&self) -> EvalType {
+        EvalType::SelfManaged
+    }
+}
+
+/// Represents an evaluation hash join operator, which joins the tuples from its LHS and RHS as an
+/// [`EvalJoin`] of kind `Inner` or `Left` does, if its RHS does not reference its LHS. The RHS is
+/// evaluated once and its tuples hashed by their `keys`, so that the condition `on` is only
+/// evaluated on the pairs of tuples whose keys may be equal.
+#[derive(Debug)]
+pub(crate) struct EvalHashJoin {
+    pub(crate) kind: EvalJoinKind,
+    /// The pairs of the LHS and RHS expressions `on` requires to be equal.
+    pub(crate) keys: Vec<(Box<dyn EvalExpr>, Box<dyn EvalExpr>)>,
+    pub(crate) on: Box<dyn EvalExpr>,
+    pub(crate) input: Option<Value>,
+    pub(crate) left: Box<dyn Evaluable>,
+    pub(crate) right: Box<dyn Evaluable>,
+}
+
+/// The key of a tuple joined by an [`EvalHashJoin`].
+enum HashKey {
+    /// Values which are only equal to the same values, e.g., integers and strings.
+    Hashed(Vec<Value>),
+    /// Values which may be equal to values of other types, e.g., `1.0` to `1`, so are compared to
+    /// all keys.
+    Unhashed,
+    /// Values including a `NULL` or `MISSING`, which are equal to no value.
+    Absent,
+}
+
+impl EvalHashJoin {
+    /// The key of the tuple `bindings`, of the values of the LHS `keys` if `left`, or else of the
+    /// RHS ones.
+    fn key(&self, bindings: &Tuple, left: bool, ctx: &dyn EvalContext) -> HashKey {
+        let mut values = Vec::with_capacity(self.keys.len());
+        let mut hashed = true;
+        for (lhs, rhs) in &self.keys {
+            let expr = if left { lhs } else { rhs };
+            let value = expr.evaluate(bindings, ctx).into_owned();
+            match value {
+                Null | Missing => return HashKey::Absent,
+                Value::Integer(_) | Value::String(_) | Boolean(_) => {}
+                _ => hashed = false,
+            }
+            values.push(value);
+        }
+        if hashed {
+            HashKey::Hashed(values)
+        } else {
+            HashKey::Unhashed
+        }
+    }
+}
+
+impl Evaluable for EvalHashJoin {
+    fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
+        let input_env = self.input.take().unwrap_or_else(|| Value::from(tuple![]));
+        let env = input_env.as_tuple_ref();
+        self.left.update_input(input_env.clone(), 0, ctx);
+        let left_bindings = match self.left.evaluate(ctx) {
+            Value::Bag(t) => *t,
+            _ => {
+                ctx.add_error(EvaluationError::IllegalState(
+                    "Left side of FROM source should result in a bag of bindings".to_string(),
+                ));
+                return Missing;
+            }
+        };
+        self.right.update_input(input_env.clone(), 0, ctx);
+        let right_bindings: Vec<_> = match self.right.evaluate(ctx) {
+            Value::Bag(t) => t.into_iter().collect(),
+            _ => vec![Value::from(tuple![])],
+        };
+
+        // hash the RHS tuples by their keys
+        let mut hashed: HashMap<Vec<Value>, Vec<usize>> = HashMap::new();
+        let mut unhashed = vec![];
+        for (idx, b_r) in right_bindings.iter().enumerate() {
+            let env_b_r = env.as_ref().tuple_concat(b_r.as_tuple_ref().borrow());
+            match self.key(&env_b_r, false, ctx) {
+                HashKey::Hashed(key) => hashed.entry(key).or_default().push(idx),
+                HashKey::Unhashed => unhashed.push(idx),
+                HashKey::Absent => {}
+            }
+        }
+
+        let mut output_bag = bag![];
+        for b_l in left_bindings.iter() {
+            let env_b_l = env.as_ref().tuple_concat(b_l.as_tuple_ref().borrow());
+            // the RHS tuples whose keys may equal that of b_l, in order
+            let candidates: Vec<usize> = match self.key(&env_b_l, true, ctx) {
+                HashKey::Hashed(key) => hashed
+                    .get(&key)
+                    .into_iter()
+                    .flatten()
+                    .chain(&unhashed)
+                    .copied()
+                    .sorted_unstable()
+                    .collect(),
+                HashKey::Unhashed => (0..right_bindings.len()).collect(),
+                HashKey::Absent => vec![],
+            };
+
+            let mut matched = false;
+            for idx in candidates {
+                let b_l_b_r = b_l
+                    .as_tuple_ref()
+                    .as_ref()
+                    .tuple_concat(right_bindings[idx].as_tuple_ref().borrow());
+                let env_b_l_b_r = &env.as_ref().tuple_concat(&b_l_b_r);
+                if self.on.evaluate(env_b_l_b_r, ctx).as_ref() == &Value::Boolean(true) {
+                    matched = true;
+                    output_bag.push(Value::from(b_l_b_r));
+                }
+            }
+
+            // for `LEFT` joins, add b_l || <v_1_r: NULL, ..., v_n_r: NULL> for each unmatched b_l
+            if !matched && matches!(self.kind, EvalJoinKind::Left) {
+                let attrs = self.right.get_vars().unwrap_or(&[]);
+                let new_binding = b_l
+                    .as_tuple_ref()
+                    .as_ref()
+                    .tuple_concat(&tuple_with_null_vals(attrs));
+                output_bag.push(Value::from(new_binding));
+            }
+        }
+        Value::Bag(Box::new(output_bag))
+    }
+
+    fn update_input(&mut self, input: Value, _branch_num: u8, _ctx: &dyn EvalContext) {
+        self.input = Some(input);
+    }
+
+    fn eval_type(&self) -> EvalType {
+        EvalType::SelfManaged
+    }
+}
+
+/// Represents an evaluation sort-merge join operator, which joins the tuples from its LHS and RHS
+/// as an [`EvalHashJoin`] does. The tuples of each operand are sorted by their `keys` with an
+/// [`ExternalSorter`], unless they are input `sorted` by them, and merged, so that the condition
+/// `on` is only evaluated on the pairs of tuples whose keys may be equal. The joined tuples are
+/// output in the order of the sorted LHS tuples, i.e., sorted by the LHS keys.
+#[derive(Debug)]
+pub(crate) struct EvalSortMergeJoin {
+    pub(crate) kind: EvalJoinKind,
+    /// The pairs of the LHS and RHS expressions `on` requires to be equal.
+    pub(crate) keys: Vec<(Box<dyn EvalExpr>, Box<dyn EvalExpr>)>,
+    pub(crate) on: Box<dyn EvalExpr>,
+    /// Whether the LHS and the RHS tuples, respectively, are input sorted by their keys.
+    pub(crate) sorted: [bool; 2],
+    pub(crate) sort_config: ExternalSortConfig,
+    pub(crate) input: Option<Value>,
+    pub(crate) left: Box<dyn Evaluable>,
+    pub(crate) right: Box<dyn Evaluable>,
+}
+
+/// The key of a tuple joined by an [`EvalSortMergeJoin`]. Keys are sorted as `Absent` keys, then
+/// `Ordered` keys by their values, then `Unordered` keys.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum MergeKey {
+    /// Values including a `NULL` or `MISSING`, which are equal to no value.
+    Absent,
+    /// Values which are only equal to the values they are ordered as equal to, e.g., integers,
+    /// decimals, and strings.
+    Ordered(Vec<Value>),
+    /// Values which may be equal to values they are not ordered as equal to, e.g., `1e0` to `1`,
+    /// so are compared to all keys.
+    Unordered,
+}
+
+impl MergeKey {
+    /// Encodes the key and the tuple `binding` of it as a value an [`ExternalSorter`] sorts by
+    /// [`MergeKey::compare_encoded`].
+    fn encode(self, binding: Value) -> Value {
+        let (rank, values) = match self {
+            MergeKey::Absent => (0, vec![]),
+            MergeKey::Ordered(values) => (1, values),
+            MergeKey::Unordered => (2, vec![]),
+        };
+        Value::from(List::from(vec![
+            Value::from(rank),
+            Value::from(List::from(values)),
+            binding,
+        ]))
+    }
+
+    /// Decodes a value [encoded](MergeKey::encode) as a key and the tuple of it.
+    fn decode(encoded: Value) -> (MergeKey, Value) {
+        let mut parts = encoded.into_iter();
+        let (rank, values, binding) = (parts.next(), parts.next(), parts.next());
+        let key = match (rank, values) {
+            (Some(Value::Integer(1)), Some(values)) => {
+                MergeKey::Ordered(values.into_iter().collect())
+            }
+            (Some(Value::Integer(2)), _) => MergeKey::Unordered,
+            _ => MergeKey::Absent,
+        };
+        (key, binding.unwrap_or(Missing))
+    }
+
+    /// Compares [encoded](MergeKey::encode) keys as the keys they encode are ordered.
+    fn compare_encoded(l: &Value, r: &Value) -> Ordering {
+        match (l, r) {
+            (Value::List(l), Value::List(r)) => l.iter().take(2).cmp(r.iter().take(2)),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+impl EvalSortMergeJoin {
+    /// The key of the tuple `bindings`, of the values of the LHS `keys` if `left`, or else of the
+    /// RHS ones.
+    fn key(&self, bindings: &Tuple, left: bool, ctx: &dyn EvalContext) -> MergeKey {
+        let mut values = Vec::with_capacity(self.keys.len());
+        let mut ordered = true;
+        for (lhs, rhs) in &self.keys {
+            let expr = if left { lhs } else { rhs };
+            let value = expr.evaluate(bindings, ctx).into_owned();
+            match value {
+                Null | Missing => return MergeKey::Absent,
+                Value::Integer(_) | Value::Decimal(_) | Value::String(_) | Boolean(_) => {}
+                _ => ordered = false,
+            }
+            values.push(value);
+        }
+        if ordered {
+            MergeKey::Ordered(values)
+        } else {
+            MergeKey::Unordered
+        }
+    }
+
+    /// The tuples `bindings` of the LHS if `left`, or else of the RHS, along with their keys in
+    /// `env`, sorted by their keys.
+    fn sort(
+        &self,
+        bindings: Vec<Value>,
+        env: &Tuple,
+        left: bool,
+        ctx: &dyn EvalContext,
+    ) -> Result<Vec<(MergeKey, Value)>, ExternalSortError> {
+        let keyed = bindings.into_iter().map(|binding| {
+            let env_binding = env.tuple_concat(binding.as_tuple_ref().borrow());
+            (self.key(&env_binding, left, ctx), binding)
+        });
+        if self.sorted[usize::from(!left)] {
+            return Ok(keyed.collect());
+        }
+        let mut sorter = ExternalSorter::new(&self.sort_config, MergeKey::compare_encoded);
+        for (key, binding) in keyed {
+            sorter.push(key.encode(binding))?;
+        }
+        sorter
+            .finish()?
+            .map(|encoded| encoded.map(MergeKey::decode))
+            .collect()
+    }
+}
+
+impl Evaluable for EvalSortMergeJoin {
+    fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
+        let input_env = self.input.take().unwrap_or_else(|| Value::from(tuple![]));
+        let env = input_env.as_tuple_ref();
+        self.left.update_input(input_env.clone(), 0, ctx);
+        let left_bindings: Vec<_> = match self.left.evaluate(ctx) {
+            Value::Bag(t) => t.into_iter().collect(),
+            _ => {
+                ctx.add_error(EvaluationError::IllegalState(
+                    "Left side of FROM source should result in a bag of bindings".to_string(),
                 ));
                 return Missing;
             }
         };
+        self.right.update_input(input_env.clone(), 0, ctx);
+        let right_bindings: Vec<_> = match self.right.evaluate(ctx) {
+            Value::Bag(t) => t.into_iter().collect(),
+            _ => vec![Value::from(tuple![])],
+        };
+
+        let sorted = self
+            .sort(left_bindings, env.as_ref(), true, ctx)
+            .and_then(|left| Ok((left, self.sort(right_bindings, env.as_ref(), false, ctx)?)));
+        let (left, right) = match sorted {
+            Ok(sorted) => sorted,
+            Err(err) => {
+                ctx.add_error(EvaluationError::ExternalSortFailed(err.to_string()));
+                return Missing;
+            }
+        };
+
+        // the sorted RHS tuples of `Ordered` keys are `right[ordered..unordered]`, followed by
+        // those of `Unordered` keys
+        let ordered = right.partition_point(|(key, _)| key == &MergeKey::Absent);
+        let unordered = right.partition_point(|(key, _)| key != &MergeKey::Unordered);
+        let mut cursor = ordered;
+        let mut output_bag = bag![];
+        for (key, b_l) in left {
+            // the RHS tuples whose keys may equal that of b_l, in order
+            let candidates = match &key {
+                MergeKey::Ordered(_) => {
+                    while cursor < unordered && right[cursor].0.cmp(&key) == Ordering::Less {
+                        cursor += 1;
+                    }
+                    let equal = right[cursor..unordered]
+                        .iter()
+                        .take_while(|(other, _)| other.cmp(&key) == Ordering::Equal)
+                        .count();
+                    (cursor..cursor + equal).chain(unordered..right.len())
+                }
+                MergeKey::Unordered => (ordered..unordered).chain(unordered..right.len()),
+                MergeKey::Absent => (0..0).chain(0..0),
+            };
+
+            let mut matched = false;
+            for idx in candidates {
+                let b_l_b_r = b_l
+                    .as_tuple_ref()
+                    .as_ref()
+                    .tuple_concat(right[idx].1.as_tuple_ref().borrow());
+                let env_b_l_b_r = &env.as_ref().tuple_concat(&b_l_b_r);
+                if self.on.evaluate(env_b_l_b_r, ctx).as_ref() == &Value::Boolean(true) {
+                    matched = true;
+                    output_bag.push(Value::from(b_l_b_r));
+                }
+            }
+
+            // for `LEFT` joins, add b_l || <v_1_r: NULL, ..., v_n_r: NULL> for each unmatched b_l
+            if !matched && matches!(self.kind, EvalJoinKind::Left) {
+                let attrs = self.right.get_vars().unwrap_or(&[]);
+                let new_binding = b_l
+                    .as_tuple_ref()
+                    .as_ref()
+                    .tuple_concat(&tuple_with_null_vals(attrs));
+                output_bag.push(Value::from(new_binding));
+            }
+        }
         Value::Bag(Box::new(output_bag))
     }
 
@@ -343,7 +742,7 @@ pub trait AggregateFunction {
     fn compute(&self, group: &Tuple) -> Result<Value, EvaluationError>;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum AggFunc {
     // TODO: modeling COUNT(*)
     Avg(Avg),
@@ -376,7 +775,7 @@ impl AggregateFunction for AggFunc {
 }
 
 /// Filter values based on the given condition
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub(crate) enum AggFilterFn {
     /// Keeps only distinct values in each group
     Distinct(AggFilterDistinct),
@@ -396,7 +795,7 @@ impl AggFilterFn {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct AggFilterDistinct {
     seen_vals: HashMap<Tuple, HashSet<Value>>,
 }
@@ -430,7 +829,7 @@ impl AggFilterDistinct {
 }
 
 /// Represents SQL's `AVG` aggregation function
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Avg {
     avgs: HashMap<Tuple, (usize, Value)>,
     aggregator: AggFilterFn,
@@ -481,7 +880,7 @@ impl AggregateFunction for Avg {
 }
 
 /// Represents SQL's `COUNT` aggregation function
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Count {
     counts: HashMap<Tuple, usize>,
     aggregator: AggFilterFn,
@@ -530,7 +929,7 @@ impl AggregateFunction for Count {
 }
 
 /// Represents SQL's `MAX` aggregation function
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Max {
     maxes: HashMap<Tuple, Value>,
     aggregator: AggFilterFn,
@@ -579,7 +978,7 @@ impl AggregateFunction for Max {
 }
 
 /// Represents SQL's `MIN` aggregation function
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Min {
     mins: HashMap<Tuple, Value>,
     aggregator: AggFilterFn,
@@ -628,7 +1027,7 @@ impl AggregateFunction for Min {
 }
 
 /// Represents SQL's `SUM` aggregation function
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Sum {
     sums: HashMap<Tuple, Value>,
     aggregator: AggFilterFn,
@@ -686,6 +1085,11 @@ pub(crate) struct EvalGroupBy {
     pub(crate) exprs: HashMap<String, Box<dyn EvalExpr>>,
     pub(crate) aggregate_exprs: Vec<AggregateExpression>,
     pub(crate) group_as_alias: Option<String>,
+    /// The attributes the elements of the `GROUP AS` group are projected to, if any.
+    pub(crate) group_as_attributes: Option<Vec<BindingsName>>,
+    /// Whether the input is ordered by the grouping keys, so that each group is a run of
+    /// consecutive bindings, and the groups are output in order.
+    pub(crate) sorted: bool,
     pub(crate) input: Option<Value>,
 }
 
@@ -697,18 +1101,45 @@ pub(crate) enum EvalGroupingStrategy {
 }
 
 impl EvalGroupBy {
+    /// The group of the input `bindings`: a tuple of their grouping keys. A `MISSING` key is
+    /// `NULL` when grouping fully, and is left out of the group when grouping partially.
     #[inline]
     fn eval_group(&self, bindings: &Tuple, ctx: &dyn EvalContext) -> Tuple {
         self.exprs
             .iter()
-            .map(
-                |(alias, expr)| match expr.evaluate(bindings, ctx).into_owned() {
-                    Missing => (alias.as_str(), Value::Null),
-                    val => (alias.as_str(), val),
-                },
-            )
+            .filter_map(|(alias, expr)| {
+                match (expr.evaluate(bindings, ctx).into_owned(), &self.strategy) {
+                    (Missing, EvalGroupingStrategy::GroupFull) => {
+                        Some((alias.as_str(), Value::Null))
+                    }
+                    (Missing, EvalGroupingStrategy::GroupPartial) => None,
+                    (val, _) => Some((alias.as_str(), val)),
+                }
+            })
             .collect::<Tuple>()
     }
+
+    /// The element of the `GROUP AS` group for the input `bindings`.
+    #[inline]
+    fn group_element(&self, bindings: &Tuple) -> Value {
+        match &self.group_as_attributes {
+            None => Value::Tuple(Box::new(bindings.clone())),
+            Some(attrs) => {
+                let mut indexes: Vec<_> = attrs
+                    .iter()
+                    .filter_map(|attr| bindings.index_of(attr))
+                    .collect();
+                indexes.sort_unstable();
+                indexes.dedup();
+                let element: Tuple = indexes
+                    .into_iter()
+                    .filter_map(|idx| bindings.get_at(idx))
+                    .map(|(attr, value)| (attr, value.clone()))
+                    .collect();
+                Value::from(element)
+            }
+        }
+    }
 }
 
 impl Evaluable for EvalGroupBy {
@@ -716,64 +1147,70 @@ impl Evaluable for EvalGroupBy {
         let group_as_alias = &self.group_as_alias;
         let input_value = take_input!(self.input.take(), ctx);
 
-        match self.strategy {
-            EvalGroupingStrategy::GroupPartial => {
-                ctx.add_error(EvaluationError::NotYetImplemented(
-                    "GROUP PARTIAL".to_string(),
-                ));
-                Missing
+        let mut groups: HashMap<Tuple, Vec<Value>> = HashMap::new();
+        let mut runs: Vec<(Tuple, Vec<Value>)> = vec![];
+        // Aggregate into fresh copies of the aggregation functions, so that re-evaluating
+        // (e.g., a correlated subquery) doesn't accumulate into previous results
+        let mut funcs: Vec<AggFunc> = self
+            .aggregate_exprs
+            .iter()
+            .map(|aggregate_expr| aggregate_expr.func.clone())
+            .collect();
+        for v in input_value.into_iter() {
+            let v_as_tuple = v.coerce_to_tuple();
+            let group = self.eval_group(&v_as_tuple, ctx);
+            // Compute next aggregation result for each of the aggregation expressions
+            for (aggregate_expr, func) in self.aggregate_exprs.iter().zip(funcs.iter_mut()) {
+                let evaluated_val = aggregate_expr.expr.evaluate(&v_as_tuple, ctx).into_owned();
+                func.next_value(&evaluated_val, &group);
             }
-            EvalGroupingStrategy::GroupFull => {
-                let mut groups: HashMap<Tuple, Vec<Value>> = HashMap::new();
-                for v in input_value.into_iter() {
-                    let v_as_tuple = v.coerce_to_tuple();
-                    let group = self.eval_group(&v_as_tuple, ctx);
-                    // Compute next aggregation result for each of the aggregation expressions
-                    for aggregate_expr in self.aggregate_exprs.iter_mut() {
-                        let evaluated_val =
-                            aggregate_expr.expr.evaluate(&v_as_tuple, ctx).into_owned();
-                        aggregate_expr.func.next_value(&evaluated_val, &group);
-                    }
-                    groups
-                        .entry(group)
-                        .or_insert(vec![])
-                        .push(Value::Tuple(Box::new(v_as_tuple.clone())));
-                }
+            let element = match group_as_alias {
+                Some(_) => self.group_element(&v_as_tuple),
+                None => Missing,
+            };
+            match runs.last_mut() {
+                Some((last, elements)) if self.sorted && *last == group => elements.push(element),
+                _ if self.sorted => runs.push((group, vec![element])),
+                _ => groups.entry(group).or_insert(vec![]).push(element),
+            }
+        }
 
-                let bag = groups
-                    .into_iter()
-                    .map(|(mut k, v)| {
-                        // Finalize aggregation computation and include result in output binding
-                        // tuple
-                        let mut agg_results: Vec<(&str, Value)> = vec![];
-                        for aggregate_expr in &self.aggregate_exprs {
-                            match aggregate_expr.func.compute(&k) {
-                                Ok(agg_result) => {
-                                    agg_results.push((aggregate_expr.name.as_str(), agg_result))
-                                }
-                                Err(err) => {
-                                    ctx.add_error(err);
-                                    return Missing;
-                                }
-                            }
+        let groups: Box<dyn Iterator<Item = _>> = if self.sorted {
+            Box::new(runs.into_iter())
+        } else {
+            Box::new(groups.into_iter())
+        };
+        let bag = groups
+            .map(|(mut k, v)| {
+                // Finalize aggregation computation and include result in output binding
+                // tuple
+                let mut agg_results: Vec<(&str, Value)> = vec![];
+                for (aggregate_expr, func) in self.aggregate_exprs.iter().zip(&funcs) {
+                    match func.compute(&k) {
+                        Ok(agg_result) => {
+                            agg_results.push((aggregate_expr.name.as_str(), agg_result))
                         }
-                        agg_results
-                            .into_iter()
-                            .for_each(|(agg_name, agg_result)| k.insert(agg_name, agg_result));
-
-                        match group_as_alias {
-                            None => Value::from(k),
-                            Some(alias) => {
-                                let mut tuple_with_group = k;
-                                tuple_with_group.insert(alias, Value::Bag(Box::new(Bag::from(v))));
-                                Value::from(tuple_with_group)
-                            }
+                        Err(err) => {
+                            ctx.add_error(err);
+                            return Missing;
                         }
-                    })
-                    .collect::<Bag>();
-                Value::from(bag)
-            }
-        }
+                    }
+                }
+                agg_results
+                    .into_iter()
+                    .for_each(|(agg_name, agg_result)| k.insert(agg_name, agg_result));
+
+                match group_as_alias {
+                    None => Value::from(k),
+                    Some(alias) => {
+                        let mut tuple_with_group = k;
+                        tuple_with_group.insert(alias, Value::Bag(Box::new(Bag::from(v))));
+                        Value::from(tuple_with_group)
+                    }
+                }
+            })
+            .collect::<Bag>();
+        Value::from(bag)
     }
 
     fn update_input(&mut self, input: Value, _branch_num: u8, _ctx: &dyn EvalContext) {
@@ -860,22 +1297,32 @@ impl EvalUnpivot {
 
 impl Evaluable for EvalUnpivot {
     fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
-        let tuple = match self.expr.evaluate(&Tuple::new(), ctx).into_owned() {
-            Value::Tuple(tuple) => *tuple,
-            other => other.coerce_to_tuple(),
+        let input_value = self.input.take().unwrap_or(Missing);
+
+        // As with `Scan`, the expression is evaluated in the environment of each input binding
+        // (e.g., the left-hand side bindings in `FROM t, UNPIVOT t.x AS v AT k`)
+        let bindings = match input_value {
+            Value::Bag(t) => *t,
+            Value::Tuple(t) => bag![*t],
+            _ => bag![tuple![]],
         };
 
         let as_key = self.as_key.as_str();
-        let pairs = tuple;
-        let unpivoted = if let Some(at_key) = &self.at_key {
-            pairs
-                .map(|(k, v)| Tuple::from([(as_key, v), (at_key.as_str(), k.into())]))
-                .collect::<Bag>()
-        } else {
-            pairs
-                .map(|(_, v)| Tuple::from([(as_key, v)]))
-                .collect::<Bag>()
-        };
+        let mut unpivoted = bag![];
+        for binding in bindings.iter() {
+            let binding_tuple = binding.as_tuple_ref();
+            let pairs = match self.expr.evaluate(&binding_tuple, ctx).into_owned() {
+                Value::Tuple(tuple) => *tuple,
+                other => other.coerce_to_tuple(),
+            };
+            for (k, v) in pairs {
+                let out = match &self.at_key {
+                    Some(at_key) => Tuple::from([(as_key, v), (at_key.as_str(), k.into())]),
+                    None => Tuple::from([(as_key, v)]),
+                };
+                unpivoted.push(Value::from(out));
+            }
+        }
         Value::from(unpivoted)
     }
 
@@ -931,6 +1378,75 @@ impl Evaluable for EvalFilter {
     }
 }
 
+/// Represents the operand of a join filtered before being joined, e.g., the scan of `t` by
+/// `t.a = 1` in `FROM t, s WHERE t.a = 1`, which a join evaluates as it evaluates its unfiltered
+/// operands.
+#[derive(Debug)]
+pub(crate) struct EvalFilteredOperand {
+    pub(crate) operand: Box<dyn Evaluable>,
+    pub(crate) filter: EvalFilter,
+}
+
+impl Evaluable for EvalFilteredOperand {
+    fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
+        let bindings = self.operand.evaluate(ctx);
+        self.filter.update_input(bindings, 0, ctx);
+        self.filter.evaluate(ctx)
+    }
+
+    fn update_input(&mut self, input: Value, branch_num: u8, ctx: &dyn EvalContext) {
+        self.operand.update_input(input, branch_num, ctx);
+    }
+
+    fn get_vars(&self) -> Option<&[String]> {
+        self.operand.get_vars()
+    }
+}
+
+/// Represents an evaluation `Let` operator; for each binding tuple of its input the `Let` adds the
+/// attributes specified by `bindings`, e.g. `LET t.a + 1 AS b` in `SELECT b FROM t LET t.a + 1 AS b`.
+#[derive(Debug)]
+pub(crate) struct EvalLet {
+    pub(crate) bindings: Vec<(String, Box<dyn EvalExpr>)>,
+    pub(crate) input: Option<Value>,
+}
+
+impl EvalLet {
+    pub(crate) fn new(bindings: Vec<(String, Box<dyn EvalExpr>)>) -> Self {
+        EvalLet {
+            bindings,
+            input: None,
+        }
+    }
+}
+
+impl Evaluable for EvalLet {
+    fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
+        let input_value = take_input!(self.input.take(), ctx);
+
+        let ordered = input_value.is_ordered();
+
+        let values = input_value.into_iter().map(|v| {
+            let mut v_as_tuple = v.coerce_to_tuple();
+            // Each binding is in scope of the bindings that precede it
+            for (alias, expr) in &self.bindings {
+                let evaluated_val = expr.evaluate(&v_as_tuple, ctx).into_owned();
+                v_as_tuple.insert(alias, evaluated_val);
+            }
+            Value::from(v_as_tuple)
+        });
+
+        match ordered {
+            true => Value::from(values.collect::<List>()),
+            false => Value::from(values.collect::<Bag>()),
+        }
+    }
+
+    fn update_input(&mut self, input: Value, _branch_num: u8, _ctx: &dyn EvalContext) {
+        self.input = Some(input);
+    }
+}
+
 /// Represents an evaluation `Having` operator; for an input bag of binding tuples the `Having`
 /// operator filters out the binding tuples that does not meet the condition expressed as `expr`,
 /// e.g. `a = 10` in `HAVING a = 10` expression.
@@ -994,59 +1510,227 @@ pub(crate) enum EvalOrderBySortSpec {
 #[derive(Debug)]
 pub(crate) struct EvalOrderBy {
     pub(crate) cmp: Vec<EvalOrderBySortCondition>,
+    pub(crate) sort_config: ExternalSortConfig,
     pub(crate) input: Option<Value>,
 }
 
 impl EvalOrderBy {
     #[inline]
     fn compare(&self, l: &Value, r: &Value, ctx: &dyn EvalContext) -> Ordering {
-        let l = l.as_tuple_ref();
-        let r = r.as_tuple_ref();
-        self.cmp
-            .iter()
-            .map(|spec| {
-                let l = spec.expr.evaluate(&l, ctx);
-                let r = spec.expr.evaluate(&r, ctx);
-
-                match spec.spec {
-                    EvalOrderBySortSpec::AscNullsFirst => l.as_ref().cmp(r.as_ref()),
-                    EvalOrderBySortSpec::AscNullsLast => match (l.as_ref(), r.as_ref()) {
-                        (Null, Null) => Ordering::Equal,
-                        (Null, Missing) => Ordering::Less,
-                        (Missing, Missing) => Ordering::Equal,
-                        (Missing, Null) => Ordering::Greater,
-                        (Null, _) => Ordering::Greater,
-                        (Missing, _) => Ordering::Greater,
-                        (_, Null) => Ordering::Less,
-                        (_, Missing) => Ordering::Less,
-                        (l, r) => l.cmp(r),
-                    },
-                    EvalOrderBySortSpec::DescNullsFirst => match (l.as_ref(), r.as_ref()) {
-                        (Null, Null) => Ordering::Equal,
-                        (Null, Missing) => Ordering::Less,
-                        (Missing, Missing) => Ordering::Equal,
-                        (Missing, Null) => Ordering::Greater,
-                        (Null, _) => Ordering::Less,
-                        (Missing, _) => Ordering::Less,
-                        (_, Null) => Ordering::Greater,
-                        (_, Missing) => Ordering::Greater,
-                        (l, r) => r.cmp(l),
-                    },
-                    EvalOrderBySortSpec::DescNullsLast => r.as_ref().cmp(l.as_ref()),
+        compare_sort_conditions(&self.cmp, &l.as_tuple_ref(), &r.as_tuple_ref(), ctx)
+    }
+}
+
+/// Compares the binding tuples `l` & `r` by the sort conditions `cmp`, in order.
+fn compare_sort_conditions(
+    cmp: &[EvalOrderBySortCondition],
+    l: &Tuple,
+    r: &Tuple,
+    ctx: &dyn EvalContext,
+) -> Ordering {
+    cmp.iter()
+        .map(|spec| {
+            let l = spec.expr.evaluate(l, ctx);
+            let r = spec.expr.evaluate(r, ctx);
+
+            match spec.spec {
+                EvalOrderBySortSpec::AscNullsFirst => l.as_ref().cmp(r.as_ref()),
+                EvalOrderBySortSpec::AscNullsLast => match (l.as_ref(), r.as_ref()) {
+                    (Null, Null) => Ordering::Equal,
+                    (Null, Missing) => Ordering::Less,
+                    (Missing, Missing) => Ordering::Equal,
+                    (Missing, Null) => Ordering::Greater,
+                    (Null, _) => Ordering::Greater,
+                    (Missing, _) => Ordering::Greater,
+                    (_, Null) => Ordering::Less,
+                    (_, Missing) => Ordering::Less,
+                    (l, r) => l.cmp(r),
+                },
+                EvalOrderBySortSpec::DescNullsFirst => match (l.as_ref(), r.as_ref()) {
+                    (Null, Null) => Ordering::Equal,
+                    (Null, Missing) => Ordering::Less,
+                    (Missing, Missing) => Ordering::Equal,
+                    (Missing, Null) => Ordering::Greater,
+                    (Null, _) => Ordering::Less,
+                    (Missing, _) => Ordering::Less,
+                    (_, Null) => Ordering::Greater,
+                    (_, Missing) => Ordering::Greater,
+                    (l, r) => r.cmp(l),
+                },
+                EvalOrderBySortSpec::DescNullsLast => r.as_ref().cmp(l.as_ref()),
+            }
+        })
+        .find_or_last(|o| o != &Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+impl Evaluable for EvalOrderBy {
+    fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
+        let input_value = take_input!(self.input.take(), ctx);
+
+        let sorted = external_sort(input_value, &self.sort_config, |l, r| {
+            self.compare(l, r, ctx)
+        });
+        match sorted {
+            Ok(values) => Value::from(List::from(values)),
+            Err(err) => {
+                ctx.add_error(EvaluationError::ExternalSortFailed(err.to_string()));
+                Missing
+            }
+        }
+    }
+
+    fn update_input(&mut self, input: Value, _branch_num: u8, _ctx: &dyn EvalContext) {
+        self.input = Some(input);
+    }
+}
+
+/// Represents an evaluation `Window` operator; for each binding tuple of its input the `Window`
+/// binds `name` to the result of `func` over the tuples of its partition ordered by `cmp`, e.g.
+/// `ROW_NUMBER() OVER (PARTITION BY t.a ORDER BY t.b)` in
+/// `SELECT ROW_NUMBER() OVER (PARTITION BY t.a ORDER BY t.b) AS n FROM t`.
+#[derive(Debug)]
+pub(crate) struct EvalWindow {
+    pub(crate) partition_by: Vec<Box<dyn EvalExpr>>,
+    pub(crate) cmp: Vec<EvalOrderBySortCondition>,
+    pub(crate) name: String,
+    pub(crate) func: EvalWindowFunction,
+    pub(crate) input: Option<Value>,
+}
+
+#[derive(Debug)]
+pub(crate) enum EvalWindowFunction {
+    RowNumber,
+    Rank,
+    DenseRank,
+    Lag(EvalWindowOffset),
+    Lead(EvalWindowOffset),
+}
+
+/// The arguments of the `LAG` & `LEAD` window functions.
+#[derive(Debug)]
+pub(crate) struct EvalWindowOffset {
+    pub(crate) expr: Box<dyn EvalExpr>,
+    pub(crate) offset: Box<dyn EvalExpr>,
+    pub(crate) default: Box<dyn EvalExpr>,
+    /// Whether an offset which is not a non-negative integer is an error, rather than `MISSING`.
+    pub(crate) strict: bool,
+}
+
+impl EvalWindow {
+    /// The results of the window function for each of the (ordered) tuples of `partition`.
+    fn evaluate_partition(
+        &self,
+        partition: &[Tuple],
+        ctx: &dyn EvalContext,
+    ) -> Result<Vec<Value>, EvaluationError> {
+        // whether the `i`th tuple is ordered the same as its predecessor
+        let is_peer = |i: usize| {
+            i > 0
+                && compare_sort_conditions(&self.cmp, &partition[i - 1], &partition[i], ctx)
+                    == Ordering::Equal
+        };
+        match &self.func {
+            EvalWindowFunction::RowNumber => {
+                Ok((1..=partition.len() as i64).map(Value::from).collect())
+            }
+            EvalWindowFunction::Rank => {
+                let mut rank = 0;
+                Ok((0..partition.len())
+                    .map(|i| {
+                        if !is_peer(i) {
+                            rank = i as i64 + 1;
+                        }
+                        Value::from(rank)
+                    })
+                    .collect())
+            }
+            EvalWindowFunction::DenseRank => {
+                let mut rank = 0;
+                Ok((0..partition.len())
+                    .map(|i| {
+                        if !is_peer(i) {
+                            rank += 1;
+                        }
+                        Value::from(rank)
+                    })
+                    .collect())
+            }
+            EvalWindowFunction::Lag(args) => offset_values(partition, args, true, ctx),
+            EvalWindowFunction::Lead(args) => offset_values(partition, args, false, ctx),
+        }
+    }
+}
+
+/// The values of `LAG` (if `backward`) or `LEAD` for each of the tuples of `partition`, or an
+/// error in strict mode if an offset is not a non-negative integer.
+fn offset_values(
+    partition: &[Tuple],
+    args: &EvalWindowOffset,
+    backward: bool,
+    ctx: &dyn EvalContext,
+) -> Result<Vec<Value>, EvaluationError> {
+    partition
+        .iter()
+        .enumerate()
+        .map(|(i, tuple)| {
+            let offset = match args.offset.evaluate(tuple, ctx).as_ref() {
+                Value::Integer(offset) if *offset >= 0 => *offset as usize,
+                offset if args.strict => {
+                    let name = if backward { "LAG" } else { "LEAD" };
+                    return Err(EvaluationError::InvalidArgument(format!(
+                        "the offset of {name} must be a non-negative integer, found {offset:?}"
+                    )));
                 }
+                _ => return Ok(Missing),
+            };
+            let other = match backward {
+                true => i.checked_sub(offset),
+                false => i.checked_add(offset),
+            };
+            Ok(match other.and_then(|j| partition.get(j)) {
+                Some(other) => args.expr.evaluate(other, ctx).into_owned(),
+                None => args.default.evaluate(tuple, ctx).into_owned(),
             })
-            .find_or_last(|o| o != &Ordering::Equal)
-            .unwrap_or(Ordering::Equal)
-    }
+        })
+        .collect()
 }
 
-impl Evaluable for EvalOrderBy {
+impl Evaluable for EvalWindow {
     fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
         let input_value = take_input!(self.input.take(), ctx);
 
-        let mut values = input_value.into_iter().collect_vec();
-        values.sort_by(|l, r| self.compare(l, r, ctx));
-        Value::from(List::from(values))
+        let mut partitions: Vec<Vec<Tuple>> = vec![];
+        let mut keys: HashMap<Vec<Value>, usize> = HashMap::new();
+        for tuple in input_value.into_iter().map(Value::coerce_to_tuple) {
+            let key = self
+                .partition_by
+                .iter()
+                .map(|expr| expr.evaluate(&tuple, ctx).into_owned())
+                .collect();
+            let partition = *keys.entry(key).or_insert_with(|| {
+                partitions.push(vec![]);
+                partitions.len() - 1
+            });
+            partitions[partition].push(tuple);
+        }
+
+        let mut output = vec![];
+        for mut partition in partitions {
+            partition.sort_by(|l, r| compare_sort_conditions(&self.cmp, l, r, ctx));
+            let values = match self.evaluate_partition(&partition, ctx) {
+                Ok(values) => values,
+                Err(err) => {
+                    ctx.add_error(err);
+                    return Missing;
+                }
+            };
+            output.extend(partition.into_iter().zip(values).map(|(mut tuple, value)| {
+                tuple.insert(&self.name, value);
+                Value::from(tuple)
+            }));
+        }
+        Value::from(Bag::from(output))
     }
 
     fn update_input(&mut self, input: Value, _branch_num: u8, _ctx: &dyn EvalContext) {
@@ -1264,27 +1948,68 @@ impl Evaluable for EvalExprQuery {
     }
 }
 
+/// Sorts `values` by `cmp`, spilling to disk as configured by `config`.
+fn external_sort<'a>(
+    values: Value,
+    config: &ExternalSortConfig,
+    cmp: impl Fn(&Value, &Value) -> Ordering + 'a,
+) -> Result<Vec<Value>, ExternalSortError> {
+    let mut sorter = ExternalSorter::new(config, cmp);
+    for value in values {
+        sorter.push(value)?;
+    }
+    sorter.finish()?.collect()
+}
+
 /// Represents an SQL `DISTINCT` operator, e.g. in `SELECT DISTINCT a FROM t`.
-#[derive(Debug, Default)]
+///
+/// The distinct values of a bag are found by (externally) sorting them such that duplicates are
+/// adjacent; the distinct values of a list are found in order.
+#[derive(Debug)]
 pub(crate) struct EvalDistinct {
+    pub(crate) sort_config: ExternalSortConfig,
     pub(crate) input: Option<Value>,
 }
 
 impl EvalDistinct {
-    pub(crate) fn new() -> Self {
-        Self::default()
+    pub(crate) fn new(sort_config: ExternalSortConfig) -> Self {
+        EvalDistinct {
+            sort_config,
+            input: None,
+        }
     }
 }
 
 impl Evaluable for EvalDistinct {
     fn evaluate(&mut self, ctx: &dyn EvalContext) -> Value {
         let input_value = take_input!(self.input.take(), ctx);
-        let ordered = input_value.is_ordered();
+        if input_value.is_ordered() {
+            return Value::from(input_value.into_iter().unique().collect::<List>());
+        }
 
-        let values = input_value.into_iter().unique();
-        match ordered {
-            true => Value::from(values.collect::<List>()),
-            false => Value::from(values.collect::<Bag>()),
+        match external_sort(input_value, &self.sort_config, |l, r| l.cmp(r)) {
+            Ok(sorted) => {
+                // Values which compare as equal are adjacent; keep the first of each run of
+                // equal values.
+                let mut distinct: Vec<Value> = vec![];
+                let mut group_start = 0;
+                for value in sorted {
+                    let same_group = distinct
+                        .last()
+                        .is_some_and(|last| last.cmp(&value) == Ordering::Equal);
+                    if !same_group {
+                        group_start = distinct.len();
+                    }
+                    if !distinct[group_start..].contains(&value) {
+                        distinct.push(value);
+                    }
+                }
+                Value::from(Bag::from(distinct))
+            }
+            Err(err) => {
+                ctx.add_error(EvaluationError::ExternalSortFailed(err.to_string()));
+                Missing
+            }
         }
     }
 
@@ -1314,28 +2039,128 @@ impl Evaluable for EvalSink {
 #[derive(Debug)]
 pub(crate) struct EvalSubQueryExpr {
     pub(crate) plan: Rc<RefCell<EvalPlan>>,
+    pub(crate) coercion: SubQueryCoercion,
+    pub(crate) strict: bool,
 }
 
 impl EvalSubQueryExpr {
-    pub(crate) fn new(plan: EvalPlan) -> Self {
+    pub(crate) fn new(plan: EvalPlan, coercion: SubQueryCoercion, strict: bool) -> Self {
         EvalSubQueryExpr {
             plan: Rc::new(RefCell::new(plan)),
+            coercion,
+            strict,
+        }
+    }
+}
+
+/// Indicates how the result of a subquery is used by its enclosing expression.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum SubQueryCoercion {
+    Collection,
+    Scalar,
+}
+
+impl SubQueryCoercion {
+    /// Coerces the result of a subquery, which in strict mode fails if a scalar subquery returns
+    /// more than one row or a row of other than one attribute, and is `MISSING` otherwise.
+    #[inline]
+    fn coerce(self, value: Value, strict: bool, ctx: &dyn EvalContext) -> Value {
+        match self {
+            SubQueryCoercion::Collection => value,
+            SubQueryCoercion::Scalar => match coerce_scalar(value) {
+                Ok(value) => value,
+                Err(err) => {
+                    if strict {
+                        ctx.add_error(err);
+                    }
+                    Missing
+                }
+            },
+        }
+    }
+}
+
+/// Coerces the result of a scalar subquery to the single value of its single tuple; an empty
+/// result is coerced to `NULL`, more than one row or a row of other than one attribute is an
+/// error, and any other result is coerced to `MISSING`.
+#[inline]
+fn coerce_scalar(value: Value) -> Result<Value, EvaluationError> {
+    let mut rows = value.into_iter();
+    match (rows.next(), rows.next()) {
+        (None, _) => Ok(Null),
+        (Some(_), Some(_)) => Err(EvaluationError::CardinalityViolation(
+            "a scalar subquery returned more than one row".to_string(),
+        )),
+        (Some(Value::Tuple(row)), None) if row.len() == 1 => {
+            Ok(row.into_values().next().unwrap_or(Missing))
         }
+        (Some(Value::Tuple(row)), None) => Err(EvaluationError::CardinalityViolation(format!(
+            "a scalar subquery returned a row of {} attributes rather than one",
+            row.len()
+        ))),
+        _ => Ok(Missing),
     }
 }
 
 impl EvalExpr for EvalSubQueryExpr {
-    fn evaluate<'a>(&'a self, bindings: &'a Tuple, _ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
-        let value = if let Ok(evaluated) = self
-            .plan
-            .borrow_mut()
-            .execute_mut(MapBindings::from(bindings))
-        {
+    fn evaluate<'a>(&'a self, bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
+        let nested = NestedContext::new(bindings, ctx);
+        let value = if let Ok(evaluated) = self.plan.borrow_mut().execute_in(&nested) {
             evaluated.result
         } else {
             Missing
         };
-        Cow::Owned(value)
+        Cow::Owned(self.coercion.coerce(value, self.strict, ctx))
+    }
+}
+
+/// An uncorrelated subquery whose result is computed at most once per execution of the plan
+/// containing it, and shared by all of the subquery's references.
+#[derive(Debug)]
+pub(crate) struct MaterializedSubQuery {
+    plan: RefCell<EvalPlan>,
+    result: RefCell<Option<Value>>,
+}
+
+impl MaterializedSubQuery {
+    pub(crate) fn new(plan: EvalPlan) -> Self {
+        MaterializedSubQuery {
+            plan: RefCell::new(plan),
+            result: RefCell::new(None),
+        }
+    }
+
+    /// Discards the computed result, e.g., before the plan containing the subquery is executed
+    /// anew.
+    pub(crate) fn reset(&self) {
+        self.result.take();
+    }
+
+    fn evaluate(&self, ctx: &dyn EvalContext) -> Value {
+        if let Some(value) = self.result.borrow().as_ref() {
+            return value.clone();
+        }
+        let value = match self.plan.borrow_mut().execute_in(ctx) {
+            Ok(evaluated) => evaluated.result,
+            Err(_) => Missing,
+        };
+        *self.result.borrow_mut() = Some(value.clone());
+        value
+    }
+}
+
+/// Represents a reference to a [`MaterializedSubQuery`].
+#[derive(Debug)]
+pub(crate) struct EvalMaterializedSubQueryExpr {
+    pub(crate) subquery: Rc<MaterializedSubQuery>,
+    pub(crate) coercion: SubQueryCoercion,
+    pub(crate) strict: bool,
+}
+
+impl EvalExpr for EvalMaterializedSubQueryExpr {
+    fn evaluate<'a>(&'a self, _bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
+        let value = self.subquery.evaluate(ctx);
+        Cow::Owned(self.coercion.coerce(value, self.strict, ctx))
     }
 }
 
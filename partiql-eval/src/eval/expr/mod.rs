@@ -1,24 +1,26 @@
 use crate::env::Bindings;
 use crate::error::EvaluationError;
 use crate::eval::evaluable::SetQuantifier;
-use crate::eval::expr::pattern_match::like_to_re_pattern;
 use crate::eval::EvalContext;
 use itertools::Itertools;
-use partiql_catalog::BaseTableExpr;
-use partiql_logical::Type;
+use partiql_catalog::{BaseTableExpr, ScalarFnExpr, ScanLimit};
+use partiql_logical::{Parameter, Type};
 use partiql_value::Value::{Boolean, Missing, Null};
 use partiql_value::{
     Bag, BinaryAnd, BinaryOr, BindingsName, DateTime, List, NullableEq, NullableOrd, Tuple,
     UnaryPlus, Value,
 };
-use regex::{Regex, RegexBuilder};
+use regex::Regex;
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
 use std::borrow::{Borrow, Cow};
 use std::fmt::Debug;
+use unicase::UniCase;
 
 pub(crate) mod pattern_match;
 
+pub use pattern_match::PatternCache;
+
 /// A trait for expressions that require evaluation, e.g. `a + b` or `c > 2`.
 pub trait EvalExpr: Debug {
     fn evaluate<'a>(&'a self, bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value>;
@@ -103,10 +105,14 @@ pub(crate) struct EvalPath {
 
 #[derive(Debug)]
 pub(crate) enum EvalPathComponent {
-    Key(BindingsName),
-    KeyExpr(Box<dyn EvalExpr>),
+    Key(EvalAttrName),
     Index(i64),
-    IndexExpr(Box<dyn EvalExpr>),
+    /// An index (if an integer) or case-sensitive key (if a string) computed by an expression.
+    Expr(Box<dyn EvalExpr>),
+    /// `[*]`, which navigates into each element of a collection.
+    Wildcard,
+    /// `.*`, which navigates into each attribute value of a tuple.
+    Unpivot,
 }
 
 impl EvalExpr for EvalPath {
@@ -120,41 +126,98 @@ impl EvalExpr for EvalPath {
         ) -> Option<&'a Value> {
             match path {
                 EvalPathComponent::Key(k) => match value {
-                    Value::Tuple(tuple) => tuple.get(k),
+                    Value::Tuple(tuple) => k.get_in(tuple),
                     _ => None,
                 },
                 EvalPathComponent::Index(idx) => match value {
                     Value::List(list) if (*idx as usize) < list.len() => list.get(*idx),
                     _ => None,
                 },
-                EvalPathComponent::KeyExpr(ke) => {
-                    let key = ke.evaluate(bindings, ctx);
-                    match (value, key.as_ref()) {
-                        (Value::Tuple(tuple), Value::String(key)) => {
-                            tuple.get(&BindingsName::CaseInsensitive(key.as_ref().clone()))
-                        }
-                        _ => None,
+                EvalPathComponent::Expr(e) => match (value, e.evaluate(bindings, ctx).as_ref()) {
+                    (Value::List(list), Value::Integer(idx)) if (*idx as usize) < list.len() => {
+                        list.get(*idx)
                     }
-                }
-                EvalPathComponent::IndexExpr(ie) => {
-                    if let Value::Integer(idx) = ie.evaluate(bindings, ctx).as_ref() {
-                        match value {
-                            Value::List(list) if (*idx as usize) < list.len() => list.get(*idx),
-                            _ => None,
-                        }
-                    } else {
-                        None
+                    (Value::Tuple(tuple), Value::String(key)) => {
+                        tuple.get(&BindingsName::CaseSensitive(key.as_ref().clone()))
                     }
-                }
+                    _ => None,
+                },
+                EvalPathComponent::Wildcard | EvalPathComponent::Unpivot => None,
             }
         }
         let value = self.expr.evaluate(bindings, ctx);
-        self.components
-            .iter()
-            .fold(Some(value.as_ref()), |v, path| {
-                v.and_then(|v| path_into(v, path, bindings, ctx))
-            })
-            .map_or_else(|| Cow::Owned(Value::Missing), |v| Cow::Owned(v.clone()))
+        let is_wildcard = |path: &EvalPathComponent| {
+            matches!(
+                path,
+                EvalPathComponent::Wildcard | EvalPathComponent::Unpivot
+            )
+        };
+        if !self.components.iter().any(is_wildcard) {
+            // A path into a borrowed value (e.g., a variable) borrows the value it navigates to
+            // rather than cloning it
+            let mut navigate = self.components.iter();
+            return match value {
+                Cow::Borrowed(value) => navigate
+                    .try_fold(value, |v, path| path_into(v, path, bindings, ctx))
+                    .map_or(Cow::Owned(Missing), Cow::Borrowed),
+                Cow::Owned(value) => navigate
+                    .try_fold(&value, |v, path| path_into(v, path, bindings, ctx))
+                    .map_or(Cow::Owned(Missing), |v| Cow::Owned(v.clone())),
+            };
+        }
+
+        // A path with wildcards navigates into each of the values matched so far and results in
+        // a bag of all (non-`MISSING`) values matched by the full path. A wildcard over a value
+        // which is not a collection (resp. tuple) matches the value itself.
+        let mut values = vec![value.as_ref()];
+        for path in &self.components {
+            values = match path {
+                EvalPathComponent::Wildcard => values
+                    .into_iter()
+                    .flat_map(|v| match v {
+                        Value::List(list) => list.iter().collect_vec(),
+                        Value::Bag(bag) => bag.iter().collect_vec(),
+                        v => vec![v],
+                    })
+                    .collect(),
+                EvalPathComponent::Unpivot => values
+                    .into_iter()
+                    .flat_map(|v| match v {
+                        Value::Tuple(tuple) => tuple.values().collect_vec(),
+                        v => vec![v],
+                    })
+                    .collect(),
+                path => values
+                    .into_iter()
+                    .filter_map(|v| path_into(v, path, bindings, ctx))
+                    .collect(),
+            };
+        }
+        let values = values.into_iter().filter(|v| !matches!(v, Missing));
+        Cow::Owned(Value::from(values.cloned().collect::<Bag>()))
+    }
+}
+
+/// Represents a query parameter, e.g. `?` or `:name`, whose value is bound in the evaluation
+/// context.
+#[derive(Debug)]
+pub(crate) struct EvalParameter {
+    pub(crate) parameter: Parameter,
+}
+
+impl EvalExpr for EvalParameter {
+    fn evaluate<'a>(&'a self, _bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
+        match ctx.parameters().get(&self.parameter) {
+            Some(value) => Cow::Owned(value.clone()),
+            None => {
+                let parameter = match &self.parameter {
+                    Parameter::Positional(position) => format!("at position {position}"),
+                    Parameter::Named(name) => format!("`{name:?}`"),
+                };
+                ctx.add_error(EvaluationError::UnboundParameter(parameter));
+                Cow::Owned(Missing)
+            }
+        }
     }
 }
 
@@ -178,15 +241,77 @@ impl EvalExpr for EvalDynamicLookup {
     }
 }
 
+/// An attribute name resolved when the plan is compiled for its lookups in each row, e.g., with
+/// its case folded once if it is case-insensitive rather than on each lookup.
+#[derive(Debug)]
+pub(crate) struct EvalAttrName {
+    name: BindingsName,
+    /// The case-folded name, if it is case-insensitive.
+    folded: Option<UniCase<String>>,
+}
+
+impl EvalAttrName {
+    /// The name, e.g., to look up in bindings other than tuples.
+    #[inline]
+    pub(crate) fn name(&self) -> &BindingsName {
+        &self.name
+    }
+
+    /// The value of the first attribute of `tuple` with the name, if any.
+    #[inline]
+    pub(crate) fn get_in<'a>(&self, tuple: &'a Tuple) -> Option<&'a Value> {
+        match &self.folded {
+            Some(folded) => tuple
+                .pairs()
+                .find_map(|(attr, value)| (UniCase::new(attr) == *folded).then_some(value)),
+            None => tuple.get(&self.name),
+        }
+    }
+}
+
+impl From<BindingsName> for EvalAttrName {
+    fn from(name: BindingsName) -> Self {
+        let folded = match &name {
+            BindingsName::CaseSensitive(_) => None,
+            BindingsName::CaseInsensitive(s) => Some(UniCase::new(s.clone())),
+        };
+        EvalAttrName { name, folded }
+    }
+}
+
 /// Represents a variable reference in a (sub)query, e.g. `a` in `SELECT b as a FROM`.
 #[derive(Debug)]
 pub(crate) struct EvalVarRef {
-    pub(crate) name: BindingsName,
+    pub(crate) name: EvalAttrName,
 }
 
 impl EvalExpr for EvalVarRef {
     fn evaluate<'a>(&'a self, bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
-        let value = Bindings::get(bindings, &self.name).or_else(|| ctx.bindings().get(&self.name));
+        let value = self
+            .name
+            .get_in(bindings)
+            .or_else(|| ctx.bindings().get(self.name.name()));
+
+        match value {
+            None => Cow::Owned(Missing),
+            Some(v) => Cow::Borrowed(v),
+        }
+    }
+}
+
+/// Represents a variable reference which is resolved against the global environment before the
+/// local one, e.g. `a` in `SELECT * FROM a`.
+#[derive(Debug)]
+pub(crate) struct EvalGlobalVarRef {
+    pub(crate) name: BindingsName,
+}
+
+impl EvalExpr for EvalGlobalVarRef {
+    fn evaluate<'a>(&'a self, bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
+        let value = ctx
+            .bindings()
+            .get(&self.name)
+            .or_else(|| Bindings::get(bindings, &self.name));
 
         match value {
             None => Cow::Owned(Missing),
@@ -280,6 +405,7 @@ pub(crate) enum EvalBinOp {
     Gteq,
     Lt,
     Lteq,
+    IsDistinctFrom,
 
     // Arithmetic ops
     Add,
@@ -303,6 +429,7 @@ impl EvalExpr for EvalBinOpExpr {
                 (EvalBinOp::And, Missing) | (EvalBinOp::Or, Missing) | (EvalBinOp::In, Missing) => {
                     Some(Null)
                 }
+                (EvalBinOp::IsDistinctFrom, _) => None,
                 (_, Missing) => Some(Missing),
                 _ => None,
             }
@@ -346,6 +473,15 @@ impl EvalExpr for EvalBinOpExpr {
             EvalBinOp::Gteq => NullableOrd::gteq(lhs, rhs),
             EvalBinOp::Lt => NullableOrd::lt(lhs, rhs),
             EvalBinOp::Lteq => NullableOrd::lteq(lhs, rhs),
+            // `NULL` and `MISSING` are not distinct from each other, but are from any other value.
+            EvalBinOp::IsDistinctFrom => match (lhs, rhs) {
+                (Null | Missing, Null | Missing) => Boolean(false),
+                (Null | Missing, _) | (_, Null | Missing) => Boolean(true),
+                _ => match NullableEq::eq(lhs, rhs) {
+                    Boolean(eq) => Boolean(!eq),
+                    _ => Boolean(true),
+                },
+            },
             EvalBinOp::Add => lhs + rhs,
             EvalBinOp::Sub => lhs - rhs,
             EvalBinOp::Mul => lhs * rhs,
@@ -451,6 +587,7 @@ pub(crate) struct EvalLikeNonStringNonLiteralMatch {
     pub(crate) value: Box<dyn EvalExpr>,
     pub(crate) pattern: Box<dyn EvalExpr>,
     pub(crate) escape: Box<dyn EvalExpr>,
+    pub(crate) patterns: PatternCache,
 }
 
 impl EvalLikeNonStringNonLiteralMatch {
@@ -458,11 +595,13 @@ impl EvalLikeNonStringNonLiteralMatch {
         value: Box<dyn EvalExpr>,
         pattern: Box<dyn EvalExpr>,
         escape: Box<dyn EvalExpr>,
+        patterns: PatternCache,
     ) -> Self {
         EvalLikeNonStringNonLiteralMatch {
             value,
             pattern,
             escape,
+            patterns,
         }
     }
 }
@@ -487,10 +626,7 @@ impl EvalExpr for EvalLikeNonStringNonLiteralMatch {
                     ));
                 }
                 let escape = e.chars().next();
-                let regex_pattern = RegexBuilder::new(&like_to_re_pattern(p, escape))
-                    .size_limit(RE_SIZE_LIMIT)
-                    .build();
-                match regex_pattern {
+                match self.patterns.like(p, escape) {
                     Ok(pattern) => Boolean(pattern.is_match(v.as_ref())),
                     Err(err) => {
                         ctx.add_error(EvaluationError::IllegalState(err.to_string()));
@@ -871,13 +1007,15 @@ impl EvalExpr for EvalFnExists {
     #[inline]
     fn evaluate<'a>(&'a self, bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
         let value = self.value.evaluate(bindings, ctx);
-        let exists = match value.borrow() {
-            Value::Bag(b) => !b.is_empty(),
-            Value::List(l) => !l.is_empty(),
-            Value::Tuple(t) => !t.is_empty(),
-            _ => false,
+        let result = match value.borrow() {
+            Null => Null,
+            Missing => Missing,
+            Value::Bag(b) => Value::Boolean(!b.is_empty()),
+            Value::List(l) => Value::Boolean(!l.is_empty()),
+            Value::Tuple(t) => Value::Boolean(!t.is_empty()),
+            _ => Missing,
         };
-        Cow::Owned(Value::Boolean(exists))
+        Cow::Owned(result)
     }
 }
 
@@ -957,6 +1095,69 @@ impl EvalExpr for EvalFnCardinality {
     }
 }
 
+/// Represents the union of the attributes of tuples, e.g., of the expansion of `t.*` in
+/// `SELECT t.*, u.name FROM t, u`; a non-tuple argument is an attribute named after its (1-based)
+/// position, e.g., `_1`, and a `MISSING` argument has no attributes.
+#[derive(Debug)]
+pub(crate) struct EvalFnTupleUnion {
+    pub(crate) args: Vec<Box<dyn EvalExpr>>,
+}
+
+impl EvalExpr for EvalFnTupleUnion {
+    fn evaluate<'a>(&'a self, bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
+        let mut tuple = Tuple::new();
+        for (i, arg) in self.args.iter().enumerate() {
+            match arg.evaluate(bindings, ctx).into_owned() {
+                Value::Tuple(t) => {
+                    for (attr, v) in t.into_pairs() {
+                        tuple.insert(&attr, v);
+                    }
+                }
+                Missing => {}
+                v => tuple.insert(&format!("_{}", i + 1), v),
+            }
+        }
+        Cow::Owned(Value::from(tuple))
+    }
+}
+
+/// Represents an `ELEMENT_AT` function, e.g. `element_at([1, 2, 3], 0)`, which returns the element
+/// of a list at a (zero-based) index or `MISSING` if the index is out of range.
+#[derive(Debug)]
+pub(crate) struct EvalFnElementAt {
+    pub(crate) value: Box<dyn EvalExpr>,
+    pub(crate) index: Box<dyn EvalExpr>,
+}
+
+impl EvalExpr for EvalFnElementAt {
+    #[inline]
+    fn evaluate<'a>(&'a self, bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
+        let value = self.value.evaluate(bindings, ctx);
+        let index = self.index.evaluate(bindings, ctx);
+        let result = match (value.borrow(), index.borrow()) {
+            (Missing, _) | (_, Missing) => Missing,
+            (Null, _) | (_, Null) => Null,
+            (Value::List(l), Value::Integer(idx)) if *idx >= 0 => {
+                l.get(*idx).cloned().unwrap_or(Missing)
+            }
+            _ => Missing,
+        };
+        Cow::Owned(result)
+    }
+}
+
+/// Represents a `UTCNOW` function, e.g. `utcnow()`, which returns the current time of the
+/// evaluation context's [`Clock`](crate::eval::sources::Clock).
+#[derive(Debug)]
+pub(crate) struct EvalFnUtcNow {}
+
+impl EvalExpr for EvalFnUtcNow {
+    #[inline]
+    fn evaluate<'a>(&'a self, _bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
+        Cow::Owned(Value::from(ctx.clock().now()))
+    }
+}
+
 /// Represents a year `EXTRACT` function, e.g. `extract(YEAR FROM t)`.
 #[derive(Debug)]
 pub(crate) struct EvalFnExtractYear {
@@ -1438,11 +1639,38 @@ impl EvalExpr for EvalFnCollSum {
     }
 }
 
+/// Represents a call to a scalar function of the catalog
+#[derive(Debug)]
+pub(crate) struct EvalFnScalarExpr {
+    pub(crate) args: Vec<Box<dyn EvalExpr>>,
+    pub(crate) expr: Box<dyn ScalarFnExpr>,
+}
+
+impl EvalExpr for EvalFnScalarExpr {
+    #[inline]
+    fn evaluate<'a>(&'a self, bindings: &'a Tuple, ctx: &'a dyn EvalContext) -> Cow<'a, Value> {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.evaluate(bindings, ctx))
+            .collect_vec();
+        match self.expr.evaluate(&args) {
+            Ok(value) => Cow::Owned(value),
+            Err(err) => {
+                ctx.add_error(EvaluationError::FunctionFailed(err.to_string()));
+                Cow::Owned(Missing)
+            }
+        }
+    }
+}
+
 /// Represents a Base Table Expr
 #[derive(Debug)]
 pub(crate) struct EvalFnBaseTableExpr {
     pub(crate) args: Vec<Box<dyn EvalExpr>>,
     pub(crate) expr: Box<dyn BaseTableExpr>,
+    /// The hint of how many of the table's values are consumed.
+    pub(crate) limit: ScanLimit,
 }
 
 impl EvalExpr for EvalFnBaseTableExpr {
@@ -1453,10 +1681,15 @@ impl EvalExpr for EvalFnBaseTableExpr {
             .iter()
             .map(|arg| arg.evaluate(bindings, ctx))
             .collect_vec();
-        let results = self.expr.evaluate(&args);
+        let results = self.expr.evaluate_limited(&args, self.limit);
         let result = match results {
             Ok(it) => {
-                let bag: Result<Bag, _> = it.collect();
+                let bag: Result<Bag, _> = match self.limit.max_values() {
+                    Some(max) => it
+                        .take(usize::try_from(max).unwrap_or(usize::MAX))
+                        .collect(),
+                    None => it.collect(),
+                };
                 match bag {
                     Ok(b) => Value::from(b),
                     Err(_) => {
@@ -1,5 +1,60 @@
 // TODO docs
 
+use crate::eval::expr::RE_SIZE_LIMIT;
+use regex::{Regex, RegexBuilder};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The maximum number of regexes a [`PatternCache`] holds; it is emptied when full, as patterns
+/// computed from data (e.g., `s LIKE t.pattern`) may be unbounded in number.
+const PATTERN_CACHE_CAPACITY: usize = 1024;
+
+/// A cache of the regexes compiled from `LIKE` patterns, keyed by the pattern text and escape
+/// character. Clones of a cache share its regexes, e.g., between the plans compiled by
+/// [`EvaluatorPlanner`]s sharing it, so that compiling a statement again or evaluating a pattern
+/// computed per row does not recompile the regex.
+///
+/// [`EvaluatorPlanner`]: crate::plan::EvaluatorPlanner
+#[derive(Debug, Clone, Default)]
+pub struct PatternCache(Rc<RefCell<HashMap<PatternKey, Regex>>>);
+
+/// A `LIKE` pattern along with its escape character, if any.
+type PatternKey = (String, Option<char>);
+
+impl PatternCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of regexes in the cache.
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    /// The regex matching the `LIKE` pattern `pattern` with escape character `escape`, compiled
+    /// unless it is in the cache.
+    pub(crate) fn like(&self, pattern: &str, escape: Option<char>) -> Result<Regex, regex::Error> {
+        let key = (pattern.to_string(), escape);
+        if let Some(regex) = self.0.borrow().get(&key) {
+            return Ok(regex.clone());
+        }
+        let regex = RegexBuilder::new(&like_to_re_pattern(pattern, escape))
+            .size_limit(RE_SIZE_LIMIT)
+            .build()?;
+        let mut cache = self.0.borrow_mut();
+        if cache.len() >= PATTERN_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(key, regex.clone());
+        Ok(regex)
+    }
+}
+
 // TODO consider how to use the appropriate mechanisms to prevent exhaustion of
 //      resources by query-written regexes
 //      See https://docs.rs/regex/latest/regex/#untrusted-input
@@ -146,4 +201,20 @@ mod tests {
         let re = Regex::new(&pat).unwrap();
         assert!(re.is_match("abc"));
     }
+
+    #[test]
+    fn pattern_cache() {
+        let cache = PatternCache::new();
+        let shared = cache.clone();
+        let regex = cache.like("foo%", Some('\\')).expect("regex");
+        assert!(regex.is_match("foobar"));
+        assert!(shared.like("foo%", Some('\\')).is_ok());
+        assert!(shared.like("foo%", None).is_ok());
+        assert_eq!(cache.len(), 2);
+
+        for i in 0..PATTERN_CACHE_CAPACITY {
+            assert!(cache.like(&format!("{i}%"), None).is_ok());
+        }
+        assert!(cache.len() <= PATTERN_CACHE_CAPACITY);
+    }
 }
@@ -1,5 +1,7 @@
-use itertools::Itertools;
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use std::fmt::Debug;
 
@@ -8,25 +10,86 @@ use petgraph::dot::{Config, Dot};
 use petgraph::prelude::StableGraph;
 use petgraph::{Directed, Outgoing};
 
-use partiql_value::Value;
+use partiql_logical::Parameter;
+use partiql_value::{BindingsName, Tuple, Value};
 
+use crate::admission::AdmissionCheck;
 use crate::env::basic::MapBindings;
 use crate::env::Bindings;
 
 use petgraph::graph::NodeIndex;
 
 use crate::error::{EvalErr, EvaluationError};
-use petgraph::visit::EdgeRef;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
 
-use crate::eval::evaluable::{EvalType, Evaluable};
+use crate::eval::evaluable::{EvalType, Evaluable, MaterializedSubQuery};
+use crate::eval::sources::{Clock, Rng, SeededRng, SystemClock, SystemRng};
 
 pub mod evaluable;
 pub mod expr;
+pub mod sort;
+pub mod sources;
 
 /// Represents a PartiQL evaluation query plan which is a plan that can be evaluated to produce
-/// a result. The plan uses a directed `petgraph::StableGraph`.
+/// a result. The plan uses a directed `petgraph::StableGraph`, which is private such that the
+/// plan's [`Schedule`] remains that of its graph; see [`EvalPlan::operators`] and
+/// [`EvalPlan::flows`].
 #[derive(Debug)]
-pub struct EvalPlan(pub StableGraph<Box<dyn Evaluable>, u8, Directed>);
+pub struct EvalPlan(
+    pub(crate) StableGraph<Box<dyn Evaluable>, u8, Directed>,
+    pub(crate) Option<AdmissionCheck>,
+    pub(crate) Vec<Rc<MaterializedSubQuery>>,
+    pub(crate) Option<Schedule>,
+    /// The renderings of the physical operators the plan's operators were compiled from, by id.
+    pub(crate) HashMap<usize, String>,
+);
+
+/// The order in which the operators of an [`EvalPlan`] are evaluated along with the flows of their
+/// outputs, resolved from the plan's graph once rather than on each execution; this matters most
+/// for subqueries, whose plans are executed once per row of their enclosing query.
+#[derive(Debug)]
+pub(crate) struct Schedule {
+    /// The graph-managed operators, in topological order.
+    steps: Vec<Step>,
+}
+
+#[derive(Debug)]
+struct Step {
+    op: NodeIndex,
+    /// The `(branch number, operator)` pairs the output of `op` flows to.
+    destinations: Vec<(u8, NodeIndex)>,
+}
+
+impl Schedule {
+    pub(crate) fn new(
+        graph: &StableGraph<Box<dyn Evaluable>, u8, Directed>,
+    ) -> Result<Self, EvalErr> {
+        // We are only interested in DAGs that can be used as execution plans, which leads to the
+        // following definition.
+        // A DAG is a directed, cycle-free graph G = (V, E) with a denoted root node v0 ∈ V such
+        // that all v ∈ V \{v0} are reachable from v0. Note that this is the definition of trees
+        // without the condition |E| = |V | − 1. Hence, all trees are DAGs.
+        // Reference: https://link.springer.com/article/10.1007/s00450-009-0061-0
+        let ops = toposort(graph, None).map_err(|e| EvalErr {
+            errors: vec![EvaluationError::InvalidEvaluationPlan(format!(
+                "Malformed evaluation plan detected: {e:?}"
+            ))],
+        })?;
+
+        let steps = ops
+            .into_iter()
+            .filter(|idx| is_graph_managed(graph, *idx))
+            .map(|op| Step {
+                op,
+                destinations: graph
+                    .edges_directed(op, Outgoing)
+                    .map(|e| (*e.weight(), e.target()))
+                    .collect(),
+            })
+            .collect();
+        Ok(Schedule { steps })
+    }
+}
 
 impl Default for EvalPlan {
     fn default() -> Self {
@@ -44,80 +107,145 @@ fn err_illegal_state(msg: impl AsRef<str>) -> EvalErr {
 impl EvalPlan {
     /// Creates a new evaluation plan.
     fn new() -> Self {
-        EvalPlan(StableGraph::<Box<dyn Evaluable>, u8, Directed>::new())
-    }
-
-    #[inline]
-    fn plan_graph(&mut self) -> &mut StableGraph<Box<dyn Evaluable>, u8> {
-        &mut self.0
+        EvalPlan(
+            StableGraph::<Box<dyn Evaluable>, u8, Directed>::new(),
+            None,
+            vec![],
+            None,
+            HashMap::new(),
+        )
     }
 
-    #[inline]
-    fn get_node(&mut self, idx: NodeIndex) -> Result<&mut Box<dyn Evaluable>, EvalErr> {
-        self.plan_graph()
-            .node_weight_mut(idx)
-            .ok_or_else(|| err_illegal_state("Error in retrieving node"))
+    /// Resolves the plan's [`Schedule`], unless it has been already.
+    fn schedule(&mut self) -> Result<(), EvalErr> {
+        if self.3.is_none() {
+            self.3 = Some(Schedule::new(&self.0)?);
+        }
+        Ok(())
     }
 
     /// Executes the plan while mutating its state by changing the inputs and outputs of plan
     /// operators.
+    ///
+    /// If the plan was compiled with an [`AdmissionControl`](crate::admission::AdmissionControl),
+    /// it is consulted before any operator is evaluated.
     pub fn execute_mut(&mut self, bindings: MapBindings<Value>) -> Result<Evaluated, EvalErr> {
-        let ctx: Box<dyn EvalContext> = Box::new(BasicContext::new(bindings));
-        // We are only interested in DAGs that can be used as execution plans, which leads to the
-        // following definition.
-        // A DAG is a directed, cycle-free graph G = (V, E) with a denoted root node v0 ∈ V such
-        // that all v ∈ V \{v0} are reachable from v0. Note that this is the definition of trees
-        // without the condition |E| = |V | − 1. Hence, all trees are DAGs.
-        // Reference: https://link.springer.com/article/10.1007/s00450-009-0061-0
-        let ops = toposort(&self.0, None).map_err(|e| EvalErr {
-            errors: vec![EvaluationError::InvalidEvaluationPlan(format!(
-                "Malformed evaluation plan detected: {e:?}"
-            ))],
-        })?;
+        self.execute_mut_in(BasicContext::new(bindings))
+    }
+
+    /// Executes the plan as [`EvalPlan::execute_mut`] does, in the context `ctx`, e.g., one with
+    /// an injected [`Clock`] or [`Rng`]; see [`sources`].
+    pub fn execute_mut_in(&mut self, ctx: BasicContext) -> Result<Evaluated, EvalErr> {
+        if let Some(admission) = &self.1 {
+            admission.check(ctx.bindings())?;
+        }
+        self.reset_materialized();
+        self.execute_in(&ctx)
+    }
 
+    /// Executes the plan as [`EvalPlan::execute_mut`] does, additionally collecting runtime
+    /// statistics of each operator evaluated.
+    pub fn execute_mut_with_stats(
+        &mut self,
+        bindings: MapBindings<Value>,
+    ) -> (Result<Evaluated, EvalErr>, EvalStats) {
+        if let Some(admission) = &self.1 {
+            if let Err(err) = admission.check(&bindings) {
+                return (Err(err), EvalStats::default());
+            }
+        }
+        self.reset_materialized();
+        let ctx = BasicContext::new(bindings);
+        let mut stats = EvalStats::default();
+        let result = self.execute_collecting(&ctx, Some(&mut stats), None);
+        (result, stats)
+    }
+
+    /// Executes the plan as [`EvalPlan::execute_mut_in`] does, calling `on_progress` each time an
+    /// operator has been evaluated, e.g., to render a progress bar or to detect a stalled query.
+    pub fn execute_mut_with_progress(
+        &mut self,
+        ctx: BasicContext,
+        mut on_progress: impl FnMut(&Progress<'_>),
+    ) -> Result<Evaluated, EvalErr> {
+        if let Some(admission) = &self.1 {
+            admission.check(ctx.bindings())?;
+        }
+        self.reset_materialized();
+        let mut stats = EvalStats::default();
+        self.execute_collecting(&ctx, Some(&mut stats), Some(&mut on_progress))
+    }
+
+    /// Discards the results of the plan's materialized subqueries computed by a previous
+    /// execution.
+    fn reset_materialized(&self) {
+        self.2.iter().for_each(|subquery| subquery.reset());
+    }
+
+    /// Executes the plan in the context `ctx`, e.g., that of an enclosing plan for subqueries.
+    pub(crate) fn execute_in(&mut self, ctx: &dyn EvalContext) -> Result<Evaluated, EvalErr> {
+        self.execute_collecting(ctx, None, None)
+    }
+
+    fn execute_collecting(
+        &mut self,
+        ctx: &dyn EvalContext,
+        mut stats: Option<&mut EvalStats>,
+        mut on_progress: Option<&mut dyn FnMut(&Progress<'_>)>,
+    ) -> Result<Evaluated, EvalErr> {
+        let execution_start = Instant::now();
+        self.schedule()?;
+        let EvalPlan(graph, _, _, Some(schedule), _) = self else {
+            return Err(err_illegal_state("Error in retrieving schedule"));
+        };
+
+        let total_operators = schedule.steps.len();
         let mut result = None;
-        for idx in ops.into_iter() {
-            let destinations: Vec<(usize, (u8, NodeIndex))> = self
-                .plan_graph()
-                .edges_directed(idx, Outgoing)
-                .map(|e| (*e.weight(), e.target()))
-                .enumerate()
-                .collect_vec();
-
-            // Some evaluables (i.e., `JOIN`) manage their own inputs
-            let graph_managed = destinations.is_empty()
-                || destinations.iter().any(|(_, (_, dest_idx))| {
-                    matches!(
-                        self.get_node(*dest_idx).map(|d| d.eval_type()),
-                        Ok(EvalType::GraphManaged)
-                    )
+        for Step { op, destinations } in &schedule.steps {
+            let src = get_node(graph, *op)?;
+            let start = Instant::now();
+            result = Some(src.evaluate(ctx));
+            if let Some(stats) = stats.as_deref_mut() {
+                stats.operators.push(OperatorStats {
+                    id: op.index(),
+                    operator: src.name(),
+                    elapsed: start.elapsed(),
+                    rows: result.as_ref().and_then(cardinality),
                 });
-            if graph_managed {
-                let src = self.get_node(idx)?;
-                result = Some(src.evaluate(&*ctx));
-
-                // return on first evaluation error
-                if ctx.has_errors() {
-                    return Err(EvalErr {
-                        errors: ctx.errors(),
+                stats.elapsed = execution_start.elapsed();
+                if let Some(on_progress) = on_progress.as_mut() {
+                    on_progress(&Progress {
+                        elapsed: stats.elapsed,
+                        total_operators,
+                        operators: &stats.operators,
                     });
                 }
+            }
 
-                let num_destinations = destinations.len();
-                for (i, (branch_num, dst_id)) in destinations {
-                    let res = if i == num_destinations - 1 {
-                        result.take()
-                    } else {
-                        result.clone()
-                    };
-
-                    let res =
-                        res.ok_or_else(|| err_illegal_state("Error in retrieving source value"))?;
-                    self.get_node(dst_id)?.update_input(res, branch_num, &*ctx);
+            // return on first evaluation error
+            if ctx.has_errors() {
+                return Err(EvalErr {
+                    errors: ctx.errors(),
+                });
+            }
+
+            if let Some(((last_branch, last_dst), rest)) = destinations.split_last() {
+                for (branch_num, dst_id) in rest {
+                    let res = result
+                        .clone()
+                        .ok_or_else(|| err_illegal_state("Error in retrieving source value"))?;
+                    get_node(graph, *dst_id)?.update_input(res, *branch_num, ctx);
                 }
+                let res = result
+                    .take()
+                    .ok_or_else(|| err_illegal_state("Error in retrieving source value"))?;
+                get_node(graph, *last_dst)?.update_input(res, *last_branch, ctx);
             }
         }
 
+        if let Some(stats) = stats {
+            stats.elapsed = execution_start.elapsed();
+        }
         let result = result.ok_or_else(|| err_illegal_state("Error in retrieving eval output"))?;
         Ok(Evaluated { result })
     }
@@ -125,6 +253,103 @@ impl EvalPlan {
     pub fn to_dot_graph(&self) -> String {
         format!("{:?}", Dot::with_config(&self.0, &[Config::EdgeNoLabel]))
     }
+
+    /// Returns the operators of the plan along with their ids.
+    pub fn operators(&self) -> impl Iterator<Item = (usize, &dyn Evaluable)> + '_ {
+        self.0
+            .node_indices()
+            .map(|idx| (idx.index(), self.0[idx].as_ref()))
+    }
+
+    /// Returns a rendering of the physical operator that the operator with id `id` was compiled
+    /// from, e.g., `Filter(Gt(x.a, 1))`.
+    pub fn operator_detail(&self, id: usize) -> Option<&str> {
+        self.4.get(&id).map(String::as_str)
+    }
+
+    /// Returns the flows of the plan as `(source id, destination id, branch number)`.
+    pub fn flows(&self) -> impl Iterator<Item = (usize, usize, u8)> + '_ {
+        (&self.0)
+            .edge_references()
+            .map(|e| (e.source().index(), e.target().index(), *e.weight()))
+    }
+}
+
+#[inline]
+fn get_node(
+    graph: &mut StableGraph<Box<dyn Evaluable>, u8, Directed>,
+    idx: NodeIndex,
+) -> Result<&mut Box<dyn Evaluable>, EvalErr> {
+    graph
+        .node_weight_mut(idx)
+        .ok_or_else(|| err_illegal_state("Error in retrieving node"))
+}
+
+/// Whether the operator `idx` is evaluated by the plan itself, as opposed to by the operator its
+/// output flows to; some evaluables (i.e., `JOIN`) manage their own inputs, and so those of the
+/// operators flowing to them, e.g., the filters of their operands.
+fn is_graph_managed(graph: &StableGraph<Box<dyn Evaluable>, u8, Directed>, idx: NodeIndex) -> bool {
+    let mut destinations = graph
+        .edges_directed(idx, Outgoing)
+        .map(|e| e.target())
+        .peekable();
+    destinations.peek().is_none()
+        || destinations.any(|dst| {
+            matches!(
+                graph.node_weight(dst).map(|d| d.eval_type()),
+                Some(EvalType::GraphManaged)
+            ) && is_graph_managed(graph, dst)
+        })
+}
+
+/// The number of values in `value` if it is a collection.
+fn cardinality(value: &Value) -> Option<usize> {
+    match value {
+        Value::Bag(bag) => Some(bag.len()),
+        Value::List(list) => Some(list.len()),
+        _ => None,
+    }
+}
+
+/// Runtime statistics of an evaluation of an [`EvalPlan`].
+#[derive(Debug, Clone, Default)]
+pub struct EvalStats {
+    /// The time taken by the whole evaluation.
+    pub elapsed: Duration,
+    /// The statistics of each operator, in the order the operators were evaluated.
+    pub operators: Vec<OperatorStats>,
+}
+
+/// Runtime statistics of a single operator of an [`EvalPlan`].
+#[derive(Debug, Clone)]
+pub struct OperatorStats {
+    /// The id of the operator within the plan, as given by [`EvalPlan::operators`].
+    pub id: usize,
+    /// The name of the operator, as given by [`Evaluable::name`].
+    pub operator: &'static str,
+    /// The time taken to evaluate the operator.
+    pub elapsed: Duration,
+    /// The number of values output by the operator, if it output a collection.
+    pub rows: Option<usize>,
+}
+
+/// The progress of an evaluation of an [`EvalPlan`]; see [`EvalPlan::execute_mut_with_progress`].
+#[derive(Debug, Clone)]
+pub struct Progress<'a> {
+    /// The time elapsed since the evaluation started.
+    pub elapsed: Duration,
+    /// The number of operators the evaluation will evaluate, excluding those evaluated by other
+    /// operators (e.g., the inputs of a `JOIN`).
+    pub total_operators: usize,
+    /// The statistics of each operator evaluated so far, in the order they were evaluated.
+    pub operators: &'a [OperatorStats],
+}
+
+impl Progress<'_> {
+    /// The total number of values output by the operators evaluated so far.
+    pub fn rows(&self) -> usize {
+        self.operators.iter().filter_map(|op| op.rows).sum()
+    }
 }
 
 /// Represents an evaluation result that contains evaluated result or the error.
@@ -133,7 +358,6 @@ pub type EvalResult = Result<Evaluated, EvalErr>;
 /// Represents result of evaluation as an evaluated entity.
 #[non_exhaustive]
 #[derive(Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Evaluated {
     pub result: Value,
 }
@@ -144,12 +368,74 @@ pub trait EvalContext {
     fn add_error(&self, error: EvaluationError);
     fn has_errors(&self) -> bool;
     fn errors(&self) -> Vec<EvaluationError>;
+    /// The source of the current time for nondeterministic built-ins, e.g., `UTCNOW()`; the
+    /// system's time unless overridden.
+    fn clock(&self) -> &dyn Clock {
+        &SystemClock
+    }
+    /// The source of random numbers for nondeterministic built-ins; a [`SystemRng`] unless
+    /// overridden.
+    fn rng(&self) -> &dyn Rng {
+        &SystemRng
+    }
+    /// The values bound to the parameters of the query, e.g., `?` or `:name`; none unless
+    /// overridden.
+    fn parameters(&self) -> &Parameters {
+        static NONE: Parameters = Parameters::NONE;
+        &NONE
+    }
+}
+
+/// The values bound to the parameters of a query; see [`BasicContext::with_parameters`].
+#[derive(Debug, Clone, Default)]
+pub struct Parameters {
+    positional: Vec<Value>,
+    named: Tuple,
+}
+
+impl Parameters {
+    /// Binds no values to the query's parameters.
+    pub const NONE: Parameters = Parameters {
+        positional: Vec::new(),
+        named: Tuple::new(),
+    };
+
+    /// Creates parameters binding `values` to the query's positional parameters, in order.
+    pub fn positional(values: impl IntoIterator<Item = Value>) -> Self {
+        Parameters {
+            positional: values.into_iter().collect(),
+            named: Tuple::new(),
+        }
+    }
+
+    /// Binds `value` to the named parameter `name`.
+    pub fn with_named(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.named.insert(name, value.into());
+        self
+    }
+
+    /// The value bound to `parameter`, if any.
+    pub fn get(&self, parameter: &Parameter) -> Option<&Value> {
+        match parameter {
+            Parameter::Positional(position) => self.positional.get(*position),
+            Parameter::Named(name) => self.named.get(name),
+        }
+    }
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct BasicContext {
     bindings: MapBindings<Value>,
     errors: RefCell<Vec<EvaluationError>>,
+    clock: Box<dyn Clock>,
+    rng: Box<dyn Rng>,
+    parameters: Parameters,
+}
+
+impl Default for BasicContext {
+    fn default() -> Self {
+        BasicContext::new(MapBindings::default())
+    }
 }
 
 impl BasicContext {
@@ -157,8 +443,30 @@ impl BasicContext {
         BasicContext {
             bindings,
             errors: RefCell::new(vec![]),
+            clock: Box::new(SystemClock),
+            rng: Box::new(SeededRng::from_system_time()),
+            parameters: Parameters::default(),
         }
     }
+
+    /// Binds the values of the query's parameters.
+    pub fn with_parameters(mut self, parameters: Parameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Replaces the context's [`Clock`], which defaults to a [`SystemClock`].
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Replaces the context's [`Rng`], which defaults to a [`SeededRng`] seeded by the system's
+    /// time.
+    pub fn with_rng(mut self, rng: impl Rng + 'static) -> Self {
+        self.rng = Box::new(rng);
+        self
+    }
 }
 
 impl EvalContext for BasicContext {
@@ -177,4 +485,81 @@ impl EvalContext for BasicContext {
     fn errors(&self) -> Vec<EvaluationError> {
         self.errors.take()
     }
+
+    fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    fn rng(&self) -> &dyn Rng {
+        self.rng.as_ref()
+    }
+
+    fn parameters(&self) -> &Parameters {
+        &self.parameters
+    }
+}
+
+/// An evaluation context for a plan nested within another (e.g., a subquery), in which names are
+/// looked up first in the `local` bindings and then in the bindings of the enclosing context.
+pub(crate) struct NestedContext<'a> {
+    local: &'a Tuple,
+    parent: &'a dyn EvalContext,
+    errors: RefCell<Vec<EvaluationError>>,
+}
+
+impl<'a> NestedContext<'a> {
+    pub(crate) fn new(local: &'a Tuple, parent: &'a dyn EvalContext) -> Self {
+        NestedContext {
+            local,
+            parent,
+            errors: RefCell::new(vec![]),
+        }
+    }
+}
+
+impl Debug for NestedContext<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NestedContext")
+            .field("local", &self.local)
+            .field("errors", &self.errors)
+            .finish()
+    }
+}
+
+impl Bindings<Value> for NestedContext<'_> {
+    fn get(&self, name: &BindingsName) -> Option<&Value> {
+        self.local
+            .get(name)
+            .or_else(|| self.parent.bindings().get(name))
+    }
+}
+
+impl EvalContext for NestedContext<'_> {
+    fn bindings(&self) -> &dyn Bindings<Value> {
+        self
+    }
+
+    fn add_error(&self, error: EvaluationError) {
+        self.errors.borrow_mut().push(error)
+    }
+
+    fn has_errors(&self) -> bool {
+        !self.errors.borrow().is_empty()
+    }
+
+    fn errors(&self) -> Vec<EvaluationError> {
+        self.errors.take()
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.parent.clock()
+    }
+
+    fn rng(&self) -> &dyn Rng {
+        self.parent.rng()
+    }
+
+    fn parameters(&self) -> &Parameters {
+        self.parent.parameters()
+    }
 }
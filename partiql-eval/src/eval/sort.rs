@@ -0,0 +1,483 @@
+//! An external (i.e., disk-spilling) sort of [`Value`]s.
+//!
+//! An [`ExternalSorter`] buffers the values pushed to it in memory until
+//! [`run_size`](ExternalSortConfig::run_size) values have been buffered, at which point they are
+//! sorted and written to a file in the configured [`temp_dir`](ExternalSortConfig::temp_dir) as a
+//! sorted *run*. Each run is a checkpoint of the values pushed so far which no longer need to be
+//! held in memory. When all values have been pushed, [`ExternalSorter::finish`] merges the runs
+//! (and any values still buffered) into a single sorted sequence, merging at most
+//! [`max_fan_in`](ExternalSortConfig::max_fan_in) runs at a time, so as many files are open at
+//! once: while there are more runs, passes merge each group of consecutive runs into a single run.
+//!
+//! Runs are encoded as Ion, so are only spilled to disk with the `ion` feature (enabled by
+//! default); without it, runs are held in memory.
+//!
+//! The sort is stable: values which compare as equal are output in the order they were pushed.
+//!
+//! It sorts the input of `ORDER BY`, of `DISTINCT` over bags, and the operands of
+//! [sort-merge joins](crate::physical::PhysicalOp::SortMergeJoin), as configured by
+//! [`EvaluatorPlanner::with_sort_config`](crate::plan::EvaluatorPlanner::with_sort_config).
+//!
+//! ```
+//! use partiql_eval::eval::sort::{ExternalSortConfig, ExternalSorter};
+//! use partiql_value::Value;
+//!
+//! let config = ExternalSortConfig::default().with_run_size(2);
+//! let mut sorter = ExternalSorter::new(&config, |l: &Value, r: &Value| l.cmp(r));
+//! for i in [5, 3, 4, 1, 2] {
+//!     sorter.push(Value::from(i)).expect("push");
+//! }
+//! assert_eq!(sorter.runs(), 2);
+//!
+//! let sorted: Result<Vec<_>, _> = sorter.finish().expect("finish").collect();
+//! assert_eq!(sorted.expect("sorted"), (1..=5).map(Value::from).collect::<Vec<_>>());
+//! ```
+
+#[cfg(feature = "ion")]
+use ion_rs::{BinaryWriterBuilder, IonWriter, ReaderBuilder};
+#[cfg(feature = "ion")]
+use partiql_extension_ion::decode::{IonDecoderBuilder, IonDecoderConfig, IonValueIter};
+#[cfg(feature = "ion")]
+use partiql_extension_ion::encode::{IonEncoderBuilder, IonEncoderConfig};
+#[cfg(feature = "ion")]
+use partiql_extension_ion::Encoding;
+use partiql_value::Value;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+#[cfg(feature = "ion")]
+use std::fs::File;
+#[cfg(feature = "ion")]
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::rc::Rc;
+#[cfg(feature = "ion")]
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use thiserror::Error;
+
+type ValueCmp<'a> = Rc<dyn Fn(&Value, &Value) -> Ordering + 'a>;
+
+/// Configuration of an [`ExternalSorter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalSortConfig {
+    /// The maximum number of values held in memory before they are spilled to disk as a run.
+    pub run_size: usize,
+    /// The directory in which runs are written.
+    pub temp_dir: PathBuf,
+    /// The maximum number of runs merged at once, i.e., of the files read at once.
+    pub max_fan_in: usize,
+}
+
+impl Default for ExternalSortConfig {
+    fn default() -> Self {
+        ExternalSortConfig {
+            run_size: 1 << 16,
+            temp_dir: std::env::temp_dir(),
+            max_fan_in: 64,
+        }
+    }
+}
+
+impl ExternalSortConfig {
+    /// Set the maximum number of values held in memory to `run_size`.
+    pub fn with_run_size(mut self, run_size: usize) -> Self {
+        self.run_size = run_size.max(1);
+        self
+    }
+
+    /// Set the directory in which runs are written to `temp_dir`.
+    pub fn with_temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = temp_dir.into();
+        self
+    }
+
+    /// Set the maximum number of runs merged at once to `max_fan_in`, which is at least 2.
+    pub fn with_max_fan_in(mut self, max_fan_in: usize) -> Self {
+        self.max_fan_in = max_fan_in.max(2);
+        self
+    }
+}
+
+/// An error in writing or reading the runs of an [`ExternalSorter`].
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ExternalSortError {
+    #[error("External sort I/O error: `{0}`")]
+    Io(#[from] std::io::Error),
+    #[error("External sort encoding error: `{0}`")]
+    Encode(String),
+    #[error("External sort decoding error: `{0}`")]
+    Decode(String),
+}
+
+/// Sorts [`Value`]s, spilling them to disk as sorted runs when they exceed the configured
+/// [`run_size`](ExternalSortConfig::run_size); see the [module documentation](self).
+pub struct ExternalSorter<'a> {
+    config: ExternalSortConfig,
+    cmp: ValueCmp<'a>,
+    buffer: Vec<Value>,
+    runs: Vec<Run>,
+}
+
+impl Debug for ExternalSorter<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExternalSorter")
+            .field("config", &self.config)
+            .field("buffered", &self.buffer.len())
+            .field("runs", &self.runs.len())
+            .finish()
+    }
+}
+
+impl<'a> ExternalSorter<'a> {
+    /// Creates a sorter ordering values by `cmp`.
+    pub fn new(config: &ExternalSortConfig, cmp: impl Fn(&Value, &Value) -> Ordering + 'a) -> Self {
+        ExternalSorter {
+            config: config.clone(),
+            cmp: Rc::new(cmp),
+            buffer: vec![],
+            runs: vec![],
+        }
+    }
+
+    /// Adds `value` to the values to sort, spilling the buffered values to disk if the buffer is
+    /// full.
+    pub fn push(&mut self, value: Value) -> Result<(), ExternalSortError> {
+        self.buffer.push(value);
+        if self.buffer.len() >= self.config.run_size {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    /// Sorts the buffered values and spills them to disk as a run.
+    pub fn checkpoint(&mut self) -> Result<(), ExternalSortError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let mut values = std::mem::take(&mut self.buffer);
+        values.sort_by(|l, r| (self.cmp)(l, r));
+        self.runs
+            .push(Run::write(&self.config, values.into_iter().map(Ok))?);
+        Ok(())
+    }
+
+    /// The number of runs spilled to disk so far.
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Merges all values pushed into a single sorted sequence.
+    pub fn finish(mut self) -> Result<SortedValues<'a>, ExternalSortError> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.sort_by(|l, r| (self.cmp)(l, r));
+
+        // Runs are merged in the order they were written, followed by the values still buffered,
+        // such that ties are broken in favor of values pushed earlier; so are the consecutive runs
+        // merged by each pass.
+        let fan_in = self.config.max_fan_in.max(2);
+        let mut runs = std::mem::take(&mut self.runs);
+        while runs.len() >= fan_in {
+            let mut merged = Vec::with_capacity(runs.len() / fan_in + 1);
+            let mut pending = runs.into_iter().peekable();
+            while pending.peek().is_some() {
+                let group: Vec<Run> = pending.by_ref().take(fan_in).collect();
+                let sources = group.into_iter().map(Run::read).collect::<Result<_, _>>()?;
+                let values = SortedValues::new(self.cmp.clone(), sources)?;
+                merged.push(Run::write(&self.config, values)?);
+            }
+            runs = merged;
+        }
+
+        let mut sources = runs
+            .into_iter()
+            .map(Run::read)
+            .collect::<Result<Vec<_>, _>>()?;
+        sources.push(Source::Memory(buffer.into_iter()));
+        SortedValues::new(self.cmp.clone(), sources)
+    }
+}
+
+/// The sorted values of an [`ExternalSorter`], produced by a k-way merge of its runs.
+pub struct SortedValues<'a> {
+    cmp: ValueCmp<'a>,
+    sources: Vec<Source>,
+    /// The next value of each of `sources`.
+    heads: Vec<Option<Value>>,
+}
+
+impl<'a> SortedValues<'a> {
+    fn new(cmp: ValueCmp<'a>, mut sources: Vec<Source>) -> Result<Self, ExternalSortError> {
+        let heads = sources
+            .iter_mut()
+            .map(|source| source.next().transpose())
+            .collect::<Result<_, _>>()?;
+        Ok(SortedValues {
+            cmp,
+            sources,
+            heads,
+        })
+    }
+}
+
+impl Iterator for SortedValues<'_> {
+    type Item = Result<Value, ExternalSortError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut min: Option<usize> = None;
+        for (idx, head) in self.heads.iter().enumerate() {
+            if let Some(value) = head {
+                let is_less = match min.and_then(|m| self.heads[m].as_ref()) {
+                    Some(min_value) => (self.cmp)(value, min_value) == Ordering::Less,
+                    None => true,
+                };
+                if is_less {
+                    min = Some(idx);
+                }
+            }
+        }
+
+        let idx = min?;
+        let value = self.heads[idx].take();
+        match self.sources[idx].next().transpose() {
+            Ok(next) => self.heads[idx] = next,
+            Err(err) => return Some(Err(err)),
+        }
+        value.map(Ok)
+    }
+}
+
+enum Source {
+    Memory(std::vec::IntoIter<Value>),
+    #[cfg(feature = "ion")]
+    Run(RunReader),
+}
+
+impl Source {
+    fn next(&mut self) -> Option<Result<Value, ExternalSortError>> {
+        match self {
+            Source::Memory(values) => values.next().map(Ok),
+            #[cfg(feature = "ion")]
+            Source::Run(run) => run
+                .values
+                .next()
+                .map(|res| res.map_err(|e| ExternalSortError::Decode(e.to_string()))),
+        }
+    }
+}
+
+/// Used to generate unique file names for runs.
+#[cfg(feature = "ion")]
+static NEXT_RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A sorted run spilled to disk; the file is removed when the run is dropped.
+#[cfg(feature = "ion")]
+#[derive(Debug)]
+struct Run {
+    path: PathBuf,
+}
+
+#[cfg(feature = "ion")]
+impl Run {
+    fn write(
+        config: &ExternalSortConfig,
+        values: impl IntoIterator<Item = Result<Value, ExternalSortError>>,
+    ) -> Result<Run, ExternalSortError> {
+        let id = NEXT_RUN_ID.fetch_add(1, AtomicOrdering::Relaxed);
+        let path = config
+            .temp_dir
+            .join(format!("partiql-sort-{}-{id}.ion", std::process::id()));
+        let run = Run { path };
+
+        let file = BufWriter::new(File::create(&run.path)?);
+        let mut writer = BinaryWriterBuilder::new()
+            .build(file)
+            .map_err(|e| ExternalSortError::Encode(e.to_string()))?;
+        let mut encoder = IonEncoderBuilder::new(
+            IonEncoderConfig::default().with_mode(Encoding::PartiqlEncodedAsIon),
+        )
+        .build(&mut writer)
+        .map_err(|e| ExternalSortError::Encode(e.to_string()))?;
+        for value in values {
+            encoder
+                .write_value(&value?)
+                .map_err(|e| ExternalSortError::Encode(e.to_string()))?;
+        }
+        drop(encoder);
+        writer
+            .flush()
+            .map_err(|e| ExternalSortError::Encode(e.to_string()))?;
+        Ok(run)
+    }
+
+    fn read(self) -> Result<Source, ExternalSortError> {
+        let file = BufReader::new(File::open(&self.path)?);
+        let reader = ReaderBuilder::new()
+            .build(file)
+            .map_err(|e| ExternalSortError::Decode(e.to_string()))?;
+        let values = IonDecoderBuilder::new(
+            IonDecoderConfig::default().with_mode(Encoding::PartiqlEncodedAsIon),
+        )
+        .build(reader)
+        .map_err(|e| ExternalSortError::Decode(e.to_string()))?;
+        Ok(Source::Run(RunReader { _run: self, values }))
+    }
+}
+
+#[cfg(feature = "ion")]
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A reader of the values of a [`Run`].
+#[cfg(feature = "ion")]
+struct RunReader {
+    // Held to remove the run's file once it has been read
+    _run: Run,
+    values: IonValueIter<'static>,
+}
+
+/// A sorted run, held in memory without the `ion` feature to encode it to disk.
+#[cfg(not(feature = "ion"))]
+#[derive(Debug)]
+struct Run {
+    values: Vec<Value>,
+}
+
+#[cfg(not(feature = "ion"))]
+impl Run {
+    fn write(
+        _config: &ExternalSortConfig,
+        values: impl IntoIterator<Item = Result<Value, ExternalSortError>>,
+    ) -> Result<Run, ExternalSortError> {
+        let values = values.into_iter().collect::<Result<_, _>>()?;
+        Ok(Run { values })
+    }
+
+    fn read(self) -> Result<Source, ExternalSortError> {
+        Ok(Source::Memory(self.values.into_iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use partiql_value::{bag, tuple, DateTime};
+
+    fn sort(values: Vec<Value>, run_size: usize) -> (Vec<Value>, usize) {
+        let config = ExternalSortConfig::default().with_run_size(run_size);
+        let mut sorter = ExternalSorter::new(&config, |l: &Value, r: &Value| l.cmp(r));
+        for value in values {
+            sorter.push(value).expect("push");
+        }
+        let runs = sorter.runs();
+        let sorted = sorter
+            .finish()
+            .expect("finish")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("merge");
+        (sorted, runs)
+    }
+
+    #[test]
+    fn merges_runs() {
+        let values: Vec<Value> = (0..100).map(|i| Value::from((i * 37) % 100)).collect();
+        let (sorted, runs) = sort(values, 7);
+        assert_eq!(runs, 14);
+        assert_eq!(sorted, (0..100).map(Value::from).collect::<Vec<_>>());
+
+        let (sorted, runs) = sort(vec![], 7);
+        assert_eq!(runs, 0);
+        assert!(sorted.is_empty());
+    }
+
+    #[test]
+    fn roundtrips_values() {
+        let values = vec![
+            Value::from(tuple![("a", 1), ("b", Value::Missing)]),
+            Value::from(bag![Value::Null, "str", 2.5]),
+            Value::from(DateTime::from_hms(12, 30, 0)),
+            Value::Missing,
+            Value::from(rust_decimal_macros::dec!(1.50)),
+        ];
+        let mut expected = values.clone();
+        expected.sort();
+        let (sorted, runs) = sort(values, 2);
+        assert_eq!(runs, 2);
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn stable() {
+        let config = ExternalSortConfig::default().with_run_size(2);
+        // sort tuples by their `k` attribute only
+        let key = |v: &Value| match v {
+            Value::Tuple(t) => t
+                .get(&partiql_value::BindingsName::CaseSensitive("k".into()))
+                .cloned(),
+            _ => None,
+        };
+        let mut sorter = ExternalSorter::new(&config, |l: &Value, r: &Value| key(l).cmp(&key(r)));
+        for (k, seq) in [(2, 0), (1, 1), (2, 2), (1, 3), (2, 4)] {
+            sorter
+                .push(Value::from(tuple![("k", k), ("seq", seq)]))
+                .expect("push");
+        }
+        let seqs: Vec<Value> = sorter
+            .finish()
+            .expect("finish")
+            .map(|v| match v.expect("value") {
+                Value::Tuple(t) => t
+                    .get(&partiql_value::BindingsName::CaseSensitive("seq".into()))
+                    .cloned()
+                    .expect("seq"),
+                _ => panic!("expected tuple"),
+            })
+            .collect();
+        assert_eq!(
+            seqs,
+            vec![1, 3, 0, 2, 4]
+                .into_iter()
+                .map(Value::from)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn bounded_fan_in() {
+        let config = ExternalSortConfig::default()
+            .with_run_size(3)
+            .with_max_fan_in(2);
+        let mut sorter = ExternalSorter::new(&config, |l: &Value, r: &Value| l.cmp(r));
+        for i in 0..100 {
+            sorter.push(Value::from((i * 37) % 100)).expect("push");
+        }
+        assert_eq!(sorter.runs(), 33);
+        let sorted = sorter
+            .finish()
+            .expect("finish")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("merge");
+        assert_eq!(sorted, (0..100).map(Value::from).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[cfg(feature = "ion")]
+    fn removes_runs() {
+        let dir = std::env::temp_dir().join(format!("partiql-sort-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let config = ExternalSortConfig::default()
+            .with_run_size(1)
+            .with_temp_dir(&dir);
+        let mut sorter = ExternalSorter::new(&config, |l: &Value, r: &Value| l.cmp(r));
+        for i in 0..3 {
+            sorter.push(Value::from(i)).expect("push");
+        }
+        assert_eq!(std::fs::read_dir(&dir).expect("read dir").count(), 3);
+        let sorted = sorter.finish().expect("finish").count();
+        assert_eq!(sorted, 3);
+        assert_eq!(std::fs::read_dir(&dir).expect("read dir").count(), 0);
+        std::fs::remove_dir(&dir).expect("remove temp dir");
+    }
+}
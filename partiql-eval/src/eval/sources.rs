@@ -0,0 +1,175 @@
+//! Sources of nondeterminism available to evaluation through its [`EvalContext`].
+//!
+//! Built-in functions whose results are not determined by their arguments (e.g., `UTCNOW()`)
+//! obtain the current time from the context's [`Clock`] and random numbers from its [`Rng`].
+//! Injecting a [`FixedClock`] and a [`SeededRng`] makes evaluation reproducible, e.g., in tests or
+//! when replaying a query to debug it.
+//!
+//! ```
+//! use partiql_eval::env::basic::MapBindings;
+//! use partiql_eval::eval::sources::{FixedClock, SeededRng};
+//! use partiql_eval::eval::BasicContext;
+//! use partiql_value::DateTime;
+//! use std::num::NonZeroU8;
+//!
+//! let now = DateTime::from_ymdhms_nano_offset_minutes(
+//!     2023, NonZeroU8::new(1).unwrap(), 1, 0, 0, 0, 0, Some(0),
+//! );
+//! let ctx = BasicContext::new(MapBindings::default())
+//!     .with_clock(FixedClock::new(now))
+//!     .with_rng(SeededRng::new(42));
+//! ```
+//!
+//! [`EvalContext`]: crate::eval::EvalContext
+
+use partiql_value::DateTime;
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of the current time.
+pub trait Clock: Debug {
+    /// The current time, as a timestamp with a UTC offset.
+    fn now(&self) -> DateTime;
+}
+
+/// A [`Clock`] reading the system's time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        DateTime::TimestampWithTz(time::OffsetDateTime::now_utc())
+    }
+}
+
+/// A [`Clock`] which is stopped at a given time.
+#[derive(Debug, Clone)]
+pub struct FixedClock {
+    now: DateTime,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime) -> Self {
+        FixedClock { now }
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime {
+        self.now.clone()
+    }
+}
+
+/// A source of pseudo-random numbers.
+pub trait Rng: Debug {
+    /// The next pseudo-random 64-bit integer.
+    fn next_u64(&self) -> u64;
+
+    /// The next pseudo-random number uniformly distributed in `[0, 1)`.
+    fn next_f64(&self) -> f64 {
+        // Use the upper 53 bits, i.e., the precision of an `f64`'s mantissa.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// A (non-cryptographic) [`Rng`] whose sequence of numbers is determined by its seed.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: Cell<u64>,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng {
+            state: Cell::new(seed),
+        }
+    }
+
+    /// Creates an [`Rng`] seeded by the system's time.
+    pub fn from_system_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        SeededRng::new(nanos)
+    }
+}
+
+impl Rng for SeededRng {
+    fn next_u64(&self) -> u64 {
+        // SplitMix64; see https://prng.di.unimi.it/splitmix64.c
+        let state = self.state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        self.state.set(state);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// An [`Rng`] drawing from a per-thread [`SeededRng`] seeded by the system's time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemRng;
+
+impl Rng for SystemRng {
+    fn next_u64(&self) -> u64 {
+        thread_local! {
+            static RNG: SeededRng = SeededRng::from_system_time();
+        }
+        RNG.with(Rng::next_u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_rng() {
+        let (a, b) = (SeededRng::new(7), SeededRng::new(7));
+        let a: Vec<_> = (0..8).map(|_| a.next_u64()).collect();
+        let b: Vec<_> = (0..8).map(|_| b.next_u64()).collect();
+        assert_eq!(a, b);
+        assert_ne!(a[0], a[1]);
+
+        let rng = SeededRng::new(7);
+        assert!((0..1000)
+            .map(|_| rng.next_f64())
+            .all(|f| (0.0..1.0).contains(&f)));
+    }
+
+    #[test]
+    fn default_sources() {
+        use crate::env::basic::MapBindings;
+        use crate::env::Bindings;
+        use crate::error::EvaluationError;
+        use crate::eval::EvalContext;
+        use partiql_logical::Parameter;
+        use partiql_value::{BindingsName, Value};
+
+        // A context implementing only the required methods of `EvalContext`
+        #[derive(Default)]
+        struct Context(MapBindings<Value>);
+
+        impl EvalContext for Context {
+            fn bindings(&self) -> &dyn Bindings<Value> {
+                &self.0
+            }
+            fn add_error(&self, _error: EvaluationError) {}
+            fn has_errors(&self) -> bool {
+                false
+            }
+            fn errors(&self) -> Vec<EvaluationError> {
+                vec![]
+            }
+        }
+
+        let ctx = Context::default();
+        assert!(ctx.clock().now() <= SystemClock.now());
+        assert_ne!(ctx.rng().next_u64(), ctx.rng().next_u64());
+        assert_eq!(ctx.parameters().get(&Parameter::Positional(0)), None);
+        let name = BindingsName::CaseInsensitive("p".to_string());
+        assert_eq!(ctx.parameters().get(&Parameter::Named(name)), None);
+    }
+}
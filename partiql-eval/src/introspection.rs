@@ -0,0 +1,163 @@
+//! Introspection of the engine through the `information_schema` global.
+//!
+//! An [`InformationSchema`] describes what is queryable: the tables bound in the evaluation's
+//! global bindings, the functions available to queries along with their signatures, and the
+//! settings of the [`EvaluatorPlanner`] compiling them. [Binding](InformationSchema::bind) it
+//! makes the description queryable as the `information_schema` global, e.g., by
+//! `SELECT VALUE f.name FROM information_schema.functions AS f`. Its attributes are:
+//!
+//! - `tables`: a bag of `{'name': ..., 'type': ...}` tuples, the `type` being the description of
+//!   the table's type declared in the catalog, if any
+//! - `functions`: a bag of `{'name': ..., 'aliases': [...], 'kind': ..., 'signatures': [...]}`
+//!   tuples, the `kind` being `'scalar'` or `'table'` and each signature being of the form
+//!   `name(_, _)`, in which named arguments are written as `name: _`
+//! - `settings`: a tuple of the planner's settings, e.g., `{'mode': 'permissive', ...}`
+//!
+//! ```
+//! use partiql_catalog::PartiqlCatalog;
+//! use partiql_eval::env::basic::MapBindings;
+//! use partiql_eval::introspection::InformationSchema;
+//! use partiql_eval::plan::{EvaluationMode, EvaluatorPlanner};
+//! use partiql_value::{bag, Value};
+//!
+//! let catalog = PartiqlCatalog::default();
+//! let planner = EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog);
+//!
+//! let mut bindings = MapBindings::default();
+//! bindings.insert("customers", Value::from(bag![1, 2, 3]));
+//! planner.information_schema().bind(&mut bindings);
+//! ```
+//!
+//! [`EvaluatorPlanner`]: crate::plan::EvaluatorPlanner
+
+use crate::env::basic::MapBindings;
+use partiql_catalog::call_defs::{CallDef, CallSpecArg};
+use partiql_catalog::{Catalog, FunctionEntryFunction};
+use partiql_value::{Bag, List, Tuple, Value};
+
+/// The name of the global an [`InformationSchema`] is bound to.
+pub const INFORMATION_SCHEMA: &str = "information_schema";
+
+/// A description of what is queryable; see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct InformationSchema<'c> {
+    catalog: &'c dyn Catalog,
+    /// The descriptions of the functions, along with their names.
+    functions: Vec<(&'static str, Value)>,
+    settings: Tuple,
+}
+
+impl<'c> InformationSchema<'c> {
+    /// Creates a description of the tables & functions of `catalog`.
+    pub fn new(catalog: &'c dyn Catalog) -> Self {
+        let functions = catalog
+            .functions()
+            .iter()
+            .filter_map(|entry| {
+                let kind = match entry.function() {
+                    FunctionEntryFunction::Table(_) => "table",
+                    FunctionEntryFunction::Scalar(_) => "scalar",
+                    FunctionEntryFunction::Aggregate() => return None,
+                };
+                Some(describe_function(entry.call_def(), kind))
+            })
+            .collect();
+        InformationSchema {
+            catalog,
+            functions,
+            settings: Tuple::new(),
+        }
+    }
+
+    /// Adds the scalar functions defined by `defs`, e.g., the built-in functions of the logical
+    /// planner.
+    pub fn with_scalar_functions<'a>(
+        mut self,
+        defs: impl IntoIterator<Item = &'a CallDef>,
+    ) -> Self {
+        self.functions
+            .extend(defs.into_iter().map(|def| describe_function(def, "scalar")));
+        self
+    }
+
+    /// Adds the setting `name` of value `value`.
+    pub fn with_setting(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.settings.insert(name, value.into());
+        self
+    }
+
+    /// Binds the description, including that of the tables bound in `bindings`, as the
+    /// `information_schema` global of `bindings`, unless `bindings` already binds that name.
+    pub fn bind(self, bindings: &mut MapBindings<Value>) {
+        if bindings
+            .names()
+            .any(|name| name.eq_ignore_ascii_case(INFORMATION_SCHEMA))
+        {
+            return;
+        }
+        let schema = self.to_value(bindings);
+        bindings.insert(INFORMATION_SCHEMA, schema);
+    }
+
+    /// The description, including that of the tables bound in `bindings`.
+    pub fn to_value(&self, bindings: &MapBindings<Value>) -> Value {
+        let tables: Bag = bindings
+            .names()
+            .map(|name| {
+                let mut table = Tuple::new();
+                table.insert("name", Value::from(name));
+                if let Some(entry) = self.catalog.resolve_type(name) {
+                    table.insert("type", Value::from(format!("{:?}", entry.ty().kind())));
+                }
+                Value::from(table)
+            })
+            .collect();
+
+        let mut functions = self.functions.clone();
+        functions.sort_by_key(|(name, _)| *name);
+        let functions: Bag = functions
+            .into_iter()
+            .map(|(_, function)| function)
+            .collect();
+
+        let mut schema = Tuple::new();
+        schema.insert("tables", Value::from(tables));
+        schema.insert("functions", Value::from(functions));
+        schema.insert("settings", Value::from(self.settings.clone()));
+        Value::from(schema)
+    }
+}
+
+fn describe_function(def: &CallDef, kind: &str) -> (&'static str, Value) {
+    let (&name, aliases) = def.names.split_first().unwrap_or((&"", &[]));
+    let signatures: List = def
+        .overloads
+        .iter()
+        .map(|overload| {
+            let args: Vec<_> = overload
+                .input
+                .iter()
+                .map(|arg| match arg {
+                    CallSpecArg::Positional => "_".to_string(),
+                    CallSpecArg::Named(name) => format!("{name}: _"),
+                })
+                .collect();
+            Value::from(format!("{name}({})", args.join(", ")))
+        })
+        .collect();
+
+    let mut function = Tuple::new();
+    function.insert("name", Value::from(name));
+    function.insert(
+        "aliases",
+        Value::from(
+            aliases
+                .iter()
+                .map(|alias| Value::from(*alias))
+                .collect::<List>(),
+        ),
+    );
+    function.insert("kind", Value::from(kind));
+    function.insert("signatures", Value::from(signatures));
+    (name, Value::from(function))
+}
@@ -1,7 +1,11 @@
+pub mod admission;
 pub mod env;
 pub mod error;
 pub mod eval;
+pub mod introspection;
+pub mod physical;
 pub mod plan;
+pub mod usage;
 
 #[cfg(test)]
 mod tests {
@@ -1363,7 +1367,7 @@ mod tests {
         );
         let index = ValueExpr::Path(
             Box::new(list),
-            vec![PathComponent::IndexExpr(Box::new(index_expr))],
+            vec![PathComponent::Expr(Box::new(index_expr))],
         );
         test(index, Value::Integer(3));
 
@@ -1376,7 +1380,7 @@ mod tests {
         );
         let index = ValueExpr::Path(
             Box::new(tuple),
-            vec![PathComponent::KeyExpr(Box::new(index_expr))],
+            vec![PathComponent::Expr(Box::new(index_expr))],
         );
         test(index, Value::Integer(10));
     }
@@ -2064,7 +2068,10 @@ mod tests {
 
         let from_lhs = scan("data", "t");
         let from_rhs = BindingsOp::Scan(logical::Scan {
-            expr: ValueExpr::SubQueryExpr(logical::SubQueryExpr { plan: subq_plan }),
+            expr: ValueExpr::SubQueryExpr(logical::SubQueryExpr {
+                plan: subq_plan,
+                coercion: logical::SubQueryCoercion::Collection,
+            }),
             as_key: "s".to_string(),
             at_key: None,
         });
@@ -2142,7 +2149,10 @@ mod tests {
                 ("ta".to_string(), ta),
                 (
                     "s".to_string(),
-                    ValueExpr::SubQueryExpr(logical::SubQueryExpr { plan: subq_plan }),
+                    ValueExpr::SubQueryExpr(logical::SubQueryExpr {
+                        plan: subq_plan,
+                        coercion: logical::SubQueryCoercion::Collection,
+                    }),
                 ),
             ]),
         }));
@@ -2200,7 +2210,7 @@ mod tests {
 
             let mut scan = EvalScan::new_with_at_key(
                 Box::new(EvalVarRef {
-                    name: BindingsName::CaseInsensitive("someOrderedTable".to_string()),
+                    name: BindingsName::CaseInsensitive("someOrderedTable".to_string()).into(),
                 }),
                 "x",
                 "y",
@@ -2226,7 +2236,7 @@ mod tests {
 
             let mut scan = EvalScan::new_with_at_key(
                 Box::new(EvalVarRef {
-                    name: BindingsName::CaseInsensitive("someUnorderedTable".to_string()),
+                    name: BindingsName::CaseInsensitive("someUnorderedTable".to_string()).into(),
                 }),
                 "x",
                 "y",
@@ -2255,13 +2265,13 @@ mod tests {
             p0.insert("someOrderedTable", some_ordered_table().into());
 
             let table_ref = EvalVarRef {
-                name: BindingsName::CaseInsensitive("someOrderedTable".to_string()),
+                name: BindingsName::CaseInsensitive("someOrderedTable".to_string()).into(),
             };
             let path_to_scalar = EvalPath {
                 expr: Box::new(table_ref),
                 components: vec![
                     EvalPathComponent::Index(0),
-                    EvalPathComponent::Key(BindingsName::CaseInsensitive("a".into())),
+                    EvalPathComponent::Key(BindingsName::CaseInsensitive("a".into()).into()),
                 ],
             };
             let mut scan = EvalScan::new(Box::new(path_to_scalar), "x");
@@ -2280,13 +2290,13 @@ mod tests {
             p0.insert("someOrderedTable", some_ordered_table().into());
 
             let table_ref = EvalVarRef {
-                name: BindingsName::CaseInsensitive("someOrderedTable".to_string()),
+                name: BindingsName::CaseInsensitive("someOrderedTable".to_string()).into(),
             };
             let path_to_scalar = EvalPath {
                 expr: Box::new(table_ref),
                 components: vec![
                     EvalPathComponent::Index(0),
-                    EvalPathComponent::Key(BindingsName::CaseInsensitive("c".into())),
+                    EvalPathComponent::Key(BindingsName::CaseInsensitive("c".into()).into()),
                 ],
             };
             let mut scan = EvalScan::new(Box::new(path_to_scalar), "x");
@@ -2320,7 +2330,7 @@ mod tests {
 
             let mut unpivot = EvalUnpivot::new(
                 Box::new(EvalVarRef {
-                    name: BindingsName::CaseInsensitive("justATuple".to_string()),
+                    name: BindingsName::CaseInsensitive("justATuple".to_string()).into(),
                 }),
                 "price",
                 Some("symbol".into()),
@@ -2344,7 +2354,7 @@ mod tests {
 
             let mut unpivot = EvalUnpivot::new(
                 Box::new(EvalVarRef {
-                    name: BindingsName::CaseInsensitive("nonTuple".to_string()),
+                    name: BindingsName::CaseInsensitive("nonTuple".to_string()).into(),
                 }),
                 "x",
                 Some("y".into()),
@@ -0,0 +1,1069 @@
+//! Physical plans, which select how the operators of logical plans are evaluated.
+//!
+//! A logical plan states what a query computes, while some of its operators may be evaluated by
+//! several implementations, e.g., a join by a nested loop over its operands or by hashing one of
+//! them by the keys of its equality conditions. A [`PhysicalPlanner`] lowers a logical plan to a
+//! [`PhysicalPlan`] of the same operators and flows, selecting an implementation for each of its
+//! operators, which [`EvaluatorPlanner::compile_physical`] compiles:
+//! - joins are [nested loop joins](PhysicalOp::NestedLoopJoin), or, if they are inner or left joins
+//!   whose condition equates expressions of their left operand to expressions of their right one,
+//!   which does not refer to the left one, [hash joins](PhysicalOp::HashJoin) or
+//!   [sort-merge joins](PhysicalOp::SortMergeJoin), which do not sort an operand already sorted
+//!   by their keys, as a sort-merge join by the same keys outputs it;
+//! - groupings are [hash aggregates](PhysicalOp::HashAggregate), or, if they group bindings
+//!   ordered by their keys, by an `ORDER BY` they take their input from,
+//!   [sort aggregates](PhysicalOp::SortAggregate).
+//!
+//! Among the implementations of an operator, the planner selects the cheapest by its
+//! [`CostModel`], which defaults to the [`DefaultCostModel`], so an embedder knowing of its data
+//! (e.g., of the indexes of its tables) may favor the implementations suiting it. The
+//! implementations of joins are selected along with those of the joins they are operands of, so
+//! that a sort-merge join may be selected for the order of its output it reuses.
+//!
+//! [`EvaluatorPlanner::compile`] compiles a logical plan as the physical plan its
+//! [`PhysicalPlanner`] lowers it to.
+//!
+//! ```
+//! use partiql_eval::physical::PhysicalPlanner;
+//! use partiql_logical::{BinaryOp, JoinKind, PathComponent, PlanBuilder, ValueExpr};
+//! use partiql_value::BindingsName;
+//!
+//! let id = |var: &str| {
+//!     ValueExpr::Path(
+//!         Box::new(ValueExpr::VarRef(BindingsName::CaseInsensitive(var.to_string()))),
+//!         vec![PathComponent::Key(BindingsName::CaseInsensitive("id".to_string()))],
+//!     )
+//! };
+//! let on = ValueExpr::BinaryExpr(BinaryOp::Eq, Box::new(id("a")), Box::new(id("b")));
+//! let plan = PlanBuilder::scan("a")
+//!     .join(JoinKind::Inner, PlanBuilder::scan("b"), Some(on))
+//!     .project_all()
+//!     .build();
+//!
+//! let physical = PhysicalPlanner::default().lower(&plan);
+//! assert_eq!(
+//!     format!("{physical:#}"),
+//!     "#5 Sink\n  \
+//!        #4 ProjectAll\n    \
+//!          #3 HashJoin(Inner, Scan(global::a AS a), Scan(global::b AS b) ON Eq(a.id, b.id) \
+//!             BY a.id = b.id)\n      \
+//!            #1 Scan(global::a AS a)\n      \
+//!            #2 Scan(global::b AS b)\n"
+//! );
+//! ```
+//!
+//! [`EvaluatorPlanner::compile`]: crate::plan::EvaluatorPlanner::compile
+//! [`EvaluatorPlanner::compile_physical`]: crate::plan::EvaluatorPlanner::compile_physical
+
+use crate::admission::{PREDICATE_SELECTIVITY, UNKNOWN_CARDINALITY};
+use crate::plan::{name_matches, referenced_variables, referenced_variables_op};
+use partiql_logical::{
+    BinaryOp, BindingsOp, GroupBy, GroupingStrategy, Join, JoinKind, LogicalPlan, OpId, Statistics,
+    ValueExpr,
+};
+use partiql_value::Value;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+/// A plan of [`PhysicalOp`]s, with the [`OpId`]s and flows of the logical plan it is lowered from.
+pub type PhysicalPlan = LogicalPlan<PhysicalOp>;
+
+/// An operator of a [`PhysicalPlan`], i.e., a logical operator along with the implementation
+/// evaluating it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PhysicalOp {
+    /// A logical operator evaluated by its only implementation.
+    Logical(BindingsOp),
+    /// A join evaluating its right operand, and its condition, for each binding of its left one.
+    NestedLoopJoin(NestedLoopJoin),
+    /// A join hashing the bindings of its right operand by the keys of its condition.
+    HashJoin(HashJoin),
+    /// A join sorting the bindings of its operands by the keys of its condition, and merging them.
+    SortMergeJoin(SortMergeJoin),
+    /// A grouping hashing its input bindings by their keys.
+    HashAggregate(GroupBy),
+    /// A grouping of input bindings ordered by their keys, grouping runs of equal keys.
+    SortAggregate(GroupBy),
+}
+
+impl Default for PhysicalOp {
+    fn default() -> Self {
+        PhysicalOp::Logical(BindingsOp::default())
+    }
+}
+
+/// A [`PhysicalOp::NestedLoopJoin`], of operands lowered as those of the [`Join`] it evaluates.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NestedLoopJoin {
+    pub kind: JoinKind,
+    pub left: Box<PhysicalOp>,
+    pub right: Box<PhysicalOp>,
+    pub on: Option<ValueExpr>,
+}
+
+/// A [`PhysicalOp::HashJoin`], of operands lowered as those of the [`Join`] it evaluates, of kind
+/// `Inner` or `Left`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HashJoin {
+    pub kind: JoinKind,
+    pub left: Box<PhysicalOp>,
+    pub right: Box<PhysicalOp>,
+    /// The pairs of expressions of the left and right operands `on` requires to be equal.
+    pub keys: Vec<(ValueExpr, ValueExpr)>,
+    pub on: ValueExpr,
+}
+
+/// A [`PhysicalOp::SortMergeJoin`], of operands lowered as those of the [`Join`] it evaluates, of
+/// kind `Inner` or `Left`, which outputs its bindings sorted as its left operand.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SortMergeJoin {
+    pub kind: JoinKind,
+    pub left: Box<PhysicalOp>,
+    pub right: Box<PhysicalOp>,
+    /// The pairs of expressions of the left and right operands `on` requires to be equal.
+    pub keys: Vec<(ValueExpr, ValueExpr)>,
+    pub on: ValueExpr,
+    /// Whether the left operand outputs its bindings sorted by their keys, so is not sorted.
+    pub left_sorted: bool,
+    /// Whether the right operand outputs its bindings sorted by their keys, so is not sorted.
+    pub right_sorted: bool,
+}
+
+impl PhysicalOp {
+    /// The logical operator this operator evaluates.
+    pub fn to_logical(&self) -> BindingsOp {
+        match self {
+            PhysicalOp::Logical(op) => op.clone(),
+            PhysicalOp::NestedLoopJoin(join) => BindingsOp::Join(Join {
+                kind: join.kind.clone(),
+                left: Box::new(join.left.to_logical()),
+                right: Box::new(join.right.to_logical()),
+                on: join.on.clone(),
+            }),
+            PhysicalOp::HashJoin(join) => BindingsOp::Join(Join {
+                kind: join.kind.clone(),
+                left: Box::new(join.left.to_logical()),
+                right: Box::new(join.right.to_logical()),
+                on: Some(join.on.clone()),
+            }),
+            PhysicalOp::SortMergeJoin(join) => BindingsOp::Join(Join {
+                kind: join.kind.clone(),
+                left: Box::new(join.left.to_logical()),
+                right: Box::new(join.right.to_logical()),
+                on: Some(join.on.clone()),
+            }),
+            PhysicalOp::HashAggregate(group_by) | PhysicalOp::SortAggregate(group_by) => {
+                BindingsOp::GroupBy(group_by.clone())
+            }
+        }
+    }
+}
+
+/// Renders the operator as its logical operator, named after its implementation.
+impl Display for PhysicalOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhysicalOp::Logical(op) => Display::fmt(op, f),
+            PhysicalOp::NestedLoopJoin(join) => {
+                write!(
+                    f,
+                    "NestedLoopJoin({:?}, {}, {}",
+                    join.kind, join.left, join.right
+                )?;
+                if let Some(on) = &join.on {
+                    write!(f, " ON {on}")?;
+                }
+                write!(f, ")")
+            }
+            PhysicalOp::HashJoin(join) => {
+                write!(f, "HashJoin({:?}, {}, {}", join.kind, join.left, join.right)?;
+                write_keys(f, &join.on, &join.keys)?;
+                write!(f, ")")
+            }
+            PhysicalOp::SortMergeJoin(join) => {
+                write!(
+                    f,
+                    "SortMergeJoin({:?}, {}, {}",
+                    join.kind, join.left, join.right
+                )?;
+                write_keys(f, &join.on, &join.keys)?;
+                if join.left_sorted {
+                    write!(f, " LEFT SORTED")?;
+                }
+                if join.right_sorted {
+                    write!(f, " RIGHT SORTED")?;
+                }
+                write!(f, ")")
+            }
+            PhysicalOp::HashAggregate(group_by) => write_aggregate(f, "HashAggregate", group_by),
+            PhysicalOp::SortAggregate(group_by) => write_aggregate(f, "SortAggregate", group_by),
+        }
+    }
+}
+
+/// Writes the condition `on` of a join, and the pairs of expressions `keys` it equates.
+fn write_keys(
+    f: &mut Formatter<'_>,
+    on: &ValueExpr,
+    keys: &[(ValueExpr, ValueExpr)],
+) -> std::fmt::Result {
+    write!(f, " ON {on} BY ")?;
+    for (i, (left, right)) in keys.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{left} = {right}")?;
+    }
+    Ok(())
+}
+
+/// Writes `group_by` as the `GroupBy` it is, renamed to `name`.
+fn write_aggregate(f: &mut Formatter<'_>, name: &str, group_by: &GroupBy) -> std::fmt::Result {
+    let group_by = group_by.to_string();
+    let args = group_by.strip_prefix("GroupBy").unwrap_or(&group_by);
+    write!(f, "{name}{args}")
+}
+
+/// The cost of hashing a value, relative to processing it.
+pub const HASH_COST: f64 = 2.0;
+
+/// The cost of sorting a value, relative to processing it.
+pub const SORT_COST: f64 = 4.0;
+
+/// A model of the costs of the implementations of operators, by which a [`PhysicalPlanner`]
+/// selects the cheapest implementation of each operator; see the [module documentation](self).
+///
+/// Costs are only compared among the implementations of the same operator, along with the costs of
+/// the implementations of its operands, so need only be relative to each other, e.g., a model
+/// knowing of an index of the table `t` may cost the nested loop joins of which a scan of `t` is
+/// the right operand below the [`DefaultCostModel`]'s cost of their hash joins.
+pub trait CostModel {
+    /// The estimated cost of evaluating the join or grouping `op`, excluding the costs of its
+    /// operands, i.e., of the operators it keeps inline.
+    fn cost(&self, op: &PhysicalOp) -> f64;
+}
+
+impl<F> CostModel for F
+where
+    F: Fn(&PhysicalOp) -> f64,
+{
+    fn cost(&self, op: &PhysicalOp) -> f64 {
+        self(op)
+    }
+}
+
+/// The [`CostModel`] of a [`PhysicalPlanner`] unless configured otherwise, costing operators by
+/// the number of values they process, of which hashing one costs [`HASH_COST`], and sorting one
+/// [`SORT_COST`]:
+/// - joins by the estimated cardinalities of their operands, which are those of the scans of
+///   literal collections, or those of its [`Statistics`], e.g., the ones the optimizer reorders
+///   joins by, and otherwise assumed to be [`UNKNOWN_CARDINALITY`];
+/// - groupings per binding of their input.
+#[derive(Clone, Default)]
+pub struct DefaultCostModel {
+    statistics: Option<Rc<dyn Statistics>>,
+}
+
+impl Debug for DefaultCostModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DefaultCostModel").finish_non_exhaustive()
+    }
+}
+
+impl DefaultCostModel {
+    /// Creates a model estimating the cardinalities of the data sources of scans by `statistics`.
+    pub fn with_statistics(statistics: Rc<dyn Statistics>) -> Self {
+        DefaultCostModel {
+            statistics: Some(statistics),
+        }
+    }
+
+    /// The estimated number of bindings of the operand of a join `op`.
+    fn cardinality(&self, op: &PhysicalOp) -> f64 {
+        let join = |left: &PhysicalOp, right: &PhysicalOp, on: bool| {
+            let matched = self.cardinality(left) * self.cardinality(right);
+            if on {
+                matched * PREDICATE_SELECTIVITY
+            } else {
+                matched
+            }
+        };
+        match op {
+            PhysicalOp::Logical(BindingsOp::Scan(scan)) => match &scan.expr {
+                ValueExpr::BagExpr(bag) => bag.elements.len() as f64,
+                ValueExpr::ListExpr(list) => list.elements.len() as f64,
+                ValueExpr::Lit(lit) => match lit.as_ref() {
+                    Value::Bag(bag) => bag.len() as f64,
+                    Value::List(list) => list.len() as f64,
+                    _ => 1.0,
+                },
+                expr => self
+                    .statistics
+                    .as_ref()
+                    .and_then(|statistics| statistics.cardinality(expr))
+                    .map_or(UNKNOWN_CARDINALITY, |cardinality| cardinality as f64),
+            },
+            PhysicalOp::NestedLoopJoin(nested) => {
+                join(&nested.left, &nested.right, nested.on.is_some())
+            }
+            PhysicalOp::HashJoin(hash) => join(&hash.left, &hash.right, true),
+            PhysicalOp::SortMergeJoin(merge) => join(&merge.left, &merge.right, true),
+            _ => UNKNOWN_CARDINALITY,
+        }
+    }
+}
+
+impl CostModel for DefaultCostModel {
+    fn cost(&self, op: &PhysicalOp) -> f64 {
+        match op {
+            // the right operand is evaluated for each binding of the left one
+            PhysicalOp::NestedLoopJoin(join) => {
+                let left = self.cardinality(&join.left);
+                left + left * self.cardinality(&join.right)
+            }
+            // the right operand is evaluated once and hashed, and each binding of the left one
+            // hashed to probe it
+            PhysicalOp::HashJoin(join) => {
+                let (left, right) = (self.cardinality(&join.left), self.cardinality(&join.right));
+                left * (1.0 + HASH_COST) + right * (1.0 + HASH_COST)
+            }
+            // the operands are evaluated once, those not yet sorted by their keys sorted, and the
+            // bindings of each merged
+            PhysicalOp::SortMergeJoin(join) => {
+                let sort = |operand: &PhysicalOp, sorted: bool| {
+                    let cardinality = self.cardinality(operand);
+                    if sorted {
+                        cardinality
+                    } else {
+                        cardinality * (1.0 + SORT_COST)
+                    }
+                };
+                sort(&join.left, join.left_sorted) + sort(&join.right, join.right_sorted)
+            }
+            PhysicalOp::HashAggregate(_) => 1.0 + HASH_COST,
+            PhysicalOp::SortAggregate(_) => 1.0,
+            PhysicalOp::Logical(_) => 0.0,
+        }
+    }
+}
+
+/// Lowers logical plans to [`PhysicalPlan`]s; see the [module documentation](self).
+#[derive(Clone)]
+pub struct PhysicalPlanner {
+    cost_model: Rc<dyn CostModel>,
+}
+
+impl Default for PhysicalPlanner {
+    fn default() -> Self {
+        PhysicalPlanner {
+            cost_model: Rc::new(DefaultCostModel::default()),
+        }
+    }
+}
+
+impl Debug for PhysicalPlanner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PhysicalPlanner").finish_non_exhaustive()
+    }
+}
+
+impl PhysicalPlanner {
+    /// Selects the implementations of operators by the costs of `model`, instead of the
+    /// [`DefaultCostModel`]'s.
+    pub fn with_cost_model(mut self, model: impl CostModel + 'static) -> Self {
+        self.cost_model = Rc::new(model);
+        self
+    }
+
+    /// Lowers `plan`, keeping the [`OpId`]s of its operators. The plans of its subqueries are
+    /// lowered when compiled.
+    pub fn lower(&self, plan: &LogicalPlan<BindingsOp>) -> PhysicalPlan {
+        plan.map(|id, op| match op {
+            BindingsOp::Join(join) => self.lower_join(plan, id, join),
+            BindingsOp::GroupBy(group_by) => {
+                let mut candidates = vec![];
+                if is_sorted_by_keys(plan, id, group_by) {
+                    candidates.push(PhysicalOp::SortAggregate(group_by.clone()));
+                }
+                candidates.push(PhysicalOp::HashAggregate(group_by.clone()));
+                self.cheapest(candidates)
+            }
+            op => PhysicalOp::Logical(op.clone()),
+        })
+    }
+
+    /// The cheapest of the implementations `candidates` of an operator, or the first of the
+    /// cheapest ones.
+    fn cheapest(&self, candidates: Vec<PhysicalOp>) -> PhysicalOp {
+        candidates
+            .into_iter()
+            .map(|op| (self.cost_model.cost(&op), op))
+            .reduce(|cheapest, candidate| {
+                if candidate.0 < cheapest.0 {
+                    candidate
+                } else {
+                    cheapest
+                }
+            })
+            .map(|(_, op)| op)
+            .unwrap_or_default()
+    }
+
+    /// Lowers the join `id` of `plan` as the cheapest of its lowerings, or, if it is an operand of
+    /// another join, as the join lowering it does, e.g., to reuse the order of its bindings.
+    fn lower_join(&self, plan: &LogicalPlan<BindingsOp>, id: OpId, join: &Join) -> PhysicalOp {
+        let consumer =
+            plan.flows()
+                .into_iter()
+                .find_map(|(src, dst, branch_num)| match plan.operator(dst) {
+                    Some(BindingsOp::Join(consumer)) if src == id => {
+                        Some((dst, consumer, branch_num))
+                    }
+                    _ => None,
+                });
+        if let Some((consumer_id, consumer, branch_num)) = consumer {
+            let operand = match self.lower_join(plan, consumer_id, consumer) {
+                PhysicalOp::NestedLoopJoin(NestedLoopJoin { left, right, .. })
+                | PhysicalOp::HashJoin(HashJoin { left, right, .. })
+                | PhysicalOp::SortMergeJoin(SortMergeJoin { left, right, .. }) => {
+                    if branch_num == 0 {
+                        left
+                    } else {
+                        right
+                    }
+                }
+                _ => Box::default(),
+            };
+            if operand.to_logical() == BindingsOp::Join(join.clone()) {
+                return *operand;
+            }
+        }
+        self.lower_joins(join)
+            .into_iter()
+            .next()
+            .map(|(_, op)| op)
+            .unwrap_or_default()
+    }
+
+    /// The lowerings of an operand of a join, along with their costs.
+    fn lower_operand(&self, op: &BindingsOp) -> Vec<(f64, PhysicalOp)> {
+        match op {
+            BindingsOp::Join(join) => self.lower_joins(join),
+            op => vec![(0.0, PhysicalOp::Logical(op.clone()))],
+        }
+    }
+
+    /// The lowerings of `join`, along with their costs including those of their operands: the
+    /// cheapest, or the first of the cheapest, followed by the cheapest of those outputting their
+    /// bindings in each other [order](sort_order), which a join they are an operand of may reuse.
+    fn lower_joins(&self, join: &Join) -> Vec<(f64, PhysicalOp)> {
+        let lefts = self.lower_operand(&join.left);
+        let rights = self.lower_operand(&join.right);
+        let keys = join_keys(join);
+        let mut lowerings: Vec<(f64, PhysicalOp)> = vec![];
+        for (left_cost, left) in &lefts {
+            for (right_cost, right) in &rights {
+                let (left, right) = (Box::new(left.clone()), Box::new(right.clone()));
+                let mut candidates = vec![];
+                if let (Some(on), Some(keys)) = (&join.on, &keys) {
+                    candidates.push(PhysicalOp::HashJoin(HashJoin {
+                        kind: join.kind.clone(),
+                        left: left.clone(),
+                        right: right.clone(),
+                        keys: keys.clone(),
+                        on: on.clone(),
+                    }));
+                    let is_sorted = |operand: &PhysicalOp, left: bool| {
+                        let keys = keys.iter().map(|(l, r)| if left { l } else { r });
+                        sort_order(operand).is_some_and(|order| order.into_iter().eq(keys))
+                    };
+                    candidates.push(PhysicalOp::SortMergeJoin(SortMergeJoin {
+                        kind: join.kind.clone(),
+                        left_sorted: is_sorted(&left, true),
+                        right_sorted: is_sorted(&right, false),
+                        left: left.clone(),
+                        right: right.clone(),
+                        keys: keys.clone(),
+                        on: on.clone(),
+                    }));
+                }
+                candidates.push(PhysicalOp::NestedLoopJoin(NestedLoopJoin {
+                    kind: join.kind.clone(),
+                    left,
+                    right,
+                    on: join.on.clone(),
+                }));
+
+                // keep the cheapest lowering of each order
+                for op in candidates {
+                    let cost = left_cost + right_cost + self.cost_model.cost(&op);
+                    let order = sort_order(&op);
+                    match lowerings
+                        .iter()
+                        .position(|(_, other)| sort_order(other) == order)
+                    {
+                        Some(idx) if cost < lowerings[idx].0 => lowerings[idx] = (cost, op),
+                        Some(_) => {}
+                        None => lowerings.push((cost, op)),
+                    }
+                }
+            }
+        }
+        // a stable sort, keeping the first of the cheapest first
+        lowerings.sort_by(|(l, _), (r, _)| l.total_cmp(r));
+        lowerings
+    }
+}
+
+/// The expressions by whose values the bindings output by `op` are sorted, if any, which are the
+/// left keys of a sort-merge join.
+fn sort_order(op: &PhysicalOp) -> Option<Vec<&ValueExpr>> {
+    match op {
+        PhysicalOp::SortMergeJoin(join) => Some(join.keys.iter().map(|(left, _)| left).collect()),
+        _ => None,
+    }
+}
+
+/// The pairs of expressions of the left and right operands of `join` its condition requires to be
+/// equal, if it may be evaluated as a hash or sort-merge join.
+fn join_keys(join: &Join) -> Option<Vec<(ValueExpr, ValueExpr)>> {
+    if !matches!(
+        join.kind,
+        JoinKind::Inner | JoinKind::Cross | JoinKind::Left
+    ) {
+        return None;
+    }
+    let (left, right) = (join.left.bound_variables(), join.right.bound_variables());
+    // the right operand is evaluated once, so must not refer to the left one
+    let mut referenced = vec![];
+    referenced_variables_op(&join.right, &mut referenced);
+    if referenced
+        .iter()
+        .any(|name| left.iter().any(|var| name_matches(name, var)))
+    {
+        return None;
+    }
+
+    // whether `expr` refers to some of the variables `vars` and none of `others`
+    let refers_only = |expr: &ValueExpr, vars: &[String], others: &[String]| {
+        let mut referenced = vec![];
+        referenced_variables(expr, &mut referenced);
+        let refers = |vars: &[String]| {
+            referenced
+                .iter()
+                .any(|name| vars.iter().any(|var| name_matches(name, var)))
+        };
+        refers(vars) && !refers(others)
+    };
+    let keys: Vec<_> = join
+        .on
+        .as_ref()?
+        .conjuncts()
+        .into_iter()
+        .filter_map(|conjunct| match conjunct {
+            ValueExpr::BinaryExpr(BinaryOp::Eq, lhs, rhs) => {
+                if refers_only(lhs, &left, &right) && refers_only(rhs, &right, &left) {
+                    Some((lhs.as_ref().clone(), rhs.as_ref().clone()))
+                } else if refers_only(rhs, &left, &right) && refers_only(lhs, &right, &left) {
+                    Some((rhs.as_ref().clone(), lhs.as_ref().clone()))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .collect();
+    (!keys.is_empty()).then_some(keys)
+}
+
+/// Whether the grouping `id` of `plan` takes its input from an `ORDER BY` sorting first by its
+/// keys, and only those, e.g., of `FROM t ORDER BY t.a GROUP BY t.a`.
+///
+/// Groupings leaving out `MISSING` keys, as `GROUP PARTIAL BY` does, are not considered sorted, as
+/// the bindings of `NULL` and `MISSING` keys may be interleaved.
+fn is_sorted_by_keys(plan: &LogicalPlan<BindingsOp>, id: OpId, group_by: &GroupBy) -> bool {
+    if group_by.exprs.is_empty() || !matches!(group_by.strategy, GroupingStrategy::GroupFull) {
+        return false;
+    }
+    let [input] = plan.inputs(id)[..] else {
+        return false;
+    };
+    let Some(BindingsOp::OrderBy(order_by)) = plan.operator(input) else {
+        return false;
+    };
+    let Some(leading) = order_by.specs.get(..group_by.exprs.len()) else {
+        return false;
+    };
+    leading
+        .iter()
+        .all(|spec| group_by.exprs.values().any(|expr| expr == &spec.expr))
+        && group_by
+            .exprs
+            .values()
+            .all(|expr| leading.iter().any(|spec| &spec.expr == expr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::basic::MapBindings;
+    use crate::eval::sort::ExternalSortConfig;
+    use crate::plan::{EvaluationMode, EvaluatorPlanner};
+    use partiql_catalog::PartiqlCatalog;
+    use partiql_logical::{
+        AggFunc, AggregateExpression, BagExpr, PathComponent, PlanBuilder, SetQuantifier, SortSpec,
+        SortSpecNullOrder, SortSpecOrder,
+    };
+    use partiql_value::{bag, tuple, BindingsName, Value};
+    use rust_decimal_macros::dec;
+
+    fn var(name: &str) -> ValueExpr {
+        ValueExpr::VarRef(BindingsName::CaseInsensitive(name.to_string()))
+    }
+
+    fn path(name: &str, key: &str) -> ValueExpr {
+        ValueExpr::Path(
+            Box::new(var(name)),
+            vec![PathComponent::Key(BindingsName::CaseInsensitive(
+                key.to_string(),
+            ))],
+        )
+    }
+
+    fn eq(lhs: ValueExpr, rhs: ValueExpr) -> ValueExpr {
+        ValueExpr::BinaryExpr(BinaryOp::Eq, Box::new(lhs), Box::new(rhs))
+    }
+
+    fn count_by_a(input: PlanBuilder) -> LogicalPlan<BindingsOp> {
+        input
+            .group_by(GroupBy {
+                strategy: GroupingStrategy::GroupFull,
+                exprs: [("a".to_string(), path("t", "a"))].into_iter().collect(),
+                aggregate_exprs: vec![AggregateExpression {
+                    name: "n".to_string(),
+                    expr: path("t", "b"),
+                    func: AggFunc::AggCount,
+                    setq: SetQuantifier::All,
+                }],
+                group_as_alias: None,
+            })
+            .project([("a", var("a")), ("n", var("n"))])
+            .build()
+    }
+
+    fn order_by(expr: ValueExpr) -> Vec<SortSpec> {
+        vec![SortSpec {
+            expr,
+            order: SortSpecOrder::Asc,
+            null_order: SortSpecNullOrder::First,
+        }]
+    }
+
+    fn evaluate(plan: &LogicalPlan<BindingsOp>, bindings: MapBindings<Value>) -> Value {
+        let catalog = PartiqlCatalog::default();
+        let mut planner = EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog);
+        let mut plan = planner.compile(plan).expect("plan");
+        plan.execute_mut(bindings).expect("evaluation").result
+    }
+
+    /// The implementations of the operators of the lowering of `plan`, by their display.
+    fn implementations(plan: &LogicalPlan<BindingsOp>) -> Vec<String> {
+        lowered_implementations(&PhysicalPlanner::default(), plan)
+    }
+
+    fn lowered_implementations(
+        planner: &PhysicalPlanner,
+        plan: &LogicalPlan<BindingsOp>,
+    ) -> Vec<String> {
+        let physical = planner.lower(plan);
+        let mut ops: Vec<_> = physical
+            .operators_by_id()
+            .map(|(_, op)| op.to_string())
+            .filter_map(|op| op.split_once('(').map(|(name, _)| name.to_string()))
+            .collect();
+        ops.sort();
+        ops
+    }
+
+    #[test]
+    fn join_implementations() {
+        let equi = PlanBuilder::scan("a")
+            .join(
+                JoinKind::Left,
+                PlanBuilder::scan("b"),
+                Some(eq(path("b", "id"), path("a", "id"))),
+            )
+            .build();
+        assert_eq!(implementations(&equi), ["HashJoin", "Scan", "Scan"]);
+        let physical = PhysicalPlanner::default().lower(&equi);
+        assert_eq!(physical.map(|_, op| op.to_logical()), equi);
+
+        let full = PlanBuilder::scan("a")
+            .join(
+                JoinKind::Full,
+                PlanBuilder::scan("b"),
+                Some(eq(path("a", "id"), path("b", "id"))),
+            )
+            .build();
+        assert_eq!(implementations(&full), ["NestedLoopJoin", "Scan", "Scan"]);
+
+        let lateral = PlanBuilder::scan("a")
+            .join(
+                JoinKind::Inner,
+                PlanBuilder::scan_as(path("a", "items"), "i"),
+                Some(eq(path("a", "id"), path("i", "id"))),
+            )
+            .build();
+        assert_eq!(
+            implementations(&lateral),
+            ["NestedLoopJoin", "Scan", "Scan"]
+        );
+
+        let theta = PlanBuilder::scan("a")
+            .join(
+                JoinKind::Inner,
+                PlanBuilder::scan("b"),
+                Some(ValueExpr::BinaryExpr(
+                    BinaryOp::Lt,
+                    Box::new(path("a", "id")),
+                    Box::new(path("b", "id")),
+                )),
+            )
+            .build();
+        assert_eq!(implementations(&theta), ["NestedLoopJoin", "Scan", "Scan"]);
+    }
+
+    #[test]
+    fn sort_merge_join_implementations() {
+        let joins = |key: ValueExpr| {
+            PlanBuilder::scan("a")
+                .join(
+                    JoinKind::Inner,
+                    PlanBuilder::scan("b"),
+                    Some(eq(path("a", "id"), path("b", "id"))),
+                )
+                .join(
+                    JoinKind::Left,
+                    PlanBuilder::scan("c"),
+                    Some(eq(key, path("c", "id"))),
+                )
+                .build()
+        };
+        let outer_join = |plan: &LogicalPlan<BindingsOp>| {
+            let physical = PhysicalPlanner::default().lower(plan);
+            let join = physical
+                .operators_by_id()
+                .map(|(_, op)| op.clone())
+                .filter(|op| !matches!(op, PhysicalOp::Logical(_)))
+                .last();
+            join.expect("join")
+        };
+
+        // the join by the ids of `a` reuses the order of the bindings the join of `a` and `b`
+        // outputs, so both are sort-merge joins
+        let reused = joins(path("a", "id"));
+        assert_eq!(
+            implementations(&reused),
+            ["Scan", "Scan", "Scan", "SortMergeJoin", "SortMergeJoin"]
+        );
+        let PhysicalOp::SortMergeJoin(join) = outer_join(&reused) else {
+            panic!("expected a sort-merge join");
+        };
+        assert!(join.left_sorted && !join.right_sorted);
+        assert!(matches!(*join.left, PhysicalOp::SortMergeJoin(_)));
+        let physical = PhysicalPlanner::default().lower(&reused);
+        assert_eq!(physical.map(|_, op| op.to_logical()), reused);
+
+        // the join by the ids of `b` does not, so both are hash joins
+        let unsorted = joins(path("b", "id"));
+        assert_eq!(
+            implementations(&unsorted),
+            ["HashJoin", "HashJoin", "Scan", "Scan", "Scan"]
+        );
+        let PhysicalOp::HashJoin(join) = outer_join(&unsorted) else {
+            panic!("expected a hash join");
+        };
+        assert!(matches!(*join.left, PhysicalOp::HashJoin(_)));
+    }
+
+    #[test]
+    fn aggregate_implementations() {
+        let hashed = count_by_a(PlanBuilder::scan("t"));
+        assert_eq!(
+            implementations(&hashed),
+            ["HashAggregate", "Project", "Scan"]
+        );
+
+        let sorted = count_by_a(PlanBuilder::scan("t").order_by(order_by(path("t", "a"))));
+        assert_eq!(
+            implementations(&sorted),
+            ["OrderBy", "Project", "Scan", "SortAggregate"]
+        );
+
+        let sorted_by_other = count_by_a(PlanBuilder::scan("t").order_by(order_by(path("t", "b"))));
+        assert_eq!(
+            implementations(&sorted_by_other),
+            ["HashAggregate", "OrderBy", "Project", "Scan"]
+        );
+    }
+
+    #[test]
+    fn cost_models() {
+        let equi = |left: PlanBuilder| {
+            left.join(
+                JoinKind::Inner,
+                PlanBuilder::scan("b"),
+                Some(eq(path("a", "id"), path("b", "id"))),
+            )
+            .build()
+        };
+        let small = ValueExpr::BagExpr(BagExpr {
+            elements: vec![ValueExpr::Lit(Box::new(Value::from(tuple![("id", 1)])))],
+        });
+        assert_eq!(
+            implementations(&equi(PlanBuilder::scan_as(small, "a"))),
+            ["NestedLoopJoin", "Scan", "Scan"]
+        );
+
+        // statistics, e.g., those the optimizer reorders joins by, of a small table `a`
+        struct Sizes;
+        impl Statistics for Sizes {
+            fn cardinality(&self, expr: &ValueExpr) -> Option<u64> {
+                match expr {
+                    ValueExpr::GlobalVarRef(BindingsName::CaseInsensitive(name)) if name == "a" => {
+                        Some(1)
+                    }
+                    _ => None,
+                }
+            }
+        }
+        assert_eq!(
+            implementations(&equi(PlanBuilder::scan("a"))),
+            ["HashJoin", "Scan", "Scan"]
+        );
+        let informed = PhysicalPlanner::default()
+            .with_cost_model(DefaultCostModel::with_statistics(Rc::new(Sizes)));
+        assert_eq!(
+            lowered_implementations(&informed, &equi(PlanBuilder::scan("a"))),
+            ["NestedLoopJoin", "Scan", "Scan"]
+        );
+
+        // a model of an index of `b`, probed by the nested loop joins of which it is the right
+        // operand
+        let indexed = PhysicalPlanner::default().with_cost_model(|op: &PhysicalOp| match op {
+            PhysicalOp::NestedLoopJoin(NestedLoopJoin { right, .. })
+                if right.to_string() == "Scan(global::b AS b)" =>
+            {
+                0.0
+            }
+            op => DefaultCostModel::default().cost(op),
+        });
+        assert_eq!(
+            lowered_implementations(&indexed, &equi(PlanBuilder::scan("a"))),
+            ["NestedLoopJoin", "Scan", "Scan"]
+        );
+        let sorted = count_by_a(PlanBuilder::scan("t").order_by(order_by(path("t", "a"))));
+        assert_eq!(
+            lowered_implementations(&indexed, &sorted),
+            ["OrderBy", "Project", "Scan", "SortAggregate"]
+        );
+
+        let hashing = PhysicalPlanner::default().with_cost_model(|op: &PhysicalOp| match op {
+            PhysicalOp::HashAggregate(_) => 0.0,
+            _ => 1.0,
+        });
+        assert_eq!(
+            lowered_implementations(&hashing, &sorted),
+            ["HashAggregate", "OrderBy", "Project", "Scan"]
+        );
+    }
+
+    #[test]
+    fn hash_join() {
+        let mut bindings = MapBindings::default();
+        bindings.insert(
+            "a",
+            bag![
+                tuple![("id", 1), ("x", "one")],
+                tuple![("id", Value::Decimal(Box::new(dec!(2.0)))), ("x", "two")],
+                tuple![("id", Value::Null), ("x", "null")],
+                tuple![("x", "missing")],
+            ]
+            .into(),
+        );
+        bindings.insert(
+            "b",
+            bag![
+                tuple![("id", 1), ("y", "uno")],
+                tuple![("id", 1), ("y", "eins")],
+                tuple![("id", 2), ("y", "dos")],
+                tuple![("id", Value::Null), ("y", "nulo")],
+            ]
+            .into(),
+        );
+        let join = |kind| {
+            PlanBuilder::scan("a")
+                .join(
+                    kind,
+                    PlanBuilder::scan("b"),
+                    Some(eq(path("a", "id"), path("b", "id"))),
+                )
+                .project([("x", path("a", "x")), ("y", path("b", "y"))])
+                .build()
+        };
+
+        assert_eq!(
+            evaluate(&join(JoinKind::Inner), bindings.clone()),
+            Value::from(bag![
+                tuple![("x", "one"), ("y", "uno")],
+                tuple![("x", "one"), ("y", "eins")],
+                tuple![("x", "two"), ("y", "dos")],
+            ])
+        );
+        assert_eq!(
+            evaluate(&join(JoinKind::Left), bindings),
+            Value::from(bag![
+                tuple![("x", "one"), ("y", "uno")],
+                tuple![("x", "one"), ("y", "eins")],
+                tuple![("x", "two"), ("y", "dos")],
+                tuple![("x", "null")],
+                tuple![("x", "missing")],
+            ])
+        );
+    }
+
+    #[test]
+    fn sort_merge_join() {
+        let mut bindings = MapBindings::default();
+        bindings.insert(
+            "a",
+            bag![
+                tuple![("id", 2), ("x", "two")],
+                tuple![("id", Value::Null), ("x", "null")],
+                tuple![("id", 1), ("x", "one")],
+                tuple![("id", Value::Decimal(Box::new(dec!(3.0)))), ("x", "three")],
+                tuple![("id", 1.0), ("x", "one real")],
+                tuple![("x", "missing")],
+            ]
+            .into(),
+        );
+        bindings.insert(
+            "b",
+            bag![
+                tuple![("id", 3), ("y", "tres")],
+                tuple![("id", 1), ("y", "uno")],
+                tuple![("id", 2), ("y", "dos")],
+                tuple![("id", Value::Null), ("y", "nulo")],
+                tuple![("id", 1), ("y", "eins")],
+                tuple![("id", 2.0), ("y", "dos real")],
+            ]
+            .into(),
+        );
+        bindings.insert(
+            "c",
+            bag![
+                tuple![("id", 2), ("z", "deux")],
+                tuple![("id", 1), ("z", "un")],
+            ]
+            .into(),
+        );
+        let joins = |kind| {
+            PlanBuilder::scan("a")
+                .join(
+                    kind,
+                    PlanBuilder::scan("b"),
+                    Some(eq(path("a", "id"), path("b", "id"))),
+                )
+                .join(
+                    JoinKind::Left,
+                    PlanBuilder::scan("c"),
+                    Some(eq(path("a", "id"), path("c", "id"))),
+                )
+                .project([
+                    ("x", path("a", "x")),
+                    ("y", path("b", "y")),
+                    ("z", path("c", "z")),
+                ])
+                .build()
+        };
+        // sort-merge joins of runs of 2 bindings, compiled as the joins they are plans of
+        let merging = PhysicalPlanner::default().with_cost_model(|op: &PhysicalOp| match op {
+            PhysicalOp::SortMergeJoin(_) => 0.0,
+            _ => 1.0,
+        });
+        let catalog = PartiqlCatalog::default();
+        let evaluate_merging = |plan: &LogicalPlan<BindingsOp>, bindings| {
+            let physical = merging.lower(plan);
+            assert_eq!(
+                lowered_implementations(&merging, plan),
+                [
+                    "Project",
+                    "Scan",
+                    "Scan",
+                    "Scan",
+                    "SortMergeJoin",
+                    "SortMergeJoin"
+                ]
+            );
+            // the outer join merges the bindings of the inner one as they are output
+            assert!(physical.operators().into_iter().any(|op| matches!(
+                op,
+                PhysicalOp::SortMergeJoin(SortMergeJoin {
+                    left_sorted: true,
+                    ..
+                })
+            )));
+            let mut planner = EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+                .with_sort_config(ExternalSortConfig::default().with_run_size(2));
+            let mut plan = planner.compile_physical(&physical).expect("plan");
+            plan.execute_mut(bindings).expect("evaluation").result
+        };
+
+        for kind in [JoinKind::Inner, JoinKind::Left] {
+            let plan = joins(kind);
+            assert_eq!(
+                evaluate_merging(&plan, bindings.clone()),
+                evaluate(&plan, bindings.clone())
+            );
+        }
+        assert_eq!(
+            evaluate_merging(&joins(JoinKind::Inner), bindings),
+            Value::from(bag![
+                tuple![("x", "one"), ("y", "uno"), ("z", "un")],
+                tuple![("x", "one"), ("y", "eins"), ("z", "un")],
+                tuple![("x", "one real"), ("y", "uno"), ("z", "un")],
+                tuple![("x", "one real"), ("y", "eins"), ("z", "un")],
+                tuple![("x", "two"), ("y", "dos"), ("z", "deux")],
+                tuple![("x", "two"), ("y", "dos real"), ("z", "deux")],
+                tuple![("x", "three"), ("y", "tres")],
+            ])
+        );
+    }
+
+    #[test]
+    fn sort_aggregate() {
+        let mut bindings = MapBindings::default();
+        bindings.insert(
+            "t",
+            bag![
+                tuple![("a", 2), ("b", 1)],
+                tuple![("a", 1), ("b", 1)],
+                tuple![("a", 2), ("b", 1)],
+                tuple![("a", Value::Null), ("b", 1)],
+                tuple![("b", 1)],
+                tuple![("a", 1), ("b", 1)],
+                tuple![("a", 2), ("b", 1)],
+            ]
+            .into(),
+        );
+        let expected = Value::from(bag![
+            tuple![("a", Value::Null), ("n", 2)],
+            tuple![("a", 1), ("n", 2)],
+            tuple![("a", 2), ("n", 3)],
+        ]);
+        let sorted = count_by_a(PlanBuilder::scan("t").order_by(order_by(path("t", "a"))));
+        assert_eq!(evaluate(&sorted, bindings.clone()), expected);
+        let hashed = count_by_a(PlanBuilder::scan("t"));
+        assert_eq!(evaluate(&hashed, bindings), expected);
+    }
+}
@@ -1,38 +1,50 @@
 use itertools::Itertools;
 use petgraph::prelude::StableGraph;
-use regex::RegexBuilder;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use partiql_logical as logical;
 
 use partiql_logical::{
     AggFunc, BagOperator, BinaryOp, BindingsOp, CallName, GroupingStrategy, IsTypeExpr, JoinKind,
     LogicalPlan, OpId, PathComponent, Pattern, PatternMatchExpr, SearchedCase, SetQuantifier,
-    SortSpecNullOrder, SortSpecOrder, Type, UnaryOp, ValueExpr,
+    SortSpecNullOrder, SortSpecOrder, Type, UnaryOp, ValueExpr, WindowFunction,
 };
 
+use crate::admission::{AdmissionCheck, AdmissionControl};
 use crate::error::{ErrorNode, PlanErr, PlanningError};
 use crate::eval;
 use crate::eval::evaluable::{
-    Avg, Count, EvalGroupingStrategy, EvalJoinKind, EvalOrderBy, EvalOrderBySortCondition,
-    EvalOrderBySortSpec, EvalOuterExcept, EvalOuterIntersect, EvalOuterUnion, EvalSubQueryExpr,
-    Evaluable, Max, Min, Sum,
+    Avg, Count, EvalFilter, EvalFilteredOperand, EvalGroupingStrategy, EvalHashJoin, EvalJoinKind,
+    EvalMaterializedSubQueryExpr, EvalOrderBy, EvalOrderBySortCondition, EvalOrderBySortSpec,
+    EvalOuterExcept, EvalOuterIntersect, EvalOuterUnion, EvalSortMergeJoin, EvalSubQueryExpr,
+    EvalWindow, EvalWindowFunction, EvalWindowOffset, Evaluable, MaterializedSubQuery, Max, Min,
+    SubQueryCoercion, Sum,
 };
 use crate::eval::expr::pattern_match::like_to_re_pattern;
 use crate::eval::expr::{
     EvalBagExpr, EvalBetweenExpr, EvalBinOp, EvalBinOpExpr, EvalDynamicLookup, EvalExpr, EvalFnAbs,
     EvalFnBaseTableExpr, EvalFnBitLength, EvalFnBtrim, EvalFnCardinality, EvalFnCharLength,
-    EvalFnCollAvg, EvalFnCollCount, EvalFnCollMax, EvalFnCollMin, EvalFnCollSum, EvalFnExists,
-    EvalFnExtractDay, EvalFnExtractHour, EvalFnExtractMinute, EvalFnExtractMonth,
+    EvalFnCollAvg, EvalFnCollCount, EvalFnCollMax, EvalFnCollMin, EvalFnCollSum, EvalFnElementAt,
+    EvalFnExists, EvalFnExtractDay, EvalFnExtractHour, EvalFnExtractMinute, EvalFnExtractMonth,
     EvalFnExtractSecond, EvalFnExtractTimezoneHour, EvalFnExtractTimezoneMinute, EvalFnExtractYear,
     EvalFnLower, EvalFnLtrim, EvalFnModulus, EvalFnOctetLength, EvalFnOverlay, EvalFnPosition,
-    EvalFnRtrim, EvalFnSubstring, EvalFnUpper, EvalIsTypeExpr, EvalLikeMatch,
-    EvalLikeNonStringNonLiteralMatch, EvalListExpr, EvalLitExpr, EvalPath, EvalSearchedCaseExpr,
-    EvalTupleExpr, EvalUnaryOp, EvalUnaryOpExpr, EvalVarRef, RE_SIZE_LIMIT,
+    EvalFnRtrim, EvalFnScalarExpr, EvalFnSubstring, EvalFnTupleUnion, EvalFnUpper, EvalFnUtcNow,
+    EvalGlobalVarRef, EvalIsTypeExpr, EvalLikeMatch, EvalLikeNonStringNonLiteralMatch,
+    EvalListExpr, EvalLitExpr, EvalParameter, EvalPath, EvalSearchedCaseExpr, EvalTupleExpr,
+    EvalUnaryOp, EvalUnaryOpExpr, EvalVarRef, PatternCache,
 };
-use crate::eval::EvalPlan;
-use partiql_catalog::Catalog;
+use crate::eval::sort::ExternalSortConfig;
+use crate::eval::{EvalPlan, Schedule};
+use crate::introspection::InformationSchema;
+use crate::physical::{PhysicalOp, PhysicalPlan, PhysicalPlanner};
+use partiql_catalog::{
+    attribute_type, Catalog, FunctionEntryFunction, ScanLimit, UndeclaredAttribute,
+};
+use partiql_types::{StructType, TypeKind};
 use partiql_value::Value::Null;
+use partiql_value::{BindingsName, Value};
+use unicase::UniCase;
 
 #[macro_export]
 macro_rules! correct_num_args_or_err {
@@ -63,6 +75,25 @@ pub enum EvaluationMode {
 pub struct EvaluatorPlanner<'c> {
     mode: EvaluationMode,
     catalog: &'c dyn Catalog,
+    admission: Option<Rc<dyn AdmissionControl>>,
+    sort_config: ExternalSortConfig,
+    /// For each of the plans being compiled, the closed struct types of the variables bound by its
+    /// scans over catalog-typed collections.
+    closed_structs: Vec<HashMap<String, StructType>>,
+    materialize_subqueries: bool,
+    /// The names of the variables bound by each of the plans enclosing the plan being compiled.
+    enclosing_variables: Vec<Vec<String>>,
+    /// The materialized subqueries of the plan being compiled, along with their logical plans.
+    materialized: Vec<(LogicalPlan<BindingsOp>, Rc<MaterializedSubQuery>)>,
+    project_groups: bool,
+    physical: PhysicalPlanner,
+    /// For each of the plans being compiled, the attributes its `GROUP AS` group's elements are
+    /// projected to, if any; see [`EvaluatorPlanner::with_group_as_projection`].
+    group_as_attributes: Vec<Option<Vec<BindingsName>>>,
+    /// The conditions of the filters flowing to the left and right operands of the join being
+    /// compiled, if any; see [`operand_filters`].
+    operand_filters: [Option<ValueExpr>; 2],
+    patterns: PatternCache,
     errors: Vec<PlanningError>,
 }
 
@@ -80,66 +111,287 @@ impl<'c> EvaluatorPlanner<'c> {
         EvaluatorPlanner {
             mode,
             catalog,
+            admission: None,
+            sort_config: ExternalSortConfig::default(),
+            closed_structs: vec![],
+            materialize_subqueries: false,
+            enclosing_variables: vec![],
+            materialized: vec![],
+            project_groups: false,
+            physical: PhysicalPlanner::default(),
+            group_as_attributes: vec![],
+            operand_filters: Default::default(),
+            patterns: PatternCache::default(),
             errors: vec![],
         }
     }
 
+    /// Configures the cache of the regexes compiled from `LIKE` patterns by the compiled plans,
+    /// e.g., to share it between planners compiling the same statements; by default, each planner
+    /// has a cache of its own.
+    pub fn with_pattern_cache(mut self, cache: PatternCache) -> Self {
+        self.patterns = cache;
+        self
+    }
+
+    /// Configures whether the compiled plans project the elements of each `GROUP AS` group to the
+    /// attributes (i.e., the `FROM` variables) the query refers to, limiting the memory a group
+    /// takes when the input bindings are wide. E.g., the group `g` of
+    /// `SELECT k, (SELECT VALUE v.x.a FROM g AS v) AS a FROM t AS x, u AS y GROUP BY x.k AS k GROUP AS g`
+    /// only includes the binding of `x`.
+    ///
+    /// Groups whose elements may be referred to as a whole (e.g., `SELECT g FROM ...`) are not
+    /// projected.
+    pub fn with_group_as_projection(mut self, project: bool) -> Self {
+        self.project_groups = project;
+        self
+    }
+
+    /// Configures whether the compiled plans evaluate each uncorrelated subquery at most once per
+    /// execution, sharing its result between all identical references to it, e.g., between the
+    /// references to a common table expression such as `t` in `WITH t AS (...) SELECT ...`.
+    ///
+    /// A subquery is considered correlated, and is evaluated for each of its references' inputs,
+    /// if it refers to a variable named as one bound by a plan enclosing it.
+    pub fn with_subquery_materialization(mut self, materialize: bool) -> Self {
+        self.materialize_subqueries = materialize;
+        self
+    }
+
+    /// Describes what the plans compiled by this planner may query, i.e., the functions of its
+    /// catalog, and its settings; see [`crate::introspection`].
+    pub fn information_schema(&self) -> InformationSchema<'c> {
+        let mode = match self.mode {
+            EvaluationMode::Strict => "strict",
+            EvaluationMode::Permissive => "permissive",
+        };
+        InformationSchema::new(self.catalog)
+            .with_setting("mode", mode)
+            .with_setting("admission_control", self.admission.is_some())
+            .with_setting("sort_run_size", self.sort_config.run_size as i64)
+            .with_setting("subquery_materialization", self.materialize_subqueries)
+            .with_setting("group_as_projection", self.project_groups)
+    }
+
+    /// Registers `control` to be consulted before each execution of the plans compiled by this
+    /// planner; see [`crate::admission`].
+    pub fn with_admission_control(mut self, control: impl AdmissionControl + 'static) -> Self {
+        self.admission = Some(Rc::new(control));
+        self
+    }
+
+    /// Configures the external sort used by sorting operators (e.g., `ORDER BY`, `DISTINCT`, and
+    /// sort-merge joins) of the compiled plans; see [`crate::eval::sort`].
+    pub fn with_sort_config(mut self, config: ExternalSortConfig) -> Self {
+        self.sort_config = config;
+        self
+    }
+
+    /// Configures the planner lowering the plans compiled, and the plans of their subqueries, to
+    /// the physical plans selecting how their operators are evaluated; see [`crate::physical`].
+    pub fn with_physical_planner(mut self, planner: PhysicalPlanner) -> Self {
+        self.physical = planner;
+        self
+    }
+
     #[inline]
     pub fn compile(&mut self, plan: &LogicalPlan<BindingsOp>) -> Result<EvalPlan, PlanErr> {
-        let plan = match self.mode {
-            EvaluationMode::Strict => self.plan_eval::<true>(plan),
-            EvaluationMode::Permissive => self.plan_eval::<false>(plan),
+        let physical = self.physical.lower(plan);
+        self.compile_plans(plan, &physical)
+    }
+
+    /// Compiles `plan`, evaluating its operators by the implementations it selects. The plans of
+    /// its subqueries are lowered by the planner's [`PhysicalPlanner`].
+    pub fn compile_physical(&mut self, plan: &PhysicalPlan) -> Result<EvalPlan, PlanErr> {
+        let logical = plan.map(|_, op| op.to_logical());
+        self.compile_plans(&logical, plan)
+    }
+
+    /// Compiles the physical plan `physical` of the logical plan `plan`.
+    fn compile_plans(
+        &mut self,
+        plan: &LogicalPlan<BindingsOp>,
+        physical: &PhysicalPlan,
+    ) -> Result<EvalPlan, PlanErr> {
+        let EvalPlan(graph, _, _, schedule, details) = match self.mode {
+            EvaluationMode::Strict => self.plan_physical::<true>(plan, physical),
+            EvaluationMode::Permissive => self.plan_physical::<false>(plan, physical),
         };
+        let materialized = std::mem::take(&mut self.materialized)
+            .into_iter()
+            .map(|(_, subquery)| subquery)
+            .collect();
         let errors = std::mem::take(&mut self.errors);
         if !errors.is_empty() {
             Err(PlanErr { errors })
         } else {
-            Ok(plan)
+            let admission = self.admission.as_ref().map(|control| AdmissionCheck {
+                control: control.clone(),
+                plan: plan.clone(),
+            });
+            Ok(EvalPlan(graph, admission, materialized, schedule, details))
         }
     }
 
     #[inline]
     fn plan_eval<const STRICT: bool>(&mut self, lg: &LogicalPlan<BindingsOp>) -> EvalPlan {
-        let flows = lg.flows();
+        let physical = self.physical.lower(lg);
+        self.plan_physical::<{ STRICT }>(lg, &physical)
+    }
 
+    /// Plans the physical plan `physical` of the logical plan `lg`, which has the same operator
+    /// ids.
+    fn plan_physical<const STRICT: bool>(
+        &mut self,
+        lg: &LogicalPlan<BindingsOp>,
+        physical: &PhysicalPlan,
+    ) -> EvalPlan {
+        let flows = physical.flows();
+        self.closed_structs.push(self.bound_closed_structs(lg));
+        self.enclosing_variables.push(bound_variables(lg));
+        let group_as_attributes = lg.operators().iter().find_map(|op| match op {
+            BindingsOp::GroupBy(logical::GroupBy {
+                group_as_alias: Some(alias),
+                ..
+            }) if self.project_groups => group_as_attributes(lg, alias),
+            _ => None,
+        });
+        self.group_as_attributes.push(group_as_attributes);
+
+        let scan_limits = scan_limits(lg);
+        let mut operand_filters = operand_filters(lg);
         let mut graph: StableGraph<_, _> = Default::default();
         let mut seen = HashMap::new();
+        let mut details = HashMap::new();
 
-        for (s, d, w) in flows {
-            let mut add_node = |op_id: &OpId| {
-                let logical_op = lg.operator(*op_id).unwrap();
-                *seen
-                    .entry(*op_id)
-                    .or_insert_with(|| graph.add_node(self.get_eval_node::<{ STRICT }>(logical_op)))
+        for (s, d, w) in &flows {
+            let (Some(src_op), Some(dst_op)) = (physical.operator(*s), physical.operator(*d))
+            else {
+                self.errors.push(PlanningError::IllegalState(format!(
+                    "flow {s:?} -> {d:?} from or to an operator not in the plan"
+                )));
+                continue;
+            };
+            let mut add_node = |op_id: &OpId, physical_op: &PhysicalOp| {
+                *seen.entry(*op_id).or_insert_with(|| {
+                    let node = match (physical_op, scan_limits.get(op_id)) {
+                        (PhysicalOp::Logical(BindingsOp::Scan(scan)), Some(limit)) => {
+                            self.get_limited_scan::<{ STRICT }>(scan, *limit)
+                        }
+                        _ => {
+                            self.operand_filters =
+                                operand_filters.remove(op_id).unwrap_or_default();
+                            self.get_physical_node::<{ STRICT }>(physical_op)
+                        }
+                    };
+                    let idx = graph.add_node(node);
+                    details.insert(idx.index(), physical_op.to_string());
+                    idx
+                })
             };
 
-            let (s, d) = (add_node(s), add_node(d));
+            let (s, d) = (add_node(s, src_op), add_node(d, dst_op));
             graph.add_edge(s, d, *w);
         }
 
-        EvalPlan(graph)
+        self.closed_structs.pop();
+        self.enclosing_variables.pop();
+        self.group_as_attributes.pop();
+        // A malformed plan is left unscheduled, to be reported when it is executed.
+        let schedule = Schedule::new(&graph).ok();
+        EvalPlan(graph, None, vec![], schedule, details)
     }
 
-    fn get_eval_node<const STRICT: bool>(&mut self, be: &BindingsOp) -> Box<dyn Evaluable> {
-        match be {
-            BindingsOp::Scan(logical::Scan {
-                expr,
+    /// Whether `lg` refers to a variable named as one bound by the plans enclosing it.
+    fn is_correlated(&self, lg: &LogicalPlan<BindingsOp>) -> bool {
+        let mut referenced = vec![];
+        lg.operators()
+            .iter()
+            .for_each(|op| referenced_variables_op(op, &mut referenced));
+        referenced.iter().any(|name| {
+            self.enclosing_variables
+                .iter()
+                .flatten()
+                .any(|bound| name_matches(name, bound))
+        })
+    }
+
+    /// Returns the materialized subquery for `lg`, shared by all identical subqueries.
+    fn materialize<const STRICT: bool>(
+        &mut self,
+        lg: &LogicalPlan<BindingsOp>,
+    ) -> Rc<MaterializedSubQuery> {
+        if let Some((_, subquery)) = self.materialized.iter().find(|(plan, _)| plan == lg) {
+            return subquery.clone();
+        }
+        let subquery = Rc::new(MaterializedSubQuery::new(self.plan_eval::<{ STRICT }>(lg)));
+        self.materialized.push((lg.clone(), subquery.clone()));
+        subquery
+    }
+
+    /// Returns the variables of `lg` which are bound, by a scan over a collection whose type is
+    /// declared in the catalog, to elements of a closed struct type.
+    fn bound_closed_structs(&self, lg: &LogicalPlan<BindingsOp>) -> HashMap<String, StructType> {
+        let mut closed_structs = HashMap::new();
+        for op in lg.operators() {
+            let BindingsOp::Scan(logical::Scan {
+                expr: ValueExpr::VarRef(name) | ValueExpr::GlobalVarRef(name),
                 as_key,
-                at_key,
-            }) => {
-                if let Some(at_key) = at_key {
-                    Box::new(eval::evaluable::EvalScan::new_with_at_key(
-                        self.plan_values::<{ STRICT }>(expr),
-                        as_key,
-                        at_key,
-                    ))
-                } else {
-                    Box::new(eval::evaluable::EvalScan::new(
-                        self.plan_values::<{ STRICT }>(expr),
-                        as_key,
-                    ))
+                ..
+            }) = op
+            else {
+                continue;
+            };
+            let name = match name {
+                BindingsName::CaseSensitive(name) | BindingsName::CaseInsensitive(name) => name,
+            };
+            let Some(entry) = self.catalog.resolve_type(name) else {
+                continue;
+            };
+            let element = match entry.ty().kind() {
+                TypeKind::Bag(bag) => bag.element_type(),
+                TypeKind::Array(array) => array.element_type(),
+                _ => continue,
+            };
+            if let TypeKind::Struct(st) = element.kind() {
+                if !st.is_open() {
+                    closed_structs.insert(as_key.clone(), st.clone());
                 }
             }
+        }
+        closed_structs
+    }
+
+    /// If `expr` is a variable bound to a closed struct type which does not declare the attribute
+    /// navigated to by the first of `components`, returns the error of accessing it. The variable
+    /// is that bound by the innermost of the plans being compiled which binds it.
+    fn undeclared_attribute(
+        &self,
+        expr: &ValueExpr,
+        components: &[PathComponent],
+    ) -> Option<UndeclaredAttribute> {
+        let (ValueExpr::VarRef(var), Some(PathComponent::Key(attr))) = (expr, components.first())
+        else {
+            return None;
+        };
+        let (closed_structs, _) = self
+            .closed_structs
+            .iter()
+            .zip(&self.enclosing_variables)
+            .rev()
+            .find(|(_, bound)| bound.iter().any(|bound| name_matches(var, bound)))?;
+        let st = closed_structs
+            .iter()
+            .find_map(|(bound, st)| name_matches(var, bound).then_some(st))?;
+        attribute_type(st, attr).err()
+    }
+
+    fn get_eval_node<const STRICT: bool>(&mut self, be: &BindingsOp) -> Box<dyn Evaluable> {
+        match be {
+            BindingsOp::Scan(scan) => {
+                let expr = self.plan_values::<{ STRICT }>(&scan.expr);
+                eval_scan(scan, expr)
+            }
             BindingsOp::Project(logical::Project { exprs }) => {
                 let exprs: Vec<(_, _)> = exprs
                     .iter()
@@ -152,13 +404,22 @@ impl<'c> EvaluatorPlanner<'c> {
                 let expr = self.plan_values::<{ STRICT }>(expr);
                 Box::new(eval::evaluable::EvalSelectValue::new(expr))
             }
+            BindingsOp::Let(logical::Let { bindings }) => {
+                let bindings: Vec<(_, _)> = bindings
+                    .iter()
+                    .map(|(k, v)| (k.clone(), self.plan_values::<{ STRICT }>(v)))
+                    .collect();
+                Box::new(eval::evaluable::EvalLet::new(bindings))
+            }
             BindingsOp::Filter(logical::Filter { expr }) => Box::new(
                 eval::evaluable::EvalFilter::new(self.plan_values::<{ STRICT }>(expr)),
             ),
             BindingsOp::Having(logical::Having { expr }) => Box::new(
                 eval::evaluable::EvalHaving::new(self.plan_values::<{ STRICT }>(expr)),
             ),
-            BindingsOp::Distinct => Box::new(eval::evaluable::EvalDistinct::new()),
+            BindingsOp::Distinct => {
+                Box::new(eval::evaluable::EvalDistinct::new(self.sort_config.clone()))
+            }
             BindingsOp::Sink => Box::new(eval::evaluable::EvalSink { input: None }),
             BindingsOp::Pivot(logical::Pivot { key, value }) => {
                 Box::new(eval::evaluable::EvalPivot::new(
@@ -181,116 +442,59 @@ impl<'c> EvaluatorPlanner<'c> {
                 right,
                 on,
             }) => {
-                let kind = match kind {
-                    // Model CROSS JOINs as INNER JOINs as mentioned by equivalence mentioned in
-                    // section 5.3 of spec https://partiql.org/assets/PartiQL-Specification.pdf#subsection.5.3
-                    JoinKind::Cross | JoinKind::Inner => EvalJoinKind::Inner,
-                    JoinKind::Left => EvalJoinKind::Left,
-                    JoinKind::Right => EvalJoinKind::Right,
-                    JoinKind::Full => EvalJoinKind::Full,
-                };
+                let kind = eval_join_kind(kind);
                 let on = on
                     .as_ref()
                     .map(|on_condition| self.plan_values::<{ STRICT }>(on_condition));
+                let [left_filter, right_filter] = std::mem::take(&mut self.operand_filters);
+                let left = self.get_eval_node::<{ STRICT }>(left);
+                let right = self.get_eval_node::<{ STRICT }>(right);
                 Box::new(eval::evaluable::EvalJoin::new(
                     kind,
-                    self.get_eval_node::<{ STRICT }>(left),
-                    self.get_eval_node::<{ STRICT }>(right),
+                    self.filter_operand::<{ STRICT }>(left, left_filter),
+                    self.filter_operand::<{ STRICT }>(right, right_filter),
                     on,
                 ))
             }
-            BindingsOp::GroupBy(logical::GroupBy {
-                strategy,
-                exprs,
-                aggregate_exprs,
-                group_as_alias,
-            }) => {
-                let strategy = match strategy {
-                    GroupingStrategy::GroupFull => EvalGroupingStrategy::GroupFull,
-                    GroupingStrategy::GroupPartial => EvalGroupingStrategy::GroupPartial,
-                };
-                let exprs: HashMap<_, _> = exprs
-                    .iter()
-                    .map(|(k, v)| (k.clone(), self.plan_values::<{ STRICT }>(v)))
-                    .collect();
-                let aggregate_exprs = aggregate_exprs
-                    .iter()
-                    .map(|a_e| {
-                        let func = match (a_e.func.clone(), a_e.setq.clone()) {
-                            (AggFunc::AggAvg, logical::SetQuantifier::All) => {
-                                eval::evaluable::AggFunc::Avg(Avg::new_all())
-                            }
-                            (AggFunc::AggCount, logical::SetQuantifier::All) => {
-                                eval::evaluable::AggFunc::Count(Count::new_all())
-                            }
-                            (AggFunc::AggMax, logical::SetQuantifier::All) => {
-                                eval::evaluable::AggFunc::Max(Max::new_all())
-                            }
-                            (AggFunc::AggMin, logical::SetQuantifier::All) => {
-                                eval::evaluable::AggFunc::Min(Min::new_all())
-                            }
-                            (AggFunc::AggSum, logical::SetQuantifier::All) => {
-                                eval::evaluable::AggFunc::Sum(Sum::new_all())
-                            }
-                            (AggFunc::AggAvg, logical::SetQuantifier::Distinct) => {
-                                eval::evaluable::AggFunc::Avg(Avg::new_distinct())
-                            }
-                            (AggFunc::AggCount, logical::SetQuantifier::Distinct) => {
-                                eval::evaluable::AggFunc::Count(Count::new_distinct())
-                            }
-                            (AggFunc::AggMax, logical::SetQuantifier::Distinct) => {
-                                eval::evaluable::AggFunc::Max(Max::new_distinct())
-                            }
-                            (AggFunc::AggMin, logical::SetQuantifier::Distinct) => {
-                                eval::evaluable::AggFunc::Min(Min::new_distinct())
-                            }
-                            (AggFunc::AggSum, logical::SetQuantifier::Distinct) => {
-                                eval::evaluable::AggFunc::Sum(Sum::new_distinct())
-                            }
-                        };
-                        eval::evaluable::AggregateExpression {
-                            name: a_e.name.to_string(),
-                            expr: self.plan_values::<{ STRICT }>(&a_e.expr),
-                            func,
-                        }
-                    })
-                    .collect();
-                let group_as_alias = group_as_alias.as_ref().map(|alias| alias.to_string());
-                Box::new(eval::evaluable::EvalGroupBy {
-                    strategy,
-                    exprs,
-                    aggregate_exprs,
-                    group_as_alias,
-                    input: None,
-                })
-            }
+            BindingsOp::GroupBy(group_by) => self.plan_group_by::<{ STRICT }>(group_by, false),
             BindingsOp::ExprQuery(logical::ExprQuery { expr }) => {
                 let expr = self.plan_values::<{ STRICT }>(expr);
                 Box::new(eval::evaluable::EvalExprQuery::new(expr))
             }
-            BindingsOp::OrderBy(logical::OrderBy { specs }) => {
-                let cmp = specs
-                    .iter()
-                    .map(|spec| {
-                        let expr = self.plan_values::<{ STRICT }>(&spec.expr);
-                        let spec = match (&spec.order, &spec.null_order) {
-                            (SortSpecOrder::Asc, SortSpecNullOrder::First) => {
-                                EvalOrderBySortSpec::AscNullsFirst
-                            }
-                            (SortSpecOrder::Asc, SortSpecNullOrder::Last) => {
-                                EvalOrderBySortSpec::AscNullsLast
-                            }
-                            (SortSpecOrder::Desc, SortSpecNullOrder::First) => {
-                                EvalOrderBySortSpec::DescNullsFirst
-                            }
-                            (SortSpecOrder::Desc, SortSpecNullOrder::Last) => {
-                                EvalOrderBySortSpec::DescNullsLast
-                            }
-                        };
-                        EvalOrderBySortCondition { expr, spec }
-                    })
-                    .collect_vec();
-                Box::new(EvalOrderBy { cmp, input: None })
+            BindingsOp::OrderBy(logical::OrderBy { specs }) => Box::new(EvalOrderBy {
+                cmp: self.plan_sort_conditions::<{ STRICT }>(specs),
+                sort_config: self.sort_config.clone(),
+                input: None,
+            }),
+            BindingsOp::Window(logical::Window {
+                partition_by,
+                order_by,
+                name,
+                func,
+            }) => {
+                let mut plan_offset = |args: &logical::WindowOffset| EvalWindowOffset {
+                    expr: self.plan_values::<{ STRICT }>(&args.expr),
+                    offset: self.plan_values::<{ STRICT }>(&args.offset),
+                    default: self.plan_values::<{ STRICT }>(&args.default),
+                    strict: STRICT,
+                };
+                let func = match func {
+                    WindowFunction::RowNumber => EvalWindowFunction::RowNumber,
+                    WindowFunction::Rank => EvalWindowFunction::Rank,
+                    WindowFunction::DenseRank => EvalWindowFunction::DenseRank,
+                    WindowFunction::Lag(args) => EvalWindowFunction::Lag(plan_offset(args)),
+                    WindowFunction::Lead(args) => EvalWindowFunction::Lead(plan_offset(args)),
+                };
+                Box::new(EvalWindow {
+                    partition_by: partition_by
+                        .iter()
+                        .map(|expr| self.plan_values::<{ STRICT }>(expr))
+                        .collect(),
+                    cmp: self.plan_sort_conditions::<{ STRICT }>(order_by),
+                    name: name.clone(),
+                    func,
+                    input: None,
+                })
             }
             BindingsOp::LimitOffset(logical::LimitOffset { limit, offset }) => {
                 Box::new(eval::evaluable::EvalLimitOffset {
@@ -316,6 +520,237 @@ impl<'c> EvaluatorPlanner<'c> {
         }
     }
 
+    /// Plans the operator `op` of a physical plan, by the implementation it selects.
+    /// Filters the compiled operand of a join by the condition `filter`, if any.
+    fn filter_operand<const STRICT: bool>(
+        &mut self,
+        operand: Box<dyn Evaluable>,
+        filter: Option<ValueExpr>,
+    ) -> Box<dyn Evaluable> {
+        match filter {
+            Some(expr) => Box::new(EvalFilteredOperand {
+                operand,
+                filter: EvalFilter::new(self.plan_values::<{ STRICT }>(&expr)),
+            }),
+            None => operand,
+        }
+    }
+
+    fn get_physical_node<const STRICT: bool>(&mut self, op: &PhysicalOp) -> Box<dyn Evaluable> {
+        match op {
+            PhysicalOp::Logical(op) => self.get_eval_node::<{ STRICT }>(op),
+            PhysicalOp::NestedLoopJoin(join) => {
+                let on = join
+                    .on
+                    .as_ref()
+                    .map(|on_condition| self.plan_values::<{ STRICT }>(on_condition));
+                let [left_filter, right_filter] = std::mem::take(&mut self.operand_filters);
+                let left = self.get_physical_node::<{ STRICT }>(&join.left);
+                let right = self.get_physical_node::<{ STRICT }>(&join.right);
+                Box::new(eval::evaluable::EvalJoin::new(
+                    eval_join_kind(&join.kind),
+                    self.filter_operand::<{ STRICT }>(left, left_filter),
+                    self.filter_operand::<{ STRICT }>(right, right_filter),
+                    on,
+                ))
+            }
+            PhysicalOp::HashJoin(join) => {
+                let kind = match eval_join_kind(&join.kind) {
+                    kind @ (EvalJoinKind::Inner | EvalJoinKind::Left) => kind,
+                    _ => {
+                        return self.err(PlanningError::IllegalState(format!(
+                            "hash join of kind {:?}",
+                            join.kind
+                        )))
+                    }
+                };
+                let keys = join
+                    .keys
+                    .iter()
+                    .map(|(left, right)| {
+                        (
+                            self.plan_values::<{ STRICT }>(left),
+                            self.plan_values::<{ STRICT }>(right),
+                        )
+                    })
+                    .collect();
+                let [left_filter, right_filter] = std::mem::take(&mut self.operand_filters);
+                let left = self.get_physical_node::<{ STRICT }>(&join.left);
+                let right = self.get_physical_node::<{ STRICT }>(&join.right);
+                Box::new(EvalHashJoin {
+                    kind,
+                    keys,
+                    on: self.plan_values::<{ STRICT }>(&join.on),
+                    input: None,
+                    left: self.filter_operand::<{ STRICT }>(left, left_filter),
+                    right: self.filter_operand::<{ STRICT }>(right, right_filter),
+                })
+            }
+            PhysicalOp::SortMergeJoin(join) => {
+                let kind = match eval_join_kind(&join.kind) {
+                    kind @ (EvalJoinKind::Inner | EvalJoinKind::Left) => kind,
+                    _ => {
+                        return self.err(PlanningError::IllegalState(format!(
+                            "sort-merge join of kind {:?}",
+                            join.kind
+                        )))
+                    }
+                };
+                let keys = join
+                    .keys
+                    .iter()
+                    .map(|(left, right)| {
+                        (
+                            self.plan_values::<{ STRICT }>(left),
+                            self.plan_values::<{ STRICT }>(right),
+                        )
+                    })
+                    .collect();
+                let [left_filter, right_filter] = std::mem::take(&mut self.operand_filters);
+                let left = self.get_physical_node::<{ STRICT }>(&join.left);
+                let right = self.get_physical_node::<{ STRICT }>(&join.right);
+                Box::new(EvalSortMergeJoin {
+                    kind,
+                    keys,
+                    on: self.plan_values::<{ STRICT }>(&join.on),
+                    sorted: [join.left_sorted, join.right_sorted],
+                    sort_config: self.sort_config.clone(),
+                    input: None,
+                    left: self.filter_operand::<{ STRICT }>(left, left_filter),
+                    right: self.filter_operand::<{ STRICT }>(right, right_filter),
+                })
+            }
+            PhysicalOp::HashAggregate(group_by) => {
+                self.plan_group_by::<{ STRICT }>(group_by, false)
+            }
+            PhysicalOp::SortAggregate(group_by) => self.plan_group_by::<{ STRICT }>(group_by, true),
+        }
+    }
+
+    /// Plans `group_by`, grouping runs of equal keys if its input is `sorted` by them.
+    fn plan_group_by<const STRICT: bool>(
+        &mut self,
+        group_by: &logical::GroupBy,
+        sorted: bool,
+    ) -> Box<dyn Evaluable> {
+        let logical::GroupBy {
+            strategy,
+            exprs,
+            aggregate_exprs,
+            group_as_alias,
+        } = group_by;
+        let strategy = match strategy {
+            GroupingStrategy::GroupFull => EvalGroupingStrategy::GroupFull,
+            GroupingStrategy::GroupPartial => EvalGroupingStrategy::GroupPartial,
+        };
+        let exprs: HashMap<_, _> = exprs
+            .iter()
+            .map(|(k, v)| (k.clone(), self.plan_values::<{ STRICT }>(v)))
+            .collect();
+        let aggregate_exprs = aggregate_exprs
+            .iter()
+            .map(|a_e| {
+                let func = match (a_e.func.clone(), a_e.setq.clone()) {
+                    (AggFunc::AggAvg, logical::SetQuantifier::All) => {
+                        eval::evaluable::AggFunc::Avg(Avg::new_all())
+                    }
+                    (AggFunc::AggCount, logical::SetQuantifier::All) => {
+                        eval::evaluable::AggFunc::Count(Count::new_all())
+                    }
+                    (AggFunc::AggMax, logical::SetQuantifier::All) => {
+                        eval::evaluable::AggFunc::Max(Max::new_all())
+                    }
+                    (AggFunc::AggMin, logical::SetQuantifier::All) => {
+                        eval::evaluable::AggFunc::Min(Min::new_all())
+                    }
+                    (AggFunc::AggSum, logical::SetQuantifier::All) => {
+                        eval::evaluable::AggFunc::Sum(Sum::new_all())
+                    }
+                    (AggFunc::AggAvg, logical::SetQuantifier::Distinct) => {
+                        eval::evaluable::AggFunc::Avg(Avg::new_distinct())
+                    }
+                    (AggFunc::AggCount, logical::SetQuantifier::Distinct) => {
+                        eval::evaluable::AggFunc::Count(Count::new_distinct())
+                    }
+                    (AggFunc::AggMax, logical::SetQuantifier::Distinct) => {
+                        eval::evaluable::AggFunc::Max(Max::new_distinct())
+                    }
+                    (AggFunc::AggMin, logical::SetQuantifier::Distinct) => {
+                        eval::evaluable::AggFunc::Min(Min::new_distinct())
+                    }
+                    (AggFunc::AggSum, logical::SetQuantifier::Distinct) => {
+                        eval::evaluable::AggFunc::Sum(Sum::new_distinct())
+                    }
+                };
+                eval::evaluable::AggregateExpression {
+                    name: a_e.name.to_string(),
+                    expr: self.plan_values::<{ STRICT }>(&a_e.expr),
+                    func,
+                }
+            })
+            .collect();
+        let group_as_alias = group_as_alias.as_ref().map(|alias| alias.to_string());
+        let group_as_attributes = self.group_as_attributes.last().cloned().flatten();
+        Box::new(eval::evaluable::EvalGroupBy {
+            strategy,
+            exprs,
+            aggregate_exprs,
+            group_as_alias,
+            group_as_attributes,
+            sorted,
+            input: None,
+        })
+    }
+
+    /// Plans `scan`, of which only the values hinted by `limit` are consumed.
+    fn get_limited_scan<const STRICT: bool>(
+        &mut self,
+        scan: &logical::Scan,
+        limit: ScanLimit,
+    ) -> Box<dyn Evaluable> {
+        let expr = match &scan.expr {
+            ValueExpr::Call(logical::CallExpr {
+                name: CallName::ByName(name),
+                arguments,
+            }) => {
+                let args = arguments
+                    .iter()
+                    .map(|arg| self.plan_values::<{ STRICT }>(arg))
+                    .collect();
+                self.plan_catalog_function(name, args, limit)
+            }
+            expr => self.plan_values::<{ STRICT }>(expr),
+        };
+        eval_scan(scan, expr)
+    }
+
+    fn plan_sort_conditions<const STRICT: bool>(
+        &mut self,
+        specs: &[logical::SortSpec],
+    ) -> Vec<EvalOrderBySortCondition> {
+        specs
+            .iter()
+            .map(|spec| {
+                let expr = self.plan_values::<{ STRICT }>(&spec.expr);
+                let spec = match (&spec.order, &spec.null_order) {
+                    (SortSpecOrder::Asc, SortSpecNullOrder::First) => {
+                        EvalOrderBySortSpec::AscNullsFirst
+                    }
+                    (SortSpecOrder::Asc, SortSpecNullOrder::Last) => {
+                        EvalOrderBySortSpec::AscNullsLast
+                    }
+                    (SortSpecOrder::Desc, SortSpecNullOrder::First) => {
+                        EvalOrderBySortSpec::DescNullsFirst
+                    }
+                    (SortSpecOrder::Desc, SortSpecNullOrder::Last) => {
+                        EvalOrderBySortSpec::DescNullsLast
+                    }
+                };
+                EvalOrderBySortCondition { expr, spec }
+            })
+            .collect_vec()
+    }
+
     #[inline]
     fn err_nyi(&mut self, feature: &str) -> Box<ErrorNode> {
         let msg = format!("{feature} not yet implemented in evaluator");
@@ -352,6 +787,7 @@ impl<'c> EvaluatorPlanner<'c> {
                     BinaryOp::Gteq => EvalBinOp::Gteq,
                     BinaryOp::Lt => EvalBinOp::Lt,
                     BinaryOp::Lteq => EvalBinOp::Lteq,
+                    BinaryOp::IsDistinctFrom => EvalBinOp::IsDistinctFrom,
                     BinaryOp::Add => EvalBinOp::Add,
                     BinaryOp::Sub => EvalBinOp::Sub,
                     BinaryOp::Mul => EvalBinOp::Mul,
@@ -363,23 +799,43 @@ impl<'c> EvaluatorPlanner<'c> {
                 Box::new(EvalBinOpExpr { op, lhs, rhs })
             }
             ValueExpr::Lit(lit) => Box::new(EvalLitExpr { lit: lit.clone() }),
-            ValueExpr::Path(expr, components) => Box::new(EvalPath {
-                expr: self.plan_values::<{ STRICT }>(expr),
-                components: components
-                    .iter()
-                    .map(|c| match c {
-                        PathComponent::Key(k) => eval::expr::EvalPathComponent::Key(k.clone()),
-                        PathComponent::Index(i) => eval::expr::EvalPathComponent::Index(*i),
-                        PathComponent::KeyExpr(k) => eval::expr::EvalPathComponent::KeyExpr(
-                            self.plan_values::<{ STRICT }>(k),
-                        ),
-                        PathComponent::IndexExpr(i) => eval::expr::EvalPathComponent::IndexExpr(
-                            self.plan_values::<{ STRICT }>(i),
-                        ),
-                    })
-                    .collect(),
+            ValueExpr::Path(expr, components) => {
+                // References to undeclared attributes of closed structs are errors in strict mode
+                // and always evaluate to `MISSING` in permissive mode.
+                if let Some(err) = self.undeclared_attribute(expr, components) {
+                    if STRICT {
+                        return self.err(err.into());
+                    } else {
+                        return Box::new(EvalLitExpr {
+                            lit: Box::new(Value::Missing),
+                        });
+                    }
+                }
+                Box::new(EvalPath {
+                    expr: self.plan_values::<{ STRICT }>(expr),
+                    components: components
+                        .iter()
+                        .map(|c| match c {
+                            PathComponent::Key(k) => {
+                                eval::expr::EvalPathComponent::Key(k.clone().into())
+                            }
+                            PathComponent::Index(i) => eval::expr::EvalPathComponent::Index(*i),
+                            PathComponent::Expr(e) => eval::expr::EvalPathComponent::Expr(
+                                self.plan_values::<{ STRICT }>(e),
+                            ),
+                            PathComponent::Wildcard => eval::expr::EvalPathComponent::Wildcard,
+                            PathComponent::Unpivot => eval::expr::EvalPathComponent::Unpivot,
+                        })
+                        .collect(),
+                })
+            }
+            ValueExpr::VarRef(name) => Box::new(EvalVarRef {
+                name: name.clone().into(),
+            }),
+            ValueExpr::GlobalVarRef(name) => Box::new(EvalGlobalVarRef { name: name.clone() }),
+            ValueExpr::Parameter(parameter) => Box::new(EvalParameter {
+                parameter: parameter.clone(),
             }),
-            ValueExpr::VarRef(name) => Box::new(EvalVarRef { name: name.clone() }),
             ValueExpr::TupleExpr(expr) => {
                 let attrs: Vec<Box<dyn EvalExpr>> = expr
                     .attrs
@@ -427,12 +883,10 @@ impl<'c> EvaluatorPlanner<'c> {
                             return Box::new(ErrorNode::new());
                         }
                         let escape = escape.chars().next();
-                        let regex = like_to_re_pattern(pattern, escape);
-                        let regex_pattern =
-                            RegexBuilder::new(&regex).size_limit(RE_SIZE_LIMIT).build();
-                        match regex_pattern {
-                            Ok(pattern) => Box::new(EvalLikeMatch::new(value, pattern)),
+                        match self.patterns.like(pattern, escape) {
+                            Ok(regex) => Box::new(EvalLikeMatch::new(value, regex)),
                             Err(err) => {
+                                let regex = like_to_re_pattern(pattern, escape);
                                 self.errors.push(PlanningError::IllegalState(format!(
                                     "Invalid LIKE expression pattern: {regex}. Regex error: {err}"
                                 )));
@@ -447,14 +901,33 @@ impl<'c> EvaluatorPlanner<'c> {
                         let pattern = self.plan_values::<{ STRICT }>(pattern);
                         let escape = self.plan_values::<{ STRICT }>(escape);
                         Box::new(EvalLikeNonStringNonLiteralMatch::new(
-                            value, pattern, escape,
+                            value,
+                            pattern,
+                            escape,
+                            self.patterns.clone(),
                         ))
                     }
                 }
             }
-            ValueExpr::SubQueryExpr(expr) => Box::new(EvalSubQueryExpr::new(
-                self.plan_eval::<{ STRICT }>(&expr.plan),
-            )),
+            ValueExpr::SubQueryExpr(expr) => {
+                let coercion = match expr.coercion {
+                    logical::SubQueryCoercion::Collection => SubQueryCoercion::Collection,
+                    logical::SubQueryCoercion::Scalar => SubQueryCoercion::Scalar,
+                };
+                if self.materialize_subqueries && !self.is_correlated(&expr.plan) {
+                    Box::new(EvalMaterializedSubQueryExpr {
+                        subquery: self.materialize::<{ STRICT }>(&expr.plan),
+                        coercion,
+                        strict: STRICT,
+                    })
+                } else {
+                    Box::new(EvalSubQueryExpr::new(
+                        self.plan_eval::<{ STRICT }>(&expr.plan),
+                        coercion,
+                        STRICT,
+                    ))
+                }
+            }
             ValueExpr::SimpleCase(e) => {
                 let cases = e
                     .cases
@@ -692,6 +1165,16 @@ impl<'c> EvaluatorPlanner<'c> {
                             value: args.pop().unwrap(),
                         })
                     }
+                    CallName::ElementAt => {
+                        correct_num_args_or_err!(self, args, 2, "element_at");
+                        let index = args.pop().unwrap();
+                        let value = args.pop().unwrap();
+                        Box::new(EvalFnElementAt { value, index })
+                    }
+                    CallName::UtcNow => {
+                        correct_num_args_or_err!(self, args, 0, "utcnow");
+                        Box::new(EvalFnUtcNow {})
+                    }
                     CallName::ExtractYear => {
                         correct_num_args_or_err!(self, args, 1, "extract year");
                         Box::new(EvalFnExtractYear {
@@ -775,20 +1258,447 @@ impl<'c> EvaluatorPlanner<'c> {
                             elems: args.pop().unwrap(),
                         })
                     }
-                    CallName::ByName(name) => match self.catalog.get_function(name) {
-                        None => {
-                            self.errors.push(PlanningError::IllegalState(format!(
-                                "Function to exist in catalog {name}",
-                            )));
-                            Box::new(ErrorNode::new())
-                        }
-                        Some(function) => {
-                            let eval = function.plan_eval();
-                            Box::new(EvalFnBaseTableExpr { args, expr: eval })
-                        }
-                    },
+                    CallName::TupleUnion => Box::new(EvalFnTupleUnion { args }),
+                    CallName::ByName(name) => {
+                        self.plan_catalog_function(name, args, ScanLimit::default())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Plans a call to the function `name` of the catalog; `limit` only applies to table
+    /// functions.
+    fn plan_catalog_function(
+        &mut self,
+        name: &str,
+        args: Vec<Box<dyn EvalExpr>>,
+        limit: ScanLimit,
+    ) -> Box<dyn EvalExpr> {
+        match self
+            .catalog
+            .get_function(name)
+            .map(|entry| entry.function())
+        {
+            None => {
+                self.errors.push(PlanningError::IllegalState(format!(
+                    "Function to exist in catalog {name}",
+                )));
+                Box::new(ErrorNode::new())
+            }
+            Some(FunctionEntryFunction::Table(function)) => {
+                let expr = function.plan_eval();
+                Box::new(EvalFnBaseTableExpr { args, expr, limit })
+            }
+            Some(FunctionEntryFunction::Scalar(function)) => {
+                let expr = function.plan_eval();
+                Box::new(EvalFnScalarExpr { args, expr })
+            }
+            Some(FunctionEntryFunction::Aggregate()) => {
+                self.errors.push(PlanningError::NotYetImplemented(format!(
+                    "aggregate function {name} of the catalog"
+                )));
+                Box::new(ErrorNode::new())
+            }
+        }
+    }
+}
+
+fn eval_join_kind(kind: &JoinKind) -> EvalJoinKind {
+    match kind {
+        // Model CROSS JOINs as INNER JOINs as mentioned by equivalence mentioned in
+        // section 5.3 of spec https://partiql.org/assets/PartiQL-Specification.pdf#subsection.5.3
+        JoinKind::Cross | JoinKind::Inner => EvalJoinKind::Inner,
+        JoinKind::Left => EvalJoinKind::Left,
+        JoinKind::Right => EvalJoinKind::Right,
+        JoinKind::Full => EvalJoinKind::Full,
+    }
+}
+
+fn eval_scan(scan: &logical::Scan, expr: Box<dyn EvalExpr>) -> Box<dyn Evaluable> {
+    match &scan.at_key {
+        Some(at_key) => Box::new(eval::evaluable::EvalScan::new_with_at_key(
+            expr,
+            &scan.as_key,
+            at_key,
+        )),
+        None => Box::new(eval::evaluable::EvalScan::new(expr, &scan.as_key)),
+    }
+}
+
+/// The hints of how many values are consumed from the scans of `lg` over table functions whose
+/// values flow, one for one, into a constant `LIMIT`, e.g., the scan of
+/// `SELECT * FROM read_ion('t.ion') LIMIT 10`.
+fn scan_limits(lg: &LogicalPlan<BindingsOp>) -> HashMap<OpId, ScanLimit> {
+    fn constant(expr: &ValueExpr) -> Option<u64> {
+        match expr {
+            ValueExpr::Lit(lit) => match lit.as_ref() {
+                Value::Integer(n) => u64::try_from(*n).ok(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    let mut limits = HashMap::new();
+    for (id, op) in lg.operators_by_id() {
+        let BindingsOp::LimitOffset(logical::LimitOffset {
+            limit: Some(limit),
+            offset,
+        }) = op
+        else {
+            continue;
+        };
+        let (Some(limit), Some(offset)) =
+            (constant(limit), offset.as_ref().map_or(Some(0), constant))
+        else {
+            continue;
+        };
+
+        let flows = lg.flows();
+        let mut consumer = id;
+        loop {
+            let mut inputs = flows.iter().filter(|(_, dst, _)| *dst == consumer);
+            let (Some((src, _, _)), None) = (inputs.next(), inputs.next()) else {
+                break;
+            };
+            match lg.operator(*src) {
+                Some(
+                    BindingsOp::Project(_)
+                    | BindingsOp::ProjectAll
+                    | BindingsOp::ProjectValue(_)
+                    | BindingsOp::Let(_),
+                ) => consumer = *src,
+                Some(BindingsOp::Scan(logical::Scan {
+                    expr:
+                        ValueExpr::Call(logical::CallExpr {
+                            name: CallName::ByName(_),
+                            ..
+                        }),
+                    ..
+                })) => {
+                    limits.insert(*src, ScanLimit::new(limit, offset));
+                    break;
+                }
+                _ => break,
+            }
+        }
+    }
+    limits
+}
+
+/// The conditions of the filters flowing to the left and right operands of the joins of `lg`, by
+/// the ids of the joins. A join keeps its operands inline, so the operators flowing to it are not
+/// evaluated, but it evaluates the conditions of the filters between them and it on the bindings
+/// of its operands, e.g., the filters pushed down to its operands by an optimizer.
+fn operand_filters(lg: &LogicalPlan<BindingsOp>) -> HashMap<OpId, [Option<ValueExpr>; 2]> {
+    let mut filters: HashMap<OpId, [Option<ValueExpr>; 2]> = HashMap::new();
+    for (src, dst, branch_num) in lg.flows() {
+        if let (Some(BindingsOp::Filter(filter)), Some(BindingsOp::Join(_))) =
+            (lg.operator(src), lg.operator(dst))
+        {
+            if let Some(operand) = filters.entry(dst).or_default().get_mut(branch_num as usize) {
+                *operand = Some(filter.expr.clone());
+            }
+        }
+    }
+    filters
+}
+
+/// Whether `name` refers to `candidate`.
+pub(crate) fn name_matches(name: &BindingsName, candidate: &str) -> bool {
+    match name {
+        BindingsName::CaseSensitive(name) => name == candidate,
+        BindingsName::CaseInsensitive(name) => UniCase::new(name) == UniCase::new(candidate),
+    }
+}
+
+/// The names of the variables bound by the operators of `lg`, e.g., the `AS` and `AT` variables of
+/// its scans.
+fn bound_variables(lg: &LogicalPlan<BindingsOp>) -> Vec<String> {
+    fn bound_by(op: &BindingsOp, names: &mut Vec<String>) {
+        match op {
+            BindingsOp::Scan(logical::Scan { as_key, at_key, .. })
+            | BindingsOp::Unpivot(logical::Unpivot { as_key, at_key, .. }) => {
+                names.push(as_key.clone());
+                names.extend(at_key.clone());
+            }
+            BindingsOp::Let(logical::Let { bindings }) => {
+                names.extend(bindings.iter().map(|(name, _)| name.clone()))
+            }
+            BindingsOp::Join(logical::Join { left, right, .. }) => {
+                bound_by(left, names);
+                bound_by(right, names);
+            }
+            BindingsOp::GroupBy(logical::GroupBy {
+                exprs,
+                aggregate_exprs,
+                group_as_alias,
+                ..
+            }) => {
+                names.extend(exprs.keys().cloned());
+                names.extend(aggregate_exprs.iter().map(|agg| agg.name.clone()));
+                names.extend(group_as_alias.clone());
+            }
+            BindingsOp::Project(logical::Project { exprs }) => {
+                names.extend(exprs.iter().map(|(name, _)| name.clone()))
+            }
+            _ => {}
+        }
+    }
+
+    let mut names = vec![];
+    lg.operators()
+        .iter()
+        .for_each(|op| bound_by(op, &mut names));
+    names
+}
+
+/// Adds the names of the variables referred to by the expressions of `op`, including those of its
+/// subqueries, to `names`.
+pub(crate) fn referenced_variables_op(op: &BindingsOp, names: &mut Vec<BindingsName>) {
+    for_each_op_expr(op, &mut |expr| referenced_variables(expr, names));
+}
+
+/// Adds the names of the variables referred to by `expr`, including those of its subqueries, to
+/// `names`.
+pub(crate) fn referenced_variables(expr: &ValueExpr, names: &mut Vec<BindingsName>) {
+    match expr {
+        ValueExpr::VarRef(name) | ValueExpr::GlobalVarRef(name) => names.push(name.clone()),
+        ValueExpr::SubQueryExpr(logical::SubQueryExpr { plan, .. }) => plan
+            .operators()
+            .iter()
+            .for_each(|op| referenced_variables_op(op, names)),
+        _ => for_each_subexpr(expr, &mut |expr| referenced_variables(expr, names)),
+    }
+}
+
+/// Calls `f` on each of the expressions of `op`, including those of the operands of a join.
+fn for_each_op_expr(op: &BindingsOp, f: &mut dyn FnMut(&ValueExpr)) {
+    match op {
+        BindingsOp::Scan(logical::Scan { expr, .. })
+        | BindingsOp::Unpivot(logical::Unpivot { expr, .. })
+        | BindingsOp::Filter(logical::Filter { expr })
+        | BindingsOp::Having(logical::Having { expr })
+        | BindingsOp::ProjectValue(logical::ProjectValue { expr })
+        | BindingsOp::ExprQuery(logical::ExprQuery { expr }) => f(expr),
+        BindingsOp::Pivot(logical::Pivot { key, value }) => {
+            f(key);
+            f(value);
+        }
+        BindingsOp::Let(logical::Let { bindings: exprs })
+        | BindingsOp::Project(logical::Project { exprs }) => {
+            exprs.iter().for_each(|(_, expr)| f(expr))
+        }
+        BindingsOp::OrderBy(logical::OrderBy { specs }) => {
+            specs.iter().for_each(|spec| f(&spec.expr))
+        }
+        BindingsOp::Window(logical::Window {
+            partition_by,
+            order_by,
+            func,
+            ..
+        }) => {
+            partition_by.iter().for_each(&mut *f);
+            order_by.iter().for_each(|spec| f(&spec.expr));
+            if let WindowFunction::Lag(args) | WindowFunction::Lead(args) = func {
+                f(&args.expr);
+                f(&args.offset);
+                f(&args.default);
+            }
+        }
+        BindingsOp::LimitOffset(logical::LimitOffset { limit, offset }) => {
+            limit.iter().chain(offset).for_each(f)
+        }
+        BindingsOp::Join(logical::Join {
+            left, right, on, ..
+        }) => {
+            on.iter().for_each(&mut *f);
+            for_each_op_expr(left, f);
+            for_each_op_expr(right, f);
+        }
+        BindingsOp::GroupBy(logical::GroupBy {
+            exprs,
+            aggregate_exprs,
+            ..
+        }) => {
+            exprs.values().for_each(&mut *f);
+            aggregate_exprs.iter().for_each(|agg| f(&agg.expr));
+        }
+        BindingsOp::BagOp(_) | BindingsOp::ProjectAll | BindingsOp::Distinct | BindingsOp::Sink => {
+        }
+    }
+}
+
+/// Calls `f` on each of the immediate subexpressions of `expr`; the expressions of a subquery's
+/// plan are not visited.
+pub(crate) fn for_each_subexpr(expr: &ValueExpr, f: &mut dyn FnMut(&ValueExpr)) {
+    match expr {
+        ValueExpr::VarRef(_)
+        | ValueExpr::GlobalVarRef(_)
+        | ValueExpr::Lit(_)
+        | ValueExpr::Parameter(_)
+        | ValueExpr::SubQueryExpr(_) => {}
+        ValueExpr::UnExpr(_, expr) | ValueExpr::IsTypeExpr(IsTypeExpr { expr, .. }) => f(expr),
+        ValueExpr::BinaryExpr(_, lhs, rhs)
+        | ValueExpr::NullIfExpr(logical::NullIfExpr { lhs, rhs }) => {
+            f(lhs);
+            f(rhs);
+        }
+        ValueExpr::DynamicLookup(exprs) => exprs.iter().for_each(f),
+        ValueExpr::Path(root, components) => {
+            f(root);
+            for_each_path_expr(components, f);
+        }
+        ValueExpr::TupleExpr(logical::TupleExpr { attrs, values }) => {
+            attrs.iter().chain(values).for_each(f)
+        }
+        ValueExpr::ListExpr(logical::ListExpr { elements })
+        | ValueExpr::BagExpr(logical::BagExpr { elements })
+        | ValueExpr::CoalesceExpr(logical::CoalesceExpr { elements })
+        | ValueExpr::Call(logical::CallExpr {
+            arguments: elements,
+            ..
+        }) => elements.iter().for_each(f),
+        ValueExpr::BetweenExpr(logical::BetweenExpr { value, from, to }) => {
+            f(value);
+            f(from);
+            f(to);
+        }
+        ValueExpr::PatternMatchExpr(PatternMatchExpr { value, pattern }) => {
+            f(value);
+            if let Pattern::LikeNonStringNonLiteral(logical::LikeNonStringNonLiteralMatch {
+                pattern,
+                escape,
+            }) = pattern
+            {
+                f(pattern);
+                f(escape);
+            }
+        }
+        ValueExpr::SimpleCase(logical::SimpleCase {
+            expr,
+            cases,
+            default,
+        }) => {
+            f(expr);
+            cases.iter().for_each(|(when, then)| {
+                f(when);
+                f(then);
+            });
+            default.iter().for_each(|expr| f(expr));
+        }
+        ValueExpr::SearchedCase(SearchedCase { cases, default }) => {
+            cases.iter().for_each(|(when, then)| {
+                f(when);
+                f(then);
+            });
+            default.iter().for_each(|expr| f(expr));
+        }
+    }
+}
+
+/// Calls `f` on each of the expressions of the path `components`, e.g., `b || c` in `a[b || c]`.
+pub(crate) fn for_each_path_expr(components: &[PathComponent], f: &mut dyn FnMut(&ValueExpr)) {
+    for component in components {
+        match component {
+            PathComponent::Expr(expr) => f(expr),
+            PathComponent::Key(_)
+            | PathComponent::Index(_)
+            | PathComponent::Wildcard
+            | PathComponent::Unpivot => {}
+        }
+    }
+}
+
+/// The attributes of the elements of the group bound to `alias` by the `GROUP AS` of `lg` which
+/// `lg` may refer to, or `None` if it may refer to the group's elements as a whole.
+///
+/// The group is only considered to be referred to by attribute if it is solely scanned (e.g., by
+/// `FROM g AS v` in a subquery) and the variables bound to its elements are solely navigated by
+/// key (e.g., by `v.x.a`, referring to the attribute `x`).
+fn group_as_attributes(lg: &LogicalPlan<BindingsOp>, alias: &str) -> Option<Vec<BindingsName>> {
+    let mut uses = GroupAsUses {
+        alias,
+        elements: vec![],
+        attributes: vec![],
+        whole: false,
+    };
+    uses.find_elements(lg);
+    lg.operators().iter().for_each(|op| uses.visit_op(op));
+    (!uses.whole).then_some(uses.attributes)
+}
+
+struct GroupAsUses<'a> {
+    alias: &'a str,
+    /// The variables bound to the group's elements.
+    elements: Vec<String>,
+    attributes: Vec<BindingsName>,
+    whole: bool,
+}
+
+impl<'a> GroupAsUses<'a> {
+    fn is_group(&self, name: &BindingsName) -> bool {
+        name_matches(name, self.alias)
+    }
+
+    fn is_element(&self, name: &BindingsName) -> bool {
+        self.elements
+            .iter()
+            .any(|element| name_matches(name, element))
+    }
+
+    fn find_elements(&mut self, lg: &LogicalPlan<BindingsOp>) {
+        for op in lg.operators() {
+            if let BindingsOp::Scan(logical::Scan {
+                expr: ValueExpr::VarRef(name) | ValueExpr::GlobalVarRef(name),
+                as_key,
+                ..
+            }) = op
+            {
+                if self.is_group(name) {
+                    self.elements.push(as_key.clone());
+                }
+            }
+            for_each_op_expr(op, &mut |expr| self.find_elements_expr(expr));
+        }
+    }
+
+    fn find_elements_expr(&mut self, expr: &ValueExpr) {
+        match expr {
+            ValueExpr::SubQueryExpr(logical::SubQueryExpr { plan, .. }) => self.find_elements(plan),
+            _ => for_each_subexpr(expr, &mut |expr| self.find_elements_expr(expr)),
+        }
+    }
+
+    fn visit_op(&mut self, op: &BindingsOp) {
+        match op {
+            BindingsOp::Scan(logical::Scan {
+                expr: ValueExpr::VarRef(name) | ValueExpr::GlobalVarRef(name),
+                ..
+            }) if self.is_group(name) => {}
+            _ => for_each_op_expr(op, &mut |expr| self.visit_expr(expr)),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &ValueExpr) {
+        match expr {
+            ValueExpr::VarRef(name) | ValueExpr::GlobalVarRef(name) => {
+                self.whole |= self.is_group(name) || self.is_element(name);
+            }
+            ValueExpr::Path(root, components) => match (root.as_ref(), components.first()) {
+                (ValueExpr::VarRef(name), Some(PathComponent::Key(attribute)))
+                    if self.is_element(name) =>
+                {
+                    self.attributes.push(attribute.clone());
+                    for_each_path_expr(components, &mut |expr| self.visit_expr(expr));
                 }
+                _ => for_each_subexpr(expr, &mut |expr| self.visit_expr(expr)),
+            },
+            ValueExpr::SubQueryExpr(logical::SubQueryExpr { plan, .. }) => {
+                plan.operators().iter().for_each(|op| self.visit_op(op))
             }
+            _ => for_each_subexpr(expr, &mut |expr| self.visit_expr(expr)),
         }
     }
 }
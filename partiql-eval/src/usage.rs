@@ -0,0 +1,272 @@
+//! Reports of the attributes of tables used by a plan.
+//!
+//! An [`AttributeUsage`] report describes, for each table scanned by a logical plan (including the
+//! plans of its subqueries), which of the attributes of the table's values are read and, among
+//! them, which are filtered on (by `WHERE` or `HAVING`), joined on (by `ON`), and projected (by
+//! `SELECT` or `PIVOT`). It allows applications embedding the engine to attribute the cost of scans
+//! to attributes and to plan indexes.
+//!
+//! A value used as a whole, e.g., by `SELECT *` or `SELECT VALUE t`, is reported as the attribute
+//! [`WHOLE_VALUE`].
+//!
+//! ```
+//! use partiql_eval::usage::AttributeUsage;
+//! use partiql_logical::{BindingsOp, LogicalPlan, PathComponent, Scan, ValueExpr};
+//! use partiql_value::BindingsName;
+//!
+//! // `SELECT VALUE c.name FROM customers AS c`
+//! let mut plan = LogicalPlan::new();
+//! let scan = plan.add_operator(BindingsOp::Scan(Scan {
+//!     expr: ValueExpr::GlobalVarRef(BindingsName::CaseInsensitive("customers".to_string())),
+//!     as_key: "c".to_string(),
+//!     at_key: None,
+//! }));
+//! let project = plan.add_operator(BindingsOp::ProjectValue(partiql_logical::ProjectValue {
+//!     expr: ValueExpr::Path(
+//!         Box::new(ValueExpr::VarRef(BindingsName::CaseInsensitive("c".to_string()))),
+//!         vec![PathComponent::Key(BindingsName::CaseInsensitive("name".to_string()))],
+//!     ),
+//! }));
+//! let sink = plan.add_operator(BindingsOp::Sink);
+//! plan.add_flow(scan, project);
+//! plan.add_flow(project, sink);
+//!
+//! let usage = AttributeUsage::new(&plan);
+//! let customers = usage.table("customers").unwrap();
+//! assert!(customers.projected.contains("name"));
+//! assert!(customers.filtered.is_empty());
+//! ```
+
+use crate::plan::{for_each_path_expr, for_each_subexpr, name_matches};
+use partiql_logical as logical;
+use partiql_logical::{BindingsOp, LogicalPlan, PathComponent, ValueExpr};
+use partiql_value::BindingsName;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The name under which the use of a table's values as a whole is reported.
+pub const WHOLE_VALUE: &str = "*";
+
+/// A report of the attributes of tables used by a plan; see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributeUsage {
+    tables: BTreeMap<String, TableUsage>,
+}
+
+/// The attributes of a table used by a plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableUsage {
+    /// The attributes read by any expression.
+    pub read: BTreeSet<String>,
+    /// The attributes read by a `WHERE` or `HAVING` condition.
+    pub filtered: BTreeSet<String>,
+    /// The attributes read by a join condition.
+    pub joined: BTreeSet<String>,
+    /// The attributes read by a projection.
+    pub projected: BTreeSet<String>,
+}
+
+/// The clause in which an attribute is read.
+#[derive(Debug, Clone, Copy)]
+enum Role {
+    Read,
+    Filtered,
+    Joined,
+    Projected,
+}
+
+impl AttributeUsage {
+    /// Reports the attributes used by `plan`.
+    pub fn new(plan: &LogicalPlan<BindingsOp>) -> Self {
+        let mut collector = Collector::default();
+        collector.plan(plan);
+        collector.usage
+    }
+
+    /// The usage of the table `name`, if it is scanned by the plan.
+    pub fn table(&self, name: &str) -> Option<&TableUsage> {
+        self.tables.get(name)
+    }
+
+    /// The tables scanned by the plan, by name, along with their usage.
+    pub fn tables(&self) -> impl Iterator<Item = (&str, &TableUsage)> {
+        self.tables
+            .iter()
+            .map(|(name, usage)| (name.as_str(), usage))
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    /// The variables bound to the values of tables, along with the names of the tables.
+    variables: Vec<(String, String)>,
+    /// The index of the first of `variables` bound by the plan being collected.
+    scope: usize,
+    usage: AttributeUsage,
+}
+
+impl Collector {
+    fn plan(&mut self, plan: &LogicalPlan<BindingsOp>) {
+        let enclosing = std::mem::replace(&mut self.scope, self.variables.len());
+        plan.operators().iter().for_each(|op| self.bind(op));
+        plan.operators().iter().for_each(|op| self.op(op));
+        self.variables.truncate(self.scope);
+        self.scope = enclosing;
+    }
+
+    /// Binds the variables of the scans over tables of `op`.
+    fn bind(&mut self, op: &BindingsOp) {
+        match op {
+            BindingsOp::Scan(logical::Scan {
+                expr: ValueExpr::VarRef(table) | ValueExpr::GlobalVarRef(table),
+                as_key,
+                ..
+            }) => {
+                let table = match table {
+                    BindingsName::CaseSensitive(name) | BindingsName::CaseInsensitive(name) => name,
+                };
+                self.variables.push((as_key.clone(), table.clone()));
+                self.usage.tables.entry(table.clone()).or_default();
+            }
+            BindingsOp::Join(logical::Join { left, right, .. }) => {
+                self.bind(left);
+                self.bind(right);
+            }
+            _ => {}
+        }
+    }
+
+    fn op(&mut self, op: &BindingsOp) {
+        match op {
+            // The scan binding a variable to the values of a table reads none of their attributes.
+            BindingsOp::Scan(logical::Scan {
+                expr: ValueExpr::VarRef(_) | ValueExpr::GlobalVarRef(_),
+                ..
+            }) => {}
+            BindingsOp::Scan(logical::Scan { expr, .. })
+            | BindingsOp::Unpivot(logical::Unpivot { expr, .. })
+            | BindingsOp::ExprQuery(logical::ExprQuery { expr }) => self.expr(expr, Role::Read),
+            BindingsOp::Filter(logical::Filter { expr })
+            | BindingsOp::Having(logical::Having { expr }) => self.expr(expr, Role::Filtered),
+            BindingsOp::ProjectValue(logical::ProjectValue { expr }) => {
+                self.expr(expr, Role::Projected)
+            }
+            BindingsOp::Pivot(logical::Pivot { key, value }) => {
+                self.expr(key, Role::Projected);
+                self.expr(value, Role::Projected);
+            }
+            BindingsOp::Project(logical::Project { exprs }) => exprs
+                .iter()
+                .for_each(|(_, expr)| self.expr(expr, Role::Projected)),
+            BindingsOp::ProjectAll => {
+                let tables: Vec<_> = self.variables[self.scope..]
+                    .iter()
+                    .map(|(_, table)| table.clone())
+                    .collect();
+                for table in tables {
+                    self.record(&table, WHOLE_VALUE, Role::Projected);
+                }
+            }
+            BindingsOp::Let(logical::Let { bindings }) => bindings
+                .iter()
+                .for_each(|(_, expr)| self.expr(expr, Role::Read)),
+            BindingsOp::OrderBy(logical::OrderBy { specs }) => specs
+                .iter()
+                .for_each(|spec| self.expr(&spec.expr, Role::Read)),
+            BindingsOp::Window(logical::Window {
+                partition_by,
+                order_by,
+                func,
+                ..
+            }) => {
+                partition_by
+                    .iter()
+                    .for_each(|expr| self.expr(expr, Role::Read));
+                order_by
+                    .iter()
+                    .for_each(|spec| self.expr(&spec.expr, Role::Read));
+                if let logical::WindowFunction::Lag(args) | logical::WindowFunction::Lead(args) =
+                    func
+                {
+                    self.expr(&args.expr, Role::Read);
+                    self.expr(&args.offset, Role::Read);
+                    self.expr(&args.default, Role::Read);
+                }
+            }
+            BindingsOp::LimitOffset(logical::LimitOffset { limit, offset }) => limit
+                .iter()
+                .chain(offset)
+                .for_each(|expr| self.expr(expr, Role::Read)),
+            BindingsOp::Join(logical::Join {
+                left, right, on, ..
+            }) => {
+                on.iter().for_each(|expr| self.expr(expr, Role::Joined));
+                self.op(left);
+                self.op(right);
+            }
+            BindingsOp::GroupBy(logical::GroupBy {
+                exprs,
+                aggregate_exprs,
+                ..
+            }) => {
+                exprs.values().for_each(|expr| self.expr(expr, Role::Read));
+                aggregate_exprs
+                    .iter()
+                    .for_each(|agg| self.expr(&agg.expr, Role::Read));
+            }
+            BindingsOp::BagOp(_) | BindingsOp::Distinct | BindingsOp::Sink => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &ValueExpr, role: Role) {
+        match expr {
+            ValueExpr::VarRef(var) => {
+                if let Some(table) = self.table_of(var) {
+                    self.record(&table, WHOLE_VALUE, role);
+                }
+            }
+            ValueExpr::Path(root, components) => {
+                let table = match root.as_ref() {
+                    ValueExpr::VarRef(var) => self.table_of(var),
+                    _ => None,
+                };
+                match table {
+                    Some(table) => {
+                        let attribute = match components.first() {
+                            Some(PathComponent::Key(
+                                BindingsName::CaseSensitive(name)
+                                | BindingsName::CaseInsensitive(name),
+                            )) => name.as_str(),
+                            _ => WHOLE_VALUE,
+                        };
+                        self.record(&table, attribute, role);
+                    }
+                    None => self.expr(root, role),
+                }
+                for_each_path_expr(components, &mut |expr| self.expr(expr, role));
+            }
+            ValueExpr::SubQueryExpr(logical::SubQueryExpr { plan, .. }) => self.plan(plan),
+            _ => for_each_subexpr(expr, &mut |expr| self.expr(expr, role)),
+        }
+    }
+
+    /// The table whose values the innermost variable named `var` is bound to, if any.
+    fn table_of(&self, var: &BindingsName) -> Option<String> {
+        self.variables
+            .iter()
+            .rev()
+            .find(|(bound, _)| name_matches(var, bound))
+            .map(|(_, table)| table.clone())
+    }
+
+    fn record(&mut self, table: &str, attribute: &str, role: Role) {
+        let usage = self.usage.tables.entry(table.to_string()).or_default();
+        usage.read.insert(attribute.to_string());
+        let roles = match role {
+            Role::Read => return,
+            Role::Filtered => &mut usage.filtered,
+            Role::Joined => &mut usage.joined,
+            Role::Projected => &mut usage.projected,
+        };
+        roles.insert(attribute.to_string());
+    }
+}
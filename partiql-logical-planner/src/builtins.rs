@@ -367,6 +367,36 @@ fn function_call_def_cardinality() -> CallDef {
     }
 }
 
+fn function_call_def_element_at() -> CallDef {
+    CallDef {
+        names: vec!["element_at"],
+        overloads: vec![CallSpec {
+            input: vec![CallSpecArg::Positional, CallSpecArg::Positional],
+            output: Box::new(|args| {
+                logical::ValueExpr::Call(logical::CallExpr {
+                    name: logical::CallName::ElementAt,
+                    arguments: args,
+                })
+            }),
+        }],
+    }
+}
+
+fn function_call_def_utcnow() -> CallDef {
+    CallDef {
+        names: vec!["utcnow"],
+        overloads: vec![CallSpec {
+            input: vec![],
+            output: Box::new(|args| {
+                logical::ValueExpr::Call(logical::CallExpr {
+                    name: logical::CallName::UtcNow,
+                    arguments: args,
+                })
+            }),
+        }],
+    }
+}
+
 fn function_call_def_extract() -> CallDef {
     CallDef {
         names: vec!["extract"],
@@ -670,6 +700,11 @@ impl FnSymTab {
             .get(&fn_name.into())
             .and_then(|name| self.calls.get(name))
     }
+
+    /// The definitions of the functions in the table.
+    pub fn calls(&self) -> impl Iterator<Item = &CallDef> {
+        self.calls.values()
+    }
 }
 
 pub fn function_call_def() -> FnSymTab {
@@ -692,6 +727,8 @@ pub fn function_call_def() -> FnSymTab {
         function_call_def_abs(),
         function_call_def_mod(),
         function_call_def_cardinality(),
+        function_call_def_element_at(),
+        function_call_def_utcnow(),
         function_call_def_extract(),
         function_call_def_coll_avg(),
         function_call_def_coll_count(),
@@ -1,3 +1,28 @@
+//! Lowering of PartiQL ASTs to logical plans.
+//!
+//! # Panics
+//!
+//! Lowering doesn't panic explicitly: the `clippy::panic`, `clippy::todo`,
+//! `clippy::unimplemented`, and `clippy::unreachable` lints are denied in this crate, and errors
+//! lowering an AST are reported as an [`AstTransformationError`]. Lowering still unwraps the
+//! internal stacks it lowers AST nodes with, relying on the shape of the ASTs the parser produces;
+//! ASTs built or modified otherwise may make it panic.
+//!
+//! Lowering recurses over the AST, with the depth limited by the parser (see
+//! `partiql_parser::ParserBuilder::max_nesting_depth`) except for chains of binary operators other
+//! than set operators, so lowering such a chain that is tens of thousands of operators long may
+//! overflow the stack.
+
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::panic,
+        clippy::todo,
+        clippy::unimplemented,
+        clippy::unreachable
+    )
+)]
+
 use crate::lower::AstToLogical;
 
 use partiql_ast_passes::error::AstTransformationError;
@@ -5,10 +30,18 @@ use partiql_ast_passes::name_resolver::NameResolver;
 use partiql_logical as logical;
 use partiql_parser::Parsed;
 
+use partiql_catalog::call_defs::CallDef;
 use partiql_catalog::Catalog;
 
 mod builtins;
 mod lower;
+pub mod optimizer;
+pub mod typing;
+
+/// The definitions of the functions built into the planner, e.g., `lower` or `substring`.
+pub fn builtin_functions() -> impl Iterator<Item = &'static CallDef> {
+    builtins::FN_SYM_TAB.calls()
+}
 
 pub struct LogicalPlanner<'c> {
     catalog: &'c dyn Catalog,
@@ -35,23 +68,30 @@ impl<'c> LogicalPlanner<'c> {
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
-    use partiql_ast_passes::error::AstTransformationError;
+    use partiql_ast_passes::error::{AstTransformError, AstTransformationError};
     use partiql_catalog::PartiqlCatalog;
 
     use partiql_eval::env::basic::MapBindings;
 
+    use partiql_eval::eval::sort::ExternalSortConfig;
     use partiql_eval::plan;
     use partiql_eval::plan::EvaluationMode;
 
     use crate::LogicalPlanner;
+    use partiql_ast::arena::Arena;
     use partiql_logical as logical;
     use partiql_logical::{BindingsOp, LogicalPlan};
-    use partiql_parser::{Parsed, Parser};
-    use partiql_value::{bag, tuple, Value};
+    use partiql_parser::{ParseError, Parsed, Parser};
+    use partiql_value::{bag, list, tuple, Value};
+
+    /// An arena outliving the test, for the ASTs returned by [`parse`].
+    fn arena() -> &'static Arena {
+        Box::leak(Box::default())
+    }
 
     #[track_caller]
-    fn parse(text: &str) -> Parsed {
-        Parser::default().parse(text).unwrap()
+    fn parse(text: &str) -> Parsed<'_> {
+        Parser::default().parse(text, arena()).unwrap()
     }
 
     #[track_caller]
@@ -60,7 +100,11 @@ mod tests {
     ) -> Result<logical::LogicalPlan<logical::BindingsOp>, AstTransformationError> {
         let catalog = PartiqlCatalog::default();
         let planner = LogicalPlanner::new(&catalog);
-        planner.lower(parsed)
+        let lowered = planner.lower(parsed)?;
+        if let Err(err) = lowered.validate() {
+            panic!("invalid plan {lowered}: {:?}", err.errors);
+        }
+        Ok(lowered)
     }
 
     #[track_caller]
@@ -123,6 +167,2051 @@ mod tests {
         });
     }
 
+    #[test]
+    pub fn test_having() {
+        let query = "\
+        SELECT firstName, SUM(balance) AS total \
+        FROM customer \
+        GROUP BY firstName \
+        HAVING COUNT(*) > 1";
+        let parsed = parse(query);
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let out = evaluate(lowered, data_customer());
+
+        println!("{:?}", &out);
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![
+                tuple![("firstName", "jason"), ("total", 70)],
+                tuple![("firstName", "miriam"), ("total", 30)],
+            ];
+            assert_eq!(*bag, expected);
+        });
+    }
+
+    #[test]
+    pub fn test_let() {
+        let query = "\
+        SELECT firstName, doubled \
+        FROM customer \
+        LET balance * 2 AS doubled, doubled > 0 AS positive \
+        WHERE positive";
+        let parsed = parse(query);
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let out = evaluate(lowered, data_customer());
+
+        println!("{:?}", &out);
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![
+                tuple![("firstName", "jason"), ("doubled", 200)],
+                tuple![("firstName", "miriam"), ("doubled", 40)],
+                tuple![("firstName", "miriam"), ("doubled", 20)],
+            ];
+            assert_eq!(*bag, expected);
+        });
+    }
+
+    #[test]
+    pub fn test_pivot() {
+        let query = "PIVOT c.balance AT c.firstName FROM customer AS c WHERE c.id > 3";
+        let parsed = parse(query);
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let out = evaluate(lowered, data_customer());
+
+        println!("{:?}", &out);
+        assert_matches!(out, Value::Tuple(tuple) => {
+            let expected = tuple![("jason", 100), ("sisko", 0)];
+            assert_eq!(*tuple, expected);
+        });
+
+        let out =
+            evaluate_query("FROM <<{'k': 'a', 'v': 1}, {'k': 'b', 'v': 2}>> AS t PIVOT t.v AT t.k");
+        println!("{:?}", &out);
+        assert_matches!(out, Value::Tuple(tuple) => {
+            let expected = tuple![("a", 1), ("b", 2)];
+            assert_eq!(*tuple, expected);
+        });
+    }
+
+    #[test]
+    pub fn test_unpivot() {
+        let out = evaluate_query("SELECT v, k FROM UNPIVOT {'a': 1, 'b': 2} AS v AT k");
+        println!("{:?}", &out);
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![tuple![("v", 1), ("k", "a")], tuple![("v", 2), ("k", "b")]];
+            assert_eq!(*bag, expected);
+        });
+
+        // the `UNPIVOT` expression may reference variables bound by preceding `FROM` items
+        let query = "\
+        SELECT c.id AS id, attr, val \
+        FROM customer AS c, UNPIVOT c AS val AT attr \
+        WHERE c.id = 1 AND attr <> 'id'";
+        let parsed = parse(query);
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let out = evaluate(lowered, data_customer());
+        println!("{:?}", &out);
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![
+                tuple![("id", 1), ("attr", "firstName"), ("val", "miriam")],
+                tuple![("id", 1), ("attr", "balance"), ("val", 10)],
+            ];
+            assert_eq!(*bag, expected);
+        });
+    }
+
+    #[test]
+    pub fn test_joins() {
+        // note that paths into the `NULL` bindings of outer joins produce `MISSING`, which is
+        // omitted from the projected tuples
+        let bindings = || {
+            let mut bindings = data_customer();
+            let orders = bag![
+                tuple![("id", 5), ("total", 7)],
+                tuple![("id", 2), ("total", 3)],
+                tuple![("id", 9), ("total", 1)],
+            ];
+            bindings.insert("orders", orders.into());
+            bindings
+        };
+        let run = |query: &str| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            evaluate(lowered, bindings())
+        };
+
+        let out = run("SELECT c.id AS id, o.total AS total \
+            FROM customer AS c INNER JOIN orders AS o ON c.id = o.id");
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![tuple![("id", 5), ("total", 7)], tuple![("id", 2), ("total", 3)]];
+            assert_eq!(*bag, expected);
+        });
+
+        let out = run("SELECT c.id AS id, o.total AS total \
+            FROM customer AS c JOIN orders AS o USING (id)");
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![tuple![("id", 5), ("total", 7)], tuple![("id", 2), ("total", 3)]];
+            assert_eq!(*bag, expected);
+        });
+
+        let out = run("SELECT c.id AS cid, o.id AS oid \
+            FROM customer AS c LEFT OUTER JOIN orders AS o USING (id)");
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![
+                tuple![("cid", 5), ("oid", 5)],
+                tuple![("cid", 4)],
+                tuple![("cid", 3)],
+                tuple![("cid", 2), ("oid", 2)],
+                tuple![("cid", 1)],
+            ];
+            assert_eq!(*bag, expected);
+        });
+
+        let out = run("SELECT c.id AS cid, o.id AS oid \
+            FROM customer AS c RIGHT JOIN orders AS o ON c.id = o.id");
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![
+                tuple![("cid", 5), ("oid", 5)],
+                tuple![("cid", 2), ("oid", 2)],
+                tuple![("oid", 9)],
+            ];
+            assert_eq!(*bag, expected);
+        });
+
+        let out = run("SELECT c.id AS cid, o.id AS oid \
+            FROM customer AS c FULL OUTER JOIN orders AS o ON c.id = o.id");
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![
+                tuple![("cid", 5), ("oid", 5)],
+                tuple![("cid", 4)],
+                tuple![("cid", 3)],
+                tuple![("cid", 2), ("oid", 2)],
+                tuple![("cid", 1)],
+                tuple![("oid", 9)],
+            ];
+            assert_eq!(*bag, expected);
+        });
+    }
+
+    #[test]
+    pub fn test_lateral() {
+        let orders = "<<{'id': 1, 'items': [1, 2]}, {'id': 2, 'items': []}>>";
+
+        // later `FROM` items may reference the variables of earlier ones
+        let out = evaluate_query(&format!(
+            "SELECT o.id AS id, i FROM {orders} AS o, o.items AS i"
+        ));
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![tuple![("id", 1), ("i", 1)], tuple![("id", 1), ("i", 2)]];
+            assert_eq!(*bag, expected);
+        });
+
+        let out = evaluate_query(&format!(
+            "SELECT o.id AS id, i FROM {orders} AS o LEFT JOIN LATERAL o.items AS i ON true"
+        ));
+        assert_matches!(out, Value::Bag(bag) => {
+            let expected = bag![
+                tuple![("id", 1), ("i", 1)],
+                tuple![("id", 1), ("i", 2)],
+                tuple![("id", 2), ("i", Value::Null)],
+            ];
+            assert_eq!(*bag, expected);
+        });
+
+        // ... except for the right side of `RIGHT` and `FULL` joins
+        let query =
+            format!("SELECT o.id AS id, i FROM {orders} AS o FULL JOIN o.items AS i ON true");
+        let parsed = parse(&query);
+        assert_matches!(lower(&parsed), Err(AstTransformationError { errors }) => {
+            assert_matches!(errors.as_slice(), [AstTransformError::IllegalLateralReference(_)]);
+        });
+    }
+
+    #[test]
+    pub fn test_illegal_aggregation() {
+        for query in [
+            "SELECT a FROM t WHERE SUM(a) > 1",
+            "SELECT COUNT(*) AS n FROM t GROUP BY SUM(a)",
+            "SELECT SUM(MAX(a)) AS s FROM t",
+            "SELECT x FROM t AS x JOIN u AS y ON SUM(x.a) > 1",
+            "SELECT x FROM t AS x LIMIT COUNT(*)",
+        ] {
+            let parsed = parse(query);
+            assert_matches!(lower(&parsed), Err(AstTransformationError { errors }) => {
+                assert_matches!(errors.as_slice(), [AstTransformError::IllegalAggregation(_)]);
+            }, "{query}");
+        }
+
+        // aggregates of subqueries are legal where those of the query are not
+        let parsed = parse(
+            "SELECT x.a AS a, SUM(x.b) AS s FROM t AS x \
+             WHERE x.a > (SELECT MAX(y.a) FROM u AS y) GROUP BY x.a HAVING COUNT(*) > 1 \
+             ORDER BY SUM(x.b)",
+        );
+        assert!(lower(&parsed).is_ok());
+    }
+
+    #[test]
+    pub fn test_subqueries() {
+        let run = |query: &str| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            evaluate(lowered, data_customer())
+        };
+
+        // scalar subqueries are coerced to their single value
+        let out = run("SELECT (SELECT MAX(c.balance) FROM customer AS c) AS m FROM <<1>> AS x");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("m", 100)]]);
+        });
+        let out = run("(SELECT MAX(c.balance) FROM customer AS c) + 1");
+        assert_matches!(out, Value::Integer(101));
+        let out = run("SELECT c.id AS id FROM customer AS c \
+            WHERE c.balance = (SELECT MAX(d.balance) FROM customer AS d)");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("id", 5)]]);
+        });
+        let out = run("CASE WHEN (SELECT x FROM <<true>> AS x) THEN 1 ELSE 2 END");
+        assert_matches!(out, Value::Integer(1));
+        let out = run("CASE (SELECT MAX(c.id) FROM customer AS c) WHEN 5 THEN 'max' END");
+        assert_matches!(out, Value::String(s) => assert_eq!(*s, "max"));
+        let out = run("UPPER((SELECT c.firstName FROM customer AS c WHERE c.id = 4))");
+        assert_matches!(out, Value::String(s) => assert_eq!(*s, "SISKO"));
+        let out = run("SUBSTRING((SELECT c.firstName FROM customer AS c WHERE c.id = 4) FROM 2)");
+        assert_matches!(out, Value::String(s) => assert_eq!(*s, "isko"));
+
+        // correlated subqueries
+        let out = run("SELECT c.id AS id, \
+            (SELECT COUNT(*) FROM customer AS d WHERE d.firstName = c.firstName) AS n \
+            FROM customer AS c WHERE c.id > 3");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("id", 5), ("n", 2)], tuple![("id", 4), ("n", 1)]]);
+        });
+
+        // subqueries in collection contexts are not coerced
+        let out = run("SELECT c.id AS id FROM customer AS c \
+            WHERE c.id IN (SELECT VALUE d.id FROM customer AS d WHERE d.balance > 10)");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("id", 5)], tuple![("id", 2)]]);
+        });
+        let out =
+            run("SELECT s.x AS x FROM (SELECT c.id AS x FROM customer AS c) AS s WHERE s.x < 3");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("x", 2)], tuple![("x", 1)]]);
+        });
+        let out = run("CARDINALITY((SELECT c.id FROM customer AS c))");
+        assert_matches!(out, Value::Integer(5));
+        let out = run("COLL_MAX((SELECT VALUE c.id FROM customer AS c))");
+        assert_matches!(out, Value::Integer(5));
+
+        // scalar subqueries returning more than one row, or a row of more than one attribute, are
+        // errors in strict mode, and result in `MISSING` in permissive mode
+        let catalog = PartiqlCatalog::default();
+        for query in [
+            "(SELECT c.id FROM customer AS c) + 1",
+            "SELECT c.id AS id FROM customer AS c \
+                WHERE c.id = (SELECT d.id, d.balance FROM customer AS d WHERE d.id = 1)",
+        ] {
+            let lowered = lower(&parse(query)).expect("Expect no lower error");
+            let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Strict, &catalog)
+                .compile(&lowered)
+                .expect("Expect no plan error");
+            let err = plan
+                .execute_mut(data_customer())
+                .expect_err("Expect eval error");
+            assert_matches!(
+                &err.errors[..],
+                [
+                    partiql_eval::error::EvaluationError::CardinalityViolation(_),
+                    ..
+                ],
+                "{query}"
+            );
+        }
+        let out = run("(SELECT c.id FROM customer AS c) + 1");
+        assert_matches!(out, Value::Missing);
+        let out = run("SELECT c.id AS id FROM customer AS c \
+            WHERE c.id = (SELECT d.id, d.balance FROM customer AS d WHERE d.id = 1)");
+        assert_matches!(out, Value::Bag(bag) => assert!(bag.is_empty()));
+    }
+
+    #[test]
+    pub fn test_aggregate_set_quantifiers() {
+        let run = |query: &str| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            evaluate(lowered, data_customer())
+        };
+
+        let out = run(
+            "SELECT COUNT(DISTINCT c.firstName) AS n, COUNT(ALL c.firstName) AS m \
+            FROM customer AS c",
+        );
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("n", 3), ("m", 5)]]);
+        });
+        let out = run(
+            "SELECT name AS name, SUM(DISTINCT c.id % 2) AS s, SUM(ALL c.id % 2) AS t \
+            FROM customer AS c GROUP BY c.firstName AS name HAVING COUNT(*) > 1",
+        );
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(
+                *bag,
+                bag![tuple![("name", "jason"), ("s", 1), ("t", 2)], tuple![("name", "miriam"), ("s", 1), ("t", 1)]]
+            );
+        });
+    }
+
+    #[test]
+    pub fn test_coll_aggregates() {
+        assert_matches!(evaluate_query("COLL_SUM([1, 2, 2, 3])"), Value::Integer(8));
+        assert_matches!(
+            evaluate_query("COLL_SUM(DISTINCT [1, 2, 2, 3])"),
+            Value::Integer(6)
+        );
+        assert_matches!(
+            evaluate_query("coll_count(ALL <<1, 1, NULL>>)"),
+            Value::Integer(2)
+        );
+        assert_matches!(
+            evaluate_query("COLL_COUNT(DISTINCT <<1, 1, NULL>>)"),
+            Value::Integer(1)
+        );
+        assert_matches!(
+            evaluate_query("COLL_MIN(DISTINCT [3, 1, 2])"),
+            Value::Integer(1)
+        );
+        assert_matches!(evaluate_query("COLL_MAX([3, 1, 2])"), Value::Integer(3));
+        assert_matches!(
+            evaluate_query("COLL_AVG(DISTINCT [1, 1, 2]) = 1.5"),
+            Value::Boolean(true)
+        );
+
+        let parsed = parse(
+            "SELECT c.firstName AS name FROM customer AS c \
+            WHERE c.balance > COLL_AVG(SELECT VALUE d.balance FROM customer AS d)",
+        );
+        let out = evaluate(
+            lower(&parsed).expect("Expect no lower error"),
+            data_customer(),
+        );
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("name", "jason")]]);
+        });
+    }
+
+    #[test]
+    pub fn test_is_distinct_from() {
+        let cases = [
+            ("1 IS DISTINCT FROM 1", false),
+            ("1 IS DISTINCT FROM 2", true),
+            ("1 IS DISTINCT FROM NULL", true),
+            ("NULL IS DISTINCT FROM NULL", false),
+            ("NULL IS DISTINCT FROM MISSING", false),
+            ("MISSING IS NOT DISTINCT FROM 1", false),
+            ("NULL IS NOT DISTINCT FROM NULL", true),
+            ("[1, NULL] IS NOT DISTINCT FROM [1, NULL]", true),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(evaluate_query(query), Value::Boolean(expected), "{query}");
+        }
+    }
+
+    #[test]
+    pub fn test_collection_functions() {
+        let cases = [
+            ("CARDINALITY([1, 2, 3])", Value::from(3)),
+            ("CARDINALITY(<<>>)", Value::from(0)),
+            ("CARDINALITY(NULL)", Value::Null),
+            ("CARDINALITY(MISSING)", Value::Missing),
+            ("exists([1])", Value::from(true)),
+            ("exists(<<>>)", Value::from(false)),
+            ("exists(NULL)", Value::Null),
+            ("exists(1)", Value::Missing),
+            ("ELEMENT_AT([1, 2, 3], 0)", Value::from(1)),
+            ("ELEMENT_AT([1, 2, 3], 2)", Value::from(3)),
+            ("ELEMENT_AT([1, 2, 3], 3)", Value::Missing),
+            ("ELEMENT_AT([1, 2, 3], -1)", Value::Missing),
+            ("ELEMENT_AT(<<1>>, 0)", Value::Missing),
+            ("ELEMENT_AT([1], NULL)", Value::Null),
+            ("ELEMENT_AT(NULL, MISSING)", Value::Missing),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(evaluate_query(query), expected, "{query}");
+        }
+    }
+
+    #[test]
+    pub fn test_wildcard_paths() {
+        let out = evaluate_query("[{'a': 1}, {'a': 2}, {'b': 3}][*].a");
+        assert_eq!(out, Value::from(bag![1, 2]));
+
+        let out = evaluate_query("{'a': {'x': 1}, 'b': {'x': 2}, 'c': 3}.*.x");
+        assert_eq!(out, Value::from(bag![1, 2]));
+
+        let out = evaluate_query("{'a': [[1, 2], [3]]}.a[*][*]");
+        assert_eq!(out, Value::from(bag![1, 2, 3]));
+
+        let out = evaluate_query("{'a': 5}.a[*]");
+        assert_eq!(out, Value::from(bag![5]));
+
+        let parsed = parse("SELECT VALUE c.* FROM customer AS c WHERE c.id = 1");
+        let out = evaluate(
+            lower(&parsed).expect("Expect no lower error"),
+            data_customer(),
+        );
+        assert_eq!(out, Value::from(bag![bag![1, "miriam", 10]]));
+    }
+
+    #[test]
+    pub fn test_path_expressions() {
+        let cases = [
+            (r#"{'Some Key': 1}['Some Key']"#, Value::from(1)),
+            (r#"{'Some Key': 1}['some key']"#, Value::Missing),
+            (r#"{'Ab': 1}."Ab""#, Value::from(1)),
+            (r#"{'Ab': 1}."ab""#, Value::Missing),
+            (r#"{'Ab': 1}.ab"#, Value::from(1)),
+            ("[1, 2, 3][1 + 1]", Value::from(3)),
+            ("[1, 2, 3][1 + 2]", Value::Missing),
+            ("{'ab': 1}['a' || 'b']", Value::from(1)),
+            ("{'ab': 1}['A' || 'b']", Value::Missing),
+            ("[1, 2, 3]['a' || 'b']", Value::Missing),
+            ("{'ab': [1, 2]}['a' || 'b'][0 + 1]", Value::from(2)),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(evaluate_query(query), expected, "{query}");
+        }
+    }
+
+    #[test]
+    pub fn test_closed_structs() {
+        use partiql_catalog::{Catalog, TypeEnvEntry};
+        use partiql_types::{
+            BagType, PartiqlType, StructConstraint, StructField, StructType, TypeKind,
+        };
+
+        let mut catalog = PartiqlCatalog::default();
+        let customer = StructType::new(vec![
+            StructConstraint::Open(false),
+            StructConstraint::Fields(StructField::new("id", PartiqlType::new(TypeKind::Int))),
+            StructConstraint::Fields(StructField::new(
+                "firstName",
+                PartiqlType::new(TypeKind::String),
+            )),
+            StructConstraint::Fields(StructField::new("balance", PartiqlType::new(TypeKind::Int))),
+        ]);
+        let ty = PartiqlType::new_bag(BagType::new(Box::new(PartiqlType::new_struct(customer))));
+        catalog
+            .add_type_entry(TypeEnvEntry::new("customer", &[], ty))
+            .expect("type entry");
+
+        let run = |query: &str, mode: EvaluationMode| {
+            let parsed = parse(query);
+            let lowered = LogicalPlanner::new(&catalog)
+                .lower(&parsed)
+                .expect("Expect no lower error");
+            plan::EvaluatorPlanner::new(mode, &catalog)
+                .compile(&lowered)
+                .map(|mut plan| plan.execute_mut(data_customer()).expect("eval").result)
+        };
+
+        let query = "SELECT c.id AS id, c.nickname AS nick FROM customer AS c WHERE c.id = 1";
+        let out = run(query, EvaluationMode::Permissive).expect("Expect no plan error");
+        assert_eq!(out, Value::from(bag![tuple![("id", 1)]]));
+
+        let errs = run(query, EvaluationMode::Strict).expect_err("Expect plan error");
+        assert_eq!(
+            errs.errors,
+            vec![partiql_eval::error::PlanningError::UndeclaredAttribute(
+                partiql_catalog::UndeclaredAttribute("nickname".to_string())
+            )]
+        );
+        // reported as typing reports it
+        assert_eq!(
+            errs.errors[0].to_string(),
+            "`nickname` is not an attribute of a closed struct"
+        );
+
+        let query = "SELECT c.FIRSTNAME AS name FROM customer AS c WHERE c.id = 1";
+        let out = run(query, EvaluationMode::Strict).expect("Expect no plan error");
+        assert_eq!(out, Value::from(bag![tuple![("name", "miriam")]]));
+
+        // A subquery's variable shadows that of its enclosing query...
+        let query = "SELECT VALUE (SELECT VALUE c.nickname FROM [{'nickname': 'm'}] AS c) \
+                     FROM customer AS c WHERE c.id = 1";
+        let out = run(query, EvaluationMode::Strict).expect("Expect no plan error");
+        assert_eq!(out, Value::from(bag![bag!["m"]]));
+
+        // ...whose variables are still bound within the subquery otherwise.
+        let query = "SELECT VALUE (SELECT VALUE c.nickname FROM [1] AS x) FROM customer AS c";
+        assert!(run(query, EvaluationMode::Strict).is_err());
+
+        // The variables of a compiled plan are not bound in the plans compiled after it.
+        let mut planner = plan::EvaluatorPlanner::new(EvaluationMode::Strict, &catalog);
+        let compile = |planner: &mut plan::EvaluatorPlanner, query: &str| {
+            let lowered = LogicalPlanner::new(&catalog)
+                .lower(&parse(query))
+                .expect("Expect no lower error");
+            planner.compile(&lowered).map(|_| ())
+        };
+        assert!(compile(&mut planner, "SELECT c.id AS id FROM customer AS c").is_ok());
+        let query = "SELECT c.nickname AS nick FROM [{'nickname': 'm'}] AS c";
+        assert!(compile(&mut planner, query).is_ok());
+    }
+
+    #[test]
+    pub fn test_utcnow() {
+        use partiql_eval::eval::sources::FixedClock;
+        use partiql_eval::eval::BasicContext;
+        use partiql_value::DateTime;
+        use std::num::NonZeroU8;
+
+        let now = DateTime::from_ymdhms_nano_offset_minutes(
+            2023,
+            NonZeroU8::new(3).unwrap(),
+            14,
+            15,
+            9,
+            26,
+            0,
+            Some(0),
+        );
+        let parsed = parse("SELECT VALUE UTCNOW() FROM [1, 2] AS x");
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let catalog = PartiqlCatalog::default();
+        let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+            .compile(&lowered)
+            .expect("Expect no plan error");
+        let ctx =
+            BasicContext::new(MapBindings::default()).with_clock(FixedClock::new(now.clone()));
+        let out = plan
+            .execute_mut_in(ctx)
+            .expect("Expect no eval error")
+            .result;
+        assert_eq!(out, Value::from(bag![now.clone(), now]));
+    }
+
+    #[test]
+    pub fn test_parameters() {
+        use partiql_eval::eval::{BasicContext, Parameters};
+
+        let parsed = parse(
+            "SELECT VALUE c.id FROM customer AS c WHERE c.firstName = ? AND c.balance > :min",
+        );
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let catalog = PartiqlCatalog::default();
+        let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+            .compile(&lowered)
+            .expect("Expect no plan error");
+
+        let parameters = Parameters::positional([Value::from("jason")]).with_named("MIN", 0);
+        let ctx = BasicContext::new(data_customer()).with_parameters(parameters);
+        let out = plan.execute_mut_in(ctx).expect("Expect no eval error");
+        assert_eq!(out.result, Value::from(bag![5]));
+
+        let ctx = BasicContext::new(data_customer());
+        assert!(plan.execute_mut_in(ctx).is_err());
+    }
+
+    #[test]
+    pub fn test_select_list_order() {
+        let parsed = parse(
+            "SELECT c.firstName AS z, c.id AS a, c.nope AS m, c.balance AS b \
+             FROM customer AS c WHERE c.id = 2",
+        );
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let out = evaluate(lowered, data_customer());
+        let rows: Vec<_> = out.into_iter().collect();
+        let [Value::Tuple(row)] = &rows[..] else {
+            panic!("expected a single tuple, got {rows:?}");
+        };
+        assert_eq!(row.attrs().collect::<Vec<_>>(), vec!["z", "a", "b"]);
+        assert_eq!(row.value_at(0), Some(&Value::from("miriam")));
+        assert_eq!(row.value_at(1), Some(&Value::from(2)));
+        assert_eq!(row.value_at(2), Some(&Value::from(20)));
+    }
+
+    #[test]
+    pub fn test_progress() {
+        use partiql_eval::eval::BasicContext;
+
+        let parsed = parse("SELECT c.id FROM customer AS c WHERE c.balance > 0");
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let catalog = PartiqlCatalog::default();
+        let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+            .compile(&lowered)
+            .expect("Expect no plan error");
+
+        let mut reports = vec![];
+        let out = plan
+            .execute_mut_with_progress(BasicContext::new(data_customer()), |progress| {
+                let last = progress.operators.last().expect("an evaluated operator");
+                reports.push((
+                    progress.operators.len(),
+                    progress.total_operators,
+                    last.operator,
+                    last.rows,
+                    progress.rows(),
+                ));
+            })
+            .expect("Expect no eval error");
+        assert_eq!(out.result.into_iter().count(), 3);
+        assert_eq!(
+            reports,
+            vec![
+                (1, 4, "EvalScan", Some(5), 5),
+                (2, 4, "EvalFilter", Some(3), 8),
+                (3, 4, "EvalSelect", Some(3), 11),
+                (4, 4, "EvalSink", Some(3), 14),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_pattern_cache() {
+        use partiql_eval::eval::expr::PatternCache;
+
+        let parsed = parse(
+            "SELECT VALUE c.id FROM customer AS c \
+             WHERE c.firstName LIKE c.firstName OR c.firstName LIKE 'j%'",
+        );
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let catalog = PartiqlCatalog::default();
+        let cache = PatternCache::new();
+        for _ in 0..2 {
+            let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+                .with_pattern_cache(cache.clone())
+                .compile(&lowered)
+                .expect("Expect no plan error");
+            let out = plan
+                .execute_mut(data_customer())
+                .expect("Expect no eval error");
+            assert_eq!(out.result.into_iter().count(), 5);
+        }
+        // `'j%'` and each of the distinct names
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[test]
+    pub fn test_limited_scan() {
+        use partiql_catalog::call_defs::{CallDef, CallSpec};
+        use partiql_catalog::{
+            BaseTableExpr, BaseTableExprResult, BaseTableFunctionInfo, Catalog, TableFunction,
+        };
+        use std::borrow::Cow;
+
+        // An unbounded table of the natural numbers, which can only be scanned when limited.
+        #[derive(Debug)]
+        struct Naturals(CallDef);
+
+        impl BaseTableFunctionInfo for Naturals {
+            fn call_def(&self) -> &CallDef {
+                &self.0
+            }
+
+            fn plan_eval(&self) -> Box<dyn BaseTableExpr> {
+                Box::new(EvalNaturals)
+            }
+        }
+
+        #[derive(Debug)]
+        struct EvalNaturals;
+
+        impl BaseTableExpr for EvalNaturals {
+            fn evaluate(&self, _args: &[Cow<Value>]) -> BaseTableExprResult<'_> {
+                Ok(Box::new((0..).map(|n: i64| Ok(Value::from(n)))))
+            }
+        }
+
+        let mut catalog = PartiqlCatalog::default();
+        let naturals = Naturals(CallDef {
+            names: vec!["naturals"],
+            overloads: vec![CallSpec {
+                input: vec![],
+                output: Box::new(|args| {
+                    logical::ValueExpr::Call(logical::CallExpr {
+                        name: logical::CallName::ByName("naturals".to_string()),
+                        arguments: args,
+                    })
+                }),
+            }],
+        });
+        catalog
+            .add_table_function(TableFunction::new(Box::new(naturals)))
+            .expect("Expect no catalog error");
+
+        let parsed = parse("SELECT VALUE n * 10 FROM naturals() AS n LIMIT 3 OFFSET 2");
+        let lowered = LogicalPlanner::new(&catalog)
+            .lower(&parsed)
+            .expect("Expect no lower error");
+        let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+            .compile(&lowered)
+            .expect("Expect no plan error");
+        let out = plan
+            .execute_mut(MapBindings::default())
+            .expect("Expect no eval error");
+        assert_eq!(out.result, Value::from(bag![20, 30, 40]));
+    }
+
+    #[test]
+    pub fn test_scalar_function() {
+        use partiql_catalog::call_defs::{CallDef, CallSpec, CallSpecArg};
+        use partiql_catalog::{
+            Catalog, ScalarFnExpr, ScalarFnExprResult, ScalarFunction, ScalarFunctionInfo,
+        };
+        use std::borrow::Cow;
+
+        #[derive(Debug)]
+        struct Twice(CallDef);
+
+        impl ScalarFunctionInfo for Twice {
+            fn call_def(&self) -> &CallDef {
+                &self.0
+            }
+
+            fn plan_eval(&self) -> Box<dyn ScalarFnExpr> {
+                Box::new(EvalTwice)
+            }
+        }
+
+        #[derive(Debug)]
+        struct EvalTwice;
+
+        impl ScalarFnExpr for EvalTwice {
+            fn evaluate(&self, args: &[Cow<Value>]) -> ScalarFnExprResult {
+                match args.iter().map(AsRef::as_ref).collect::<Vec<_>>()[..] {
+                    [Value::Integer(n)] => Ok(Value::from(n * 2)),
+                    _ => Err("twice takes one integer".into()),
+                }
+            }
+        }
+
+        let mut catalog = PartiqlCatalog::default();
+        let twice = Twice(CallDef {
+            names: vec!["twice", "double"],
+            overloads: vec![CallSpec {
+                input: vec![CallSpecArg::Positional],
+                output: Box::new(|args| {
+                    logical::ValueExpr::Call(logical::CallExpr {
+                        name: logical::CallName::ByName("twice".to_string()),
+                        arguments: args,
+                    })
+                }),
+            }],
+        });
+        catalog
+            .add_scalar_function(ScalarFunction::new(Box::new(twice)))
+            .expect("Expect no catalog error");
+
+        let run = |query: &str| {
+            let parsed = parse(query);
+            let lowered = LogicalPlanner::new(&catalog)
+                .lower(&parsed)
+                .expect("Expect no lower error");
+            plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+                .compile(&lowered)
+                .expect("Expect no plan error")
+                .execute_mut(MapBindings::default())
+        };
+
+        let out = run("SELECT VALUE [twice(n), DOUBLE(n)] FROM <<1, 2>> AS n")
+            .expect("Expect no eval error");
+        assert_eq!(out.result, Value::from(bag![list![2, 2], list![4, 4]]));
+
+        let err = run("SELECT VALUE twice(n) FROM <<1, 'a'>> AS n").expect_err("Expect eval error");
+        assert_eq!(
+            err.errors,
+            vec![partiql_eval::error::EvaluationError::FunctionFailed(
+                "twice takes one integer".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    pub fn test_attribute_usage() {
+        use partiql_eval::usage::{AttributeUsage, WHOLE_VALUE};
+
+        let parsed = parse(
+            "SELECT c.firstName, o.total FROM customer AS c JOIN orders AS o ON c.id = o.customer \
+             WHERE c.balance > 0 AND EXISTS (SELECT * FROM returns AS r WHERE r.id = o.id) \
+             ORDER BY o.placed",
+        );
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let usage = AttributeUsage::new(&lowered);
+
+        let names =
+            |attrs: &std::collections::BTreeSet<String>| attrs.iter().cloned().collect::<Vec<_>>();
+        let customer = usage.table("customer").expect("customer usage");
+        assert_eq!(names(&customer.read), ["balance", "firstName", "id"]);
+        assert_eq!(names(&customer.filtered), ["balance"]);
+        assert_eq!(names(&customer.joined), ["id"]);
+        assert_eq!(names(&customer.projected), ["firstName"]);
+
+        let orders = usage.table("orders").expect("orders usage");
+        assert_eq!(names(&orders.read), ["customer", "id", "placed", "total"]);
+        assert_eq!(names(&orders.filtered), ["id"]);
+        assert_eq!(names(&orders.joined), ["customer"]);
+        assert_eq!(names(&orders.projected), ["total"]);
+
+        let returns = usage.table("returns").expect("returns usage");
+        assert_eq!(names(&returns.filtered), ["id"]);
+        assert_eq!(names(&returns.projected), [WHOLE_VALUE]);
+        assert_eq!(usage.tables().count(), 3);
+    }
+
+    #[test]
+    pub fn test_reexecute() {
+        let parsed = parse(
+            "SELECT c.id, (SELECT VALUE d.id FROM customer AS d WHERE d.firstName = c.firstName) AS ids \
+             FROM customer AS c WHERE c.balance > 0",
+        );
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let catalog = PartiqlCatalog::default();
+        let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+            .compile(&lowered)
+            .expect("Expect no plan error");
+
+        let first = plan
+            .execute_mut(data_customer())
+            .expect("Expect no eval error");
+        let second = plan
+            .execute_mut(data_customer())
+            .expect("Expect no eval error");
+        assert_eq!(first.result, second.result);
+        assert_eq!(first.result.into_iter().count(), 3);
+    }
+
+    #[test]
+    pub fn test_with() {
+        let out = evaluate_query(
+            "WITH x AS (SELECT VALUE v FROM [1, 2] AS v) SELECT VALUE y * 10 FROM x AS y",
+        );
+        assert_eq!(out, Value::from(bag![10, 20]));
+
+        let parsed = parse(
+            "WITH rich AS (SELECT VALUE c FROM customer AS c WHERE c.balance > 10), \
+                  names AS (SELECT VALUE r.firstName FROM rich AS r) \
+             SELECT VALUE n FROM names AS n",
+        );
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        let out = evaluate(lowered, data_customer());
+        assert_eq!(out, Value::from(bag!["jason", "miriam"]));
+
+        // the names of a `WITH` clause's common table expressions are unique
+        for query in [
+            "WITH x AS (SELECT VALUE 1 FROM [1] AS v), x AS (SELECT VALUE 2 FROM [1] AS v) \
+             SELECT VALUE y FROM x AS y",
+            "WITH x AS (SELECT VALUE 1 FROM [1] AS v), \"X\" AS (SELECT VALUE 2 FROM [1] AS v) \
+             SELECT VALUE y FROM x AS y",
+        ] {
+            let err = lower(&parse(query)).expect_err("Expect lower error");
+            assert_matches!(
+                &err.errors[..],
+                [AstTransformError::DuplicateName(_)],
+                "{query}"
+            );
+        }
+        // but for quoted names that differ in case
+        let out = evaluate_query(
+            "WITH \"x\" AS (SELECT VALUE 1 FROM [1] AS v), \"X\" AS (SELECT VALUE 2 FROM [1] AS v) \
+             SELECT VALUE y FROM \"X\" AS y",
+        );
+        assert_eq!(out, Value::from(bag![2]));
+    }
+
+    #[test]
+    pub fn test_identifier_case() {
+        let query_bag = |query: &str| {
+            let lowered = lower(&parse(query)).expect("Expect no lower error");
+            match evaluate(lowered, data_customer()) {
+                Value::Bag(bag) => bag.len(),
+                out => panic!("{query}: {out:?}"),
+            }
+        };
+
+        // regular identifiers are looked up case-insensitively
+        assert_eq!(query_bag("SELECT VALUE C.FIRSTNAME FROM CUSTOMER AS c"), 5);
+        assert_eq!(
+            query_bag("SELECT VALUE c.firstname FROM customer AS C WHERE C.ID < 3"),
+            2
+        );
+        // quoted identifiers are looked up case-sensitively
+        assert_eq!(
+            query_bag(r#"SELECT VALUE c."firstName" FROM "customer" AS c"#),
+            5
+        );
+        assert_eq!(query_bag(r#"SELECT c."FIRSTNAME" FROM customer AS c"#), 5);
+        assert_eq!(
+            evaluate(
+                lower(&parse(r#"SELECT VALUE c."FIRSTNAME" FROM customer AS c"#)).unwrap(),
+                data_customer()
+            ),
+            Value::from(bag![Value::Missing; 5])
+        );
+        assert_eq!(
+            evaluate(
+                lower(&parse(r#"SELECT VALUE c.id FROM "Customer" AS c"#)).unwrap(),
+                data_customer()
+            ),
+            Value::from(bag![Value::Missing])
+        );
+    }
+
+    #[test]
+    pub fn test_scope_qualifier() {
+        let mut bindings = data_customer();
+        bindings.insert("c", Value::from(bag![tuple![("firstName", "global")]]));
+
+        // an unqualified `FROM` source is resolved against the global environment first
+        let query = "SELECT VALUE x.firstName FROM customer AS c, c AS x";
+        let lowered = lower(&parse(query)).expect("Expect no lower error");
+        let out = evaluate(lowered, bindings.clone());
+        assert_eq!(out, Value::from(bag!["global"; 5]));
+
+        // `@` resolves it against the lexical scope first
+        let query = "SELECT VALUE x.firstName FROM customer AS c, @c AS x WHERE c.id < 3";
+        let lowered = lower(&parse(query)).expect("Expect no lower error");
+        let out = evaluate(lowered, bindings);
+        assert_eq!(out, Value::from(bag!["miriam", "miriam"]));
+    }
+
+    #[test]
+    pub fn test_window() {
+        let run = |query: &str| {
+            let lowered = lower(&parse(query)).expect("Expect no lower error");
+            evaluate(lowered, data_customer())
+        };
+
+        let out = run(
+            "SELECT c.id, ROW_NUMBER() OVER (PARTITION BY c.firstName ORDER BY c.id) AS n \
+             FROM customer AS c",
+        );
+        let expected = bag![
+            tuple![("id", 5), ("n", 2)],
+            tuple![("id", 4), ("n", 1)],
+            tuple![("id", 3), ("n", 1)],
+            tuple![("id", 2), ("n", 2)],
+            tuple![("id", 1), ("n", 1)],
+        ];
+        assert_eq!(out, Value::from(expected));
+
+        let out = run("SELECT c.id, RANK() OVER (ORDER BY c.firstName) AS r, \
+                    DENSE_RANK() OVER (ORDER BY c.firstName) AS d \
+             FROM customer AS c");
+        let expected = bag![
+            tuple![("id", 5), ("r", 1), ("d", 1)],
+            tuple![("id", 3), ("r", 1), ("d", 1)],
+            tuple![("id", 2), ("r", 3), ("d", 2)],
+            tuple![("id", 1), ("r", 3), ("d", 2)],
+            tuple![("id", 4), ("r", 5), ("d", 3)],
+        ];
+        assert_eq!(out, Value::from(expected));
+
+        let out = run("SELECT VALUE [c.id, LAG(c.id) OVER (ORDER BY c.id), \
+                    LEAD(c.id, 2, -1) OVER (ORDER BY c.id)] \
+             FROM customer AS c ORDER BY c.id");
+        let expected = list![
+            list![1, Value::Null, 3],
+            list![2, 1, 4],
+            list![3, 2, 5],
+            list![4, 3, -1],
+            list![5, 4, -1],
+        ];
+        assert_eq!(out, Value::from(expected));
+
+        // offsets which are not non-negative integers are errors in strict mode, and result in
+        // `MISSING` in permissive mode
+        let catalog = PartiqlCatalog::default();
+        for query in [
+            "SELECT VALUE LAG(c.id, -1) OVER (ORDER BY c.id) FROM customer AS c",
+            "SELECT VALUE LEAD(c.id, 'a') OVER (ORDER BY c.id) FROM customer AS c",
+        ] {
+            let lowered = lower(&parse(query)).expect("Expect no lower error");
+            let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Strict, &catalog)
+                .compile(&lowered)
+                .expect("Expect no plan error");
+            let err = plan
+                .execute_mut(data_customer())
+                .expect_err("Expect eval error");
+            assert_matches!(
+                &err.errors[..],
+                [partiql_eval::error::EvaluationError::InvalidArgument(_)],
+                "{query}"
+            );
+
+            let out = run(query);
+            assert_eq!(out, Value::from(bag![Value::Missing; 5]), "{query}");
+        }
+    }
+
+    #[test]
+    pub fn test_subquery_materialization() {
+        use partiql_eval::eval::sources::Clock;
+        use partiql_eval::eval::BasicContext;
+        use partiql_value::DateTime;
+        use std::cell::Cell;
+
+        /// A [`Clock`] which advances by a second each time it is read.
+        #[derive(Debug, Default)]
+        struct TickingClock(Cell<u8>);
+
+        impl Clock for TickingClock {
+            fn now(&self) -> DateTime {
+                let secs = self.0.get();
+                self.0.set(secs + 1);
+                let month = std::num::NonZeroU8::new(1).unwrap();
+                DateTime::from_ymdhms_nano_offset_minutes(2023, month, 1, 0, 0, secs, 0, Some(0))
+            }
+        }
+
+        let run = |query: &str, materialize: bool| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            let catalog = PartiqlCatalog::default();
+            let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+                .with_subquery_materialization(materialize)
+                .compile(&lowered)
+                .expect("Expect no plan error");
+            let mut results = vec![];
+            for _ in 0..2 {
+                let ctx = BasicContext::new(data_customer()).with_clock(TickingClock::default());
+                let out = plan.execute_mut_in(ctx).expect("Expect no eval error");
+                results.push(out.result);
+            }
+            results
+        };
+
+        let query = "WITH t AS (SELECT VALUE UTCNOW() FROM [1] AS x) \
+                     SELECT VALUE a = b FROM t AS a, t AS b";
+        // Each evaluation of the plan reads a fresh clock, so a materialized result must not
+        // outlive the evaluation it was computed by.
+        assert_eq!(run(query, true), vec![Value::from(bag![true]); 2]);
+        assert_eq!(run(query, false), vec![Value::from(bag![false]); 2]);
+
+        // Correlated subqueries are evaluated for each of their inputs.
+        let query = "SELECT VALUE c.id FROM customer AS c \
+                     WHERE EXISTS (SELECT VALUE d FROM customer AS d \
+                                   WHERE d.firstName = c.firstName AND d.balance > c.balance)";
+        assert_eq!(run(query, true), vec![Value::from(bag![3, 1]); 2]);
+    }
+
+    #[test]
+    pub fn test_group_as_projection() {
+        let run = |query: &str, project: bool| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            let catalog = PartiqlCatalog::default();
+            let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+                .with_group_as_projection(project)
+                .compile(&lowered)
+                .expect("Expect no plan error");
+            plan.execute_mut(data_customer())
+                .expect("Expect no eval error")
+                .result
+        };
+
+        let query = "SELECT name, (SELECT VALUE v.c.balance FROM g AS v) AS balances \
+                     FROM customer AS c, [1, 2] AS o \
+                     WHERE o = 1 \
+                     GROUP BY c.firstName AS name GROUP AS g";
+        let expected = Value::from(bag![
+            tuple![("name", "jason"), ("balances", bag![100, -30])],
+            tuple![("name", "sisko"), ("balances", bag![0])],
+            tuple![("name", "miriam"), ("balances", bag![20, 10])],
+        ]);
+        assert_eq!(run(query, true), expected);
+        assert_eq!(run(query, false), expected);
+
+        // Groups referred to as a whole keep all of the input bindings
+        let query = "SELECT name, g FROM customer AS c, [1] AS o \
+                     WHERE c.id = 4 GROUP BY c.firstName AS name GROUP AS g";
+        let expected = Value::from(bag![tuple![
+            ("name", "sisko"),
+            (
+                "g",
+                bag![tuple![
+                    (
+                        "c",
+                        tuple![("id", 4), ("firstName", "sisko"), ("balance", 0)]
+                    ),
+                    ("o", 1)
+                ]]
+            )
+        ]]);
+        assert_eq!(run(query, true), expected);
+        assert_eq!(run(query, false), expected);
+    }
+
+    #[test]
+    pub fn test_information_schema() {
+        let catalog = PartiqlCatalog::default();
+        let planner = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+            .with_subquery_materialization(true);
+        let mut bindings = data_customer();
+        planner
+            .information_schema()
+            .with_scalar_functions(crate::builtin_functions())
+            .bind(&mut bindings);
+
+        let run = |query: &str| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            evaluate(lowered, bindings.clone())
+        };
+
+        let tables = run("SELECT VALUE t.name FROM information_schema.tables AS t");
+        assert_eq!(tables, Value::from(bag!["customer"]));
+
+        let signatures = run(
+            "SELECT f.kind, f.signatures FROM information_schema.functions AS f \
+             WHERE f.name = 'lower' OR f.name = 'position'",
+        );
+        assert_eq!(
+            signatures,
+            Value::from(bag![
+                tuple![("kind", "scalar"), ("signatures", list!["lower(_)"])],
+                tuple![
+                    ("kind", "scalar"),
+                    ("signatures", list!["position(_, in: _)"])
+                ],
+            ])
+        );
+
+        let settings =
+            run("SELECT s.mode, s.subquery_materialization FROM information_schema.settings AS s");
+        assert_eq!(
+            settings,
+            Value::from(bag![tuple![
+                ("mode", "permissive"),
+                ("subquery_materialization", true)
+            ]])
+        );
+
+        // Bindings of the name `information_schema` take precedence
+        let mut bindings = MapBindings::default();
+        bindings.insert("INFORMATION_SCHEMA", Value::from(1));
+        planner.information_schema().bind(&mut bindings);
+        let parsed = parse("information_schema");
+        let lowered = lower(&parsed).expect("Expect no lower error");
+        assert_eq!(evaluate(lowered, bindings), Value::from(1));
+    }
+
+    #[test]
+    pub fn test_exists() {
+        let run = |query: &str| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            evaluate(lowered, data_customer())
+        };
+
+        let out = run("SELECT c.id AS id FROM customer AS c \
+            WHERE EXISTS (SELECT * FROM customer AS d WHERE d.firstName = c.firstName AND d.id <> c.id)");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(
+                *bag,
+                bag![tuple![("id", 5)], tuple![("id", 3)], tuple![("id", 2)], tuple![("id", 1)]]
+            );
+        });
+        let out = run("SELECT c.id AS id FROM customer AS c \
+            WHERE NOT EXISTS (SELECT VALUE d.id FROM customer AS d WHERE d.balance > c.balance)");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("id", 5)]]);
+        });
+        let out = run("EXISTS (SELECT * FROM customer AS c WHERE c.balance > 1000)");
+        assert_matches!(out, Value::Boolean(false));
+    }
+
+    #[test]
+    pub fn test_in_subquery() {
+        let run = |query: &str| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            evaluate(lowered, data_customer())
+        };
+
+        let out = run("SELECT c.id AS id FROM customer AS c \
+            WHERE c.id IN (SELECT d.id FROM customer AS d WHERE d.balance > 10)");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("id", 5)], tuple![("id", 2)]]);
+        });
+        let out = run("SELECT c.id AS id FROM customer AS c \
+            WHERE c.id NOT IN (SELECT d.id FROM customer AS d WHERE d.balance > 10)");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("id", 4)], tuple![("id", 3)], tuple![("id", 1)]]);
+        });
+        let out = run("SELECT c.id AS id FROM customer AS c \
+            WHERE c.firstName IN (SELECT VALUE d.firstName FROM customer AS d WHERE d.id = 4)");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(*bag, bag![tuple![("id", 4)]]);
+        });
+    }
+
+    #[test]
+    pub fn test_external_sort() {
+        // A run size of 2 forces the customers to be spilled to disk over several runs.
+        let run = |query: &str| {
+            let parsed = parse(query);
+            let lowered = lower(&parsed).expect("Expect no lower error");
+            let catalog = PartiqlCatalog::default();
+            let mut plan = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+                .with_sort_config(ExternalSortConfig::default().with_run_size(2))
+                .compile(&lowered)
+                .expect("Expect no plan error");
+            plan.execute_mut(data_customer())
+                .expect("Expect no eval error")
+                .result
+        };
+
+        let out = run("SELECT c.id AS id FROM customer AS c ORDER BY c.balance DESC, c.id");
+        assert_matches!(out, Value::List(list) => {
+            let ids = [5, 2, 1, 4, 3].map(|id| tuple![("id", id)].into());
+            assert_eq!(list.iter().cloned().collect::<Vec<Value>>(), ids);
+        });
+
+        let out = run("SELECT DISTINCT c.firstName AS name FROM customer AS c");
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(
+                *bag,
+                bag![tuple![("name", "jason")], tuple![("name", "sisko")], tuple![("name", "miriam")]]
+            );
+        });
+    }
+
+    #[test]
+    pub fn test_order_by_nulls() {
+        let run = |order: &str| {
+            evaluate_query(&format!(
+                "SELECT VALUE x FROM [2, NULL, 1] AS x ORDER BY x {order}"
+            ))
+        };
+
+        assert_eq!(run("ASC"), Value::from(list![1, 2, Value::Null]));
+        assert_eq!(run("NULLS FIRST"), Value::from(list![Value::Null, 1, 2]));
+        assert_eq!(run("ASC NULLS LAST"), Value::from(list![1, 2, Value::Null]));
+        assert_eq!(run("DESC"), Value::from(list![Value::Null, 2, 1]));
+        assert_eq!(
+            run("DESC NULLS LAST"),
+            Value::from(list![2, 1, Value::Null])
+        );
+    }
+
+    #[test]
+    pub fn test_select_value() {
+        let lowered = lower(&parse(
+            "SELECT VALUE {'name': c.firstName} FROM customer AS c WHERE c.id < 3",
+        ))
+        .expect("Expect no lower error");
+        assert!(lowered
+            .operators()
+            .iter()
+            .any(|op| matches!(op, BindingsOp::ProjectValue(_))));
+        let out = evaluate(lowered, data_customer());
+        assert_eq!(
+            out,
+            Value::from(bag![tuple![("name", "miriam")], tuple![("name", "miriam")]])
+        );
+    }
+
+    #[test]
+    pub fn test_select_list_wildcard() {
+        let mut bindings = data_customer();
+        bindings.insert(
+            "orders",
+            Value::from(bag![tuple![("customer", 2), ("total", 7)]]),
+        );
+        let query = "SELECT c.*, o.total FROM customer AS c, orders AS o WHERE c.id = o.customer";
+        let lowered = lower(&parse(query)).expect("Expect no lower error");
+        let out = evaluate(lowered, bindings);
+        let expected = bag![tuple![
+            ("id", 2),
+            ("firstName", "miriam"),
+            ("balance", 20),
+            ("total", 7)
+        ]];
+        assert_eq!(out, Value::from(expected));
+
+        // the attributes of a non-tuple value are named after its position
+        let out = evaluate_query("SELECT x.*, 2 AS y FROM <<1>> AS x");
+        assert_eq!(out, Value::from(bag![tuple![("_1", 1), ("y", 2)]]));
+    }
+
+    #[test]
+    pub fn test_group_partial() {
+        let data = "<<{'a': 1}, {'a': NULL}, {'b': 2}>>";
+        // grouping fully, a missing key is grouped with the null ones
+        let out = evaluate_query(&format!(
+            "SELECT k, COUNT(*) AS n FROM {data} AS t GROUP BY t.a AS k"
+        ));
+        assert_eq!(
+            out,
+            Value::from(bag![
+                tuple![("k", 1), ("n", 1)],
+                tuple![("k", Value::Null), ("n", 2)]
+            ])
+        );
+        assert_eq!(
+            evaluate_query(&format!(
+                "SELECT k, COUNT(*) AS n FROM {data} AS t GROUP ALL BY t.a AS k"
+            )),
+            out
+        );
+
+        // grouping partially, it is a group of its own, which lacks the key
+        let out = evaluate_query(&format!(
+            "SELECT k, COUNT(*) AS n FROM {data} AS t GROUP PARTIAL BY t.a AS k"
+        ));
+        assert_eq!(
+            out,
+            Value::from(bag![
+                tuple![("k", 1), ("n", 1)],
+                tuple![("k", Value::Null), ("n", 1)],
+                tuple![("n", 1)]
+            ])
+        );
+    }
+
+    #[test]
+    pub fn test_computed_struct_keys() {
+        let cases = [
+            ("{'a' || 'b': 1, 'c': 2}", tuple![("ab", 1), ("c", 2)]),
+            ("{UPPER('k'): 1, 'k': 2}", tuple![("K", 1), ("k", 2)]),
+            ("{(SELECT VALUE 'q' FROM <<1>>): 1}", tuple![]),
+            ("{1: 'a', 'b': 2}", tuple![("b", 2)]),
+            ("{1 + 1: 'a', 'b': 2}", tuple![("b", 2)]),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(evaluate_query(query), Value::from(expected), "{query}");
+        }
+
+        let query = "SELECT VALUE {x.k: x.v} FROM <<{'k': 'a', 'v': 1}, {'k': 'b', 'v': 2}>> AS x";
+        assert_eq!(
+            evaluate_query(query),
+            Value::from(bag![tuple![("a", 1)], tuple![("b", 2)]])
+        );
+    }
+
+    #[test]
+    pub fn test_outer_bag_ops() {
+        let cases = [
+            ("<<1, 2, 2>> OUTER UNION ALL <<2, 3>>", bag![1, 2, 2, 2, 3]),
+            ("<<1, 2, 2>> OUTER UNION <<2, 3>>", bag![1, 2, 3]),
+            (
+                "<<1, 2, 2, 3>> OUTER INTERSECT ALL <<2, 2, 2, 4>>",
+                bag![2, 2],
+            ),
+            ("<<1, 2, 2, 3>> OUTER INTERSECT <<2, 2, 2, 4>>", bag![2]),
+            ("<<1, 2, 2, 3>> OUTER EXCEPT ALL <<2, 3>>", bag![1, 2]),
+            ("<<1, 2, 2, 3>> OUTER EXCEPT <<3>>", bag![1, 2]),
+            // Non-collection operands are coerced to singleton bags, and absent values to empty ones
+            ("1 OUTER UNION ALL [2, 3]", bag![1, 2, 3]),
+            ("NULL OUTER UNION ALL <<1>>", bag![1]),
+            (
+                "(SELECT VALUE c.id FROM <<{'id': 1}, {'id': 2}>> AS c) \
+                 OUTER EXCEPT ALL (SELECT VALUE o.id FROM <<{'id': 2}>> AS o)",
+                bag![1],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(evaluate_query(query), Value::from(expected), "{query}");
+        }
+    }
+
+    #[test]
+    fn test_desugared() {
+        for query in [
+            "SELECT firstName, balance * 2 AS b FROM customer WHERE balance > 0",
+            "SELECT DISTINCT c.firstName FROM customer AS c",
+            "SELECT firstName, COUNT(*) AS n FROM customer GROUP BY firstName",
+            "SELECT c.firstName, COUNT(*) AS n FROM customer AS c GROUP BY c.firstName",
+            "SELECT c.firstName || '!' AS f FROM customer AS c GROUP BY c.firstName",
+            "SELECT c.id, (SELECT VALUE x FROM [1] AS x) AS s FROM customer c ORDER BY c.id LIMIT 2",
+        ] {
+            let parsed = parse(query);
+            let expected = evaluate(lower(&parsed).expect("lower"), data_customer());
+            let mut desugared = parse(query);
+            partiql_ast_passes::desugar::desugar(&mut desugared.ast, arena());
+            let actual = evaluate(lower(&desugared).expect("lower"), data_customer());
+            assert_eq!(expected, actual, "{query}");
+        }
+
+        let mut desugared =
+            parse("SELECT c.firstName, COUNT(*) AS n FROM customer AS c GROUP BY c.firstName");
+        partiql_ast_passes::desugar::desugar(&mut desugared.ast, arena());
+        let out = evaluate(lower(&desugared).expect("lower"), data_customer());
+        assert_matches!(out, Value::Bag(bag) => {
+            assert_eq!(
+                *bag,
+                bag![
+                    tuple![("firstName", "jason"), ("n", 2)],
+                    tuple![("firstName", "sisko"), ("n", 1)],
+                    tuple![("firstName", "miriam"), ("n", 2)],
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_optimizer() {
+        use crate::optimizer::{Optimizer, OptimizerRule, OptimizerTrace};
+
+        let query = "SELECT c.id AS id FROM customer AS c \
+                     WHERE EXISTS (SELECT VALUE 1 FROM [1] AS x WHERE true) \
+                     GROUP BY c.id HAVING true";
+        let expected = evaluate(lower(&parse(query)).expect("lower"), data_customer());
+        let mut plan = lower(&parse(query)).expect("lower");
+        let trace = Optimizer::default().optimize(&mut plan);
+        assert_eq!(
+            trace,
+            OptimizerTrace {
+                fired: vec!["remove_true_filters", "remove_true_filters"],
+                passes: 2,
+                fixpoint: true,
+            }
+        );
+        assert!(!plan.to_string().contains("true"), "{plan}");
+        assert_eq!(plan.validate(), Ok(()));
+        assert_eq!(evaluate(plan, data_customer()), expected);
+
+        // a filter on a filter
+        let mut plan =
+            lower(&parse("SELECT VALUE c FROM customer AS c WHERE c.id > 1")).expect("lower");
+        let filter = plan
+            .operators_by_id()
+            .find_map(|(id, op)| matches!(op, BindingsOp::Filter(_)).then_some(id))
+            .unwrap();
+        let [scan] = plan.inputs(filter)[..] else {
+            panic!("{plan}")
+        };
+        let inner = plan.add_operator(BindingsOp::Filter(logical::Filter {
+            expr: logical::ValueExpr::BinaryExpr(
+                logical::BinaryOp::Lt,
+                Box::new(logical::ValueExpr::Path(
+                    Box::new(logical::ValueExpr::VarRef(
+                        partiql_value::BindingsName::CaseInsensitive("c".to_string()),
+                    )),
+                    vec![logical::PathComponent::Key(
+                        partiql_value::BindingsName::CaseInsensitive("id".to_string()),
+                    )],
+                )),
+                Box::new(logical::ValueExpr::Lit(Box::new(Value::from(5)))),
+            ),
+        }));
+        plan.remove_flows(scan, filter);
+        plan.extend_with_flows(&[(scan, inner), (inner, filter)]);
+        let trace = Optimizer::default().optimize(&mut plan);
+        assert_eq!(trace.fired, vec!["merge_filters"]);
+        assert!(
+            plan.to_string()
+                .contains("Filter(And(Lt(c.id, 5), Gt(c.id, 1)))"),
+            "{plan}"
+        );
+        assert_eq!(
+            evaluate(plan, data_customer()),
+            Value::from(bag![
+                tuple![("id", 4), ("firstName", "sisko"), ("balance", 0)],
+                tuple![("id", 3), ("firstName", "jason"), ("balance", -30)],
+                tuple![("id", 2), ("firstName", "miriam"), ("balance", 20)],
+            ])
+        );
+
+        // a rule that always changes the plan never reaches a fixpoint
+        struct Always;
+        impl OptimizerRule for Always {
+            fn name(&self) -> &'static str {
+                "always"
+            }
+            fn apply(&self, _plan: &mut LogicalPlan<BindingsOp>) -> bool {
+                true
+            }
+        }
+        let mut plan = lower(&parse("1")).expect("lower");
+        let trace = Optimizer::new(vec![Box::new(Always)])
+            .with_max_passes(3)
+            .optimize(&mut plan);
+        assert_eq!(
+            trace,
+            OptimizerTrace {
+                fired: vec!["always"; 3],
+                passes: 3,
+                fixpoint: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_push_down_filters() {
+        use crate::optimizer::Optimizer;
+
+        let optimized = |query: &str| {
+            let expected = evaluate(lower(&parse(query)).expect("lower"), data_customer());
+            let mut plan = lower(&parse(query)).expect("lower");
+            let trace = Optimizer::default().optimize(&mut plan);
+            assert_eq!(plan.validate(), Ok(()));
+            let display = format!("{plan:#}");
+            assert_eq!(evaluate(plan, data_customer()), expected);
+            (trace.fired, display)
+        };
+
+        let (fired, plan) = optimized(
+            "SELECT c.id AS id, d.id AS did FROM customer AS c, customer AS d \
+             WHERE c.id = d.id AND c.balance > 0",
+        );
+        assert_eq!(fired, vec!["push_down_filters"]);
+        // the condition of only `c` filters its operand
+        let expected = "\
+            #6 Sink\n  \
+              #1 Project(c.id AS id, d.id AS did)\n    \
+                #4 Join(Inner, Scan(global::customer AS c), Scan(global::customer AS d) \
+                   ON Eq(c.id, d.id))\n      \
+                  #7 Filter(Gt(c.balance, 0))\n        \
+                    #2 Scan(global::customer AS c)\n      \
+                  #3 Scan(global::customer AS d)\n";
+        assert_eq!(plan, expected);
+
+        // as do the conditions of an `ON` referring to a single operand
+        let (fired, plan) = optimized(
+            "SELECT c.id AS id, d.id AS did FROM customer AS c JOIN customer AS d \
+             ON c.id = d.id AND d.balance < 0 AND c.id > 1",
+        );
+        assert_eq!(fired, vec!["push_down_filters"]);
+        assert!(plan.contains("ON Eq(c.id, d.id))"), "{plan}");
+        assert!(
+            plan.contains("Filter(Gt(c.id, 1))\n        #2 Scan(global::customer AS c)"),
+            "{plan}"
+        );
+        assert!(
+            plan.contains("Filter(Lt(d.balance, 0))\n        #3 Scan(global::customer AS d)"),
+            "{plan}"
+        );
+
+        let (fired, plan) = optimized(
+            "SELECT c.id AS id FROM customer AS c LET c.balance * 2 AS b \
+             WHERE b > 0 AND c.id > 1",
+        );
+        assert_eq!(fired, vec!["push_down_filters"]);
+        let expected = "\
+            #5 Sink\n  \
+              #1 Project(c.id AS id)\n    \
+                #4 Filter(Gt(Lookup(b), 0))\n      \
+                  #3 Let(Mul(c.balance, 2) AS b)\n        \
+                    #6 Filter(Gt(c.id, 1))\n          \
+                      #2 Scan(global::customer AS c)\n";
+        assert_eq!(plan, expected);
+
+        let (fired, _) = optimized(
+            "SELECT c.id AS id FROM customer AS c LEFT JOIN customer AS d ON c.id = d.id \
+             WHERE c.balance > 0",
+        );
+        assert!(fired.is_empty());
+
+        // a filter on a projection
+        let mut plan =
+            lower(&parse("SELECT DISTINCT c.id AS id FROM customer AS c")).expect("lower");
+        let distinct = plan
+            .operators_by_id()
+            .find_map(|(id, op)| matches!(op, BindingsOp::Distinct).then_some(id))
+            .unwrap();
+        let [sink] = plan.successors(distinct)[..] else {
+            panic!("{plan}")
+        };
+        let filter = plan.add_operator(BindingsOp::Filter(logical::Filter {
+            expr: logical::ValueExpr::BinaryExpr(
+                logical::BinaryOp::Gt,
+                Box::new(logical::ValueExpr::VarRef(
+                    partiql_value::BindingsName::CaseInsensitive("id".to_string()),
+                )),
+                Box::new(logical::ValueExpr::Lit(Box::new(Value::from(2)))),
+            ),
+        }));
+        plan.remove_flows(distinct, sink);
+        plan.extend_with_flows(&[(distinct, filter), (filter, sink)]);
+        let trace = Optimizer::default().optimize(&mut plan);
+        assert_eq!(trace.fired, vec!["push_down_filters"; 2]);
+        let expected = "\
+            #4 Sink\n  \
+              #2 Distinct\n    \
+                #1 Project(c.id AS id)\n      \
+                  #5 Filter(Gt(c.id, 2))\n        \
+                    #3 Scan(global::customer AS c)\n";
+        assert_eq!(format!("{plan:#}"), expected);
+        assert_eq!(
+            evaluate(plan, data_customer()),
+            Value::from(bag![
+                tuple![("id", 3)],
+                tuple![("id", 4)],
+                tuple![("id", 5)]
+            ])
+        );
+    }
+
+    #[test]
+    fn test_prune_projections() {
+        use crate::optimizer::Optimizer;
+
+        let optimized = |query: &str| {
+            let expected = evaluate(lower(&parse(query)).expect("lower"), data_customer());
+            let mut plan = lower(&parse(query)).expect("lower");
+            let trace = Optimizer::default().optimize(&mut plan);
+            assert_eq!(plan.validate(), Ok(()));
+            let display = format!("{plan:#}");
+            assert_eq!(evaluate(plan, data_customer()), expected);
+            (trace.fired, display)
+        };
+
+        let (fired, plan) = optimized(
+            "SELECT c.id AS id, COUNT(*) AS n FROM customer AS c AT i LET c.balance AS b \
+             GROUP BY c.id GROUP AS g",
+        );
+        assert_eq!(fired, vec!["prune_projections"; 2]);
+        assert_eq!(
+            plan,
+            "\
+            #1 Sink\n  \
+              #2 Project(\"id\" AS id, \"$__agg_1\" AS n)\n    \
+                #5 GroupBy(GroupFull, c.id AS id, AggCount(All 1) AS $__agg_1)\n      \
+                  #3 Scan(global::customer AS c)\n"
+        );
+
+        let (fired, plan) = optimized(
+            "SELECT x.id AS id FROM (SELECT c.id AS id, c.balance AS b FROM customer AS c) AS x",
+        );
+        assert_eq!(fired, vec!["prune_projections"]);
+        assert_eq!(
+            plan,
+            "\
+            #3 Sink\n  \
+              #1 Project(x.id AS id)\n    \
+                #2 Scan(CollectionSubQuery(#1 Project(c.id AS id); #2 Scan(global::customer AS c); \
+                   #3 Sink; #1 -> #3; #2 -> #1) AS x)\n"
+        );
+
+        let (fired, plan) = optimized(
+            "SELECT c.id AS id FROM customer AS c AT i, customer AS d AT j WHERE c.id = d.id",
+        );
+        assert_eq!(fired, vec!["push_down_filters", "prune_projections"]);
+        assert_eq!(
+            plan,
+            "\
+            #6 Sink\n  \
+              #1 Project(c.id AS id)\n    \
+                #4 Join(Inner, Scan(global::customer AS c), Scan(global::customer AS d) \
+                   ON Eq(c.id, d.id))\n      \
+                  #2 Scan(global::customer AS c)\n      \
+                  #3 Scan(global::customer AS d)\n"
+        );
+
+        let (fired, _) = optimized("SELECT * FROM customer AS c AT i LET c.id AS x");
+        assert!(fired.is_empty());
+        let (fired, _) = optimized(
+            "SELECT x.id AS id FROM (SELECT DISTINCT c.id AS id, c.balance AS b FROM customer AS c) AS x",
+        );
+        assert!(fired.is_empty());
+    }
+
+    #[test]
+    fn test_push_down_filters_and_prune_projections() {
+        use crate::optimizer::Optimizer;
+
+        // the filters pushed above the operands of joins use the variables the joins keep inline
+        let same_results = |query: &str| {
+            let expected = evaluate(lower(&parse(query)).expect("lower"), data_customer());
+            let mut plan = lower(&parse(query)).expect("lower");
+            let trace = Optimizer::default().optimize(&mut plan);
+            assert_eq!(plan.validate(), Ok(()));
+            assert!(trace.fired.contains(&"push_down_filters"), "{query}");
+            let display = format!("{plan:#}");
+            assert_eq!(evaluate(plan, data_customer()), expected, "{display}");
+            expected
+        };
+
+        let expected = same_results(
+            "SELECT c.id AS id FROM customer AS c, UNPIVOT c AS v AT k WHERE k = 'firstName'",
+        );
+        assert_eq!(
+            expected,
+            Value::from(bag![
+                tuple![("id", 5)],
+                tuple![("id", 4)],
+                tuple![("id", 3)],
+                tuple![("id", 2)],
+                tuple![("id", 1)]
+            ])
+        );
+        same_results(
+            "SELECT c.id AS id FROM customer AS c JOIN UNPIVOT c AS v AT k ON k = 'firstName'",
+        );
+        same_results(
+            "SELECT c.id AS id FROM customer AS c AT i, customer AS d WHERE i > 2 AND c.id = d.id",
+        );
+        same_results(
+            "SELECT c.id AS id FROM customer AS c, customer AS d AT j \
+             WHERE j < 2 AND c.id = d.id AND c.balance > 0",
+        );
+    }
+
+    #[test]
+    fn test_eliminate_common_subexpressions() {
+        use crate::optimizer::Optimizer;
+
+        let optimized = |query: &str| {
+            let expected = evaluate(lower(&parse(query)).expect("lower"), data_customer());
+            let mut plan = lower(&parse(query)).expect("lower");
+            let trace = Optimizer::default().optimize(&mut plan);
+            assert_eq!(plan.validate(), Ok(()));
+            let display = format!("{plan:#}");
+            assert_eq!(evaluate(plan, data_customer()), expected);
+            (trace.fired, display)
+        };
+
+        let (fired, plan) = optimized(
+            "SELECT c.balance * 2 + 1 AS x, c.balance * 2 AS y FROM customer AS c \
+             WHERE c.id + 1 > 2 AND c.id + 1 < 5",
+        );
+        assert_eq!(fired, vec!["eliminate_common_subexpressions"]);
+        let expected = "\
+            #4 Sink\n  \
+              #1 Project(Add(\"$__cse_1\", 1) AS x, \"$__cse_1\" AS y)\n    \
+                #5 Let(Mul(c.balance, 2) AS $__cse_1)\n      \
+                  #3 Filter(And(Gt(\"$__cse_2\", 2), Lt(\"$__cse_2\", 5)))\n        \
+                    #6 Let(Add(c.id, 1) AS $__cse_2)\n          \
+                      #2 Scan(global::customer AS c)\n";
+        assert_eq!(plan, expected);
+
+        let (fired, _) =
+            optimized("SELECT * FROM customer AS c WHERE c.id + 1 > 2 AND c.id + 1 < 5");
+        assert!(fired.is_empty());
+        let (fired, _) = optimized(
+            "SELECT c.id AS id FROM customer AS c \
+             WHERE EXISTS (SELECT VALUE c.id + 1 FROM [1] AS x) AND c.id + 1 > 0",
+        );
+        assert!(fired.is_empty());
+
+        // the expressions only evaluated conditionally are not bound, e.g., dividing by zero
+        let (fired, plan) = optimized(
+            "SELECT CASE WHEN c.balance = 0 THEN 0 ELSE 10 / c.balance END AS p, \
+             CASE WHEN c.balance = 0 THEN 1 ELSE 10 / c.balance END AS q FROM customer AS c",
+        );
+        assert_eq!(fired, vec!["eliminate_common_subexpressions"]);
+        assert!(
+            plan.contains("Let(Eq(c.balance, 0) AS $__cse_1)\n"),
+            "{plan}"
+        );
+        assert!(!plan.contains("AS $__cse_2"), "{plan}");
+        let (fired, _) = optimized(
+            "SELECT c.id > 0 OR c.balance / c.id > 1 AS x, c.id > 0 OR c.balance / c.id > 2 AS y \
+             FROM customer AS c",
+        );
+        assert_eq!(fired, vec!["eliminate_common_subexpressions"]);
+        let (fired, _) = optimized(
+            "SELECT COALESCE(c.id, c.balance * 2) AS x, COALESCE(c.id, c.balance * 2) + 1 AS y \
+             FROM customer AS c",
+        );
+        assert_eq!(fired, vec!["eliminate_common_subexpressions"]);
+        let (fired, plan) = optimized(
+            "SELECT c.balance * 2 AS x, CASE WHEN c.id > 2 THEN c.balance * 2 END AS y \
+             FROM customer AS c",
+        );
+        assert_eq!(fired, vec!["eliminate_common_subexpressions"]);
+        assert!(
+            plan.contains("Let(Mul(c.balance, 2) AS $__cse_1)"),
+            "{plan}"
+        );
+
+        // nor are the calls of functions that may not return the same value each call
+        let mut plan = lower(&parse(
+            "SELECT c.id AS id FROM customer AS c WHERE UTCNOW() = UTCNOW()",
+        ))
+        .expect("lower");
+        assert!(Optimizer::default().optimize(&mut plan).fired.is_empty());
+        let (fired, _) =
+            optimized("SELECT UTCNOW() IS NULL AS x, UTCNOW() IS NULL AS y FROM customer AS c");
+        assert!(fired.is_empty());
+    }
+
+    #[test]
+    fn test_reorder_joins() {
+        use crate::optimizer::{Optimizer, PushDownFilters, ReorderJoins, Statistics};
+
+        /// The sizes of `customer` and of lists.
+        struct Sizes;
+
+        impl Statistics for Sizes {
+            fn cardinality(&self, expr: &logical::ValueExpr) -> Option<u64> {
+                match expr {
+                    logical::ValueExpr::GlobalVarRef(_) => Some(5),
+                    logical::ValueExpr::ListExpr(list) => Some(list.elements.len() as u64),
+                    logical::ValueExpr::Lit(lit) => match lit.as_ref() {
+                        Value::List(list) => Some(list.len() as u64),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+        }
+
+        let optimized = |query: &str| {
+            let expected = evaluate(lower(&parse(query)).expect("lower"), data_customer());
+            let mut plan = lower(&parse(query)).expect("lower");
+            let optimizer = Optimizer::new(vec![
+                Box::new(PushDownFilters),
+                Box::new(ReorderJoins::new(Box::new(Sizes))),
+            ]);
+            let trace = optimizer.optimize(&mut plan);
+            assert!(trace.fixpoint);
+            assert_eq!(plan.validate(), Ok(()));
+            let display = format!("{plan:#}");
+            assert_eq!(evaluate(plan, data_customer()), expected);
+            (trace.fired, display)
+        };
+
+        let (fired, plan) = optimized(
+            "SELECT c.id AS id, d.id AS did, x.n AS n \
+             FROM customer AS c, customer AS d, [{'n': 1}, {'n': 2}] AS x \
+             WHERE c.id = d.id AND c.id = x.n",
+        );
+        assert_eq!(fired, vec!["push_down_filters", "reorder_joins"]);
+        assert_eq!(
+            plan,
+            "\
+            #8 Sink\n  \
+              #1 Project(c.id AS id, d.id AS did, x.n AS n)\n    \
+                #6 Join(Inner, Join(Inner, Scan([{ n: 1 }, { n: 2 }] AS x), \
+                   Scan(global::customer AS c) ON Eq(c.id, x.n)), Scan(global::customer AS d) \
+                   ON Eq(c.id, d.id))\n      \
+                  #5 Join(Inner, Scan([{ n: 1 }, { n: 2 }] AS x), \
+                     Scan(global::customer AS c) ON Eq(c.id, x.n))\n        \
+                    #9 Scan([{ n: 1 }, { n: 2 }] AS x)\n        \
+                    #4 Scan(global::customer AS c)\n      \
+                  #3 Scan(global::customer AS d)\n"
+        );
+
+        let (fired, plan) = optimized(
+            "SELECT c.id AS id, x AS x, y AS y FROM customer AS c, [1, 2, 3] AS x, [c.id] AS y",
+        );
+        assert_eq!(fired, vec!["reorder_joins"]);
+        assert_eq!(
+            plan,
+            "\
+            #7 Sink\n  \
+              #1 Project(c.id AS id, Lookup(x) AS x, Lookup(y) AS y)\n    \
+                #6 Join(Cross, Join(Cross, Scan([1, 2, 3] AS x), Scan(global::customer AS c)), \
+                   Scan([c.id] AS y))\n      \
+                  #5 Join(Cross, Scan([1, 2, 3] AS x), Scan(global::customer AS c))\n        \
+                    #4 Scan([1, 2, 3] AS x)\n        \
+                    #3 Scan(global::customer AS c)\n      \
+                  #2 Scan([c.id] AS y)\n"
+        );
+
+        let (fired, _) =
+            optimized("SELECT c.id AS id FROM customer AS c LEFT JOIN [1, 2] AS x ON c.id = x");
+        assert!(fired.is_empty());
+    }
+
+    #[test]
+    fn test_plan_builder() {
+        use logical::{BinaryOp, PathComponent, PlanBuilder, ValueExpr};
+        use partiql_value::BindingsName;
+
+        let attr = |key: &str| {
+            ValueExpr::Path(
+                Box::new(ValueExpr::VarRef(BindingsName::CaseInsensitive(
+                    "c".to_string(),
+                ))),
+                vec![PathComponent::Key(BindingsName::CaseInsensitive(
+                    key.to_string(),
+                ))],
+            )
+        };
+        let customer = ValueExpr::GlobalVarRef(BindingsName::CaseInsensitive("customer".into()));
+        let built = PlanBuilder::scan_as(customer, "c")
+            .filter(ValueExpr::BinaryExpr(
+                BinaryOp::Gt,
+                Box::new(attr("balance")),
+                Box::new(ValueExpr::Lit(Box::new(Value::from(0)))),
+            ))
+            .project([("id", attr("id"))])
+            .build();
+        let lowered = lower(&parse(
+            "SELECT c.id AS id FROM customer AS c WHERE c.balance > 0",
+        ))
+        .expect("lower");
+        assert_eq!(
+            evaluate(built, data_customer()),
+            evaluate(lowered, data_customer())
+        );
+    }
+
+    #[test]
+    fn test_typing() {
+        use crate::typing::{PlanTyper, TypingError};
+        use partiql_catalog::UndeclaredAttribute;
+        use partiql_catalog::{Catalog, TypeEnvEntry};
+        use partiql_types::{
+            int, str, BagType, PartiqlType, StructConstraint, StructField, StructType,
+        };
+
+        let mut catalog = PartiqlCatalog::default();
+        let customer = StructType::new(vec![
+            StructConstraint::Open(false),
+            StructConstraint::Fields(StructField::new("id", int!())),
+            StructConstraint::Fields(StructField::new("firstName", str!())),
+            StructConstraint::Fields(StructField::new("balance", int!())),
+        ]);
+        let ty = PartiqlType::new_bag(BagType::new(Box::new(PartiqlType::new_struct(customer))));
+        catalog
+            .add_type_entry(TypeEnvEntry::new("customer", &[], ty))
+            .expect("type entry");
+
+        let typed = |query: &str| {
+            let plan = LogicalPlanner::new(&catalog)
+                .lower(&parse(query))
+                .expect("lower");
+            let typer = PlanTyper::new(&catalog);
+            typer.type_plan(&plan).map(|typed| {
+                let shapes: Vec<_> = plan
+                    .operators_by_id()
+                    .map(|(id, op)| format!("{op}: {}", typed.shape(id).expect("shape")))
+                    .collect();
+                (typed.output().expect("output").to_string(), shapes)
+            })
+        };
+
+        let (output, shapes) = typed(
+            "SELECT c.id AS id, c.firstName || '!' AS name FROM customer AS c WHERE c.balance > 0",
+        )
+        .expect("typed");
+        assert_eq!(output, "{id: INT, name: STRING}");
+        assert!(shapes.contains(
+            &"Scan(global::customer AS c): {c: {id: INT, firstName: STRING, balance: INT}}"
+                .to_string()
+        ));
+
+        let cases = [
+            ("SELECT VALUE c.id * 2 FROM customer AS c", "INT"),
+            ("SELECT VALUE c.id || '!' FROM customer AS c", "ANY"),
+            ("SELECT t.a AS a FROM t", "{a: ANY}"),
+            (
+                "SELECT * FROM customer AS c, [1, 2] AS x",
+                "{id: INT, firstName: STRING, balance: INT, ...}",
+            ),
+            (
+                "SELECT name, COUNT(c.id) AS n FROM customer AS c \
+                 GROUP BY c.firstName AS name",
+                "{name: STRING, n: INT}",
+            ),
+            (
+                "SELECT c.id AS id FROM customer AS c \
+                 UNION ALL SELECT c.balance AS id FROM customer AS c",
+                "{id: INT}",
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(typed(query).expect("typed").0, expected, "{query}");
+        }
+
+        let errors = |query| typed(query).expect_err("typing errors").errors;
+        assert_eq!(
+            errors("SELECT c.id AS id, c.nickname AS nick FROM customer AS c"),
+            vec![TypingError::UndeclaredAttribute(UndeclaredAttribute(
+                "nickname".to_string()
+            ))]
+        );
+        // in a subquery referring to the variable of the enclosing query
+        assert_eq!(
+            errors("SELECT VALUE (SELECT VALUE c.NICKNAME FROM [1] AS x) FROM customer AS c"),
+            vec![TypingError::UndeclaredAttribute(UndeclaredAttribute(
+                "NICKNAME".to_string()
+            ))]
+        );
+        assert_eq!(
+            errors("SELECT VALUE c.firstName + 1 FROM customer AS c"),
+            vec![TypingError::IncompatibleTypes(
+                "`Add` of an operand of type STRING".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_plan_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let plan = |query| lower(&parse(query)).expect("lower");
+        let hash = |plan: &partiql_logical::LogicalPlan<_>| {
+            let mut hasher = DefaultHasher::new();
+            plan.hash(&mut hasher);
+            hasher.finish()
+        };
+        let query = "SELECT c.firstName, COUNT(*) AS n FROM customer AS c \
+                     WHERE c.balance > (SELECT VALUE 1 FROM [1]) GROUP BY c.firstName";
+        assert_eq!(plan(query), plan(query));
+        assert_eq!(hash(&plan(query)), hash(&plan(query)));
+        assert_ne!(plan(query), plan("SELECT c.firstName FROM customer AS c"));
+    }
+
+    #[test]
+    pub fn test_plan_display() {
+        let lowered = lower(&parse(
+            "SELECT c.id AS id FROM customer AS c WHERE c.balance > 10",
+        ))
+        .expect("Expect no lower error");
+        let expected = "\
+            #1 Project(c.id AS id)\n\
+            #2 Scan(global::customer AS c)\n\
+            #3 Filter(Gt(c.balance, 10))\n\
+            #4 Sink\n\
+            #1 -> #4\n\
+            #2 -> #3\n\
+            #3 -> #1\n";
+        assert_eq!(lowered.to_string(), expected);
+
+        let expected = "\
+            #4 Sink\n  \
+              #1 Project(c.id AS id)\n    \
+                #3 Filter(Gt(c.balance, 10))\n      \
+                  #2 Scan(global::customer AS c)\n";
+        assert_eq!(format!("{lowered:#}"), expected);
+    }
+
     #[test]
     pub fn test_5() {
         let out = evaluate_query("5");
@@ -139,4 +2228,73 @@ mod tests {
             assert_eq!(*bag, expected);
         });
     }
+
+    #[test]
+    fn test_flow_to_unknown_operator() {
+        let mut other: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        other.add_operator(BindingsOp::Sink);
+        let unknown = other.add_operator(BindingsOp::Sink);
+
+        let mut logical: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        let sink = logical.add_operator(BindingsOp::Sink);
+        logical.add_flow(unknown, sink);
+
+        let catalog = PartiqlCatalog::default();
+        let mut planner = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog);
+        assert!(planner.compile(&logical).is_err());
+    }
+
+    /// Mutations of the parser's fuzzing seed corpus: each input, each of its prefixes and
+    /// suffixes, and each of its runs of words along with the input missing them.
+    fn fuzz_seed_mutations() -> Vec<String> {
+        let seeds = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../partiql-parser/fuzz/inputs/seed");
+        let mut inputs = vec![];
+        for entry in std::fs::read_dir(seeds).expect("seed corpus") {
+            let seed = std::fs::read_to_string(entry.expect("seed").path()).expect("seed");
+            for (i, _) in seed.char_indices() {
+                inputs.push(seed[..i].to_string());
+                inputs.push(seed[i..].to_string());
+            }
+            let words: Vec<_> = seed.split_whitespace().collect();
+            for i in 0..words.len() {
+                for j in i + 1..=words.len() {
+                    inputs.push(words[i..j].join(" "));
+                    inputs.push([&words[..i], &words[j..]].concat().join(" "));
+                }
+            }
+            inputs.push(seed);
+        }
+        inputs
+    }
+
+    #[test]
+    fn test_deep_set_operators() {
+        // Chains of set operators lower by recursion, so far longer chains fail to parse rather
+        // than overflowing the stack when lowered
+        let text = format!("SELECT * FROM t{}", " UNION SELECT * FROM t".repeat(20_000));
+        let err = Parser::default().parse(&text, arena()).unwrap_err();
+        assert_matches!(err.errors[..], [ParseError::NestingTooDeep(_)]);
+
+        let text = format!("SELECT * FROM t{}", " UNION SELECT * FROM t".repeat(500));
+        assert!(lower(&parse(&text)).is_ok());
+    }
+
+    #[test]
+    fn test_no_panic_on_mutated_input() {
+        let catalog = PartiqlCatalog::default();
+        for input in fuzz_seed_mutations() {
+            println!("{input}");
+            let arena = Arena::new();
+            let _ = Parser::default().parse_statement(&input, &arena);
+            let Ok(parsed) = Parser::default().parse(&input, &arena) else {
+                continue;
+            };
+            let Ok(logical) = LogicalPlanner::new(&catalog).lower(&parsed) else {
+                continue;
+            };
+            let mut planner = plan::EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog);
+            let _ = planner.compile(&logical);
+        }
+    }
 }
@@ -5,15 +5,17 @@ use ordered_float::OrderedFloat;
 use partiql_ast::ast;
 use partiql_ast::ast::{
     Assignment, Bag, BagOpExpr, BagOperator, Between, BinOp, BinOpKind, Call, CallAgg, CallArg,
-    CallArgNamed, CaseSensitivity, CreateIndex, CreateTable, Ddl, DdlOp, Delete, Dml, DmlOp,
-    DropIndex, DropTable, Expr, FromClause, FromLet, FromLetKind, GroupByExpr, GroupKey,
-    GroupingStrategy, Insert, InsertValue, Item, Join, JoinKind, JoinSpec, Like, List, Lit, NodeId,
-    NullOrderingSpec, OnConflict, OrderByExpr, OrderingSpec, Path, PathStep, ProjectExpr,
-    Projection, ProjectionKind, Query, QuerySet, Remove, SearchedCase, Select, Set, SetQuantifier,
-    Sexp, SimpleCase, SortSpec, Struct, SymbolPrimitive, UniOp, UniOpKind, VarRef,
+    CallArgNamed, CallWindow, CaseSensitivity, CreateIndex, CreateTable, Ddl, DdlOp, Delete, Dml,
+    DmlOp, DropIndex, DropTable, Exec, Expr, FromClause, FromLet, FromLetKind, GroupByExpr,
+    GroupKey, GroupingStrategy, Insert, InsertValue, Item, Join, JoinKind, JoinSpec, Like, List,
+    Lit, NodeId, NullOrderingSpec, OnConflict, OrderByExpr, OrderingSpec, Path, PathStep,
+    ProjectExpr, ProjectItem, Projection, ProjectionKind, Query, QuerySet, Remove, ScopeQualifier,
+    SearchedCase, Select, Set, SetQuantifier, Sexp, SimpleCase, SortSpec, Struct, SymbolPrimitive,
+    UniOp, UniOpKind, VarRef,
 };
 use partiql_ast::visit::{Traverse, Visit, Visitor};
 use partiql_logical as logical;
+use partiql_logical::visit::{self, PlanRewriter};
 use partiql_logical::{
     AggregateExpression, BagExpr, BagOp, BetweenExpr, BindingsOp, IsTypeExpr, LikeMatch,
     LikeNonStringNonLiteralMatch, ListExpr, LogicalPlan, OpId, PathComponent, Pattern,
@@ -32,10 +34,13 @@ use partiql_catalog::call_defs::{CallArgument, CallDef};
 use partiql_ast_passes::error::{AstTransformError, AstTransformationError};
 
 use partiql_catalog::Catalog;
+#[cfg(feature = "ion")]
 use partiql_extension_ion::decode::{IonDecoderBuilder, IonDecoderConfig};
+#[cfg(feature = "ion")]
 use partiql_extension_ion::Encoding;
 use partiql_logical::AggFunc::{AggAvg, AggCount, AggMax, AggMin, AggSum};
 use std::sync::atomic::{AtomicU32, Ordering};
+use unicase::UniCase;
 
 type FnvIndexMap<K, V> = IndexMap<K, V, FnvBuildHasher>;
 
@@ -95,29 +100,37 @@ struct QueryClauses {
     where_clause: Option<logical::OpId>,
     group_by_clause: Option<logical::OpId>,
     having_clause: Option<logical::OpId>,
+    window_clauses: Vec<logical::OpId>,
     order_by_clause: Option<logical::OpId>,
     limit_offset_clause: Option<logical::OpId>,
     select_clause: Option<logical::OpId>,
     distinct: Option<logical::OpId>,
+    /// The clauses being lowered in which calls to aggregate functions are not allowed, innermost
+    /// last.
+    no_aggregates_in: Vec<&'static str>,
 }
 
 impl QueryClauses {
     pub fn evaluation_order(&self) -> Vec<OpId> {
-        [
+        let before_windows = [
             self.from_clause,
             self.let_clause,
             self.where_clause,
             self.group_by_clause,
             self.having_clause,
+        ];
+        let after_windows = [
             self.order_by_clause,
             self.limit_offset_clause,
             self.select_clause,
             self.distinct,
-        ]
-        .iter()
-        .cloned()
-        .flatten()
-        .collect()
+        ];
+        before_windows
+            .into_iter()
+            .flatten()
+            .chain(self.window_clauses.iter().cloned())
+            .chain(after_windows.into_iter().flatten())
+            .collect()
     }
 }
 
@@ -145,7 +158,7 @@ impl IdGenerator {
 }
 
 #[derive(Debug)]
-pub struct AstToLogical<'a> {
+pub struct AstToLogical<'a, 'ast> {
     // current stack of node ids
     id_stack: Vec<NodeId>,
 
@@ -157,10 +170,14 @@ pub struct AstToLogical<'a> {
     path_stack: Vec<Vec<PathComponent>>,
     sort_stack: Vec<Vec<logical::SortSpec>>,
     aggregate_exprs: Vec<AggregateExpression>,
+    // the enclosing plans & aggregates of the subqueries being lowered
+    subquery_stack: Vec<(LogicalPlan<BindingsOp>, Vec<AggregateExpression>)>,
+    // the names & lowered queries of the `WITH` clause's common table expressions
+    ctes: Vec<(SymbolPrimitive<'ast>, ValueExpr)>,
 
     from_lets: HashSet<ast::NodeId>,
 
-    aliases: FnvIndexMap<NodeId, SymbolPrimitive>,
+    aliases: FnvIndexMap<NodeId, SymbolPrimitive<'ast>>,
 
     // generator of 'fresh' ids
     id: IdGenerator,
@@ -170,7 +187,7 @@ pub struct AstToLogical<'a> {
     plan: LogicalPlan<BindingsOp>,
 
     // catalog & data flow data
-    key_registry: name_resolver::KeyRegistry,
+    key_registry: name_resolver::KeyRegistry<'ast>,
     fnsym_tab: &'static FnSymTab,
     catalog: &'a dyn Catalog,
 
@@ -180,39 +197,130 @@ pub struct AstToLogical<'a> {
 
 /// Attempt to infer an alias for a simple variable reference expression.
 /// For example infer such that  `SELECT a, b.c.d.e ...` <=> `SELECT a as a, b.c.d.e as e`  
-fn infer_id(expr: &ValueExpr) -> Option<SymbolPrimitive> {
-    let sensitive = |value| {
-        Some(SymbolPrimitive {
-            value,
-            case: CaseSensitivity::CaseSensitive,
-        })
-    };
-    let insensitive = |value| {
-        Some(SymbolPrimitive {
-            value,
-            case: CaseSensitivity::CaseInsensitive,
-        })
-    };
-
+fn infer_id(expr: &ValueExpr) -> Option<String> {
     match expr {
-        ValueExpr::VarRef(BindingsName::CaseInsensitive(s)) => insensitive(s.clone()),
-        ValueExpr::VarRef(BindingsName::CaseSensitive(s)) => sensitive(s.clone()),
+        ValueExpr::VarRef(BindingsName::CaseInsensitive(s) | BindingsName::CaseSensitive(s)) => {
+            Some(s.clone())
+        }
         ValueExpr::Path(_root, steps) => match steps.last() {
-            Some(PathComponent::Key(BindingsName::CaseInsensitive(s))) => insensitive(s.clone()),
-            Some(PathComponent::Key(BindingsName::CaseSensitive(s))) => sensitive(s.clone()),
-            Some(PathComponent::KeyExpr(ke)) => match &**ke {
-                ValueExpr::VarRef(BindingsName::CaseInsensitive(s)) => insensitive(s.clone()),
-                ValueExpr::VarRef(BindingsName::CaseSensitive(s)) => sensitive(s.clone()),
-                _ => None,
-            },
+            Some(PathComponent::Key(
+                BindingsName::CaseInsensitive(s) | BindingsName::CaseSensitive(s),
+            )) => Some(s.clone()),
             _ => None,
         },
         _ => None,
     }
 }
 
-impl<'a> AstToLogical<'a> {
-    pub fn new(catalog: &'a dyn Catalog, registry: name_resolver::KeyRegistry) -> Self {
+/// Whether the names `a` and `b` denote the same name; unless both are case-sensitive (i.e.,
+/// quoted), they are compared case-insensitively.
+fn same_name(a: &SymbolPrimitive<'_>, b: &SymbolPrimitive<'_>) -> bool {
+    match (&a.case, &b.case) {
+        (CaseSensitivity::CaseSensitive, CaseSensitivity::CaseSensitive) => a.value == b.value,
+        _ => UniCase::new(a.value) == UniCase::new(b.value),
+    }
+}
+
+/// Collects the ids of the `FROM` items in `source`.
+fn from_let_ids(source: &ast::FromSource, ids: &mut Vec<NodeId>) {
+    match source {
+        ast::FromSource::FromLet(from_let) => ids.push(from_let.id),
+        ast::FromSource::Join(join) => {
+            from_let_ids(&join.node.left, ids);
+            from_let_ids(&join.node.right, ids);
+        }
+    }
+}
+
+/// The name under which a `FROM` source binds its values; for a join, this is the name bound by
+/// its left-most source.
+fn bindings_alias(op: &BindingsOp) -> Option<&str> {
+    match op {
+        BindingsOp::Scan(logical::Scan { as_key, .. })
+        | BindingsOp::Unpivot(logical::Unpivot { as_key, .. }) => Some(as_key),
+        BindingsOp::Join(logical::Join { left, .. }) => bindings_alias(left),
+        _ => None,
+    }
+}
+
+/// Rewrites the lowered `USING` paths of a join into a conjunction of equalities between the
+/// paths evaluated relative to the left and right sources, e.g., `a JOIN b USING (x, y.z)` into
+/// `a.x = b.x AND a.y.z = b.y.z`.
+fn join_using_predicate(
+    paths: &[Path],
+    lowered: Vec<ValueExpr>,
+    lalias: &str,
+    ralias: &str,
+) -> Option<ValueExpr> {
+    let relative_to = |alias: &str, key: &BindingsName, steps: &[PathComponent]| {
+        let mut components = vec![PathComponent::Key(key.clone())];
+        components.extend_from_slice(steps);
+        ValueExpr::Path(
+            Box::new(ValueExpr::VarRef(BindingsName::CaseSensitive(
+                alias.to_string(),
+            ))),
+            components,
+        )
+    };
+
+    std::iter::zip(paths, lowered)
+        .filter_map(|(path, lowered)| {
+            let key = match path.root.as_ref() {
+                Expr::VarRef(varref) => match varref.node.name.case {
+                    CaseSensitivity::CaseSensitive => {
+                        BindingsName::CaseSensitive(varref.node.name.value.to_string())
+                    }
+                    CaseSensitivity::CaseInsensitive => {
+                        BindingsName::CaseInsensitive(varref.node.name.value.to_string())
+                    }
+                },
+                _ => return None,
+            };
+            let steps = match &lowered {
+                ValueExpr::Path(_, steps) => steps.as_slice(),
+                _ => &[],
+            };
+            Some(ValueExpr::BinaryExpr(
+                logical::BinaryOp::Eq,
+                Box::new(relative_to(lalias, &key, steps)),
+                Box::new(relative_to(ralias, &key, steps)),
+            ))
+        })
+        .reduce(|lhs, rhs| {
+            ValueExpr::BinaryExpr(logical::BinaryOp::And, Box::new(lhs), Box::new(rhs))
+        })
+}
+
+/// Resolves the variable at the root of the `FROM` source `expr`, lowered from `source`, against
+/// the global environment before the local one, unless it is qualified by `@`.
+///
+/// Cf. specification section 10
+fn qualify_from_source(source: &ast::Expr, expr: ValueExpr) -> ValueExpr {
+    let qualifier = match source {
+        Expr::VarRef(varref) => &varref.node.qualifier,
+        Expr::Path(path) => match path.node.root.as_ref() {
+            Expr::VarRef(varref) => &varref.node.qualifier,
+            _ => return expr,
+        },
+        _ => return expr,
+    };
+    if let ScopeQualifier::Qualified = qualifier {
+        return expr;
+    }
+    match expr {
+        ValueExpr::VarRef(name) => ValueExpr::GlobalVarRef(name),
+        ValueExpr::Path(root, steps) => match *root {
+            ValueExpr::VarRef(name) => {
+                ValueExpr::Path(Box::new(ValueExpr::GlobalVarRef(name)), steps)
+            }
+            root => ValueExpr::Path(Box::new(root), steps),
+        },
+        expr => expr,
+    }
+}
+
+impl<'a, 'ast> AstToLogical<'a, 'ast> {
+    pub fn new(catalog: &'a dyn Catalog, registry: name_resolver::KeyRegistry<'ast>) -> Self {
         let fnsym_tab: &FnSymTab = &FN_SYM_TAB;
         AstToLogical {
             id_stack: Default::default(),
@@ -225,6 +333,8 @@ impl<'a> AstToLogical<'a> {
             path_stack: Default::default(),
             sort_stack: Default::default(),
             aggregate_exprs: Default::default(),
+            subquery_stack: Default::default(),
+            ctes: Default::default(),
 
             from_lets: Default::default(),
 
@@ -247,7 +357,7 @@ impl<'a> AstToLogical<'a> {
 
     pub fn lower_query(
         mut self,
-        query: &ast::AstNode<ast::TopLevelQuery>,
+        query: &'ast ast::AstNode<ast::TopLevelQuery>,
     ) -> Result<logical::LogicalPlan<logical::BindingsOp>, AstTransformationError> {
         query.visit(&mut self);
         if !self.errors.is_empty() {
@@ -264,29 +374,43 @@ impl<'a> AstToLogical<'a> {
     }
 
     #[inline]
-    fn gen_id(&self) -> SymbolPrimitive {
+    fn gen_id(&self) -> String {
         // TODO assure non-collision with provided identifiers. e.g., we shouldn't generate `_1` if the query contains `AS _1`
-        SymbolPrimitive {
-            value: self.id.id(),
-            case: CaseSensitivity::CaseInsensitive,
-        }
+        self.id.id()
     }
 
     #[inline]
-    fn infer_id(&self, expr: &ValueExpr, as_alias: &Option<SymbolPrimitive>) -> SymbolPrimitive {
+    fn infer_id(&self, expr: &ValueExpr, as_alias: &Option<SymbolPrimitive<'_>>) -> String {
         as_alias
-            .to_owned()
+            .as_ref()
+            .map(|sym| sym.value.to_string())
             .or_else(|| infer_id(expr))
             .unwrap_or_else(|| self.gen_id())
     }
 
+    /// Returns the lowered query of the common table expression referenced by `name`, if any.
+    fn resolve_cte(&self, name: &SymbolPrimitive<'_>) -> Option<&ValueExpr> {
+        self.ctes
+            .iter()
+            .rev()
+            .find(|(cte, _)| match name.case {
+                CaseSensitivity::CaseSensitive => cte.value == name.value,
+                CaseSensitivity::CaseInsensitive => {
+                    UniCase::new(cte.value) == UniCase::new(name.value)
+                }
+            })
+            .map(|(_, query)| query)
+    }
+
     fn resolve_varref(&self, varref: &ast::VarRef) -> logical::ValueExpr {
         // Convert a `SymbolPrimitive` into a `BindingsName`
-        fn symprim_to_binding(sym: &SymbolPrimitive) -> BindingsName {
+        fn symprim_to_binding(sym: &SymbolPrimitive<'_>) -> BindingsName {
             match sym.case {
-                CaseSensitivity::CaseSensitive => BindingsName::CaseSensitive(sym.value.clone()),
+                CaseSensitivity::CaseSensitive => {
+                    BindingsName::CaseSensitive(sym.value.to_string())
+                }
                 CaseSensitivity::CaseInsensitive => {
-                    BindingsName::CaseInsensitive(sym.value.clone())
+                    BindingsName::CaseInsensitive(sym.value.to_string())
                 }
             }
         }
@@ -314,8 +438,12 @@ impl<'a> AstToLogical<'a> {
                 for lookup in &name_ref.lookup {
                     match lookup {
                         name_resolver::NameLookup::Global => {
-                            if !lookups.contains(&var_ref_expr) {
-                                lookups.push(var_ref_expr.clone())
+                            let global = self
+                                .resolve_cte(&name_ref.sym)
+                                .unwrap_or(&var_ref_expr)
+                                .clone();
+                            if !lookups.contains(&global) {
+                                lookups.push(global)
                             }
                         }
                         name_resolver::NameLookup::Local => {
@@ -341,7 +469,7 @@ impl<'a> AstToLogical<'a> {
                                             if sym == &varref.name {
                                                 let expr = ValueExpr::VarRef(
                                                     sym_to_binding(produce).unwrap_or_else(|| {
-                                                        symprim_to_binding(&self.gen_id())
+                                                        BindingsName::CaseInsensitive(self.gen_id())
                                                     }),
                                                 );
                                                 if !lookups.contains(&expr) {
@@ -354,7 +482,7 @@ impl<'a> AstToLogical<'a> {
                                         let path = logical::ValueExpr::Path(
                                             Box::new(ValueExpr::VarRef(
                                                 sym_to_binding(produce).unwrap_or_else(|| {
-                                                    symprim_to_binding(&self.gen_id())
+                                                    BindingsName::CaseInsensitive(self.gen_id())
                                                 }),
                                             )),
                                             vec![PathComponent::Key(var_binding.clone())],
@@ -376,7 +504,25 @@ impl<'a> AstToLogical<'a> {
         // TODO in the presence of schema, error if the variable reference doesn't correspond to a data table
 
         // assume global
-        ValueExpr::VarRef(symprim_to_binding(&varref.name))
+        match self.resolve_cte(&varref.name) {
+            Some(cte) => cte.clone(),
+            None => ValueExpr::VarRef(symprim_to_binding(&varref.name)),
+        }
+    }
+
+    #[inline]
+    fn enter_subquery(&mut self) {
+        let plan = std::mem::take(&mut self.plan);
+        let aggregate_exprs = std::mem::take(&mut self.aggregate_exprs);
+        self.subquery_stack.push((plan, aggregate_exprs));
+        self.enter_benv();
+    }
+
+    #[inline]
+    fn exit_subquery(&mut self) -> Option<LogicalPlan<BindingsOp>> {
+        let (plan, aggregate_exprs) = self.subquery_stack.pop()?;
+        self.aggregate_exprs = aggregate_exprs;
+        Some(std::mem::replace(&mut self.plan, plan))
     }
 
     #[inline]
@@ -391,6 +537,22 @@ impl<'a> AstToLogical<'a> {
         self.q_stack.pop().expect("q level")
     }
 
+    /// Disallows calls to aggregate functions of the current query until the matching
+    /// [`exit_no_aggregates`](Self::exit_no_aggregates), e.g., in its `WHERE` clause.
+    #[inline]
+    fn enter_no_aggregates(&mut self, clause: &'static str) {
+        if let Some(clauses) = self.q_stack.last_mut() {
+            clauses.no_aggregates_in.push(clause);
+        }
+    }
+
+    #[inline]
+    fn exit_no_aggregates(&mut self) {
+        if let Some(clauses) = self.q_stack.last_mut() {
+            clauses.no_aggregates_in.pop();
+        }
+    }
+
     #[inline]
     fn current_ctx(&self) -> Option<&QueryContext> {
         self.ctx_stack.last()
@@ -505,7 +667,7 @@ impl<'a> AstToLogical<'a> {
 // so there is nothing done between the `enter_<x>` and `exit_<x>` calls.
 // By convention, processing for them is done in the `enter_<x>` calls here.
 //
-impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
+impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a, 'ast> {
     fn enter_ast_node(&mut self, id: NodeId) -> Traverse {
         self.id_stack.push(id);
         Traverse::Continue
@@ -520,6 +682,10 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         not_yet_implemented_fault!(self, "Item");
     }
 
+    fn enter_exec(&mut self, _exec: &'ast Exec) -> Traverse {
+        not_yet_implemented_fault!(self, "Exec".to_string());
+    }
+
     fn enter_ddl(&mut self, _ddl: &'ast Ddl) -> Traverse {
         not_yet_implemented_fault!(self, "Ddl".to_string());
     }
@@ -593,6 +759,91 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         Traverse::Continue
     }
 
+    fn enter_with_clause(&mut self, with_clause: &'ast ast::WithClause) -> Traverse {
+        if with_clause.recursive {
+            not_yet_implemented_fault!(self, "WITH RECURSIVE".to_string());
+        }
+        let names: Vec<_> = with_clause
+            .withs
+            .iter()
+            .map(|with_element| &with_element.node.query_name)
+            .collect();
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].iter().any(|prev| same_name(prev, name)) {
+                self.errors.push(AstTransformError::DuplicateName(format!(
+                    "common table expression `{}` is defined more than once",
+                    name.value
+                )));
+                return Traverse::Stop;
+            }
+        }
+        self.enter_env();
+        Traverse::Continue
+    }
+
+    fn exit_with_clause(&mut self, _with_clause: &'ast ast::WithClause) -> Traverse {
+        let env = self.exit_env();
+        eq_or_fault!(self, env.len(), 0, "env.len() != 0");
+        Traverse::Continue
+    }
+
+    fn enter_with_element(&mut self, with_element: &'ast ast::WithElement) -> Traverse {
+        if with_element.columns.is_some() {
+            not_yet_implemented_fault!(self, "WITH column list".to_string());
+        }
+        Traverse::Continue
+    }
+
+    fn exit_with_element(&mut self, with_element: &'ast ast::WithElement) -> Traverse {
+        let Some(query) = self.vexpr_stack.last_mut().and_then(Vec::pop) else {
+            self.errors.push(AstTransformError::IllegalState(
+                "Expected a lowered WITH query".to_string(),
+            ));
+            return Traverse::Stop;
+        };
+        self.ctes.push((with_element.query_name.clone(), query));
+        Traverse::Continue
+    }
+
+    fn enter_expr(&mut self, expr: &'ast Expr) -> Traverse {
+        // subqueries are lowered into plans of their own
+        if let Expr::Query(_) | Expr::ScalarSubQuery(_) = expr {
+            self.enter_subquery();
+        }
+        Traverse::Continue
+    }
+
+    fn exit_expr(&mut self, expr: &'ast Expr) -> Traverse {
+        let coercion = match expr {
+            Expr::Query(_) => logical::SubQueryCoercion::Collection,
+            Expr::ScalarSubQuery(_) => logical::SubQueryCoercion::Scalar,
+            _ => return Traverse::Continue,
+        };
+
+        let mut benv = self.exit_benv();
+        eq_or_fault!(self, benv.len(), 1, "benv.len() != 1");
+        let Some(out) = benv.pop() else {
+            self.errors.push(AstTransformError::IllegalState(
+                "Expect subquery output".to_string(),
+            ));
+            return Traverse::Stop;
+        };
+        let sink_id = self.plan.add_operator(BindingsOp::Sink);
+        self.plan.add_flow(out, sink_id);
+
+        let Some(plan) = self.exit_subquery() else {
+            self.errors.push(AstTransformError::IllegalState(
+                "Expect subquery level".to_string(),
+            ));
+            return Traverse::Stop;
+        };
+        self.push_vexpr(ValueExpr::SubQueryExpr(logical::SubQueryExpr {
+            plan,
+            coercion,
+        }));
+        Traverse::Continue
+    }
+
     fn enter_query(&mut self, query: &'ast Query) -> Traverse {
         self.enter_benv();
         if let QuerySet::Select(_) = query.set.node {
@@ -750,11 +1001,25 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
 
         let select: BindingsOp = match _projection_kind {
             ProjectionKind::ProjectStar => logical::BindingsOp::ProjectAll,
-            ProjectionKind::ProjectList(_) => {
-                true_or_fault!(self, env.len().is_even(), "env.len() is not even");
-                let mut exprs = Vec::with_capacity(env.len() / 2);
+            ProjectionKind::ProjectList(items) => {
+                // Each `<expr> [AS <id>]` item lowers to its expression and alias, and each
+                // `<expr>.*` item to its expression only.
+                let expected = items
+                    .iter()
+                    .map(|item| match item.node {
+                        ProjectItem::ProjectAll(_) => 1,
+                        ProjectItem::ProjectExpr(_) => 2,
+                    })
+                    .sum::<usize>();
+                eq_or_fault!(self, env.len(), expected, "env.len() != expected");
+                let mut exprs = Vec::with_capacity(items.len());
                 let mut iter = env.into_iter();
-                while let Some(value) = iter.next() {
+                for item in items {
+                    let value = iter.next().unwrap();
+                    if let ProjectItem::ProjectAll(_) = item.node {
+                        exprs.push((None, value));
+                        continue;
+                    }
                     let alias = iter.next().unwrap();
                     let alias = match alias {
                         ValueExpr::Lit(lit) => match *lit {
@@ -775,10 +1040,42 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
                             "".to_string()
                         }
                     };
-                    exprs.push((alias, value));
+                    exprs.push((Some(alias), value));
                 }
 
-                logical::BindingsOp::Project(logical::Project { exprs })
+                if exprs.iter().all(|(alias, _)| alias.is_some()) {
+                    let exprs = exprs
+                        .into_iter()
+                        .filter_map(|(alias, value)| Some((alias?, value)))
+                        .collect();
+                    logical::BindingsOp::Project(logical::Project { exprs })
+                } else {
+                    // Expand each `<expr>.*` item into the attributes of its value, e.g.,
+                    // `SELECT t.*, u.name AS name` into `SELECT VALUE tuple_union(t, {'name': u.name})`
+                    let arguments = exprs
+                        .into_iter()
+                        .group_by(|(alias, _)| alias.is_some())
+                        .into_iter()
+                        .flat_map(|(aliased, group)| {
+                            if aliased {
+                                let mut tuple = TupleExpr::new();
+                                for (alias, value) in group {
+                                    let alias = alias.unwrap_or_default();
+                                    tuple.attrs.push(ValueExpr::Lit(Box::new(alias.into())));
+                                    tuple.values.push(value);
+                                }
+                                vec![ValueExpr::TupleExpr(tuple)]
+                            } else {
+                                group.map(|(_, value)| value).collect()
+                            }
+                        })
+                        .collect();
+                    let expr = ValueExpr::Call(logical::CallExpr {
+                        name: logical::CallName::TupleUnion,
+                        arguments,
+                    });
+                    logical::BindingsOp::ProjectValue(logical::ProjectValue { expr })
+                }
             }
             ProjectionKind::ProjectPivot(_) => {
                 eq_or_fault!(self, env.len(), 2, "env.len() != 2");
@@ -808,7 +1105,7 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
             .expect("alias");
         // TODO intern strings
         let as_key = match as_key {
-            name_resolver::Symbol::Known(sym) => sym.value.clone(),
+            name_resolver::Symbol::Known(sym) => sym.value.to_string(),
             name_resolver::Symbol::Unknown(id) => format!("_{id}"),
         };
         self.push_value(as_key.into());
@@ -826,48 +1123,48 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
 
         let rhs = env.pop().unwrap();
         let lhs = env.pop().unwrap();
-        if _bin_op.kind == BinOpKind::Is {
-            let is_type = match rhs {
-                ValueExpr::Lit(lit) => match lit.as_ref() {
-                    Value::Null => logical::Type::NullType,
-                    Value::Missing => logical::Type::MissingType,
+        let op = match _bin_op.kind {
+            BinOpKind::Add => logical::BinaryOp::Add,
+            BinOpKind::Div => logical::BinaryOp::Div,
+            BinOpKind::Exp => logical::BinaryOp::Exp,
+            BinOpKind::Mod => logical::BinaryOp::Mod,
+            BinOpKind::Mul => logical::BinaryOp::Mul,
+            BinOpKind::Sub => logical::BinaryOp::Sub,
+            BinOpKind::And => logical::BinaryOp::And,
+            BinOpKind::Or => logical::BinaryOp::Or,
+            BinOpKind::Concat => logical::BinaryOp::Concat,
+            BinOpKind::Eq => logical::BinaryOp::Eq,
+            BinOpKind::Gt => logical::BinaryOp::Gt,
+            BinOpKind::Gte => logical::BinaryOp::Gteq,
+            BinOpKind::Lt => logical::BinaryOp::Lt,
+            BinOpKind::Lte => logical::BinaryOp::Lteq,
+            BinOpKind::Ne => logical::BinaryOp::Neq,
+            BinOpKind::IsDistinctFrom => logical::BinaryOp::IsDistinctFrom,
+            BinOpKind::Is => {
+                let is_type = match rhs {
+                    ValueExpr::Lit(lit) => match lit.as_ref() {
+                        Value::Null => logical::Type::NullType,
+                        Value::Missing => logical::Type::MissingType,
+                        _ => {
+                            not_yet_implemented_fault!(
+                                self,
+                                "Unsupported rhs literal for `IS`".to_string()
+                            );
+                        }
+                    },
                     _ => {
-                        not_yet_implemented_fault!(
-                            self,
-                            "Unsupported rhs literal for `IS`".to_string()
-                        );
+                        not_yet_implemented_fault!(self, "Unsupported rhs for `IS`".to_string());
                     }
-                },
-                _ => {
-                    not_yet_implemented_fault!(self, "Unsupported rhs for `IS`".to_string());
-                }
-            };
-            self.push_vexpr(ValueExpr::IsTypeExpr(IsTypeExpr {
-                not: false,
-                expr: Box::new(lhs),
-                is_type,
-            }));
-        } else {
-            let op = match _bin_op.kind {
-                BinOpKind::Add => logical::BinaryOp::Add,
-                BinOpKind::Div => logical::BinaryOp::Div,
-                BinOpKind::Exp => logical::BinaryOp::Exp,
-                BinOpKind::Mod => logical::BinaryOp::Mod,
-                BinOpKind::Mul => logical::BinaryOp::Mul,
-                BinOpKind::Sub => logical::BinaryOp::Sub,
-                BinOpKind::And => logical::BinaryOp::And,
-                BinOpKind::Or => logical::BinaryOp::Or,
-                BinOpKind::Concat => logical::BinaryOp::Concat,
-                BinOpKind::Eq => logical::BinaryOp::Eq,
-                BinOpKind::Gt => logical::BinaryOp::Gt,
-                BinOpKind::Gte => logical::BinaryOp::Gteq,
-                BinOpKind::Lt => logical::BinaryOp::Lt,
-                BinOpKind::Lte => logical::BinaryOp::Lteq,
-                BinOpKind::Ne => logical::BinaryOp::Neq,
-                BinOpKind::Is => unreachable!(),
-            };
-            self.push_vexpr(ValueExpr::BinaryExpr(op, Box::new(lhs), Box::new(rhs)));
-        }
+                };
+                self.push_vexpr(ValueExpr::IsTypeExpr(IsTypeExpr {
+                    not: false,
+                    expr: Box::new(lhs),
+                    is_type,
+                }));
+                return Traverse::Continue;
+            }
+        };
+        self.push_vexpr(ValueExpr::BinaryExpr(op, Box::new(lhs), Box::new(rhs)));
         Traverse::Continue
     }
 
@@ -914,8 +1211,31 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         let mut env = self.exit_env();
         eq_or_fault!(self, env.len(), 2, "env.len() != 2");
 
-        let rhs = env.pop().unwrap();
+        let mut rhs = env.pop().unwrap();
         let lhs = env.pop().unwrap();
+        // as for SQL's `IN` subqueries, the tuples of a `SELECT` subquery projecting a single
+        // attribute are coerced to the values of the attribute, e.g., `SELECT u.a FROM u` in
+        // `t.a IN (SELECT u.a FROM u)`
+        if let ValueExpr::SubQueryExpr(logical::SubQueryExpr {
+            plan,
+            coercion: logical::SubQueryCoercion::Collection,
+        }) = &mut rhs
+        {
+            let projects: Vec<_> = plan
+                .operators_by_id()
+                .filter_map(|(id, op)| match op {
+                    BindingsOp::Project(logical::Project { exprs }) if exprs.len() == 1 => {
+                        Some((id, exprs[0].1.clone()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            for (id, expr) in projects {
+                if let Some(op) = plan.operator_as_mut(id) {
+                    *op = BindingsOp::ProjectValue(logical::ProjectValue { expr });
+                }
+            }
+        }
         self.push_vexpr(logical::ValueExpr::BinaryExpr(
             logical::BinaryOp::In,
             Box::new(lhs),
@@ -924,6 +1244,22 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         Traverse::Continue
     }
 
+    fn enter_exists(&mut self, _exists: &'ast ast::Exists) -> Traverse {
+        self.enter_env();
+        Traverse::Continue
+    }
+    fn exit_exists(&mut self, _exists: &'ast ast::Exists) -> Traverse {
+        let mut env = self.exit_env();
+        eq_or_fault!(self, env.len(), 1, "env.len() != 1");
+
+        let value = env.pop().unwrap();
+        self.push_vexpr(ValueExpr::Call(logical::CallExpr {
+            name: logical::CallName::Exists,
+            arguments: vec![value],
+        }));
+        Traverse::Continue
+    }
+
     fn enter_like(&mut self, _like: &'ast Like) -> Traverse {
         self.enter_env();
         Traverse::Continue
@@ -1012,7 +1348,7 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         let mut env = self.exit_env();
         match _call_arg {
             CallArg::Star() => {
-                not_yet_implemented_fault!(self, "* as a call argument".to_string());
+                self.push_call_arg(CallArgument::Star());
             }
             CallArg::Positional(_) => {
                 eq_or_fault!(self, env.len(), 1, "env.len() != 1");
@@ -1107,14 +1443,30 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
 
     fn enter_call_agg(&mut self, _call_agg: &'ast CallAgg) -> Traverse {
         self.enter_call();
+        self.enter_no_aggregates("the argument of an aggregate function");
         Traverse::Continue
     }
 
     fn exit_call_agg(&mut self, call_agg: &'ast CallAgg) -> Traverse {
         // Relates to the SQL aggregation functions (e.g. AVG, COUNT, SUM) -- not the `COLL_`
         // functions
+        self.exit_no_aggregates();
         let mut env = self.exit_call();
         let name = call_agg.func_name.value.to_lowercase();
+        let Some(clauses) = self.q_stack.last() else {
+            self.errors
+                .push(AstTransformError::IllegalAggregation(format!(
+                    "{name} outside of a query"
+                )));
+            return Traverse::Stop;
+        };
+        if let Some(clause) = clauses.no_aggregates_in.last() {
+            self.errors
+                .push(AstTransformError::IllegalAggregation(format!(
+                    "{name} in {clause}"
+                )));
+            return Traverse::Stop;
+        }
 
         // Rewrites the SQL aggregation function call to be a variable reference that the `GROUP BY`
         // clause will add to the binding tuples.
@@ -1127,18 +1479,24 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
 
         true_or_fault!(self, !env.is_empty(), "env is empty");
         // Default set quantifier if the set quantifier keyword is omitted will be `ALL`
-        let (setq, arg) = match env.pop().unwrap() {
-            CallArgument::Positional(ve) => (logical::SetQuantifier::All, ve),
-            CallArgument::Named(name, ve) => match name.as_ref() {
-                "all" => (logical::SetQuantifier::All, ve),
-                "distinct" => (logical::SetQuantifier::Distinct, ve),
-                _ => {
-                    self.errors.push(AstTransformError::IllegalState(
-                        "Invalid set quantifier".to_string(),
-                    ));
-                    return Traverse::Stop;
-                }
-            },
+        let setq = match call_agg.setq {
+            Some(SetQuantifier::Distinct) => logical::SetQuantifier::Distinct,
+            Some(SetQuantifier::All) | None => logical::SetQuantifier::All,
+        };
+        let arg = match env.pop().unwrap() {
+            CallArgument::Positional(ve) => ve,
+            CallArgument::Named(_, _) => {
+                self.errors.push(AstTransformError::IllegalState(
+                    "Named argument to aggregate function".to_string(),
+                ));
+                return Traverse::Stop;
+            }
+            // `COUNT(*)` counts every binding tuple of the group; since a literal is never
+            // `NULL` or `MISSING`, lower it as `COUNT(1)`
+            CallArgument::Star() if name == "count" => ValueExpr::Lit(Box::new(Value::from(1))),
+            CallArgument::Star() => {
+                not_yet_implemented_fault!(self, format!("* as an argument to {name}"));
+            }
         };
 
         let agg_expr = match name.as_str() {
@@ -1185,10 +1543,16 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
                 }
             }
         };
-        self.aggregate_exprs.push(agg_expr);
+        self.aggregate_exprs.push(agg_expr.clone());
         // PartiQL permits SQL aggregations without a GROUP BY (e.g. SELECT SUM(t.a) FROM ...)
         // What follows adds a GROUP BY clause with the rewrite `... GROUP BY true AS $__gk`
-        if self.current_clauses_mut().group_by_clause.is_none() {
+        if let Some(group_by_id) = self.current_clauses_mut().group_by_clause {
+            // Aggregations visited after the `GROUP BY` clause (e.g., in `HAVING`) are added to the
+            // already lowered `GROUP BY` operator
+            if let Some(BindingsOp::GroupBy(group_by)) = self.plan.operator_as_mut(group_by_id) {
+                group_by.aggregate_exprs.push(agg_expr);
+            }
+        } else {
             let exprs = HashMap::from([(
                 "$__gk".to_string(),
                 ValueExpr::Lit(Box::new(Value::from(true))),
@@ -1205,6 +1569,91 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         Traverse::Continue
     }
 
+    fn enter_call_window(&mut self, _call_window: &'ast CallWindow) -> Traverse {
+        self.enter_call();
+        self.enter_env();
+        self.enter_sort();
+        Traverse::Continue
+    }
+
+    fn exit_call_window(&mut self, call_window: &'ast CallWindow) -> Traverse {
+        let order_by = self.exit_sort();
+        let partition_by = self.exit_env();
+        let args = self.exit_call();
+        let name = call_window.func_name.value.to_lowercase();
+        if self.q_stack.is_empty() {
+            self.errors
+                .push(AstTransformError::IllegalAggregation(format!(
+                    "{name} outside of a query"
+                )));
+            return Traverse::Stop;
+        }
+
+        // Rewrites the window function call to be a variable reference that a `Window` operator
+        // will add to the binding tuples, as for the SQL aggregation functions.
+        // E.g. SELECT a, RANK() OVER (ORDER BY b) AS r FROM t
+        //      SELECT a AS a, $__win_1 AS r FROM t
+        let new_name = "$__win".to_owned() + &self.agg_id.id();
+        let new_binding_name = BindingsName::CaseSensitive(new_name.clone());
+        self.push_vexpr(ValueExpr::VarRef(new_binding_name));
+
+        let args: Option<Vec<_>> = args
+            .into_iter()
+            .map(|arg| match arg {
+                CallArgument::Positional(expr) => Some(expr),
+                _ => None,
+            })
+            .collect();
+        let Some(args) = args else {
+            self.errors.push(AstTransformError::IllegalState(
+                "Non-positional argument to window function".to_string(),
+            ));
+            return Traverse::Continue;
+        };
+
+        // The window frame, if any, is ignored as none of the supported functions depends on it
+        let func = match (name.as_str(), args.len()) {
+            ("row_number", 0) => logical::WindowFunction::RowNumber,
+            ("rank", 0) => logical::WindowFunction::Rank,
+            ("dense_rank", 0) => logical::WindowFunction::DenseRank,
+            ("lag" | "lead", 1..=3) => {
+                // `LAG(expr [, offset [, default]])`, with an offset of 1 & a default of `NULL` if
+                // omitted
+                let mut args = args.into_iter();
+                let lit = |value: Value| ValueExpr::Lit(Box::new(value));
+                let offset = logical::WindowOffset {
+                    expr: Box::new(args.next().unwrap()),
+                    offset: Box::new(args.next().unwrap_or_else(|| lit(Value::from(1)))),
+                    default: Box::new(args.next().unwrap_or_else(|| lit(Value::Null))),
+                };
+                match name.as_str() {
+                    "lag" => logical::WindowFunction::Lag(offset),
+                    _ => logical::WindowFunction::Lead(offset),
+                }
+            }
+            ("row_number" | "rank" | "dense_rank" | "lag" | "lead", _) => {
+                self.errors
+                    .push(AstTransformError::InvalidNumberOfArguments(name));
+                return Traverse::Continue;
+            }
+            _ => {
+                self.errors
+                    .push(AstTransformError::UnsupportedFunction(name));
+                return Traverse::Continue;
+            }
+        };
+
+        let window = BindingsOp::Window(logical::Window {
+            partition_by,
+            order_by,
+            name: new_name,
+            func,
+        });
+        let id = self.plan.add_operator(window);
+        self.current_clauses_mut().window_clauses.push(id);
+        Traverse::Continue
+    }
+
     fn enter_var_ref(&mut self, _var_ref: &'ast VarRef) -> Traverse {
         let is_from_path = matches!(self.current_ctx(), Some(QueryContext::FromLet));
         let is_path = matches!(self.current_ctx(), Some(QueryContext::Path));
@@ -1213,15 +1662,21 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         if should_resolve {
             let options = self.resolve_varref(_var_ref);
             self.push_vexpr(options);
+        } else if let Some(cte) = is_from_path
+            .then(|| self.resolve_cte(&_var_ref.name))
+            .flatten()
+        {
+            self.push_vexpr(cte.clone());
         } else {
-            // TODO scope qualifier
             let VarRef {
                 name: SymbolPrimitive { value, case },
                 qualifier: _,
             } = _var_ref;
             let name = match case {
-                CaseSensitivity::CaseSensitive => BindingsName::CaseSensitive(value.clone()),
-                CaseSensitivity::CaseInsensitive => BindingsName::CaseInsensitive(value.clone()),
+                CaseSensitivity::CaseSensitive => BindingsName::CaseSensitive(value.to_string()),
+                CaseSensitivity::CaseInsensitive => {
+                    BindingsName::CaseInsensitive(value.to_string())
+                }
             };
             self.push_vexpr(ValueExpr::VarRef(name));
         }
@@ -1232,6 +1687,24 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         Traverse::Continue
     }
 
+    fn enter_parameter(&mut self, parameter: &'ast ast::Parameter) -> Traverse {
+        let parameter = match parameter {
+            ast::Parameter::Positional(position) => logical::Parameter::Positional(*position),
+            ast::Parameter::Named(SymbolPrimitive { value, case }) => {
+                logical::Parameter::Named(match case {
+                    CaseSensitivity::CaseSensitive => {
+                        BindingsName::CaseSensitive(value.to_string())
+                    }
+                    CaseSensitivity::CaseInsensitive => {
+                        BindingsName::CaseInsensitive(value.to_string())
+                    }
+                })
+            }
+        };
+        self.push_vexpr(ValueExpr::Parameter(parameter));
+        Traverse::Continue
+    }
+
     fn enter_path(&mut self, _path: &'ast Path) -> Traverse {
         self.enter_env();
         self.enter_path();
@@ -1266,26 +1739,20 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
                 match path {
                     ValueExpr::Lit(val) => match *val {
                         Value::Integer(idx) => logical::PathComponent::Index(idx),
+                        // e.g., `a['b']`, which is equivalent to `a."b"`
                         Value::String(k) => {
-                            logical::PathComponent::Key(BindingsName::CaseInsensitive(*k))
+                            logical::PathComponent::Key(BindingsName::CaseSensitive(*k))
+                        }
+                        expr => {
+                            logical::PathComponent::Expr(Box::new(ValueExpr::Lit(Box::new(expr))))
                         }
-                        expr => logical::PathComponent::IndexExpr(Box::new(ValueExpr::Lit(
-                            Box::new(expr),
-                        ))),
                     },
                     ValueExpr::VarRef(name) => logical::PathComponent::Key(name),
-                    expr => {
-                        // TODO if type is statically STRING, then use KeyExpr
-                        logical::PathComponent::IndexExpr(Box::new(expr))
-                    }
+                    expr => logical::PathComponent::Expr(Box::new(expr)),
                 }
             }
-            PathStep::PathWildCard => {
-                not_yet_implemented_fault!(self, "PathStep::PathWildCard".to_string());
-            }
-            PathStep::PathUnpivot => {
-                not_yet_implemented_fault!(self, "PathStep::PathUnpivot".to_string());
-            }
+            PathStep::PathWildCard => logical::PathComponent::Wildcard,
+            PathStep::PathUnpivot => logical::PathComponent::Unpivot,
         };
 
         self.push_path_step(step);
@@ -1295,10 +1762,12 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
     fn enter_from_clause(&mut self, _from_clause: &'ast FromClause) -> Traverse {
         self.enter_benv();
         self.enter_env();
+        self.enter_no_aggregates("FROM");
         Traverse::Continue
     }
 
     fn exit_from_clause(&mut self, _from_clause: &'ast FromClause) -> Traverse {
+        self.exit_no_aggregates();
         let mut benv = self.exit_benv();
         eq_or_fault!(self, benv.len(), 1, "benv.len() != 1");
 
@@ -1335,15 +1804,17 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         let expr = env.pop().unwrap();
 
         let FromLet {
+            expr: source,
             kind,
             as_alias,
             at_alias,
             ..
         } = from_let;
-        let as_key = self.infer_id(&expr, as_alias).value;
+        let as_key = self.infer_id(&expr, as_alias);
+        let expr = qualify_from_source(source, expr);
         let at_key = at_alias
             .as_ref()
-            .map(|SymbolPrimitive { value, case: _ }| value.clone());
+            .map(|SymbolPrimitive { value, case: _ }| value.to_string());
 
         let bexpr = match kind {
             FromLetKind::Scan => logical::BindingsOp::Scan(logical::Scan {
@@ -1373,13 +1844,10 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         eq_or_fault!(self, benv.len(), 2, "benv.len() != 2");
 
         let mut env = self.exit_env();
-        true_or_fault!(
-            self,
-            (0..=1).contains(&env.len()),
-            "env.len() is not between 0 and 1"
-        );
 
-        let Join { kind, .. } = join;
+        let Join {
+            kind, predicate, ..
+        } = join;
 
         let kind = match kind {
             JoinKind::Inner => logical::JoinKind::Inner,
@@ -1389,12 +1857,55 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
             JoinKind::Cross => logical::JoinKind::Cross,
         };
 
-        let on = env.pop();
-
         let rid = benv.pop().unwrap();
         let lid = benv.pop().unwrap();
         let left = Box::new(self.plan.operator(lid).unwrap().clone());
         let right = Box::new(self.plan.operator(rid).unwrap().clone());
+
+        // The right side of a `RIGHT` or `FULL` join is evaluated independently of its left
+        if matches!(kind, logical::JoinKind::Right | logical::JoinKind::Full) {
+            let (mut lids, mut rids) = (vec![], vec![]);
+            from_let_ids(&join.left, &mut lids);
+            from_let_ids(&join.right, &mut rids);
+            let is_correlated = rids.iter().any(|rid| {
+                self.key_registry
+                    .correlated
+                    .get(rid)
+                    .is_some_and(|preceding| preceding.iter().any(|id| lids.contains(id)))
+            });
+            if is_correlated {
+                self.errors.push(AstTransformError::IllegalLateralReference(
+                    "right side of a RIGHT or FULL join may not reference its left side"
+                        .to_string(),
+                ));
+                return Traverse::Stop;
+            }
+        }
+
+        let on = match predicate.as_ref().map(|spec| &spec.node) {
+            Some(JoinSpec::Using(paths)) => {
+                eq_or_fault!(self, env.len(), paths.len(), "env.len() != paths.len()");
+                match (bindings_alias(&left), bindings_alias(&right)) {
+                    (Some(lalias), Some(ralias)) => {
+                        join_using_predicate(paths, env, lalias, ralias)
+                    }
+                    _ => {
+                        not_yet_implemented_fault!(
+                            self,
+                            "JoinSpec::Using on an unaliased join operand".to_string()
+                        );
+                    }
+                }
+            }
+            _ => {
+                true_or_fault!(
+                    self,
+                    (0..=1).contains(&env.len()),
+                    "env.len() is not between 0 and 1"
+                );
+                env.pop()
+            }
+        };
         let join = logical::BindingsOp::Join(logical::Join {
             kind,
             on,
@@ -1414,7 +1925,8 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
                 // visitor recurse into expr will put the condition in the current env
             }
             JoinSpec::Using(_) => {
-                not_yet_implemented_fault!(self, "JoinSpec::Using".to_string());
+                // visitor recurse into paths will put them in the current env; they are
+                // rewritten into an equi-join condition by `exit_join`
             }
             JoinSpec::Natural => {
                 not_yet_implemented_fault!(self, "JoinSpec::Natural".to_string());
@@ -1423,12 +1935,38 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         Traverse::Continue
     }
 
+    fn enter_let(&mut self, _let: &'ast ast::Let) -> Traverse {
+        self.enter_env();
+        Traverse::Continue
+    }
+
+    fn exit_let(&mut self, let_clause: &'ast ast::Let) -> Traverse {
+        let env = self.exit_env();
+        eq_or_fault!(
+            self,
+            env.len(),
+            let_clause.let_bindings.len(),
+            "env.len() != let_bindings.len()"
+        );
+
+        let bindings = std::iter::zip(&let_clause.let_bindings, env)
+            .map(|(binding, expr)| (binding.as_alias.value.to_string(), expr))
+            .collect();
+        let let_op = logical::BindingsOp::Let(logical::Let { bindings });
+        let id = self.plan.add_operator(let_op);
+
+        self.current_clauses_mut().let_clause.replace(id);
+        Traverse::Continue
+    }
+
     fn enter_where_clause(&mut self, _where_clause: &'ast ast::WhereClause) -> Traverse {
         self.enter_env();
+        self.enter_no_aggregates("WHERE");
         Traverse::Continue
     }
 
     fn exit_where_clause(&mut self, _where_clause: &'ast ast::WhereClause) -> Traverse {
+        self.exit_no_aggregates();
         let mut env = self.exit_env();
         eq_or_fault!(self, env.len(), 1, "env.len() != 1");
 
@@ -1479,7 +2017,7 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         let group_as_alias = _group_by_expr
             .group_as_alias
             .as_ref()
-            .map(|SymbolPrimitive { value, case: _ }| value.clone());
+            .map(|SymbolPrimitive { value, case: _ }| value.to_string());
 
         let strategy = match _group_by_expr.strategy {
             GroupingStrategy::GroupFull => logical::GroupingStrategy::GroupFull,
@@ -1493,8 +2031,9 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         //   SELECT t.a + 1 AS a FROM t GROUP BY t.a + 1 AS some_alias
         // Since the group by key expression (t.a + 1) is the same as the select list expression, we
         // can replace the query to be `SELECT some_alias AS a FROM t GROUP BY t.a + 1 AS some_alias`
-        // This isn't quite correct as it doesn't deal with SELECT VALUE expressions and expressions
-        // that are in the `HAVING` and `ORDER BY` clauses.
+        // The same goes for the key expressions nested in the select list expressions and in
+        // `SELECT VALUE` expressions, e.g. `{'a': t.a + 1}`. This isn't quite correct as it doesn't
+        // deal with expressions that are in the `HAVING` and `ORDER BY` clauses.
         let select_clause_op_id = self.current_clauses_mut().select_clause;
         if select_clause_op_id.is_none() {
             self.errors.push(AstTransformError::IllegalState(
@@ -1506,11 +2045,12 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
             .plan
             .operator_as_mut(select_clause_op_id.expect("select_clause_op_id not None"))
             .unwrap();
-        let mut binding = Vec::new();
-        let select_clause_exprs = match select_clause {
-            BindingsOp::Project(ref mut project) => &mut project.exprs,
-            BindingsOp::ProjectAll => &mut binding,
-            BindingsOp::ProjectValue(_) => &mut binding, // TODO: replacement of SELECT VALUE expressions
+        let mut select_clause_exprs: Vec<&mut ValueExpr> = match select_clause {
+            BindingsOp::Project(ref mut project) => {
+                project.exprs.iter_mut().map(|(_, expr)| expr).collect()
+            }
+            BindingsOp::ProjectAll => vec![],
+            BindingsOp::ProjectValue(ref mut project) => vec![&mut project.expr],
             _ => {
                 self.errors.push(AstTransformError::IllegalState(
                     "Unexpected project type".to_string(),
@@ -1541,12 +2081,12 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
                     return Traverse::Stop;
                 }
             };
-            for (alias, expr) in select_clause_exprs.iter_mut() {
-                if *expr == value {
-                    let new_binding_name = BindingsName::CaseSensitive(alias.clone());
-                    let new_expr = ValueExpr::VarRef(new_binding_name);
-                    *expr = new_expr
-                }
+            let mut replace = ReplaceGroupKey {
+                key: &value,
+                alias: &alias,
+            };
+            for expr in select_clause_exprs.iter_mut() {
+                expr.rewrite(&mut replace);
             }
             exprs.insert(alias, value);
         }
@@ -1559,11 +2099,21 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         });
 
         let id = self.plan.add_operator(group_by);
-        self.current_clauses_mut().group_by_clause.replace(id);
+        // The `GROUP BY true` added for the aggregations of the `SELECT` clause, which is lowered
+        // before the `GROUP BY` clause, is superseded
+        if let Some(implicit) = self.current_clauses_mut().group_by_clause.replace(id) {
+            self.plan.remove_operator(implicit);
+        }
+        Traverse::Continue
+    }
+
+    fn enter_group_key(&mut self, _group_key: &'ast GroupKey) -> Traverse {
+        self.enter_no_aggregates("GROUP BY");
         Traverse::Continue
     }
 
     fn exit_group_key(&mut self, _group_key: &'ast GroupKey) -> Traverse {
+        self.exit_no_aggregates();
         let as_key: &name_resolver::Symbol = self
             .key_registry
             .aliases
@@ -1571,7 +2121,7 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
             .expect("alias");
         // TODO intern strings
         let as_key = match as_key {
-            name_resolver::Symbol::Known(sym) => sym.value.clone(),
+            name_resolver::Symbol::Known(sym) => sym.value.to_string(),
             name_resolver::Symbol::Unknown(id) => format!("_{id}"),
         };
         self.push_value(as_key.into());
@@ -1636,10 +2186,12 @@ impl<'a, 'ast> Visitor<'ast> for AstToLogical<'a> {
         _limit_offset: &'ast ast::LimitOffsetClause,
     ) -> Traverse {
         self.enter_env();
+        self.enter_no_aggregates("LIMIT or OFFSET");
         Traverse::Continue
     }
 
     fn exit_limit_offset_clause(&mut self, limit_offset: &'ast ast::LimitOffsetClause) -> Traverse {
+        self.exit_no_aggregates();
         let mut env = self.exit_env();
         true_or_fault!(
             self,
@@ -1752,11 +2304,8 @@ fn lit_to_value(lit: &Lit) -> Result<Value, AstTransformError> {
     fn tuple_pair(pair: &ast::ExprPair) -> Option<Result<(String, Value), AstTransformError>> {
         let key = match expect_lit(pair.first.as_ref()) {
             Ok(Value::String(s)) => s.as_ref().clone(),
-            Ok(_) => {
-                return Some(Err(AstTransformError::IllegalState(
-                    "non string literal in literal struct key".to_string(),
-                )))
-            }
+            // As when evaluating a struct constructor, a field whose key isn't a string is omitted
+            Ok(_) => return None,
             Err(e) => return Some(Err(e)),
         };
 
@@ -1781,8 +2330,8 @@ fn lit_to_value(lit: &Lit) -> Result<Value, AstTransformError> {
         Lit::DoubleLit(f) => Value::Real(OrderedFloat::from(*f)),
         Lit::BoolLit(b) => Value::Boolean(*b),
         Lit::IonStringLit(s) => parse_embedded_ion_str(s)?,
-        Lit::CharStringLit(s) => Value::String(Box::new(s.clone())),
-        Lit::NationalCharStringLit(s) => Value::String(Box::new(s.clone())),
+        Lit::CharStringLit(s) => Value::String(Box::new(s.to_string())),
+        Lit::NationalCharStringLit(s) => Value::String(Box::new(s.to_string())),
         Lit::BitStringLit(_) => {
             return Err(AstTransformError::NotYetImplemented(
                 "Lit::BitStringLit".to_string(),
@@ -1821,10 +2370,16 @@ fn lit_to_value(lit: &Lit) -> Result<Value, AstTransformError> {
                 "Lit::TypedLit".to_string(),
             ))
         }
+        Lit::IntervalLit(_) => {
+            return Err(AstTransformError::NotYetImplemented(
+                "Lit::IntervalLit".to_string(),
+            ))
+        }
     };
     Ok(val)
 }
 
+#[cfg(feature = "ion")]
 fn parse_embedded_ion_str(contents: &str) -> Result<Value, AstTransformError> {
     fn lit_err(literal: &str, err: impl std::error::Error) -> AstTransformError {
         AstTransformError::Literal {
@@ -1848,6 +2403,29 @@ fn parse_embedded_ion_str(contents: &str) -> Result<Value, AstTransformError> {
         .map_err(|e| lit_err(contents, e))
 }
 
+#[cfg(not(feature = "ion"))]
+fn parse_embedded_ion_str(contents: &str) -> Result<Value, AstTransformError> {
+    Err(AstTransformError::Literal {
+        literal: contents.into(),
+        error: "embedded Ion literals require the `ion` feature".into(),
+    })
+}
+
+/// Replaces the expressions equal to the key `key` of a `GROUP BY` by references to its alias.
+struct ReplaceGroupKey<'a> {
+    key: &'a ValueExpr,
+    alias: &'a str,
+}
+
+impl PlanRewriter for ReplaceGroupKey<'_> {
+    fn enter_expr(&mut self, expr: &mut ValueExpr) -> visit::Traverse {
+        if expr == self.key {
+            *expr = ValueExpr::VarRef(BindingsName::CaseSensitive(self.alias.to_string()));
+        }
+        visit::Traverse::Continue
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1858,8 +2436,9 @@ mod tests {
     fn test_plan_non_existent_fns() {
         let catalog = PartiqlCatalog::default();
         let statement = "foo(1, 2) + bar(3)";
+        let arena = partiql_ast::arena::Arena::new();
         let parsed = partiql_parser::Parser::default()
-            .parse(statement)
+            .parse(statement, &arena)
             .expect("Expect successful parse");
         let planner = LogicalPlanner::new(&catalog);
         let logical = planner.lower(&parsed);
@@ -1880,8 +2459,9 @@ mod tests {
     fn test_plan_bad_num_arguments() {
         let catalog = PartiqlCatalog::default();
         let statement = "abs(1, 2) + mod(3)";
+        let arena = partiql_ast::arena::Arena::new();
         let parsed = partiql_parser::Parser::default()
-            .parse(statement)
+            .parse(statement, &arena)
             .expect("Expect successful parse");
         let planner = LogicalPlanner::new(&catalog);
         let logical = planner.lower(&parsed);
@@ -0,0 +1,226 @@
+//! Rules computing once the expressions operators repeat.
+
+use super::pruning::Uses;
+use super::{insert_below, OptimizerRule};
+use partiql_logical::visit::{PlanRewriter, PlanVisitor, Traverse};
+use partiql_logical::{
+    BindingsOp, CallExpr, CallName, Let, LogicalPlan, OpId, PathComponent, ValueExpr,
+};
+use partiql_value::BindingsName;
+use std::collections::HashMap;
+
+/// The prefix of the names of the variables common subexpressions are bound to.
+const CSE_PREFIX: &str = "$__cse_";
+
+/// Computes the expressions a filter or a projection repeats once, binding them to variables by a
+/// `LET` the operator takes its input from, e.g., `t.a.b` in
+/// `SELECT t.a.b + 1 AS x, t.a.b * 2 AS y FROM t`.
+///
+/// Variables, their attributes, literals and parameters are not bound, nor the expressions of the
+/// plans of subqueries. The expressions of a filter are not bound if an operator it flows to uses
+/// its bindings as a whole, e.g., `SELECT *`, which would output the variables.
+///
+/// As the bound expressions are evaluated for every binding, an expression is only bound if one
+/// of its occurrences is evaluated unconditionally, i.e., is not in a `CASE` branch nor an operand
+/// evaluated only if the preceding ones do not decide the value, e.g., `10 / x` in
+/// `CASE WHEN x = 0 THEN 0 ELSE 10 / x END`. Nor are expressions calling functions that may not
+/// return the same value each call, e.g., `UTCNOW()` or those of the catalog.
+pub struct EliminateCommonSubexpressions;
+
+impl OptimizerRule for EliminateCommonSubexpressions {
+    fn name(&self) -> &'static str {
+        "eliminate_common_subexpressions"
+    }
+
+    fn apply(&self, plan: &mut LogicalPlan<BindingsOp>) -> bool {
+        let ops: Vec<_> = plan
+            .operators_by_id()
+            .filter(|&(id, op)| match op {
+                BindingsOp::Project(_) | BindingsOp::ProjectValue(_) => true,
+                BindingsOp::Filter(_) => Uses::downstream(plan, id).is_some(),
+                _ => false,
+            })
+            .map(|(id, _)| id)
+            .collect();
+        let mut changed = false;
+        for id in ops {
+            changed |= eliminate(plan, id);
+        }
+        changed
+    }
+}
+
+/// Binds the expressions the operator `id` repeats, returning whether it repeats any.
+fn eliminate(plan: &mut LogicalPlan<BindingsOp>, id: OpId) -> bool {
+    if plan.inputs(id).len() != 1 {
+        return false;
+    }
+    let mut next = plan
+        .operators()
+        .into_iter()
+        .filter_map(|op| match op {
+            BindingsOp::Let(lets) => Some(&lets.bindings),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|(name, _)| name.strip_prefix(CSE_PREFIX)?.parse::<usize>().ok())
+        .max()
+        .unwrap_or_default();
+
+    let mut bindings = vec![];
+    while let Some(repeated) = plan.operator(id).and_then(Occurrences::repeated) {
+        next += 1;
+        let name = format!("{CSE_PREFIX}{next}");
+        let mut replace = Replace {
+            expr: &repeated,
+            var: ValueExpr::VarRef(BindingsName::CaseSensitive(name.clone())),
+            depth: 0,
+        };
+        if let Some(op) = plan.operator_as_mut(id) {
+            op.rewrite(&mut replace);
+        }
+        bindings.push((name, repeated));
+    }
+    !bindings.is_empty() && insert_below(plan, id, BindingsOp::Let(Let { bindings })).is_some()
+}
+
+/// Whether binding `expr` to a variable would not save evaluating it, as it is a variable or a
+/// single attribute of one.
+fn is_trivial(expr: &ValueExpr) -> bool {
+    match expr {
+        ValueExpr::Lit(_)
+        | ValueExpr::VarRef(_)
+        | ValueExpr::GlobalVarRef(_)
+        | ValueExpr::DynamicLookup(_)
+        | ValueExpr::Parameter(_) => true,
+        ValueExpr::Path(root, components) => {
+            is_trivial(root)
+                && matches!(
+                    components[..],
+                    [PathComponent::Key(_) | PathComponent::Index(_)]
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Whether `expr` calls a function that may not return the same value each call, in it or in the
+/// plans of its subqueries.
+fn is_impure(expr: &ValueExpr) -> bool {
+    let mut impure = Impure(false);
+    expr.visit(&mut impure);
+    impure.0
+}
+
+/// Whether the expressions visited call a function that may not return the same value each call.
+struct Impure(bool);
+
+impl<'a> PlanVisitor<'a> for Impure {
+    fn enter_expr(&mut self, expr: &'a ValueExpr) -> Traverse {
+        if let ValueExpr::Call(CallExpr {
+            name: CallName::UtcNow | CallName::ByName(_),
+            ..
+        }) = expr
+        {
+            self.0 = true;
+            return Traverse::Stop;
+        }
+        Traverse::Continue
+    }
+}
+
+/// The occurrences of the expressions of an operator, outside of the plans of subqueries.
+#[derive(Default)]
+struct Occurrences<'a> {
+    /// The number of occurrences of each expression, and whether any is evaluated
+    /// unconditionally.
+    counts: HashMap<&'a ValueExpr, (usize, bool)>,
+    /// The expressions in the order they first occur in, i.e., each before its sub-expressions.
+    exprs: Vec<&'a ValueExpr>,
+    /// For each expression entered, whether it is evaluated conditionally, and the number of its
+    /// sub-expressions entered.
+    entered: Vec<(bool, usize)>,
+    depth: usize,
+}
+
+impl<'a> Occurrences<'a> {
+    /// The first expression `op` repeats and evaluates unconditionally, if any.
+    fn repeated(op: &'a BindingsOp) -> Option<ValueExpr> {
+        let mut occurrences = Occurrences::default();
+        op.visit(&mut occurrences);
+        occurrences
+            .exprs
+            .into_iter()
+            .find(|expr| {
+                let (count, unconditional) = occurrences.counts[expr];
+                count > 1 && unconditional && !is_impure(expr)
+            })
+            .cloned()
+    }
+}
+
+impl<'a> PlanVisitor<'a> for Occurrences<'a> {
+    fn enter_plan(&mut self, _plan: &'a LogicalPlan<BindingsOp>) -> Traverse {
+        self.depth += 1;
+        Traverse::Continue
+    }
+
+    fn exit_plan(&mut self, _plan: &'a LogicalPlan<BindingsOp>) -> Traverse {
+        self.depth -= 1;
+        Traverse::Continue
+    }
+
+    fn enter_expr(&mut self, expr: &'a ValueExpr) -> Traverse {
+        // only the first sub-expression of an expression is evaluated unconditionally, as e.g.
+        // operators and functions do not evaluate their other operands if it is `MISSING`, and
+        // `CASE`s their branches if their first condition holds
+        let conditional = match self.entered.last_mut() {
+            Some((conditional, entered)) => {
+                *entered += 1;
+                *conditional || *entered > 1
+            }
+            None => false,
+        };
+        self.entered.push((conditional, 0));
+        if self.depth == 0 && !is_trivial(expr) {
+            let (count, unconditional) = self.counts.entry(expr).or_default();
+            if *count == 0 {
+                self.exprs.push(expr);
+            }
+            *count += 1;
+            *unconditional |= !conditional;
+        }
+        Traverse::Continue
+    }
+
+    fn exit_expr(&mut self, _expr: &'a ValueExpr) -> Traverse {
+        self.entered.pop();
+        Traverse::Continue
+    }
+}
+
+/// Replaces the occurrences of an expression, outside of the plans of subqueries, by a variable.
+struct Replace<'a> {
+    expr: &'a ValueExpr,
+    var: ValueExpr,
+    depth: usize,
+}
+
+impl PlanRewriter for Replace<'_> {
+    fn enter_plan(&mut self, _plan: &mut LogicalPlan<BindingsOp>) -> Traverse {
+        self.depth += 1;
+        Traverse::Continue
+    }
+
+    fn exit_plan(&mut self, _plan: &mut LogicalPlan<BindingsOp>) -> Traverse {
+        self.depth -= 1;
+        Traverse::Continue
+    }
+
+    fn enter_expr(&mut self, expr: &mut ValueExpr) -> Traverse {
+        if self.depth == 0 && expr == self.expr {
+            *expr = self.var.clone();
+        }
+        Traverse::Continue
+    }
+}
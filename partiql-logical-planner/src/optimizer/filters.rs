@@ -0,0 +1,85 @@
+//! Rules simplifying the filters of plans.
+
+use super::{bypass, OptimizerRule};
+use partiql_logical::{BinaryOp, BindingsOp, Filter, LogicalPlan, ValueExpr};
+use partiql_value::Value;
+
+/// Removes the `WHERE` and `HAVING` filters whose conditions are `TRUE`, e.g., of
+/// `SELECT * FROM t WHERE TRUE`.
+pub struct RemoveTrueFilters;
+
+impl OptimizerRule for RemoveTrueFilters {
+    fn name(&self) -> &'static str {
+        "remove_true_filters"
+    }
+
+    fn apply(&self, plan: &mut LogicalPlan<BindingsOp>) -> bool {
+        let true_filters: Vec<_> = plan
+            .operators_by_id()
+            .filter(|(_, op)| match op {
+                BindingsOp::Filter(Filter { expr }) => is_true(expr),
+                BindingsOp::Having(having) => is_true(&having.expr),
+                _ => false,
+            })
+            .map(|(id, _)| id)
+            .collect();
+        for &id in &true_filters {
+            bypass(plan, id);
+        }
+        !true_filters.is_empty()
+    }
+}
+
+fn is_true(expr: &ValueExpr) -> bool {
+    matches!(expr, ValueExpr::Lit(lit) if **lit == Value::Boolean(true))
+}
+
+/// Merges a filter into the filter it takes its input from if it is the only operator the latter
+/// flows to, as the conjunction of their conditions, e.g., of the filters left adjacent by other
+/// rules.
+pub struct MergeFilters;
+
+impl OptimizerRule for MergeFilters {
+    fn name(&self) -> &'static str {
+        "merge_filters"
+    }
+
+    fn apply(&self, plan: &mut LogicalPlan<BindingsOp>) -> bool {
+        let mut changed = false;
+        loop {
+            let merge = plan.operators_by_id().find_map(|(id, op)| {
+                let BindingsOp::Filter(_) = op else {
+                    return None;
+                };
+                match plan.inputs(id)[..] {
+                    [input]
+                        if matches!(plan.operator(input), Some(BindingsOp::Filter(_)))
+                            && plan.successors(input) == [id] =>
+                    {
+                        Some((id, input))
+                    }
+                    _ => None,
+                }
+            });
+            let Some((outer, inner)) = merge else {
+                break;
+            };
+            let Some(BindingsOp::Filter(Filter { expr: outer_expr })) =
+                plan.operator(outer).cloned()
+            else {
+                break;
+            };
+            if let Some(BindingsOp::Filter(Filter { expr })) = plan.operator_as_mut(inner) {
+                let inner_expr = std::mem::replace(expr, ValueExpr::Lit(Box::default()));
+                *expr = ValueExpr::BinaryExpr(
+                    BinaryOp::And,
+                    Box::new(inner_expr),
+                    Box::new(outer_expr),
+                );
+            }
+            bypass(plan, outer);
+            changed = true;
+        }
+        changed
+    }
+}
@@ -0,0 +1,186 @@
+//! Rules reordering joins.
+
+use super::{conjunction, conjuncts, OptimizerRule, References};
+use partiql_logical::{BindingsOp, Join, JoinKind, LogicalPlan, OpId, Statistics, ValueExpr};
+
+/// Reorders the operands of inner and cross joins, e.g., of the comma joins of
+/// `FROM t1, t2, t3`, by the increasing cardinality [`Statistics`] estimate of their data sources,
+/// so the joins nest their larger operands in their smaller ones. The operands whose cardinality
+/// is unknown are kept last.
+///
+/// An operand referring to the variables of another one (e.g., `x.items` in
+/// `FROM t AS x, x.items AS i`) is kept after it, and the conditions of the joins are moved to the
+/// first join binding all the variables they refer to.
+pub struct ReorderJoins {
+    statistics: Box<dyn Statistics>,
+}
+
+impl ReorderJoins {
+    pub fn new(statistics: Box<dyn Statistics>) -> Self {
+        ReorderJoins { statistics }
+    }
+}
+
+impl OptimizerRule for ReorderJoins {
+    fn name(&self) -> &'static str {
+        "reorder_joins"
+    }
+
+    fn apply(&self, plan: &mut LogicalPlan<BindingsOp>) -> bool {
+        // the joins the operators flowing to a join mirror the inline operands of are reordered
+        // with it
+        let joins: Vec<_> = plan
+            .operators_by_id()
+            .filter(|&(id, op)| {
+                is_inner(op)
+                    && plan
+                        .successors(id)
+                        .into_iter()
+                        .all(|succ| !matches!(plan.operator(succ), Some(BindingsOp::Join(_))))
+            })
+            .map(|(id, _)| id)
+            .collect();
+        let mut changed = false;
+        for id in joins {
+            changed |= self.reorder(plan, id);
+        }
+        changed
+    }
+}
+
+impl ReorderJoins {
+    /// Reorders the operands of the join `id` and of the inner joins it nests, returning whether
+    /// their order changed.
+    fn reorder(&self, plan: &mut LogicalPlan<BindingsOp>, id: OpId) -> bool {
+        let Some(join) = plan.operator(id) else {
+            return false;
+        };
+        let mut operands = vec![];
+        let mut conditions = vec![];
+        flatten(join, &mut operands, &mut conditions);
+        let order = self.order(&operands);
+        if order.iter().copied().eq(0..operands.len()) {
+            return false;
+        }
+        // the filters of the operands of the join are moved to the joins of the reordered operands
+        for input in plan.inputs(id) {
+            if let Some(BindingsOp::Filter(filter)) = plan.operator(input) {
+                conditions.extend(conjuncts(&filter.expr));
+            }
+        }
+
+        let bound: Vec<_> = operands.iter().map(BindingsOp::bound_variables).collect();
+        let all_bound = bound.concat();
+        let mut order = order.into_iter();
+        let first = order.next().expect("operands");
+        let mut reordered = operands[first].clone();
+        let mut bound_so_far = bound[first].clone();
+        for (i, operand) in order.enumerate() {
+            bound_so_far.extend(bound[operand].iter().cloned());
+            let last = i + 2 == operands.len();
+            let (on, rest): (Vec<_>, Vec<_>) = conditions.into_iter().partition(|cond| {
+                let refs = References::of(cond);
+                last || (!refs.subquery
+                    && all_bound
+                        .iter()
+                        .all(|name| !refs.refers_to(name) || bound_so_far.contains(name)))
+            });
+            conditions = rest;
+            let on = conjunction(on);
+            reordered = BindingsOp::Join(Join {
+                kind: if on.is_some() {
+                    JoinKind::Inner
+                } else {
+                    JoinKind::Cross
+                },
+                left: Box::new(reordered),
+                right: Box::new(operands[operand].clone()),
+                on,
+            });
+        }
+
+        remove_inputs(plan, id);
+        if let BindingsOp::Join(join) = &reordered {
+            add_inputs(plan, id, join);
+        }
+        if let Some(join) = plan.operator_as_mut(id) {
+            *join = reordered;
+        }
+        true
+    }
+
+    /// The order of `operands` by their cardinality, keeping the operands referring to the
+    /// variables of others after them.
+    fn order(&self, operands: &[BindingsOp]) -> Vec<usize> {
+        let bound: Vec<_> = operands.iter().map(BindingsOp::bound_variables).collect();
+        let refs: Vec<_> = operands.iter().map(References::of_op).collect();
+        // whether the operands `i` and `j` must be kept in their order
+        let dependent = |i: usize, j: usize| {
+            refs[i].subquery
+                || refs[j].subquery
+                || bound[i].iter().any(|name| refs[j].refers_to(name))
+                || bound[j].iter().any(|name| refs[i].refers_to(name))
+        };
+        let cardinality = |i: usize| match &operands[i] {
+            BindingsOp::Scan(scan) => self.statistics.cardinality(&scan.expr),
+            _ => None,
+        };
+
+        let mut order: Vec<usize> = vec![];
+        let mut remaining: Vec<usize> = (0..operands.len()).collect();
+        while !remaining.is_empty() {
+            let (pos, _) = remaining
+                .iter()
+                .enumerate()
+                .filter(|&(_, &j)| remaining.iter().all(|&i| i >= j || !dependent(i, j)))
+                .min_by_key(|&(_, &j)| (cardinality(j).unwrap_or(u64::MAX), j))
+                .expect("an operand depending on no remaining one");
+            order.push(remaining.remove(pos));
+        }
+        order
+    }
+}
+
+fn is_inner(op: &BindingsOp) -> bool {
+    matches!(
+        op,
+        BindingsOp::Join(Join {
+            kind: JoinKind::Inner | JoinKind::Cross,
+            ..
+        })
+    )
+}
+
+/// Collects the operands of the inner join `op` and of the inner joins it nests, and the
+/// conditions of these joins.
+fn flatten(op: &BindingsOp, operands: &mut Vec<BindingsOp>, conditions: &mut Vec<ValueExpr>) {
+    match op {
+        BindingsOp::Join(join) if is_inner(op) => {
+            flatten(&join.left, operands, conditions);
+            flatten(&join.right, operands, conditions);
+            conditions.extend(join.on.iter().flat_map(conjuncts));
+        }
+        _ => operands.push(op.clone()),
+    }
+}
+
+/// Removes the operators flowing only to the operator `id`, and recursively their inputs.
+fn remove_inputs(plan: &mut LogicalPlan<BindingsOp>, id: OpId) {
+    for input in plan.inputs(id) {
+        if plan.successors(input) == [id] {
+            remove_inputs(plan, input);
+            plan.remove_operator(input);
+        }
+    }
+}
+
+/// Adds operators flowing to the operator `id` as the operands `join` keeps inline.
+fn add_inputs(plan: &mut LogicalPlan<BindingsOp>, id: OpId, join: &Join) {
+    for (branch_num, operand) in [(0, &join.left), (1, &join.right)] {
+        let input = plan.add_operator(operand.as_ref().clone());
+        plan.add_flow_with_branch_num(input, id, branch_num);
+        if let BindingsOp::Join(join) = operand.as_ref() {
+            add_inputs(plan, input, join);
+        }
+    }
+}
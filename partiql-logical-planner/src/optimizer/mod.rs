@@ -0,0 +1,246 @@
+//! Rule-based optimization of logical plans.
+//!
+//! An [`Optimizer`] applies its [`OptimizerRule`]s, in order, to a plan and to the plans of its
+//! subqueries, in passes repeated until none of the rules changes the plan (i.e., to a fixpoint)
+//! or a maximum number of passes. The rules that changed the plan are recorded in the
+//! [`OptimizerTrace`] it returns.
+//!
+//! ```
+//! use partiql_catalog::PartiqlCatalog;
+//! use partiql_logical_planner::optimizer::Optimizer;
+//! use partiql_logical_planner::LogicalPlanner;
+//!
+//! let arena = partiql_ast::arena::Arena::new();
+//! let parsed = partiql_parser::Parser::default()
+//!     .parse("SELECT VALUE t FROM t WHERE TRUE", &arena)
+//!     .unwrap();
+//! let catalog = PartiqlCatalog::default();
+//! let mut plan = LogicalPlanner::new(&catalog).lower(&parsed).unwrap();
+//!
+//! let trace = Optimizer::default().optimize(&mut plan);
+//! assert_eq!(trace.fired, vec!["remove_true_filters"]);
+//! ```
+
+use partiql_logical::visit::{PlanRewriter, PlanVisitor, Traverse};
+use partiql_logical::{BinaryOp, BindingsOp, LogicalPlan, OpId, ValueExpr};
+use partiql_value::BindingsName;
+use unicase::UniCase;
+
+mod cse;
+mod filters;
+mod joins;
+mod pruning;
+mod pushdown;
+
+pub use cse::EliminateCommonSubexpressions;
+pub use filters::{MergeFilters, RemoveTrueFilters};
+pub use joins::ReorderJoins;
+pub use partiql_logical::Statistics;
+pub use pruning::PruneProjections;
+pub use pushdown::PushDownFilters;
+
+/// The default maximum number of passes of an [`Optimizer`] over a plan.
+const DEFAULT_MAX_PASSES: usize = 16;
+
+/// A rewrite of logical plans that preserves their results.
+pub trait OptimizerRule {
+    /// The name of the rule, as recorded in [`OptimizerTrace`]s.
+    fn name(&self) -> &'static str;
+
+    /// Rewrites `plan`, returning whether it changed it. The plans of subqueries are rewritten
+    /// separately.
+    fn apply(&self, plan: &mut LogicalPlan<BindingsOp>) -> bool;
+}
+
+/// The record of the optimization of a plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptimizerTrace {
+    /// The names of the rules that changed the plan or the plans of its subqueries, in the order
+    /// they did.
+    pub fired: Vec<&'static str>,
+    /// The largest number of passes over the plan or any of the plans of its subqueries.
+    pub passes: usize,
+    /// Whether the optimization of every plan reached a fixpoint within the maximum number of
+    /// passes.
+    pub fixpoint: bool,
+}
+
+/// Applies [`OptimizerRule`]s to logical plans; see the [module documentation](self).
+pub struct Optimizer {
+    rules: Vec<Box<dyn OptimizerRule>>,
+    max_passes: usize,
+}
+
+impl Default for Optimizer {
+    /// Creates an optimizer with the rules of this module, but [`ReorderJoins`], which needs
+    /// [`Statistics`].
+    fn default() -> Self {
+        Optimizer::new(vec![
+            Box::new(RemoveTrueFilters),
+            Box::new(PushDownFilters),
+            Box::new(MergeFilters),
+            Box::new(PruneProjections),
+            Box::new(EliminateCommonSubexpressions),
+        ])
+    }
+}
+
+impl Optimizer {
+    /// Creates an optimizer applying `rules`, in order.
+    pub fn new(rules: Vec<Box<dyn OptimizerRule>>) -> Self {
+        Optimizer {
+            rules,
+            max_passes: DEFAULT_MAX_PASSES,
+        }
+    }
+
+    /// Sets the maximum number of passes over a plan.
+    pub fn with_max_passes(mut self, max_passes: usize) -> Self {
+        self.max_passes = max_passes;
+        self
+    }
+
+    /// Optimizes `plan` and the plans of its subqueries, the latter first.
+    pub fn optimize(&self, plan: &mut LogicalPlan<BindingsOp>) -> OptimizerTrace {
+        let mut optimization = Optimization {
+            optimizer: self,
+            trace: OptimizerTrace {
+                fixpoint: true,
+                ..Default::default()
+            },
+        };
+        plan.rewrite(&mut optimization);
+        optimization.trace
+    }
+
+    /// Applies the rules to `plan` until none changes it or the maximum number of passes.
+    fn optimize_plan(&self, plan: &mut LogicalPlan<BindingsOp>, trace: &mut OptimizerTrace) {
+        let mut passes = 0;
+        let mut changed = true;
+        while changed && passes < self.max_passes {
+            passes += 1;
+            changed = false;
+            for rule in &self.rules {
+                if rule.apply(plan) {
+                    trace.fired.push(rule.name());
+                    changed = true;
+                }
+            }
+        }
+        trace.passes = trace.passes.max(passes);
+        trace.fixpoint &= !changed;
+    }
+}
+
+/// Optimizes each plan as it is exited, i.e., after the plans of its subqueries.
+struct Optimization<'a> {
+    optimizer: &'a Optimizer,
+    trace: OptimizerTrace,
+}
+
+impl PlanRewriter for Optimization<'_> {
+    fn exit_plan(&mut self, plan: &mut LogicalPlan<BindingsOp>) -> Traverse {
+        self.optimizer.optimize_plan(plan, &mut self.trace);
+        Traverse::Continue
+    }
+}
+
+/// Whether a reference to `r` refers to a variable bound as `name`.
+pub(crate) fn binds(name: &str, r: &BindingsName) -> bool {
+    match r {
+        BindingsName::CaseSensitive(r) => name == r,
+        BindingsName::CaseInsensitive(r) => UniCase::new(name) == UniCase::new(r.as_str()),
+    }
+}
+
+/// Inserts `op` between the operator `id`, which has a single input, and its input, returning its
+/// id.
+fn insert_below(plan: &mut LogicalPlan<BindingsOp>, id: OpId, op: BindingsOp) -> Option<OpId> {
+    let [input] = plan.inputs(id)[..] else {
+        return None;
+    };
+    let branch_num = plan
+        .flows()
+        .into_iter()
+        .find_map(|(src, dst, branch_num)| ((src, dst) == (input, id)).then_some(branch_num));
+    let inserted = plan.add_operator(op);
+    plan.remove_flows(input, id);
+    plan.add_flow(input, inserted);
+    plan.add_flow_with_branch_num(inserted, id, branch_num.unwrap_or_default());
+    Some(inserted)
+}
+
+/// Removes the operator `id`, which has a single input, from `plan`, connecting its input to its
+/// outputs on the same branches.
+fn bypass(plan: &mut LogicalPlan<BindingsOp>, id: OpId) {
+    let [input] = plan.inputs(id)[..] else {
+        return;
+    };
+    let outputs: Vec<_> = plan
+        .flows()
+        .into_iter()
+        .filter(|&(src, _, _)| src == id)
+        .collect();
+    plan.remove_operator(id);
+    for (_, dst, branch_num) in outputs {
+        plan.add_flow_with_branch_num(input, dst, branch_num);
+    }
+}
+
+/// The conditions of the conjunction `expr`.
+fn conjuncts(expr: &ValueExpr) -> Vec<ValueExpr> {
+    expr.conjuncts().into_iter().cloned().collect()
+}
+
+/// The conjunction of `conditions`, if any.
+fn conjunction(conditions: Vec<ValueExpr>) -> Option<ValueExpr> {
+    conditions
+        .into_iter()
+        .reduce(|lhs, rhs| ValueExpr::BinaryExpr(BinaryOp::And, Box::new(lhs), Box::new(rhs)))
+}
+
+/// The variables an expression refers to.
+#[derive(Default)]
+struct References<'a> {
+    locals: Vec<&'a BindingsName>,
+    globals: Vec<&'a BindingsName>,
+    /// Whether the expression has a subquery, whose references are not tracked.
+    subquery: bool,
+}
+
+impl<'a> References<'a> {
+    fn of(expr: &'a ValueExpr) -> Self {
+        let mut refs = References::default();
+        expr.visit(&mut refs);
+        refs
+    }
+
+    fn of_op(op: &'a BindingsOp) -> Self {
+        let mut refs = References::default();
+        op.visit(&mut refs);
+        refs
+    }
+
+    /// Whether the expression refers to a variable bound as `name`.
+    fn refers_to(&self, name: &str) -> bool {
+        self.locals
+            .iter()
+            .chain(&self.globals)
+            .any(|r| binds(name, r))
+    }
+}
+
+impl<'a> PlanVisitor<'a> for References<'a> {
+    fn enter_expr(&mut self, expr: &'a ValueExpr) -> Traverse {
+        match expr {
+            ValueExpr::VarRef(name) => self.locals.push(name),
+            ValueExpr::GlobalVarRef(name) => self.globals.push(name),
+            ValueExpr::SubQueryExpr(_) => {
+                self.subquery = true;
+                return Traverse::Stop;
+            }
+            _ => {}
+        }
+        Traverse::Continue
+    }
+}
@@ -0,0 +1,264 @@
+//! Rules removing the bindings and attributes plans compute but do not use.
+
+use super::{binds, bypass, OptimizerRule};
+use partiql_logical::visit::{PlanVisitor, Traverse};
+use partiql_logical::{
+    BindingsOp, GroupBy, LogicalPlan, OpId, PathComponent, Scan, SubQueryExpr, ValueExpr,
+};
+use partiql_value::BindingsName;
+
+/// Removes the outputs of operators that none of the operators they flow to uses:
+/// - the `AT` variables of scans and unpivots, including those of the scans joins keep inline;
+/// - the variables bound by `LET`s, removing the `LET`s left binding none;
+/// - the aggregates and `GROUP AS` variables of `GROUP BY`s;
+/// - the attributes computed by the projection of a subquery scanned in `FROM`, if the variable
+///   it is scanned as is only used to access some of them, e.g., `b` in
+///   `SELECT x.a FROM (SELECT t.a, t.b FROM t) AS x`.
+///
+/// The outputs of an operator are kept if any operator they flow to uses its bindings as a whole,
+/// e.g., `SELECT *`, `DISTINCT` or the sink of the plan.
+pub struct PruneProjections;
+
+impl OptimizerRule for PruneProjections {
+    fn name(&self) -> &'static str {
+        "prune_projections"
+    }
+
+    fn apply(&self, plan: &mut LogicalPlan<BindingsOp>) -> bool {
+        let ops: Vec<_> = plan.operators_by_id().map(|(id, _)| id).collect();
+        let mut changed = false;
+        for id in ops {
+            changed |= prune(plan, id);
+        }
+        changed
+    }
+}
+
+/// Removes the outputs of the operator `id` no operator uses, returning whether any were.
+fn prune(plan: &mut LogicalPlan<BindingsOp>, id: OpId) -> bool {
+    let Some(mut uses) = Uses::downstream(plan, id) else {
+        return false;
+    };
+    // the bindings of a `LET` may use the preceding ones, and the `ON` condition of a join and
+    // the filters pushed above its operands the bindings of its operands
+    if let Some(op @ (BindingsOp::Let(_) | BindingsOp::Join(_))) = plan.operator(id) {
+        op.visit(&mut uses);
+    }
+    if let Some(BindingsOp::Join(_)) = plan.operator(id) {
+        for (filters, _) in operand_inputs(plan, id) {
+            for filter in filters {
+                if let Some(op) = plan.operator(filter) {
+                    op.visit(&mut uses);
+                }
+            }
+        }
+    }
+
+    match plan.operator_as_mut(id) {
+        Some(BindingsOp::Scan(scan)) => prune_scan(scan, &uses),
+        Some(BindingsOp::Unpivot(unpivot)) => prune_at_key(&mut unpivot.at_key, &uses),
+        Some(BindingsOp::Join(join)) => {
+            let operands = [join.left.as_ref().clone(), join.right.as_ref().clone()];
+            if !(prune_inline(&mut join.left, &uses) | prune_inline(&mut join.right, &uses)) {
+                return false;
+            }
+            // keep the operators flowing to the join as those it keeps inline
+            let pruned = [join.left.as_ref().clone(), join.right.as_ref().clone()];
+            for (_, input) in operand_inputs(plan, id) {
+                if let Some(op) = plan.operator_as_mut(input) {
+                    if let Some(i) = operands.iter().position(|operand| operand == op) {
+                        *op = pruned[i].clone();
+                    }
+                }
+            }
+            true
+        }
+        Some(BindingsOp::Let(lets)) => {
+            let bindings = lets.bindings.len();
+            lets.bindings.retain(|(name, _)| uses.uses(name));
+            if lets.bindings.is_empty() {
+                bypass(plan, id);
+                true
+            } else {
+                bindings != lets.bindings.len()
+            }
+        }
+        Some(BindingsOp::GroupBy(GroupBy {
+            aggregate_exprs,
+            group_as_alias,
+            ..
+        })) => {
+            let aggregates = aggregate_exprs.len();
+            aggregate_exprs.retain(|agg| uses.uses(&agg.name));
+            let pruned = aggregates != aggregate_exprs.len();
+            prune_at_key(group_as_alias, &uses) | pruned
+        }
+        _ => false,
+    }
+}
+
+/// The operators flowing to the join `id` on each of its branches, with the filters between them
+/// and the join, e.g., those conditions of the join are pushed into.
+fn operand_inputs(plan: &LogicalPlan<BindingsOp>, id: OpId) -> Vec<(Vec<OpId>, OpId)> {
+    plan.inputs(id)
+        .into_iter()
+        .map(|mut input| {
+            let mut filters = vec![];
+            while let (Some(BindingsOp::Filter(_)), [below]) =
+                (plan.operator(input), &plan.inputs(input)[..])
+            {
+                filters.push(input);
+                input = *below;
+            }
+            (filters, input)
+        })
+        .collect()
+}
+
+/// Removes the unused outputs of an operator a join keeps inline.
+fn prune_inline(op: &mut BindingsOp, uses: &Uses) -> bool {
+    match op {
+        BindingsOp::Scan(scan) => prune_scan(scan, uses),
+        BindingsOp::Unpivot(unpivot) => prune_at_key(&mut unpivot.at_key, uses),
+        BindingsOp::Join(join) => {
+            prune_inline(&mut join.left, uses) | prune_inline(&mut join.right, uses)
+        }
+        _ => false,
+    }
+}
+
+fn prune_scan(scan: &mut Scan, uses: &Uses) -> bool {
+    prune_at_key(&mut scan.at_key, uses) | prune_attributes(scan, uses)
+}
+
+/// Removes the variable `name` if unused.
+fn prune_at_key(name: &mut Option<String>, uses: &Uses) -> bool {
+    if name.as_deref().is_some_and(|name| !uses.uses(name)) {
+        *name = None;
+        true
+    } else {
+        false
+    }
+}
+
+/// Removes the attributes the projection of the subquery `scan` scans computes but are unused.
+fn prune_attributes(scan: &mut Scan, uses: &Uses) -> bool {
+    let ValueExpr::SubQueryExpr(SubQueryExpr { plan, .. }) = &mut scan.expr else {
+        return false;
+    };
+    let Some(attributes) = uses.attributes(&scan.as_key) else {
+        return false;
+    };
+    // the projection must be the last operator of the subquery, as e.g. `DISTINCT` uses all
+    // attributes
+    let Some(project) = plan.operators_by_id().find_map(|(id, op)| match op {
+        BindingsOp::Sink => match plan.inputs(id)[..] {
+            [project] if plan.successors(project) == [id] => Some(project),
+            _ => None,
+        },
+        _ => None,
+    }) else {
+        return false;
+    };
+    let Some(BindingsOp::Project(project)) = plan.operator_as_mut(project) else {
+        return false;
+    };
+    let exprs = project.exprs.len();
+    project
+        .exprs
+        .retain(|(name, _)| attributes.iter().any(|attr| binds(name, attr)));
+    exprs != project.exprs.len()
+}
+
+/// The variables expressions use, and the attributes of them they access.
+#[derive(Default)]
+pub(super) struct Uses {
+    /// The variables used, with the attribute accessed if only one is.
+    vars: Vec<(BindingsName, Option<BindingsName>)>,
+    /// Whether all variables are used, e.g., by `SELECT *`.
+    all: bool,
+    /// Whether the next expression entered is the variable of a recorded attribute access.
+    accessed: bool,
+}
+
+impl Uses {
+    /// The variables the operators the operator `id` flows to use, unless one of them uses its
+    /// bindings as a whole.
+    pub(super) fn downstream(plan: &LogicalPlan<BindingsOp>, id: OpId) -> Option<Self> {
+        let mut uses = Uses::default();
+        let mut pending = plan.successors(id);
+        let mut seen = vec![];
+        while let Some(next) = pending.pop() {
+            if seen.contains(&next) {
+                continue;
+            }
+            seen.push(next);
+            match plan.operator(next)? {
+                op @ (BindingsOp::Filter(_)
+                | BindingsOp::Let(_)
+                | BindingsOp::OrderBy(_)
+                | BindingsOp::LimitOffset(_)
+                | BindingsOp::Having(_)
+                | BindingsOp::Window(_)) => {
+                    op.visit(&mut uses);
+                    pending.extend(plan.successors(next));
+                }
+                // operators binding only the variables they compute
+                op @ (BindingsOp::Project(_)
+                | BindingsOp::ProjectValue(_)
+                | BindingsOp::Pivot(_)
+                | BindingsOp::GroupBy(GroupBy {
+                    group_as_alias: None,
+                    ..
+                })) => {
+                    op.visit(&mut uses);
+                }
+                _ => return None,
+            }
+        }
+        (!uses.all).then_some(uses)
+    }
+
+    /// Whether the variable `name` is used.
+    fn uses(&self, name: &str) -> bool {
+        self.vars.iter().any(|(var, _)| binds(name, var))
+    }
+
+    /// The attributes of the variable `name` accessed, unless it is used otherwise.
+    fn attributes(&self, name: &str) -> Option<Vec<&BindingsName>> {
+        self.vars
+            .iter()
+            .filter(|(var, _)| binds(name, var))
+            .map(|(_, attr)| attr.as_ref())
+            .collect()
+    }
+}
+
+impl<'a> PlanVisitor<'a> for Uses {
+    fn enter_operator(&mut self, _id: OpId, op: &'a BindingsOp) -> Traverse {
+        self.all |= matches!(op, BindingsOp::ProjectAll);
+        Traverse::Continue
+    }
+
+    fn enter_expr(&mut self, expr: &'a ValueExpr) -> Traverse {
+        if std::mem::take(&mut self.accessed) {
+            return Traverse::Continue;
+        }
+        match expr {
+            ValueExpr::Path(root, components) => {
+                if let (ValueExpr::VarRef(var), Some(PathComponent::Key(attr))) =
+                    (root.as_ref(), components.first())
+                {
+                    self.vars.push((var.clone(), Some(attr.clone())));
+                    // the root is the next expression entered
+                    self.accessed = true;
+                }
+            }
+            ValueExpr::VarRef(var) | ValueExpr::GlobalVarRef(var) => {
+                self.vars.push((var.clone(), None))
+            }
+            _ => {}
+        }
+        Traverse::Continue
+    }
+}
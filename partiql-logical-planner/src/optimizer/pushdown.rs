@@ -0,0 +1,238 @@
+//! Rules pushing filters down plans, closer to the data they filter.
+
+use super::{binds, bypass, conjunction, conjuncts, insert_below, OptimizerRule, References};
+use partiql_logical::visit::{PlanRewriter, Traverse};
+use partiql_logical::{BindingsOp, Filter, Join, JoinKind, LogicalPlan, OpId, ValueExpr};
+
+/// Pushes the conditions of `WHERE` filters below the operators they do not depend on, so that
+/// these operators, and the operators a filter is pushed further below, process fewer bindings:
+/// - below `DISTINCT`s and `ORDER BY`s, which filters commute with;
+/// - below `LET`s, the conditions not referring to the variables they bind;
+/// - below projections, the conditions referring only to the variables they bind, which are
+///   substituted by their expressions;
+/// - into the `ON` conditions of inner and cross joins, which are evaluated on each pair of
+///   bindings of their operands, or, the conditions referring to the variables of only one of
+///   their operands, into filters above that operand, which are evaluated on each of its bindings.
+///
+/// A condition is only pushed below an operator the filter is the only consumer of. The
+/// conditions of the `ON` of an inner join referring to one of its operands are pushed above it as
+/// well, e.g., `x.a = 1` of `FROM t AS x JOIN s AS y ON x.a = 1 AND x.b = y.b`.
+///
+/// As joins keep their operands inline, only the operands of the joins that do not flow to other
+/// joins, i.e., whose operands are the operators flowing to them, are filtered.
+pub struct PushDownFilters;
+
+impl OptimizerRule for PushDownFilters {
+    fn name(&self) -> &'static str {
+        "push_down_filters"
+    }
+
+    fn apply(&self, plan: &mut LogicalPlan<BindingsOp>) -> bool {
+        let filters: Vec<_> = plan
+            .operators_by_id()
+            .filter(|(_, op)| matches!(op, BindingsOp::Filter(_)))
+            .map(|(id, _)| id)
+            .collect();
+        let mut changed = false;
+        for filter in filters {
+            changed |= push_down(plan, filter);
+        }
+        let joins: Vec<_> = plan
+            .operators_by_id()
+            .filter(|(_, op)| {
+                matches!(
+                    op,
+                    BindingsOp::Join(Join {
+                        kind: JoinKind::Inner,
+                        on: Some(_),
+                        ..
+                    })
+                )
+            })
+            .map(|(id, _)| id)
+            .collect();
+        for join in joins {
+            changed |= push_down_on(plan, join);
+        }
+        changed
+    }
+}
+
+/// Pushes the conditions of `filter` that do not depend on its input below it, returning whether
+/// any were.
+fn push_down(plan: &mut LogicalPlan<BindingsOp>, filter: OpId) -> bool {
+    let [below] = plan.inputs(filter)[..] else {
+        return false;
+    };
+    if plan.successors(below) != [filter] {
+        return false;
+    }
+    let Some(BindingsOp::Filter(Filter { expr })) = plan.operator(filter) else {
+        return false;
+    };
+    // the filters of the operands of joins are evaluated by the joins
+    if flows_to_join(plan, filter) {
+        return false;
+    }
+    let (pushed, kept): (Vec<_>, Vec<_>) = match plan.operator(below) {
+        Some(BindingsOp::Distinct | BindingsOp::OrderBy(_)) => (conjuncts(expr), vec![]),
+        Some(BindingsOp::Let(lets)) => conjuncts(expr).into_iter().partition(|conjunct| {
+            let refs = References::of(conjunct);
+            !refs.subquery && !lets.bindings.iter().any(|(name, _)| refs.refers_to(name))
+        }),
+        Some(BindingsOp::Project(project)) => {
+            let (mut pushed, kept): (Vec<_>, Vec<_>) =
+                conjuncts(expr).into_iter().partition(|conjunct| {
+                    let refs = References::of(conjunct);
+                    !refs.subquery
+                        && refs.globals.is_empty()
+                        && refs
+                            .locals
+                            .iter()
+                            .all(|r| project.exprs.iter().any(|(name, _)| binds(name, r)))
+                });
+            let mut substitute = Substitute(&project.exprs);
+            for conjunct in &mut pushed {
+                conjunct.rewrite(&mut substitute);
+            }
+            (pushed, kept)
+        }
+        Some(BindingsOp::Join(Join {
+            kind: JoinKind::Inner | JoinKind::Cross,
+            ..
+        })) => (conjuncts(expr), vec![]),
+        _ => return false,
+    };
+    if pushed.is_empty() {
+        return false;
+    }
+
+    match plan.operator(below) {
+        Some(BindingsOp::Join(_)) => {
+            let pushed = filter_operands(plan, below, pushed);
+            if let Some(BindingsOp::Join(join)) = plan.operator_as_mut(below) {
+                if !pushed.is_empty() {
+                    join.kind = JoinKind::Inner;
+                }
+                join.on = conjunction(join.on.take().into_iter().chain(pushed).collect());
+            }
+        }
+        _ => {
+            let filter = BindingsOp::Filter(Filter {
+                expr: conjunction(pushed).expect("pushed conditions"),
+            });
+            if insert_below(plan, below, filter).is_none() {
+                return false;
+            }
+        }
+    }
+    match conjunction(kept) {
+        Some(kept) => {
+            if let Some(BindingsOp::Filter(Filter { expr })) = plan.operator_as_mut(filter) {
+                *expr = kept;
+            }
+        }
+        None => bypass(plan, filter),
+    }
+    true
+}
+
+/// Pushes the conditions of the `ON` of the inner join `id` referring to one of its operands into
+/// filters above that operand, returning whether any were.
+fn push_down_on(plan: &mut LogicalPlan<BindingsOp>, id: OpId) -> bool {
+    let Some(BindingsOp::Join(Join { on: Some(on), .. })) = plan.operator(id) else {
+        return false;
+    };
+    let conditions = conjuncts(on);
+    let count = conditions.len();
+    let kept = filter_operands(plan, id, conditions);
+    if kept.len() == count {
+        return false;
+    }
+    if let Some(BindingsOp::Join(join)) = plan.operator_as_mut(id) {
+        join.on = conjunction(kept);
+    }
+    true
+}
+
+/// Pushes the `conditions` of the join `id` referring to the variables of only one of its operands
+/// into a filter above that operand, i.e., between the operator flowing to the join on its branch
+/// and the join, returning the other conditions.
+fn filter_operands(
+    plan: &mut LogicalPlan<BindingsOp>,
+    id: OpId,
+    conditions: Vec<ValueExpr>,
+) -> Vec<ValueExpr> {
+    let Some(BindingsOp::Join(join)) = plan.operator(id) else {
+        return conditions;
+    };
+    let inputs: Vec<_> = plan
+        .flows()
+        .into_iter()
+        .filter(|&(_, dst, _)| dst == id)
+        .collect();
+    if inputs.len() != 2 || flows_to_join(plan, id) {
+        return conditions;
+    }
+    let bound = [join.left.bound_variables(), join.right.bound_variables()];
+    let mut operand_conditions: [Vec<ValueExpr>; 2] = Default::default();
+    let mut kept = vec![];
+    for condition in conditions {
+        let refs = References::of(&condition);
+        let refers = |bound: &[String]| bound.iter().any(|name| refs.refers_to(name));
+        match (refs.subquery, refers(&bound[0]), refers(&bound[1])) {
+            (false, true, false) => operand_conditions[0].push(condition),
+            (false, false, true) => operand_conditions[1].push(condition),
+            _ => kept.push(condition),
+        }
+    }
+
+    for (input, _, branch_num) in inputs {
+        let Some(conditions) = operand_conditions.get_mut(branch_num as usize) else {
+            continue;
+        };
+        if conditions.is_empty() {
+            continue;
+        }
+        let conditions = std::mem::take(conditions);
+        match plan.operator_as_mut(input) {
+            Some(BindingsOp::Filter(Filter { expr })) => {
+                *expr = conjunction([expr.clone()].into_iter().chain(conditions).collect())
+                    .expect("filtered conditions");
+            }
+            _ => {
+                let filter = plan.add_operator(BindingsOp::Filter(Filter {
+                    expr: conjunction(conditions).expect("pushed conditions"),
+                }));
+                plan.remove_flows(input, id);
+                plan.add_flow(input, filter);
+                plan.add_flow_with_branch_num(filter, id, branch_num);
+            }
+        }
+    }
+    // the conditions of operands not flowing to the join, if any
+    kept.extend(operand_conditions.into_iter().flatten());
+    kept
+}
+
+/// Whether the operator `id` flows to a join, e.g., as one of its operands.
+fn flows_to_join(plan: &LogicalPlan<BindingsOp>, id: OpId) -> bool {
+    plan.successors(id)
+        .into_iter()
+        .any(|succ| matches!(plan.operator(succ), Some(BindingsOp::Join(_))))
+}
+
+/// Substitutes the references to the variables of a projection by their expressions.
+struct Substitute<'a>(&'a [(String, ValueExpr)]);
+
+impl PlanRewriter for Substitute<'_> {
+    // substituting on exit does not traverse the substitutes
+    fn exit_expr(&mut self, expr: &mut ValueExpr) -> Traverse {
+        if let ValueExpr::VarRef(r) = expr {
+            if let Some((_, substitute)) = self.0.iter().find(|(name, _)| binds(name, r)) {
+                *expr = substitute.clone();
+            }
+        }
+        Traverse::Continue
+    }
+}
@@ -0,0 +1,668 @@
+//! Typing of logical plans.
+//!
+//! A [`PlanTyper`] infers the shape of the values each operator of a plan outputs, as a
+//! [`PartiqlType`]: the bindings operators output are typed as closed structs of the variables
+//! they bind, e.g., `{c: {id: INT, name: STRING}}` for `FROM customer AS c`, and the values
+//! projections output as the types of their expressions. The types of the global variables, e.g.,
+//! of tables, are those the catalog declares; the types that cannot be inferred are `ANY`.
+//!
+//! Typing detects errors before evaluation, e.g., accessing an attribute a closed struct does not
+//! declare, which a plan evaluated in strict mode would fail on.
+//!
+//! ```
+//! use partiql_catalog::{Catalog, PartiqlCatalog, TypeEnvEntry};
+//! use partiql_logical_planner::typing::PlanTyper;
+//! use partiql_logical_planner::LogicalPlanner;
+//! use partiql_types::{int, BagType, PartiqlType, StructConstraint, StructField, StructType};
+//!
+//! let mut catalog = PartiqlCatalog::default();
+//! let row = StructType::new(vec![
+//!     StructConstraint::Open(false),
+//!     StructConstraint::Fields(StructField::new("id", int!())),
+//! ]);
+//! let ty = PartiqlType::new_bag(BagType::new(Box::new(PartiqlType::new_struct(row))));
+//! catalog.add_type_entry(TypeEnvEntry::new("t", &[], ty)).unwrap();
+//!
+//! let arena = partiql_ast::arena::Arena::new();
+//! let parsed = partiql_parser::Parser::default()
+//!     .parse("SELECT t.id * 2 AS double FROM t", &arena)
+//!     .unwrap();
+//! let plan = LogicalPlanner::new(&catalog).lower(&parsed).unwrap();
+//! let typed = PlanTyper::new(&catalog).type_plan(&plan).unwrap();
+//! assert_eq!(typed.output().unwrap().to_string(), "{double: INT}");
+//! ```
+
+use crate::optimizer::binds;
+use partiql_catalog::{attribute_type, Catalog, UndeclaredAttribute};
+use partiql_logical::{
+    AggFunc, BinaryOp, BindingsOp, GroupBy, Join, LogicalPlan, OpId, PathComponent,
+    SubQueryCoercion, UnaryOp, ValueExpr, WindowFunction,
+};
+use partiql_types::{
+    any, missing, ArrayType, BagType, PartiqlType, StructConstraint, StructField, StructType,
+    TypeKind,
+};
+use partiql_value::{BindingsName, Value};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Contains the errors that occur during the typing of a plan
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypingErrors {
+    pub errors: Vec<TypingError>,
+}
+
+/// Represents a typing Error
+#[derive(Error, Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TypingError {
+    /// Indicates that an attribute is accessed which a closed struct does not declare.
+    #[error(transparent)]
+    UndeclaredAttribute(#[from] UndeclaredAttribute),
+
+    /// Indicates that an operator is applied to an operand of a type it is not defined on, e.g.,
+    /// `'a' + 1`.
+    #[error("Incompatible types: {0}")]
+    IncompatibleTypes(String),
+}
+
+/// A logical plan with the shapes of the values its operators output.
+#[derive(Debug, Clone)]
+pub struct TypedPlan<'p> {
+    plan: &'p LogicalPlan<BindingsOp>,
+    shapes: HashMap<OpId, PartiqlType>,
+}
+
+impl<'p> TypedPlan<'p> {
+    /// The plan typed.
+    pub fn plan(&self) -> &'p LogicalPlan<BindingsOp> {
+        self.plan
+    }
+
+    /// The type of the values the operator `id` outputs.
+    pub fn shape(&self, id: OpId) -> Option<&PartiqlType> {
+        self.shapes.get(&id)
+    }
+
+    /// The type of the values the plan outputs, i.e., that its sink does.
+    pub fn output(&self) -> Option<&PartiqlType> {
+        let (sink, _) = self
+            .plan
+            .operators_by_id()
+            .find(|(_, op)| matches!(op, BindingsOp::Sink))?;
+        self.shape(sink)
+    }
+}
+
+/// Infers the types of the values the operators of logical plans output; see the
+/// [module documentation](self).
+pub struct PlanTyper<'c> {
+    catalog: &'c dyn Catalog,
+}
+
+impl<'c> PlanTyper<'c> {
+    pub fn new(catalog: &'c dyn Catalog) -> Self {
+        PlanTyper { catalog }
+    }
+
+    /// Types the operators of `plan`, and those of the plans of its subqueries, returning the
+    /// errors found if any.
+    pub fn type_plan<'p>(
+        &self,
+        plan: &'p LogicalPlan<BindingsOp>,
+    ) -> Result<TypedPlan<'p>, TypingErrors> {
+        let mut typing = Typing {
+            catalog: self.catalog,
+            outer: vec![],
+            errors: vec![],
+        };
+        let shapes = typing.type_plan(plan);
+        if typing.errors.is_empty() {
+            Ok(TypedPlan { plan, shapes })
+        } else {
+            Err(TypingErrors {
+                errors: typing.errors,
+            })
+        }
+    }
+}
+
+/// The variables an operator binds, with their types.
+type Bindings = Vec<(String, PartiqlType)>;
+
+struct Typing<'c> {
+    catalog: &'c dyn Catalog,
+    /// The variables bound by the operators of the plans enclosing the subquery typed.
+    outer: Bindings,
+    errors: Vec<TypingError>,
+}
+
+impl Typing<'_> {
+    fn type_plan(&mut self, plan: &LogicalPlan<BindingsOp>) -> HashMap<OpId, PartiqlType> {
+        let mut shapes = HashMap::new();
+        for (id, _) in plan.operators_by_id() {
+            self.type_op(plan, id, &mut shapes);
+        }
+        shapes
+    }
+
+    /// Types the operator `id` of `plan`, after its inputs.
+    fn type_op(
+        &mut self,
+        plan: &LogicalPlan<BindingsOp>,
+        id: OpId,
+        shapes: &mut HashMap<OpId, PartiqlType>,
+    ) -> PartiqlType {
+        if let Some(shape) = shapes.get(&id) {
+            return shape.clone();
+        }
+        let inputs: Vec<_> = plan
+            .inputs(id)
+            .into_iter()
+            .map(|input| self.type_op(plan, input, shapes))
+            .collect();
+        let input = inputs
+            .first()
+            .cloned()
+            .unwrap_or_else(|| bindings_type(vec![]));
+        let mut env = fields(&input);
+
+        let shape = match plan.operator(id) {
+            Some(op @ (BindingsOp::Scan(_) | BindingsOp::Unpivot(_) | BindingsOp::Join(_))) => {
+                bindings_type(self.bound_by(op, &[]))
+            }
+            Some(BindingsOp::Let(lets)) => {
+                for (name, expr) in &lets.bindings {
+                    let ty = self.type_expr(expr, &env);
+                    env.push((name.clone(), ty));
+                }
+                bindings_type(env)
+            }
+            Some(BindingsOp::Filter(filter)) => {
+                self.type_expr(&filter.expr, &env);
+                input
+            }
+            Some(BindingsOp::Having(having)) => {
+                self.type_expr(&having.expr, &env);
+                input
+            }
+            Some(BindingsOp::OrderBy(order_by)) => {
+                for spec in &order_by.specs {
+                    self.type_expr(&spec.expr, &env);
+                }
+                input
+            }
+            Some(BindingsOp::LimitOffset(limit_offset)) => {
+                for expr in limit_offset.limit.iter().chain(&limit_offset.offset) {
+                    self.type_expr(expr, &env);
+                }
+                input
+            }
+            Some(BindingsOp::Window(window)) => {
+                for expr in window
+                    .partition_by
+                    .iter()
+                    .chain(window.order_by.iter().map(|spec| &spec.expr))
+                {
+                    self.type_expr(expr, &env);
+                }
+                let ty = match &window.func {
+                    WindowFunction::RowNumber
+                    | WindowFunction::Rank
+                    | WindowFunction::DenseRank => PartiqlType::new(TypeKind::Int),
+                    WindowFunction::Lag(offset) | WindowFunction::Lead(offset) => {
+                        for expr in [&offset.expr, &offset.offset, &offset.default] {
+                            self.type_expr(expr, &env);
+                        }
+                        any!()
+                    }
+                };
+                env.push((window.name.clone(), ty));
+                bindings_type(env)
+            }
+            Some(BindingsOp::GroupBy(group_by)) => bindings_type(self.grouped(group_by, &input)),
+            Some(BindingsOp::Project(project)) => bindings_type(
+                project
+                    .exprs
+                    .iter()
+                    .map(|(name, expr)| (name.clone(), self.type_expr(expr, &env)))
+                    .collect(),
+            ),
+            Some(BindingsOp::ProjectValue(project)) => self.type_expr(&project.expr, &env),
+            Some(BindingsOp::ProjectAll) => project_all(&env),
+            Some(BindingsOp::Pivot(pivot)) => {
+                self.type_expr(&pivot.key, &env);
+                self.type_expr(&pivot.value, &env);
+                PartiqlType::new_struct(StructType::new_any())
+            }
+            Some(BindingsOp::ExprQuery(query)) => self.type_expr(&query.expr, &env),
+            Some(BindingsOp::BagOp(_)) => match &inputs[..] {
+                [left, right] if same_attributes(left, right) => left.clone(),
+                _ => any!(),
+            },
+            Some(BindingsOp::Distinct | BindingsOp::Sink) => input,
+            None => any!(),
+        };
+        shapes.insert(id, shape.clone());
+        shape
+    }
+
+    /// The variables bound by the scan, unpivot or join `op`, the operand of a join being typed
+    /// with the variables of its left operand, which it may refer to.
+    fn bound_by(&mut self, op: &BindingsOp, env: &[(String, PartiqlType)]) -> Bindings {
+        match op {
+            BindingsOp::Scan(scan) => {
+                let ty = self.type_expr(&scan.expr, env);
+                let (element, at) = match ty.kind() {
+                    TypeKind::Bag(bag) => (bag.element_type().clone(), any!()),
+                    TypeKind::Array(array) => (
+                        array.element_type().clone(),
+                        PartiqlType::new(TypeKind::Int),
+                    ),
+                    TypeKind::Any | TypeKind::AnyOf(_) => (any!(), any!()),
+                    // a value other than a collection is scanned as a bag of itself
+                    _ => (ty.clone(), missing!()),
+                };
+                let mut bound = vec![(scan.as_key.clone(), element)];
+                bound.extend(
+                    scan.at_key
+                        .iter()
+                        .map(|at_key| (at_key.clone(), at.clone())),
+                );
+                bound
+            }
+            BindingsOp::Unpivot(unpivot) => {
+                self.type_expr(&unpivot.expr, env);
+                let mut bound = vec![(unpivot.as_key.clone(), any!())];
+                bound.extend(
+                    unpivot
+                        .at_key
+                        .iter()
+                        .map(|at_key| (at_key.clone(), PartiqlType::new(TypeKind::String))),
+                );
+                bound
+            }
+            BindingsOp::Join(Join {
+                left, right, on, ..
+            }) => {
+                let mut bound = self.bound_by(left, env);
+                let mut lateral = env.to_vec();
+                lateral.extend(bound.iter().cloned());
+                bound.extend(self.bound_by(right, &lateral));
+                if let Some(on) = on {
+                    lateral.extend(bound.iter().cloned());
+                    self.type_expr(on, &lateral);
+                }
+                bound
+            }
+            _ => vec![],
+        }
+    }
+
+    /// The variables bound by `group_by` of the bindings typed `input`.
+    fn grouped(&mut self, group_by: &GroupBy, input: &PartiqlType) -> Bindings {
+        let env = fields(input);
+        let mut keys: Vec<_> = group_by.exprs.iter().collect();
+        keys.sort_by_key(|(name, _)| *name);
+        let mut bound: Bindings = keys
+            .into_iter()
+            .map(|(name, expr)| (name.clone(), self.type_expr(expr, &env)))
+            .collect();
+        for agg in &group_by.aggregate_exprs {
+            let ty = self.type_expr(&agg.expr, &env);
+            let ty = match agg.func {
+                AggFunc::AggCount => PartiqlType::new(TypeKind::Int),
+                AggFunc::AggMin | AggFunc::AggMax | AggFunc::AggSum => ty,
+                AggFunc::AggAvg => any!(),
+            };
+            bound.push((agg.name.clone(), ty));
+        }
+        if let Some(alias) = &group_by.group_as_alias {
+            let group = PartiqlType::new_bag(BagType::new(Box::new(input.clone())));
+            bound.push((alias.clone(), group));
+        }
+        bound
+    }
+
+    /// The type of `expr` evaluated with the variables `env`.
+    fn type_expr(&mut self, expr: &ValueExpr, env: &[(String, PartiqlType)]) -> PartiqlType {
+        match expr {
+            ValueExpr::Lit(lit) => type_of_value(lit),
+            ValueExpr::VarRef(name) => self.lookup(name, env).unwrap_or_else(|| any!()),
+            ValueExpr::GlobalVarRef(name) => {
+                let global = match name {
+                    BindingsName::CaseSensitive(name) | BindingsName::CaseInsensitive(name) => {
+                        self.catalog.resolve_type(name)
+                    }
+                };
+                match global {
+                    Some(entry) => entry.ty().clone(),
+                    None => self.lookup(name, env).unwrap_or_else(|| any!()),
+                }
+            }
+            ValueExpr::Path(root, components) => {
+                let mut ty = self.type_expr(root, env);
+                for component in components {
+                    ty = match component {
+                        PathComponent::Key(attr) => self.attribute(&ty, attr),
+                        PathComponent::Index(_) => match ty.kind() {
+                            TypeKind::Array(array) => array.element_type().clone(),
+                            _ => any!(),
+                        },
+                        PathComponent::Expr(expr) => {
+                            self.type_expr(expr, env);
+                            any!()
+                        }
+                        _ => any!(),
+                    };
+                }
+                ty
+            }
+            ValueExpr::UnExpr(op, operand) => {
+                let ty = self.type_expr(operand, env);
+                match op {
+                    UnaryOp::Not => PartiqlType::new(TypeKind::Bool),
+                    UnaryOp::Pos | UnaryOp::Neg => {
+                        self.check_numeric(op, &ty);
+                        ty
+                    }
+                }
+            }
+            ValueExpr::BinaryExpr(op, lhs, rhs) => {
+                let lhs = self.type_expr(lhs, env);
+                let rhs = self.type_expr(rhs, env);
+                match op {
+                    // operands other than strings may be absent or are concatenated as text
+                    // whose type is not inferred
+                    BinaryOp::Concat if is_string(&lhs) && is_string(&rhs) => {
+                        PartiqlType::new(TypeKind::String)
+                    }
+                    BinaryOp::Concat => any!(),
+                    BinaryOp::Add
+                    | BinaryOp::Sub
+                    | BinaryOp::Mul
+                    | BinaryOp::Div
+                    | BinaryOp::Mod
+                    | BinaryOp::Exp => {
+                        self.check_numeric(op, &lhs);
+                        self.check_numeric(op, &rhs);
+                        match (lhs.kind(), rhs.kind()) {
+                            (TypeKind::Int, TypeKind::Int) => PartiqlType::new(TypeKind::Int),
+                            (TypeKind::Float64, TypeKind::Float64) => {
+                                PartiqlType::new(TypeKind::Float64)
+                            }
+                            (TypeKind::Decimal, TypeKind::Decimal) => {
+                                PartiqlType::new(TypeKind::Decimal)
+                            }
+                            _ => any!(),
+                        }
+                    }
+                    _ => PartiqlType::new(TypeKind::Bool),
+                }
+            }
+            ValueExpr::TupleExpr(tuple) => {
+                let mut attributes = vec![];
+                let mut open = false;
+                for (attr, value) in tuple.attrs.iter().zip(&tuple.values) {
+                    self.type_expr(attr, env);
+                    let ty = self.type_expr(value, env);
+                    match attr {
+                        ValueExpr::Lit(lit) => match lit.as_ref() {
+                            Value::String(name) => attributes.push((name.to_string(), ty)),
+                            _ => open = true,
+                        },
+                        _ => open = true,
+                    }
+                }
+                struct_type(attributes, open)
+            }
+            ValueExpr::ListExpr(list) => {
+                self.type_exprs(&list.elements, env);
+                PartiqlType::new_array(ArrayType::new_any())
+            }
+            ValueExpr::BagExpr(bag) => {
+                self.type_exprs(&bag.elements, env);
+                PartiqlType::new_bag(BagType::new_any())
+            }
+            ValueExpr::BetweenExpr(between) => {
+                for expr in [&between.value, &between.from, &between.to] {
+                    self.type_expr(expr, env);
+                }
+                PartiqlType::new(TypeKind::Bool)
+            }
+            ValueExpr::PatternMatchExpr(pattern) => {
+                self.type_expr(&pattern.value, env);
+                PartiqlType::new(TypeKind::Bool)
+            }
+            ValueExpr::IsTypeExpr(is_type) => {
+                self.type_expr(&is_type.expr, env);
+                PartiqlType::new(TypeKind::Bool)
+            }
+            ValueExpr::SubQueryExpr(subquery) => {
+                let outer = self.outer.len();
+                self.outer.extend(env.iter().cloned());
+                let shape = self.type_subquery(&subquery.plan);
+                self.outer.truncate(outer);
+                match subquery.coercion {
+                    SubQueryCoercion::Collection => {
+                        PartiqlType::new_bag(BagType::new(Box::new(shape)))
+                    }
+                    // the value of the single attribute of the single tuple
+                    SubQueryCoercion::Scalar => match &fields(&shape)[..] {
+                        [(_, ty)] => ty.clone(),
+                        _ => any!(),
+                    },
+                }
+            }
+            ValueExpr::SimpleCase(case) => {
+                self.type_expr(&case.expr, env);
+                self.type_cases(&case.cases, &case.default, env)
+            }
+            ValueExpr::SearchedCase(case) => self.type_cases(&case.cases, &case.default, env),
+            ValueExpr::NullIfExpr(null_if) => {
+                self.type_expr(&null_if.lhs, env);
+                self.type_expr(&null_if.rhs, env);
+                any!()
+            }
+            ValueExpr::CoalesceExpr(coalesce) => {
+                self.type_exprs(&coalesce.elements, env);
+                any!()
+            }
+            ValueExpr::Call(call) => {
+                self.type_exprs(&call.arguments, env);
+                any!()
+            }
+            ValueExpr::DynamicLookup(lookups) => self.type_lookup(lookups, env),
+            ValueExpr::Parameter(_) => any!(),
+        }
+    }
+
+    /// The type of the first of the `lookups` of a dynamic lookup that may not be missing, i.e.,
+    /// whose variable is bound and which does not access an undeclared attribute.
+    fn type_lookup(&mut self, lookups: &[ValueExpr], env: &[(String, PartiqlType)]) -> PartiqlType {
+        for lookup in lookups {
+            let mut root = lookup;
+            while let ValueExpr::Path(expr, _) = root {
+                root = expr;
+            }
+            if let ValueExpr::VarRef(name) = root {
+                if self.lookup(name, env).is_none() {
+                    continue;
+                }
+            }
+            let errors = self.errors.len();
+            let ty = self.type_expr(lookup, env);
+            if self.errors.len() == errors {
+                return ty;
+            }
+            self.errors.truncate(errors);
+        }
+        any!()
+    }
+
+    fn type_exprs(&mut self, exprs: &[ValueExpr], env: &[(String, PartiqlType)]) {
+        for expr in exprs {
+            self.type_expr(expr, env);
+        }
+    }
+
+    fn type_cases(
+        &mut self,
+        cases: &[(Box<ValueExpr>, Box<ValueExpr>)],
+        default: &Option<Box<ValueExpr>>,
+        env: &[(String, PartiqlType)],
+    ) -> PartiqlType {
+        for (when, then) in cases {
+            self.type_expr(when, env);
+            self.type_expr(then, env);
+        }
+        if let Some(default) = default {
+            self.type_expr(default, env);
+        }
+        any!()
+    }
+
+    /// The type of the values the plan of a subquery outputs.
+    fn type_subquery(&mut self, plan: &LogicalPlan<BindingsOp>) -> PartiqlType {
+        let shapes = self.type_plan(plan);
+        plan.operators_by_id()
+            .find(|(_, op)| matches!(op, BindingsOp::Sink))
+            .and_then(|(sink, _)| shapes.get(&sink).cloned())
+            .unwrap_or_else(|| any!())
+    }
+
+    /// The type of the variable `name`, bound by the operator typed or by those of the plans
+    /// enclosing it.
+    fn lookup(&self, name: &BindingsName, env: &[(String, PartiqlType)]) -> Option<PartiqlType> {
+        env.iter()
+            .rev()
+            .chain(self.outer.iter().rev())
+            .find(|(var, _)| binds(var, name))
+            .map(|(_, ty)| ty.clone())
+    }
+
+    /// The type of the attribute `attr` of values of the type `ty`.
+    fn attribute(&mut self, ty: &PartiqlType, attr: &BindingsName) -> PartiqlType {
+        let TypeKind::Struct(st) = ty.kind() else {
+            return any!();
+        };
+        match attribute_type(st, attr) {
+            Ok(Some(ty)) => ty.clone(),
+            Ok(None) => any!(),
+            Err(err) => {
+                self.error(err.into());
+                missing!()
+            }
+        }
+    }
+
+    /// Records an error if `op` is applied to an operand of the type `ty`, which is not numeric.
+    fn check_numeric(&mut self, op: &impl std::fmt::Debug, ty: &PartiqlType) {
+        let numeric = !matches!(
+            ty.kind(),
+            TypeKind::Bool
+                | TypeKind::String
+                | TypeKind::StringFixed(_)
+                | TypeKind::StringVarying(_)
+                | TypeKind::Struct(_)
+                | TypeKind::Bag(_)
+                | TypeKind::Array(_)
+        );
+        if !numeric {
+            self.error(TypingError::IncompatibleTypes(format!(
+                "`{op:?}` of an operand of type {ty}"
+            )));
+        }
+    }
+
+    /// Records `err`, once, as the operators joins keep inline are also typed as the operators
+    /// flowing to them.
+    fn error(&mut self, err: TypingError) {
+        if !self.errors.contains(&err) {
+            self.errors.push(err);
+        }
+    }
+}
+
+fn is_string(ty: &PartiqlType) -> bool {
+    matches!(
+        ty.kind(),
+        TypeKind::String | TypeKind::StringFixed(_) | TypeKind::StringVarying(_)
+    )
+}
+
+/// The type of the bindings of the variables `bound`.
+fn bindings_type(bound: Bindings) -> PartiqlType {
+    struct_type(bound, false)
+}
+
+fn struct_type(attributes: Vec<(String, PartiqlType)>, open: bool) -> PartiqlType {
+    let open = (!open).then_some(StructConstraint::Open(false));
+    let fields = attributes
+        .into_iter()
+        .map(|(name, ty)| StructConstraint::Fields(StructField::new(&name, ty)));
+    PartiqlType::new_struct(StructType::new(open.into_iter().chain(fields).collect()))
+}
+
+/// The attributes of the struct type `ty`, e.g., the variables of bindings.
+fn fields(ty: &PartiqlType) -> Bindings {
+    match ty.kind() {
+        TypeKind::Struct(st) => st
+            .fields()
+            .map(|field| (field.name().to_string(), field.ty().clone()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// The type of the tuples `SELECT *` outputs of the variables `env`, which merge the attributes of
+/// the values of the variables.
+fn project_all(env: &[(String, PartiqlType)]) -> PartiqlType {
+    let mut attributes = vec![];
+    let mut open = false;
+    for (_, ty) in env {
+        match ty.kind() {
+            TypeKind::Struct(st) => {
+                attributes.extend(fields(ty));
+                open |= st.is_open();
+            }
+            _ => open = true,
+        }
+    }
+    struct_type(attributes, open)
+}
+
+/// Whether `left` and `right` are struct types of the same attributes, in the same order.
+fn same_attributes(left: &PartiqlType, right: &PartiqlType) -> bool {
+    match (left.kind(), right.kind()) {
+        (TypeKind::Struct(l), TypeKind::Struct(r)) => {
+            l.is_open() == r.is_open()
+                && l.fields()
+                    .map(|field| field.name())
+                    .eq(r.fields().map(|field| field.name()))
+        }
+        _ => false,
+    }
+}
+
+fn type_of_value(value: &Value) -> PartiqlType {
+    match value {
+        Value::Null => PartiqlType::new(TypeKind::Null),
+        Value::Missing => missing!(),
+        Value::Boolean(_) => PartiqlType::new(TypeKind::Bool),
+        Value::Integer(_) => PartiqlType::new(TypeKind::Int),
+        Value::Real(_) => PartiqlType::new(TypeKind::Float64),
+        Value::Decimal(_) => PartiqlType::new(TypeKind::Decimal),
+        Value::String(_) => PartiqlType::new(TypeKind::String),
+        Value::List(_) => PartiqlType::new_array(ArrayType::new_any()),
+        Value::Bag(_) => PartiqlType::new_bag(BagType::new_any()),
+        Value::Tuple(tuple) => struct_type(
+            tuple
+                .pairs()
+                .map(|(name, value)| (name.to_string(), type_of_value(value)))
+                .collect(),
+            false,
+        ),
+        Value::Blob(_) | Value::DateTime(_) => any!(),
+    }
+}
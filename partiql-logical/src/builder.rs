@@ -0,0 +1,289 @@
+//! Construction of [`LogicalPlan`]s with [`PlanBuilder`]s.
+
+use crate::{
+    BagOp, BagOperator, BindingsOp, ExprQuery, Filter, GroupBy, Having, Join, JoinKind, Let,
+    LimitOffset, LogicalPlan, OpId, OrderBy, Project, ProjectValue, Scan, SetQuantifier, SortSpec,
+    ValueExpr,
+};
+use partiql_value::BindingsName;
+
+/// A builder of a [`LogicalPlan`] as a pipeline of operators, each taking its input from the
+/// previous one, and flowing to the sink of the plan as the last one.
+///
+/// ```
+/// use partiql_logical::{BinaryOp, BindingsOp, PathComponent, PlanBuilder, ValueExpr};
+/// use partiql_value::{BindingsName, Value};
+///
+/// let id = ValueExpr::Path(
+///     Box::new(ValueExpr::VarRef(BindingsName::CaseInsensitive("t".to_string()))),
+///     vec![PathComponent::Key(BindingsName::CaseInsensitive("id".to_string()))],
+/// );
+/// let plan = PlanBuilder::scan("t")
+///     .filter(ValueExpr::BinaryExpr(
+///         BinaryOp::Gt,
+///         Box::new(id.clone()),
+///         Box::new(ValueExpr::Lit(Box::new(Value::from(1)))),
+///     ))
+///     .project([("id", id)])
+///     .build();
+///
+/// assert_eq!(plan.validate(), Ok(()));
+/// assert_eq!(
+///     plan.to_string(),
+///     "#1 Scan(global::t AS t)\n#2 Filter(Gt(t.id, 1))\n#3 Project(t.id AS id)\n#4 Sink\n\
+///      #1 -> #2\n#2 -> #3\n#3 -> #4\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct PlanBuilder {
+    plan: LogicalPlan<BindingsOp>,
+    /// The last operator of the pipeline.
+    last: OpId,
+}
+
+impl PlanBuilder {
+    /// Starts a pipeline with the operator `op`, which takes no input.
+    pub fn new(op: BindingsOp) -> Self {
+        let mut plan = LogicalPlan::new();
+        let last = plan.add_operator(op);
+        PlanBuilder { plan, last }
+    }
+
+    /// Starts a pipeline with a scan of the global variable `name`, e.g., of the table `t` of
+    /// `FROM t`, as the variable `name`.
+    pub fn scan(name: &str) -> Self {
+        let expr = ValueExpr::GlobalVarRef(BindingsName::CaseInsensitive(name.to_string()));
+        PlanBuilder::scan_as(expr, name)
+    }
+
+    /// Starts a pipeline with a scan of the values of `expr` as the variable `as_key`, e.g., of
+    /// `FROM expr AS as_key`.
+    pub fn scan_as(expr: ValueExpr, as_key: &str) -> Self {
+        PlanBuilder::new(BindingsOp::Scan(Scan {
+            expr,
+            as_key: as_key.to_string(),
+            at_key: None,
+        }))
+    }
+
+    /// Starts a pipeline evaluating the expression `expr`, e.g., of the query `1 + 1`.
+    pub fn expr(expr: ValueExpr) -> Self {
+        PlanBuilder::new(BindingsOp::ExprQuery(ExprQuery { expr }))
+    }
+
+    /// Appends the operator `op`, which takes a single input, to the pipeline.
+    pub fn then(mut self, op: BindingsOp) -> Self {
+        let op = self.plan.add_operator(op);
+        self.plan.add_flow(self.last, op);
+        self.last = op;
+        self
+    }
+
+    /// Appends a filter of the condition `expr`, e.g., of `WHERE expr`.
+    pub fn filter(self, expr: ValueExpr) -> Self {
+        self.then(BindingsOp::Filter(Filter { expr }))
+    }
+
+    /// Appends a filter of groups by the condition `expr`, e.g., of `HAVING expr`.
+    pub fn having(self, expr: ValueExpr) -> Self {
+        self.then(BindingsOp::Having(Having { expr }))
+    }
+
+    /// Appends the binding of variables to expressions, e.g., of `LET expr AS name`.
+    pub fn let_bindings<S>(self, bindings: impl IntoIterator<Item = (S, ValueExpr)>) -> Self
+    where
+        S: Into<String>,
+    {
+        let bindings = bindings
+            .into_iter()
+            .map(|(name, expr)| (name.into(), expr))
+            .collect();
+        self.then(BindingsOp::Let(Let { bindings }))
+    }
+
+    /// Appends the grouping `group_by`, e.g., of `GROUP BY`.
+    pub fn group_by(self, group_by: GroupBy) -> Self {
+        self.then(BindingsOp::GroupBy(group_by))
+    }
+
+    /// Appends the sort by `specs`, e.g., of `ORDER BY`.
+    pub fn order_by(self, specs: Vec<SortSpec>) -> Self {
+        self.then(BindingsOp::OrderBy(OrderBy { specs }))
+    }
+
+    /// Appends the limit and offset, e.g., of `LIMIT limit OFFSET offset`.
+    pub fn limit_offset(self, limit: Option<ValueExpr>, offset: Option<ValueExpr>) -> Self {
+        self.then(BindingsOp::LimitOffset(LimitOffset { limit, offset }))
+    }
+
+    /// Appends the projection of expressions as attributes, e.g., of `SELECT expr AS name`.
+    pub fn project<S>(self, exprs: impl IntoIterator<Item = (S, ValueExpr)>) -> Self
+    where
+        S: Into<String>,
+    {
+        let exprs = exprs
+            .into_iter()
+            .map(|(name, expr)| (name.into(), expr))
+            .collect();
+        self.then(BindingsOp::Project(Project { exprs }))
+    }
+
+    /// Appends the projection of the expression `expr`, e.g., of `SELECT VALUE expr`.
+    pub fn project_value(self, expr: ValueExpr) -> Self {
+        self.then(BindingsOp::ProjectValue(ProjectValue { expr }))
+    }
+
+    /// Appends the projection of all variables, e.g., of `SELECT *`.
+    pub fn project_all(self) -> Self {
+        self.then(BindingsOp::ProjectAll)
+    }
+
+    /// Appends the elimination of duplicates, e.g., of `SELECT DISTINCT`.
+    pub fn distinct(self) -> Self {
+        self.then(BindingsOp::Distinct)
+    }
+
+    /// Joins the last operators of this pipeline and of the pipeline `right`, which the join keeps
+    /// inline and evaluates itself, as planners lower joins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either pipeline has an operator taking an input, i.e., if it is not a scan, an
+    /// unpivot or a join of such.
+    pub fn join(self, kind: JoinKind, right: PlanBuilder, on: Option<ValueExpr>) -> Self {
+        for operands in [&self, &right] {
+            assert!(
+                operands.plan.flows().iter().all(|&(_, dst, _)| matches!(
+                    operands.plan.operator(dst),
+                    Some(BindingsOp::Join(_))
+                )),
+                "a joined pipeline has an operator taking an input"
+            );
+        }
+        let join = BindingsOp::Join(Join {
+            kind,
+            left: Box::new(self.last_op().clone()),
+            right: Box::new(right.last_op().clone()),
+            on,
+        });
+        self.combine(right, join)
+    }
+
+    /// Combines the outputs of this pipeline and of the pipeline `right` by the bag operator `op`,
+    /// e.g., of `UNION ALL`.
+    pub fn bag_op(self, op: BagOperator, setq: SetQuantifier, right: PlanBuilder) -> Self {
+        self.combine(right, BindingsOp::BagOp(BagOp { bag_op: op, setq }))
+    }
+
+    /// Completes the plan with its sink.
+    pub fn build(self) -> LogicalPlan<BindingsOp> {
+        self.then(BindingsOp::Sink).plan
+    }
+
+    fn last_op(&self) -> &BindingsOp {
+        self.plan.operator(self.last).expect("last operator")
+    }
+
+    /// Appends the operator `op`, which takes the outputs of this pipeline and of the pipeline
+    /// `right` on the branches `0` and `1`, to the pipeline.
+    fn combine(mut self, right: PlanBuilder, op: BindingsOp) -> Self {
+        let ids: Vec<_> = right
+            .plan
+            .operators_by_id()
+            .map(|(id, op)| (id, self.plan.add_operator(op.clone())))
+            .collect();
+        let id = |right_id: OpId| {
+            ids.iter()
+                .find_map(|&(id, new)| (id == right_id).then_some(new))
+                .expect("operator of the right pipeline")
+        };
+        for (src, dst, branch_num) in right.plan.flows() {
+            self.plan
+                .add_flow_with_branch_num(id(src), id(dst), branch_num);
+        }
+
+        let op = self.plan.add_operator(op);
+        self.plan.add_flow_with_branch_num(self.last, op, 0);
+        self.plan.add_flow_with_branch_num(id(right.last), op, 1);
+        self.last = op;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinaryOp, PathComponent};
+    use partiql_value::Value;
+
+    fn path(var: &str, key: &str) -> ValueExpr {
+        ValueExpr::Path(
+            Box::new(ValueExpr::VarRef(BindingsName::CaseInsensitive(
+                var.to_string(),
+            ))),
+            vec![PathComponent::Key(BindingsName::CaseInsensitive(
+                key.to_string(),
+            ))],
+        )
+    }
+
+    #[test]
+    fn pipeline() {
+        let plan = PlanBuilder::scan("t")
+            .let_bindings([("b", path("t", "b"))])
+            .filter(ValueExpr::Lit(Box::new(Value::from(true))))
+            .project_value(path("t", "a"))
+            .distinct()
+            .build();
+        assert_eq!(plan.validate(), Ok(()));
+        assert_eq!(
+            format!("{plan:#}"),
+            "#6 Sink\n  #5 Distinct\n    #4 ProjectValue(t.a)\n      #3 Filter(true)\n        \
+             #2 Let(t.b AS b)\n          #1 Scan(global::t AS t)\n"
+        );
+    }
+
+    #[test]
+    fn join_and_bag_op() {
+        let joined = PlanBuilder::scan("a")
+            .join(JoinKind::Cross, PlanBuilder::scan("b"), None)
+            .join(
+                JoinKind::Inner,
+                PlanBuilder::scan("c"),
+                Some(ValueExpr::BinaryExpr(
+                    BinaryOp::Eq,
+                    Box::new(path("a", "id")),
+                    Box::new(path("c", "id")),
+                )),
+            )
+            .project_all();
+        let plan = joined
+            .bag_op(
+                BagOperator::Union,
+                SetQuantifier::All,
+                PlanBuilder::scan("d").project_all(),
+            )
+            .build();
+        assert_eq!(plan.validate(), Ok(()));
+        assert_eq!(
+            format!("{plan:#}"),
+            "#10 Sink\n  \
+               #9 BagOp(Union All)\n    \
+                 #6 ProjectAll\n      \
+                   #5 Join(Inner, Join(Cross, Scan(global::a AS a), Scan(global::b AS b)), \
+                      Scan(global::c AS c) ON Eq(a.id, c.id))\n        \
+                     #3 Join(Cross, Scan(global::a AS a), Scan(global::b AS b))\n          \
+                       #1 Scan(global::a AS a)\n          \
+                       #2 Scan(global::b AS b)\n        \
+                     #4 Scan(global::c AS c)\n    \
+                 #8 ProjectAll\n      \
+                   #7 Scan(global::d AS d)\n"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a joined pipeline has an operator taking an input")]
+    fn join_of_pipeline() {
+        PlanBuilder::scan("a").join(JoinKind::Cross, PlanBuilder::scan("b").distinct(), None);
+    }
+}
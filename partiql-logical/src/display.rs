@@ -0,0 +1,456 @@
+//! Compact & deterministic renderings of [`LogicalPlan`]s for, e.g., readable diffs of failing
+//! planner tests; the `Debug` renderings show the raw structure.
+//!
+//! A plan is rendered with one operator per line, prefixed by its [`OpId`], followed by its flows
+//! in order, e.g., for `SELECT c.id AS id FROM customer AS c WHERE c.balance > 10`:
+//!
+//! ```text
+//! #1 Project(c.id AS id)
+//! #2 Scan(global::customer AS c)
+//! #3 Filter(Gt(c.balance, 10))
+//! #4 Sink
+//! #1 -> #4
+//! #2 -> #3
+//! #3 -> #1
+//! ```
+//!
+//! The alternate rendering (`{:#}`) shows the plan as a tree instead, each operator indented under
+//! the operator it flows to, e.g.:
+//!
+//! ```text
+//! #4 Sink
+//!   #1 Project(c.id AS id)
+//!     #3 Filter(Gt(c.balance, 10))
+//!       #2 Scan(global::customer AS c)
+//! ```
+//!
+//! [`LogicalPlan::to_dot`] renders a plan as a [Graphviz](https://graphviz.org/) DOT graph.
+//!
+//! Expressions are rendered as function-like calls of their operators (e.g., `Gt(c.balance, 10)`),
+//! case-sensitive names are quoted and subqueries are rendered inline.
+
+use crate::{
+    AggregateExpression, BindingsOp, CallExpr, GroupBy, Join, LogicalPlan, OpId, Parameter,
+    PathComponent, Pattern, SortSpec, SortSpecNullOrder, SortSpecOrder, SubQueryExpr, ValueExpr,
+    Window, WindowFunction,
+};
+use partiql_value::BindingsName;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Result, Write};
+
+impl<T> Display for LogicalPlan<T>
+where
+    T: Default + Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if f.alternate() {
+            write_tree(f, self)
+        } else {
+            write_plan(f, self, "\n")?;
+            writeln!(f)
+        }
+    }
+}
+
+impl<T> LogicalPlan<T>
+where
+    T: Default + Display,
+{
+    /// Renders the plan as a DOT graph, with a node per operator labeled as in the [`Display`]
+    /// rendering of the plan and an edge per flow labeled by its branch number if it is not `0`.
+    ///
+    /// The output can be rendered with, e.g., `dot -Tsvg plan.dot -o plan.svg`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph plan {\n  node [shape=box];\n");
+        for (id, op) in self.operators_by_id() {
+            let label = escape(&format!("{} {op}", Id(id)));
+            let _ = writeln!(dot, "  n{} [label=\"{label}\"];", id.index());
+        }
+        let mut flows = self.flows();
+        flows.sort_by_key(|(src, dst, branch)| (src.index(), dst.index(), *branch));
+        for (src, dst, branch) in flows {
+            let label = match branch {
+                0 => String::new(),
+                _ => format!(" [label=\"{branch}\"]"),
+            };
+            let _ = writeln!(dot, "  n{} -> n{}{label};", src.index(), dst.index());
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Escapes `label` for a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Writes the operators of `plan` as trees rooted at the operators flowing to none (e.g., its
+/// `Sink`), followed by the flows from or to operators not in the plan. An operator flowing to
+/// several is written in full under the first of them only, as are operators on a cycle.
+fn write_tree<T>(f: &mut Formatter<'_>, plan: &LogicalPlan<T>) -> Result
+where
+    T: Default + Display,
+{
+    let ids: Vec<OpId> = plan.operators_by_id().map(|(id, _)| id).collect();
+    let roots = ids.iter().filter(|&&id| plan.successors(id).is_empty());
+    let mut written = HashSet::new();
+    for &id in roots.chain(&ids) {
+        if !written.contains(&id) {
+            write_subtree(f, plan, id, 0, &mut written)?;
+        }
+    }
+    for (src, dst, branch) in &plan.dangling {
+        writeln!(f, "{} -> {} ({branch}) not in plan", Id(*src), Id(*dst))?;
+    }
+    Ok(())
+}
+
+fn write_subtree<T>(
+    f: &mut Formatter<'_>,
+    plan: &LogicalPlan<T>,
+    id: OpId,
+    depth: usize,
+    written: &mut HashSet<OpId>,
+) -> Result
+where
+    T: Default + Display,
+{
+    let indent = "  ".repeat(depth);
+    if !written.insert(id) {
+        return writeln!(f, "{indent}{} ...", Id(id));
+    }
+    match plan.operator(id) {
+        Some(op) => writeln!(f, "{indent}{} {op}", Id(id))?,
+        None => writeln!(f, "{indent}{}", Id(id))?,
+    }
+    for input in plan.inputs(id) {
+        write_subtree(f, plan, input, depth + 1, written)?;
+    }
+    Ok(())
+}
+
+/// Writes the operators & then the flows of `plan`, separated by `sep`.
+fn write_plan<T>(f: &mut Formatter<'_>, plan: &LogicalPlan<T>, sep: &str) -> Result
+where
+    T: Default + Display,
+{
+    let mut lines: Vec<String> = plan
+        .operators_by_id()
+        .map(|(id, op)| format!("{} {op}", Id(id)))
+        .collect();
+
+    let mut flows = plan.flows().clone();
+    flows.sort_by_key(|(src, dst, branch)| (src.index(), dst.index(), *branch));
+    lines.extend(flows.iter().map(|(src, dst, branch)| match branch {
+        0 => format!("{} -> {}", Id(*src), Id(*dst)),
+        _ => format!("{} -> {} ({branch})", Id(*src), Id(*dst)),
+    }));
+    write!(f, "{}", lines.join(sep))
+}
+
+struct Id(OpId);
+
+impl Display for Id {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "#{}", self.0.index())
+    }
+}
+
+struct Name<'a>(&'a BindingsName);
+
+impl Display for Name<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.0 {
+            BindingsName::CaseSensitive(name) => write!(f, "\"{name}\""),
+            BindingsName::CaseInsensitive(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Writes `items`, separated by commas.
+fn write_list<I>(f: &mut Formatter<'_>, items: I) -> Result
+where
+    I: IntoIterator,
+    I::Item: Display,
+{
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
+/// Writes `name(items)`.
+fn write_call<I>(f: &mut Formatter<'_>, name: impl Display, items: I) -> Result
+where
+    I: IntoIterator,
+    I::Item: Display,
+{
+    write!(f, "{name}(")?;
+    write_list(f, items)?;
+    write!(f, ")")
+}
+
+/// Writes `expr AS alias`, or `expr AS alias AT at` if there is an `at`.
+fn write_as(f: &mut Formatter<'_>, expr: &ValueExpr, alias: &str, at: Option<&str>) -> Result {
+    write!(f, "{expr} AS {alias}")?;
+    match at {
+        Some(at) => write!(f, " AT {at}"),
+        None => Ok(()),
+    }
+}
+
+struct Aliased<'a>(&'a str, &'a ValueExpr);
+
+impl Display for Aliased<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write_as(f, self.1, self.0, None)
+    }
+}
+
+impl Display for BindingsOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            BindingsOp::Scan(scan) => {
+                write!(f, "Scan(")?;
+                write_as(f, &scan.expr, &scan.as_key, scan.at_key.as_deref())?;
+                write!(f, ")")
+            }
+            BindingsOp::Pivot(pivot) => write!(f, "Pivot({} AT {})", pivot.value, pivot.key),
+            BindingsOp::Unpivot(unpivot) => {
+                write!(f, "Unpivot(")?;
+                write_as(f, &unpivot.expr, &unpivot.as_key, unpivot.at_key.as_deref())?;
+                write!(f, ")")
+            }
+            BindingsOp::Let(lets) => write_call(
+                f,
+                "Let",
+                lets.bindings.iter().map(|(name, e)| Aliased(name, e)),
+            ),
+            BindingsOp::Filter(filter) => write!(f, "Filter({})", filter.expr),
+            BindingsOp::Having(having) => write!(f, "Having({})", having.expr),
+            BindingsOp::OrderBy(order_by) => write_call(f, "OrderBy", &order_by.specs),
+            BindingsOp::LimitOffset(limit_offset) => {
+                write!(f, "LimitOffset(")?;
+                if let Some(limit) = &limit_offset.limit {
+                    write!(f, "LIMIT {limit}")?;
+                }
+                if let Some(offset) = &limit_offset.offset {
+                    if limit_offset.limit.is_some() {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "OFFSET {offset}")?;
+                }
+                write!(f, ")")
+            }
+            BindingsOp::Join(join) => join.fmt(f),
+            BindingsOp::BagOp(bag_op) => write!(f, "BagOp({:?} {:?})", bag_op.bag_op, bag_op.setq),
+            BindingsOp::Project(project) => write_call(
+                f,
+                "Project",
+                project.exprs.iter().map(|(name, e)| Aliased(name, e)),
+            ),
+            BindingsOp::ProjectAll => write!(f, "ProjectAll"),
+            BindingsOp::ProjectValue(project) => write!(f, "ProjectValue({})", project.expr),
+            BindingsOp::ExprQuery(query) => write!(f, "ExprQuery({})", query.expr),
+            BindingsOp::Distinct => write!(f, "Distinct"),
+            BindingsOp::GroupBy(group_by) => group_by.fmt(f),
+            BindingsOp::Window(window) => window.fmt(f),
+            BindingsOp::Sink => write!(f, "Sink"),
+        }
+    }
+}
+
+impl Display for Join {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Join({:?}, {}, {}", self.kind, self.left, self.right)?;
+        if let Some(on) = &self.on {
+            write!(f, " ON {on}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Display for GroupBy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        // `exprs` is unordered, so its keys are sorted
+        let mut exprs: Vec<_> = self.exprs.iter().collect();
+        exprs.sort_by_key(|(name, _)| *name);
+
+        write!(f, "GroupBy({:?}", self.strategy)?;
+        for (name, expr) in exprs {
+            write!(f, ", {}", Aliased(name, expr))?;
+        }
+        for agg in &self.aggregate_exprs {
+            write!(f, ", {agg}")?;
+        }
+        if let Some(alias) = &self.group_as_alias {
+            write!(f, ", GROUP AS {alias}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Display for AggregateExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{:?}({:?} {}) AS {}",
+            self.func, self.setq, self.expr, self.name
+        )
+    }
+}
+
+impl Display for Window {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Window(")?;
+        match &self.func {
+            WindowFunction::Lag(args) => {
+                write_call(f, "Lag", [&args.expr, &args.offset, &args.default])?
+            }
+            WindowFunction::Lead(args) => {
+                write_call(f, "Lead", [&args.expr, &args.offset, &args.default])?
+            }
+            func => write!(f, "{func:?}()")?,
+        }
+        write!(f, " OVER (")?;
+        if !self.partition_by.is_empty() {
+            write!(f, "PARTITION BY ")?;
+            write_list(f, &self.partition_by)?;
+            if !self.order_by.is_empty() {
+                write!(f, " ")?;
+            }
+        }
+        if !self.order_by.is_empty() {
+            write!(f, "ORDER BY ")?;
+            write_list(f, &self.order_by)?;
+        }
+        write!(f, ") AS {})", self.name)
+    }
+}
+
+impl Display for SortSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let order = match self.order {
+            SortSpecOrder::Asc => "ASC",
+            SortSpecOrder::Desc => "DESC",
+        };
+        let null_order = match self.null_order {
+            SortSpecNullOrder::First => "NULLS FIRST",
+            SortSpecNullOrder::Last => "NULLS LAST",
+        };
+        write!(f, "{} {order} {null_order}", self.expr)
+    }
+}
+
+impl Display for ValueExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ValueExpr::UnExpr(op, expr) => write!(f, "{op:?}({expr})"),
+            ValueExpr::BinaryExpr(op, lhs, rhs) => write!(f, "{op:?}({lhs}, {rhs})"),
+            ValueExpr::Lit(lit) => write!(f, "{lit:?}"),
+            ValueExpr::DynamicLookup(lookups) => write_call(f, "Lookup", lookups.iter()),
+            ValueExpr::Path(root, components) => {
+                write!(f, "{root}")?;
+                components.iter().try_for_each(|c| c.fmt(f))
+            }
+            ValueExpr::VarRef(name) => Name(name).fmt(f),
+            ValueExpr::GlobalVarRef(name) => write!(f, "global::{}", Name(name)),
+            ValueExpr::Parameter(Parameter::Positional(position)) => write!(f, "?{position}"),
+            ValueExpr::Parameter(Parameter::Named(name)) => write!(f, ":{}", Name(name)),
+            ValueExpr::TupleExpr(tuple) => {
+                write!(f, "{{")?;
+                let attrs = tuple.attrs.iter().zip(&tuple.values);
+                write_list(f, attrs.map(|(attr, value)| format!("{attr}: {value}")))?;
+                write!(f, "}}")
+            }
+            ValueExpr::ListExpr(list) => {
+                write!(f, "[")?;
+                write_list(f, &list.elements)?;
+                write!(f, "]")
+            }
+            ValueExpr::BagExpr(bag) => {
+                write!(f, "<<")?;
+                write_list(f, &bag.elements)?;
+                write!(f, ">>")
+            }
+            ValueExpr::BetweenExpr(between) => write!(
+                f,
+                "Between({}, {}, {})",
+                between.value, between.from, between.to
+            ),
+            ValueExpr::PatternMatchExpr(pattern_match) => match &pattern_match.pattern {
+                Pattern::Like(like) => write!(
+                    f,
+                    "Like({}, '{}' ESCAPE '{}')",
+                    pattern_match.value, like.pattern, like.escape
+                ),
+                Pattern::LikeNonStringNonLiteral(like) => write!(
+                    f,
+                    "Like({}, {} ESCAPE {})",
+                    pattern_match.value, like.pattern, like.escape
+                ),
+            },
+            ValueExpr::SubQueryExpr(subquery) => subquery.fmt(f),
+            ValueExpr::SimpleCase(case) => {
+                write!(f, "Case({}", case.expr)?;
+                write_cases(f, &case.cases, &case.default)
+            }
+            ValueExpr::SearchedCase(case) => {
+                write!(f, "Case(")?;
+                write_cases(f, &case.cases, &case.default)
+            }
+            ValueExpr::IsTypeExpr(is_type) => {
+                let name = if is_type.not { "IsNot" } else { "Is" };
+                write!(f, "{name}({}, {:?})", is_type.expr, is_type.is_type)
+            }
+            ValueExpr::NullIfExpr(null_if) => write!(f, "NullIf({}, {})", null_if.lhs, null_if.rhs),
+            ValueExpr::CoalesceExpr(coalesce) => write_call(f, "Coalesce", &coalesce.elements),
+            ValueExpr::Call(call) => call.fmt(f),
+        }
+    }
+}
+
+/// Writes the `WHEN`s, `ELSE` & closing parenthesis of a `CASE`.
+fn write_cases(
+    f: &mut Formatter<'_>,
+    cases: &[(Box<ValueExpr>, Box<ValueExpr>)],
+    default: &Option<Box<ValueExpr>>,
+) -> Result {
+    for (when, then) in cases {
+        write!(f, " WHEN {when} THEN {then}")?;
+    }
+    if let Some(default) = default {
+        write!(f, " ELSE {default}")?;
+    }
+    write!(f, ")")
+}
+
+impl Display for PathComponent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            PathComponent::Key(key) => write!(f, ".{}", Name(key)),
+            PathComponent::Index(index) => write!(f, "[{index}]"),
+            PathComponent::Expr(expr) => write!(f, "[{expr}]"),
+            PathComponent::Wildcard => write!(f, "[*]"),
+            PathComponent::Unpivot => write!(f, ".*"),
+        }
+    }
+}
+
+impl Display for SubQueryExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{:?}SubQuery(", self.coercion)?;
+        write_plan(f, &self.plan, "; ")?;
+        write!(f, ")")
+    }
+}
+
+impl Display for CallExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write_call(f, format!("{:?}", self.name), &self.arguments)
+    }
+}
@@ -0,0 +1,1052 @@
+//! Serialization of logical plans to the Ion s-expressions of the `partiql_logical` domain of
+//! [partiql-lang-kotlin](https://github.com/partiql/partiql-lang-kotlin), e.g.,
+//!
+//! ```text
+//! (plan
+//!     (stmt (query (bindings_to_values (id x (case_insensitive) (unqualified))
+//!         (filter (gt (id x (case_insensitive) (unqualified)) (lit 1))
+//!             (scan (id t (case_insensitive) (unqualified)) (var_decl x) null null)))))
+//!     (version v0_0))
+//! ```
+//!
+//! for `SELECT VALUE x FROM t AS x WHERE x > 1`, which allows consuming the plans of this crate
+//! with engines built on the Kotlin implementation.
+//!
+//! The relational operators of a plan are written as the nested `bexpr`s of the operators they
+//! take their inputs from, with the operator producing the result of the plan (e.g.,
+//! [`BindingsOp::ProjectValue`]) as the expression of its statement. Operators and expressions
+//! without a counterpart in the Ion format, e.g., [`BindingsOp::Distinct`] or window functions,
+//! are [`IonPlanError::Unsupported`]. Variable references are written as the unresolved `id`s they
+//! are lowered from. `SELECT *` and `<expr>.*` are written as the `struct`s of the `struct_fields`
+//! of the variables bound by the input of the projection, and of `<expr>`, respectively, as the
+//! Kotlin implementation plans them, and `IS DISTINCT FROM` as the `searched_case` it is
+//! equivalent to.
+//!
+//! ```
+//! use ion_rs::element::Element;
+//! use partiql_logical::ion::to_ion;
+//! use partiql_logical::{BindingsOp, ExprQuery, LogicalPlan, ValueExpr};
+//! use partiql_value::Value;
+//!
+//! let mut plan = LogicalPlan::new();
+//! let query = plan.add_operator(BindingsOp::ExprQuery(ExprQuery {
+//!     expr: ValueExpr::Lit(Box::new(Value::from(42))),
+//! }));
+//! let sink = plan.add_operator(BindingsOp::Sink);
+//! plan.add_flow(query, sink);
+//! assert_eq!(
+//!     to_ion(&plan).unwrap(),
+//!     Element::read_one("(plan (stmt (query (lit 42))) (version v0_0))").unwrap()
+//! );
+//! ```
+
+use crate::{
+    AggFunc, BagOperator, BinaryOp, BindingsOp, CallName, GroupingStrategy, IsTypeExpr, JoinKind,
+    LogicalPlan, OpId, PathComponent, Pattern, SearchedCase, SetQuantifier, SortSpec,
+    SortSpecNullOrder, SortSpecOrder, SubQueryExpr, Type, UnaryOp, ValidationError, ValueExpr,
+};
+use ion_rs::element::{Element, SExp, Sequence};
+use ion_rs::{Decimal, IonType};
+use partiql_value::{BindingsName, Value};
+use thiserror::Error;
+
+/// The version of the `partiql_logical` domain plans are written in.
+pub const PLAN_VERSION: &str = "v0_0";
+
+/// An error writing a logical plan as Ion.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IonPlanError {
+    /// A part of the plan has no counterpart in the Ion format.
+    #[error("{0} cannot be written as Ion")]
+    Unsupported(String),
+    /// The plan is not well-formed.
+    #[error(transparent)]
+    Invalid(#[from] ValidationError),
+}
+
+/// Writes `plan` as an Ion `(plan ...)` of a `(query ...)` statement.
+pub fn to_ion(plan: &LogicalPlan<BindingsOp>) -> Result<Element, IonPlanError> {
+    Ok(sexp(
+        "plan",
+        [
+            sexp("stmt", [sexp("query", [plan_to_ion(plan)?])]),
+            sexp("version", [Element::symbol(PLAN_VERSION)]),
+        ],
+    ))
+}
+
+fn unsupported(what: &str) -> IonPlanError {
+    IonPlanError::Unsupported(what.to_string())
+}
+
+fn sexp(tag: &str, args: impl IntoIterator<Item = Element>) -> Element {
+    let elements = std::iter::once(Element::symbol(tag)).chain(args);
+    SExp(Sequence::new(elements)).into()
+}
+
+fn tag(tag: &str) -> Element {
+    sexp(tag, [])
+}
+
+fn null() -> Element {
+    Element::null(IonType::Null)
+}
+
+fn var_decl(name: &str) -> Element {
+    sexp("var_decl", [Element::symbol(name)])
+}
+
+fn opt_var_decl(name: &Option<String>) -> Element {
+    name.as_deref().map_or_else(null, var_decl)
+}
+
+fn exprs<'a>(
+    tag: &str,
+    exprs: impl IntoIterator<Item = &'a ValueExpr>,
+) -> Result<Element, IonPlanError> {
+    let exprs = exprs
+        .into_iter()
+        .map(expr_to_ion)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sexp(tag, exprs))
+}
+
+fn set_quantifier(setq: &SetQuantifier) -> Element {
+    match setq {
+        SetQuantifier::All => tag("all"),
+        SetQuantifier::Distinct => tag("distinct"),
+    }
+}
+
+/// Writes the expression of the result of `plan`, which is validated first.
+fn plan_to_ion(plan: &LogicalPlan<BindingsOp>) -> Result<Element, IonPlanError> {
+    plan.validate()?;
+    let sink = plan
+        .operators_by_id()
+        .find_map(|(id, op)| matches!(op, BindingsOp::Sink).then_some(id));
+    match sink.map(|sink| plan.inputs(sink)).as_deref() {
+        Some([result]) => values_to_ion(plan, *result),
+        _ => Err(unsupported("a plan without a result")),
+    }
+}
+
+/// The single operator `id` takes its input from, which a valid plan has.
+fn input(plan: &LogicalPlan<BindingsOp>, id: OpId) -> Result<Element, IonPlanError> {
+    match plan.inputs(id).as_slice() {
+        [input] => bexpr_to_ion(plan, *input),
+        _ => Err(unsupported("an operator without a single input")),
+    }
+}
+
+/// Writes the operator `id`, which produces values rather than binding tuples, as an `expr`.
+fn values_to_ion(plan: &LogicalPlan<BindingsOp>, id: OpId) -> Result<Element, IonPlanError> {
+    match plan.operator(id) {
+        Some(BindingsOp::ProjectValue(project)) => Ok(sexp(
+            "bindings_to_values",
+            [expr_to_ion(&project.expr)?, input(plan, id)?],
+        )),
+        Some(BindingsOp::Project(project)) => {
+            let fields = project
+                .exprs
+                .iter()
+                .map(|(name, expr)| {
+                    Ok(sexp(
+                        "struct_field",
+                        [
+                            sexp("lit", [Element::string(name.as_str())]),
+                            expr_to_ion(expr)?,
+                        ],
+                    ))
+                })
+                .collect::<Result<Vec<_>, IonPlanError>>()?;
+            Ok(sexp(
+                "bindings_to_values",
+                [sexp("struct", fields), input(plan, id)?],
+            ))
+        }
+        Some(BindingsOp::Pivot(pivot)) => Ok(sexp(
+            "pivot",
+            [
+                input(plan, id)?,
+                expr_to_ion(&pivot.key)?,
+                expr_to_ion(&pivot.value)?,
+            ],
+        )),
+        Some(BindingsOp::ExprQuery(query)) => expr_to_ion(&query.expr),
+        Some(BindingsOp::BagOp(bag_op)) => {
+            let op = match bag_op.bag_op {
+                BagOperator::Union => "union",
+                BagOperator::Except => "except",
+                BagOperator::Intersect => "intersect",
+                BagOperator::OuterUnion => "outer_union",
+                BagOperator::OuterExcept => "outer_except",
+                BagOperator::OuterIntersect => "outer_intersect",
+            };
+            let mut elements = vec![tag(op), set_quantifier(&bag_op.setq)];
+            for operand in plan.inputs(id) {
+                elements.push(values_to_ion(plan, operand)?);
+            }
+            Ok(sexp("bag_op", elements))
+        }
+        // `SELECT *` merges the attributes of the variables bound by its input, as `SELECT x.*`
+        // does those of `x`
+        Some(BindingsOp::ProjectAll) => {
+            let [source] = plan.inputs(id)[..] else {
+                return Err(unsupported("an operator without a single input"));
+            };
+            let fields = bound_variables(plan, source)?
+                .into_iter()
+                .map(|var| ValueExpr::VarRef(BindingsName::CaseSensitive(var)))
+                .map(|var| exprs("struct_fields", [&var]))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(sexp(
+                "bindings_to_values",
+                [sexp("struct", fields), input(plan, id)?],
+            ))
+        }
+        Some(BindingsOp::Distinct) => Err(unsupported("SELECT DISTINCT")),
+        _ => Err(unsupported("a plan whose result is not projected")),
+    }
+}
+
+/// The variables bound by the binding tuples the operator `id` outputs, in the order they are
+/// written.
+fn bound_variables(plan: &LogicalPlan<BindingsOp>, id: OpId) -> Result<Vec<String>, IonPlanError> {
+    let input = || match plan.inputs(id)[..] {
+        [input] => bound_variables(plan, input),
+        _ => Err(unsupported("an operator without a single input")),
+    };
+    match plan.operator(id) {
+        Some(op @ (BindingsOp::Scan(_) | BindingsOp::Unpivot(_) | BindingsOp::Join(_))) => {
+            Ok(op.bound_variables())
+        }
+        Some(
+            BindingsOp::Filter(_)
+            | BindingsOp::Having(_)
+            | BindingsOp::OrderBy(_)
+            | BindingsOp::LimitOffset(_),
+        ) => input(),
+        Some(BindingsOp::Let(let_op)) => {
+            let mut bound = input()?;
+            bound.extend(let_op.bindings.iter().map(|(name, _)| name.clone()));
+            Ok(bound)
+        }
+        Some(BindingsOp::GroupBy(group_by)) => {
+            let mut bound: Vec<_> = group_by.exprs.keys().cloned().collect();
+            bound.sort();
+            bound.extend(group_by.aggregate_exprs.iter().map(|agg| agg.name.clone()));
+            bound.extend(group_by.group_as_alias.iter().cloned());
+            Ok(bound)
+        }
+        _ => Err(unsupported(
+            "an operator whose output is not binding tuples",
+        )),
+    }
+}
+
+/// Writes the operator `id`, which produces binding tuples, as a `bexpr`.
+fn bexpr_to_ion(plan: &LogicalPlan<BindingsOp>, id: OpId) -> Result<Element, IonPlanError> {
+    match plan.operator(id) {
+        Some(BindingsOp::Join(join)) => {
+            let (left, right) = match plan.inputs(id).as_slice() {
+                [left, right] => (bexpr_to_ion(plan, *left)?, bexpr_to_ion(plan, *right)?),
+                _ => (source_to_ion(&join.left)?, source_to_ion(&join.right)?),
+            };
+            let kind = match join.kind {
+                JoinKind::Inner | JoinKind::Cross => "inner",
+                JoinKind::Left => "left",
+                JoinKind::Right => "right",
+                JoinKind::Full => "full",
+            };
+            let on = join.on.as_ref().map_or_else(|| Ok(null()), expr_to_ion)?;
+            Ok(sexp("join", [tag(kind), left, right, on]))
+        }
+        Some(op @ (BindingsOp::Scan(_) | BindingsOp::Unpivot(_))) => source_to_ion(op),
+        Some(BindingsOp::Filter(filter)) => Ok(sexp(
+            "filter",
+            [expr_to_ion(&filter.expr)?, input(plan, id)?],
+        )),
+        // `HAVING` filters the groups of the aggregation it takes its input from
+        Some(BindingsOp::Having(having)) => Ok(sexp(
+            "filter",
+            [expr_to_ion(&having.expr)?, input(plan, id)?],
+        )),
+        Some(BindingsOp::Let(let_op)) => {
+            let mut elements = vec![input(plan, id)?];
+            for (name, expr) in &let_op.bindings {
+                elements.push(sexp("let_binding", [expr_to_ion(expr)?, var_decl(name)]));
+            }
+            Ok(sexp("let", elements))
+        }
+        Some(BindingsOp::OrderBy(order_by)) => {
+            let mut elements = vec![input(plan, id)?];
+            for spec in &order_by.specs {
+                elements.push(sort_spec_to_ion(spec)?);
+            }
+            Ok(sexp("sort", elements))
+        }
+        Some(BindingsOp::LimitOffset(limit_offset)) => {
+            let mut source = input(plan, id)?;
+            if let Some(offset) = &limit_offset.offset {
+                source = sexp("offset", [expr_to_ion(offset)?, source]);
+            }
+            if let Some(limit) = &limit_offset.limit {
+                source = sexp("limit", [expr_to_ion(limit)?, source]);
+            }
+            Ok(source)
+        }
+        Some(BindingsOp::GroupBy(group_by)) => {
+            if group_by.group_as_alias.is_some() {
+                return Err(unsupported("GROUP AS"));
+            }
+            let strategy = match group_by.strategy {
+                GroupingStrategy::GroupFull => tag("group_full"),
+                GroupingStrategy::GroupPartial => tag("group_partial"),
+            };
+            // the keys are written in the order of their names, as they are not kept in order
+            let mut keys: Vec<_> = group_by.exprs.iter().collect();
+            keys.sort_by_key(|(name, _)| name.as_str());
+            let keys = keys
+                .into_iter()
+                .map(|(name, expr)| Ok(sexp("group_key", [expr_to_ion(expr)?, var_decl(name)])))
+                .collect::<Result<Vec<_>, IonPlanError>>()?;
+            let functions = group_by
+                .aggregate_exprs
+                .iter()
+                .map(|agg| {
+                    let name = match agg.func {
+                        AggFunc::AggAvg => "avg",
+                        AggFunc::AggCount => "count",
+                        AggFunc::AggMax => "max",
+                        AggFunc::AggMin => "min",
+                        AggFunc::AggSum => "sum",
+                    };
+                    Ok(sexp(
+                        "aggregate_function",
+                        [
+                            set_quantifier(&agg.setq),
+                            Element::symbol(name),
+                            expr_to_ion(&agg.expr)?,
+                            var_decl(&agg.name),
+                        ],
+                    ))
+                })
+                .collect::<Result<Vec<_>, IonPlanError>>()?;
+            Ok(sexp(
+                "aggregate",
+                [
+                    input(plan, id)?,
+                    strategy,
+                    sexp("group_key_list", keys),
+                    sexp("aggregate_function_list", functions),
+                ],
+            ))
+        }
+        Some(BindingsOp::Window(_)) => Err(unsupported("window functions")),
+        _ => Err(unsupported(
+            "an operator whose output is not binding tuples",
+        )),
+    }
+}
+
+/// Writes a `Scan` or `Unpivot` operator, which takes no input.
+fn source_to_ion(op: &BindingsOp) -> Result<Element, IonPlanError> {
+    let (kind, expr, as_key, at_key) = match op {
+        BindingsOp::Scan(scan) => ("scan", &scan.expr, &scan.as_key, &scan.at_key),
+        BindingsOp::Unpivot(unpivot) => {
+            ("unpivot", &unpivot.expr, &unpivot.as_key, &unpivot.at_key)
+        }
+        _ => return Err(unsupported("a join of operators other than scans")),
+    };
+    Ok(sexp(
+        kind,
+        [
+            expr_to_ion(expr)?,
+            var_decl(as_key),
+            opt_var_decl(at_key),
+            null(),
+        ],
+    ))
+}
+
+fn sort_spec_to_ion(spec: &SortSpec) -> Result<Element, IonPlanError> {
+    let ordering = match spec.order {
+        SortSpecOrder::Asc => tag("asc"),
+        SortSpecOrder::Desc => tag("desc"),
+    };
+    let nulls = match spec.null_order {
+        SortSpecNullOrder::First => tag("nulls_first"),
+        SortSpecNullOrder::Last => tag("nulls_last"),
+    };
+    Ok(sexp(
+        "sort_spec",
+        [expr_to_ion(&spec.expr)?, ordering, nulls],
+    ))
+}
+
+fn id(name: &BindingsName) -> Element {
+    let (name, case) = match name {
+        BindingsName::CaseSensitive(name) => (name, "case_sensitive"),
+        BindingsName::CaseInsensitive(name) => (name, "case_insensitive"),
+    };
+    sexp(
+        "id",
+        [
+            Element::symbol(name.as_str()),
+            tag(case),
+            tag("unqualified"),
+        ],
+    )
+}
+
+fn expr_to_ion(expr: &ValueExpr) -> Result<Element, IonPlanError> {
+    match expr {
+        ValueExpr::UnExpr(op, expr) => {
+            let op = match op {
+                UnaryOp::Pos => "pos",
+                UnaryOp::Neg => "neg",
+                UnaryOp::Not => "not",
+            };
+            Ok(sexp(op, [expr_to_ion(expr)?]))
+        }
+        ValueExpr::BinaryExpr(op, lhs, rhs) => {
+            let op = match op {
+                BinaryOp::And => "and",
+                BinaryOp::Or => "or",
+                BinaryOp::Concat => "concat",
+                BinaryOp::Eq => "eq",
+                BinaryOp::Neq => "ne",
+                BinaryOp::Gt => "gt",
+                BinaryOp::Gteq => "gte",
+                BinaryOp::Lt => "lt",
+                BinaryOp::Lteq => "lte",
+                BinaryOp::Add => "plus",
+                BinaryOp::Sub => "minus",
+                BinaryOp::Mul => "times",
+                BinaryOp::Div => "divide",
+                BinaryOp::Mod => "modulo",
+                BinaryOp::In => "in_collection",
+                BinaryOp::Exp => return Err(unsupported("^")),
+                BinaryOp::IsDistinctFrom => return is_distinct_from_to_ion(lhs, rhs),
+            };
+            exprs(op, [lhs.as_ref(), rhs])
+        }
+        ValueExpr::Lit(value) => value_to_ion(value),
+        // the alternatives of a lookup all look up the unqualified name they are lowered from
+        ValueExpr::DynamicLookup(lookups) => match lookups.first() {
+            Some(ValueExpr::VarRef(name) | ValueExpr::GlobalVarRef(name)) => Ok(id(name)),
+            Some(ValueExpr::Path(_, components)) => match components.as_slice() {
+                [PathComponent::Key(name)] => Ok(id(name)),
+                _ => Err(unsupported("dynamic lookups of paths")),
+            },
+            _ => Err(unsupported("dynamic lookups of other than names")),
+        },
+        ValueExpr::Path(root, components) => {
+            let mut elements = vec![expr_to_ion(root)?];
+            for component in components {
+                elements.push(match component {
+                    PathComponent::Key(name) => {
+                        let (name, case) = match name {
+                            BindingsName::CaseSensitive(name) => (name, "case_sensitive"),
+                            BindingsName::CaseInsensitive(name) => (name, "case_insensitive"),
+                        };
+                        sexp(
+                            "path_expr",
+                            [sexp("lit", [Element::string(name.as_str())]), tag(case)],
+                        )
+                    }
+                    PathComponent::Index(index) => sexp(
+                        "path_expr",
+                        [
+                            sexp("lit", [Element::integer(*index)]),
+                            tag("case_sensitive"),
+                        ],
+                    ),
+                    PathComponent::Expr(expr) => {
+                        sexp("path_expr", [expr_to_ion(expr)?, tag("case_sensitive")])
+                    }
+                    PathComponent::Wildcard => tag("path_wildcard"),
+                    PathComponent::Unpivot => tag("path_unpivot"),
+                });
+            }
+            Ok(sexp("path", elements))
+        }
+        ValueExpr::VarRef(name) | ValueExpr::GlobalVarRef(name) => Ok(id(name)),
+        ValueExpr::Parameter(crate::Parameter::Positional(index)) => {
+            Ok(sexp("parameter", [Element::integer(*index as i64 + 1)]))
+        }
+        ValueExpr::Parameter(crate::Parameter::Named(_)) => Err(unsupported("named parameters")),
+        ValueExpr::TupleExpr(tuple) => {
+            let fields = tuple
+                .attrs
+                .iter()
+                .zip(&tuple.values)
+                .map(|(attr, value)| exprs("struct_field", [attr, value]))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(sexp("struct", fields))
+        }
+        ValueExpr::ListExpr(list) => exprs("list", &list.elements),
+        ValueExpr::BagExpr(bag) => exprs("bag", &bag.elements),
+        ValueExpr::BetweenExpr(between) => exprs(
+            "between",
+            [between.value.as_ref(), &between.from, &between.to],
+        ),
+        ValueExpr::PatternMatchExpr(pattern_match) => {
+            let (pattern, escape) = match &pattern_match.pattern {
+                Pattern::Like(like) => (
+                    sexp("lit", [Element::string(like.pattern.as_str())]),
+                    if like.escape.is_empty() {
+                        null()
+                    } else {
+                        sexp("lit", [Element::string(like.escape.as_str())])
+                    },
+                ),
+                Pattern::LikeNonStringNonLiteral(like) => {
+                    (expr_to_ion(&like.pattern)?, expr_to_ion(&like.escape)?)
+                }
+            };
+            Ok(sexp(
+                "like",
+                [expr_to_ion(&pattern_match.value)?, pattern, escape],
+            ))
+        }
+        // scalar subqueries are written as other subqueries, as the Kotlin implementation coerces
+        // subqueries by the context they appear in
+        ValueExpr::SubQueryExpr(SubQueryExpr { plan, .. }) => plan_to_ion(plan),
+        ValueExpr::SimpleCase(case) => Ok(sexp(
+            "simple_case",
+            [
+                expr_to_ion(&case.expr)?,
+                case_pairs_to_ion(&case.cases)?,
+                case.default
+                    .as_deref()
+                    .map_or_else(|| Ok(null()), expr_to_ion)?,
+            ],
+        )),
+        ValueExpr::SearchedCase(case) => Ok(sexp(
+            "searched_case",
+            [
+                case_pairs_to_ion(&case.cases)?,
+                case.default
+                    .as_deref()
+                    .map_or_else(|| Ok(null()), expr_to_ion)?,
+            ],
+        )),
+        ValueExpr::IsTypeExpr(is_type) => {
+            let expr = sexp(
+                "is_type",
+                [expr_to_ion(&is_type.expr)?, type_to_ion(&is_type.is_type)],
+            );
+            Ok(if is_type.not {
+                sexp("not", [expr])
+            } else {
+                expr
+            })
+        }
+        ValueExpr::NullIfExpr(null_if) => {
+            exprs("null_if", [null_if.lhs.as_ref(), null_if.rhs.as_ref()])
+        }
+        ValueExpr::CoalesceExpr(coalesce) => exprs("coalesce", &coalesce.elements),
+        // the attributes of the arguments of a tuple union are merged as those of the parts of a
+        // `struct`, of which its tuples are `struct_field`s and its other arguments `struct_fields`
+        ValueExpr::Call(call) if call.name == CallName::TupleUnion => {
+            let mut parts = vec![];
+            for arg in &call.arguments {
+                match arg {
+                    ValueExpr::TupleExpr(tuple) => {
+                        for (attr, value) in tuple.attrs.iter().zip(&tuple.values) {
+                            parts.push(exprs("struct_field", [attr, value])?);
+                        }
+                    }
+                    arg => parts.push(exprs("struct_fields", [arg])?),
+                }
+            }
+            Ok(sexp("struct", parts))
+        }
+        ValueExpr::Call(call) => call_to_ion(&call.name, &call.arguments),
+    }
+}
+
+/// Writes `lhs IS DISTINCT FROM rhs` as the `searched_case` of the same result, which evaluates
+/// `lhs` and `rhs` once per condition: `NULL` and `MISSING` are not distinct from each other, but
+/// are from any other value, and other values are distinct unless equal.
+fn is_distinct_from_to_ion(lhs: &ValueExpr, rhs: &ValueExpr) -> Result<Element, IonPlanError> {
+    let is_null = |expr: &ValueExpr| {
+        Box::new(ValueExpr::IsTypeExpr(IsTypeExpr {
+            not: false,
+            expr: Box::new(expr.clone()),
+            is_type: Type::NullType,
+        }))
+    };
+    let boolean = |b: bool| Box::new(ValueExpr::Lit(Box::new(Value::Boolean(b))));
+    let case = SearchedCase {
+        cases: vec![
+            (
+                Box::new(ValueExpr::BinaryExpr(
+                    BinaryOp::And,
+                    is_null(lhs),
+                    is_null(rhs),
+                )),
+                boolean(false),
+            ),
+            (
+                Box::new(ValueExpr::BinaryExpr(
+                    BinaryOp::Or,
+                    is_null(lhs),
+                    is_null(rhs),
+                )),
+                boolean(true),
+            ),
+            (
+                Box::new(ValueExpr::BinaryExpr(
+                    BinaryOp::Eq,
+                    Box::new(lhs.clone()),
+                    Box::new(rhs.clone()),
+                )),
+                boolean(false),
+            ),
+        ],
+        default: Some(boolean(true)),
+    };
+    expr_to_ion(&ValueExpr::SearchedCase(case))
+}
+
+fn case_pairs_to_ion(cases: &[(Box<ValueExpr>, Box<ValueExpr>)]) -> Result<Element, IonPlanError> {
+    let pairs = cases
+        .iter()
+        .map(|(when, then)| exprs("expr_pair", [when.as_ref(), then.as_ref()]))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sexp("expr_pair_list", pairs))
+}
+
+fn type_to_ion(ty: &Type) -> Element {
+    let (tag, params) = match ty {
+        Type::NullType => ("null_type", 0),
+        Type::BooleanType => ("boolean_type", 0),
+        Type::Integer2Type => ("smallint_type", 0),
+        Type::Integer4Type => ("integer4_type", 0),
+        Type::Integer8Type => ("integer8_type", 0),
+        Type::DecimalType => ("decimal_type", 2),
+        Type::NumericType => ("numeric_type", 2),
+        Type::RealType => ("real_type", 0),
+        Type::DoublePrecisionType => ("double_precision_type", 0),
+        Type::TimestampType => ("timestamp_type", 0),
+        Type::CharacterType => ("character_type", 1),
+        Type::CharacterVaryingType => ("character_varying_type", 1),
+        Type::MissingType => ("missing_type", 0),
+        Type::StringType => ("string_type", 0),
+        Type::SymbolType => ("symbol_type", 0),
+        Type::BlobType => ("blob_type", 0),
+        Type::ClobType => ("clob_type", 0),
+        Type::DateType => ("date_type", 0),
+        Type::TimeType => ("time_type", 1),
+        Type::ZonedTimestampType => ("timestamp_with_time_zone_type", 0),
+        Type::StructType => ("struct_type", 0),
+        Type::TupleType => ("tuple_type", 0),
+        Type::ListType => ("list_type", 0),
+        Type::SexpType => ("sexp_type", 0),
+        Type::BagType => ("bag_type", 0),
+        Type::AnyType => ("any_type", 0),
+    };
+    sexp(tag, std::iter::repeat_with(null).take(params))
+}
+
+fn call_to_ion(name: &CallName, args: &[ValueExpr]) -> Result<Element, IonPlanError> {
+    let (name, mut elements) = match name {
+        CallName::Lower => ("lower", vec![]),
+        CallName::Upper => ("upper", vec![]),
+        CallName::CharLength => ("char_length", vec![]),
+        CallName::OctetLength => ("octet_length", vec![]),
+        CallName::BitLength => ("bit_length", vec![]),
+        CallName::LTrim => ("trim", vec![sexp("lit", [Element::symbol("leading")])]),
+        CallName::BTrim => ("trim", vec![sexp("lit", [Element::symbol("both")])]),
+        CallName::RTrim => ("trim", vec![sexp("lit", [Element::symbol("trailing")])]),
+        CallName::Substring => ("substring", vec![]),
+        CallName::Position => ("position", vec![]),
+        CallName::Overlay => ("overlay", vec![]),
+        CallName::Exists => ("exists", vec![]),
+        CallName::Abs => ("abs", vec![]),
+        CallName::Mod => ("mod", vec![]),
+        CallName::Cardinality => ("cardinality", vec![]),
+        CallName::UtcNow => ("utcnow", vec![]),
+        CallName::ExtractYear => ("extract", vec![sexp("lit", [Element::symbol("year")])]),
+        CallName::ExtractMonth => ("extract", vec![sexp("lit", [Element::symbol("month")])]),
+        CallName::ExtractDay => ("extract", vec![sexp("lit", [Element::symbol("day")])]),
+        CallName::ExtractHour => ("extract", vec![sexp("lit", [Element::symbol("hour")])]),
+        CallName::ExtractMinute => ("extract", vec![sexp("lit", [Element::symbol("minute")])]),
+        CallName::ExtractSecond => ("extract", vec![sexp("lit", [Element::symbol("second")])]),
+        CallName::ExtractTimezoneHour => (
+            "extract",
+            vec![sexp("lit", [Element::symbol("timezone_hour")])],
+        ),
+        CallName::ExtractTimezoneMinute => (
+            "extract",
+            vec![sexp("lit", [Element::symbol("timezone_minute")])],
+        ),
+        CallName::CollAvg(setq) => ("coll_avg", vec![coll_quantifier(setq)]),
+        CallName::CollCount(setq) => ("coll_count", vec![coll_quantifier(setq)]),
+        CallName::CollMax(setq) => ("coll_max", vec![coll_quantifier(setq)]),
+        CallName::CollMin(setq) => ("coll_min", vec![coll_quantifier(setq)]),
+        CallName::CollSum(setq) => ("coll_sum", vec![coll_quantifier(setq)]),
+        CallName::ElementAt => return Err(unsupported("element_at")),
+        CallName::TupleUnion => return Err(unsupported("<expr>.* projections")),
+        CallName::ByName(name) => {
+            let mut elements = vec![Element::symbol(name.to_lowercase())];
+            for arg in args {
+                elements.push(expr_to_ion(arg)?);
+            }
+            return Ok(sexp("call", elements));
+        }
+    };
+    elements.insert(0, Element::symbol(name));
+    for arg in args {
+        elements.push(expr_to_ion(arg)?);
+    }
+    Ok(sexp("call", elements))
+}
+
+/// The first argument of the `coll_*` functions, e.g., `'distinct'` in `coll_sum('distinct', a)`.
+fn coll_quantifier(setq: &SetQuantifier) -> Element {
+    let setq = match setq {
+        SetQuantifier::All => "all",
+        SetQuantifier::Distinct => "distinct",
+    };
+    sexp("lit", [Element::string(setq)])
+}
+
+fn value_to_ion(value: &Value) -> Result<Element, IonPlanError> {
+    let value = match value {
+        Value::Null => null(),
+        Value::Missing => return Ok(tag("missing")),
+        Value::Boolean(b) => Element::boolean(*b),
+        Value::Integer(i) => Element::integer(*i),
+        Value::Real(f) => Element::float(f.0),
+        Value::Decimal(d) => Element::decimal(Decimal::new(d.mantissa(), -(d.scale() as i64))),
+        Value::String(s) => Element::string(s.as_str()),
+        Value::Blob(b) => Element::blob(b.as_slice()),
+        Value::DateTime(_) => return Err(unsupported("date and time literals")),
+        Value::List(list) => return values("list", list.iter()),
+        Value::Bag(bag) => return values("bag", bag.iter()),
+        Value::Tuple(tuple) => {
+            let fields = tuple
+                .pairs()
+                .map(|(name, value)| {
+                    Ok(sexp(
+                        "struct_field",
+                        [sexp("lit", [Element::string(name)]), value_to_ion(value)?],
+                    ))
+                })
+                .collect::<Result<Vec<_>, IonPlanError>>()?;
+            return Ok(sexp("struct", fields));
+        }
+    };
+    Ok(sexp("lit", [value]))
+}
+
+fn values<'a>(tag: &str, values: impl Iterator<Item = &'a Value>) -> Result<Element, IonPlanError> {
+    let values = values.map(value_to_ion).collect::<Result<Vec<_>, _>>()?;
+    Ok(sexp(tag, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AggregateExpression, BagOp, CallExpr, ExprQuery, Filter, GroupBy, Join, LimitOffset,
+        OrderBy, Project, ProjectValue, Scan, SubQueryCoercion, TupleExpr,
+    };
+    use std::collections::HashMap;
+
+    fn name(name: &str) -> BindingsName {
+        BindingsName::CaseInsensitive(name.to_string())
+    }
+
+    fn var(n: &str) -> ValueExpr {
+        ValueExpr::VarRef(name(n))
+    }
+
+    fn lit(value: impl Into<Value>) -> ValueExpr {
+        ValueExpr::Lit(Box::new(value.into()))
+    }
+
+    fn scan(table: &str, as_key: &str) -> BindingsOp {
+        BindingsOp::Scan(Scan {
+            expr: ValueExpr::GlobalVarRef(name(table)),
+            as_key: as_key.to_string(),
+            at_key: None,
+        })
+    }
+
+    /// Adds the chain of `ops` ending with a `Sink` to `plan`, returning the id of the first.
+    fn chain(plan: &mut LogicalPlan<BindingsOp>, ops: Vec<BindingsOp>) -> OpId {
+        let ids: Vec<_> = ops
+            .into_iter()
+            .chain([BindingsOp::Sink])
+            .map(|op| plan.add_operator(op))
+            .collect();
+        for pair in ids.windows(2) {
+            plan.add_flow(pair[0], pair[1]);
+        }
+        ids[0]
+    }
+
+    #[track_caller]
+    fn assert_ion(plan: &LogicalPlan<BindingsOp>, expected: &str) {
+        let expected =
+            Element::read_one(format!("(plan (stmt (query {expected})) (version v0_0))")).unwrap();
+        let written = to_ion(plan).unwrap();
+        assert_eq!(written, expected);
+        // the plan is read back from its Ion text as written
+        assert_eq!(Element::read_one(written.to_string()).unwrap(), written);
+    }
+
+    #[test]
+    fn select() {
+        let mut plan = LogicalPlan::new();
+        chain(
+            &mut plan,
+            vec![
+                scan("t", "x"),
+                BindingsOp::Filter(Filter {
+                    expr: ValueExpr::BinaryExpr(
+                        BinaryOp::Gt,
+                        Box::new(ValueExpr::Path(
+                            Box::new(var("x")),
+                            vec![PathComponent::Key(name("a"))],
+                        )),
+                        Box::new(ValueExpr::Parameter(crate::Parameter::Positional(0))),
+                    ),
+                }),
+                BindingsOp::OrderBy(OrderBy {
+                    specs: vec![SortSpec {
+                        expr: var("x"),
+                        order: SortSpecOrder::Desc,
+                        null_order: SortSpecNullOrder::Last,
+                    }],
+                }),
+                BindingsOp::LimitOffset(LimitOffset {
+                    limit: Some(lit(10)),
+                    offset: Some(lit(5)),
+                }),
+                BindingsOp::Project(Project {
+                    exprs: vec![("b".to_string(), lit(Value::Null))],
+                }),
+            ],
+        );
+        assert_ion(
+            &plan,
+            "(bindings_to_values (struct (struct_field (lit \"b\") (lit null))) \
+                (limit (lit 10) (offset (lit 5) \
+                    (sort (filter (gt (path (id x (case_insensitive) (unqualified)) \
+                                (path_expr (lit \"a\") (case_insensitive))) \
+                            (parameter 1)) \
+                        (scan (id t (case_insensitive) (unqualified)) (var_decl x) null null)) \
+                    (sort_spec (id x (case_insensitive) (unqualified)) (desc) (nulls_last))))))",
+        );
+    }
+
+    #[test]
+    fn joins_and_aggregates() {
+        let mut plan = LogicalPlan::new();
+        let l = plan.add_operator(scan("l", "l"));
+        let r = plan.add_operator(scan("r", "r"));
+        let join = chain(
+            &mut plan,
+            vec![
+                BindingsOp::Join(Join {
+                    kind: JoinKind::Left,
+                    left: Box::new(scan("l", "l")),
+                    right: Box::new(scan("r", "r")),
+                    on: None,
+                }),
+                BindingsOp::GroupBy(GroupBy {
+                    strategy: GroupingStrategy::GroupFull,
+                    exprs: HashMap::from([("k".to_string(), var("l"))]),
+                    aggregate_exprs: vec![AggregateExpression {
+                        name: "n".to_string(),
+                        expr: var("r"),
+                        func: AggFunc::AggCount,
+                        setq: SetQuantifier::Distinct,
+                    }],
+                    group_as_alias: None,
+                }),
+                BindingsOp::ProjectValue(ProjectValue { expr: var("n") }),
+            ],
+        );
+        plan.add_flow_with_branch_num(r, join, 1);
+        plan.add_flow_with_branch_num(l, join, 0);
+        assert_ion(
+            &plan,
+            "(bindings_to_values (id n (case_insensitive) (unqualified)) \
+                (aggregate \
+                    (join (left) \
+                        (scan (id l (case_insensitive) (unqualified)) (var_decl l) null null) \
+                        (scan (id r (case_insensitive) (unqualified)) (var_decl r) null null) \
+                        null) \
+                    (group_full) \
+                    (group_key_list (group_key (id l (case_insensitive) (unqualified)) (var_decl k))) \
+                    (aggregate_function_list \
+                        (aggregate_function (distinct) count \
+                            (id r (case_insensitive) (unqualified)) (var_decl n)))))",
+        );
+    }
+
+    #[test]
+    fn bag_ops_and_subqueries() {
+        let mut subquery = LogicalPlan::new();
+        chain(
+            &mut subquery,
+            vec![
+                scan("t", "t"),
+                BindingsOp::ProjectValue(ProjectValue { expr: var("t") }),
+            ],
+        );
+
+        let mut plan = LogicalPlan::new();
+        let lhs = plan.add_operator(BindingsOp::ExprQuery(ExprQuery {
+            expr: ValueExpr::BagExpr(crate::BagExpr {
+                elements: vec![lit(1), lit(partiql_value::List::from(vec![Value::Missing]))],
+            }),
+        }));
+        let rhs = plan.add_operator(BindingsOp::ExprQuery(ExprQuery {
+            expr: ValueExpr::SubQueryExpr(SubQueryExpr {
+                plan: subquery,
+                coercion: SubQueryCoercion::Collection,
+            }),
+        }));
+        let union = chain(
+            &mut plan,
+            vec![BindingsOp::BagOp(BagOp {
+                bag_op: BagOperator::OuterUnion,
+                setq: SetQuantifier::All,
+            })],
+        );
+        plan.add_flow_with_branch_num(lhs, union, 0);
+        plan.add_flow_with_branch_num(rhs, union, 1);
+        assert_ion(
+            &plan,
+            "(bag_op (outer_union) (all) \
+                (bag (lit 1) (list (missing))) \
+                (bindings_to_values (id t (case_insensitive) (unqualified)) \
+                    (scan (id t (case_insensitive) (unqualified)) (var_decl t) null null)))",
+        );
+    }
+
+    #[test]
+    fn star_projections() {
+        let mut plan = LogicalPlan::new();
+        let l = plan.add_operator(scan("l", "l"));
+        let r = plan.add_operator(scan("r", "r"));
+        let join = chain(
+            &mut plan,
+            vec![
+                BindingsOp::Join(Join {
+                    kind: JoinKind::Inner,
+                    left: Box::new(scan("l", "l")),
+                    right: Box::new(scan("r", "r")),
+                    on: None,
+                }),
+                BindingsOp::Filter(Filter { expr: lit(true) }),
+                BindingsOp::ProjectAll,
+            ],
+        );
+        plan.add_flow_with_branch_num(l, join, 0);
+        plan.add_flow_with_branch_num(r, join, 1);
+        assert_ion(
+            &plan,
+            "(bindings_to_values \
+                (struct (struct_fields (id l (case_sensitive) (unqualified))) \
+                    (struct_fields (id r (case_sensitive) (unqualified)))) \
+                (filter (lit true) \
+                    (join (inner) \
+                        (scan (id l (case_insensitive) (unqualified)) (var_decl l) null null) \
+                        (scan (id r (case_insensitive) (unqualified)) (var_decl r) null null) \
+                        null)))",
+        );
+
+        // `SELECT t.*, t.a AS b FROM t`
+        let mut plan = LogicalPlan::new();
+        chain(
+            &mut plan,
+            vec![
+                scan("t", "t"),
+                BindingsOp::ProjectValue(ProjectValue {
+                    expr: ValueExpr::Call(CallExpr {
+                        name: CallName::TupleUnion,
+                        arguments: vec![
+                            var("t"),
+                            ValueExpr::TupleExpr(TupleExpr {
+                                attrs: vec![lit("b")],
+                                values: vec![ValueExpr::Path(
+                                    Box::new(var("t")),
+                                    vec![PathComponent::Key(name("a"))],
+                                )],
+                            }),
+                        ],
+                    }),
+                }),
+            ],
+        );
+        assert_ion(
+            &plan,
+            "(bindings_to_values \
+                (struct (struct_fields (id t (case_insensitive) (unqualified))) \
+                    (struct_field (lit \"b\") \
+                        (path (id t (case_insensitive) (unqualified)) \
+                            (path_expr (lit \"a\") (case_insensitive))))) \
+                (scan (id t (case_insensitive) (unqualified)) (var_decl t) null null))",
+        );
+    }
+
+    #[test]
+    fn is_distinct_from() {
+        let distinct = ValueExpr::BinaryExpr(
+            BinaryOp::IsDistinctFrom,
+            Box::new(var("x")),
+            Box::new(lit(1)),
+        );
+        let mut plan = LogicalPlan::new();
+        chain(
+            &mut plan,
+            vec![
+                scan("t", "x"),
+                BindingsOp::Filter(Filter {
+                    expr: ValueExpr::UnExpr(UnaryOp::Not, Box::new(distinct)),
+                }),
+                BindingsOp::ProjectValue(ProjectValue { expr: var("x") }),
+            ],
+        );
+        assert_ion(
+            &plan,
+            "(bindings_to_values (id x (case_insensitive) (unqualified)) \
+                (filter \
+                    (not (searched_case \
+                        (expr_pair_list \
+                            (expr_pair \
+                                (and (is_type (id x (case_insensitive) (unqualified)) (null_type)) \
+                                    (is_type (lit 1) (null_type))) \
+                                (lit false)) \
+                            (expr_pair \
+                                (or (is_type (id x (case_insensitive) (unqualified)) (null_type)) \
+                                    (is_type (lit 1) (null_type))) \
+                                (lit true)) \
+                            (expr_pair (eq (id x (case_insensitive) (unqualified)) (lit 1)) \
+                                (lit false))) \
+                        (lit true))) \
+                    (scan (id t (case_insensitive) (unqualified)) (var_decl x) null null)))",
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut plan = LogicalPlan::new();
+        chain(&mut plan, vec![scan("t", "t"), BindingsOp::Distinct]);
+        assert_eq!(to_ion(&plan), Err(unsupported("SELECT DISTINCT")));
+
+        let mut plan = LogicalPlan::new();
+        plan.add_operator(BindingsOp::Sink);
+        plan.add_operator(BindingsOp::Sink);
+        assert!(matches!(to_ion(&plan), Err(IonPlanError::Invalid(_))));
+    }
+}
@@ -9,6 +9,23 @@
 //! Plan graph nodes are called _operators_ and edges are called _flows_ re-instating the fact that
 //! the plan captures data flows for a given PartiQL statement.
 //!
+//! # Panics
+//!
+//! Building a plan never panics; a malformed plan (e.g., with a flow to an operator of another
+//! plan) is reported when the plan is compiled. The `clippy::panic`, `clippy::todo`,
+//! `clippy::unimplemented`, and `clippy::unreachable` lints are denied in this crate to uphold
+//! this.
+
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::panic,
+        clippy::todo,
+        clippy::unimplemented,
+        clippy::unreachable
+    )
+)]
+
 /// # Examples
 /// ```
 /// use partiql_logical::{BinaryOp, BindingsOp, LogicalPlan, PathComponent, ProjectValue, Scan, ValueExpr};
@@ -49,22 +66,42 @@
 /// assert_eq!(2, p.flows().len());
 /// ```
 use partiql_value::{BindingsName, Value};
+use petgraph::algo::toposort;
+use petgraph::prelude::{Direction, NodeIndex, StableGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
 use std::collections::HashMap;
-use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+mod builder;
+mod display;
+#[cfg(feature = "ion")]
+pub mod ion;
+mod statistics;
+mod validate;
+pub mod visit;
+
+pub use builder::PlanBuilder;
+pub use statistics::Statistics;
+pub use validate::{ValidationError, ValidationErrorKind};
+
 /// Represents a PartiQL logical plan.
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
+///
+/// The operators of a plan keep their [`OpId`]s as other operators are removed from it; the ids of
+/// removed operators may be reused by operators added later.
+#[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LogicalPlan<T>
 where
     T: Default,
 {
-    nodes: Vec<T>,
-    /// Third argument indicates the branch number into the outgoing node.
-    edges: Vec<(OpId, OpId, u8)>,
+    /// Edge weights are the branch numbers of flows into their destination operators.
+    graph: StableGraph<T, u8>,
+    /// Flows from or to operators not in the plan, kept to be reported when the plan is compiled
+    /// unless the operators are added to the plan before then.
+    dangling: Vec<(OpId, OpId, u8)>,
 }
 
 impl<T> LogicalPlan<T>
@@ -76,28 +113,56 @@ where
         Self::default()
     }
 
-    /// Adds a new operator to the plan.
+    /// Adds a new operator to the plan, along with the flows previously added from or to it.
     pub fn add_operator(&mut self, op: T) -> OpId {
-        self.nodes.push(op);
-        OpId(self.operator_count())
+        let id = OpId::from(self.graph.add_node(op));
+        for (src, dst, branch_num) in std::mem::take(&mut self.dangling) {
+            self.add_flow_with_branch_num(src, dst, branch_num);
+        }
+        id
+    }
+
+    /// Removes an operator and the flows from and to it from the plan, returning the operator if
+    /// it was in the plan.
+    pub fn remove_operator(&mut self, id: OpId) -> Option<T> {
+        let op = self.graph.remove_node(self.node_index(id)?)?;
+        self.dangling
+            .retain(|&(src, dst, _)| src != id && dst != id);
+        Some(op)
     }
 
     /// Adds a data flow to the plan.
+    ///
+    /// A flow from or to an operator that is not in the plan (e.g., the operator of another plan)
+    /// is an error reported when the plan is compiled, unless the operator is added to the plan
+    /// before then.
     #[inline]
     pub fn add_flow(&mut self, src: OpId, dst: OpId) {
-        assert!(src.index() <= self.operator_count());
-        assert!(dst.index() <= self.operator_count());
-
-        self.edges.push((src, dst, 0));
+        self.add_flow_with_branch_num(src, dst, 0);
     }
 
     /// Adds a data flow with a branch number.
-    #[inline]
     pub fn add_flow_with_branch_num(&mut self, src: OpId, dst: OpId, branch_num: u8) {
-        assert!(src.index() <= self.operator_count());
-        assert!(dst.index() <= self.operator_count());
+        match (self.node_index(src), self.node_index(dst)) {
+            (Some(s), Some(d)) => {
+                self.graph.add_edge(s, d, branch_num);
+            }
+            _ => self.dangling.push((src, dst, branch_num)),
+        }
+    }
 
-        self.edges.push((src, dst, branch_num));
+    /// Removes the data flows from `src` to `dst` from the plan, returning whether there were any.
+    pub fn remove_flows(&mut self, src: OpId, dst: OpId) -> bool {
+        let dangling = self.dangling.len();
+        self.dangling.retain(|&(s, d, _)| (s, d) != (src, dst));
+        let mut removed = dangling != self.dangling.len();
+        if let (Some(s), Some(d)) = (self.node_index(src), self.node_index(dst)) {
+            while let Some(edge) = self.graph.find_edge(s, d) {
+                self.graph.remove_edge(edge);
+                removed = true;
+            }
+        }
+        removed
     }
 
     /// Extends the logical plan with the given data flows.
@@ -129,33 +194,139 @@ where
     /// Returns the number of operators in the plan.
     #[inline]
     pub fn operator_count(&self) -> usize {
-        self.nodes.len()
+        self.graph.node_count()
     }
 
-    /// Returns the operators of the plan.
-    pub fn operators(&self) -> &Vec<T> {
-        &self.nodes
+    /// Returns the operators of the plan, ordered by their `OpId`s.
+    pub fn operators(&self) -> Vec<&T> {
+        self.graph.node_weights().collect()
     }
 
-    /// Returns the operators of the plan with their `OpId`.
+    /// Returns the operators of the plan with their `OpId`, ordered by the latter.
     pub fn operators_by_id(&self) -> impl Iterator<Item = (OpId, &T)> {
-        self.nodes.iter().enumerate().map(|(i, n)| (OpId(i + 1), n))
+        self.graph
+            .node_indices()
+            .map(|idx| (OpId::from(idx), &self.graph[idx]))
+    }
+
+    /// Returns the data flows of the plan, including those from or to operators not in the plan.
+    pub fn flows(&self) -> Vec<(OpId, OpId, u8)> {
+        self.graph
+            .edge_references()
+            .map(|e| (OpId::from(e.source()), OpId::from(e.target()), *e.weight()))
+            .chain(self.dangling.iter().copied())
+            .collect()
     }
 
-    /// Returns the data flows of the plan.
-    pub fn flows(&self) -> &Vec<(OpId, OpId, u8)> {
-        &self.edges
+    /// Returns whether the operator `id` is in the plan.
+    pub fn contains_operator(&self, id: OpId) -> bool {
+        self.node_index(id).is_some()
     }
 
     pub fn operator(&self, id: OpId) -> Option<&T> {
-        self.nodes.get(id.0 - 1)
+        self.graph.node_weight(id.node_index()?)
     }
 
     pub fn operator_as_mut(&mut self, id: OpId) -> Option<&mut T> {
-        self.nodes.get_mut(id.0 - 1)
+        self.graph.node_weight_mut(id.node_index()?)
+    }
+
+    /// Returns the operators with flows into the operator `id`.
+    pub fn predecessors(&self, id: OpId) -> Vec<OpId> {
+        self.neighbors(id, Direction::Incoming)
+    }
+
+    /// Returns the operators with flows from the operator `id`.
+    pub fn successors(&self, id: OpId) -> Vec<OpId> {
+        self.neighbors(id, Direction::Outgoing)
     }
 
-    // TODO add DAG validation method.
+    /// Returns the operators with flows into the operator `id`, ordered by the branch numbers of
+    /// their flows, e.g., the left and then the right input of a join.
+    pub fn inputs(&self, id: OpId) -> Vec<OpId> {
+        let Some(idx) = self.node_index(id) else {
+            return vec![];
+        };
+        let mut inputs: Vec<(u8, OpId)> = self
+            .graph
+            .edges_directed(idx, Direction::Incoming)
+            .map(|e| (*e.weight(), OpId::from(e.source())))
+            .collect();
+        inputs.sort_by_key(|&(branch_num, src)| (branch_num, src.index()));
+        inputs.into_iter().map(|(_, src)| src).collect()
+    }
+
+    fn neighbors(&self, id: OpId, dir: Direction) -> Vec<OpId> {
+        let Some(idx) = self.node_index(id) else {
+            return vec![];
+        };
+        let mut neighbors: Vec<_> = self
+            .graph
+            .neighbors_directed(idx, dir)
+            .map(OpId::from)
+            .collect();
+        neighbors.sort_by_key(OpId::index);
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Returns the operators of the plan in an order where each precedes the operators its flows
+    /// lead to, or `None` if the flows of the plan form a cycle.
+    pub fn topological_order(&self) -> Option<Vec<OpId>> {
+        toposort(&self.graph, None)
+            .ok()
+            .map(|order| order.into_iter().map(OpId::from).collect())
+    }
+
+    /// Returns a plan of the operators `f` maps the operators of this plan to, with the same
+    /// [`OpId`]s and flows.
+    pub fn map<U, F>(&self, mut f: F) -> LogicalPlan<U>
+    where
+        U: Default,
+        F: FnMut(OpId, &T) -> U,
+    {
+        LogicalPlan {
+            graph: self.graph.map(
+                |idx, op| f(OpId::from(idx), op),
+                |_, branch_num| *branch_num,
+            ),
+            dangling: self.dangling.clone(),
+        }
+    }
+
+    /// Returns the index of the operator `id` in the graph, if it is in the plan.
+    fn node_index(&self, id: OpId) -> Option<NodeIndex> {
+        id.node_index()
+            .filter(|&idx| self.graph.node_weight(idx).is_some())
+    }
+}
+
+/// Plans are equal if they have equal operators with the same [`OpId`]s and the same flows.
+impl<T> PartialEq for LogicalPlan<T>
+where
+    T: Default + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.operators_by_id().eq(other.operators_by_id()) && self.flows() == other.flows()
+    }
+}
+
+impl<T> Eq for LogicalPlan<T> where T: Default + Eq {}
+
+/// Plans are hashed by their operators with their [`OpId`]s and their flows, as they are compared,
+/// so that equal plans (e.g., those of the same query) can share, e.g., cache entries.
+impl<T> Hash for LogicalPlan<T>
+where
+    T: Default + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.operator_count().hash(state);
+        for (id, op) in self.operators_by_id() {
+            id.hash(state);
+            op.hash(state);
+        }
+        self.flows().hash(state);
+    }
 }
 
 /// Represents an operator identifier in a [`LogicalPlan`]
@@ -168,33 +339,28 @@ impl OpId {
     pub fn index(&self) -> usize {
         self.0
     }
+
+    /// The index of the graph node of the operator; `OpId`s count from 1.
+    fn node_index(&self) -> Option<NodeIndex> {
+        Some(NodeIndex::new(self.0.checked_sub(1)?))
+    }
 }
 
-impl<T> Display for LogicalPlan<T>
-where
-    T: Default + Debug,
-{
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let flows = self.flows();
-        writeln!(f, "LogicalPlan")?;
-        writeln!(f, "---")?;
-        for (s, d, _w) in flows {
-            let src_node = self.operator(*s).expect("Unable to get the src operator");
-            let dst_node = self.operator(*d).expect("Unable to get the dst operator");
-            writeln!(f, ">>> [{src_node:?}] -> [{dst_node:?}]")?;
-        }
-        writeln!(f)
+impl From<NodeIndex> for OpId {
+    fn from(idx: NodeIndex) -> Self {
+        OpId(idx.index() + 1)
     }
 }
 
 /// Represents PartiQL binding operators; A `BindingOp` is an operator that operates on
 /// binding tuples as specified by [PartiQL Specification 2019](https://partiql.org/assets/PartiQL-Specification.pdf).
-#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BindingsOp {
     Scan(Scan),
     Pivot(Pivot),
     Unpivot(Unpivot),
+    Let(Let),
     Filter(Filter),
     OrderBy(OrderBy),
     LimitOffset(LimitOffset),
@@ -207,12 +373,32 @@ pub enum BindingsOp {
     Distinct,
     GroupBy(GroupBy),
     Having(Having),
+    Window(Window),
     #[default]
     Sink,
 }
 
+impl BindingsOp {
+    /// The variables the operand of a join binds, e.g., the `AS` and `AT` variables of a scan, or
+    /// those of the operands of a join.
+    pub fn bound_variables(&self) -> Vec<String> {
+        match self {
+            BindingsOp::Scan(Scan { as_key, at_key, .. })
+            | BindingsOp::Unpivot(Unpivot { as_key, at_key, .. }) => {
+                [as_key].into_iter().chain(at_key).cloned().collect()
+            }
+            BindingsOp::Join(join) => {
+                let mut bound = join.left.bound_variables();
+                bound.extend(join.right.bound_variables());
+                bound
+            }
+            _ => vec![],
+        }
+    }
+}
+
 /// [`Scan`] bridges from [`ValueExpr`]s to [`BindingsOp`]s.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Scan {
     pub expr: ValueExpr,
@@ -224,7 +410,7 @@ pub struct Scan {
 /// `PIVOT sp.price AT sp."symbol" FROM todaysStockPrices sp`. For `Pivot` operational semantics,
 /// see section `6.2` of
 /// [PartiQL Specification — August 1, 2019](https://partiql.org/assets/PartiQL-Specification.pdf).
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Pivot {
     pub key: ValueExpr,
@@ -232,7 +418,7 @@ pub struct Pivot {
 }
 
 /// [`Unpivot`] bridges from [`ValueExpr`]s to [`BindingsOp`]s.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Unpivot {
     pub expr: ValueExpr,
@@ -240,35 +426,44 @@ pub struct Unpivot {
     pub at_key: Option<String>,
 }
 
+/// [`Let`] represents a `LET` clause which extends each binding tuple with the given bindings, e.g.
+/// `LET t.a + 1 AS b` in `SELECT b FROM t LET t.a + 1 AS b`. Bindings are evaluated in order, so a
+/// binding may refer to those preceding it.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Let {
+    pub bindings: Vec<(String, ValueExpr)>,
+}
+
 /// [`Filter`] represents a filter operator, e.g. `WHERE a = 10` in `SELECT a FROM t WHERE a = 10`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Filter {
     pub expr: ValueExpr,
 }
 
 /// [`Having`] represents the having operator, e.g. `HAVING a = 10` in `SELECT b FROM t GROUP BY a, b HAVING a = 10`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Having {
     pub expr: ValueExpr,
 }
 
 /// [`OrderBy`] represents a sort operatyion, e.g. `ORDER BY a DESC NULLS LAST` in `SELECT a FROM t ORDER BY a DESC NULLS LAST`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OrderBy {
     pub specs: Vec<SortSpec>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SortSpecOrder {
     Asc,
     Desc,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SortSpecNullOrder {
     First,
@@ -276,7 +471,7 @@ pub enum SortSpecNullOrder {
 }
 
 /// Represents a PartiQL sort specification.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SortSpec {
     pub expr: ValueExpr,
@@ -284,8 +479,48 @@ pub struct SortSpec {
     pub null_order: SortSpecNullOrder,
 }
 
+/// [`Window`] represents a window operator, which binds `name` in each binding tuple to the result
+/// of `func` over the tuples of its partition, e.g.,
+/// `ROW_NUMBER() OVER (PARTITION BY a ORDER BY b)` in
+/// `SELECT a, ROW_NUMBER() OVER (PARTITION BY a ORDER BY b) AS n FROM t`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Window {
+    pub partition_by: Vec<ValueExpr>,
+    pub order_by: Vec<SortSpec>,
+    pub name: String,
+    pub func: WindowFunction,
+}
+
+/// Represents a window function.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WindowFunction {
+    /// The position of the tuple in its partition, starting from 1.
+    RowNumber,
+    /// The position in its partition of the first tuple ordered the same as the tuple.
+    Rank,
+    /// The number of distinct orderings in its partition up to that of the tuple.
+    DenseRank,
+    /// The value of `expr` for the tuple `offset` positions before the tuple in its partition, or
+    /// `default` if there is none.
+    Lag(WindowOffset),
+    /// The value of `expr` for the tuple `offset` positions after the tuple in its partition, or
+    /// `default` if there is none.
+    Lead(WindowOffset),
+}
+
+/// The arguments of the [`WindowFunction::Lag`] & [`WindowFunction::Lead`] window functions.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WindowOffset {
+    pub expr: Box<ValueExpr>,
+    pub offset: Box<ValueExpr>,
+    pub default: Box<ValueExpr>,
+}
+
 /// [`LimitOffset`] represents a possible limit and/or offset operator, e.g. `LIMIT 10 OFFSET 5` in `SELECT a FROM t LIMIT 10 OFFSET 5`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LimitOffset {
     pub limit: Option<ValueExpr>,
@@ -293,7 +528,7 @@ pub struct LimitOffset {
 }
 
 /// [`BagOp`] represents a bag operator, e.g. `UNION ALL` in `SELECT a, b FROM foo UNION ALL SELECT c, d FROM bar`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BagOp {
     pub bag_op: BagOperator,
@@ -301,7 +536,7 @@ pub struct BagOp {
 }
 
 /// Represents the supported bag operator types.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BagOperator {
     Union,
@@ -314,7 +549,7 @@ pub enum BagOperator {
 
 /// ['Join`] represents a join operator, e.g. implicit `CROSS JOIN` specified by comma in `FROM`
 /// clause in `SELECT t1.a, t2.b FROM tbl1 AS t1, tbl2 AS t2`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Join {
     pub kind: JoinKind,
@@ -324,7 +559,7 @@ pub struct Join {
 }
 
 /// Represents join types.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum JoinKind {
     Inner,
@@ -335,7 +570,7 @@ pub enum JoinKind {
 }
 
 /// An SQL aggregation function call with its arguments
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AggregateExpression {
     pub name: String,
@@ -345,7 +580,7 @@ pub struct AggregateExpression {
 }
 
 /// SQL aggregate function
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AggFunc {
     // TODO: modeling of COUNT(*)
@@ -371,8 +606,20 @@ pub struct GroupBy {
     pub group_as_alias: Option<String>,
 }
 
+impl Hash for GroupBy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.strategy.hash(state);
+        // `exprs` is unordered, so its keys are sorted
+        let mut exprs: Vec<_> = self.exprs.iter().collect();
+        exprs.sort_by_key(|(name, _)| *name);
+        exprs.hash(state);
+        self.aggregate_exprs.hash(state);
+        self.group_as_alias.hash(state);
+    }
+}
+
 /// Grouping qualifier: ALL or PARTIAL
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GroupingStrategy {
     GroupFull,
@@ -380,7 +627,7 @@ pub enum GroupingStrategy {
 }
 
 /// Represents a projection, e.g. `SELECT a` in `SELECT a FROM t`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Project {
     pub exprs: Vec<(String, ValueExpr)>,
@@ -388,14 +635,14 @@ pub struct Project {
 
 /// Represents a value projection (SELECT VALUE) e.g. `SELECT VALUE t.a * 2` in
 ///`SELECT VALUE t.a * 2 IN tbl AS t`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ProjectValue {
     pub expr: ValueExpr,
 }
 
 /// Represents an expression query e.g. `a * 2` in `a * 2` or an expression like `2+2`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ExprQuery {
     pub expr: ValueExpr,
@@ -403,7 +650,7 @@ pub struct ExprQuery {
 
 /// Represents a PartiQL value expression. Evaluation of a [`ValueExpr`] leads to a PartiQL value as
 /// specified by [PartiQL Specification 2019](https://partiql.org/assets/PartiQL-Specification.pdf).
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ValueExpr {
     UnExpr(UnaryOp, Box<ValueExpr>),
@@ -412,6 +659,10 @@ pub enum ValueExpr {
     DynamicLookup(Box<Vec<ValueExpr>>),
     Path(Box<ValueExpr>, Vec<PathComponent>),
     VarRef(BindingsName),
+    /// A variable reference which is resolved against the global environment before the local
+    /// one, e.g., `t` in `FROM t`.
+    GlobalVarRef(BindingsName),
+    Parameter(Parameter),
     TupleExpr(TupleExpr),
     ListExpr(ListExpr),
     BagExpr(BagExpr),
@@ -426,9 +677,23 @@ pub enum ValueExpr {
     Call(CallExpr),
 }
 
+impl ValueExpr {
+    /// The conditions of the conjunction `self`, e.g., `a`, `b`, and `c` of `a AND (b AND c)`.
+    pub fn conjuncts(&self) -> Vec<&ValueExpr> {
+        match self {
+            ValueExpr::BinaryExpr(BinaryOp::And, lhs, rhs) => {
+                let mut conjuncts = lhs.conjuncts();
+                conjuncts.extend(rhs.conjuncts());
+                conjuncts
+            }
+            _ => vec![self],
+        }
+    }
+}
+
 // TODO we should replace this enum with some identifier that can be looked up in a symtab/funcregistry?
 /// Represents logical plan's unary operators.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UnaryOp {
     Pos,
@@ -438,7 +703,7 @@ pub enum UnaryOp {
 
 // TODO we should replace this enum with some identifier that can be looked up in a symtab/funcregistry?
 /// Represents logical plan's binary operators.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BinaryOp {
     And,
@@ -450,6 +715,8 @@ pub enum BinaryOp {
     Gteq,
     Lt,
     Lteq,
+    /// Null-safe inequality, i.e., `IS DISTINCT FROM`.
+    IsDistinctFrom,
 
     // Arithmetic ops
     Add,
@@ -462,7 +729,17 @@ pub enum BinaryOp {
     In,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// Represents a parameter of a query, whose value is bound when the plan is evaluated.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Parameter {
+    /// E.g. the second `?` in `a = ? OR b = ?`, with position `1`
+    Positional(usize),
+    /// E.g. `:name` in `a = :name`
+    Named(BindingsName),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Represents a path component in a plan.
 pub enum PathComponent {
@@ -470,12 +747,17 @@ pub enum PathComponent {
     Key(BindingsName),
     /// E.g. 4 in `a[4]`
     Index(i64),
-    KeyExpr(Box<ValueExpr>),
-    IndexExpr(Box<ValueExpr>),
+    /// E.g. `[b || c]` in `a[b || c]`; navigates by index if the expression evaluates to an integer
+    /// or by (case-sensitive) key if it evaluates to a string.
+    Expr(Box<ValueExpr>),
+    /// E.g. `[*]` in `a[*]`
+    Wildcard,
+    /// E.g. `.*` in `a.*`
+    Unpivot,
 }
 
 /// Represents a PartiQL tuple expression, e.g: `{ a.b: a.c * 2, 'count': a.c + 10}`.
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TupleExpr {
     pub attrs: Vec<ValueExpr>,
@@ -490,7 +772,7 @@ impl TupleExpr {
 }
 
 /// Represents a PartiQL list expression, e.g. `[a.c * 2, 5]`.
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ListExpr {
     pub elements: Vec<ValueExpr>,
@@ -504,7 +786,7 @@ impl ListExpr {
 }
 
 /// Represents a PartiQL bag expression, e.g. `<<a.c * 2, 5>>`.
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BagExpr {
     pub elements: Vec<ValueExpr>,
@@ -518,7 +800,7 @@ impl BagExpr {
 }
 
 /// Represents a PartiQL `BETWEEN` expression, e.g. `BETWEEN 500 AND 600`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BetweenExpr {
     pub value: Box<ValueExpr>,
@@ -527,14 +809,14 @@ pub struct BetweenExpr {
 }
 
 /// Represents a PartiQL Pattern Match expression, e.g. `'foo' LIKE 'foo'`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PatternMatchExpr {
     pub value: Box<ValueExpr>,
     pub pattern: Pattern,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Pattern {
     Like(LikeMatch), // TODO other e.g., SIMILAR_TO, or regex match
@@ -543,7 +825,7 @@ pub enum Pattern {
 
 /// Represents a LIKE expression where both the `pattern` and `escape` are string literals,
 /// e.g. `'foo%' ESCAPE '/'`
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LikeMatch {
     pub pattern: String,
@@ -552,7 +834,7 @@ pub struct LikeMatch {
 
 /// Represents a LIKE expression where one of `pattern` and `escape` is not a string literal,
 /// e.g. `some_pattern ESCAPE '/'`
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LikeNonStringNonLiteralMatch {
     pub pattern: Box<ValueExpr>,
@@ -561,15 +843,40 @@ pub struct LikeNonStringNonLiteralMatch {
 
 /// Represents a sub-query expression, e.g. `SELECT v.a*2 AS u FROM t AS v` in
 /// `SELECT t.a, s FROM data AS t, (SELECT v.a*2 AS u FROM t AS v) AS s`
-#[derive(Debug, Clone, Eq, PartialEq)]
+///
+/// `EXISTS` and `IN` subqueries are [`SubQueryCoercion::Collection`] subqueries as the argument of
+/// a [`CallName::Exists`] call and as the right operand of a [`BinaryOp::In`] expression,
+/// respectively. As for SQL's `IN` subqueries, an `IN` subquery `SELECT`ing a single attribute is
+/// lowered to `SELECT VALUE` its expression, e.g., `SELECT u.a FROM u` in
+/// `t.a IN (SELECT u.a FROM u)`.
+///
+/// A subquery is correlated if its plan references variables bound by the queries enclosing it,
+/// e.g., `c` in `SELECT c.name FROM customers AS c WHERE EXISTS (SELECT * FROM orders AS o WHERE
+/// o.customer = c.id)`. Such references are [`ValueExpr::VarRef`]s like any other, which are
+/// resolved against the binding tuple of the enclosing expression each time the subquery is
+/// evaluated; the variables of the enclosing queries are thus captured by name rather than listed
+/// in the plan.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SubQueryExpr {
     pub plan: LogicalPlan<BindingsOp>,
+    pub coercion: SubQueryCoercion,
+}
+
+/// Represents how the result of a [`SubQueryExpr`] is used by its enclosing expression.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SubQueryCoercion {
+    /// The result is used as is, e.g. `SELECT ...` in `x IN (SELECT ...)`.
+    Collection,
+    /// The result is coerced to the single value of its single tuple, as for SQL's scalar
+    /// subqueries, e.g. `SELECT MAX(a) ...` in `x > (SELECT MAX(a) ...)`.
+    Scalar,
 }
 
 /// Represents a PartiQL's simple case expressions,
 /// e.g.`CASE <expr> [ WHEN <expr> THEN <expr> ]... [ ELSE <expr> ] END`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SimpleCase {
     pub expr: Box<ValueExpr>,
@@ -579,7 +886,7 @@ pub struct SimpleCase {
 
 /// Represents a PartiQL's searched case expressions,
 /// e.g.`CASE [ WHEN <expr> THEN <expr> ]... [ ELSE <expr> ] END`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SearchedCase {
     pub cases: Vec<(Box<ValueExpr>, Box<ValueExpr>)>,
@@ -587,7 +894,7 @@ pub struct SearchedCase {
 }
 
 /// Represents an `IS` expression, e.g. `IS TRUE`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct IsTypeExpr {
     pub not: bool,
@@ -596,7 +903,7 @@ pub struct IsTypeExpr {
 }
 
 /// Represents a PartiQL Type.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Type {
     NullType,
@@ -629,7 +936,7 @@ pub enum Type {
 }
 
 /// Represents a `NULLIF` expression, e.g. `NULLIF(v1, v2)` in `SELECT NULLIF(v1, v2) FROM data`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NullIfExpr {
     pub lhs: Box<ValueExpr>,
@@ -638,14 +945,14 @@ pub struct NullIfExpr {
 
 /// Represents a `COALESCE` expression, e.g.
 /// `COALESCE(NULL, 10)` in `SELECT COALESCE(NULL, 10) FROM data`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CoalesceExpr {
     pub elements: Vec<ValueExpr>,
 }
 
 /// Represents a `CALL` expression (i.e., a function call), e.g. `LOWER("ALL CAPS")`.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CallExpr {
     pub name: CallName,
@@ -653,7 +960,7 @@ pub struct CallExpr {
 }
 
 /// Represents a known function.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CallName {
     Lower,
@@ -671,6 +978,8 @@ pub enum CallName {
     Abs,
     Mod,
     Cardinality,
+    ElementAt,
+    UtcNow,
     ExtractYear,
     ExtractMonth,
     ExtractDay,
@@ -684,11 +993,14 @@ pub enum CallName {
     CollMax(SetQuantifier),
     CollMin(SetQuantifier),
     CollSum(SetQuantifier),
+    /// The union of the attributes of tuples, e.g., of the expansion of `t.*` in `SELECT t.*`;
+    /// a non-tuple argument is an attribute named after its (1-based) position, e.g., `_1`.
+    TupleUnion,
     ByName(String),
 }
 
 /// Indicates if a set should be reduced to its distinct elements or not.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SetQuantifier {
     All,
@@ -719,5 +1031,220 @@ mod tests {
         p.extend_with_flows(&[(c, d), (b, c)]);
         assert_eq!(4, p.operators().len());
         assert_eq!(4, p.flows().len());
+
+        let expected = "#1 OrderBy()\n#2 Sink\n#3 LimitOffset()\n#4 GroupBy(GroupFull)\n\
+                        #1 -> #2\n#1 -> #3\n#2 -> #3\n#3 -> #4\n";
+        assert_eq!(p.to_string(), expected);
+    }
+
+    #[test]
+    fn test_plan_tree() {
+        let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        let lhs = p.add_operator(BindingsOp::ProjectAll);
+        let union = p.add_operator(BindingsOp::BagOp(BagOp {
+            bag_op: BagOperator::Union,
+            setq: SetQuantifier::All,
+        }));
+        let rhs = p.add_operator(BindingsOp::Distinct);
+        let sink = p.add_operator(BindingsOp::Sink);
+        let unused = p.add_operator(BindingsOp::Distinct);
+        p.add_flow_with_branch_num(rhs, union, 1);
+        p.add_flow_with_branch_num(lhs, union, 0);
+        p.extend_with_flows(&[(union, sink), (lhs, rhs), (unused, unused)]);
+        p.add_flow(OpId(9), sink);
+
+        let expected = "\
+            #4 Sink\n  \
+              #2 BagOp(Union All)\n    \
+                #1 ProjectAll\n    \
+                #3 Distinct\n      \
+                  #1 ...\n\
+            #5 Distinct\n  \
+              #5 ...\n\
+            #9 -> #4 (0) not in plan\n";
+        assert_eq!(format!("{p:#}"), expected);
+    }
+
+    #[test]
+    fn test_plan_dot() {
+        let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        let a = p.add_operator(BindingsOp::Filter(Filter {
+            expr: ValueExpr::Lit(Box::new(Value::from("\"a\""))),
+        }));
+        let b = p.add_operator(BindingsOp::Sink);
+        p.add_flow_with_branch_num(a, b, 1);
+        let expected = r##"digraph plan {
+  node [shape=box];
+  n1 [label="#1 Filter('\"a\"')"];
+  n2 [label="#2 Sink"];
+  n1 -> n2 [label="1"];
+}
+"##;
+        assert_eq!(p.to_dot(), expected);
+    }
+
+    #[test]
+    fn test_plan_graph() {
+        let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        let a = p.add_operator(BindingsOp::ProjectAll);
+        let b = p.add_operator(BindingsOp::Distinct);
+        let c = p.add_operator(BindingsOp::Sink);
+        p.extend_with_flows(&[(a, b), (b, c)]);
+        p.add_flow_with_branch_num(a, c, 1);
+
+        assert_eq!(p.predecessors(c), vec![a, b]);
+        assert_eq!(p.successors(a), vec![b, c]);
+        assert_eq!(p.topological_order(), Some(vec![a, b, c]));
+
+        assert_eq!(p.remove_operator(b), Some(BindingsOp::Distinct));
+        assert_eq!(p.remove_operator(b), None);
+        assert!(!p.contains_operator(b));
+        assert_eq!(p.operator_count(), 2);
+        assert_eq!(p.flows(), vec![(a, c, 1)]);
+        assert_eq!(p.to_string(), "#1 ProjectAll\n#3 Sink\n#1 -> #3 (1)\n");
+
+        // operators keep their ids as others are removed, whose ids are reused
+        let d = p.add_operator(BindingsOp::Distinct);
+        assert_eq!(d, b);
+        assert_eq!(p.operator(c), Some(&BindingsOp::Sink));
+
+        assert!(p.remove_flows(a, c));
+        assert!(!p.remove_flows(a, c));
+        p.extend_with_flows(&[(c, d), (d, c)]);
+        assert_eq!(p.topological_order(), None);
+    }
+
+    #[test]
+    fn test_dangling_flows() {
+        let mut other: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        other.add_operator(BindingsOp::Distinct);
+        let foreign = other.add_operator(BindingsOp::Distinct);
+
+        let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        let sink = p.add_operator(BindingsOp::Sink);
+        p.add_flow(foreign, sink);
+        assert!(!p.contains_operator(foreign));
+        assert_eq!(p.operator(foreign), None);
+        assert_eq!(p.flows(), vec![(foreign, sink, 0)]);
+        assert!(p.predecessors(sink).is_empty());
+        assert!(p.remove_flows(foreign, sink));
+        assert!(p.flows().is_empty());
+
+        // flows to operators added later are resolved once they are added...
+        let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        let project = p.add_operator(BindingsOp::ProjectAll);
+        p.add_flow(project, foreign);
+        assert!(p.successors(project).is_empty());
+        let sink = p.add_operator(BindingsOp::Sink);
+        assert_eq!(sink, foreign);
+        assert_eq!(p.successors(project), vec![sink]);
+        assert_eq!(p.flows(), vec![(project, sink, 0)]);
+
+        // ...and removed along with the operators they are from or to
+        let distinct = OpId(3);
+        p.add_flow(distinct, sink);
+        assert_eq!(p.remove_operator(sink), Some(BindingsOp::Sink));
+        assert_eq!(p.flows(), vec![]);
+        assert_eq!(p.add_operator(BindingsOp::Sink), sink);
+        assert_eq!(p.add_operator(BindingsOp::Distinct), distinct);
+        assert!(p.flows().is_empty());
+    }
+
+    #[test]
+    fn test_plan_map() {
+        let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        let removed = p.add_operator(BindingsOp::Distinct);
+        let a = p.add_operator(BindingsOp::ProjectAll);
+        let b = p.add_operator(BindingsOp::Sink);
+        p.add_flow_with_branch_num(a, b, 1);
+        p.remove_operator(removed);
+
+        let mapped = p.map(|id, op| format!("{id:?} {op}"));
+        assert_eq!(
+            mapped.operators_by_id().collect::<Vec<_>>(),
+            vec![
+                (a, &format!("{a:?} ProjectAll")),
+                (b, &format!("{b:?} Sink"))
+            ]
+        );
+        assert_eq!(mapped.flows(), vec![(a, b, 1)]);
+    }
+
+    #[test]
+    fn test_plan_eq() {
+        let plan = |branch_num| {
+            let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+            let a = p.add_operator(BindingsOp::ProjectAll);
+            let b = p.add_operator(BindingsOp::Sink);
+            p.add_flow_with_branch_num(a, b, branch_num);
+            p
+        };
+        assert_eq!(plan(0), plan(0));
+        assert_ne!(plan(0), plan(1));
+    }
+
+    #[test]
+    fn test_plan_hash() {
+        fn hash(plan: &LogicalPlan<BindingsOp>) -> u64 {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            plan.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let keys = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let plan = |keys: &mut dyn Iterator<Item = &&str>| {
+            let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+            let group_by = p.add_operator(BindingsOp::GroupBy(GroupBy {
+                strategy: GroupingStrategy::GroupFull,
+                exprs: keys
+                    .map(|k| (k.to_string(), ValueExpr::Lit(Box::new(Value::from(*k)))))
+                    .collect(),
+                aggregate_exprs: vec![],
+                group_as_alias: None,
+            }));
+            let sink = p.add_operator(BindingsOp::Sink);
+            p.add_flow(group_by, sink);
+            p
+        };
+        // the keys of a `GROUP BY` are hashed regardless of the order they were inserted in
+        let forward = plan(&mut keys.iter());
+        let backward = plan(&mut keys.iter().rev());
+        assert_eq!(forward, backward);
+        assert_eq!(hash(&forward), hash(&backward));
+        assert_ne!(hash(&forward), hash(&plan(&mut keys[1..].iter())));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_plan_json() {
+        use partiql_value::{BindingsName, Value};
+
+        let mut p: LogicalPlan<BindingsOp> = LogicalPlan::new();
+        let scan = p.add_operator(BindingsOp::Scan(Scan {
+            expr: ValueExpr::VarRef(BindingsName::CaseInsensitive("t".to_string())),
+            as_key: "t".to_string(),
+            at_key: None,
+        }));
+        let filter = p.add_operator(BindingsOp::Filter(Filter {
+            expr: ValueExpr::BinaryExpr(
+                BinaryOp::Gt,
+                Box::new(ValueExpr::VarRef(BindingsName::CaseSensitive(
+                    "a".to_string(),
+                ))),
+                Box::new(ValueExpr::Lit(Box::new(Value::from(1.5)))),
+            ),
+        }));
+        let sink = p.add_operator(BindingsOp::Sink);
+        // a removed operator leaves a hole in the ids of the plan
+        let removed = p.add_operator(BindingsOp::Distinct);
+        p.remove_operator(removed);
+        p.extend_with_flows(&[(scan, filter), (filter, sink)]);
+        p.add_flow(OpId(9), sink);
+
+        let json = serde_json::to_string(&p).expect("serialize");
+        let deserialized: LogicalPlan<BindingsOp> =
+            serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(deserialized, p);
+        assert_eq!(deserialized.to_string(), p.to_string());
     }
 }
@@ -0,0 +1,24 @@
+//! Estimates of the sizes of the data sources of plans.
+
+use crate::ValueExpr;
+use std::rc::Rc;
+
+/// A provider of estimates of the sizes of data sources, e.g., of the tables of a catalog, by
+/// which plans are optimized and their operators' implementations selected.
+///
+/// A single provider may be shared by the optimizer and the planner of physical plans as an
+/// [`Rc`].
+pub trait Statistics {
+    /// The estimated number of values the data source `expr` evaluates to, e.g., of the rows of the
+    /// table `GlobalVarRef(t)` of `FROM t`, if known.
+    fn cardinality(&self, expr: &ValueExpr) -> Option<u64>;
+}
+
+impl<S> Statistics for Rc<S>
+where
+    S: Statistics + ?Sized,
+{
+    fn cardinality(&self, expr: &ValueExpr) -> Option<u64> {
+        self.as_ref().cardinality(expr)
+    }
+}
@@ -0,0 +1,265 @@
+//! Validation of the structure of [`LogicalPlan`]s.
+
+use crate::{BindingsOp, LogicalPlan, OpId};
+use petgraph::prelude::Direction;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Contains the errors found by [`LogicalPlan::validate`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Plan validation error: encountered errors")]
+pub struct ValidationError {
+    pub errors: Vec<ValidationErrorKind>,
+}
+
+/// Plan validation error kind
+///
+/// ### Notes
+/// This is marked `#[non_exhaustive]`, to reserve the right to add more variants in the future.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationErrorKind {
+    /// A flow from or to an operator that is not in the plan.
+    #[error("flow {0:?} -> {1:?} from or to an operator not in the plan")]
+    DanglingFlow(OpId, OpId),
+
+    /// The flows of the plan form a cycle.
+    #[error("the flows of the plan form a cycle")]
+    Cycle,
+
+    /// The plan does not have exactly one `Sink` operator.
+    #[error("the plan has {0} sinks rather than one")]
+    SinkCount(usize),
+
+    /// An operator whose output does not flow to the sink of the plan.
+    #[error("operator {0:?} does not flow to the sink")]
+    Unreachable(OpId),
+
+    /// An operator with a number of inputs it does not take.
+    #[error("operator {op:?} has {actual} inputs but takes {expected}")]
+    InputCount {
+        op: OpId,
+        expected: &'static str,
+        actual: usize,
+    },
+
+    /// An operator with two inputs whose flows are not on the branches `0` and `1`.
+    #[error("the inputs of operator {0:?} are not on branches 0 and 1")]
+    Branches(OpId),
+}
+
+/// The numbers of inputs `op` takes, and their description.
+fn input_counts(op: &BindingsOp) -> (&'static [usize], &'static str) {
+    match op {
+        BindingsOp::Scan(_) | BindingsOp::Unpivot(_) | BindingsOp::ExprQuery(_) => (&[0], "none"),
+        BindingsOp::BagOp(_) => (&[2], "2"),
+        // a join either evaluates its `left` & `right` operators or takes their outputs as inputs
+        BindingsOp::Join(_) => (&[0, 2], "none or 2"),
+        BindingsOp::Pivot(_)
+        | BindingsOp::Let(_)
+        | BindingsOp::Filter(_)
+        | BindingsOp::OrderBy(_)
+        | BindingsOp::LimitOffset(_)
+        | BindingsOp::Project(_)
+        | BindingsOp::ProjectAll
+        | BindingsOp::ProjectValue(_)
+        | BindingsOp::Distinct
+        | BindingsOp::GroupBy(_)
+        | BindingsOp::Having(_)
+        | BindingsOp::Window(_)
+        | BindingsOp::Sink => (&[1], "1"),
+    }
+}
+
+impl LogicalPlan<BindingsOp> {
+    /// Checks that the plan is well-formed: its flows are between operators of the plan and form
+    /// no cycle, it has a single `Sink` that the outputs of all of its operators flow to, and each
+    /// of its operators has as many inputs as it takes, on the branches `0` and `1` for those that
+    /// take two.
+    ///
+    /// The plans of the subqueries of the plan are not validated.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut errors: Vec<_> = self
+            .dangling
+            .iter()
+            .map(|&(src, dst, _)| ValidationErrorKind::DanglingFlow(src, dst))
+            .collect();
+
+        if self.topological_order().is_none() {
+            errors.push(ValidationErrorKind::Cycle);
+        }
+
+        let sinks: Vec<OpId> = self
+            .operators_by_id()
+            .filter(|(_, op)| matches!(op, BindingsOp::Sink))
+            .map(|(id, _)| id)
+            .collect();
+        match sinks.as_slice() {
+            [sink] => {
+                let mut reaching = HashSet::from([*sink]);
+                let mut pending = vec![*sink];
+                while let Some(id) = pending.pop() {
+                    for pred in self.predecessors(id) {
+                        if reaching.insert(pred) {
+                            pending.push(pred);
+                        }
+                    }
+                }
+                errors.extend(
+                    self.operators_by_id()
+                        .filter(|(id, _)| !reaching.contains(id))
+                        .map(|(id, _)| ValidationErrorKind::Unreachable(id)),
+                );
+            }
+            sinks => errors.push(ValidationErrorKind::SinkCount(sinks.len())),
+        }
+
+        for idx in self.graph.node_indices() {
+            let id = OpId::from(idx);
+            let mut branches: Vec<u8> = self
+                .graph
+                .edges_directed(idx, Direction::Incoming)
+                .map(|e| *e.weight())
+                .collect();
+            let (counts, expected) = input_counts(&self.graph[idx]);
+            if !counts.contains(&branches.len()) {
+                errors.push(ValidationErrorKind::InputCount {
+                    op: id,
+                    expected,
+                    actual: branches.len(),
+                });
+            } else if branches.len() == 2 {
+                branches.sort();
+                if branches != [0, 1] {
+                    errors.push(ValidationErrorKind::Branches(id));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { errors })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Filter, Scan, ValueExpr};
+    use partiql_value::{BindingsName, Value};
+
+    fn scan_op() -> BindingsOp {
+        BindingsOp::Scan(Scan {
+            expr: ValueExpr::VarRef(BindingsName::CaseInsensitive("t".to_string())),
+            as_key: "t".to_string(),
+            at_key: None,
+        })
+    }
+
+    fn filter_op() -> BindingsOp {
+        BindingsOp::Filter(Filter {
+            expr: ValueExpr::Lit(Box::new(Value::from(true))),
+        })
+    }
+
+    fn errors(plan: &LogicalPlan<BindingsOp>) -> Vec<ValidationErrorKind> {
+        plan.validate().err().map(|e| e.errors).unwrap_or_default()
+    }
+
+    #[test]
+    fn valid() {
+        let mut p = LogicalPlan::new();
+        let scan = p.add_operator(scan_op());
+        let filter = p.add_operator(filter_op());
+        let sink = p.add_operator(BindingsOp::Sink);
+        p.extend_with_flows(&[(scan, filter), (filter, sink)]);
+        assert_eq!(p.validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut p = LogicalPlan::new();
+        let scan = p.add_operator(scan_op());
+        let filter = p.add_operator(filter_op());
+        let sink = p.add_operator(BindingsOp::Sink);
+        p.extend_with_flows(&[(scan, filter), (filter, filter), (filter, sink)]);
+        assert_eq!(
+            errors(&p),
+            vec![
+                ValidationErrorKind::Cycle,
+                ValidationErrorKind::InputCount {
+                    op: filter,
+                    expected: "1",
+                    actual: 2
+                }
+            ]
+        );
+
+        // an operator of a plan with more operators than `p`
+        let mut other_plan = LogicalPlan::new();
+        for _ in 0..4 {
+            other_plan.add_operator(BindingsOp::Sink);
+        }
+        let foreign = other_plan.add_operator(BindingsOp::Sink);
+
+        let mut p = LogicalPlan::new();
+        let scan = p.add_operator(scan_op());
+        let filter = p.add_operator(filter_op());
+        let sink = p.add_operator(BindingsOp::Sink);
+        p.extend_with_flows(&[(scan, filter), (filter, sink), (foreign, sink)]);
+        assert_eq!(
+            errors(&p),
+            vec![ValidationErrorKind::DanglingFlow(foreign, sink)]
+        );
+
+        // the flow is no longer dangling once an operator with the id it is from is added
+        let other = p.add_operator(scan_op());
+        p.add_flow_with_branch_num(other, filter, 1);
+        let other_sink = p.add_operator(BindingsOp::Sink);
+        assert_eq!(other_sink, foreign);
+        assert_eq!(
+            errors(&p),
+            vec![
+                ValidationErrorKind::SinkCount(2),
+                ValidationErrorKind::InputCount {
+                    op: filter,
+                    expected: "1",
+                    actual: 2
+                },
+                ValidationErrorKind::InputCount {
+                    op: sink,
+                    expected: "1",
+                    actual: 2
+                },
+                ValidationErrorKind::InputCount {
+                    op: other_sink,
+                    expected: "1",
+                    actual: 0
+                }
+            ]
+        );
+
+        let mut p = LogicalPlan::new();
+        let lhs = p.add_operator(scan_op());
+        let rhs = p.add_operator(scan_op());
+        let union = p.add_operator(BindingsOp::BagOp(crate::BagOp {
+            bag_op: crate::BagOperator::Union,
+            setq: crate::SetQuantifier::All,
+        }));
+        let sink = p.add_operator(BindingsOp::Sink);
+        p.extend_with_flows(&[(lhs, union), (rhs, union), (union, sink)]);
+        assert_eq!(errors(&p), vec![ValidationErrorKind::Branches(union)]);
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut p = LogicalPlan::new();
+        let scan = p.add_operator(scan_op());
+        let unused = p.add_operator(scan_op());
+        let sink = p.add_operator(BindingsOp::Sink);
+        p.add_flow(scan, sink);
+        assert_eq!(errors(&p), vec![ValidationErrorKind::Unreachable(unused)]);
+    }
+}
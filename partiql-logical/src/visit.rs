@@ -0,0 +1,657 @@
+//! Traversal of [`LogicalPlan`]s by [`PlanVisitor`]s and [`PlanRewriter`]s.
+//!
+//! A traversal enters a plan, then each of its operators in the order of its flows (or, if they
+//! form a cycle, in the order of their [`OpId`]s), and each of the expressions of an operator
+//! before its sub-expressions. The plan of a subquery is traversed after the sub-expressions of its
+//! [`ValueExpr::SubQueryExpr`]. The operators a [`Join`](crate::Join) keeps inline are part of the
+//! join; their expressions are traversed as those of the join, before its `ON` condition.
+//!
+//! ```
+//! use partiql_logical::visit::{PlanRewriter, Traverse};
+//! use partiql_logical::{BindingsOp, ExprQuery, LogicalPlan, ValueExpr};
+//! use partiql_value::{BindingsName, Value};
+//!
+//! /// Replaces references to `x` by `42`.
+//! struct Inline;
+//!
+//! impl PlanRewriter for Inline {
+//!     fn enter_expr(&mut self, expr: &mut ValueExpr) -> Traverse {
+//!         if *expr == ValueExpr::VarRef(BindingsName::CaseInsensitive("x".to_string())) {
+//!             *expr = ValueExpr::Lit(Box::new(Value::from(42)));
+//!         }
+//!         Traverse::Continue
+//!     }
+//! }
+//!
+//! let mut plan = LogicalPlan::new();
+//! let query = plan.add_operator(BindingsOp::ExprQuery(ExprQuery {
+//!     expr: ValueExpr::VarRef(BindingsName::CaseInsensitive("x".to_string())),
+//! }));
+//! plan.rewrite(&mut Inline);
+//! assert_eq!(
+//!     plan.operator(query),
+//!     Some(&BindingsOp::ExprQuery(ExprQuery {
+//!         expr: ValueExpr::Lit(Box::new(Value::from(42)))
+//!     }))
+//! );
+//! ```
+
+use crate::{BindingsOp, LogicalPlan, OpId, PathComponent, Pattern, ValueExpr, WindowFunction};
+
+/// Indicates if the traversal of the entire plan should continue or not.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Traverse {
+    /// Signals the traversal of the entire plan should continue.
+    Continue,
+    /// Signals the traversal of the entire plan should stop.
+    Stop,
+}
+
+/// Returns `Traverse::Stop` from the enclosing function if `$traverse` is.
+macro_rules! stop_if_stopped {
+    ($traverse:expr) => {
+        if $traverse == Traverse::Stop {
+            return Traverse::Stop;
+        }
+    };
+}
+
+/// A visitor of the operators and expressions of a plan; see the [module documentation](self).
+pub trait PlanVisitor<'plan> {
+    fn enter_plan(&mut self, _plan: &'plan LogicalPlan<BindingsOp>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_plan(&mut self, _plan: &'plan LogicalPlan<BindingsOp>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_operator(&mut self, _id: OpId, _op: &'plan BindingsOp) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_operator(&mut self, _id: OpId, _op: &'plan BindingsOp) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_expr(&mut self, _expr: &'plan ValueExpr) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_expr(&mut self, _expr: &'plan ValueExpr) -> Traverse {
+        Traverse::Continue
+    }
+}
+
+/// A visitor that may modify the operators and expressions of a plan as it visits them; see the
+/// [module documentation](self).
+///
+/// The operators of a plan are those it has once entered, and the sub-expressions of an
+/// expression those it has once entered; e.g., an expression replaced by
+/// [`enter_expr`](PlanRewriter::enter_expr) has the sub-expressions of its replacement traversed.
+pub trait PlanRewriter {
+    fn enter_plan(&mut self, _plan: &mut LogicalPlan<BindingsOp>) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_plan(&mut self, _plan: &mut LogicalPlan<BindingsOp>) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_operator(&mut self, _id: OpId, _op: &mut BindingsOp) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_operator(&mut self, _id: OpId, _op: &mut BindingsOp) -> Traverse {
+        Traverse::Continue
+    }
+    fn enter_expr(&mut self, _expr: &mut ValueExpr) -> Traverse {
+        Traverse::Continue
+    }
+    fn exit_expr(&mut self, _expr: &mut ValueExpr) -> Traverse {
+        Traverse::Continue
+    }
+}
+
+impl LogicalPlan<BindingsOp> {
+    /// Traverses the plan with the visitor `v`; see the [module documentation](self).
+    pub fn visit<'plan, V>(&'plan self, v: &mut V) -> Traverse
+    where
+        V: PlanVisitor<'plan>,
+    {
+        stop_if_stopped!(v.enter_plan(self));
+        for id in self.traversal_order() {
+            let Some(op) = self.operator(id) else {
+                continue;
+            };
+            stop_if_stopped!(v.enter_operator(id, op));
+            for expr in op_exprs(op) {
+                stop_if_stopped!(visit_expr(expr, v));
+            }
+            stop_if_stopped!(v.exit_operator(id, op));
+        }
+        v.exit_plan(self)
+    }
+
+    /// Traverses the plan with the rewriter `r`; see the [module documentation](self).
+    pub fn rewrite<R>(&mut self, r: &mut R) -> Traverse
+    where
+        R: PlanRewriter,
+    {
+        stop_if_stopped!(r.enter_plan(self));
+        for id in self.traversal_order() {
+            let Some(op) = self.operator_as_mut(id) else {
+                continue;
+            };
+            stop_if_stopped!(r.enter_operator(id, op));
+            for expr in op_exprs_mut(op) {
+                stop_if_stopped!(rewrite_expr(expr, r));
+            }
+            stop_if_stopped!(r.exit_operator(id, op));
+        }
+        r.exit_plan(self)
+    }
+
+    /// The operators of the plan in the order of its flows if they form no cycle, or else in the
+    /// order of their ids.
+    fn traversal_order(&self) -> Vec<OpId> {
+        self.topological_order()
+            .unwrap_or_else(|| self.operators_by_id().map(|(id, _)| id).collect())
+    }
+}
+
+impl BindingsOp {
+    /// Traverses the expressions of the operator with the visitor `v`, as a traversal of a plan
+    /// traverses them, without entering the operator itself.
+    pub fn visit<'plan, V>(&'plan self, v: &mut V) -> Traverse
+    where
+        V: PlanVisitor<'plan>,
+    {
+        for expr in op_exprs(self) {
+            stop_if_stopped!(visit_expr(expr, v));
+        }
+        Traverse::Continue
+    }
+
+    /// Traverses the expressions of the operator with the rewriter `r`, as a traversal of a plan
+    /// traverses them, without entering the operator itself.
+    pub fn rewrite<R>(&mut self, r: &mut R) -> Traverse
+    where
+        R: PlanRewriter,
+    {
+        for expr in op_exprs_mut(self) {
+            stop_if_stopped!(rewrite_expr(expr, r));
+        }
+        Traverse::Continue
+    }
+}
+
+impl ValueExpr {
+    /// Traverses the expression with the visitor `v`, as a traversal of a plan traverses the
+    /// expressions of its operators.
+    pub fn visit<'plan, V>(&'plan self, v: &mut V) -> Traverse
+    where
+        V: PlanVisitor<'plan>,
+    {
+        visit_expr(self, v)
+    }
+
+    /// Traverses the expression with the rewriter `r`, as a traversal of a plan traverses the
+    /// expressions of its operators.
+    pub fn rewrite<R>(&mut self, r: &mut R) -> Traverse
+    where
+        R: PlanRewriter,
+    {
+        rewrite_expr(self, r)
+    }
+}
+
+fn visit_expr<'plan, V>(expr: &'plan ValueExpr, v: &mut V) -> Traverse
+where
+    V: PlanVisitor<'plan>,
+{
+    stop_if_stopped!(v.enter_expr(expr));
+    for sub_expr in sub_exprs(expr) {
+        stop_if_stopped!(visit_expr(sub_expr, v));
+    }
+    if let ValueExpr::SubQueryExpr(subquery) = expr {
+        stop_if_stopped!(subquery.plan.visit(v));
+    }
+    v.exit_expr(expr)
+}
+
+fn rewrite_expr<R>(expr: &mut ValueExpr, r: &mut R) -> Traverse
+where
+    R: PlanRewriter,
+{
+    stop_if_stopped!(r.enter_expr(expr));
+    for sub_expr in sub_exprs_mut(expr) {
+        stop_if_stopped!(rewrite_expr(sub_expr, r));
+    }
+    if let ValueExpr::SubQueryExpr(subquery) = expr {
+        stop_if_stopped!(subquery.plan.rewrite(r));
+    }
+    r.exit_expr(expr)
+}
+
+/// The expressions of `op`, in the order they are traversed in.
+fn op_exprs(op: &BindingsOp) -> Vec<&ValueExpr> {
+    match op {
+        BindingsOp::Scan(scan) => vec![&scan.expr],
+        BindingsOp::Pivot(pivot) => vec![&pivot.key, &pivot.value],
+        BindingsOp::Unpivot(unpivot) => vec![&unpivot.expr],
+        BindingsOp::Let(lets) => lets.bindings.iter().map(|(_, expr)| expr).collect(),
+        BindingsOp::Filter(filter) => vec![&filter.expr],
+        BindingsOp::Having(having) => vec![&having.expr],
+        BindingsOp::OrderBy(order_by) => order_by.specs.iter().map(|spec| &spec.expr).collect(),
+        BindingsOp::LimitOffset(limit_offset) => limit_offset
+            .limit
+            .iter()
+            .chain(&limit_offset.offset)
+            .collect(),
+        BindingsOp::Join(join) => {
+            let mut exprs = op_exprs(&join.left);
+            exprs.extend(op_exprs(&join.right));
+            exprs.extend(&join.on);
+            exprs
+        }
+        BindingsOp::Project(project) => project.exprs.iter().map(|(_, expr)| expr).collect(),
+        BindingsOp::ProjectValue(project) => vec![&project.expr],
+        BindingsOp::ExprQuery(query) => vec![&query.expr],
+        BindingsOp::GroupBy(group_by) => {
+            // `exprs` is unordered, so its keys are sorted
+            let mut keys: Vec<_> = group_by.exprs.iter().collect();
+            keys.sort_by_key(|(name, _)| *name);
+            let keys = keys.into_iter().map(|(_, expr)| expr);
+            keys.chain(group_by.aggregate_exprs.iter().map(|agg| &agg.expr))
+                .collect()
+        }
+        BindingsOp::Window(window) => {
+            let mut exprs: Vec<_> = window.partition_by.iter().collect();
+            exprs.extend(window.order_by.iter().map(|spec| &spec.expr));
+            if let WindowFunction::Lag(args) | WindowFunction::Lead(args) = &window.func {
+                exprs.extend([args.expr.as_ref(), &args.offset, &args.default]);
+            }
+            exprs
+        }
+        BindingsOp::BagOp(_) | BindingsOp::ProjectAll | BindingsOp::Distinct | BindingsOp::Sink => {
+            vec![]
+        }
+    }
+}
+
+/// The expressions of `op`, in the order they are traversed in.
+fn op_exprs_mut(op: &mut BindingsOp) -> Vec<&mut ValueExpr> {
+    match op {
+        BindingsOp::Scan(scan) => vec![&mut scan.expr],
+        BindingsOp::Pivot(pivot) => vec![&mut pivot.key, &mut pivot.value],
+        BindingsOp::Unpivot(unpivot) => vec![&mut unpivot.expr],
+        BindingsOp::Let(lets) => lets.bindings.iter_mut().map(|(_, expr)| expr).collect(),
+        BindingsOp::Filter(filter) => vec![&mut filter.expr],
+        BindingsOp::Having(having) => vec![&mut having.expr],
+        BindingsOp::OrderBy(order_by) => order_by
+            .specs
+            .iter_mut()
+            .map(|spec| &mut spec.expr)
+            .collect(),
+        BindingsOp::LimitOffset(limit_offset) => limit_offset
+            .limit
+            .iter_mut()
+            .chain(&mut limit_offset.offset)
+            .collect(),
+        BindingsOp::Join(join) => {
+            let mut exprs = op_exprs_mut(&mut join.left);
+            exprs.extend(op_exprs_mut(&mut join.right));
+            exprs.extend(&mut join.on);
+            exprs
+        }
+        BindingsOp::Project(project) => project.exprs.iter_mut().map(|(_, expr)| expr).collect(),
+        BindingsOp::ProjectValue(project) => vec![&mut project.expr],
+        BindingsOp::ExprQuery(query) => vec![&mut query.expr],
+        BindingsOp::GroupBy(group_by) => {
+            // `exprs` is unordered, so its keys are sorted
+            let mut keys: Vec<_> = group_by.exprs.iter_mut().collect();
+            keys.sort_by_key(|(name, _)| *name);
+            let keys = keys.into_iter().map(|(_, expr)| expr);
+            keys.chain(group_by.aggregate_exprs.iter_mut().map(|agg| &mut agg.expr))
+                .collect()
+        }
+        BindingsOp::Window(window) => {
+            let mut exprs: Vec<_> = window.partition_by.iter_mut().collect();
+            exprs.extend(window.order_by.iter_mut().map(|spec| &mut spec.expr));
+            if let WindowFunction::Lag(args) | WindowFunction::Lead(args) = &mut window.func {
+                exprs.extend([
+                    args.expr.as_mut(),
+                    args.offset.as_mut(),
+                    args.default.as_mut(),
+                ]);
+            }
+            exprs
+        }
+        BindingsOp::BagOp(_) | BindingsOp::ProjectAll | BindingsOp::Distinct | BindingsOp::Sink => {
+            vec![]
+        }
+    }
+}
+
+/// The sub-expressions of `expr`, in the order they are traversed in; the plan of a subquery is
+/// not an expression.
+fn sub_exprs(expr: &ValueExpr) -> Vec<&ValueExpr> {
+    match expr {
+        ValueExpr::UnExpr(_, expr) => vec![expr],
+        ValueExpr::BinaryExpr(_, lhs, rhs) => vec![lhs, rhs],
+        ValueExpr::DynamicLookup(lookups) => lookups.iter().collect(),
+        ValueExpr::Path(root, components) => {
+            let mut exprs = vec![root.as_ref()];
+            exprs.extend(components.iter().filter_map(|component| match component {
+                PathComponent::Expr(expr) => Some(expr.as_ref()),
+                PathComponent::Key(_)
+                | PathComponent::Index(_)
+                | PathComponent::Wildcard
+                | PathComponent::Unpivot => None,
+            }));
+            exprs
+        }
+        ValueExpr::TupleExpr(tuple) => tuple
+            .attrs
+            .iter()
+            .zip(&tuple.values)
+            .flat_map(|(attr, value)| [attr, value])
+            .collect(),
+        ValueExpr::ListExpr(list) => list.elements.iter().collect(),
+        ValueExpr::BagExpr(bag) => bag.elements.iter().collect(),
+        ValueExpr::BetweenExpr(between) => vec![&between.value, &between.from, &between.to],
+        ValueExpr::PatternMatchExpr(pattern_match) => match &pattern_match.pattern {
+            Pattern::Like(_) => vec![&pattern_match.value],
+            Pattern::LikeNonStringNonLiteral(like) => {
+                vec![&pattern_match.value, &like.pattern, &like.escape]
+            }
+        },
+        ValueExpr::SimpleCase(case) => {
+            let mut exprs = vec![case.expr.as_ref()];
+            for (when, then) in &case.cases {
+                exprs.extend([when.as_ref(), then.as_ref()]);
+            }
+            exprs.extend(case.default.as_deref());
+            exprs
+        }
+        ValueExpr::SearchedCase(case) => {
+            let mut exprs = vec![];
+            for (when, then) in &case.cases {
+                exprs.extend([when.as_ref(), then.as_ref()]);
+            }
+            exprs.extend(case.default.as_deref());
+            exprs
+        }
+        ValueExpr::IsTypeExpr(is_type) => vec![&is_type.expr],
+        ValueExpr::NullIfExpr(null_if) => vec![&null_if.lhs, &null_if.rhs],
+        ValueExpr::CoalesceExpr(coalesce) => coalesce.elements.iter().collect(),
+        ValueExpr::Call(call) => call.arguments.iter().collect(),
+        ValueExpr::Lit(_)
+        | ValueExpr::VarRef(_)
+        | ValueExpr::GlobalVarRef(_)
+        | ValueExpr::Parameter(_)
+        | ValueExpr::SubQueryExpr(_) => vec![],
+    }
+}
+
+/// The sub-expressions of `expr`, in the order they are traversed in; the plan of a subquery is
+/// not an expression.
+fn sub_exprs_mut(expr: &mut ValueExpr) -> Vec<&mut ValueExpr> {
+    match expr {
+        ValueExpr::UnExpr(_, expr) => vec![expr],
+        ValueExpr::BinaryExpr(_, lhs, rhs) => vec![lhs, rhs],
+        ValueExpr::DynamicLookup(lookups) => lookups.iter_mut().collect(),
+        ValueExpr::Path(root, components) => {
+            let mut exprs = vec![root.as_mut()];
+            exprs.extend(
+                components
+                    .iter_mut()
+                    .filter_map(|component| match component {
+                        PathComponent::Expr(expr) => Some(expr.as_mut()),
+                        PathComponent::Key(_)
+                        | PathComponent::Index(_)
+                        | PathComponent::Wildcard
+                        | PathComponent::Unpivot => None,
+                    }),
+            );
+            exprs
+        }
+        ValueExpr::TupleExpr(tuple) => tuple
+            .attrs
+            .iter_mut()
+            .zip(&mut tuple.values)
+            .flat_map(|(attr, value)| [attr, value])
+            .collect(),
+        ValueExpr::ListExpr(list) => list.elements.iter_mut().collect(),
+        ValueExpr::BagExpr(bag) => bag.elements.iter_mut().collect(),
+        ValueExpr::BetweenExpr(between) => vec![
+            between.value.as_mut(),
+            between.from.as_mut(),
+            between.to.as_mut(),
+        ],
+        ValueExpr::PatternMatchExpr(pattern_match) => match &mut pattern_match.pattern {
+            Pattern::Like(_) => vec![pattern_match.value.as_mut()],
+            Pattern::LikeNonStringNonLiteral(like) => vec![
+                pattern_match.value.as_mut(),
+                like.pattern.as_mut(),
+                like.escape.as_mut(),
+            ],
+        },
+        ValueExpr::SimpleCase(case) => {
+            let mut exprs = vec![case.expr.as_mut()];
+            for (when, then) in &mut case.cases {
+                exprs.extend([when.as_mut(), then.as_mut()]);
+            }
+            exprs.extend(case.default.as_deref_mut());
+            exprs
+        }
+        ValueExpr::SearchedCase(case) => {
+            let mut exprs = vec![];
+            for (when, then) in &mut case.cases {
+                exprs.extend([when.as_mut(), then.as_mut()]);
+            }
+            exprs.extend(case.default.as_deref_mut());
+            exprs
+        }
+        ValueExpr::IsTypeExpr(is_type) => vec![is_type.expr.as_mut()],
+        ValueExpr::NullIfExpr(null_if) => vec![null_if.lhs.as_mut(), null_if.rhs.as_mut()],
+        ValueExpr::CoalesceExpr(coalesce) => coalesce.elements.iter_mut().collect(),
+        ValueExpr::Call(call) => call.arguments.iter_mut().collect(),
+        ValueExpr::Lit(_)
+        | ValueExpr::VarRef(_)
+        | ValueExpr::GlobalVarRef(_)
+        | ValueExpr::Parameter(_)
+        | ValueExpr::SubQueryExpr(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinaryOp, Filter, Join, JoinKind, ProjectValue, Scan, SubQueryCoercion};
+    use crate::{SubQueryExpr, UnaryOp};
+    use partiql_value::BindingsName;
+
+    fn var(name: &str) -> ValueExpr {
+        ValueExpr::VarRef(BindingsName::CaseInsensitive(name.to_string()))
+    }
+
+    fn scan(name: &str) -> BindingsOp {
+        BindingsOp::Scan(Scan {
+            expr: var(name),
+            as_key: name.to_string(),
+            at_key: None,
+        })
+    }
+
+    /// `SELECT VALUE x FROM (a JOIN b ON a = b) WHERE NOT (SELECT VALUE y FROM c)`
+    fn plan() -> LogicalPlan<BindingsOp> {
+        let mut subquery = LogicalPlan::new();
+        let c = subquery.add_operator(scan("c"));
+        let y = subquery.add_operator(BindingsOp::ProjectValue(ProjectValue { expr: var("y") }));
+        let sink = subquery.add_operator(BindingsOp::Sink);
+        subquery.extend_with_flows(&[(c, y), (y, sink)]);
+
+        let mut p = LogicalPlan::new();
+        let sink = p.add_operator(BindingsOp::Sink);
+        let x = p.add_operator(BindingsOp::ProjectValue(ProjectValue { expr: var("x") }));
+        let filter = p.add_operator(BindingsOp::Filter(Filter {
+            expr: ValueExpr::UnExpr(
+                UnaryOp::Not,
+                Box::new(ValueExpr::SubQueryExpr(SubQueryExpr {
+                    plan: subquery,
+                    coercion: SubQueryCoercion::Scalar,
+                })),
+            ),
+        }));
+        let a = p.add_operator(scan("a"));
+        let b = p.add_operator(scan("b"));
+        let join = p.add_operator(BindingsOp::Join(Join {
+            kind: JoinKind::Inner,
+            left: Box::new(scan("a")),
+            right: Box::new(scan("b")),
+            on: Some(ValueExpr::BinaryExpr(
+                BinaryOp::Eq,
+                Box::new(var("a")),
+                Box::new(var("b")),
+            )),
+        }));
+        p.add_flow_with_branch_num(a, join, 0);
+        p.add_flow_with_branch_num(b, join, 1);
+        p.extend_with_flows(&[(join, filter), (filter, x), (x, sink)]);
+        p
+    }
+
+    /// Records the traversal of a plan.
+    #[derive(Default)]
+    struct Trace {
+        events: Vec<String>,
+        stop_at: Option<&'static str>,
+    }
+
+    impl Trace {
+        fn record(&mut self, event: String) -> Traverse {
+            let stop = self.stop_at == Some(event.as_str());
+            self.events.push(event);
+            if stop {
+                Traverse::Stop
+            } else {
+                Traverse::Continue
+            }
+        }
+    }
+
+    impl<'plan> PlanVisitor<'plan> for Trace {
+        fn enter_plan(&mut self, _plan: &'plan LogicalPlan<BindingsOp>) -> Traverse {
+            self.record("plan".to_string())
+        }
+        fn exit_plan(&mut self, _plan: &'plan LogicalPlan<BindingsOp>) -> Traverse {
+            self.record("/plan".to_string())
+        }
+        fn enter_operator(&mut self, id: OpId, op: &'plan BindingsOp) -> Traverse {
+            let name = format!("{op}");
+            let name = name.split('(').next().unwrap_or_default();
+            self.record(format!("#{} {name}", id.index()))
+        }
+        fn enter_expr(&mut self, expr: &'plan ValueExpr) -> Traverse {
+            self.record(expr.to_string())
+        }
+    }
+
+    #[test]
+    fn visit() {
+        let p = plan();
+        let mut trace = Trace::default();
+        assert_eq!(p.visit(&mut trace), Traverse::Continue);
+        assert_eq!(
+            trace.events,
+            vec![
+                "plan",
+                "#5 Scan",
+                "b",
+                "#4 Scan",
+                "a",
+                "#6 Join",
+                "a",
+                "b",
+                "Eq(a, b)",
+                "a",
+                "b",
+                "#3 Filter",
+                "Not(ScalarSubQuery(#1 Scan(c AS c); #2 ProjectValue(y); #3 Sink; \
+                 #1 -> #2; #2 -> #3))",
+                "ScalarSubQuery(#1 Scan(c AS c); #2 ProjectValue(y); #3 Sink; #1 -> #2; #2 -> #3)",
+                "plan",
+                "#1 Scan",
+                "c",
+                "#2 ProjectValue",
+                "y",
+                "#3 Sink",
+                "/plan",
+                "#2 ProjectValue",
+                "x",
+                "#1 Sink",
+                "/plan",
+            ]
+        );
+
+        let mut trace = Trace {
+            stop_at: Some("#2 ProjectValue"),
+            ..Default::default()
+        };
+        assert_eq!(p.visit(&mut trace), Traverse::Stop);
+        assert_eq!(
+            trace.events.last().map(String::as_str),
+            Some("#2 ProjectValue")
+        );
+        assert!(!trace.events.contains(&"x".to_string()));
+
+        let mut trace = Trace::default();
+        let (_, join) = p
+            .operators_by_id()
+            .find(|(_, op)| matches!(op, BindingsOp::Join(_)))
+            .unwrap();
+        assert_eq!(join.visit(&mut trace), Traverse::Continue);
+        assert_eq!(trace.events, vec!["a", "b", "Eq(a, b)", "a", "b"]);
+    }
+
+    /// Renames the variables `a` to `z`, and removes `Filter`s.
+    struct Rename;
+
+    impl PlanRewriter for Rename {
+        fn enter_plan(&mut self, plan: &mut LogicalPlan<BindingsOp>) -> Traverse {
+            let filters: Vec<_> = plan
+                .operators_by_id()
+                .filter(|(_, op)| matches!(op, BindingsOp::Filter(_)))
+                .map(|(id, _)| id)
+                .collect();
+            for filter in filters {
+                let (inputs, outputs) = (plan.inputs(filter), plan.successors(filter));
+                plan.remove_operator(filter);
+                for (input, output) in inputs.into_iter().zip(outputs) {
+                    plan.add_flow(input, output);
+                }
+            }
+            Traverse::Continue
+        }
+
+        fn exit_expr(&mut self, expr: &mut ValueExpr) -> Traverse {
+            if *expr == var("a") {
+                *expr = var("z");
+            }
+            Traverse::Continue
+        }
+    }
+
+    #[test]
+    fn rewrite() {
+        let mut p = plan();
+        assert_eq!(p.rewrite(&mut Rename), Traverse::Continue);
+        assert_eq!(
+            p.to_string(),
+            "#1 Sink\n#2 ProjectValue(x)\n#4 Scan(z AS a)\n#5 Scan(b AS b)\n\
+             #6 Join(Inner, Scan(z AS a), Scan(b AS b) ON Eq(z, b))\n\
+             #2 -> #1\n#4 -> #6\n#5 -> #6 (1)\n#6 -> #2\n"
+        );
+
+        let mut expr = ValueExpr::BinaryExpr(BinaryOp::Eq, Box::new(var("a")), Box::new(var("b")));
+        assert_eq!(expr.rewrite(&mut Rename), Traverse::Continue);
+        assert_eq!(
+            expr,
+            ValueExpr::BinaryExpr(BinaryOp::Eq, Box::new(var("z")), Box::new(var("b")))
+        );
+    }
+}
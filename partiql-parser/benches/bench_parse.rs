@@ -1,5 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use partiql_parser::{Parser, ParserResult};
+use partiql_ast::arena::Arena;
+use partiql_parser::Parser;
 use std::time::Duration;
 
 const Q_STAR: &str = "SELECT *";
@@ -34,9 +35,19 @@ const Q_COMPLEX_FEXPR: &str = r#"
             AS deltas FROM SOURCE_VIEW_DELTA_FULL_TRANSACTIONS delta_full_transactions
             "#;
 
+/// A query of tens of thousands of AST nodes: `SELECT VALUE [..]` of many paths, calls, and
+/// operators.
+fn q_large() -> String {
+    let items: Vec<_> = (0..4000)
+        .map(|i| format!("t.a{i}.b[{i}] + foo(x, 'str{i}') * -{i}"))
+        .collect();
+    format!("SELECT VALUE [{}] FROM data AS t", items.join(", "))
+}
+
 fn parse_bench(c: &mut Criterion) {
-    fn parse(text: &str) -> ParserResult {
-        Parser::default().parse(text)
+    fn parse(text: &str) {
+        let arena = Arena::new();
+        let _ = black_box(Parser::default().parse(text, &arena));
     }
     c.bench_function("parse-simple", |b| b.iter(|| parse(black_box(Q_STAR))));
     c.bench_function("parse-ion", |b| b.iter(|| parse(black_box(Q_ION))));
@@ -45,6 +56,8 @@ fn parse_bench(c: &mut Criterion) {
     c.bench_function("parse-complex-fexpr", |b| {
         b.iter(|| parse(black_box(Q_COMPLEX_FEXPR)))
     });
+    let q_large = q_large();
+    c.bench_function("parse-large", |b| b.iter(|| parse(black_box(&q_large))));
 }
 
 criterion_group! {
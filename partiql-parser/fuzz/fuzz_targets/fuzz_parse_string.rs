@@ -2,10 +2,14 @@
 use libfuzzer_sys::fuzz_target;
 extern crate partiql_parser;
 
-use partiql_parser::{parse_partiql, ParserError, ParserResult};
+use partiql_ast::arena::Arena;
+use partiql_parser::Parser;
 
 fuzz_target!(|data: &[u8]| {
     if let Ok(s) = std::str::from_utf8(data) {
-        let _ = parse_partiql(s);
+        let parser = Parser::default();
+        let arena = Arena::new();
+        let _ = parser.parse(s, &arena);
+        let _ = parser.parse_statement(s, &arena);
     }
 });
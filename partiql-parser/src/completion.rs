@@ -0,0 +1,169 @@
+//! Token-level auto-completion of PartiQL statements.
+//!
+//! Given statement text and a cursor position, [`complete`] runs the parser over the text before
+//! the cursor and reports the keywords and punctuation that may follow it, along with the aliases
+//! defined in the statement when an identifier may follow it.
+
+use crate::lexer::{PartiqlLexer, Token};
+use crate::parse::expected_tokens;
+use partiql_source_map::line_offset_tracker::LineOffsetTracker;
+
+/// Grammar terminals which are classes of tokens rather than fixed text.
+const IDENTIFIER_TERMINALS: [&str; 4] = [
+    "UnquotedIdent",
+    "QuotedIdent",
+    "UnquotedAtIdentifier",
+    "QuotedAtIdentifier",
+];
+const LITERAL_TERMINALS: [&str; 5] = ["Int", "Real", "ExpReal", "String", "Ion"];
+
+/// The completion candidates at a cursor position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completions {
+    /// The partially typed word immediately before the cursor, which the candidates complete.
+    pub partial: String,
+    /// Keywords and punctuation that may follow the text before the cursor, e.g., `FROM` or `(`.
+    pub tokens: Vec<String>,
+    /// Aliases defined in the statement (e.g., by `AS` or `AT`) that may follow the text before
+    /// the cursor.
+    pub aliases: Vec<String>,
+}
+
+/// Compute the completion candidates for `text` with the cursor at byte offset `cursor`.
+///
+/// If the cursor immediately follows a partially typed word, only candidates starting with that
+/// word (ignoring case) are returned.
+///
+/// # Example
+///
+/// ```
+/// use partiql_parser::complete;
+///
+/// let completions = complete("SELECT c.name FROM customers AS c WH", 36);
+/// assert_eq!(completions.partial, "WH");
+/// assert_eq!(completions.tokens, vec!["WHERE"]);
+///
+/// let completions = complete("SELECT  FROM customers AS c", 7);
+/// assert!(completions.tokens.contains(&"DISTINCT".to_string()));
+/// assert_eq!(completions.aliases, vec!["c"]);
+/// ```
+pub fn complete(text: &str, cursor: usize) -> Completions {
+    let mut cursor = cursor.min(text.len());
+    while !text.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    let prefix = &text[..cursor];
+    let word_start = prefix
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(cursor, |(idx, _)| idx);
+    let partial = &prefix[word_start..];
+
+    let Some(expected) = expected_tokens(&prefix[..word_start]) else {
+        return Completions {
+            partial: partial.to_string(),
+            ..Completions::default()
+        };
+    };
+
+    let matches_partial = |candidate: &str| {
+        candidate.len() >= partial.len()
+            && candidate.is_char_boundary(partial.len())
+            && candidate[..partial.len()].eq_ignore_ascii_case(partial)
+    };
+
+    let identifier_expected = expected
+        .iter()
+        .any(|t| IDENTIFIER_TERMINALS.contains(&t.as_str()));
+    let tokens = expected
+        .into_iter()
+        .filter(|t| {
+            !IDENTIFIER_TERMINALS.contains(&t.as_str()) && !LITERAL_TERMINALS.contains(&t.as_str())
+        })
+        .filter(|t| matches_partial(t))
+        .collect();
+    let aliases = if identifier_expected {
+        aliases(text, word_start..cursor)
+            .into_iter()
+            .filter(|alias| matches_partial(alias))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    Completions {
+        partial: partial.to_string(),
+        tokens,
+        aliases,
+    }
+}
+
+/// Collects the names bound by `AS` and `AT` in `text`, ignoring any token within `exclude`
+/// (i.e., the word being completed).
+fn aliases(text: &str, exclude: std::ops::Range<usize>) -> Vec<String> {
+    let mut offsets = LineOffsetTracker::default();
+    let mut aliases: Vec<String> = vec![];
+    let mut after_as = false;
+    for result in PartiqlLexer::new(text, &mut offsets) {
+        let Ok((start, tok, _)) = result else {
+            after_as = false;
+            continue;
+        };
+        let start = start.to_usize();
+        if after_as && !exclude.contains(&start) {
+            let alias = match tok {
+                Token::UnquotedIdent(id) | Token::QuotedIdent(id) => Some(id),
+                _ => None,
+            };
+            if let Some(alias) = alias {
+                if !aliases.iter().any(|a| a == alias) {
+                    aliases.push(alias.to_string());
+                }
+            }
+        }
+        after_as = matches!(tok, Token::As | Token::At);
+    }
+    aliases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords() {
+        let completions = complete("SELECT a FROM t ", 16);
+        for kw in ["WHERE", "GROUP", "ORDER", "LIMIT", "AS", "UNION"] {
+            assert!(completions.tokens.contains(&kw.to_string()), "{kw}");
+        }
+        assert!(!completions.tokens.contains(&"SELECT".to_string()));
+
+        let completions = complete("SELECT a FROM t gr", 18);
+        assert_eq!(completions.partial, "gr");
+        assert_eq!(completions.tokens, vec!["GROUP"]);
+    }
+
+    #[test]
+    fn aliases() {
+        let text = "SELECT o. FROM orders AS o, o.items AS item AT idx";
+        let completions = complete(text, 7);
+        assert_eq!(completions.aliases, vec!["o", "item", "idx"]);
+
+        let completions = complete("SELECT it FROM orders AS o, o.items AS item", 9);
+        assert_eq!(completions.partial, "it");
+        assert_eq!(completions.aliases, vec!["item"]);
+
+        // no identifiers may follow `SELECT a FROM t AS x `
+        let completions = complete("SELECT a FROM t AS x ", 21);
+        assert!(completions.aliases.is_empty());
+    }
+
+    #[test]
+    fn cursor_out_of_range() {
+        let completions = complete("SELECT", 100);
+        assert_eq!(completions.partial, "SELECT");
+        assert!(completions.tokens.contains(&"SELECT".to_string()));
+    }
+}
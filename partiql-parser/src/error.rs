@@ -25,14 +25,36 @@ pub enum LexError<'input> {
     /// Embedded Ion value is not properly terminated.
     #[error("Lexing error: unterminated ion literal")]
     UnterminatedIonLiteral,
+    /// Embedded Ion value is not valid Ion.
+    #[error("Lexing error: invalid ion literal: {}", .0)]
+    InvalidIonLiteral(Cow<'input, str>),
     /// Comment is not properly terminated.
     #[error("Lexing error: unterminated comment")]
     UnterminatedComment,
+    /// Escape sequence in a string literal is not valid.
+    #[error("Lexing error: invalid escape sequence `{}`", .0)]
+    InvalidEscapeSequence(Cow<'input, str>),
     /// Any other lexing error.
     #[error("Lexing error: unknown error")]
     Unknown,
 }
 
+impl LexError<'_> {
+    /// Converts the error into one that owns its text.
+    pub fn into_owned(self) -> LexError<'static> {
+        match self {
+            LexError::InvalidInput(s) => LexError::InvalidInput(s.into_owned().into()),
+            LexError::UnterminatedIonLiteral => LexError::UnterminatedIonLiteral,
+            LexError::InvalidIonLiteral(s) => LexError::InvalidIonLiteral(s.into_owned().into()),
+            LexError::UnterminatedComment => LexError::UnterminatedComment,
+            LexError::InvalidEscapeSequence(s) => {
+                LexError::InvalidEscapeSequence(s.into_owned().into())
+            }
+            LexError::Unknown => LexError::Unknown,
+        }
+    }
+}
+
 /// Errors in the syntactic structure of a PartiQL query.
 ///
 /// ### Notes
@@ -57,9 +79,19 @@ where
     Unknown(Loc),
 
     /// There was a token that was not expected
-    #[error("Unexpected token `{}` at `{}`", _0.inner.token, _0.location)]
+    #[error(
+        "Unexpected token `{}` at `{}`{}{}",
+        _0.inner.token,
+        _0.location,
+        fmt_expected(&_0.inner.expected),
+        fmt_suggestion(&_0.inner.suggestion)
+    )]
     UnexpectedToken(UnexpectedToken<'input, Loc>),
 
+    /// The nesting of delimiters (e.g., parentheses), prefix operators and set operators exceeds the maximum depth, which is given
+    #[error("Nesting depth exceeds the maximum of {} at `{}`", _0.inner, _0.location)]
+    NestingTooDeep(Located<usize, Loc>),
+
     /// There was an error lexing the input
     #[error("{} at `{}`", _0.inner, _0.location)]
     LexicalError(Located<LexError<'input>, Loc>),
@@ -69,15 +101,65 @@ where
     IllegalState(String),
 }
 
+/// The token of a [`ParseError::UnexpectedToken`] along with the tokens expected in its place.
+///
+/// ### Notes
+/// This is marked `#[non_exhaustive]`, to reserve the right to add more fields in the future.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub struct UnexpectedTokenData<'input> {
     /// The unexpected token
     pub token: Cow<'input, str>,
-    // TODO expected: ...,
+    /// The tokens that were expected in its place, e.g., `FROM` or `UnquotedIdent`
+    pub expected: Vec<String>,
+    /// A keyword likely misspelled as the token or the identifier preceding it, if any
+    pub suggestion: Option<Suggestion>,
 }
 pub type UnexpectedToken<'input, L> = Located<UnexpectedTokenData<'input>, L>;
 
+impl UnexpectedTokenData<'_> {
+    /// Converts the data into one that owns its text.
+    pub fn into_owned(self) -> UnexpectedTokenData<'static> {
+        UnexpectedTokenData {
+            token: Cow::Owned(self.token.into_owned()),
+            expected: self.expected,
+            suggestion: self.suggestion,
+        }
+    }
+}
+
+/// A keyword suggested in place of an identifier that is likely a misspelling of it, e.g., `SELECT`
+/// in place of `SELCT`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Suggestion {
+    /// The misspelled identifier
+    pub identifier: String,
+    /// The suggested keyword
+    pub keyword: String,
+}
+
+/// Formats the tokens `expected` in place of an unexpected token as a suffix of its error message.
+fn fmt_expected(expected: &[String]) -> String {
+    match expected {
+        [] => String::new(),
+        [token] => format!("; expected `{token}`"),
+        tokens => format!("; expected one of `{}`", tokens.join("`, `")),
+    }
+}
+
+/// Formats the keyword `suggestion` as a suffix of an error message.
+fn fmt_suggestion(suggestion: &Option<Suggestion>) -> String {
+    match suggestion {
+        None => String::new(),
+        Some(Suggestion {
+            identifier,
+            keyword,
+        }) => format!("; did you mean `{keyword}` instead of `{identifier}`?"),
+    }
+}
+
 impl<'input, Loc: Debug> ParseError<'input, Loc>
 where
     Loc: Display,
@@ -92,11 +174,35 @@ where
             ParseError::SyntaxError(l) => ParseError::SyntaxError(l.map_loc(tx)),
             ParseError::UnexpectedEndOfInput => ParseError::UnexpectedEndOfInput,
             ParseError::UnexpectedToken(l) => ParseError::UnexpectedToken(l.map_loc(tx)),
+            ParseError::NestingTooDeep(l) => ParseError::NestingTooDeep(l.map_loc(tx)),
             ParseError::LexicalError(l) => ParseError::LexicalError(l.map_loc(tx)),
             ParseError::IllegalState(s) => ParseError::IllegalState(s),
             _ => ParseError::IllegalState("Unhandled internal error".to_string()),
         }
     }
+
+    /// Converts the error into one that owns its text.
+    pub fn into_owned(self) -> ParseError<'static, Loc> {
+        match self {
+            ParseError::SyntaxError(l) => ParseError::SyntaxError(l),
+            ParseError::UnexpectedEndOfInput => ParseError::UnexpectedEndOfInput,
+            ParseError::Unknown(l) => ParseError::Unknown(l),
+            ParseError::UnexpectedToken(Located { inner, location }) => {
+                ParseError::UnexpectedToken(Located {
+                    inner: inner.into_owned(),
+                    location,
+                })
+            }
+            ParseError::NestingTooDeep(l) => ParseError::NestingTooDeep(l),
+            ParseError::LexicalError(Located { inner, location }) => {
+                ParseError::LexicalError(Located {
+                    inner: inner.into_owned(),
+                    location,
+                })
+            }
+            ParseError::IllegalState(s) => ParseError::IllegalState(s),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -118,14 +224,77 @@ mod tests {
     #[test]
     fn unexpected_token() {
         let e1 = ParseError::UnexpectedToken(
-            UnexpectedTokenData { token: "/".into() }
-                .to_located(BytePosition(0.into())..ByteOffset::from(1).into()),
+            UnexpectedTokenData {
+                token: "/".into(),
+                expected: vec![],
+                suggestion: None,
+            }
+            .to_located(BytePosition(0.into())..ByteOffset::from(1).into()),
         );
 
         let e2 = e1.map_loc(|x| BytePosition(x.0 + 1));
         assert_eq!(e2.to_string(), "Unexpected token `/` at `(b1..b2)`")
     }
 
+    #[test]
+    fn unexpected_token_expected() {
+        let location = BytePosition(0.into())..ByteOffset::from(1).into();
+        let e1 = ParseError::UnexpectedToken(
+            UnexpectedTokenData {
+                token: "/".into(),
+                expected: vec!["FROM".to_string()],
+                suggestion: None,
+            }
+            .to_located(location.clone()),
+        );
+        assert_eq!(
+            e1.to_string(),
+            "Unexpected token `/` at `(b0..b1)`; expected `FROM`"
+        );
+
+        let e2 = ParseError::UnexpectedToken(
+            UnexpectedTokenData {
+                token: "/".into(),
+                expected: vec!["FROM".to_string(), "WHERE".to_string()],
+                suggestion: None,
+            }
+            .to_located(location.clone()),
+        );
+        assert_eq!(
+            e2.to_string(),
+            "Unexpected token `/` at `(b0..b1)`; expected one of `FROM`, `WHERE`"
+        );
+
+        let e3 = ParseError::UnexpectedToken(
+            UnexpectedTokenData {
+                token: "a".into(),
+                expected: vec![],
+                suggestion: Some(Suggestion {
+                    identifier: "SELCT".to_string(),
+                    keyword: "SELECT".to_string(),
+                }),
+            }
+            .to_located(location),
+        );
+        assert_eq!(
+            e3.to_string(),
+            "Unexpected token `a` at `(b0..b1)`; did you mean `SELECT` instead of `SELCT`?"
+        );
+    }
+
+    #[test]
+    fn nesting_too_deep() {
+        let e1: ParseError<BytePosition> = ParseError::NestingTooDeep(
+            3.to_located(BytePosition(3.into())..ByteOffset::from(4).into()),
+        );
+
+        let e2 = e1.map_loc(|x| BytePosition(x.0 + 1));
+        assert_eq!(
+            e2.to_string(),
+            "Nesting depth exceeds the maximum of 3 at `(b4..b5)`"
+        )
+    }
+
     #[test]
     fn lexical_error() {
         let lex = LexError::InvalidInput("🤷".into())
@@ -142,6 +311,29 @@ mod tests {
         )
     }
 
+    #[test]
+    fn into_owned() {
+        let text = String::from("SELECT ; 🤷");
+        let location = BytePosition(7.into())..ByteOffset::from(8).into();
+        let errors = [
+            ParseError::UnexpectedToken(
+                UnexpectedTokenData {
+                    token: text[7..8].into(),
+                    expected: vec!["FROM".to_string()],
+                    suggestion: None,
+                }
+                .to_located(location.clone()),
+            ),
+            ParseError::LexicalError(LexError::InvalidInput(text[9..].into()).to_located(location)),
+        ];
+        let displayed: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        let owned: Vec<ParseError<'static, BytePosition>> =
+            errors.into_iter().map(ParseError::into_owned).collect();
+        drop(text);
+        let owned: Vec<_> = owned.iter().map(ToString::to_string).collect();
+        assert_eq!(owned, displayed);
+    }
+
     #[test]
     fn illegal_state() {
         let e1: ParseError<BytePosition> = ParseError::IllegalState("uh oh".to_string());
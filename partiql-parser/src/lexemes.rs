@@ -0,0 +1,129 @@
+//! Lexing of PartiQL text into classified tokens, e.g., for syntax highlighting.
+
+use crate::lexer::PartiqlLexer;
+use crate::{Lexeme, ParseError};
+use partiql_source_map::line_offset_tracker::LineOffsetTracker;
+use partiql_source_map::location::Location;
+
+/// The [`Lexeme`]s of PartiQL text, or the lexical errors in it, in order of their locations.
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct Lexed<'input> {
+    pub text: &'input str,
+    pub offsets: LineOffsetTracker,
+    /// The lexemes, including comments; an error does not prevent lexing the text following it.
+    pub lexemes: Vec<Result<Lexeme<'input>, ParseError<'input>>>,
+}
+
+/// Lex `text` into [`Lexeme`]s, each classified by a [`TokenKind`](crate::TokenKind), without
+/// parsing it.
+///
+/// # Example
+///
+/// ```
+/// use partiql_parser::{lex_partiql, TokenKind};
+///
+/// let lexed = lex_partiql("SELECT a.b -- comment\nFROM t WHERE c = 'x'");
+/// let kinds: Vec<_> = lexed
+///     .lexemes
+///     .iter()
+///     .map(|lexeme| {
+///         let lexeme = lexeme.as_ref().unwrap();
+///         (lexeme.kind, lexeme.text.as_ref())
+///     })
+///     .collect();
+/// assert_eq!(
+///     kinds[..5],
+///     [
+///         (TokenKind::Keyword, "SELECT"),
+///         (TokenKind::Identifier, "a"),
+///         (TokenKind::Operator, "."),
+///         (TokenKind::Identifier, "b"),
+///         (TokenKind::Comment, "-- comment"),
+///     ]
+/// );
+/// assert_eq!(kinds.last(), Some(&(TokenKind::Literal, "'x'")));
+/// assert_eq!(lexed.offsets.num_lines(), 2);
+/// ```
+pub fn lex_partiql(text: &str) -> Lexed<'_> {
+    let mut offsets = LineOffsetTracker::default();
+    let lexemes = PartiqlLexer::new(text, &mut offsets)
+        .map(|result| {
+            result.map(|(start, token, end)| Lexeme {
+                kind: token.kind(),
+                text: text[start.to_usize()..end.to_usize()].into(),
+                location: Location {
+                    start: start.into(),
+                    end: end.into(),
+                },
+            })
+        })
+        .collect();
+    Lexed {
+        text,
+        offsets,
+        lexemes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenKind;
+
+    fn kinds(text: &str) -> Vec<(TokenKind, String)> {
+        lex_partiql(text)
+            .lexemes
+            .into_iter()
+            .map(|lexeme| {
+                let lexeme = lexeme.unwrap();
+                (lexeme.kind, lexeme.text.into_owned())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classification() {
+        use TokenKind::*;
+        let expected = [
+            (Keyword, "SELECT"),
+            (Keyword, "DISTINCT"),
+            (Identifier, "\"q\""),
+            (Operator, ","),
+            (Identifier, "@x"),
+            (Operator, "||"),
+            (Literal, "1.5e3"),
+            (Operator, "("),
+            (Literal, "NULL"),
+            (Operator, ")"),
+            (Comment, "/* c */"),
+            (Keyword, "FROM"),
+            (Operator, "<<"),
+            (Literal, "{a: 1}"),
+            (Operator, ","),
+            (Literal, "TRUE"),
+            (Operator, ">>"),
+            (Keyword, "WHERE"),
+            (Identifier, "y"),
+            (Operator, "="),
+            (Operator, "?"),
+        ];
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(kind, text)| (kind, text.to_string()))
+            .collect();
+        assert_eq!(
+            kinds("SELECT DISTINCT \"q\", @x || 1.5e3 (NULL) /* c */ FROM <<`{a: 1}`, TRUE>> WHERE y = ?"),
+            expected
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let lexed = lex_partiql("a # b");
+        assert_eq!(lexed.lexemes.len(), 3);
+        assert!(lexed.lexemes[0].is_ok());
+        assert!(matches!(lexed.lexemes[1], Err(ParseError::LexicalError(_))));
+        assert_eq!(lexed.lexemes[2].as_ref().unwrap().text, "b");
+    }
+}
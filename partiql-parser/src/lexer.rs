@@ -1,4 +1,4 @@
-use partiql_source_map::location::{ByteOffset, BytePosition, ToLocated};
+use partiql_source_map::location::{ByteOffset, BytePosition, Location, ToLocated};
 use std::borrow::Cow;
 
 use logos::{Logos, Span};
@@ -9,8 +9,123 @@ use std::fmt;
 use std::fmt::Formatter;
 
 use crate::error::{LexError, ParseError};
+use crate::{TokenKind, Trivia, TriviaKind};
 use partiql_source_map::line_offset_tracker::LineOffsetTracker;
 
+/// Decodes the text `s` between the quotes of a string literal: each doubled quote (`''`) is a
+/// quote and, if `escapes` is set, each backslash escape sequence (i.e., `\b`, `\f`, `\n`, `\r`,
+/// `\t`, `\0`, `\'`, `\"`, `\\`, `\/`, or `\u` followed by 4 hexadecimal digits) is the character
+/// it denotes.
+pub(crate) fn decode_string(s: &str, escapes: bool) -> Result<Cow<'_, str>, LexError<'_>> {
+    if !(s.contains('\'') || escapes && s.contains('\\')) {
+        return Ok(Cow::Borrowed(s));
+    }
+
+    let invalid = |at: usize, len: usize| {
+        let sequence = s[at..].chars().take(len).collect::<String>();
+        LexError::InvalidEscapeSequence(sequence.into())
+    };
+    let mut decoded = String::with_capacity(s.len());
+    let mut chars = s.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        let c = match c {
+            '\'' => {
+                chars.next_if(|(_, c)| *c == '\'');
+                '\''
+            }
+            '\\' if escapes => match chars.next().map(|(_, c)| c) {
+                Some('b') => '\u{8}',
+                Some('f') => '\u{c}',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some(c @ ('\'' | '"' | '\\' | '/')) => c,
+                Some('u') => {
+                    let hex: String = s[at + 2..].chars().take(4).collect();
+                    let c = (hex.len() == 4 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                        .then(|| u32::from_str_radix(&hex, 16).ok())
+                        .flatten()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| invalid(at, 6))?;
+                    chars.nth(3);
+                    c
+                }
+                _ => return Err(invalid(at, 2)),
+            },
+            c => c,
+        };
+        decoded.push(c);
+    }
+    Ok(Cow::Owned(decoded))
+}
+
+/// Validates the text `ion` between the backticks of an embedded Ion literal, returning the error
+/// and the offset into `ion` at which it occurs if it is not a single valid Ion value. Embedded Ion
+/// is only validated with the `ion` feature enabled.
+pub(crate) fn validate_ion(ion: &str) -> Result<(), (usize, LexError<'static>)> {
+    #[cfg(feature = "ion")]
+    match ion_rs::element::Element::read_all(ion) {
+        Ok(values) if values.len() > 1 => {
+            let error = format!("expected a single Ion value, found {}", values.len());
+            return Err((0, LexError::InvalidIonLiteral(error.into())));
+        }
+        Ok(_) => {}
+        Err(error) => {
+            let offset = match &error {
+                ion_rs::result::IonError::Incomplete { position, .. } => position.byte_offset(),
+                ion_rs::result::IonError::DecodingError { description } => {
+                    decoding_error_offset(ion, description).unwrap_or(0)
+                }
+                _ => 0,
+            };
+            let error = LexError::InvalidIonLiteral(error.to_string().into());
+            return Err((offset.min(ion.len()), error));
+        }
+    }
+    #[cfg(not(feature = "ion"))]
+    let _ = ion;
+    Ok(())
+}
+
+/// The offset into `ion` at which Ion fails to decode, as described by `description`.
+///
+/// ion-rs describes decoding errors by the number of lines it loaded, e.g., `near line 1`, and
+/// quotes the text remaining from the failure to the end of the loaded lines, e.g., `' b 2}'`. The
+/// offset is that of the first non-whitespace character of the remaining text.
+#[cfg(feature = "ion")]
+fn decoding_error_offset(ion: &str, description: &str) -> Option<usize> {
+    let (_, line) = description.rsplit_once("line ")?;
+    let lines: usize = line
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    // the loaded lines end with the newline of the last line, but for the last line of `ion`
+    let loaded = lines
+        .checked_sub(1)
+        .and_then(|line| ion.match_indices('\n').nth(line))
+        .map(|(newline, _)| newline + 1);
+    let (start, end) = loaded.into_iter().chain([ion.len()]).find_map(|end| {
+        (end.saturating_sub(description.len())..end)
+            .filter(|start| ion.is_char_boundary(*start))
+            .find(|start| description.contains(&format!("'{}'", &ion[*start..end])))
+            .map(|start| (start, end))
+    })?;
+    let remaining = &ion[start..end];
+    Some(start + remaining.len() - remaining.trim_start().len())
+}
+
+/// Parses the text `s` of an integer literal, which is decimal, hexadecimal (prefixed with `0x`),
+/// or binary (prefixed with `0b`).
+pub(crate) fn parse_int(s: &str) -> Result<i64, std::num::ParseIntError> {
+    match s.get(..2) {
+        Some("0x" | "0X") => i64::from_str_radix(&s[2..], 16),
+        Some("0b" | "0B") => i64::from_str_radix(&s[2..], 2),
+        _ => s.parse(),
+    }
+}
+
 /// A 3-tuple of (start, `Tok`, end) denoting a token and it start and end offsets.
 pub type Spanned<Tok, Loc> = (Loc, Tok, Loc);
 /// A [`Result`] of a [`Spanned`] token.
@@ -208,7 +323,13 @@ impl<'input, 'tracker> EmbeddedIonLexer<'input, 'tracker> {
                                     let offset: ByteOffset = embed.start.into();
                                     return Some(Err((s + offset, err, e + offset)));
                                 }
-                                None => unreachable!(),
+                                None => {
+                                    return Some(Err((
+                                        embed.start.into(),
+                                        LexError::UnterminatedComment,
+                                        embed.end.into(),
+                                    )));
+                                }
                             }
                         }
                         Some(EmbeddedIonToken::LongString) => {
@@ -489,6 +610,8 @@ pub enum Token<'input> {
     QuotedAtIdentifier(&'input str),
 
     #[regex("[0-9]+", |lex| lex.slice())]
+    #[regex("0[xX][0-9a-fA-F]+", |lex| lex.slice())]
+    #[regex("0[bB][01]+", |lex| lex.slice())]
     Int(&'input str),
 
     #[regex("[0-9]+\\.[0-9]*([eE][-+]?[0-9]+)", |lex| lex.slice())]
@@ -502,7 +625,7 @@ pub enum Token<'input> {
 
     // strings are single-quoted in SQL/PartiQL
     #[regex(r#"'([^'\\]|\\t|\\u|\\n|\\'|\\|(?:''))*'"#,
-        |lex| lex.slice().trim_matches('\''))]
+        |lex| { let s = lex.slice(); &s[1..s.len() - 1] })]
     String(&'input str),
 
     #[token("`")]
@@ -526,16 +649,24 @@ pub enum Token<'input> {
     By,
     #[regex("(?i:Case)")]
     Case,
+    #[regex("(?i:Create)")]
+    Create,
     #[regex("(?i:Cross)")]
     Cross,
+    #[regex("(?i:Current)")]
+    Current,
     #[regex("(?i:Cycle)")]
     Cycle,
     #[regex("(?i:Date)")]
     Date,
+    #[regex("(?i:Delete)")]
+    Delete,
     #[regex("(?i:Desc)")]
     Desc,
     #[regex("(?i:Distinct)")]
     Distinct,
+    #[regex("(?i:Drop)")]
+    Drop,
     #[regex("(?i:Else)")]
     Else,
     #[regex("(?i:End)")]
@@ -544,10 +675,18 @@ pub enum Token<'input> {
     Escape,
     #[regex("(?i:Except)")]
     Except,
+    #[regex("(?i:Exec)")]
+    Exec,
+    #[regex("(?i:Exists)")]
+    Exists,
     #[regex("(?i:False)")]
     False,
+    // `FETCH`, `NEXT`, and `ONLY` are lexed as identifiers; see `ContextualKeywordLexer`.
+    Fetch,
     #[regex("(?i:First)")]
     First,
+    #[regex("(?i:Following)")]
+    Following,
     #[regex("(?i:For)")]
     For,
     #[regex("(?i:Full)")]
@@ -560,12 +699,20 @@ pub enum Token<'input> {
     Having,
     #[regex("(?i:In)")]
     In,
+    #[regex("(?i:Index)")]
+    Index,
     #[regex("(?i:Inner)")]
     Inner,
+    #[regex("(?i:Insert)")]
+    Insert,
+    #[regex("(?i:Into)")]
+    Into,
     #[regex("(?i:Is)")]
     Is,
     #[regex("(?i:Intersect)")]
     Intersect,
+    #[regex("(?i:Interval)")]
+    Interval,
     #[regex("(?i:Join)")]
     Join,
     #[regex("(?i:Last)")]
@@ -574,6 +721,8 @@ pub enum Token<'input> {
     Lateral,
     #[regex("(?i:Left)")]
     Left,
+    #[regex("(?i:Let)")]
+    Let,
     #[regex("(?i:Like)")]
     Like,
     #[regex("(?i:Limit)")]
@@ -582,6 +731,7 @@ pub enum Token<'input> {
     Missing,
     #[regex("(?i:Natural)")]
     Natural,
+    Next,
     #[regex("(?i:Not)")]
     Not,
     #[regex("(?i:Null)")]
@@ -592,26 +742,45 @@ pub enum Token<'input> {
     Offset,
     #[regex("(?i:On)")]
     On,
+    Only,
     #[regex("(?i:Or)")]
     Or,
     #[regex("(?i:Order)")]
     Order,
     #[regex("(?i:Outer)")]
     Outer,
+    #[regex("(?i:Over)")]
+    Over,
     #[regex("(?i:Partial)")]
     Partial,
+    #[regex("(?i:Partition)")]
+    Partition,
     #[regex("(?i:Pivot)")]
     Pivot,
+    #[regex("(?i:Preceding)")]
+    Preceding,
     #[regex("(?i:Preserve)")]
     Preserve,
+    #[regex("(?i:Range)")]
+    Range,
     #[regex("(?i:Right)")]
     Right,
     #[regex("(?i:Recursive)")]
     Recursive,
+    #[regex("(?i:Remove)")]
+    Remove,
+    #[regex("(?i:Returning)")]
+    Returning,
+    #[regex("(?i:Row)")]
+    Row,
+    #[regex("(?i:Rows)")]
+    Rows,
     #[regex("(?i:Select)")]
     Select,
     #[regex("(?i:Search)")]
     Search,
+    #[regex("(?i:Set)")]
+    Set,
     #[regex("(?i:Table)")]
     Table,
     #[regex("(?i:Time)")]
@@ -620,12 +789,18 @@ pub enum Token<'input> {
     Timestamp,
     #[regex("(?i:Then)")]
     Then,
+    #[regex("(?i:To)")]
+    To,
     #[regex("(?i:True)")]
     True,
+    #[regex("(?i:Unbounded)")]
+    Unbounded,
     #[regex("(?i:Union)")]
     Union,
     #[regex("(?i:Unpivot)")]
     Unpivot,
+    #[regex("(?i:Update)")]
+    Update,
     #[regex("(?i:Using)")]
     Using,
     #[regex("(?i:Value)")]
@@ -645,6 +820,56 @@ pub enum Token<'input> {
 }
 
 impl<'input> Token<'input> {
+    /// The class of the token; see [`TokenKind`].
+    pub(crate) fn kind(&self) -> TokenKind {
+        match self {
+            Token::CommentLine(_) | Token::CommentBlock(_) => TokenKind::Comment,
+            Token::UnquotedIdent(_)
+            | Token::QuotedIdent(_)
+            | Token::UnquotedAtIdentifier(_)
+            | Token::QuotedAtIdentifier(_) => TokenKind::Identifier,
+            Token::Int(_)
+            | Token::ExpReal(_)
+            | Token::Real(_)
+            | Token::String(_)
+            | Token::Ion(_)
+            | Token::True
+            | Token::False
+            | Token::Null
+            | Token::Missing => TokenKind::Literal,
+            Token::OpenSquare
+            | Token::CloseSquare
+            | Token::OpenCurly
+            | Token::CloseCurly
+            | Token::OpenParen
+            | Token::CloseParen
+            | Token::OpenDblAngle
+            | Token::CloseDblAngle
+            | Token::Comma
+            | Token::Semicolon
+            | Token::Colon
+            | Token::EqualEqual
+            | Token::BangEqual
+            | Token::LessGreater
+            | Token::LessEqual
+            | Token::GreaterEqual
+            | Token::Equal
+            | Token::LessThan
+            | Token::GreaterThan
+            | Token::Minus
+            | Token::Plus
+            | Token::Star
+            | Token::SqlParameter
+            | Token::Percent
+            | Token::Slash
+            | Token::Caret
+            | Token::Period
+            | Token::DblPipe => TokenKind::Operator,
+            // The remaining tokens used internally are never lexed
+            _ => TokenKind::Keyword,
+        }
+    }
+
     pub fn is_keyword(&self) -> bool {
         matches!(
             self,
@@ -656,52 +881,79 @@ impl<'input> Token<'input> {
                 | Token::Between
                 | Token::By
                 | Token::Case
+                | Token::Create
                 | Token::Cross
+                | Token::Current
                 | Token::Cycle
                 | Token::Date
+                | Token::Delete
                 | Token::Desc
                 | Token::Distinct
+                | Token::Drop
                 | Token::Escape
                 | Token::Except
+                | Token::Exec
+                | Token::Exists
+                | Token::Fetch
                 | Token::First
+                | Token::Following
                 | Token::For
                 | Token::Full
                 | Token::From
                 | Token::Group
                 | Token::Having
                 | Token::In
+                | Token::Index
                 | Token::Inner
+                | Token::Insert
+                | Token::Into
                 | Token::Is
                 | Token::Intersect
+                | Token::Interval
                 | Token::Join
                 | Token::Last
                 | Token::Lateral
                 | Token::Left
+                | Token::Let
                 | Token::Like
                 | Token::Limit
                 | Token::Missing
                 | Token::Natural
+                | Token::Next
                 | Token::Not
                 | Token::Null
                 | Token::Nulls
                 | Token::Offset
                 | Token::On
+                | Token::Only
                 | Token::Or
                 | Token::Order
                 | Token::Outer
+                | Token::Over
                 | Token::Partial
+                | Token::Partition
                 | Token::Pivot
+                | Token::Preceding
                 | Token::Preserve
+                | Token::Range
                 | Token::Right
                 | Token::Recursive
+                | Token::Remove
+                | Token::Returning
+                | Token::Row
+                | Token::Rows
                 | Token::Search
                 | Token::Select
+                | Token::Set
                 | Token::Table
                 | Token::Time
                 | Token::Timestamp
                 | Token::Then
+                | Token::To
+                | Token::Unbounded
                 | Token::Union
                 | Token::Unpivot
+                | Token::Update
                 | Token::Using
                 | Token::Value
                 | Token::Values
@@ -766,56 +1018,83 @@ impl<'input> fmt::Display for Token<'input> {
             | Token::Between
             | Token::By
             | Token::Case
+            | Token::Create
             | Token::Cross
+            | Token::Current
             | Token::Cycle
             | Token::Date
+            | Token::Delete
             | Token::Desc
             | Token::Distinct
+            | Token::Drop
             | Token::Else
             | Token::End
             | Token::Escape
             | Token::Except
+            | Token::Exec
+            | Token::Exists
             | Token::False
+            | Token::Fetch
             | Token::First
+            | Token::Following
             | Token::For
             | Token::Full
             | Token::From
             | Token::Group
             | Token::Having
             | Token::In
+            | Token::Index
             | Token::Inner
+            | Token::Insert
+            | Token::Into
             | Token::Is
             | Token::Intersect
+            | Token::Interval
             | Token::Join
             | Token::Last
             | Token::Lateral
             | Token::Left
+            | Token::Let
             | Token::Like
             | Token::Limit
             | Token::Missing
             | Token::Natural
+            | Token::Next
             | Token::Not
             | Token::Null
             | Token::Nulls
             | Token::Offset
             | Token::On
+            | Token::Only
             | Token::Or
             | Token::Order
             | Token::Outer
+            | Token::Over
             | Token::Partial
+            | Token::Partition
             | Token::Pivot
+            | Token::Preceding
             | Token::Preserve
+            | Token::Range
             | Token::Right
             | Token::Recursive
+            | Token::Remove
+            | Token::Returning
+            | Token::Row
+            | Token::Rows
             | Token::Search
             | Token::Select
+            | Token::Set
             | Token::Table
             | Token::Time
             | Token::Timestamp
             | Token::Then
+            | Token::To
             | Token::True
+            | Token::Unbounded
             | Token::Union
             | Token::Unpivot
+            | Token::Update
             | Token::Using
             | Token::Value
             | Token::Values
@@ -830,12 +1109,13 @@ impl<'input> fmt::Display for Token<'input> {
     }
 }
 
-/// A lexer that wraps another lexer and skips comments.
+/// A lexer that wraps another lexer and skips comments, optionally preserving them as [`Trivia`].
 pub(crate) struct CommentSkippingLexer<'input, L>
 where
     L: Iterator<Item = LexResult<'input>>,
 {
     lexer: L,
+    trivia: Option<Vec<Trivia<'input>>>,
 }
 
 impl<'input, L> CommentSkippingLexer<'input, L>
@@ -845,7 +1125,21 @@ where
     /// Creates a new CommentSkippingLexer wrapping `lexer`
     #[inline]
     pub fn new(lexer: L) -> Self {
-        Self { lexer }
+        Self {
+            lexer,
+            trivia: None,
+        }
+    }
+
+    /// Toggles the preservation of the skipped comments; see [`Self::into_trivia`].
+    pub fn preserving(mut self, enabled: bool) -> Self {
+        self.trivia = enabled.then(Vec::new);
+        self
+    }
+
+    /// The comments skipped so far, if preserving them.
+    pub fn into_trivia(self) -> Vec<Trivia<'input>> {
+        self.trivia.unwrap_or_default()
     }
 }
 
@@ -859,14 +1153,254 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         'next_tok: loop {
             let next = self.lexer.next();
-            if matches!(
-                next,
-                Some(Ok((_, Token::CommentBlock(_) | Token::CommentLine(_), _)))
-            ) {
-                continue 'next_tok;
+            let (start, kind, text, end) = match next {
+                Some(Ok((start, Token::CommentLine(text), end))) => {
+                    (start, TriviaKind::LineComment, text, end)
+                }
+                Some(Ok((start, Token::CommentBlock(text), end))) => {
+                    (start, TriviaKind::BlockComment, text, end)
+                }
+                _ => return next,
+            };
+            if let Some(trivia) = &mut self.trivia {
+                trivia.push(Trivia {
+                    kind,
+                    text,
+                    location: Location {
+                        start: start.into(),
+                        end: end.into(),
+                    },
+                });
             }
+            continue 'next_tok;
+        }
+    }
+}
+
+/// A lexer wrapper that lexes the keywords of the `FETCH` clause (i.e., `FETCH`, `NEXT`, and `ONLY`),
+/// which are not reserved, as keywords only where they begin or continue the clause and as
+/// identifiers elsewhere, e.g., in `SELECT next FROM t AS only`.
+///
+/// `FETCH` is a keyword when followed by `FIRST` or `NEXT`, `NEXT` when following `FETCH`, and
+/// `ONLY` when following `ROW` or `ROWS`.
+///
+/// Other keywords (but for the literals `TRUE`, `FALSE`, `NULL`, and `MISSING`) are lexed as
+/// identifiers where they begin an item of a list, i.e., follow `SELECT`, its `ALL`, `DISTINCT`, or
+/// `VALUE`, or `,`, and are followed by `FROM`, `,`, `AS`, or `.`, as no keyword may, e.g., in
+/// `SELECT value, order.a AS limit FROM t`. Elsewhere in an expression, keywords are reserved, e.g.,
+/// in `SELECT value + 1 FROM t` or `SELECT a, t.b order FROM t`, unless quoted.
+pub(crate) struct ContextualKeywordLexer<'input, L>
+where
+    L: Iterator<Item = LexResult<'input>>,
+{
+    input: &'input str,
+    lexer: std::iter::Peekable<L>,
+    after_fetch: bool,
+    after_rows: bool,
+    before_item: bool,
+}
+
+impl<'input, L> ContextualKeywordLexer<'input, L>
+where
+    L: Iterator<Item = LexResult<'input>>,
+{
+    /// Creates a new ContextualKeywordLexer wrapping `lexer` of `input`
+    #[inline]
+    pub fn new(input: &'input str, lexer: L) -> Self {
+        Self {
+            input,
+            lexer: lexer.peekable(),
+            after_fetch: false,
+            after_rows: false,
+            before_item: false,
+        }
+    }
+}
+
+impl<'input, L> Iterator for ContextualKeywordLexer<'input, L>
+where
+    L: Iterator<Item = LexResult<'input>>,
+{
+    type Item = LexResult<'input>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut next = self.lexer.next();
+        if let Some(Ok((start, token, end))) = &mut next {
+            if self.before_item && token.kind() == TokenKind::Keyword {
+                let ends_item = matches!(
+                    self.lexer.peek(),
+                    Some(Ok((
+                        _,
+                        Token::From | Token::Comma | Token::As | Token::Period,
+                        _
+                    )))
+                );
+                if let (true, Some(id)) =
+                    (ends_item, self.input.get(start.to_usize()..end.to_usize()))
+                {
+                    *token = Token::UnquotedIdent(id);
+                }
+            }
+            if let Token::UnquotedIdent(id) = token {
+                if id.eq_ignore_ascii_case("fetch") {
+                    let begins_clause = match self.lexer.peek() {
+                        Some(Ok((_, Token::First, _))) => true,
+                        Some(Ok((_, Token::UnquotedIdent(next), _))) => {
+                            next.eq_ignore_ascii_case("next")
+                        }
+                        _ => false,
+                    };
+                    if begins_clause {
+                        *token = Token::Fetch;
+                    }
+                } else if self.after_fetch && id.eq_ignore_ascii_case("next") {
+                    *token = Token::Next;
+                } else if self.after_rows && id.eq_ignore_ascii_case("only") {
+                    *token = Token::Only;
+                }
+            }
+            self.after_fetch = matches!(token, Token::Fetch);
+            self.after_rows = matches!(token, Token::Row | Token::Rows);
+            self.before_item = matches!(
+                token,
+                Token::Select | Token::All | Token::Distinct | Token::Value | Token::Comma
+            );
+        }
+        next
+    }
+}
+
+/// A lexer wrapper that fails once the nesting of delimiters (e.g., parentheses), prefix operators
+/// and set operators in the lexed text exceeds a maximum depth.
+///
+/// Each run of consecutive prefix operators (e.g., `NOT NOT x` or `- -1`) counts as a level of
+/// nesting per operator, until the operand following them. Each set operator (e.g., `UNION`) counts
+/// as a level of nesting until the delimiters it is nested in close, as chains of set operators
+/// nest without delimiters. Chains of other binary operators (e.g., `1 + 1 + 1` or `a OR b OR c`)
+/// do not count towards the depth.
+pub(crate) struct NestingLimitingLexer<'input, L>
+where
+    L: Iterator<Item = LexResult<'input>>,
+{
+    lexer: L,
+    max_depth: Option<usize>,
+    depth: usize,
+    /// The nesting within each of the delimiters open, and outside of them.
+    levels: Vec<NestingLevel>,
+    failed: bool,
+}
+
+/// The nesting without delimiters lexed within a level of delimiters.
+#[derive(Default)]
+struct NestingLevel {
+    /// The number of consecutive prefix operators lexed since the last operand.
+    prefix_operators: usize,
+    /// The number of set operators lexed.
+    set_operators: usize,
+}
+
+impl NestingLevel {
+    #[inline]
+    fn depth(&self) -> usize {
+        self.prefix_operators + self.set_operators
+    }
+}
+
+impl<'input, L> NestingLimitingLexer<'input, L>
+where
+    L: Iterator<Item = LexResult<'input>>,
+{
+    /// Creates a new NestingLimitingLexer wrapping `lexer`, which fails at a depth over
+    /// `max_depth`, if any.
+    #[inline]
+    pub fn new(lexer: L, max_depth: Option<usize>) -> Self {
+        Self {
+            lexer,
+            max_depth,
+            depth: 0,
+            levels: vec![NestingLevel::default()],
+            failed: false,
+        }
+    }
+
+    /// Ends the current run of prefix operators, as an operand or other token follows them.
+    #[inline]
+    fn end_prefix_operators(&mut self) {
+        if let Some(level) = self.levels.last_mut() {
+            self.depth -= level.prefix_operators;
+            level.prefix_operators = 0;
+        }
+    }
+}
+
+impl<'input, L> Iterator for NestingLimitingLexer<'input, L>
+where
+    L: Iterator<Item = LexResult<'input>>,
+{
+    type Item = LexResult<'input>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let next = self.lexer.next();
+        let Some(max_depth) = self.max_depth else {
             return next;
+        };
+        let Some(Ok((start, token, end))) = &next else {
+            return next;
+        };
+        match token {
+            Token::OpenParen
+            | Token::OpenSquare
+            | Token::OpenCurly
+            | Token::OpenDblAngle
+            | Token::Case => {
+                self.depth += 1;
+                self.levels.push(NestingLevel::default());
+            }
+            Token::CloseParen
+            | Token::CloseSquare
+            | Token::CloseCurly
+            | Token::CloseDblAngle
+            | Token::End => {
+                if self.levels.len() > 1 {
+                    let level = self.levels.pop().unwrap_or_default();
+                    self.depth -= 1 + level.depth();
+                }
+                self.end_prefix_operators();
+                return next;
+            }
+            Token::Not | Token::Minus | Token::Plus => {
+                // A binary `-`, `+` or `NOT` (e.g., `NOT IN`) counts until the operand following
+                // it, which never takes the depth over that of a prefix operator in its place
+                self.depth += 1;
+                if let Some(level) = self.levels.last_mut() {
+                    level.prefix_operators += 1;
+                }
+            }
+            Token::Union | Token::Intersect | Token::Except => {
+                self.end_prefix_operators();
+                self.depth += 1;
+                if let Some(level) = self.levels.last_mut() {
+                    level.set_operators += 1;
+                }
+            }
+            _ => {
+                self.end_prefix_operators();
+                return next;
+            }
         }
+        if self.depth > max_depth {
+            self.failed = true;
+            let location = BytePosition::from(*start)..BytePosition::from(*end);
+            return Some(Err(ParseError::NestingTooDeep(
+                max_depth.to_located(location),
+            )));
+        }
+        next
     }
 }
 
@@ -880,6 +1414,77 @@ mod tests {
 
     use itertools::Itertools;
 
+    #[test]
+    fn decode_strings() {
+        assert_eq!(decode_string("foo", true).unwrap(), "foo");
+        assert_eq!(decode_string("it''s", false).unwrap(), "it's");
+        assert_eq!(decode_string("''", false).unwrap(), "'");
+        assert_eq!(decode_string(r"a\nb", false).unwrap(), r"a\nb");
+        assert_eq!(
+            decode_string(r"a\nb\t\\\'\u00e9\u00E9", true).unwrap(),
+            "a\nb\t\\'éé"
+        );
+        assert_eq!(
+            decode_string(r"\q", true),
+            Err(LexError::InvalidEscapeSequence(r"\q".into()))
+        );
+        assert_eq!(
+            decode_string(r"\u00g1", true),
+            Err(LexError::InvalidEscapeSequence(r"\u00g1".into()))
+        );
+        assert_eq!(
+            decode_string(r"\u12", true),
+            Err(LexError::InvalidEscapeSequence(r"\u12".into()))
+        );
+        assert_eq!(
+            decode_string(r"\ud83d", true),
+            Err(LexError::InvalidEscapeSequence(r"\ud83d".into()))
+        );
+    }
+
+    #[test]
+    fn ints() {
+        assert_eq!(parse_int("42"), Ok(42));
+        assert_eq!(parse_int("0x1F"), Ok(31));
+        assert_eq!(parse_int("0Xff"), Ok(255));
+        assert_eq!(parse_int("0b1010"), Ok(10));
+        assert!(parse_int("0x8000000000000000").is_err());
+
+        let mut offsets = LineOffsetTracker::default();
+        let toks: Vec<_> = PartiqlLexer::new("0x1F 0b1010 0b12 0x", &mut offsets)
+            .map(|r| r.map(|(_, t, _)| t))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            toks,
+            vec![
+                Token::Int("0x1F"),
+                Token::Int("0b1010"),
+                Token::Int("0b1"),
+                Token::Int("2"),
+                Token::Int("0"),
+                Token::UnquotedIdent("x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_strings() {
+        let mut offsets = LineOffsetTracker::default();
+        let toks: Vec<_> = PartiqlLexer::new("'''' 'it''s' ''", &mut offsets)
+            .map(|r| r.map(|(_, t, _)| t))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            toks,
+            vec![
+                Token::String("''"),
+                Token::String("it''s"),
+                Token::String("")
+            ]
+        );
+    }
+
     #[test]
     fn display() -> Result<(), ParseError<'static, BytePosition>> {
         let symbols =
@@ -888,7 +1493,7 @@ mod tests {
         let keywords =
             "WiTH Where Value uSiNg Unpivot UNION True Select right Preserve pivoT Outer Order Or \
              On Offset Nulls Null Not Natural Missing Limit Like Left Lateral Last Join \
-             Intersect Is Inner In Having Group From For Full First False Except Escape Desc \
+             Intersect Is Inner In Having Group From For Full First False Exists Except Escape Desc \
              Cross Table Time Timestamp Date By Between At As And Asc All Values Case When Then Else End";
         let symbols = symbols.split(' ').chain(primitives.split(' '));
         let keywords = keywords.split(' ');
@@ -909,7 +1514,7 @@ mod tests {
             "LATERAL", ".", "LAST", "||", "JOIN", ":", "INTERSECT", "--", "IS", "/**/", "INNER",
             "<unquoted_ident:UNQUOTED_IDENT>", "IN", "<quoted_ident:QUOTED_IDENT>", "HAVING",
             "<unquoted_atident:UNQUOTED_ATIDENT>", "GROUP", "<quoted_atident:QUOTED_ATIDENT>",
-            "FROM", "FOR", "FULL", "FIRST", "FALSE", "EXCEPT", "ESCAPE", "DESC", "CROSS", "TABLE",
+            "FROM", "FOR", "FULL", "FIRST", "FALSE", "EXISTS", "EXCEPT", "ESCAPE", "DESC", "CROSS", "TABLE",
             "TIME", "TIMESTAMP", "DATE", "BY", "BETWEEN", "AT", "AS", "AND", "ASC", "ALL", "VALUES",
             "CASE", "WHEN", "THEN", "ELSE", "END"
         ];
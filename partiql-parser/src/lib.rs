@@ -5,34 +5,103 @@
 //! # Usage
 //!
 //! ```
+//! # #[cfg(feature = "parser")]
+//! # fn main() {
+//! use partiql_ast::arena::Arena;
 //! use partiql_parser::{Parser, ParserError, ParserResult};
 //!
 //! let parser = Parser::default();
+//! let arena = Arena::new();
 //!
-//! let parsed = parser.parse("SELECT g FROM data GROUP BY a").expect("successful parse");
+//! let parsed = parser
+//!     .parse("SELECT g FROM data GROUP BY a", &arena)
+//!     .expect("successful parse");
 //!
-//! let errs: ParserError = parser.parse("SELECT").expect_err("expected error");
+//! let errs: ParserError = parser.parse("SELECT", &arena).expect_err("expected error");
 //!
-//! let errs_at: ParserError =
-//!     parser.parse("SELECT * FROM a AY a CROSS JOIN c AS c AT q").unwrap_err();
-//! assert_eq!(errs_at.errors[0].to_string(), "Unexpected token `<a:UNQUOTED_IDENT>` at `(b19..b20)`");
+//! let errs_at: ParserError = parser
+//!     .parse("SELECT * FROM a AY a CROSS JOIN c AS c AT q", &arena)
+//!     .unwrap_err();
+//! assert!(errs_at.errors[0]
+//!     .to_string()
+//!     .starts_with("Unexpected token `<a:UNQUOTED_IDENT>` at `(b19..b20)`; expected one of `,`, `AT`,"));
+//! # }
+//! # #[cfg(not(feature = "parser"))]
+//! # fn main() {}
 //! ```
 //!
+//! # Features
+//!
+//! - `parser` (default): parsing of PartiQL text into an AST. Without it, only the lexer is built
+//!   (i.e., [`lex_partiql`], [`StreamingLexer`], and [`normalize`]), without the grammar and the
+//!   dependencies of the parser (e.g., `partiql-ast`, `lalrpop-util`, and `regex`), e.g., for
+//!   embedders that only highlight or fingerprint statements.
+//! - `ion`: validation of embedded Ion literals while parsing.
+//! - `serde`: [serde](https://serde.rs) support for lexemes, errors, and parsed ASTs.
+//!
+//! # Panics
+//!
+//! Parsing never panics: any text, however malformed, parses to either a [`Parsed`] AST or a
+//! [`ParserError`]. The `clippy::panic`, `clippy::todo`, `clippy::unimplemented`, and
+//! `clippy::unreachable` lints are denied in this crate to uphold this, and the parser is fuzzed
+//! from the seed corpus in `fuzz/inputs/seed`.
+//!
+//! Parsing doesn't recurse, so it doesn't overflow the stack either. The ASTs it produces are
+//! traversed and dropped by recursion, however, so their nesting is limited (see
+//! [`ParserBuilder::max_nesting_depth`]), except for chains of binary operators other than set
+//! operators (e.g., `a = 1 OR a = 2 OR ...`): traversing or dropping the AST of such a chain that
+//! is tens of thousands of operators long may overflow the stack.
+//!
 //! [partiql]: https://partiql.org
 
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::panic,
+        clippy::todo,
+        clippy::unimplemented,
+        clippy::unreachable
+    )
+)]
+
+#[cfg(feature = "parser")]
+mod completion;
 mod error;
+mod lexemes;
+// the lexer supports the parser, e.g., decoding string literals, beyond lexing alone
+#[cfg_attr(not(feature = "parser"), allow(dead_code))]
 mod lexer;
+mod normalize;
+#[cfg(feature = "parser")]
 mod parse;
+#[cfg(feature = "parser")]
 mod preprocessor;
+mod stream;
+#[cfg(feature = "parser")]
+mod suggest;
+#[cfg(feature = "parser")]
 mod token_parser;
 
-use parse::{parse_partiql, AstData, ErrorData};
+#[cfg(feature = "parser")]
+pub use completion::{complete, Completions};
+pub use lexemes::{lex_partiql, Lexed};
+pub use normalize::{normalize, NormalizedQuery};
+#[cfg(feature = "parser")]
+use parse::{parse_partiql, parse_partiql_statement, AstData, ErrorData, ParserOptions};
+#[cfg(feature = "parser")]
+use partiql_ast::arena::Arena;
+#[cfg(feature = "parser")]
 use partiql_ast::ast;
 use partiql_source_map::line_offset_tracker::LineOffsetTracker;
-use partiql_source_map::location::BytePosition;
+#[cfg(feature = "parser")]
+use partiql_source_map::location::LineAndColumn;
+use partiql_source_map::location::{BytePosition, Location};
+#[cfg(feature = "parser")]
 use partiql_source_map::metadata::LocationMap;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+pub use stream::{StreamingLexError, StreamingLexer};
 
 /// [`std::error::Error`] type for errors in the lexical structure for the PartiQL parser.
 pub type LexicalError<'input> = error::LexError<'input>;
@@ -40,47 +109,358 @@ pub type LexicalError<'input> = error::LexError<'input>;
 /// [`std::error::Error`] type for errors in the syntactic structure for the PartiQL parser.
 pub type ParseError<'input> = error::ParseError<'input, BytePosition>;
 
+#[cfg(feature = "parser")]
 /// General [`Result`] type for the PartiQL [`Parser`].
 pub type ParserResult<'input> = Result<Parsed<'input>, ParserError<'input>>;
 
+#[cfg(feature = "parser")]
+/// [`Result`] type for [`Parser::parse_statement`].
+pub type StatementResult<'input> = Result<ParsedStatement<'input>, ParserError<'input>>;
+
+#[cfg(feature = "parser")]
 /// A PartiQL parser from statement strings to AST.
+///
+/// A parser with options other than the defaults is built with a [`ParserBuilder`].
 #[non_exhaustive]
 #[derive(Debug, Default)]
-pub struct Parser {}
+pub struct Parser {
+    options: ParserOptions,
+}
 
+#[cfg(feature = "parser")]
 impl Parser {
-    /// Parse a PartiQL statement into an AST.
-    pub fn parse<'input>(&self, text: &'input str) -> ParserResult<'input> {
-        match parse_partiql(text) {
+    /// Creates a [`ParserBuilder`] to configure a parser.
+    pub fn builder() -> ParserBuilder {
+        ParserBuilder::default()
+    }
+
+    /// Parse a PartiQL statement into an AST, whose nodes are allocated in `arena`.
+    pub fn parse<'input>(&self, text: &'input str, arena: &'input Arena) -> ParserResult<'input> {
+        match parse_partiql(text, arena, &self.options) {
             Ok(AstData {
                 ast,
                 locations,
                 offsets,
+                trivia,
             }) => Ok(Parsed {
                 text,
                 offsets,
                 ast,
                 locations,
+                trivia,
+            }),
+            Err(ErrorData {
+                errors, offsets, ..
+            }) => Err(ParserError {
+                text,
+                offsets,
+                errors,
+            }),
+        }
+    }
+
+    /// Parse a PartiQL statement, which may be a query, a data manipulation statement (e.g.,
+    /// `INSERT INTO tbl VALUE {'a': 1}`), a data definition statement (e.g.,
+    /// `CREATE TABLE tbl`), or a stored procedure call (e.g., `EXEC proc 1, 'a'`), into an AST.
+    pub fn parse_statement<'input>(
+        &self,
+        text: &'input str,
+        arena: &'input Arena,
+    ) -> StatementResult<'input> {
+        match parse_partiql_statement(text, arena, &self.options) {
+            Ok(AstData {
+                ast,
+                locations,
+                offsets,
+                trivia,
+            }) => Ok(ParsedStatement {
+                text,
+                offsets,
+                ast,
+                locations,
+                trivia,
             }),
-            Err(ErrorData { errors, offsets }) => Err(ParserError {
+            Err(ErrorData {
+                errors, offsets, ..
+            }) => Err(ParserError {
                 text,
                 offsets,
                 errors,
             }),
         }
     }
+
+    /// Parse a PartiQL statement as [`Parser::parse_statement`] does, but return the AST recovered
+    /// from syntax errors along with them, e.g., for tools that highlight or offer completions
+    /// for text as it is written.
+    ///
+    /// ```
+    /// use partiql_ast::arena::Arena;
+    /// use partiql_parser::Parser;
+    ///
+    /// let arena = Arena::new();
+    /// let parsed = Parser::default().parse_partial("SELECT a, ^ FROM t", &arena);
+    /// assert!(!parsed.errors.is_empty());
+    /// assert_eq!(parsed.ast.unwrap().to_string(), "SELECT a, <error> ^ <error> FROM t");
+    /// ```
+    pub fn parse_partial<'input>(
+        &self,
+        text: &'input str,
+        arena: &'input Arena,
+    ) -> PartialParse<'input> {
+        let (ast, locations, offsets, errors) =
+            match parse_partiql_statement(text, arena, &self.options) {
+                Ok(AstData {
+                    ast,
+                    locations,
+                    offsets,
+                    ..
+                }) => (Some(ast), locations, offsets, vec![]),
+                Err(ErrorData {
+                    errors,
+                    offsets,
+                    recovered,
+                }) => match recovered {
+                    Some(recovered) => (Some(recovered.ast), recovered.locations, offsets, errors),
+                    None => (None, LocationMap::default(), offsets, errors),
+                },
+            };
+        PartialParse {
+            text,
+            offsets,
+            ast,
+            locations,
+            errors,
+        }
+    }
+}
+
+#[cfg(feature = "parser")]
+/// A builder of [`Parser`]s with configured options.
+///
+/// ```
+/// use partiql_ast::arena::Arena;
+/// use partiql_parser::Parser;
+///
+/// let parser = Parser::builder()
+///     .max_nesting_depth(2)
+///     .error_recovery(false)
+///     .build();
+///
+/// let arena = Arena::new();
+/// assert!(parser.parse("[[1]]", &arena).is_ok());
+/// assert!(parser.parse("[[[1]]]", &arena).is_err());
+/// ```
+#[derive(Debug, Default)]
+pub struct ParserBuilder {
+    options: ParserOptions,
+}
+
+#[cfg(feature = "parser")]
+impl ParserBuilder {
+    /// Sets the maximum depth of nested parentheses, brackets, braces, bag delimiters (i.e., `<<`
+    /// and `>>`), `CASE` expressions, prefix operators (e.g., `NOT` or `-`, each of which counts as
+    /// a level of nesting up to its operand), and set operators (e.g., `UNION`, each of which
+    /// counts as a level of nesting up to the delimiter closing it); deeper text fails to parse
+    /// with a `ParseError::NestingTooDeep`. 512 by default, which guards against overflowing the
+    /// stack on malicious or generated text.
+    ///
+    /// Chains of other binary operators (e.g., `a = 1 OR a = 2 OR ...`) do not count towards the
+    /// depth, however long.
+    pub fn max_nesting_depth(mut self, depth: usize) -> Self {
+        self.options.max_nesting_depth = Some(depth);
+        self
+    }
+
+    /// Sets whether the parser recovers from syntax errors to report the errors following them,
+    /// or stops parsing at the first error, reporting it only. Enabled by default.
+    pub fn error_recovery(mut self, enabled: bool) -> Self {
+        self.options.error_recovery = enabled;
+        self
+    }
+
+    /// Sets whether backslash escape sequences in string literals (i.e., `\b`, `\f`, `\n`, `\r`,
+    /// `\t`, `\0`, `\'`, `\"`, `\\`, `\/`, and `\u` followed by 4 hexadecimal digits) are decoded
+    /// to the characters they denote; a backslash is otherwise an ordinary character. Disabled by
+    /// default.
+    ///
+    /// ```
+    /// use partiql_ast::arena::Arena;
+    /// use partiql_ast::ast::{Expr, Item, Lit, QuerySet};
+    /// use partiql_parser::Parser;
+    ///
+    /// let parser = Parser::builder().string_escapes(true).build();
+    /// let arena = Arena::new();
+    /// let parsed = parser.parse(r"'caf\u00e9\n'", &arena).unwrap();
+    /// let QuerySet::Expr(expr) = &parsed.ast.node.query.node.set.node else {
+    ///     panic!("expected an expression")
+    /// };
+    /// assert!(matches!(expr.as_ref(), Expr::Lit(lit) if lit.node == Lit::CharStringLit("café\n".into())));
+    /// ```
+    pub fn string_escapes(mut self, enabled: bool) -> Self {
+        self.options.string_escapes = enabled;
+        self
+    }
+
+    /// Sets whether comments are preserved as the [`Trivia`] of parsed statements, e.g., for
+    /// formatters that must not drop them; comments are otherwise discarded. Disabled by default.
+    ///
+    /// ```
+    /// use partiql_ast::arena::Arena;
+    /// use partiql_parser::{Parser, TriviaKind};
+    ///
+    /// let parser = Parser::builder().preserve_comments(true).build();
+    /// let arena = Arena::new();
+    /// let parsed = parser
+    ///     .parse("SELECT a -- the a\nFROM t /* all of t */", &arena)
+    ///     .unwrap();
+    /// let comments: Vec<_> = parsed.trivia.iter().map(|t| (t.kind, t.text)).collect();
+    /// assert_eq!(
+    ///     comments,
+    ///     vec![
+    ///         (TriviaKind::LineComment, "-- the a"),
+    ///         (TriviaKind::BlockComment, "/* all of t */"),
+    ///     ]
+    /// );
+    /// assert_eq!(parsed.trivia[0].location.start.0.to_usize(), 9);
+    /// ```
+    pub fn preserve_comments(mut self, enabled: bool) -> Self {
+        self.options.preserve_comments = enabled;
+        self
+    }
+
+    /// Builds the configured [`Parser`].
+    pub fn build(self) -> Parser {
+        Parser {
+            options: self.options,
+        }
+    }
 }
 
+#[cfg(feature = "parser")]
 /// The output of parsing PartiQL statement strings: an AST and auxiliary data.
 #[non_exhaustive]
 #[derive(Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 #[allow(dead_code)]
 pub struct Parsed<'input> {
     pub text: &'input str,
     pub offsets: LineOffsetTracker,
-    pub ast: ast::AstNode<ast::TopLevelQuery>,
+    pub ast: ast::AstNode<ast::TopLevelQuery<'input>>,
     pub locations: LocationMap,
+    /// The comments in `text`, if preserved; see [`ParserBuilder::preserve_comments`].
+    pub trivia: Vec<Trivia<'input>>,
+}
+
+#[cfg(feature = "parser")]
+impl<'input> Parsed<'input> {
+    /// The location in `text` of the AST node `id`, in lines and columns, if `id` is a node of
+    /// the AST.
+    ///
+    /// ```
+    /// use partiql_ast::arena::Arena;
+    /// use partiql_parser::Parser;
+    /// use partiql_source_map::location::LineAndColumn;
+    ///
+    /// let arena = Arena::new();
+    /// let parsed = Parser::default().parse("SELECT a\nFROM t", &arena).unwrap();
+    /// let location = parsed.location(parsed.ast.id).unwrap();
+    /// assert_eq!(location.start, LineAndColumn::new(1, 1).unwrap());
+    /// assert_eq!(location.end, LineAndColumn::new(2, 7).unwrap());
+    /// ```
+    pub fn location(&self, id: ast::NodeId) -> Option<Location<LineAndColumn>> {
+        line_and_column(self.text, &self.offsets, &self.locations, id)
+    }
+
+    /// The preserved comments, keyed by the id of the AST node each comment is attached to: the
+    /// outermost node following the comment or, for comments following all nodes, the outermost
+    /// node preceding it.
+    ///
+    /// ```
+    /// use partiql_ast::arena::Arena;
+    /// use partiql_parser::Parser;
+    ///
+    /// let parser = Parser::builder().preserve_comments(true).build();
+    /// let arena = Arena::new();
+    /// let parsed = parser
+    ///     .parse("SELECT a, /* the b */ b FROM t -- the end", &arena)
+    ///     .unwrap();
+    /// let trivia = parsed.trivia_by_node();
+    /// assert_eq!(trivia.len(), 2);
+    /// assert_eq!(trivia[&parsed.ast.id][0].text, "-- the end");
+    /// ```
+    pub fn trivia_by_node(&self) -> ast::AstTypeMap<Vec<&Trivia<'input>>> {
+        trivia_by_node(&self.trivia, &self.locations)
+    }
+}
+
+#[cfg(feature = "parser")]
+/// The output of parsing a PartiQL statement with [`Parser::parse_statement`]: an AST and
+/// auxiliary data.
+#[non_exhaustive]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[allow(dead_code)]
+pub struct ParsedStatement<'input> {
+    pub text: &'input str,
+    pub offsets: LineOffsetTracker,
+    pub ast: ast::Item<'input>,
+    pub locations: LocationMap,
+    /// The comments in `text`, if preserved; see [`ParserBuilder::preserve_comments`].
+    pub trivia: Vec<Trivia<'input>>,
+}
+
+#[cfg(feature = "parser")]
+impl<'input> ParsedStatement<'input> {
+    /// The location in `text` of the AST node `id`, in lines and columns, if `id` is a node of
+    /// the AST.
+    pub fn location(&self, id: ast::NodeId) -> Option<Location<LineAndColumn>> {
+        line_and_column(self.text, &self.offsets, &self.locations, id)
+    }
+
+    /// The preserved comments, keyed by the id of the AST node each comment is attached to; see
+    /// [`Parsed::trivia_by_node`].
+    pub fn trivia_by_node(&self) -> ast::AstTypeMap<Vec<&Trivia<'input>>> {
+        trivia_by_node(&self.trivia, &self.locations)
+    }
+}
+
+#[cfg(feature = "parser")]
+fn trivia_by_node<'a, 'input>(
+    trivia: &'a [Trivia<'input>],
+    locations: &LocationMap,
+) -> ast::AstTypeMap<Vec<&'a Trivia<'input>>> {
+    let mut by_node = ast::AstTypeMap::<Vec<_>>::default();
+    for t in trivia {
+        let following = locations
+            .iter()
+            .filter(|(_, loc)| loc.start >= t.location.end)
+            .min_by(|(_, l), (_, r)| l.start.cmp(&r.start).then(r.end.cmp(&l.end)));
+        let preceding = || {
+            locations
+                .iter()
+                .filter(|(_, loc)| loc.end <= t.location.start)
+                .max_by(|(_, l), (_, r)| l.end.cmp(&r.end).then(r.start.cmp(&l.start)))
+        };
+        if let Some((id, _)) = following.or_else(preceding) {
+            by_node.entry(*id).or_default().push(t);
+        }
+    }
+    by_node
+}
+
+#[cfg(feature = "parser")]
+fn line_and_column(
+    text: &str,
+    offsets: &LineOffsetTracker,
+    locations: &LocationMap,
+    id: ast::NodeId,
+) -> Option<Location<LineAndColumn>> {
+    let Location { start, end } = locations.get(&id)?;
+    Some(Location {
+        start: offsets.at(text, *start).ok()?.into(),
+        end: offsets.at(text, *end).ok()?.into(),
+    })
 }
 
 /// The output of errors when parsing PartiQL statement strings: an errors and auxiliary data.
@@ -93,3 +473,127 @@ pub struct ParserError<'input> {
     pub offsets: LineOffsetTracker,
     pub errors: Vec<ParseError<'input>>,
 }
+
+impl ParserError<'_> {
+    /// Converts the errors into an [`OwnedParserError`], e.g., to keep them after the text of the
+    /// statement is dropped.
+    ///
+    /// ```
+    /// # #[cfg(feature = "parser")]
+    /// # fn main() {
+    /// use partiql_ast::arena::Arena;
+    /// use partiql_parser::{OwnedParserError, Parser, ParserError};
+    ///
+    /// fn parse(text: String) -> Result<(), Box<OwnedParserError>> {
+    ///     let arena = Arena::new();
+    ///     Parser::default()
+    ///         .parse(&text, &arena)
+    ///         .map(|_| ())
+    ///         .map_err(|err| Box::new(err.into_owned()))
+    /// }
+    ///
+    /// let err = parse("SELECT".to_string()).unwrap_err();
+    /// assert_eq!(err.text, "SELECT");
+    /// assert_eq!(err.errors[0].to_string(), "Unexpected end of input");
+    /// # }
+    /// # #[cfg(not(feature = "parser"))]
+    /// # fn main() {}
+    /// ```
+    pub fn into_owned(self) -> OwnedParserError {
+        OwnedParserError {
+            text: self.text.to_string(),
+            offsets: self.offsets,
+            errors: self
+                .errors
+                .into_iter()
+                .map(error::ParseError::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// A [`ParserError`] that owns the text of the statement and of its errors.
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedParserError {
+    pub text: String,
+    pub offsets: LineOffsetTracker,
+    pub errors: Vec<ParseError<'static>>,
+}
+
+impl From<ParserError<'_>> for OwnedParserError {
+    fn from(err: ParserError<'_>) -> Self {
+        err.into_owned()
+    }
+}
+
+#[cfg(feature = "parser")]
+/// The output of parsing a PartiQL statement with [`Parser::parse_partial`]: the AST recovered
+/// from syntax errors, if any, along with the errors.
+#[non_exhaustive]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PartialParse<'input> {
+    pub text: &'input str,
+    pub offsets: LineOffsetTracker,
+    /// The AST of the statement, with an [`ast::Expr::Error`] in place of each expression that
+    /// failed to parse, unless the parser could not recover from an error.
+    pub ast: Option<ast::Item<'input>>,
+    /// The locations of the nodes of `ast`.
+    pub locations: LocationMap,
+    pub errors: Vec<ParseError<'input>>,
+}
+
+/// The text of a token, e.g., a keyword, a literal, or a comment, along with its location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Lexeme<'input> {
+    pub kind: TokenKind,
+    /// The text of the token; that of an embedded Ion literal excludes its backticks.
+    pub text: Cow<'input, str>,
+    /// The location of the token in the lexed text.
+    pub location: Location<BytePosition>,
+}
+
+/// The classes of [`Lexeme`]s, e.g., for syntax highlighting.
+///
+/// ### Notes
+/// This is marked `#[non_exhaustive]`, to reserve the right to add more variants in the future.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum TokenKind {
+    /// A keyword, e.g., `SELECT` or `BETWEEN`.
+    Keyword,
+    /// An identifier, quoted or not, e.g., `a`, `"a"`, or `@a`.
+    Identifier,
+    /// A literal, e.g., `1`, `'a'`, an embedded Ion value, or `TRUE`, `FALSE`, `NULL`, or `MISSING`.
+    Literal,
+    /// An operator or punctuation, e.g., `+`, `||`, `(`, `,`, or the parameter `?`.
+    Operator,
+    /// A `--` or `/* */` comment.
+    Comment,
+}
+
+/// Text of a statement that does not affect its meaning, preserved by a parser built with
+/// [`ParserBuilder::preserve_comments`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trivia<'input> {
+    pub kind: TriviaKind,
+    /// The text of the trivia, including any delimiters (e.g., `--` or `/*` and `*/`).
+    pub text: &'input str,
+    /// The location of the trivia in the statement text.
+    pub location: Location<BytePosition>,
+}
+
+/// The kinds of [`Trivia`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TriviaKind {
+    /// A `--` comment, which extends to the end of its line.
+    LineComment,
+    /// A `/* */` comment, which may be nested.
+    BlockComment,
+}
@@ -0,0 +1,184 @@
+//! Query text normalization and fingerprinting.
+//!
+//! Normalization lexes a query and re-renders it such that logically identical queries produce
+//! identical text:
+//!
+//! - literals (numbers, strings & embedded Ion) are replaced with the parameter placeholder `?`
+//! - keywords are upper-cased & unquoted (i.e., case-insensitive) identifiers are lower-cased
+//! - comments are removed & whitespace is made uniform
+//!
+//! The fingerprint of a query is a stable hash of its normalized text.
+
+use crate::lexer::{PartiqlLexer, Token};
+use crate::{ParseError, ParserError};
+use partiql_source_map::line_offset_tracker::LineOffsetTracker;
+use std::fmt::Write;
+
+/// The normalized form of a PartiQL statement along with its fingerprint.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NormalizedQuery {
+    /// The normalized statement text.
+    pub text: String,
+    /// A stable hash of the normalized text; logically identical statements share a fingerprint.
+    pub fingerprint: u64,
+}
+
+/// Normalize a PartiQL statement and compute its fingerprint.
+///
+/// # Example
+///
+/// ```
+/// use partiql_parser::normalize;
+///
+/// let a = normalize("select a from t where b = 1").expect("normalize");
+/// let b = normalize("SELECT A\n  FROM T WHERE B = 42 -- comment").expect("normalize");
+/// assert_eq!(a.text, "SELECT a FROM t WHERE b = ?");
+/// assert_eq!(a, b);
+/// ```
+pub fn normalize(text: &str) -> Result<NormalizedQuery, ParserError<'_>> {
+    let mut offsets = LineOffsetTracker::default();
+    let mut errors: Vec<ParseError<'_>> = vec![];
+    let mut normalized = String::with_capacity(text.len());
+    let mut prev: Option<Token<'_>> = None;
+
+    for result in PartiqlLexer::new(text, &mut offsets) {
+        let tok = match result {
+            Ok((_, tok, _)) => tok,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        if matches!(tok, Token::CommentLine(_) | Token::CommentBlock(_)) {
+            continue;
+        }
+
+        if let Some(prev) = &prev {
+            if !no_space_after(prev) && !no_space_before(&tok) && !is_call_or_index(prev, &tok) {
+                normalized.push(' ');
+            }
+        }
+        write_token(&mut normalized, &tok);
+        prev = Some(tok);
+    }
+
+    if errors.is_empty() {
+        let fingerprint = fingerprint(&normalized);
+        Ok(NormalizedQuery {
+            text: normalized,
+            fingerprint,
+        })
+    } else {
+        Err(ParserError {
+            text,
+            offsets,
+            errors,
+        })
+    }
+}
+
+#[inline]
+fn no_space_after(tok: &Token<'_>) -> bool {
+    matches!(
+        tok,
+        Token::OpenParen | Token::OpenSquare | Token::OpenCurly | Token::Period
+    )
+}
+
+#[inline]
+fn no_space_before(tok: &Token<'_>) -> bool {
+    matches!(
+        tok,
+        Token::CloseParen
+            | Token::CloseSquare
+            | Token::CloseCurly
+            | Token::Period
+            | Token::Comma
+            | Token::Semicolon
+    )
+}
+
+/// Whether `tok` opens a function call or an index into `prev` (e.g., `f(x)` or `a[0]`).
+#[inline]
+fn is_call_or_index(prev: &Token<'_>, tok: &Token<'_>) -> bool {
+    matches!(tok, Token::OpenParen | Token::OpenSquare)
+        && matches!(
+            prev,
+            Token::UnquotedIdent(_)
+                | Token::QuotedIdent(_)
+                | Token::UnquotedAtIdentifier(_)
+                | Token::QuotedAtIdentifier(_)
+                | Token::CloseParen
+                | Token::CloseSquare
+        )
+}
+
+fn write_token(out: &mut String, tok: &Token<'_>) {
+    // Writing to a `String` cannot fail
+    let _ = match tok {
+        Token::Int(_)
+        | Token::Real(_)
+        | Token::ExpReal(_)
+        | Token::String(_)
+        | Token::Ion(_)
+        | Token::SqlParameter => write!(out, "?"),
+        Token::UnquotedIdent(id) => write!(out, "{}", id.to_lowercase()),
+        Token::QuotedIdent(id) => write!(out, "\"{id}\""),
+        Token::UnquotedAtIdentifier(id) => write!(out, "@{}", id.to_lowercase()),
+        Token::QuotedAtIdentifier(id) => write!(out, "@\"{id}\""),
+        // Remaining tokens are punctuation & keywords, whose `Display` is already normalized
+        tok => write!(out, "{tok}"),
+    };
+}
+
+/// 64-bit FNV-1a; used rather than [`std::collections::hash_map::DefaultHasher`] as its output
+/// must be stable across Rust releases.
+fn fingerprint(text: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    text.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals_and_casing() {
+        let normalized = normalize(
+            r#"select "A", b.c[0], f(x, 'str', 1.5e3) FROM tbl AS t WHERE t.x IN (1, 2) lImIt 10"#,
+        )
+        .expect("normalize");
+        assert_eq!(
+            normalized.text,
+            r#"SELECT "A", b.c[?], f(x, ?, ?) FROM tbl AS t WHERE t.x IN (?, ?) LIMIT ?"#
+        );
+    }
+
+    #[test]
+    fn whitespace_and_comments() {
+        let a = normalize("SELECT a /* block */ FROM t\n\t-- line\nWHERE a = `{x: 1}`")
+            .expect("normalize");
+        let b = normalize("SELECT a FROM t WHERE a = 'v'").expect("normalize");
+        assert_eq!(a.text, "SELECT a FROM t WHERE a = ?");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn fingerprints_differ() {
+        let a = normalize("SELECT a FROM t").expect("normalize");
+        let b = normalize("SELECT b FROM t").expect("normalize");
+        let c = normalize(r#"SELECT "a" FROM t"#).expect("normalize");
+        assert_ne!(a.fingerprint, b.fingerprint);
+        assert_ne!(a.fingerprint, c.fingerprint);
+    }
+
+    #[test]
+    fn lex_error() {
+        let res = normalize("SELECT a FROM t WHERE a = 'unterminated");
+        assert!(res.is_err());
+        assert_eq!(1, res.unwrap_err().errors.len());
+    }
+}
@@ -1,16 +1,20 @@
 // Copyright Amazon.com, Inc. or its affiliates.
 
-//! Provides the [`parse_partiql`] function to parse a PartiQL query.
+//! Provides the [`parse_partiql`] function to parse a PartiQL query and the
+//! [`parse_partiql_statement`] function to parse any PartiQL statement.
 
 mod parse_util;
 mod parser_state;
 
 use crate::error::{ParseError, UnexpectedTokenData};
 use crate::lexer;
-use crate::lexer::CommentSkippingLexer;
+use crate::lexer::{CommentSkippingLexer, ContextualKeywordLexer, NestingLimitingLexer};
 use crate::parse::parser_state::{IdGenerator, ParserState};
 use crate::preprocessor::{PreprocessingPartiqlLexer, BUILT_INS};
+use crate::suggest::suggest_keyword;
+use crate::Trivia;
 use lalrpop_util as lpop;
+use partiql_ast::arena::Arena;
 use partiql_ast::ast;
 use partiql_source_map::line_offset_tracker::LineOffsetTracker;
 use partiql_source_map::location::{ByteOffset, BytePosition, ToLocated};
@@ -27,45 +31,140 @@ use partiql_source_map::metadata::LocationMap;
 #[allow(clippy::unused_unit)]
 #[allow(unused_variables)]
 #[allow(dead_code)]
+#[allow(clippy::panic)]
+#[allow(clippy::unreachable)]
 mod grammar {
     include!(concat!(env!("OUT_DIR"), "/partiql.rs"));
 }
 
 type LalrpopError<'input> =
     lpop::ParseError<ByteOffset, lexer::Token<'input>, ParseError<'input, BytePosition>>;
-type LalrpopResult<'input> = Result<ast::AstNode<ast::TopLevelQuery>, LalrpopError<'input>>;
+type LalrpopResult<'input> = Result<ast::Item<'input>, LalrpopError<'input>>;
 type LalrpopErrorRecovery<'input> =
     lpop::ErrorRecovery<ByteOffset, lexer::Token<'input>, ParseError<'input, BytePosition>>;
 
-#[derive(Debug, Clone)]
-pub(crate) struct AstData {
-    pub ast: ast::AstNode<ast::TopLevelQuery>,
+#[derive(Debug)]
+pub(crate) struct AstData<'input, T = ast::AstNode<ast::TopLevelQuery<'input>>> {
+    pub ast: T,
     pub locations: LocationMap,
     pub offsets: LineOffsetTracker,
+    pub trivia: Vec<Trivia<'input>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub(crate) struct ErrorData<'input> {
     pub errors: Vec<ParseError<'input, BytePosition>>,
     pub offsets: LineOffsetTracker,
+    pub recovered: Option<Box<RecoveredAst<'input>>>,
+}
+
+/// The AST recovered from the errors of a parse.
+#[derive(Debug)]
+pub(crate) struct RecoveredAst<'input> {
+    pub ast: ast::Item<'input>,
+    pub locations: LocationMap,
+}
+
+pub(crate) type AstResult<'input, T = ast::AstNode<ast::TopLevelQuery<'input>>> =
+    Result<AstData<'input, T>, ErrorData<'input>>;
+
+/// The maximum depth of nested delimiters, prefix operators and set operators by default; deeper
+/// text would risk overflowing the stack of the threads traversing or dropping its AST.
+pub(crate) const DEFAULT_MAX_NESTING_DEPTH: usize = 512;
+
+/// Options tuning the behavior of the parser; see [`crate::ParserBuilder`].
+#[derive(Debug, Clone)]
+pub(crate) struct ParserOptions {
+    /// The maximum depth of nested parentheses, brackets, braces, bag delimiters, `CASE`s, prefix
+    /// operators, and set operators, if any.
+    pub max_nesting_depth: Option<usize>,
+    /// Whether to recover from syntax errors to report the errors following them.
+    pub error_recovery: bool,
+    /// Whether to decode backslash escape sequences in string literals.
+    pub string_escapes: bool,
+    /// Whether to preserve comments as [`Trivia`] rather than discard them.
+    pub preserve_comments: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            max_nesting_depth: Some(DEFAULT_MAX_NESTING_DEPTH),
+            error_recovery: true,
+            string_escapes: false,
+            preserve_comments: false,
+        }
+    }
 }
 
-pub(crate) type AstResult<'input> = Result<AstData, ErrorData<'input>>;
+/// Parse PartiQL query text into an AST allocated in `arena`.
+pub(crate) fn parse_partiql<'input>(
+    s: &'input str,
+    arena: &'input Arena,
+    options: &ParserOptions,
+) -> AstResult<'input> {
+    parse_partiql_with_state(s, ParserState::new(arena), options)
+}
 
-/// Parse PartiQL query text into an AST.
-pub(crate) fn parse_partiql(s: &str) -> AstResult {
-    parse_partiql_with_state(s, ParserState::default())
+/// Parse PartiQL statement text (e.g., a query or a DML statement) into an AST allocated in
+/// `arena`.
+pub(crate) fn parse_partiql_statement<'input>(
+    s: &'input str,
+    arena: &'input Arena,
+    options: &ParserOptions,
+) -> AstResult<'input, ast::Item<'input>> {
+    parse_statement_with_state(s, ParserState::new(arena), options)
 }
 
 fn parse_partiql_with_state<'input, Id: IdGenerator>(
     s: &'input str,
-    mut state: ParserState<'input, Id>,
+    state: ParserState<'input, Id>,
+    options: &ParserOptions,
 ) -> AstResult<'input> {
+    let AstData {
+        ast,
+        locations,
+        offsets,
+        trivia,
+    } = parse_statement_with_state(s, state, options)?;
+    match ast {
+        ast::Item::Query(ast) => Ok(AstData {
+            ast,
+            locations,
+            offsets,
+            trivia,
+        }),
+        ast => {
+            let location = BytePosition::from(0)..BytePosition::from(s.len());
+            let errors = vec![ParseError::SyntaxError(
+                "Expected a query, found a DDL or DML statement"
+                    .to_string()
+                    .to_located(location),
+            )];
+            Err(ErrorData {
+                errors,
+                offsets,
+                recovered: Some(Box::new(RecoveredAst { ast, locations })),
+            })
+        }
+    }
+}
+
+fn parse_statement_with_state<'input, Id: IdGenerator>(
+    s: &'input str,
+    mut state: ParserState<'input, Id>,
+    options: &ParserOptions,
+) -> AstResult<'input, ast::Item<'input>> {
     let mut offsets = LineOffsetTracker::default();
     let lexer = PreprocessingPartiqlLexer::new(s, &mut offsets, &BUILT_INS);
-    let lexer = CommentSkippingLexer::new(lexer);
+    let mut comments = CommentSkippingLexer::new(lexer).preserving(options.preserve_comments);
+    let lexer = ContextualKeywordLexer::new(s, &mut comments);
+    let lexer = NestingLimitingLexer::new(lexer, options.max_nesting_depth);
+    state.string_escapes = options.string_escapes;
+    state.error_recovery = options.error_recovery;
 
-    let result: LalrpopResult = grammar::TopLevelQueryParser::new().parse(s, &mut state, lexer);
+    let result: LalrpopResult = grammar::StatementParser::new().parse(s, &mut state, lexer);
+    let trivia = comments.into_trivia();
 
     let ParserState {
         locations, errors, ..
@@ -77,24 +176,88 @@ fn parse_partiql_with_state<'input, Id: IdGenerator>(
         .map(|e| ParseError::from(e.error))
         .collect();
 
-    match (result, errors.is_empty()) {
-        (Ok(_), false) => Err(ErrorData { errors, offsets }),
-        (Err(e), true) => {
-            let errors = vec![ParseError::from(e)];
-            Err(ErrorData { errors, offsets })
-        }
-        (Err(e), false) => {
+    // A parse that recovered from all of its errors still yields an AST, in which the
+    // unparsable expressions are `Expr::Error`s.
+    let ast = match result {
+        Ok(ast) => Some(ast),
+        Err(e) => {
             errors.push(ParseError::from(e));
-            Err(ErrorData { errors, offsets })
+            None
         }
-        (Ok(ast), true) => Ok(AstData {
+    };
+
+    for error in &mut errors {
+        if let ParseError::UnexpectedToken(token) = error {
+            let start = token.location.start.0.to_usize();
+            token.inner.suggestion = suggest_keyword(s, start, &token.inner.expected);
+        }
+    }
+
+    match ast {
+        Some(ast) if errors.is_empty() => Ok(AstData {
             ast,
             locations,
             offsets,
+            trivia,
+        }),
+        ast => Err(ErrorData {
+            errors,
+            offsets,
+            recovered: ast.map(|ast| Box::new(RecoveredAst { ast, locations })),
         }),
     }
 }
 
+/// Tokens used to probe which tokens the parser accepts after some text; a probe is only useful if
+/// it is itself rejected, so several seldom-valid tokens are tried in turn.
+const PROBE_TOKENS: [lexer::Token<'static>; 4] = [
+    lexer::Token::Caret,
+    lexer::Token::CloseDblAngle,
+    lexer::Token::CloseCurly,
+    lexer::Token::Colon,
+];
+
+/// Returns the names of the grammar terminals that may follow the text `s`, e.g., `"FROM"`,
+/// `"("`, or `UnquotedIdent`, or `None` if that cannot be determined (e.g., due to a lexing
+/// error).
+pub(crate) fn expected_tokens(s: &str) -> Option<Vec<String>> {
+    let end = ByteOffset::from(s.len());
+    PROBE_TOKENS.into_iter().find_map(|probe| {
+        let mut offsets = LineOffsetTracker::default();
+        let arena = Arena::new();
+        let mut state = ParserState::new(&arena);
+        let lexer = PreprocessingPartiqlLexer::new(s, &mut offsets, &BUILT_INS);
+        let lexer =
+            CommentSkippingLexer::new(lexer).chain(std::iter::once(Ok((end, probe.clone(), end))));
+
+        let result: LalrpopResult = grammar::StatementParser::new().parse(s, &mut state, lexer);
+
+        let recovered = state.errors.into_iter().map(|e| e.error);
+        let errors: Vec<_> = recovered.chain(result.err()).collect();
+        if errors
+            .iter()
+            .any(|e| matches!(e, lpop::ParseError::User { .. }))
+        {
+            return None;
+        }
+        errors.into_iter().find_map(|e| match e {
+            lpop::ParseError::UnrecognizedToken {
+                token: (start, token, _),
+                expected,
+            } if start == end && token == probe => Some(terminal_names(expected)),
+            _ => None,
+        })
+    })
+}
+
+/// Strips the quotes of the names of grammar terminals reported by the parser, e.g., `"FROM"`.
+fn terminal_names(expected: Vec<String>) -> Vec<String> {
+    expected
+        .into_iter()
+        .map(|t| t.trim_matches('"').to_string())
+        .collect()
+}
+
 impl<'input> From<LalrpopErrorRecovery<'input>> for ParseError<'input, BytePosition> {
     fn from(error_recovery: LalrpopErrorRecovery<'input>) -> Self {
         // TODO do something with error_recovery.dropped_tokens?
@@ -106,13 +269,14 @@ impl<'input> From<LalrpopError<'input>> for ParseError<'input, BytePosition> {
     #[inline]
     fn from(error: LalrpopError<'input>) -> Self {
         match error {
-            // TODO do something with UnrecognizedToken.expected
             lalrpop_util::ParseError::UnrecognizedToken {
                 token: (start, token, end),
-                expected: _,
+                expected,
             } => ParseError::UnexpectedToken(
                 UnexpectedTokenData {
                     token: token.to_string().into(),
+                    expected: terminal_names(expected),
+                    suggestion: None,
                 }
                 .to_located(start.into()..end.into()),
             ),
@@ -131,6 +295,8 @@ impl<'input> From<LalrpopError<'input>> for ParseError<'input, BytePosition> {
             } => ParseError::UnexpectedToken(
                 UnexpectedTokenData {
                     token: token.to_string().into(),
+                    expected: vec![],
+                    suggestion: None,
                 }
                 .to_located(start.into()..end.into()),
             ),
@@ -143,8 +309,19 @@ impl<'input> From<LalrpopError<'input>> for ParseError<'input, BytePosition> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    fn parse_partiql(s: &str) -> AstResult {
-        super::parse_partiql(s)
+    use partiql_ast::arena;
+
+    /// An arena for the ASTs that tests inspect after the functions parsing them return.
+    fn arena() -> &'static Arena {
+        Box::leak(Box::default())
+    }
+
+    fn parse_partiql(s: &str) -> AstResult<'_> {
+        super::parse_partiql(s, arena(), &ParserOptions::default())
+    }
+
+    fn parse_partiql_statement(s: &str) -> AstResult<'_, ast::Item<'_>> {
+        super::parse_partiql_statement(s, arena(), &ParserOptions::default())
     }
 
     macro_rules! parse {
@@ -187,6 +364,75 @@ mod tests {
             parse!("'embe''ded'");
         }
 
+        #[test]
+        fn string_escapes() {
+            let lit = |s: &'static str, string_escapes: bool| {
+                let options = ParserOptions {
+                    string_escapes,
+                    ..Default::default()
+                };
+                let ast = super::super::parse_partiql(s, arena(), &options)
+                    .unwrap()
+                    .ast;
+                match ast.node.query.node.set.node {
+                    ast::QuerySet::Expr(e) => match arena::Box::into_inner(e) {
+                        ast::Expr::Lit(lit) => lit.node,
+                        e => panic!("expected a literal, found {e:?}"),
+                    },
+                    set => panic!("expected an expression, found {set:?}"),
+                }
+            };
+            let string = ast::Lit::CharStringLit;
+            assert_eq!(lit("'it''s'", false), string("it's"));
+            assert_eq!(lit(r"'a\tb'", false), string(r"a\tb"));
+            assert_eq!(lit(r"'a\tb'", true), string("a\tb"));
+            assert_eq!(lit(r"'it\'s'", true), string("it's"));
+            assert_eq!(lit(r"'\u00e9'", true), string("é"));
+
+            let options = ParserOptions {
+                string_escapes: true,
+                ..Default::default()
+            };
+            let err_data =
+                super::super::parse_partiql(r"'a' || '\x'", arena(), &options).unwrap_err();
+            assert_eq!(
+                err_data.errors,
+                vec![ParseError::LexicalError(
+                    crate::error::LexError::InvalidEscapeSequence(r"\x".into())
+                        .to_located(BytePosition::from(7)..BytePosition::from(11))
+                )]
+            );
+        }
+
+        #[test]
+        fn string_borrows_text() {
+            let text = |s: &'static str| {
+                let ast = parse!(s);
+                match ast.node.query.node.set.node {
+                    ast::QuerySet::Expr(e) => match arena::Box::into_inner(e) {
+                        ast::Expr::Lit(ast::AstNode {
+                            node: ast::Lit::CharStringLit(text),
+                            ..
+                        }) => text,
+                        ast::Expr::VarRef(var_ref) => var_ref.node.name.value,
+                        e => panic!("expected a string or variable, found {e:?}"),
+                    },
+                    set => panic!("expected an expression, found {set:?}"),
+                }
+            };
+            let borrows =
+                |s: &str, text: &str| s.as_bytes().as_ptr_range().contains(&text.as_ptr());
+
+            let q = "'foo'";
+            assert!(borrows(q, text(q)));
+            let q = "foo";
+            assert!(borrows(q, text(q)));
+            let q = "\"foo\"";
+            assert!(borrows(q, text(q)));
+            let q = "'it''s'";
+            assert!(!borrows(q, text(q)));
+        }
+
         #[test]
         fn numeric() {
             parse!("42");
@@ -196,6 +442,27 @@ mod tests {
             parse!("17e2");
             parse!("1.317e-3");
             parse!("3141.59265e-03");
+            parse!("0x1F");
+            parse!("0b1010");
+        }
+
+        #[test]
+        fn hex_and_binary_ints() {
+            let lit = |s: &'static str| {
+                let ast = parse_partiql(s).unwrap().ast;
+                match ast.node.query.node.set.node {
+                    ast::QuerySet::Expr(e) => match arena::Box::into_inner(e) {
+                        ast::Expr::Lit(lit) => lit.node,
+                        e => panic!("expected a literal, found {e:?}"),
+                    },
+                    set => panic!("expected an expression, found {set:?}"),
+                }
+            };
+            assert_eq!(lit("0x1F"), ast::Lit::Int64Lit(31));
+            assert_eq!(lit("0XfF"), ast::Lit::Int64Lit(255));
+            assert_eq!(lit("0b1010"), ast::Lit::Int64Lit(10));
+            assert_eq!(lit("0x7FFFFFFFFFFFFFFF"), ast::Lit::Int64Lit(i64::MAX));
+            assert!(parse_partiql("0x8000000000000000").is_err());
         }
 
         #[test]
@@ -210,6 +477,86 @@ mod tests {
             parse!("time (10) WITHOUT TIME ZONE '22:12'");
         }
 
+        #[test]
+        fn interval() {
+            use ast::IntervalField::*;
+            let interval = |s: &'static str| {
+                let ast = parse_partiql(s).unwrap().ast;
+                match ast.node.query.node.set.node {
+                    ast::QuerySet::Expr(e) => match arena::Box::into_inner(e) {
+                        ast::Expr::Lit(ast::AstNode {
+                            node: ast::Lit::IntervalLit(interval),
+                            ..
+                        }) => interval,
+                        e => panic!("expected an interval, found {e:?}"),
+                    },
+                    set => panic!("expected an expression, found {set:?}"),
+                }
+            };
+            let qualifier = |start, precision, end, fractional_precision| ast::IntervalQualifier {
+                start,
+                precision,
+                end,
+                fractional_precision,
+            };
+
+            let lit = interval("INTERVAL '3' DAY");
+            assert_eq!(lit.value, "3");
+            assert_eq!(lit.qualifier, qualifier(Day, None, None, None));
+            let lit = interval("interval '1-6' year(2) to month");
+            assert_eq!(lit.value, "1-6");
+            assert_eq!(lit.qualifier, qualifier(Year, Some(2), Some(Month), None));
+            let lit = interval("INTERVAL '1:02:03.456' HOUR TO SECOND(3)");
+            assert_eq!(lit.qualifier, qualifier(Hour, None, Some(Second), Some(3)));
+            let lit = interval("INTERVAL '1.5' SECOND(2, 3)");
+            assert_eq!(lit.qualifier, qualifier(Second, Some(2), None, Some(3)));
+
+            parse!("SELECT d + INTERVAL '1' DAY - INTERVAL '2:30' HOUR TO MINUTE FROM t");
+            // The fields of intervals may still be identifiers
+            parse!("SELECT year, day, extract(year FROM d) FROM t AS second");
+
+            for invalid in [
+                "INTERVAL '1' WEEK",
+                "INTERVAL '1' DAY TO DAY",
+                "INTERVAL '1' MINUTE TO HOUR",
+                "INTERVAL '1' MONTH TO DAY",
+                "INTERVAL '1' DAY(2, 3)",
+                "INTERVAL '1' DAY TO HOUR(3)",
+                "INTERVAL '1' SECOND(2, 3) TO SECOND",
+                "INTERVAL '1'",
+                "INTERVAL 1 DAY",
+            ] {
+                assert!(parse_partiql(invalid).is_err(), "{invalid}");
+            }
+        }
+
+        #[test]
+        #[cfg(feature = "ion")]
+        fn invalid_ion() {
+            // errors are located within the backticks of the literal in the query
+            for (s, start, end) in [
+                ("`{a: 1`", 5, 6),
+                ("1 + `[1, 2 b: 2]`", 9, 16),
+                ("`{a: 1,\n b 2}`", 9, 13),
+                ("`[1, 2]\n}`", 8, 9),
+                ("`1 2`", 1, 4),
+            ] {
+                let err_data = parse_partiql(s).unwrap_err();
+                let location = match &err_data.errors[..] {
+                    [ParseError::LexicalError(partiql_source_map::location::Located {
+                        inner: crate::error::LexError::InvalidIonLiteral(_),
+                        location,
+                    })] => location,
+                    errors => panic!("{s}: {errors:?}"),
+                };
+                assert_eq!(
+                    (location.start.0.to_usize(), location.end.0.to_usize()),
+                    (start, end),
+                    "{s}"
+                );
+            }
+        }
+
         #[test]
         fn ion() {
             parse!(r#" `[{'a':1, 'b':1}, {'a':2}, "foo"]` "#);
@@ -256,6 +603,12 @@ mod tests {
             parse!(r#"{}"#);
             parse!(r#"{a_variable: 1, 'cow': 'moo', 'a': NULL}"#);
         }
+        #[test]
+        fn tuple_computed_keys() {
+            parse!(r#"{'a' || 'b': 1, UPPER(k): 2, t.k: 3, (SELECT VALUE k FROM t): 4}"#);
+            parse!(r#"{1: 2, 1 + 1: 3}"#);
+            parse!(r#"SELECT VALUE {x.k: x.v} FROM t AS x"#);
+        }
     }
 
     mod expr {
@@ -301,6 +654,46 @@ mod tests {
         fn expr_between() {
             parse!(r#"a between 2 and 3"#);
         }
+
+        #[test]
+        fn expr_is_distinct_from() {
+            parse!(r#"a IS DISTINCT FROM b"#);
+            parse!(r#"a.b is not distinct from NULL AND c IS NOT NULL"#);
+        }
+
+        #[test]
+        fn parameters() {
+            parse!(r#"a = ? AND b = :b AND c = :"C""#);
+            parse!(r#"SELECT x FROM t AS x WHERE x.a BETWEEN ? AND ?"#);
+
+            let ast = parse!("[?, :p, ?]");
+            let ast::QuerySet::Expr(expr) = &ast.node.query.node.set.node else {
+                panic!("expected expression")
+            };
+            let ast::Expr::List(list) = expr.as_ref() else {
+                panic!("expected list")
+            };
+            let params: Vec<_> = list
+                .node
+                .values
+                .iter()
+                .map(|v| match v.as_ref() {
+                    ast::Expr::Parameter(p) => p.node.clone(),
+                    other => panic!("expected a parameter, got {other:?}"),
+                })
+                .collect();
+            assert_eq!(
+                params,
+                vec![
+                    ast::Parameter::Positional(0),
+                    ast::Parameter::Named(ast::SymbolPrimitive {
+                        value: "p",
+                        case: ast::CaseSensitivity::CaseInsensitive,
+                    }),
+                    ast::Parameter::Positional(1),
+                ]
+            );
+        }
     }
 
     mod pathexpr {
@@ -318,6 +711,24 @@ mod tests {
             parse!(r#"a.b.c['item']."d"[5].e['s'].f[1+2]"#);
         }
 
+        #[test]
+        fn scope_qualifier() {
+            let qualifier = |q: &str| {
+                let ast = parse!(q);
+                let ast::QuerySet::Expr(expr) = &ast.node.query.node.set.node else {
+                    panic!("expected expression")
+                };
+                match expr.as_ref() {
+                    ast::Expr::VarRef(v) => v.node.qualifier.clone(),
+                    other => panic!("expected a variable reference, got {other:?}"),
+                }
+            };
+            assert_eq!(qualifier("a"), ast::ScopeQualifier::Unqualified);
+            assert_eq!(qualifier(r#""a""#), ast::ScopeQualifier::Unqualified);
+            assert_eq!(qualifier("@a"), ast::ScopeQualifier::Qualified);
+            assert_eq!(qualifier(r#"@"a""#), ast::ScopeQualifier::Qualified);
+        }
+
         #[test]
         fn tuple() {
             parse!(r#"{'a':1 , 'data': 2}.a"#);
@@ -418,6 +829,26 @@ mod tests {
             parse!("SELECT g, k as ck, h");
         }
 
+        #[test]
+        fn select_list_wildcard() {
+            let ast = parse!("SELECT t.*, u.name, t.a.* FROM t, u");
+            let ast::QuerySet::Select(select) = ast.node.query.node.set.node else {
+                panic!("expected a SELECT")
+            };
+            let ast::ProjectionKind::ProjectList(items) = &select.node.project.node.kind else {
+                panic!("expected a SELECT list")
+            };
+            assert!(matches!(
+                &items[0].node,
+                ast::ProjectItem::ProjectAll(all) if matches!(*all.expr, ast::Expr::VarRef(_))
+            ));
+            assert!(matches!(&items[1].node, ast::ProjectItem::ProjectExpr(_)));
+            assert!(matches!(
+                &items[2].node,
+                ast::ProjectItem::ProjectAll(all) if matches!(*all.expr, ast::Expr::Path(_))
+            ));
+        }
+
         #[test]
         fn fun_call() {
             parse!(r#"fun_call('bar', 1,2,3,4,5,'foo')"#);
@@ -433,16 +864,55 @@ mod tests {
             parse!("SELECT g FROM data GROUP BY a");
         }
 
+        #[test]
+        fn group_strategy() {
+            let strategy = |s: &str| {
+                let ast = parse!(s);
+                let ast::QuerySet::Select(select) = ast.node.query.node.set.node else {
+                    panic!("expected a SELECT")
+                };
+                select
+                    .node
+                    .group_by
+                    .as_ref()
+                    .map(|group_by| group_by.node.strategy.clone())
+            };
+            let full = Some(ast::GroupingStrategy::GroupFull);
+            let partial = Some(ast::GroupingStrategy::GroupPartial);
+            assert_eq!(strategy("SELECT g FROM data GROUP BY a"), full);
+            assert_eq!(strategy("SELECT g FROM data GROUP ALL BY a"), full);
+            assert_eq!(strategy("SELECT g FROM data GROUP PARTIAL BY a"), partial);
+        }
+
         #[test]
         fn group_complex() {
             parse!("SELECT g FROM data GROUP BY a AS x, b + c AS y, foo(d) AS z GROUP AS g");
         }
 
+        #[test]
+        fn pivot() {
+            parse!(r#"PIVOT sp.price AT sp."symbol" FROM todaysStockPrices sp"#);
+            parse!(
+                r#"FROM todaysStockPrices AS sp WHERE sp.price > 0 PIVOT sp.price AT sp."symbol""#
+            );
+        }
+
+        #[test]
+        fn let_clause() {
+            parse!(r#"SELECT y FROM t LET x + 1 AS y WHERE y > 2"#);
+            parse!(r#"SELECT a, b FROM t LET t.a AS a, a * 2 AS b"#);
+            parse!(r#"FROM t AS t LET t.a AS a SELECT a"#);
+        }
+
         #[test]
         fn order_by() {
             parse!(r#"SELECT a FROM tb ORDER BY PRESERVE"#);
             parse!(r#"SELECT a FROM tb ORDER BY rk1"#);
             parse!(r#"SELECT a FROM tb ORDER BY rk1 ASC, rk2 DESC"#);
+            parse!(r#"SELECT a FROM tb ORDER BY rk1 NULLS FIRST, rk2 DESC NULLS LAST"#);
+            parse!(r#"SELECT a FROM tb ORDER BY rk1 ASC NULLS LAST, rk2 DESC NULLS FIRST"#);
+            assert!(parse_partiql(r#"SELECT a FROM tb ORDER BY rk1 NULLS"#).is_err());
+            assert!(parse_partiql(r#"SELECT a FROM tb ORDER BY rk1 NULLS DESC"#).is_err());
         }
 
         #[test]
@@ -472,6 +942,51 @@ mod tests {
             parse!(r#"SELECT * FROM a LIMIT 10 OFFSET 2"#);
         }
 
+        #[test]
+        fn fetch_first() {
+            let printed = |s: &str| parse_partiql_statement(s).unwrap().ast.to_string();
+            let cases = [
+                (
+                    "SELECT * FROM a OFFSET 2 ROWS FETCH FIRST 10 ROWS ONLY",
+                    "SELECT * FROM a LIMIT 10 OFFSET 2",
+                ),
+                (
+                    "SELECT * FROM a ORDER BY b fetch next n + 1 row only",
+                    "SELECT * FROM a ORDER BY b LIMIT n + 1",
+                ),
+                (
+                    "SELECT * FROM a FETCH FIRST ROW ONLY",
+                    "SELECT * FROM a LIMIT 1",
+                ),
+                ("SELECT * FROM a OFFSET 1 ROW", "SELECT * FROM a OFFSET 1"),
+                (
+                    "SELECT * FROM a OFFSET 2 FETCH FIRST 3 ROWS ONLY",
+                    "SELECT * FROM a LIMIT 3 OFFSET 2",
+                ),
+            ];
+            for (fetch, limit) in cases {
+                assert_eq!(printed(fetch), printed(limit), "{fetch}");
+            }
+
+            assert!(parse_partiql("SELECT * FROM a LIMIT 1 FETCH FIRST 2 ROWS ONLY").is_err());
+            assert!(parse_partiql("SELECT * FROM a FETCH FIRST 2 ROWS").is_err());
+            assert!(parse_partiql("SELECT * FROM a FETCH FIRST 2 ROWS ONLY OFFSET 1").is_err());
+        }
+
+        #[test]
+        fn fetch_keywords_as_identifiers() {
+            let printed = |s: &str| parse_partiql_statement(s).unwrap().ast.to_string();
+            parse!(r#"SELECT next FROM t"#);
+            parse!(r#"SELECT fetch, next, only FROM t"#);
+            parse!(r#"SELECT only.a FROM t AS only"#);
+            parse!(r#"SELECT t.a AS fetch FROM t WHERE t.only = next"#);
+            parse!(r#"SELECT * FROM fetch AS next FETCH FIRST 1 ROW ONLY"#);
+            assert_eq!(
+                printed("SELECT next FROM t AS only FETCH NEXT 2 ROWS ONLY"),
+                printed("SELECT next FROM t AS only LIMIT 2")
+            );
+        }
+
         #[test]
         fn complex() {
             let q = r#"
@@ -530,6 +1045,104 @@ mod tests {
             parse!(r#"SELECT * FROM a AS a AT b CROSS JOIN c AS c AT q"#);
         }
 
+        #[test]
+        fn scalar_subqueries() {
+            let scalar = |q| {
+                let ast = parse!(q);
+                let ast::QuerySet::Select(select) = &ast.node.query.node.set.node else {
+                    panic!("expected SELECT")
+                };
+                let ast::ProjectionKind::ProjectList(items) = &select.node.project.node.kind else {
+                    panic!("expected project list")
+                };
+                let ast::ProjectItem::ProjectExpr(item) = &items[0].node else {
+                    panic!("expected project expression")
+                };
+                match item.expr.as_ref() {
+                    ast::Expr::ScalarSubQuery(_) => true,
+                    ast::Expr::Query(_) => false,
+                    _ => panic!("expected subquery"),
+                }
+            };
+            assert!(scalar("SELECT (SELECT MAX(a) FROM t) AS m FROM u"));
+            assert!(!scalar("SELECT (SELECT VALUE a FROM t) AS m FROM u"));
+
+            // the subqueries in CASE conditions and function arguments are scalar, but for the
+            // arguments of functions of collections
+            let ast = parse!(
+                "SELECT CASE WHEN (SELECT a FROM t) THEN 1 END AS c, \
+                 CASE (SELECT a FROM t) WHEN (SELECT b FROM t) THEN 1 END AS d, \
+                 UPPER((SELECT a FROM t)) AS e, TRIM(FROM (SELECT a FROM t)) AS f, \
+                 CARDINALITY((SELECT a FROM t)) AS g FROM u"
+            );
+            let ast::QuerySet::Select(select) = &ast.node.query.node.set.node else {
+                panic!("expected SELECT")
+            };
+            let ast::ProjectionKind::ProjectList(items) = &select.node.project.node.kind else {
+                panic!("expected project list")
+            };
+            let exprs: std::vec::Vec<_> = items
+                .iter()
+                .map(|item| match &item.node {
+                    ast::ProjectItem::ProjectExpr(item) => item.expr.as_ref(),
+                    _ => panic!("expected project expression"),
+                })
+                .collect();
+            let is_scalar = |expr: &ast::Expr| matches!(expr, ast::Expr::ScalarSubQuery(_));
+            let arg = |expr: &'_ ast::Expr<'_>| match expr {
+                ast::Expr::Call(call) => match &call.node.args[0].node {
+                    ast::CallArg::Positional(value) => is_scalar(value),
+                    ast::CallArg::Named(arg) => is_scalar(&arg.value),
+                    _ => panic!("expected argument"),
+                },
+                _ => panic!("expected call"),
+            };
+            let ast::Expr::Case(case) = exprs[0] else {
+                panic!("expected CASE")
+            };
+            let ast::Case::SearchedCase(case) = &case.node else {
+                panic!("expected searched CASE")
+            };
+            assert!(is_scalar(&case.cases[0].first));
+            let ast::Expr::Case(case) = exprs[1] else {
+                panic!("expected CASE")
+            };
+            let ast::Case::SimpleCase(case) = &case.node else {
+                panic!("expected simple CASE")
+            };
+            assert!(is_scalar(&case.expr));
+            assert!(is_scalar(&case.cases[0].first));
+            assert!(arg(exprs[2]));
+            assert!(arg(exprs[3]));
+            assert!(!arg(exprs[4]));
+            parse!(r#"SELECT a FROM t WHERE a > (SELECT MAX(b) FROM u) + 1"#);
+            parse!(r#"SELECT a FROM t WHERE a IN (SELECT b FROM u)"#);
+        }
+
+        #[test]
+        fn exists() {
+            parse!(r#"SELECT a FROM t WHERE EXISTS (SELECT * FROM u WHERE u.b = t.a)"#);
+            parse!(r#"SELECT a FROM t WHERE NOT EXISTS (SELECT VALUE b FROM u)"#);
+            parse!(r#"SELECT a FROM t WHERE EXISTS (SELECT b FROM u) AND a > 1"#);
+            parse!(r#"EXISTS(`[1]`)"#);
+            parse!(r#"exists(t.items)"#);
+        }
+
+        #[test]
+        fn select_with_qualified_joins() {
+            parse!(r#"SELECT * FROM a AS a JOIN b AS b ON a.id = b.id"#);
+            parse!(r#"SELECT * FROM a AS a INNER JOIN b AS b ON a.id = b.id"#);
+            parse!(r#"SELECT * FROM a AS a LEFT JOIN b AS b ON a.id = b.id"#);
+            parse!(r#"SELECT * FROM a AS a LEFT OUTER JOIN b AS b ON a.id = b.id"#);
+            parse!(r#"SELECT * FROM a AS a RIGHT OUTER JOIN b AS b ON a.id = b.id"#);
+            parse!(r#"SELECT * FROM a AS a FULL JOIN b AS b ON a.id = b.id"#);
+            parse!(r#"SELECT * FROM a AS a JOIN b AS b USING (id)"#);
+            parse!(r#"SELECT * FROM a AS a LEFT JOIN b AS b USING (id, info.code)"#);
+            parse!(
+                r#"SELECT * FROM a AS a JOIN b AS b USING (id) FULL OUTER JOIN c AS c ON b.x = c.x"#
+            );
+        }
+
         #[test]
         fn multiline_with_comments() {
             parse!(
@@ -555,12 +1168,12 @@ mod tests {
 
         impl<'input> ParserState<'input, NullIdGenerator> {
             pub(crate) fn new_null_id() -> ParserState<'input, NullIdGenerator> {
-                ParserState::with_id_gen(NullIdGenerator::default())
+                ParserState::with_id_gen(arena(), NullIdGenerator::default())
             }
         }
 
-        fn parse_partiql_null_id(s: &str) -> AstResult {
-            super::parse_partiql_with_state(s, ParserState::new_null_id())
+        fn parse_partiql_null_id(s: &str) -> AstResult<'_> {
+            super::parse_partiql_with_state(s, ParserState::new_null_id(), &Default::default())
         }
 
         // parse partiql query with all AST nodes having an id of `0` for ease of comparison regardless
@@ -721,6 +1334,91 @@ mod tests {
             parse!(r#"count(*)"#);
         }
 
+        #[test]
+        fn agg_set_quantifier() {
+            let setq = |q| {
+                let ast = parse!(q);
+                let ast::QuerySet::Expr(expr) = &ast.node.query.node.set.node else {
+                    panic!("expected expression")
+                };
+                let ast::Expr::CallAgg(call) = expr.as_ref() else {
+                    panic!("expected aggregate call")
+                };
+                call.node.setq.clone()
+            };
+            assert_eq!(setq("COUNT(a)"), None);
+            assert_eq!(
+                setq("COUNT(DISTINCT a)"),
+                Some(ast::SetQuantifier::Distinct)
+            );
+            assert_eq!(setq("sum(all a.b)"), Some(ast::SetQuantifier::All));
+            assert_eq!(
+                setq("AVG(DISTINCT [1, 1, 2])"),
+                Some(ast::SetQuantifier::Distinct)
+            );
+
+            assert!(parse_partiql("upper(DISTINCT a)").is_err());
+        }
+
+        #[test]
+        fn coll_agg_set_quantifier() {
+            parse!("COLL_SUM([1, 2, 3])");
+            let ast = parse!("coll_count(DISTINCT [1, 1, 2])");
+            let ast::QuerySet::Expr(expr) = &ast.node.query.node.set.node else {
+                panic!("expected expression")
+            };
+            let ast::Expr::Call(call) = expr.as_ref() else {
+                panic!("expected call")
+            };
+            let [arg] = &call.node.args[..] else {
+                panic!("expected a single argument")
+            };
+            let ast::CallArg::Named(ast::CallArgNamed { name, .. }) = &arg.node else {
+                panic!("expected a named argument")
+            };
+            assert_eq!(name.value, "distinct");
+
+            assert!(parse_partiql("COLL_AVG(ALL a, b)").is_err());
+        }
+
+        #[test]
+        fn window() {
+            parse!(r#"SELECT ROW_NUMBER() OVER () AS n FROM t"#);
+            parse!(r#"SELECT RANK() OVER (PARTITION BY t.a ORDER BY t.b DESC) FROM t"#);
+            parse!(r#"SELECT LAG(t.b, 2, 0) OVER (PARTITION BY t.a, t.c ORDER BY t.b) FROM t"#);
+            parse!(r#"SUM(a) OVER (ORDER BY b ROWS UNBOUNDED PRECEDING)"#);
+
+            let frame = |q| {
+                let ast = parse!(q);
+                let ast::QuerySet::Expr(expr) = &ast.node.query.node.set.node else {
+                    panic!("expected expression")
+                };
+                let ast::Expr::CallWindow(call) = expr.as_ref() else {
+                    panic!("expected window call")
+                };
+                call.node.frame.clone()
+            };
+            assert_eq!(frame("LEAD(a) OVER (ORDER BY b)"), None);
+            assert_eq!(
+                frame("AVG(a) OVER (ROWS BETWEEN 2 PRECEDING AND UNBOUNDED FOLLOWING)"),
+                Some(ast::WindowFrame {
+                    units: ast::WindowFrameUnits::Rows,
+                    start: ast::WindowFrameBound::Preceding(2),
+                    end: ast::WindowFrameBound::UnboundedFollowing,
+                })
+            );
+            assert_eq!(
+                frame("MAX(a) OVER (ORDER BY b RANGE CURRENT ROW)"),
+                Some(ast::WindowFrame {
+                    units: ast::WindowFrameUnits::Range,
+                    start: ast::WindowFrameBound::CurrentRow,
+                    end: ast::WindowFrameBound::CurrentRow,
+                })
+            );
+
+            assert!(parse_partiql("RANK() OVER (ROWS 1 FOLLOWING AND 2 FOLLOWING)").is_err());
+        }
+
         #[test]
         fn composed() {
             parse!(
@@ -769,6 +1467,344 @@ mod tests {
             parse!(r#"pUbLIC"#);
             parse!(r#"domain"#);
         }
+
+        #[test]
+        fn path_step_keywords() {
+            parse!(r#"SELECT t.order, t.limit, t.value, t.type FROM t"#);
+            parse!(r#"SELECT VALUE t.VALUE.select[0].FROM FROM t"#);
+            assert!(parse_partiql_statement("UPDATE t SET t.order = 1").is_ok());
+        }
+
+        #[test]
+        fn alias_keywords() {
+            parse!(r#"SELECT t.a AS order, t.b AS limit FROM t"#);
+            parse!(r#"SELECT "value".a FROM t AS "value" AT "first""#);
+            parse!(r#"SELECT "limit" FROM t LET t.b AS "limit""#);
+            parse!(r#"SELECT "order" FROM t GROUP BY t.a AS "order" GROUP AS "value""#);
+            parse!(r#"SELECT "value" FROM UNPIVOT t AS "value" AT "order""#);
+        }
+
+        #[test]
+        fn variable_keywords() {
+            for (query, keyword) in [
+                ("SELECT x FROM t AS limit", "limit"),
+                ("SELECT x FROM t AS x AT first", "first"),
+                ("SELECT x FROM t AS x AT i BY value", "value"),
+                ("SELECT a FROM t LET t.b AS limit", "limit"),
+                ("SELECT k FROM t GROUP BY t.a AS order", "order"),
+                ("SELECT k FROM t GROUP BY t.a AS k GROUP AS value", "value"),
+                ("SELECT v FROM UNPIVOT t AS value", "value"),
+            ] {
+                let err_data = parse_partiql(query).unwrap_err();
+                let expected = format!(
+                    "keyword `{keyword}` cannot name a variable unless quoted, e.g., `\"{keyword}\"`"
+                );
+                assert!(
+                    err_data.errors[0].to_string().contains(&expected),
+                    "{query}: {:?}",
+                    err_data.errors
+                );
+            }
+        }
+
+        #[test]
+        fn keywords_in_reserved_positions() {
+            // without `AS`, a keyword following an expression is not an alias
+            assert!(parse_partiql("SELECT a order FROM t").is_err());
+            // a keyword projected as an identifier may not begin a larger expression
+            assert!(parse_partiql("SELECT limit + 1 FROM t").is_err());
+            assert!(parse_partiql("SELECT a, order[0] FROM t").is_err());
+            assert!(parse_partiql("SELECT value FROM t AS order").is_err());
+        }
+
+        #[test]
+        fn projection_keywords() {
+            parse!(r#"SELECT value FROM t"#);
+            parse!(r#"SELECT limit FROM t"#);
+            parse!(r#"SELECT order FROM t"#);
+            parse!(r#"SELECT DISTINCT value, order AS o, limit.a FROM t"#);
+            parse!(r#"SELECT ALL t.a, value.b.c AS c FROM t"#);
+            parse!(r#"SELECT VALUE value FROM t"#);
+
+            let ast = parse_partiql_statement("SELECT Value, NULL FROM t")
+                .unwrap()
+                .ast;
+            assert_eq!(ast.to_string(), "SELECT Value, NULL FROM t");
+        }
+
+        #[test]
+        fn keyword_identifier_text() {
+            let ast = parse_partiql_statement("SELECT t.Order AS Limit FROM t")
+                .unwrap()
+                .ast;
+            assert_eq!(ast.to_string(), "SELECT t.Order AS Limit FROM t");
+        }
+    }
+
+    mod exec {
+        use super::*;
+
+        macro_rules! parse_exec {
+            ($q:expr) => {{
+                let res = parse_partiql_statement($q);
+                println!("{:#?}", res);
+                match res {
+                    Ok(AstData {
+                        ast: ast::Item::Exec(exec),
+                        ..
+                    }) => exec.node,
+                    _ => panic!("{:?}", res),
+                }
+            }};
+        }
+
+        #[test]
+        fn exec() {
+            let exec = parse_exec!(r#"EXEC foo"#);
+            assert_eq!(exec.procedure_name.value, "foo");
+            assert!(exec.args.is_empty());
+
+            let exec = parse_exec!(r#"exec "Foo" 'bar', 1 + 2, [3, 4], (SELECT * FROM t)"#);
+            assert_eq!(exec.procedure_name.value, "Foo");
+            assert_eq!(
+                exec.procedure_name.case,
+                ast::CaseSensitivity::CaseSensitive
+            );
+            assert_eq!(exec.args.len(), 4);
+            assert!(matches!(*exec.args[1], ast::Expr::BinOp(_)));
+
+            assert!(parse_partiql_statement("EXEC").is_err());
+            assert!(parse_partiql_statement("EXEC foo 1,").is_err());
+            assert!(parse_partiql_statement("EXEC foo, 1").is_err());
+        }
+    }
+
+    mod ddl {
+        use super::*;
+
+        macro_rules! parse_ddl {
+            ($q:expr) => {{
+                let res = parse_partiql_statement($q);
+                println!("{:#?}", res);
+                match res {
+                    Ok(AstData {
+                        ast: ast::Item::Ddl(ddl),
+                        ..
+                    }) => ddl.node.op,
+                    _ => panic!("{:?}", res),
+                }
+            }};
+        }
+
+        #[test]
+        fn tables() {
+            let ast::DdlOp::CreateTable(create) = parse_ddl!(r#"CREATE TABLE foo"#) else {
+                panic!("expected CREATE TABLE")
+            };
+            assert_eq!(create.table_name.value, "foo");
+            assert_eq!(
+                create.table_name.case,
+                ast::CaseSensitivity::CaseInsensitive
+            );
+
+            let ast::DdlOp::DropTable(drop) = parse_ddl!(r#"drop table "Foo""#) else {
+                panic!("expected DROP TABLE")
+            };
+            assert_eq!(drop.table_name.value, "Foo");
+            assert_eq!(drop.table_name.case, ast::CaseSensitivity::CaseSensitive);
+
+            assert!(parse_partiql_statement("CREATE TABLE").is_err());
+            assert!(parse_partiql_statement("DROP foo").is_err());
+        }
+
+        #[test]
+        fn indexes() {
+            let ast::DdlOp::CreateIndex(create) = parse_ddl!(r#"CREATE INDEX ON foo (a, b.c[0])"#)
+            else {
+                panic!("expected CREATE INDEX")
+            };
+            assert_eq!(create.index_name.value, "foo");
+            assert_eq!(create.fields.len(), 2);
+            assert!(matches!(*create.fields[0], ast::Expr::VarRef(_)));
+            assert!(matches!(*create.fields[1], ast::Expr::Path(_)));
+
+            let ast::DdlOp::DropIndex(drop) = parse_ddl!(r#"DROP INDEX bar ON foo"#) else {
+                panic!("expected DROP INDEX")
+            };
+            assert_eq!(drop.keys.value, "bar");
+            assert_eq!(drop.table.value, "foo");
+
+            assert!(parse_partiql_statement("CREATE INDEX ON foo ()").is_err());
+            assert!(parse_partiql_statement("DROP INDEX bar").is_err());
+        }
+    }
+
+    mod dml {
+        use super::*;
+
+        macro_rules! parse_dml {
+            ($q:expr) => {{
+                let res = parse_partiql_statement($q);
+                println!("{:#?}", res);
+                match res {
+                    Ok(AstData {
+                        ast: ast::Item::Dml(dml),
+                        ..
+                    }) => dml.node,
+                    _ => panic!("{:?}", res),
+                }
+            }};
+        }
+
+        #[test]
+        fn insert_value() {
+            let dml = parse_dml!(r#"INSERT INTO tbl VALUE {'a': 1, 'b': [2, 3]}"#);
+            let ast::DmlOp::InsertValue(insert) = dml.op else {
+                panic!("expected INSERT INTO ... VALUE")
+            };
+            assert!(matches!(*insert.target, ast::Expr::VarRef(_)));
+            assert!(insert.index.is_none());
+
+            let dml = parse_dml!(r#"insert into db."Tbl" value 'v' at 'k'"#);
+            let ast::DmlOp::InsertValue(insert) = dml.op else {
+                panic!("expected INSERT INTO ... VALUE")
+            };
+            assert!(matches!(*insert.target, ast::Expr::Path(_)));
+            assert!(insert.index.is_some());
+        }
+
+        #[test]
+        fn insert_values() {
+            let dml = parse_dml!(r#"INSERT INTO tbl << {'a': 1}, {'a': 2} >>"#);
+            let ast::DmlOp::Insert(insert) = dml.op else {
+                panic!("expected INSERT INTO")
+            };
+            assert!(matches!(*insert.values, ast::Expr::Lit(_)));
+
+            let dml = parse_dml!(r#"INSERT INTO tbl SELECT x.a FROM other AS x WHERE x.a > 1"#);
+            let ast::DmlOp::Insert(insert) = dml.op else {
+                panic!("expected INSERT INTO")
+            };
+            assert!(matches!(*insert.values, ast::Expr::Query(_)));
+        }
+
+        #[test]
+        fn delete() {
+            let dml = parse_dml!(r#"DELETE FROM tbl"#);
+            assert!(matches!(dml.op, ast::DmlOp::Delete(_)));
+            assert!(dml.where_clause.is_none());
+
+            let dml = parse_dml!(r#"DELETE FROM db.tbl AS t WHERE t.a = 1 AND t.b IS NULL"#);
+            assert!(matches!(dml.op, ast::DmlOp::Delete(_)));
+            assert!(dml.where_clause.is_some());
+            let Some(ast::FromClause {
+                source: ast::FromSource::FromLet(from_let),
+            }) = dml.from_clause
+            else {
+                panic!("expected a FROM clause")
+            };
+            assert!(matches!(*from_let.node.expr, ast::Expr::Path(_)));
+            assert_eq!(from_let.node.as_alias.map(|a| a.value), Some("t"));
+        }
+
+        #[test]
+        fn delete_returning() {
+            let dml = parse_dml!(r#"DELETE FROM tbl t WHERE t.a = 1 RETURNING ALL OLD *"#);
+            let elems = dml.returning.expect("RETURNING").elems;
+            assert_eq!(elems.len(), 1);
+            assert_eq!(elems[0].mapping, ast::ReturningMapping::AllOld);
+            assert_eq!(elems[0].column, ast::ColumnComponent::ReturningWildcard);
+
+            let dml = parse_dml!(r#"delete from tbl returning modified new t.a, all old t.b"#);
+            let elems = dml.returning.expect("RETURNING").elems;
+            let mappings: Vec<_> = elems.iter().map(|e| e.mapping.clone()).collect();
+            assert_eq!(
+                mappings,
+                vec![
+                    ast::ReturningMapping::ModifiedNew,
+                    ast::ReturningMapping::AllOld
+                ]
+            );
+            assert!(matches!(
+                elems[0].column,
+                ast::ColumnComponent::ReturningColumn(_)
+            ));
+
+            assert!(parse_partiql_statement("DELETE FROM tbl RETURNING ALL NEWER *").is_err());
+            assert!(parse_partiql_statement("DELETE FROM tbl RETURNING SOME OLD *").is_err());
+            assert!(parse_partiql_statement("DELETE tbl WHERE a = 1").is_err());
+        }
+
+        #[test]
+        fn update_set() {
+            let dml = parse_dml!(r#"UPDATE tbl SET a = 1"#);
+            let ast::DmlOp::Set(set) = dml.op else {
+                panic!("expected UPDATE ... SET")
+            };
+            assert_eq!(set.assignments.len(), 1);
+            assert!(matches!(*set.assignments[0].target, ast::Expr::VarRef(_)));
+            assert!(dml.where_clause.is_none());
+
+            let dml = parse_dml!(
+                r#"UPDATE db.tbl AS t SET t.a.b = t.a.b + 1, t.c[0] = 'x' WHERE t.id = 1
+                   RETURNING MODIFIED NEW *"#
+            );
+            let ast::DmlOp::Set(set) = dml.op else {
+                panic!("expected UPDATE ... SET")
+            };
+            let targets: Vec<_> = set
+                .assignments
+                .iter()
+                .map(|assignment| match assignment.target.as_ref() {
+                    ast::Expr::Path(path) => path.node.steps.len(),
+                    other => panic!("expected a path, found {other:?}"),
+                })
+                .collect();
+            assert_eq!(targets, vec![2, 2]);
+            assert!(dml.where_clause.is_some());
+            assert!(dml.returning.is_some());
+            let Some(ast::FromClause {
+                source: ast::FromSource::FromLet(from_let),
+            }) = dml.from_clause
+            else {
+                panic!("expected a FROM clause")
+            };
+            assert!(matches!(*from_let.node.expr, ast::Expr::Path(_)));
+
+            assert!(parse_partiql_statement("UPDATE tbl SET").is_err());
+            assert!(parse_partiql_statement("UPDATE tbl SET t.* = 1").is_err());
+            assert!(parse_partiql_statement("UPDATE tbl SET a == 1").is_err());
+        }
+
+        #[test]
+        fn update_remove() {
+            let dml = parse_dml!(r#"update tbl t remove t.a[1] where t.b > 2"#);
+            let ast::DmlOp::Remove(remove) = dml.op else {
+                panic!("expected UPDATE ... REMOVE")
+            };
+            assert!(matches!(*remove.target, ast::Expr::Path(_)));
+            assert!(dml.where_clause.is_some());
+
+            assert!(parse_partiql_statement("UPDATE tbl REMOVE").is_err());
+            assert!(parse_partiql_statement("UPDATE tbl REMOVE a, b").is_err());
+        }
+
+        #[test]
+        fn statements() {
+            let res = parse_partiql_statement("SELECT * FROM tbl");
+            assert!(matches!(
+                res,
+                Ok(AstData {
+                    ast: ast::Item::Query(_),
+                    ..
+                })
+            ));
+
+            // `parse_partiql` only accepts queries
+            assert!(parse_partiql("INSERT INTO tbl VALUE 1").is_err());
+            assert!(parse_partiql_statement("INSERT INTO tbl[0] VALUE 1").is_err());
+            assert!(parse_partiql_statement("INSERT tbl VALUE 1").is_err());
+        }
     }
 
     mod errors {
@@ -793,11 +1829,21 @@ mod tests {
             assert!(res.is_err());
             let err_data = res.unwrap_err();
             assert_eq!(2, err_data.errors.len());
+            let ParseError::UnexpectedToken(UnexpectedToken {
+                inner: UnexpectedTokenData { expected, .. },
+                ..
+            }) = &err_data.errors[0]
+            else {
+                panic!("expected an unexpected token error")
+            };
+            assert!(expected.contains(&"SELECT".to_string()));
             assert_eq!(
                 err_data.errors[0],
                 ParseError::UnexpectedToken(UnexpectedToken {
                     inner: UnexpectedTokenData {
-                        token: Cow::from("/")
+                        token: Cow::from("/"),
+                        expected: expected.clone(),
+                        suggestion: None,
                     },
                     location: Location {
                         start: BytePosition::from(0),
@@ -816,5 +1862,626 @@ mod tests {
                 })
             );
         }
+
+        #[test]
+        fn misspelled_keyword() {
+            let err_data = parse_partiql("SELCT a FROM t").unwrap_err();
+            assert!(err_data.errors[0]
+                .to_string()
+                .ends_with("; did you mean `SELECT` instead of `SELCT`?"));
+        }
+
+        #[test]
+        fn partial_ast() {
+            let err_data = parse_partiql_statement("SELECT a, ^ FROM t").unwrap_err();
+            assert_eq!(2, err_data.errors.len());
+            let recovered = err_data.recovered.expect("recovered ast");
+            assert_eq!(
+                recovered.ast.to_string(),
+                "SELECT a, <error> ^ <error> FROM t"
+            );
+            assert!(!recovered.locations.is_empty());
+
+            let err_data = parse_partiql_statement("SELECT `a").unwrap_err();
+            assert_eq!(1, err_data.errors.len());
+            assert!(err_data.recovered.is_none());
+        }
+
+        #[test]
+        fn nesting_depth() {
+            let options = ParserOptions {
+                max_nesting_depth: Some(3),
+                ..Default::default()
+            };
+            assert!(super::super::parse_partiql("[(<<{'a': 1}>>)]", arena(), &options).is_err());
+            assert!(super::super::parse_partiql("[(<<1>>), (<<2>>)]", arena(), &options).is_ok());
+
+            let err_data = super::super::parse_partiql("((((1))))", arena(), &options).unwrap_err();
+            assert_eq!(
+                err_data.errors,
+                vec![ParseError::NestingTooDeep(Located {
+                    inner: 3,
+                    location: Location {
+                        start: BytePosition::from(3),
+                        end: BytePosition::from(4),
+                    },
+                })]
+            );
+        }
+
+        #[test]
+        fn default_nesting_depth() {
+            let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+            let max_depth = super::super::DEFAULT_MAX_NESTING_DEPTH;
+            assert!(parse_partiql(&nested(max_depth)).is_ok());
+
+            // Far deeper text fails with an error rather than overflowing the stack
+            let too_deep = nested(100_000);
+            let err_data = parse_partiql(&too_deep).unwrap_err();
+            assert_eq!(err_data.errors.len(), 1);
+            assert!(matches!(
+                err_data.errors[0],
+                ParseError::NestingTooDeep(Located { inner, .. }) if inner == max_depth
+            ));
+        }
+
+        #[test]
+        fn operator_nesting_depth() {
+            let max_depth = super::super::DEFAULT_MAX_NESTING_DEPTH;
+            let too_deep = |text: String| {
+                let err_data = parse_partiql(&text).unwrap_err();
+                assert_eq!(err_data.errors.len(), 1);
+                assert!(matches!(
+                    err_data.errors[0],
+                    ParseError::NestingTooDeep(Located { inner, .. }) if inner == max_depth
+                ));
+            };
+
+            // Far deeper operators fail with an error rather than overflowing the stack, be it
+            // when parsing, lowering or dropping their AST
+            too_deep(format!("{}true", "NOT ".repeat(100_000)));
+            too_deep(format!("{}1", "- ".repeat(100_000)));
+            too_deep(format!("[{}1]", "- ".repeat(100_000)));
+            too_deep(format!("{}1", "- (".repeat(100_000)));
+            too_deep(format!(
+                "SELECT * FROM t WHERE {}true END",
+                "CASE WHEN ".repeat(100_000)
+            ));
+            too_deep(format!(
+                "SELECT * FROM t{}",
+                " UNION SELECT * FROM t".repeat(20_000)
+            ));
+            too_deep(format!(
+                "(SELECT * FROM t){}",
+                " INTERSECT (SELECT * FROM t)".repeat(20_000)
+            ));
+
+            assert!(parse_partiql(&format!("{}true", "NOT ".repeat(max_depth))).is_ok());
+            let set_ops = " EXCEPT SELECT * FROM t".repeat(max_depth);
+            assert!(parse_partiql(&format!("SELECT * FROM t{set_ops}")).is_ok());
+            // Chains of other binary operators do not nest, nor do operators separated by commas,
+            // or nested in closed delimiters
+            assert!(parse_partiql(&format!("1{}", " + 1".repeat(10_000))).is_ok());
+            assert!(parse_partiql(&format!("1{}", " - -1".repeat(10_000))).is_ok());
+            let predicates: Vec<_> = (0..10_000).map(|i| format!("a = {i}")).collect();
+            let predicates = predicates.join(" OR ");
+            assert!(parse_partiql(&format!("SELECT * FROM t WHERE {predicates}")).is_ok());
+            assert!(
+                parse_partiql(&format!("a IS NOT NULL{}", " AND NOT b".repeat(10_000))).is_ok()
+            );
+            let list = vec!["- 1 + 1"; 100_000].join(", ");
+            assert!(parse_partiql(&format!("[{list}]")).is_ok());
+            let sum = vec!["(NOT a)"; 400].join(" + ");
+            assert!(parse_partiql(&sum).is_ok());
+        }
+
+        #[test]
+        fn without_error_recovery() {
+            let options = ParserOptions {
+                error_recovery: false,
+                ..Default::default()
+            };
+            let err_data = super::super::parse_partiql(r#"/`܋"#, arena(), &options).unwrap_err();
+            assert_eq!(1, err_data.errors.len());
+            assert!(matches!(err_data.errors[0], ParseError::UnexpectedToken(_)));
+
+            // parsing stops at the first error, which is reported without recovering an AST
+            let text = "SELECT (1 + ), (2 + ) FROM t";
+            let recovered =
+                super::super::parse_partiql(text, arena(), &Default::default()).unwrap_err();
+            assert_eq!(2, recovered.errors.len());
+            assert!(recovered.recovered.is_some());
+            let err_data = super::super::parse_partiql(text, arena(), &options).unwrap_err();
+            assert_eq!(err_data.errors, recovered.errors[..1]);
+            assert!(err_data.recovered.is_none());
+        }
+    }
+
+    mod pretty {
+        use super::*;
+
+        fn parse_statement_null_id(s: &str) -> ast::Item<'_> {
+            let res =
+                parse_statement_with_state(s, ParserState::new_null_id(), &Default::default());
+            match res {
+                Ok(data) => data.ast,
+                _ => panic!("{s}: {res:?}"),
+            }
+        }
+
+        /// Asserts that the rendering of the statement `s` parses back to the statement's AST
+        /// and renders the same; returns the rendering.
+        fn round_trip(s: &str) -> String {
+            let ast = parse_statement_null_id(s);
+            let text = ast.to_string();
+            {
+                let reparsed = parse_statement_null_id(&text);
+                assert_eq!(ast, reparsed, "{s} rendered as {text}");
+                assert_eq!(text, reparsed.to_string());
+            }
+            text
+        }
+
+        #[test]
+        fn canonical() {
+            assert_eq!(
+                round_trip("select   a as x, b from t where   c=1 and not d"),
+                "SELECT a AS x, b FROM t WHERE c = 1 AND NOT d"
+            );
+            assert_eq!(
+                round_trip("SELECT ALL * FROM t AS t AT i"),
+                "SELECT * FROM t AS t AT i"
+            );
+            assert_eq!(round_trip("a != b"), "a <> b");
+            assert_eq!(round_trip("x IS NOT NULL"), "x IS NOT NULL");
+            assert_eq!(
+                round_trip("SELECT COLL_SUM(DISTINCT [1, 2]) FROM t"),
+                "SELECT COLL_SUM(DISTINCT [1, 2]) FROM t"
+            );
+            assert_eq!(round_trip("coll_count(all x)"), "coll_count(ALL x)");
+            assert_eq!(round_trip("`{a: 1}`"), "`{a: 1}`");
+            assert_eq!(round_trip("a.\"B\"[0][*].*.@c"), "a.\"B\"[0][*].*.@c");
+        }
+
+        #[test]
+        fn precedence() {
+            assert_eq!(round_trip("(a + b) * c"), "(a + b) * c");
+            assert_eq!(round_trip("a + (b * c)"), "a + b * c");
+            assert_eq!(round_trip("a - (b - c)"), "a - (b - c)");
+            assert_eq!(round_trip("(a - b) - c"), "a - b - c");
+            assert_eq!(round_trip("- (- a)"), "-(-a)");
+            assert_eq!(round_trip("a - -b"), "a - -b");
+            assert_eq!(round_trip("(a OR b) AND c"), "(a OR b) AND c");
+            assert_eq!(round_trip("NOT (a AND b)"), "NOT (a AND b)");
+            assert_eq!(round_trip("(a < b) = (c < d)"), "a < b = c < d");
+            assert_eq!(round_trip("(a = b) = c"), "a = b = c");
+            assert_eq!(round_trip("a = (b = c)"), "a = (b = c)");
+            assert_eq!(round_trip("(a || b) LIKE c || d"), "a || b LIKE c || d");
+            assert_eq!(round_trip("(a LIKE b) IS TRUE"), "a LIKE b IS TRUE");
+            assert_eq!(round_trip("(a + b).c"), "(a + b).c");
+            assert_eq!(
+                round_trip("(CASE WHEN a THEN b END).c"),
+                "(CASE WHEN a THEN b END).c"
+            );
+        }
+
+        #[test]
+        fn exprs() {
+            round_trip("x NOT LIKE '%a' ESCAPE '\\'");
+            round_trip("x BETWEEN 1 AND 2 + 3");
+            round_trip("x NOT BETWEEN 1 AND 2");
+            round_trip("x IN (1, 2, 3)");
+            round_trip("x NOT IN (SELECT y FROM t)");
+            round_trip("x IS DISTINCT FROM y");
+            round_trip("x IS NOT DISTINCT FROM y");
+            round_trip("NOT x IS MISSING");
+            round_trip("1.5 + 2.0 * 1e3 - 3 ^ 2 % 4");
+            round_trip("'it''s' || 'a'");
+            round_trip("{'a': [1, 2], 'b': <<x, y>>, c: {'d': NULL}}");
+            round_trip("<<<<1>>, [x, MISSING]>>");
+            round_trip("CASE x WHEN 1 THEN 'a' WHEN 2 THEN 'b' ELSE 'c' END");
+            round_trip("CASE WHEN x > 1 THEN 'a' END");
+            round_trip("EXISTS (SELECT * FROM t)");
+            round_trip("upper(a) || lower(b)");
+            round_trip("COUNT(*) + COUNT(DISTINCT a) + SUM(ALL b)");
+            round_trip("TIME WITH TIME ZONE '23:59:59'");
+            round_trip("DATE '2023-01-01'");
+            round_trip("? + ? + :a + :\"B\"");
+            round_trip("foo(1, 2).a[x + 1]");
+        }
+
+        #[test]
+        fn special_forms() {
+            assert_eq!(round_trip("CAST(x AS INT)"), "CAST(x AS INT)");
+            assert_eq!(
+                round_trip("CAST(a AS VARCHAR(20))"),
+                "CAST(a AS VARCHAR(20))"
+            );
+            assert_eq!(round_trip("cast(x as int)"), "cast(x as int)");
+            assert_eq!(round_trip("CAN_CAST(x AS BOOL)"), "CAN_CAST(x AS BOOL)");
+            assert_eq!(
+                round_trip("TRIM(LEADING 'a' FROM x)"),
+                "TRIM(LEADING 'a' FROM x)"
+            );
+            assert_eq!(round_trip("TRIM(BOTH FROM x)"), "TRIM(BOTH ' ' FROM x)");
+            assert_eq!(round_trip("TRIM(' ' FROM x)"), "TRIM(' ' FROM x)");
+            assert_eq!(round_trip("TRIM(FROM x)"), "TRIM(FROM x)");
+            assert_eq!(round_trip("TRIM(x)"), "TRIM(x)");
+            assert_eq!(
+                round_trip("SUBSTRING(x FROM 2 FOR 3)"),
+                "SUBSTRING(x FROM 2 FOR 3)"
+            );
+            assert_eq!(round_trip("SUBSTRING(x FROM 2)"), "SUBSTRING(x FROM 2)");
+            assert_eq!(round_trip("SUBSTRING(x, 2, 3)"), "SUBSTRING(x, 2, 3)");
+            assert_eq!(round_trip("EXTRACT(YEAR FROM x)"), "EXTRACT(YEAR FROM x)");
+            assert_eq!(
+                round_trip("EXTRACT(TIMEZONE_HOUR FROM x)"),
+                "EXTRACT(TIMEZONE_HOUR FROM x)"
+            );
+            assert_eq!(round_trip("POSITION('a' IN x)"), "POSITION('a' IN x)");
+            assert_eq!(
+                round_trip("POSITION(('a' IN x) IN y)"),
+                "POSITION(('a' IN x) IN y)"
+            );
+            assert_eq!(
+                round_trip("OVERLAY(x PLACING 'a' FROM 2 FOR 3)"),
+                "OVERLAY(x PLACING 'a' FROM 2 FOR 3)"
+            );
+            assert_eq!(
+                round_trip("OVERLAY(x PLACING 'a' FROM 2)"),
+                "OVERLAY(x PLACING 'a' FROM 2)"
+            );
+        }
+
+        #[test]
+        fn queries() {
+            round_trip("SELECT DISTINCT a.*, b FROM t AS a, u AS b");
+            round_trip("SELECT VALUE {'a': v} FROM t AS v");
+            assert_eq!(
+                round_trip("SELECT d + interval '1:30' hour(2) to second(3) FROM t"),
+                "SELECT d + INTERVAL '1:30' HOUR(2) TO SECOND(3) FROM t"
+            );
+            round_trip("INTERVAL '1.5' SECOND(2, 3)");
+            round_trip("PIVOT v AT k FROM t AS v");
+            round_trip("SELECT * FROM UNPIVOT t AS v AT k");
+            round_trip("FROM t AS x WHERE x.a > 1 SELECT x.b");
+            round_trip("SELECT x FROM t AS x LET x.a + 1 AS y WHERE y > 2");
+            round_trip("SELECT k, g FROM t AS x GROUP BY x.a AS k GROUP AS g HAVING COUNT(*) > 1");
+            round_trip("SELECT k FROM t AS x GROUP PARTIAL BY x.a AS k");
+            round_trip("SELECT a FROM t ORDER BY a DESC NULLS LAST, b ASC LIMIT 10 OFFSET 5");
+            round_trip("SELECT a FROM t ORDER BY PRESERVE");
+            round_trip("SELECT * FROM a LEFT JOIN b ON a.id = b.id INNER JOIN c ON TRUE");
+            round_trip("SELECT * FROM a FULL OUTER JOIN (b JOIN c ON TRUE) ON TRUE");
+            round_trip("SELECT * FROM a CROSS JOIN b, c");
+            round_trip("SELECT * FROM a NATURAL JOIN b");
+            round_trip("SELECT * FROM a JOIN b USING (id, info.id)");
+            round_trip("SELECT (SELECT MAX(y) FROM u) AS m FROM t");
+            round_trip("SELECT AVG(SELECT VALUE p FROM g AS v) FROM t GROUP BY x GROUP AS g");
+            round_trip(
+                "SELECT ROW_NUMBER() OVER (PARTITION BY a ORDER BY b ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) FROM t",
+            );
+            round_trip("SELECT SUM(a) OVER (ROWS UNBOUNDED PRECEDING) FROM t");
+            round_trip("WITH x (a, b) AS (SELECT * FROM t), y AS (u) SELECT * FROM x, y");
+            round_trip("VALUES (1), [2, 3]");
+            round_trip("TABLE t");
+        }
+
+        #[test]
+        fn set_ops() {
+            assert_eq!(
+                round_trip("a UNION ALL (b UNION c)"),
+                "a UNION ALL (b UNION c)"
+            );
+            assert_eq!(round_trip("(a UNION b) UNION c"), "a UNION b UNION c");
+            round_trip(
+                "(SELECT a FROM b ORDER BY c LIMIT 1) OUTER EXCEPT (SELECT d FROM e LIMIT 2) ORDER BY f",
+            );
+            round_trip("SELECT a FROM b INTERSECT SELECT c FROM d");
+            round_trip("(SELECT a FROM b) UNION (SELECT c FROM d) LIMIT 1");
+        }
+
+        #[test]
+        fn statements() {
+            round_trip("INSERT INTO t VALUE {'a': 1} AT 0");
+            round_trip("INSERT INTO db.t SELECT * FROM u");
+            round_trip("INSERT INTO t <<1, 2>>");
+            round_trip("UPDATE t AS x SET x.a = 1, x.b[0] = 2 WHERE x.c RETURNING MODIFIED NEW *");
+            round_trip("UPDATE t REMOVE t.a");
+            round_trip("DELETE FROM t AS x WHERE x.a = 1 RETURNING ALL OLD x.b");
+            round_trip("CREATE TABLE t");
+            round_trip("DROP TABLE \"T\"");
+            round_trip("CREATE INDEX ON t (a, b.c)");
+            round_trip("DROP INDEX i ON t");
+            round_trip("EXEC foo 'a', 1 + 2");
+            round_trip("EXEC bar");
+        }
+    }
+
+    mod locations {
+        use super::*;
+        use partiql_ast::visit::{Traverse, Visit, Visitor};
+
+        #[derive(Default)]
+        struct NodeIds(Vec<ast::NodeId>);
+
+        impl<'ast> Visitor<'ast> for NodeIds {
+            fn enter_ast_node(&mut self, id: ast::NodeId) -> Traverse {
+                self.0.push(id);
+                Traverse::Continue
+            }
+        }
+
+        /// Asserts that every node of the AST of the statement `s` has a location within `s`.
+        fn assert_located(s: &str) {
+            let data = parse_partiql_statement(s).unwrap_or_else(|e| panic!("{s}: {e:?}"));
+            let mut ids = NodeIds::default();
+            data.ast.visit(&mut ids);
+            assert!(!ids.0.is_empty());
+            for id in ids.0 {
+                let location = data
+                    .locations
+                    .get(&id)
+                    .unwrap_or_else(|| panic!("{s}: no location for node {id:?}"));
+                assert!(location.start <= location.end);
+                assert!(location.end.0 .0 as usize <= s.len());
+            }
+        }
+
+        #[test]
+        fn every_node() {
+            assert_located("1 + 2 * -a.b[0].c[*].*");
+            assert_located("a NOT LIKE 'x%' ESCAPE '!' OR b IS NOT MISSING AND c NOT IN (1, 2)");
+            assert_located("CASE WHEN a THEN 1 ELSE 2 END || CASE b WHEN 1 THEN 'x' END");
+            assert_located("CAST(a AS INT) + TRIM(BOTH 'x' FROM b) + SUBSTRING(c FROM 1 FOR 2)");
+            assert_located("{'a': [1, <<2>>], 'b': `{c: d}`, 'e': DATE '2020-01-01'}");
+            assert_located(
+                "SELECT DISTINCT t.a AS x, COUNT(*) FROM t AS t AT i, @t.b AS u \
+                 LEFT JOIN v ON u.c = v.c \
+                 LET t.a + 1 AS y \
+                 WHERE EXISTS (SELECT VALUE w FROM u AS w) \
+                 GROUP BY t.a AS g GROUP AS grp HAVING COUNT(*) > 1 \
+                 ORDER BY x DESC NULLS LAST LIMIT 10 OFFSET 2",
+            );
+            assert_located("PIVOT v AT k FROM UNPIVOT t AS v AT k");
+            assert_located("(SELECT a FROM t) UNION ALL (SELECT b FROM u) ORDER BY a LIMIT 1");
+            assert_located("VALUES (1), (2)");
+            assert_located("SELECT * FROM (SELECT a FROM t) AS s CROSS JOIN u");
+            assert_located("INSERT INTO t VALUE {'a': 1} AT 'k'");
+            assert_located("DELETE FROM t AS x WHERE x.a = 1 RETURNING ALL OLD *");
+            assert_located("CREATE INDEX ON t (a, b.c[0])");
+            assert_located("EXEC foo 'a', 1 + 2");
+        }
+    }
+
+    mod trivia {
+        use super::*;
+        use crate::TriviaKind;
+
+        #[test]
+        fn comments() {
+            let s = "/* a /* nested */ comment */ SELECT a -- first\n FROM t --last";
+            let options = ParserOptions {
+                preserve_comments: true,
+                ..Default::default()
+            };
+            let trivia = super::super::parse_partiql(s, arena(), &options)
+                .unwrap()
+                .trivia;
+            let comments: Vec<_> = trivia
+                .iter()
+                .map(|t| (t.kind, t.text, t.location.start.0.to_usize()))
+                .collect();
+            assert_eq!(
+                comments,
+                vec![
+                    (TriviaKind::BlockComment, "/* a /* nested */ comment */", 0),
+                    (TriviaKind::LineComment, "-- first", 38),
+                    (TriviaKind::LineComment, "--last", 55),
+                ]
+            );
+            for t in &trivia {
+                let (start, end) = (t.location.start.0.to_usize(), t.location.end.0.to_usize());
+                assert_eq!(&s[start..end], t.text);
+            }
+
+            // comments are discarded by default
+            assert!(parse_partiql(s).unwrap().trivia.is_empty());
+        }
+
+        #[test]
+        fn comments_by_node() {
+            let s = "-- lead\nSELECT a, /* b */ b + 1 AS c FROM t /* t */ WHERE x -- trail";
+            let parsed = crate::Parser::builder()
+                .preserve_comments(true)
+                .build()
+                .parse(s, arena())
+                .unwrap();
+            let text = |id: &ast::NodeId| {
+                let loc = &parsed.locations[id];
+                &s[loc.start.0.to_usize()..loc.end.0.to_usize()]
+            };
+            let attached: Vec<_> = parsed
+                .trivia_by_node()
+                .into_iter()
+                .flat_map(|(id, trivia)| trivia.into_iter().map(move |t| (t.text, id)))
+                .map(|(comment, id)| (comment, text(&id)))
+                .collect();
+            assert_eq!(
+                attached,
+                vec![
+                    (
+                        "-- lead",
+                        "SELECT a, /* b */ b + 1 AS c FROM t /* t */ WHERE x"
+                    ),
+                    ("/* b */", "b + 1 AS c"),
+                    ("/* t */", "WHERE x"),
+                    (
+                        "-- trail",
+                        "SELECT a, /* b */ b + 1 AS c FROM t /* t */ WHERE x"
+                    ),
+                ]
+            );
+        }
+    }
+
+    /// The ASTs read from the Ion format of partiql-lang-kotlin are those parsed from the same
+    /// queries.
+    mod ion {
+        use super::*;
+        use ion_rs::element::Element;
+        use partiql_ast::ast::ast_eq;
+        use partiql_ast::ion::{from_ion, to_ion};
+
+        #[track_caller]
+        fn same_ast(query: &str, ion: &str) {
+            let parsed = parse!(query);
+            let read = from_ion(&Element::read_one(ion).unwrap(), arena()).unwrap();
+            assert!(ast_eq(&parsed, &read), "{query}:\n{parsed:#?}\n{read:#?}");
+            assert_eq!(to_ion(&parsed).unwrap(), to_ion(&read).unwrap(), "{query}");
+        }
+
+        #[test]
+        fn queries() {
+            same_ast(
+                "SELECT DISTINCT c.name AS n, @o.* \
+                 FROM \"customers\" AS c LEFT JOIN UNPIVOT orders AS o AT k ON c = o \
+                 LET 1 AS one \
+                 WHERE a > ? AND NOT b IS NULL \
+                 GROUP BY a AS k GROUP AS g \
+                 HAVING count(DISTINCT b) > 1 \
+                 ORDER BY k DESC NULLS LAST \
+                 LIMIT 10 OFFSET 5",
+                "(query (select (setq (distinct)) \
+                    (project (project_list \
+                        (project_expr (path (id c (case_insensitive) (unqualified)) \
+                            (path_expr (lit \"name\") (case_insensitive))) n) \
+                        (project_all (id o (case_insensitive) (locals_first))))) \
+                    (from (join (left) \
+                        (scan (id customers (case_sensitive) (unqualified)) c null null) \
+                        (unpivot (id orders (case_insensitive) (unqualified)) o k null) \
+                        (eq (id c (case_insensitive) (unqualified)) \
+                            (id o (case_insensitive) (unqualified))))) \
+                    (from_let (let (let_binding (lit 1) one))) \
+                    (where (and (gt (id a (case_insensitive) (unqualified)) (parameter 1)) \
+                        (not (is_type (id b (case_insensitive) (unqualified)) (null_type))))) \
+                    (group (group_by (group_full) \
+                        (group_key_list (group_key (id a (case_insensitive) (unqualified)) k)) \
+                        g)) \
+                    (having (gt (call_agg (distinct) count (id b (case_insensitive) (unqualified))) \
+                        (lit 1))) \
+                    (order (order_by (sort_spec (id k (case_insensitive) (unqualified)) (desc) \
+                        (nulls_last)))) \
+                    (limit (lit 10)) \
+                    (offset (lit 5))))",
+            );
+            same_ast(
+                "SELECT VALUE 1.5 OUTER UNION ALL PIVOT 'v' AT 'k' FROM t",
+                "(query (bag_op (outer_union) (all) \
+                    (select (project (project_value (lit 1.5)))) \
+                    (select (project (project_pivot (lit \"v\") (lit \"k\"))) \
+                        (from (scan (id t (case_insensitive) (unqualified)) null null null)))))",
+            );
+            same_ast(
+                "SELECT * FROM t CROSS JOIN u",
+                "(query (select (project (project_star)) \
+                    (from (join (inner) \
+                        (scan (id t (case_insensitive) (unqualified)) null null null) \
+                        (scan (id u (case_insensitive) (unqualified)) null null null) \
+                        null))))",
+            );
+        }
+
+        #[test]
+        fn exprs() {
+            same_ast(
+                "{'a': 1 + 2.5 * -3, \
+                  'b': [TRUE, NULL, MISSING, `{x: 1}`], \
+                  'c': <<1, x>>, \
+                  'd': 'abc' LIKE 'a%', \
+                  'e': 2 BETWEEN 1 AND 3, \
+                  'f': 1 IN [1, 2], \
+                  'g': CASE 1 WHEN 1 THEN 'one' END, \
+                  'h': CASE WHEN TRUE THEN 1 ELSE 2 END, \
+                  'i': cast(1 AS DECIMAL), \
+                  'j': coalesce(NULL, nullif(1, 2)), \
+                  'k': upper('a'), \
+                  'l': EXISTS(SELECT * FROM t), \
+                  'm': t.\"a\"[0][*].*}",
+                "(query (struct \
+                    (expr_pair (lit \"a\") (plus (lit 1) (times (lit 2.5) (neg (lit 3))))) \
+                    (expr_pair (lit \"b\") (list (lit true) (lit null) (missing) (lit {x: 1}))) \
+                    (expr_pair (lit \"c\") (bag (lit 1) (id x (case_insensitive) (unqualified)))) \
+                    (expr_pair (lit \"d\") (like (lit \"abc\") (lit \"a%\") null)) \
+                    (expr_pair (lit \"e\") (between (lit 2) (lit 1) (lit 3))) \
+                    (expr_pair (lit \"f\") (in_collection (lit 1) (list (lit 1) (lit 2)))) \
+                    (expr_pair (lit \"g\") (simple_case (lit 1) \
+                        (expr_pair_list (expr_pair (lit 1) (lit \"one\"))) null)) \
+                    (expr_pair (lit \"h\") (searched_case \
+                        (expr_pair_list (expr_pair (lit true) (lit 1))) (lit 2))) \
+                    (expr_pair (lit \"i\") (cast (lit 1) (decimal_type null null))) \
+                    (expr_pair (lit \"j\") (coalesce (lit null) (null_if (lit 1) (lit 2)))) \
+                    (expr_pair (lit \"k\") (call upper (lit \"a\"))) \
+                    (expr_pair (lit \"l\") (call exists (select (project (project_star)) \
+                        (from (scan (id t (case_insensitive) (unqualified)) null null null))))) \
+                    (expr_pair (lit \"m\") (path (id t (case_insensitive) (unqualified)) \
+                        (path_expr (lit \"a\") (case_sensitive)) \
+                        (path_expr (lit 0) (case_sensitive)) \
+                        (path_wildcard) (path_unpivot)))))",
+            );
+        }
+
+        /// The ASTs written as Ion are read back as they were parsed.
+        #[track_caller]
+        fn round_trip(query: &str) {
+            let parsed = parse!(query);
+            let ion = to_ion(&parsed).unwrap();
+            let read = from_ion(&ion, arena()).unwrap();
+            assert!(ast_eq(&parsed, &read), "{query}:\n{ion}");
+            assert_eq!(to_ion(&read).unwrap(), ion, "{query}");
+        }
+
+        #[test]
+        fn round_trips() {
+            round_trip(
+                "SELECT UTCNOW() AS \"Now\", Upper(x.\"Name\") AS \"T\", COUNT(*), COUNT(ALL a) \
+                 FROM t AS \"T\" AT \"k\" \
+                 GROUP BY a AS \"A\" GROUP AS \"G\"",
+            );
+            round_trip("SELECT \"foo\"(a), Foo(a), foo(INT) FROM t");
+            round_trip("1 + (SELECT x FROM s AS x)");
+            round_trip("x IN (SELECT x FROM s AS x)");
+            round_trip(
+                "[Trim(BOTH ' ' FROM a), trim(a), SUBSTRING(a FROM 2 FOR 3), \
+                  Extract(YEAR FROM a), POSITION('a' IN b)]",
+            );
+            round_trip(
+                "[CAST(a AS int), Cast(a AS VARCHAR(2)), cast(a AS INT), cast(a AS INTEGER), \
+                  CAN_CAST(a AS BOOL), NULLIF(a, b), Coalesce(a, b), nullif(a, b)]",
+            );
+            round_trip(
+                "[a IS int, a IS INT, a IS BOOL, a IS VARCHAR(2), a IS MISSING, a IS NOT NULL]",
+            );
+            round_trip("SELECT * FROM t JOIN u USING (a, b.c)");
+            round_trip("SELECT * FROM t NATURAL JOIN u, v JOIN w ON TRUE");
+            round_trip(
+                "WITH RECURSIVE x (a, b) AS (SELECT a, b FROM t), \"Y\" AS (SELECT * FROM x) \
+                 SELECT * FROM \"Y\"",
+            );
+            round_trip(
+                "SELECT LAG(x, 1) OVER (PARTITION BY a ORDER BY b \
+                 ROWS BETWEEN 1 PRECEDING AND CURRENT ROW), \
+                 Lead(x) OVER (ORDER BY b DESC) FROM t",
+            );
+            round_trip(
+                "[DATE '2020-01-01', TIME WITH TIME ZONE '12:00', TIME (2) '12:00', \
+                  INTERVAL '1-6' YEAR TO MONTH, INTERVAL '1' DAY (2)]",
+            );
+            round_trip("[a IS DISTINCT FROM b, a IS NOT DISTINCT FROM :p, 2 ^ 3]");
+            round_trip("VALUES (1, 2), (3, 4)");
+            round_trip("TABLE \"T\" LIMIT 1");
+            round_trip("<<1>> ORDER BY 1");
+            round_trip("(SELECT a FROM t) UNION (SELECT a FROM u) ORDER BY a LIMIT 1");
+            round_trip("[t['a'], t.\"a\", t.a, t[a], `{x:1}`, `1`, 1.50, 2.5e0]");
+        }
     }
 }
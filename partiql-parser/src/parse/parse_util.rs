@@ -1,3 +1,4 @@
+use partiql_ast::arena::{Arena, Box, Vec};
 use partiql_ast::ast;
 
 use crate::parse::parser_state::{IdGenerator, ParserState};
@@ -42,22 +43,24 @@ impl<T> Synth<T> {
     }
 }
 
-impl<T> FromIterator<Synth<T>> for Synth<Vec<T>> {
+impl<'a, T> Synth<Vec<'a, T>> {
+    /// Collects the data of `iter` into a vector allocated in `arena`, synthesizing their
+    /// attributes.
     #[inline]
-    fn from_iter<I: IntoIterator<Item = Synth<T>>>(iter: I) -> Synth<Vec<T>> {
+    pub fn from_iter_in<I: IntoIterator<Item = Synth<T>>>(iter: I, arena: &'a Arena) -> Self {
         let mut attrs = Attrs::all();
         let iterator = iter.into_iter().map(|Synth { data, attrs: a }| {
             attrs = attrs.synthesize(a);
             data
         });
-        let data = iterator.collect::<Vec<_>>();
+        let data = Vec::from_iter_in(iterator, arena);
         Synth { data, attrs }
     }
 }
 
-pub(crate) enum CallSite {
-    Call(ast::Call),
-    CallAgg(ast::CallAgg),
+pub(crate) enum CallSite<'a> {
+    Call(ast::Call<'a>),
+    CallAgg(ast::CallAgg<'a>),
 }
 
 #[inline]
@@ -65,33 +68,31 @@ pub(crate) enum CallSite {
 // e.g. `(SELECT a FROM b ORDER BY c LIMIT d OFFSET e)` should be a Query with no additional nesting.
 // Put another way: if `q` is a Query(QuerySet::Expr(Query(inner_q), ...), return Query(inner_q).
 // Otherwise, return `q`.
-pub(crate) fn strip_query(q: ast::AstNode<ast::Query>) -> ast::AstNode<ast::Query> {
+pub(crate) fn strip_query<'a>(
+    q: ast::AstNode<ast::Query<'a>>,
+    arena: &'a Arena,
+) -> ast::AstNode<ast::Query<'a>> {
     let outer_id = q.id;
     if let ast::AstNode {
         node: ast::QuerySet::Expr(e),
         id: inner_id,
     } = q.node.set
     {
-        if let ast::Expr::Query(
-            inner_q @ ast::AstNode {
-                node: ast::Query { .. },
-                ..
-            },
-        ) = *e
-        {
-            inner_q
-        } else {
-            let set = ast::AstNode {
-                id: inner_id,
-                node: ast::QuerySet::Expr(e),
-            };
-            ast::AstNode {
-                id: outer_id,
-                node: ast::Query {
-                    set,
-                    order_by: None,
-                    limit_offset: None,
-                },
+        match Box::into_inner(e) {
+            ast::Expr::Query(inner_q) => inner_q,
+            e => {
+                let set = ast::AstNode {
+                    id: inner_id,
+                    node: ast::QuerySet::Expr(Box::new_in(e, arena)),
+                };
+                ast::AstNode {
+                    id: outer_id,
+                    node: ast::Query {
+                        set,
+                        order_by: None,
+                        limit_offset: None,
+                    },
+                }
             }
         }
     } else {
@@ -102,12 +103,12 @@ pub(crate) fn strip_query(q: ast::AstNode<ast::Query>) -> ast::AstNode<ast::Quer
 #[inline]
 // If `qs` is a `QuerySet::Expr(Expr::Query(inner_q))`, return Query(inner_q). Otherwise, return `qs` wrapped
 // in a `Query` with `None` as the `OrderBy` and `LimitOffset`
-pub(crate) fn strip_query_set<Id>(
-    qs: ast::AstNode<ast::QuerySet>,
-    state: &mut ParserState<Id>,
+pub(crate) fn strip_query_set<'a, Id>(
+    qs: ast::AstNode<ast::QuerySet<'a>>,
+    state: &mut ParserState<'a, Id>,
     lo: ByteOffset,
     hi: ByteOffset,
-) -> ast::AstNode<ast::Query>
+) -> ast::AstNode<ast::Query<'a>>
 where
     Id: IdGenerator,
 {
@@ -116,25 +117,20 @@ where
         id: inner_id,
     } = qs
     {
-        if let ast::Expr::Query(
-            inner_q @ ast::AstNode {
-                node: ast::Query { .. },
-                ..
-            },
-        ) = *q
-        {
+        match Box::into_inner(q) {
             // preserve query including limit/offset & order by if present
-            inner_q
-        } else {
-            let query = ast::Query {
-                set: ast::AstNode {
-                    id: inner_id,
-                    node: ast::QuerySet::Expr(q),
-                },
-                order_by: None,
-                limit_offset: None,
-            };
-            state.node(query, lo..hi)
+            ast::Expr::Query(inner_q) => inner_q,
+            q => {
+                let query = ast::Query {
+                    set: ast::AstNode {
+                        id: inner_id,
+                        node: ast::QuerySet::Expr(Box::new_in(q, state.arena)),
+                    },
+                    order_by: None,
+                    limit_offset: None,
+                };
+                state.node(query, lo..hi)
+            }
         }
     } else {
         let query = ast::Query {
@@ -149,7 +145,10 @@ where
 #[inline]
 // If this is just a parenthesized expr, lift it out of the query AST, otherwise return input
 //      e.g. `(1+2)` should be an `Expr`, not wrapped deep in a `Query`
-pub(crate) fn strip_expr(q: ast::AstNode<ast::Query>) -> Box<ast::Expr> {
+pub(crate) fn strip_expr<'a>(
+    q: ast::AstNode<ast::Query<'a>>,
+    arena: &'a Arena,
+) -> Box<'a, ast::Expr<'a>> {
     if let ast::AstNode {
         node:
             ast::Query {
@@ -166,6 +165,128 @@ pub(crate) fn strip_expr(q: ast::AstNode<ast::Query>) -> Box<ast::Expr> {
     {
         e
     } else {
-        Box::new(ast::Expr::Query(q))
+        Box::new_in(ast::Expr::Query(q), arena)
+    }
+}
+
+#[inline]
+// Marks a SQL-style `SELECT` subquery (i.e., not `SELECT VALUE` or `PIVOT`) in a scalar context
+// (e.g., an operand of a comparison) to be coerced to a scalar; otherwise, returns the input.
+//      e.g. `(SELECT MAX(a) FROM t)` in `x > (SELECT MAX(a) FROM t)`
+pub(crate) fn scalar_context(e: ast::Expr) -> ast::Expr {
+    match e {
+        ast::Expr::Query(q) if is_sql_select(&q.node) => ast::Expr::ScalarSubQuery(q),
+        e => e,
+    }
+}
+
+#[inline]
+// Marks a SQL-style `SELECT` subquery passed as an argument to a function in a scalar context; see
+// [`scalar_context`].
+//      e.g. `(SELECT MAX(a) FROM t)` in `UPPER((SELECT MAX(a) FROM t))`
+pub(crate) fn scalar_arg<'a>(
+    arg: ast::AstNode<ast::CallArg<'a>>,
+    arena: &'a Arena,
+) -> ast::AstNode<ast::CallArg<'a>> {
+    let ast::AstNode { id, node } = arg;
+    let node = match node {
+        ast::CallArg::Positional(expr) => {
+            ast::CallArg::Positional(Box::new_in(scalar_context(Box::into_inner(expr)), arena))
+        }
+        ast::CallArg::Named(ast::CallArgNamed { name, value }) => {
+            let value = Box::new_in(scalar_context(Box::into_inner(value)), arena);
+            ast::CallArg::Named(ast::CallArgNamed { name, value })
+        }
+        node => node,
+    };
+    ast::AstNode { id, node }
+}
+
+#[inline]
+// Makes a `SELECT`-list item ending in `.*` an item projecting all the attributes of the
+// expression preceding the `.*`; otherwise, makes an item projecting the expression.
+//      e.g. `t.*` in `SELECT t.*, u.name FROM t, u`
+pub(crate) fn project_item<'a>(e: ast::Expr<'a>, arena: &'a Arena) -> ast::ProjectItem<'a> {
+    match e {
+        ast::Expr::Path(ast::AstNode {
+            id,
+            node: ast::Path { root, mut steps },
+        }) if matches!(steps.last(), Some(ast::PathStep::PathUnpivot)) => {
+            steps.pop();
+            let expr = if steps.is_empty() {
+                root
+            } else {
+                Box::new_in(
+                    ast::Expr::Path(ast::AstNode {
+                        id,
+                        node: ast::Path { root, steps },
+                    }),
+                    arena,
+                )
+            };
+            ast::ProjectItem::ProjectAll(ast::ProjectAll { expr })
+        }
+        e => ast::ProjectItem::ProjectExpr(ast::ProjectExpr {
+            expr: Box::new_in(scalar_context(e), arena),
+            as_alias: None,
+        }),
+    }
+}
+
+/// The precision of an interval field, e.g., `(2)` or `(2, 3)` in `SECOND(2, 3)`.
+pub(crate) type IntervalPrecision = (u32, Option<u32>);
+
+// Makes the qualifier of an interval from its leading field and its trailing field, if any, along
+// with their precisions, checking that the fields are of a valid range.
+//      e.g. `HOUR(2) TO SECOND(3)` in `INTERVAL '1:02:03.456' HOUR(2) TO SECOND(3)`
+pub(crate) fn interval_qualifier(
+    start: ast::IntervalField,
+    precision: Option<IntervalPrecision>,
+    end: Option<(ast::IntervalField, Option<IntervalPrecision>)>,
+) -> Result<ast::IntervalQualifier, &'static str> {
+    use ast::IntervalField::{Month, Second, Year};
+    let is_year_month = |field| matches!(field, Year | Month);
+
+    let (precision, fractional_precision) = match precision {
+        Some((_, Some(_))) if start != Second || end.is_some() => {
+            return Err("only a SECOND interval field may have a fractional seconds precision")
+        }
+        Some((precision, fractional_precision)) => (Some(precision), fractional_precision),
+        None => (None, None),
+    };
+    let (end, fractional_precision) = match end {
+        None => (None, fractional_precision),
+        Some((end, _)) if end <= start => {
+            return Err(
+                "an interval's trailing field must be less significant than its leading field",
+            )
+        }
+        Some((end, _)) if is_year_month(start) != is_year_month(end) => {
+            return Err("an interval cannot have both year-month and day-time fields")
+        }
+        Some((end, Some((fractional_precision, None)))) if end == Second => {
+            (Some(end), Some(fractional_precision))
+        }
+        Some((_, Some(_))) => {
+            return Err("an interval's trailing field may only have a fractional seconds precision")
+        }
+        Some((end, None)) => (Some(end), None),
+    };
+    Ok(ast::IntervalQualifier {
+        start,
+        precision,
+        end,
+        fractional_precision,
+    })
+}
+
+#[inline]
+fn is_sql_select(q: &ast::Query) -> bool {
+    match &q.set.node {
+        ast::QuerySet::Select(select) => matches!(
+            select.node.project.node.kind,
+            ast::ProjectionKind::ProjectStar | ast::ProjectionKind::ProjectList(_)
+        ),
+        _ => false,
     }
 }
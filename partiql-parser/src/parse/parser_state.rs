@@ -1,7 +1,9 @@
 use crate::error::ParseError;
 use crate::parse::lexer;
+use std::borrow::Cow;
 use std::ops::Range;
 
+use partiql_ast::arena::Arena;
 use partiql_ast::ast;
 
 use lalrpop_util::ErrorRecovery;
@@ -10,12 +12,14 @@ use regex::Regex;
 
 use partiql_ast::ast::{AstNode, NodeId, SymbolPrimitive};
 
-use partiql_source_map::location::{ByteOffset, BytePosition, Location};
+use partiql_source_map::location::{ByteOffset, BytePosition, Location, ToLocated};
 use partiql_source_map::metadata::LocationMap;
 
 type ParseErrorRecovery<'input> =
     ErrorRecovery<ByteOffset, lexer::Token<'input>, ParseError<'input, BytePosition>>;
 type ParseErrors<'input> = Vec<ParseErrorRecovery<'input>>;
+type LalrpopError<'input> =
+    lalrpop_util::ParseError<ByteOffset, lexer::Token<'input>, ParseError<'input, BytePosition>>;
 
 const INIT_LOCATIONS: usize = 100;
 
@@ -50,6 +54,8 @@ impl IdGenerator for NodeIdGenerator {
 
 /// State of the parsing during parse.
 pub(crate) struct ParserState<'input, Id: IdGenerator> {
+    /// The arena in which the nodes of the AST are allocated.
+    pub arena: &'input Arena,
     /// Generator for 'fresh' [`NodeId`]s
     pub id_gen: Id,
     /// Maps AST [`NodeId`]s to the location in the source from which each was derived.
@@ -59,29 +65,51 @@ pub(crate) struct ParserState<'input, Id: IdGenerator> {
 
     /// Pattern to match names of aggregate functions.
     aggregates_pat: &'static Regex,
+    /// Pattern to match names of collection aggregate functions (e.g., `COLL_SUM`).
+    coll_aggregates_pat: &'static Regex,
+    /// Pattern to match names of functions of collections (e.g., `CARDINALITY`).
+    coll_fns_pat: &'static Regex,
+
+    /// The number of positional parameters (i.e., `?`) encountered so far.
+    positional_parameters: usize,
+
+    /// Whether backslash escape sequences in string literals are decoded.
+    pub string_escapes: bool,
+    /// Whether to recover from syntax errors, rather than stop parsing at the first.
+    pub error_recovery: bool,
 }
 
-impl<'input> Default for ParserState<'input, NodeIdGenerator> {
-    fn default() -> Self {
-        ParserState::with_id_gen(NodeIdGenerator::default())
+impl<'input> ParserState<'input, NodeIdGenerator> {
+    pub fn new(arena: &'input Arena) -> Self {
+        ParserState::with_id_gen(arena, NodeIdGenerator::default())
     }
 }
 
-// TODO: currently needs to be manually kept in-sync with preprocessor's `built_in_aggs`
 // TODO: make extensible
 const KNOWN_AGGREGATES: &str = "(?i:^count$)|(?i:^avg$)|(?i:^min$)|(?i:^max$)|(?i:^sum$)";
 static KNOWN_AGGREGATE_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(KNOWN_AGGREGATES).unwrap());
+const KNOWN_COLL_AGGREGATES: &str = "(?i:^coll_(count|avg|min|max|sum)$)";
+static KNOWN_COLL_AGGREGATE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(KNOWN_COLL_AGGREGATES).unwrap());
+const KNOWN_COLL_FNS: &str = "(?i:^cardinality$)|(?i:^exists$)|(?i:^coll_(count|avg|min|max|sum)$)";
+static KNOWN_COLL_FN_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(KNOWN_COLL_FNS).unwrap());
 
 impl<'input, I> ParserState<'input, I>
 where
     I: IdGenerator,
 {
-    pub fn with_id_gen(id_gen: I) -> Self {
+    pub fn with_id_gen(arena: &'input Arena, id_gen: I) -> Self {
         ParserState {
+            arena,
             id_gen,
             locations: LocationMap::with_capacity(INIT_LOCATIONS),
             errors: ParseErrors::default(),
             aggregates_pat: &KNOWN_AGGREGATE_PATTERN,
+            coll_aggregates_pat: &KNOWN_COLL_AGGREGATE_PATTERN,
+            coll_fns_pat: &KNOWN_COLL_FN_PATTERN,
+            positional_parameters: 0,
+            string_escapes: false,
+            error_recovery: true,
         }
     }
 }
@@ -106,9 +134,64 @@ impl<'input, Id: IdGenerator> ParserState<'input, Id> {
         self.create_node(ast, start.into()..end.into())
     }
 
+    /// Decodes the text `s` between the quotes of the string literal at `location`. The decoded
+    /// text borrows `s` unless decoding changes it, in which case it is allocated in the arena.
+    pub fn string(
+        &self,
+        s: &'input str,
+        Range { start, end }: Range<ByteOffset>,
+    ) -> Result<&'input str, LalrpopError<'input>> {
+        match lexer::decode_string(s, self.string_escapes) {
+            Ok(Cow::Borrowed(decoded)) => Ok(decoded),
+            Ok(Cow::Owned(decoded)) => Ok(self.arena.alloc_str(&decoded)),
+            Err(error) => Err(lalrpop_util::ParseError::User {
+                error: ParseError::LexicalError(error.to_located(start.into()..end.into())),
+            }),
+        }
+    }
+
+    /// Validates the text `ion` between the backticks of the embedded Ion literal at `location`;
+    /// see [`lexer::validate_ion`].
+    pub fn ion(
+        &self,
+        ion: &'input str,
+        Range { start, end }: Range<ByteOffset>,
+    ) -> Result<&'input str, LalrpopError<'input>> {
+        match lexer::validate_ion(ion) {
+            Ok(()) => Ok(ion),
+            Err((offset, error)) => {
+                let start = start + ByteOffset::from(offset);
+                Err(lalrpop_util::ParseError::User {
+                    error: ParseError::LexicalError(error.to_located(start.into()..end.into())),
+                })
+            }
+        }
+    }
+
+    /// The zero-based position of the next positional parameter of the query.
+    #[inline]
+    pub fn next_positional_parameter(&mut self) -> usize {
+        let position = self.positional_parameters;
+        self.positional_parameters += 1;
+        position
+    }
+
     /// Check if a given `name` corresponds to a known aggregate function.
     #[inline]
     pub fn is_agg_fn(&self, name: &SymbolPrimitive) -> bool {
-        self.aggregates_pat.is_match(&name.value)
+        self.aggregates_pat.is_match(name.value)
+    }
+
+    /// Check if a given `name` corresponds to a known collection aggregate function.
+    #[inline]
+    pub fn is_coll_agg_fn(&self, name: &SymbolPrimitive) -> bool {
+        self.coll_aggregates_pat.is_match(name.value)
+    }
+
+    /// Check if a given `name` corresponds to a known function of collections, whose arguments
+    /// are not in a scalar context.
+    #[inline]
+    pub fn is_coll_fn(&self, name: &SymbolPrimitive) -> bool {
+        self.coll_fns_pat.is_match(name.value)
     }
 }
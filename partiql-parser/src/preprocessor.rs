@@ -129,20 +129,6 @@ mod built_ins {
         }
     }
 
-    pub(crate) fn built_in_aggs() -> FnExpr<'static> {
-        FnExpr {
-            // TODO: currently needs to be manually kept in-sync with parsers's `KNOWN_AGGREGATES`
-            fn_names: vec!["count", "avg", "min", "max", "sum"],
-            #[rustfmt::skip]
-            patterns: vec![
-                // e.g., count(all x) => count("all": x)
-                vec![Kw(Token::All), AnyOne(true), AnyStar(false)],
-                // e.g., count(distinct x) => count("distinct": x)
-                vec![Kw(Token::Distinct), AnyOne(true), AnyStar(false)],
-            ],
-        }
-    }
-
     pub(crate) fn built_in_substring() -> FnExpr<'static> {
         FnExpr {
             fn_names: vec!["substring"],
@@ -224,7 +210,6 @@ impl<'a> FnExprSet<'a> {
 pub(crate) fn built_ins() -> FnExprSet<'static> {
     FnExprSet::new(vec![
         built_ins::built_in_trim(),
-        built_ins::built_in_aggs(),
         built_ins::built_in_extract(),
         built_ins::built_in_position(),
         built_ins::built_in_overlay(),
@@ -964,12 +949,13 @@ mod tests {
             )?
         );
 
+        // set quantifiers of aggregate functions are parsed by the grammar
         assert_eq!(preprocess(r#"count(a)"#)?, lex(r#"count(a)"#)?);
         assert_eq!(
             preprocess(r#"count(DISTINCT a)"#)?,
-            lex(r#"count("DISTINCT": a)"#)?
+            lex(r#"count(DISTINCT a)"#)?
         );
-        assert_eq!(preprocess(r#"count(all a)"#)?, lex(r#"count("all": a)"#)?);
+        assert_eq!(preprocess(r#"count(all a)"#)?, lex(r#"count(all a)"#)?);
         let q_count_1 = r#"count(1)"#;
         assert_eq!(preprocess(q_count_1)?, lex(q_count_1)?);
         let q_count_star = r#"count(*)"#;
@@ -978,9 +964,9 @@ mod tests {
         assert_eq!(preprocess(r#"sum(a)"#)?, lex(r#"sum(a)"#)?);
         assert_eq!(
             preprocess(r#"sum(DISTINCT a)"#)?,
-            lex(r#"sum("DISTINCT": a)"#)?
+            lex(r#"sum(DISTINCT a)"#)?
         );
-        assert_eq!(preprocess(r#"sum(all a)"#)?, lex(r#"sum("all": a)"#)?);
+        assert_eq!(preprocess(r#"sum(all a)"#)?, lex(r#"sum(all a)"#)?);
         let q_sum_1 = r#"sum(1)"#;
         assert_eq!(preprocess(q_sum_1)?, lex(q_sum_1)?);
         let q_sum_star = r#"sum(*)"#;
@@ -988,7 +974,7 @@ mod tests {
 
         assert_eq!(
             preprocess(r#"COUNT(DISTINCT [1,1,1,1,2])"#)?,
-            lex(r#"COUNT("DISTINCT" : [1,1,1,1,2])"#)?
+            lex(r#"COUNT(DISTINCT [1,1,1,1,2])"#)?
         );
 
         let empty_q = "";
@@ -0,0 +1,313 @@
+//! Lexing of PartiQL text read incrementally, e.g., from a socket or a large script file.
+//!
+//! A [`StreamingLexer`] buffers the text read from its source until it can tell where the tokens
+//! in it end: as no token but those delimited by quotes, backticks, or comment delimiters spans
+//! whitespace, each token followed by whitespace is unaffected by the text read after it. The
+//! tokens preceding the last whitespace in the buffer are thus lexed, and only the text following
+//! it is kept for the next read.
+
+use crate::error::LexError;
+use crate::lexer::{PartiqlLexer, Token};
+use crate::Lexeme;
+use partiql_source_map::line_offset_tracker::LineOffsetTracker;
+use partiql_source_map::location::{ByteOffset, BytePosition, Located, Location, ToLocated};
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use thiserror::Error;
+
+/// Errors reading or lexing the text of a [`StreamingLexer`].
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum StreamingLexError {
+    /// Reading the text failed, or the text is not valid UTF-8.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// There was an error lexing the text.
+    #[error("{} at `{}`", _0.inner, _0.location)]
+    Lex(Located<LexError<'static>, BytePosition>),
+}
+
+type StreamingLexResult = Result<Lexeme<'static>, StreamingLexError>;
+
+/// A lexer of PartiQL text read incrementally from a [`BufRead`] source into [`Lexeme`]s, including
+/// comments, located by their byte offsets in the whole text.
+///
+/// ```
+/// use partiql_parser::StreamingLexer;
+/// use std::io::BufReader;
+///
+/// // Read the text 4 bytes at a time
+/// let text = "SELECT a -- the a\nFROM t";
+/// let reader = BufReader::with_capacity(4, text.as_bytes());
+/// let mut lexer = StreamingLexer::new(reader);
+///
+/// let lexemes: Vec<_> = lexer.by_ref().map(|lexeme| lexeme.unwrap()).collect();
+/// let texts: Vec<_> = lexemes.iter().map(|lexeme| lexeme.text.as_ref()).collect();
+/// assert_eq!(texts, ["SELECT", "a", "-- the a", "FROM", "t"]);
+/// assert_eq!(lexemes[3].location.start.0.to_usize(), 18);
+/// assert_eq!(lexer.offsets().num_lines(), 2);
+/// ```
+pub struct StreamingLexer<R: BufRead> {
+    reader: Option<R>,
+    /// The text read but not yet lexed.
+    buffer: String,
+    /// The bytes read after `buffer`, if they end in the middle of a UTF-8 encoded character.
+    partial_char: Vec<u8>,
+    /// The offset of `buffer` in the whole text.
+    offset: usize,
+    lexed: VecDeque<StreamingLexResult>,
+    offsets: LineOffsetTracker,
+}
+
+impl<R: BufRead> StreamingLexer<R> {
+    /// Creates a new lexer of the text read from `reader`.
+    pub fn new(reader: R) -> Self {
+        StreamingLexer {
+            reader: Some(reader),
+            buffer: String::new(),
+            partial_char: Vec::new(),
+            offset: 0,
+            lexed: VecDeque::new(),
+            offsets: LineOffsetTracker::default(),
+        }
+    }
+
+    /// The line starts of the text lexed so far, for locating its lexemes by line and column.
+    pub fn offsets(&self) -> &LineOffsetTracker {
+        &self.offsets
+    }
+
+    /// Reads the next chunk of text into the buffer, returning whether the text is exhausted.
+    fn read(&mut self) -> io::Result<bool> {
+        let Some(reader) = &mut self.reader else {
+            return Ok(true);
+        };
+        let chunk = reader.fill_buf()?;
+        let len = chunk.len();
+        if len == 0 {
+            self.reader = None;
+            return match self.partial_char.is_empty() {
+                true => Ok(true),
+                false => Err(invalid_utf8()),
+            };
+        }
+        self.partial_char.extend_from_slice(chunk);
+        reader.consume(len);
+
+        let bytes = std::mem::take(&mut self.partial_char);
+        let valid = match std::str::from_utf8(&bytes) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        // `bytes[..valid]` was just validated
+        self.buffer
+            .push_str(std::str::from_utf8(&bytes[..valid]).unwrap_or_default());
+        self.partial_char = bytes[valid..].to_vec();
+        Ok(false)
+    }
+
+    /// Lexes the buffer up to the last whitespace in it or, if `exhausted`, wholly.
+    fn lex(&mut self, exhausted: bool) {
+        let mut tracker = LineOffsetTracker::default();
+        let mut lexer = PartiqlLexer::new(&self.buffer, &mut tracker);
+        let mut lexed = vec![];
+        // The index in `lexed` and offset in the buffer of the text following the last whitespace
+        let mut cut = (0, 0);
+        let mut prev_end = 0;
+        let mut complete = true;
+        while let Some(result) = lexer.next_internal() {
+            let (start, end, open) = match &result {
+                // Embedded Ion spans exclude the backticks
+                Ok((start, Token::Ion(_), end)) => (*start - 1, *end + 1, false),
+                Ok((start, _, end)) => (*start, *end, false),
+                Err((start, err, end)) => (*start, *end, is_open(err)),
+            };
+            if start.to_usize() > prev_end {
+                cut = (lexed.len(), start.to_usize());
+            }
+            if open && !exhausted {
+                complete = false;
+                break;
+            }
+            prev_end = end.to_usize();
+            lexed.push(result);
+        }
+        if exhausted || complete && self.buffer.len() > prev_end {
+            cut = (lexed.len(), self.buffer.len());
+        }
+
+        let (count, len) = cut;
+        let offset = ByteOffset::from(self.offset);
+        for result in lexed.into_iter().take(count) {
+            let lexeme = match result {
+                Ok((start, token, end)) => Ok(Lexeme {
+                    kind: token.kind(),
+                    text: self.buffer[start.to_usize()..end.to_usize()]
+                        .to_string()
+                        .into(),
+                    location: Location {
+                        start: (offset + start).into(),
+                        end: (offset + end).into(),
+                    },
+                }),
+                Err((start, err, end)) => Err(StreamingLexError::Lex(
+                    err.into_owned()
+                        .to_located((offset + start).into()..(offset + end).into()),
+                )),
+            };
+            self.lexed.push_back(lexeme);
+        }
+
+        record_newlines(&mut self.offsets, &self.buffer[..len], self.offset);
+        self.buffer.drain(..len);
+        self.offset += len;
+    }
+}
+
+impl<R: BufRead> Iterator for StreamingLexer<R> {
+    type Item = StreamingLexResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(lexeme) = self.lexed.pop_front() {
+                return Some(lexeme);
+            }
+            if self.reader.is_none() && self.buffer.is_empty() {
+                return None;
+            }
+            match self.read() {
+                Ok(exhausted) => self.lex(exhausted),
+                Err(e) => {
+                    self.reader = None;
+                    self.buffer.clear();
+                    return Some(Err(e.into()));
+                }
+            }
+        }
+    }
+}
+
+/// Whether `err` may be due to a token whose end is yet to be read, i.e., a literal, quoted
+/// identifier, or comment unterminated in the text read so far.
+fn is_open(err: &LexError<'_>) -> bool {
+    match err {
+        LexError::InvalidInput(text) => text.starts_with(['\'', '"']),
+        LexError::UnterminatedIonLiteral | LexError::UnterminatedComment => true,
+        _ => false,
+    }
+}
+
+/// Records the line starts following the line breaks of `text` at `offset` in the whole text.
+fn record_newlines(tracker: &mut LineOffsetTracker, text: &str, offset: usize) {
+    for (i, c) in text.char_indices() {
+        if matches!(c, '\n' | '\u{0085}' | '\u{2028}' | '\u{2029}') {
+            tracker.record(ByteOffset::from(offset + i + c.len_utf8()));
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "text is not valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use partiql_source_map::location::LineAndCharPosition;
+    use std::io::BufReader;
+
+    /// The texts and byte ranges of the lexemes of `text`, or the errors lexing it, read `chunk`
+    /// bytes at a time.
+    fn lexed(text: &str, chunk: usize) -> Vec<Result<(String, usize, usize), String>> {
+        StreamingLexer::new(BufReader::with_capacity(chunk, text.as_bytes()))
+            .map(|result| match result {
+                Ok(Lexeme {
+                    text,
+                    location: Location { start, end },
+                    ..
+                }) => Ok((text.into_owned(), start.0.to_usize(), end.0.to_usize())),
+                Err(e) => Err(e.to_string()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chunked() {
+        let texts = [
+            "SELECT a.b, 'it''s', \"c d\", 1.5e10 FROM t <<1, 2>> WHERE x <> y",
+            "SELECT `{a: \"b c\", d: [1, 2]}` FROM t -- comment\n/* block\n /* nested */ */ x",
+            "  'héllo wörld' || '日本語'  ",
+            "SELECT x FROM t # y 'unterminated",
+        ];
+        for text in texts {
+            let expected = lexed(text, text.len());
+            for chunk in 1..=8 {
+                assert_eq!(lexed(text, chunk), expected, "{text} in chunks of {chunk}");
+            }
+        }
+    }
+
+    #[test]
+    fn lexemes() {
+        let text = "SELECT `{a: 1}` || 'x'\n-- c\nFROM t";
+        assert_eq!(
+            lexed(text, 3),
+            vec![
+                Ok(("SELECT".to_string(), 0, 6)),
+                Ok(("{a: 1}".to_string(), 8, 14)),
+                Ok(("||".to_string(), 16, 18)),
+                Ok(("'x'".to_string(), 19, 22)),
+                Ok(("-- c".to_string(), 23, 27)),
+                Ok(("FROM".to_string(), 28, 32)),
+                Ok(("t".to_string(), 33, 34)),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            lexed("a # b", 2),
+            vec![
+                Ok(("a".to_string(), 0, 1)),
+                Err("Lexing error: invalid input `#` at `(b2..b3)`".to_string()),
+                Ok(("b".to_string(), 4, 5)),
+            ]
+        );
+        assert_eq!(
+            lexed("a /* b", 2),
+            vec![
+                Ok(("a".to_string(), 0, 1)),
+                Err("Lexing error: unterminated comment at `(b2..b6)`".to_string()),
+                Ok(("b".to_string(), 5, 6)),
+            ]
+        );
+
+        let invalid: &[u8] = b"SELECT \xff";
+        let results: Vec<_> = StreamingLexer::new(invalid).collect();
+        assert!(matches!(
+            results.as_slice(),
+            [Err(StreamingLexError::Io(_))]
+        ));
+        let truncated: &[u8] = b"SELECT '\xc3";
+        let results: Vec<_> = StreamingLexer::new(truncated).collect();
+        assert!(matches!(
+            results.as_slice(),
+            [Ok(_), Err(StreamingLexError::Io(_))]
+        ));
+    }
+
+    #[test]
+    fn offsets() {
+        let text = "SELECT a\nFROM t\r\nWHERE\u{2028}b";
+        let mut lexer = StreamingLexer::new(BufReader::with_capacity(2, text.as_bytes()));
+        assert_eq!(lexer.by_ref().count(), 6);
+        let offsets = lexer.offsets();
+        assert_eq!(offsets.num_lines(), 4);
+        let position = |offset: usize| offsets.at(text, ByteOffset::from(offset).into());
+        assert_eq!(position(9), Ok(LineAndCharPosition::new(1, 0)));
+        assert_eq!(position(17), Ok(LineAndCharPosition::new(2, 0)));
+        assert_eq!(position(25), Ok(LineAndCharPosition::new(3, 0)));
+    }
+}
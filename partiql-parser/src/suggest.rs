@@ -0,0 +1,140 @@
+//! Suggestions of keywords in place of misspelled identifiers in syntax errors.
+//!
+//! A misspelled keyword lexes as an identifier, so the error it causes is often reported at the
+//! token following it; e.g., in `SELCT a FROM t`, `SELCT` is a variable reference and `a` is
+//! unexpected. Both the unexpected token and the token preceding it are thus considered for a
+//! suggestion.
+//!
+//! Only keywords are suggested, not the names of bindings (e.g., of the catalog's tables): a
+//! misspelled name is still a well-formed identifier, so causes no syntax error to attach a
+//! suggestion to. Suggesting names is left to name resolution, which knows the bindings in scope.
+
+use crate::error::Suggestion;
+use crate::lexer::{PartiqlLexer, Token};
+use partiql_source_map::line_offset_tracker::LineOffsetTracker;
+
+/// The shortest identifier considered a possible misspelling.
+const MIN_IDENTIFIER_LEN: usize = 3;
+/// The shortest keyword suggested; shorter keywords (e.g., `AS` or `BY`) are one edit away from
+/// too many legitimate identifiers.
+const MIN_KEYWORD_LEN: usize = 4;
+
+/// Suggests a keyword in place of the identifier that is, or precedes, the token unexpected at the
+/// byte offset `start` of `text`, preferring keywords among the tokens `expected` there.
+pub(crate) fn suggest_keyword(text: &str, start: usize, expected: &[String]) -> Option<Suggestion> {
+    let mut offsets = LineOffsetTracker::default();
+    let mut preceding = None;
+    let mut unexpected = None;
+    for (tok_start, tok, _) in PartiqlLexer::new(text, &mut offsets).flatten() {
+        if tok_start.to_usize() < start {
+            preceding = Some(tok);
+        } else {
+            unexpected = (tok_start.to_usize() == start).then_some(tok);
+            break;
+        }
+    }
+
+    [unexpected, preceding]
+        .into_iter()
+        .flatten()
+        .find_map(|tok| match tok {
+            Token::UnquotedIdent(id) => suggest(id, expected),
+            _ => None,
+        })
+}
+
+/// Suggests a keyword one edit (i.e., an insertion, deletion, substitution, or transposition of
+/// a letter) away from `identifier`.
+fn suggest(identifier: &str, expected: &[String]) -> Option<Suggestion> {
+    if identifier.len() < MIN_IDENTIFIER_LEN || !identifier.is_ascii() {
+        return None;
+    }
+    let mut keywords: Vec<String> = edits(identifier)
+        .filter(|candidate| candidate.len() >= MIN_KEYWORD_LEN && is_keyword(candidate))
+        .map(|candidate| candidate.to_ascii_uppercase())
+        .collect();
+    keywords.sort();
+    keywords.dedup();
+    let keyword = keywords
+        .iter()
+        .find(|keyword| expected.contains(keyword))
+        .or(keywords.first())?;
+    Some(Suggestion {
+        identifier: identifier.to_string(),
+        keyword: keyword.clone(),
+    })
+}
+
+/// The words one edit away from the ASCII `word`.
+fn edits(word: &str) -> impl Iterator<Item = String> + '_ {
+    let deletions = (0..word.len()).map(move |i| [&word[..i], &word[i + 1..]].concat());
+    let transpositions = (1..word.len()).map(move |i| {
+        let mut chars: Vec<char> = word.chars().collect();
+        chars.swap(i - 1, i);
+        chars.into_iter().collect()
+    });
+    let substitutions = (0..word.len()).flat_map(move |i| {
+        ('A'..='Z').map(move |c| format!("{}{c}{}", &word[..i], &word[i + 1..]))
+    });
+    let insertions = (0..=word.len())
+        .flat_map(move |i| ('A'..='Z').map(move |c| format!("{}{c}{}", &word[..i], &word[i..])));
+    deletions
+        .chain(transpositions)
+        .chain(substitutions)
+        .chain(insertions)
+}
+
+/// Whether `word` lexes as a single keyword.
+fn is_keyword(word: &str) -> bool {
+    let mut offsets = LineOffsetTracker::default();
+    let mut tokens = PartiqlLexer::new(word, &mut offsets);
+    matches!(
+        (tokens.next(), tokens.next()),
+        (Some(Ok((_, tok, _))), None) if tok.is_keyword()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggested(text: &str, start: usize) -> Option<String> {
+        suggest_keyword(text, start, &[]).map(|s| format!("{} -> {}", s.identifier, s.keyword))
+    }
+
+    #[test]
+    fn misspelled_keywords() {
+        assert_eq!(
+            suggested("SELCT a FROM t", 6).as_deref(),
+            Some("SELCT -> SELECT")
+        );
+        assert_eq!(
+            suggested("SELECT a FORM t", 14).as_deref(),
+            Some("FORM -> FROM")
+        );
+        assert_eq!(
+            suggested("SELECT a FROM t WHRE b = 1", 21).as_deref(),
+            Some("WHRE -> WHERE")
+        );
+        assert_eq!(
+            suggested("select a frm t", 13).as_deref(),
+            Some("frm -> FROM")
+        );
+    }
+
+    #[test]
+    fn no_suggestion() {
+        assert_eq!(suggested("SELECT a b c", 11), None);
+        assert_eq!(suggested("SELECT a FROM t AS x y", 21), None);
+        assert_eq!(suggested("SELECT a, ^ FROM t", 10), None);
+    }
+
+    #[test]
+    fn expected_keywords_preferred() {
+        // `LEST` is one edit away from both `LAST` and `LEFT`
+        let suggestion = suggest_keyword("LEST x", 5, &["LEFT".to_string()]).unwrap();
+        assert_eq!(suggestion.keyword, "LEFT");
+        let suggestion = suggest_keyword("LEST x", 5, &["LAST".to_string()]).unwrap();
+        assert_eq!(suggestion.keyword, "LAST");
+    }
+}
@@ -2,4 +2,5 @@
 
 pub mod line_offset_tracker;
 pub mod location;
+#[cfg(feature = "metadata")]
 pub mod metadata;
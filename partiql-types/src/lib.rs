@@ -1,5 +1,6 @@
+use itertools::Itertools;
 use std::collections::HashSet;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
 
 pub trait Type {}
 
@@ -203,6 +204,44 @@ impl PartiqlType {
     }
 }
 
+/// Formats the type in the syntax of PartiQL types, with structs as `{a: INT, ...}`, where `...`
+/// marks an open struct, bags as `<<INT>>` and arrays as `[INT]`.
+impl Display for PartiqlType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            TypeKind::Any => write!(f, "ANY"),
+            TypeKind::AnyOf(any_of) => {
+                let types = any_of.types.iter().map(ToString::to_string).sorted();
+                write!(f, "UNION({})", types.format(", "))
+            }
+            TypeKind::Null => write!(f, "NULL"),
+            TypeKind::Missing => write!(f, "MISSING"),
+            TypeKind::Int => write!(f, "INT"),
+            TypeKind::Int8 => write!(f, "INT8"),
+            TypeKind::Int16 => write!(f, "INT16"),
+            TypeKind::Int32 => write!(f, "INT32"),
+            TypeKind::Int64 => write!(f, "INT64"),
+            TypeKind::Bool => write!(f, "BOOL"),
+            TypeKind::Decimal => write!(f, "DECIMAL"),
+            TypeKind::DecimalP(p, s) => write!(f, "DECIMAL({p}, {s})"),
+            TypeKind::Float32 => write!(f, "FLOAT32"),
+            TypeKind::Float64 => write!(f, "FLOAT64"),
+            TypeKind::String => write!(f, "STRING"),
+            TypeKind::StringFixed(n) => write!(f, "CHAR({n})"),
+            TypeKind::StringVarying(n) => write!(f, "VARCHAR({n})"),
+            TypeKind::Struct(st) => {
+                let fields = st
+                    .fields()
+                    .map(|field| format!("{}: {}", field.name(), field.ty()));
+                let open = st.is_open().then(|| "...".to_string());
+                write!(f, "{{{}}}", fields.chain(open).format(", "))
+            }
+            TypeKind::Bag(bag) => write!(f, "<<{}>>", bag.element_type()),
+            TypeKind::Array(array) => write!(f, "[{}]", array.element_type()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct AnyOf {
@@ -228,6 +267,23 @@ pub struct StructField {
     value: PartiqlType,
 }
 
+impl StructField {
+    pub fn new(name: &str, value: PartiqlType) -> Self {
+        StructField {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn ty(&self) -> &PartiqlType {
+        &self.value
+    }
+}
+
 impl<T> From<(String, T)> for StructField
 where
     T: Into<PartiqlType>,
@@ -250,6 +306,23 @@ impl StructType {
     pub fn new(constraints: Vec<StructConstraint>) -> Self {
         StructType { constraints }
     }
+
+    /// Whether the struct may contain attributes other than its declared [`fields`](Self::fields);
+    /// structs are open unless constrained by `StructConstraint::Open(false)`.
+    pub fn is_open(&self) -> bool {
+        !self
+            .constraints
+            .iter()
+            .any(|c| matches!(c, StructConstraint::Open(false)))
+    }
+
+    /// The declared fields of the struct.
+    pub fn fields(&self) -> impl Iterator<Item = &StructField> {
+        self.constraints.iter().filter_map(|c| match c {
+            StructConstraint::Fields(field) => Some(field),
+            _ => None,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -280,6 +353,10 @@ impl BagType {
             constraints: vec![CollectionConstraint::Ordered(false)],
         }
     }
+
+    pub fn element_type(&self) -> &PartiqlType {
+        &self.element_type
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -302,6 +379,10 @@ impl ArrayType {
             constraints: vec![CollectionConstraint::Ordered(true)],
         }
     }
+
+    pub fn element_type(&self) -> &PartiqlType {
+        &self.element_type
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -311,6 +392,32 @@ enum CollectionConstraint {
 
 #[cfg(test)]
 mod tests {
+    use crate::{ArrayType, BagType, PartiqlType, StructConstraint, StructField, StructType};
+
     #[test]
     fn todo() {}
+
+    #[test]
+    fn display() {
+        let closed = StructType::new(vec![
+            StructConstraint::Open(false),
+            StructConstraint::Fields(StructField::new("a", int!())),
+            StructConstraint::Fields(StructField::new(
+                "b",
+                PartiqlType::new_array(ArrayType::new(Box::new(str!()))),
+            )),
+        ]);
+        let ty = PartiqlType::new_bag(BagType::new(Box::new(PartiqlType::new_struct(closed))));
+        assert_eq!(ty.to_string(), "<<{a: INT, b: [STRING]}>>");
+
+        let open = StructType::new(vec![StructConstraint::Fields(StructField::new(
+            "a",
+            any!(),
+        ))]);
+        assert_eq!(PartiqlType::new_struct(open).to_string(), "{a: ANY, ...}");
+        assert_eq!(
+            PartiqlType::new_struct(StructType::new_any()).to_string(),
+            "{...}"
+        );
+    }
 }
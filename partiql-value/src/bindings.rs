@@ -0,0 +1,348 @@
+use crate::{BindingsName, Tuple, Value};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use unicase::UniCase;
+
+/// A source of named values (e.g., the global environment passed to evaluation).
+pub trait Bindings<T>: Debug {
+    fn get(&self, name: &BindingsName) -> Option<&T>;
+}
+
+impl Bindings<Value> for Tuple {
+    fn get(&self, name: &BindingsName) -> Option<&Value> {
+        self.get(name)
+    }
+}
+
+/// A flat mapping of names to values supporting both case-sensitive and case-insensitive lookup.
+#[derive(Debug, Clone)]
+pub struct MapBindings<T> {
+    sensitive: HashMap<String, usize>,
+    insensitive: HashMap<UniCase<String>, usize>,
+    values: Vec<T>,
+}
+
+impl<T> Default for MapBindings<T> {
+    fn default() -> Self {
+        MapBindings {
+            sensitive: HashMap::new(),
+            insensitive: HashMap::new(),
+            values: vec![],
+        }
+    }
+}
+
+impl<T> MapBindings<T> {
+    /// Adds a binding of `name` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a binding with the same (case-insensitive) name already exists.
+    pub fn insert(&mut self, name: &str, value: T) {
+        if let Entry::Vacant(e) = self.insensitive.entry(UniCase::new(name.to_string())) {
+            let idx = self.values.len();
+            self.values.push(value);
+            self.sensitive.insert(name.to_string(), idx);
+            e.insert(idx);
+        } else {
+            panic!("Cannot insert duplicate binding of name {name}")
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The names bound, in the order they were inserted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let mut names: Vec<_> = self.sensitive.iter().collect();
+        names.sort_by_key(|(_, idx)| **idx);
+        names.into_iter().map(|(name, _)| name.as_str())
+    }
+}
+
+impl<T> Bindings<T> for MapBindings<T>
+where
+    T: Debug,
+{
+    #[inline]
+    fn get(&self, name: &BindingsName) -> Option<&T> {
+        let idx = match name {
+            BindingsName::CaseSensitive(s) => self.sensitive.get(s),
+            BindingsName::CaseInsensitive(s) => self.insensitive.get(&UniCase::new(s.to_string())),
+        };
+        idx.and_then(|idx| self.values.get(*idx))
+    }
+}
+
+impl From<&Tuple> for MapBindings<Value> {
+    fn from(t: &Tuple) -> Self {
+        let mut bindings = MapBindings::default();
+        for (k, v) in t.pairs() {
+            bindings.insert(k, v.clone())
+        }
+        bindings
+    }
+}
+
+impl From<Tuple> for MapBindings<Value> {
+    fn from(t: Tuple) -> Self {
+        let mut bindings = MapBindings::default();
+        for (k, v) in t.into_pairs() {
+            bindings.insert(&k, v);
+        }
+        bindings
+    }
+}
+
+/// Binds the attributes of a tuple; other values bind no names.
+impl From<Value> for MapBindings<Value> {
+    fn from(val: Value) -> Self {
+        match val {
+            Value::Tuple(t) => (*t).into(),
+            _ => MapBindings::default(),
+        }
+    }
+}
+
+/// Binds the attributes of a tuple; other values bind no names.
+impl From<&Value> for MapBindings<Value> {
+    fn from(val: &Value) -> Self {
+        match val {
+            Value::Tuple(t) => t.as_ref().into(),
+            _ => MapBindings::default(),
+        }
+    }
+}
+
+/// A stack of nested [`MapBindings`] scopes.
+///
+/// Lookups resolve names from the innermost scope outwards, so that a binding in an inner scope
+/// shadows any binding of the same name in an enclosing scope.
+#[derive(Debug, Clone)]
+pub struct Environment<T> {
+    scopes: Vec<MapBindings<T>>,
+}
+
+impl<T> Default for Environment<T> {
+    fn default() -> Self {
+        Environment::new(MapBindings::default())
+    }
+}
+
+impl<T> Environment<T> {
+    /// Creates an environment whose outermost (i.e., global) scope is `globals`.
+    pub fn new(globals: MapBindings<T>) -> Self {
+        Environment {
+            scopes: vec![globals],
+        }
+    }
+
+    /// Enters a new, innermost scope.
+    pub fn push_scope(&mut self, scope: MapBindings<T>) {
+        self.scopes.push(scope);
+    }
+
+    /// Leaves the innermost scope, returning it. The global scope is never removed.
+    pub fn pop_scope(&mut self) -> Option<MapBindings<T>> {
+        if self.scopes.len() > 1 {
+            self.scopes.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Adds a binding of `name` to `value` in the innermost scope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the innermost scope already contains a binding of the same (case-insensitive) name.
+    pub fn insert(&mut self, name: &str, value: T) {
+        self.scopes
+            .last_mut()
+            .expect("global scope")
+            .insert(name, value)
+    }
+
+    /// The number of scopes, including the global scope.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+}
+
+impl<T> From<MapBindings<T>> for Environment<T> {
+    fn from(globals: MapBindings<T>) -> Self {
+        Environment::new(globals)
+    }
+}
+
+impl<T> Bindings<T> for Environment<T>
+where
+    T: Debug,
+{
+    #[inline]
+    fn get(&self, name: &BindingsName) -> Option<&T> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list, tuple};
+
+    #[test]
+    fn test_bindings_from_tuple() {
+        let t = tuple![("a", tuple![("p", 1)]), ("b", 2)];
+
+        // by ref
+        let bindings = MapBindings::from(&t);
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+            Some(&Value::from(tuple![("p", 1)]))
+        );
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("b".to_string())),
+            Some(&Value::from(2))
+        );
+
+        // by ownership
+        let bindings = MapBindings::from(t);
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+            Some(&Value::from(tuple![("p", 1)]))
+        );
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("b".to_string())),
+            Some(&Value::from(2))
+        );
+    }
+
+    #[test]
+    fn test_bindings_from_value() {
+        let bindings = MapBindings::from(Value::Null);
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+            None
+        );
+        let bindings = MapBindings::from(&Value::Null);
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+            None
+        );
+        let bindings = MapBindings::from(Value::Missing);
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+            None
+        );
+        let bindings = MapBindings::from(&Value::Missing);
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+            None
+        );
+        for value in [Value::from(1), Value::from("a"), Value::from(list!["a", 1])] {
+            let bindings = MapBindings::from(&value);
+            assert_eq!(
+                bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+                None
+            );
+            let bindings = MapBindings::from(value);
+            assert_eq!(
+                bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+                None
+            );
+        }
+
+        let t = Value::from(tuple![("a", tuple![("p", 1)]), ("b", 2)]);
+
+        // by ref
+        let bindings = MapBindings::from(&t);
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+            Some(&Value::from(tuple![("p", 1)]))
+        );
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("b".to_string())),
+            Some(&Value::from(2))
+        );
+
+        // by ownership
+        let bindings = MapBindings::from(t);
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("a".to_string())),
+            Some(&Value::from(tuple![("p", 1)]))
+        );
+        assert_eq!(
+            bindings.get(&BindingsName::CaseInsensitive("b".to_string())),
+            Some(&Value::from(2))
+        );
+    }
+
+    #[test]
+    fn test_environment_scopes() {
+        let mut globals = MapBindings::default();
+        globals.insert("a", Value::from(1));
+        globals.insert("b", Value::from(2));
+        let mut env = Environment::new(globals);
+
+        let mut scope = MapBindings::default();
+        scope.insert("A", Value::from(3));
+        env.push_scope(scope);
+        env.insert("c", Value::from(4));
+        assert_eq!(env.depth(), 2);
+
+        // inner scope shadows the global scope
+        assert_eq!(
+            env.get(&BindingsName::CaseInsensitive("a".to_string())),
+            Some(&Value::from(3))
+        );
+        assert_eq!(
+            env.get(&BindingsName::CaseSensitive("a".to_string())),
+            Some(&Value::from(1))
+        );
+        assert_eq!(
+            env.get(&BindingsName::CaseSensitive("b".to_string())),
+            Some(&Value::from(2))
+        );
+        assert_eq!(
+            env.get(&BindingsName::CaseSensitive("c".to_string())),
+            Some(&Value::from(4))
+        );
+
+        let scope = env.pop_scope().expect("inner scope");
+        assert_eq!(scope.len(), 2);
+        assert_eq!(
+            env.get(&BindingsName::CaseInsensitive("a".to_string())),
+            Some(&Value::from(1))
+        );
+        assert_eq!(env.get(&BindingsName::CaseSensitive("c".to_string())), None);
+
+        // the global scope is never popped
+        assert!(env.pop_scope().is_none());
+        assert_eq!(env.depth(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bindings_insert_panics_same_string() {
+        let mut bindings = MapBindings::default();
+        bindings.insert("foo", Value::from(1));
+        bindings.insert("foo", Value::from(2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bindings_insert_panics_case_insensitive_string() {
+        let mut bindings = MapBindings::default();
+        bindings.insert("foo", Value::from(1));
+        bindings.insert("FOO", Value::from(2));
+    }
+}
@@ -12,11 +12,13 @@ use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::{Decimal as RustDecimal, Decimal};
 
 mod bag;
+mod bindings;
 mod datetime;
 mod list;
 mod tuple;
 
 pub use bag::*;
+pub use bindings::*;
 pub use datetime::*;
 pub use list::*;
 pub use tuple::*;
@@ -1024,6 +1026,27 @@ mod tests {
         assert_eq!(pairs.next(), None);
     }
 
+    #[test]
+    fn tuple_positional_access() {
+        let mut tuple = tuple![("b", 1), ("A", 2), ("c", 3)];
+        assert_eq!(tuple.attrs().collect::<Vec<_>>(), vec!["b", "A", "c"]);
+        assert_eq!(tuple.get_at(1), Some(("A", &Value::from(2))));
+        assert_eq!(tuple.value_at(2), Some(&Value::from(3)));
+        assert_eq!(tuple.get_at(3), None);
+        assert_eq!(
+            tuple.index_of(&BindingsName::CaseInsensitive("a".to_string())),
+            Some(1)
+        );
+        assert_eq!(
+            tuple.index_of(&BindingsName::CaseSensitive("a".to_string())),
+            None
+        );
+
+        tuple.remove(&BindingsName::CaseSensitive("b".to_string()));
+        assert_eq!(tuple.get_at(0), Some(("A", &Value::from(2))));
+        assert_eq!(tuple, tuple![("c", 3), ("A", 2)]);
+    }
+
     #[test]
     fn partiql_value_ordering() {
         // TODO: some additional checking can be included in the ordering testing
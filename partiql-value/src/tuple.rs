@@ -5,7 +5,6 @@ use std::cmp::Ordering;
 use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
 use std::iter::zip;
-use std::vec;
 
 use unicase::UniCase;
 
@@ -13,6 +12,14 @@ use crate::{BindingsName, Value};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// A PartiQL tuple.
+///
+/// Attributes are kept in the order they were inserted, so a tuple output by a `SELECT` has its
+/// attributes in the order of the `SELECT` list (omitting any attribute whose value is `MISSING`).
+/// Besides by name, attributes can therefore be accessed positionally, e.g., with
+/// [`Tuple::get_at`], by consumers which treat tuples as table rows.
+///
+/// Equality, ordering and hashing of tuples do not depend upon the order of their attributes.
 #[derive(Default, Eq, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Tuple {
@@ -21,10 +28,10 @@ pub struct Tuple {
 }
 
 impl Tuple {
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Tuple {
-            attrs: vec![],
-            vals: vec![],
+            attrs: Vec::new(),
+            vals: Vec::new(),
         }
     }
 
@@ -59,48 +66,47 @@ impl Tuple {
             .collect()
     }
 
+    /// The position of the first attribute named `attr`, if any.
     #[inline]
-    pub fn get(&self, attr: &BindingsName) -> Option<&Value> {
+    pub fn index_of(&self, attr: &BindingsName) -> Option<usize> {
         match attr {
-            BindingsName::CaseSensitive(s) => match self.attrs.iter().position(|a| a.as_str() == s)
-            {
-                Some(i) => Some(&self.vals[i]),
-                _ => None,
-            },
-            BindingsName::CaseInsensitive(s) => match self
+            BindingsName::CaseSensitive(s) => self.attrs.iter().position(|a| a.as_str() == s),
+            BindingsName::CaseInsensitive(s) => self
                 .attrs
                 .iter()
-                .position(|a| UniCase::<&String>::from(a) == UniCase::<&String>::from(s))
-            {
-                Some(i) => Some(&self.vals[i]),
-                _ => None,
-            },
+                .position(|a| UniCase::<&String>::from(a) == UniCase::<&String>::from(s)),
         }
     }
 
+    #[inline]
+    pub fn get(&self, attr: &BindingsName) -> Option<&Value> {
+        self.index_of(attr).map(|i| &self.vals[i])
+    }
+
+    /// The attribute and value at position `idx`, if any.
+    #[inline]
+    pub fn get_at(&self, idx: usize) -> Option<(&str, &Value)> {
+        self.attrs.get(idx).map(|a| (a.as_str(), &self.vals[idx]))
+    }
+
+    /// The value at position `idx`, if any.
+    #[inline]
+    pub fn value_at(&self, idx: usize) -> Option<&Value> {
+        self.vals.get(idx)
+    }
+
     #[inline]
     pub fn remove(&mut self, attr: &BindingsName) -> Option<Value> {
-        match attr {
-            BindingsName::CaseSensitive(s) => match self.attrs.iter().position(|a| a.as_str() == s)
-            {
-                Some(i) => {
-                    self.attrs.remove(i);
-                    Some(self.vals.remove(i))
-                }
-                _ => None,
-            },
-            BindingsName::CaseInsensitive(s) => match self
-                .attrs
-                .iter()
-                .position(|a| UniCase::<&String>::from(a) == UniCase::<&String>::from(s))
-            {
-                Some(i) => {
-                    self.attrs.remove(i);
-                    Some(self.vals.remove(i))
-                }
-                _ => None,
-            },
-        }
+        self.index_of(attr).map(|i| {
+            self.attrs.remove(i);
+            self.vals.remove(i)
+        })
+    }
+
+    /// The tuple's attribute names, in order.
+    #[inline]
+    pub fn attrs(&self) -> impl Iterator<Item = &str> + Clone {
+        self.attrs.iter().map(String::as_str)
     }
 
     #[inline]
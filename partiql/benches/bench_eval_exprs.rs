@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use partiql_ast::arena::Arena;
+use partiql_catalog::{Catalog, PartiqlCatalog};
+use rand::{Rng, SeedableRng};
+
+use partiql_eval::env::basic::MapBindings;
+use partiql_eval::eval::EvalPlan;
+use partiql_eval::plan::{EvaluationMode, EvaluatorPlanner};
+use partiql_logical::{BindingsOp, LogicalPlan};
+use partiql_logical_planner::LogicalPlanner;
+
+use partiql_parser::Parser;
+use partiql_value::{tuple, Bag, Value};
+
+// Benchmarks the evaluation of TPC-H style queries, which filter, project, and aggregate
+// arithmetic and comparison expressions over attributes of 10000 rows of `lineitem` tuples,
+// each of 10 attributes.
+
+fn lineitem_data() -> Vec<Value> {
+    // seed the rng with a known value to assure same data across runs
+    let mut rng = rand::rngs::StdRng::from_seed([42; 32]);
+    let flags = ["A", "N", "R"];
+    let statuses = ["F", "O"];
+
+    (0..10_000)
+        .map(|key| {
+            let quantity: i64 = rng.gen_range(1..=50);
+            let price: f64 = rng.gen_range(900.0..105_000.0);
+            let discount = f64::from(rng.gen_range(0..=10)) / 100.0;
+            let tax = f64::from(rng.gen_range(0..=8)) / 100.0;
+            tuple![
+                ("l_orderkey", key / 4),
+                ("l_partkey", rng.gen_range(1..=2000)),
+                ("l_suppkey", rng.gen_range(1..=100)),
+                ("l_linenumber", key % 4 + 1),
+                ("l_quantity", quantity),
+                ("l_extendedprice", price),
+                ("l_discount", discount),
+                ("l_tax", tax),
+                ("l_returnflag", flags[rng.gen_range(0..flags.len())]),
+                ("l_linestatus", statuses[rng.gen_range(0..statuses.len())]),
+            ]
+            .into()
+        })
+        .collect()
+}
+
+fn data() -> MapBindings<Value> {
+    let data = tuple![("lineitem", Bag::from(lineitem_data()))];
+
+    data.into()
+}
+
+/// Forecasting revenue change, after TPC-H Q6.
+const QUERY_FILTER: &str = "
+            SELECT l.l_orderkey AS k, l.l_extendedprice * l.l_discount AS revenue
+            FROM lineitem AS l
+            WHERE l.l_discount >= 0.05 AND l.l_discount <= 0.07 AND l.l_quantity < 24
+            ";
+
+/// Pricing summary, after TPC-H Q1.
+const QUERY_AGGREGATE: &str = "
+            SELECT flag AS flag, status AS status,
+                   SUM(l.l_quantity) AS sum_qty,
+                   SUM(l.l_extendedprice * (1 - l.l_discount) * (1 + l.l_tax)) AS sum_charge,
+                   COUNT(*) AS count_order
+            FROM lineitem AS l
+            WHERE l.l_orderkey % 7 <> 3 OR NOT l.l_linenumber = 1
+            GROUP BY l.l_returnflag AS flag, l.l_linestatus AS status
+            ";
+
+#[inline]
+fn compile(catalog: &dyn Catalog, text: &str) -> LogicalPlan<BindingsOp> {
+    let arena = Arena::new();
+    let parsed = Parser::default()
+        .parse(text, &arena)
+        .expect("Expect no parse error");
+    let planner = LogicalPlanner::new(catalog);
+    planner.lower(&parsed).expect("Expect no lower error")
+}
+#[inline]
+fn plan(catalog: &dyn Catalog, logical: &LogicalPlan<BindingsOp>) -> EvalPlan {
+    EvaluatorPlanner::new(EvaluationMode::Permissive, catalog)
+        .compile(logical)
+        .expect("Expect no plan error")
+}
+#[inline]
+pub(crate) fn evaluate(mut eval: EvalPlan, bindings: MapBindings<Value>) -> Value {
+    if let Ok(out) = eval.execute_mut(bindings) {
+        out.result
+    } else {
+        Value::Missing
+    }
+}
+
+/// benchmark evaluation of queries that
+/// filter, project, and aggregate expressions over attributes
+/// of 10000 rows of tuples of 10 attributes
+fn bench_eval(c: &mut Criterion) {
+    let catalog = PartiqlCatalog::default();
+
+    let compiled_filter = compile(&catalog, QUERY_FILTER);
+    let compiled_aggregate = compile(&catalog, QUERY_AGGREGATE);
+
+    let bindings = data();
+
+    c.bench_function("eval-filter", |b| {
+        // the data is cloned for each evaluation outside of the measured time
+        b.iter_batched(
+            || bindings.clone(),
+            |bindings| {
+                let plan = plan(&catalog, &compiled_filter);
+                evaluate(black_box(plan), black_box(bindings))
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("eval-aggregate", |b| {
+        // the data is cloned for each evaluation outside of the measured time
+        b.iter_batched(
+            || bindings.clone(),
+            |bindings| {
+                let plan = plan(&catalog, &compiled_aggregate);
+                evaluate(black_box(plan), black_box(bindings))
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group! {
+    name = eval;
+    config = Criterion::default().measurement_time(Duration::new(5, 0));
+    targets = bench_eval
+}
+
+criterion_main!(eval);
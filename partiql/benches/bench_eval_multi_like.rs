@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use itertools::Itertools;
+use partiql_ast::arena::Arena;
 use partiql_catalog::{Catalog, PartiqlCatalog};
 use rand::{Rng, SeedableRng};
 
@@ -331,8 +332,8 @@ const QUERY_30: &str = "
             ";
 
 #[inline]
-fn parse(text: &str) -> ParserResult {
-    Parser::default().parse(text)
+fn parse<'a>(text: &'a str, arena: &'a Arena) -> ParserResult<'a> {
+    Parser::default().parse(text, arena)
 }
 #[inline]
 fn compile(catalog: &dyn Catalog, parsed: &partiql_parser::Parsed) -> LogicalPlan<BindingsOp> {
@@ -358,16 +359,23 @@ pub(crate) fn evaluate(mut eval: EvalPlan, bindings: MapBindings<Value>) -> Valu
 /// filter against 1, 15, or 30 `OR`ed `LIKE` expressions
 /// over 10201 rows of tuples containing an id and a string
 fn bench_parse(c: &mut Criterion) {
-    let parsed_1 = parse(QUERY_1);
+    let arena = Arena::new();
+    let parsed_1 = parse(QUERY_1, &arena);
     assert!(parsed_1.is_ok());
-    let parsed_15 = parse(QUERY_15);
+    let parsed_15 = parse(QUERY_15, &arena);
     assert!(parsed_15.is_ok());
-    let parsed_30 = parse(QUERY_30);
+    let parsed_30 = parse(QUERY_30, &arena);
     assert!(parsed_30.is_ok());
 
-    c.bench_function("parse-1", |b| b.iter(|| parse(black_box(QUERY_1))));
-    c.bench_function("parse-15", |b| b.iter(|| parse(black_box(QUERY_15))));
-    c.bench_function("parse-30", |b| b.iter(|| parse(black_box(QUERY_30))));
+    c.bench_function("parse-1", |b| {
+        b.iter(|| parse(black_box(QUERY_1), &Arena::new()).is_ok())
+    });
+    c.bench_function("parse-15", |b| {
+        b.iter(|| parse(black_box(QUERY_15), &Arena::new()).is_ok())
+    });
+    c.bench_function("parse-30", |b| {
+        b.iter(|| parse(black_box(QUERY_30), &Arena::new()).is_ok())
+    });
 }
 
 /// benchmark compiling of queries that
@@ -376,9 +384,10 @@ fn bench_parse(c: &mut Criterion) {
 fn bench_compile(c: &mut Criterion) {
     let catalog = PartiqlCatalog::default();
 
-    let parsed_1 = parse(QUERY_1).unwrap();
-    let parsed_15 = parse(QUERY_15).unwrap();
-    let parsed_30 = parse(QUERY_30).unwrap();
+    let arena = Arena::new();
+    let parsed_1 = parse(QUERY_1, &arena).unwrap();
+    let parsed_15 = parse(QUERY_15, &arena).unwrap();
+    let parsed_30 = parse(QUERY_30, &arena).unwrap();
 
     let compiled_1 = compile(&catalog, &parsed_1);
     assert_eq!(compiled_1.operator_count(), 4);
@@ -404,9 +413,10 @@ fn bench_compile(c: &mut Criterion) {
 fn bench_plan(c: &mut Criterion) {
     let catalog = PartiqlCatalog::default();
 
-    let compiled_1 = compile(&catalog, &parse(QUERY_1).unwrap());
-    let compiled_15 = compile(&catalog, &parse(QUERY_15).unwrap());
-    let compiled_30 = compile(&catalog, &parse(QUERY_30).unwrap());
+    let arena = Arena::new();
+    let compiled_1 = compile(&catalog, &parse(QUERY_1, &arena).unwrap());
+    let compiled_15 = compile(&catalog, &parse(QUERY_15, &arena).unwrap());
+    let compiled_30 = compile(&catalog, &parse(QUERY_30, &arena).unwrap());
 
     let _planned_1 = plan(&catalog, &compiled_1);
     let _planned_15 = plan(&catalog, &compiled_15);
@@ -429,9 +439,10 @@ fn bench_plan(c: &mut Criterion) {
 fn bench_eval(c: &mut Criterion) {
     let catalog = PartiqlCatalog::default();
 
-    let compiled_1 = compile(&catalog, &parse(QUERY_1).unwrap());
-    let compiled_15 = compile(&catalog, &parse(QUERY_15).unwrap());
-    let compiled_30 = compile(&catalog, &parse(QUERY_30).unwrap());
+    let arena = Arena::new();
+    let compiled_1 = compile(&catalog, &parse(QUERY_1, &arena).unwrap());
+    let compiled_15 = compile(&catalog, &parse(QUERY_15, &arena).unwrap());
+    let compiled_30 = compile(&catalog, &parse(QUERY_30, &arena).unwrap());
 
     let bindings = data();
 
@@ -0,0 +1,345 @@
+//! Structured explanations of how a query is planned and evaluated.
+//!
+//! An [`Explain`] document summarizes a query's AST, its logical plan, the physical operators
+//! chosen to evaluate it and, optionally, runtime statistics of an evaluation. The document is
+//! serializable (e.g., to JSON with [`Explain::to_json`]) for consumption by tools such as UIs or
+//! regression tests; its schema is versioned by [`Explain::version`].
+//!
+//! ```
+//! use partiql::eval::env::basic::MapBindings;
+//! use partiql::eval::plan::{EvaluationMode, EvaluatorPlanner};
+//! use partiql::explain::Explain;
+//! use partiql::logical_planner::LogicalPlanner;
+//! use partiql::parser::Parser;
+//!
+//! let catalog = partiql::catalog::PartiqlCatalog::default();
+//! let arena = partiql::ast::arena::Arena::new();
+//! let parsed = Parser::default()
+//!     .parse("SELECT x.a FROM << {'a': 1}, {'a': 2} >> AS x WHERE x.a > 1", &arena)
+//!     .expect("parse");
+//! let logical = LogicalPlanner::new(&catalog).lower(&parsed).expect("lower");
+//! let mut physical = EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+//!     .compile(&logical)
+//!     .expect("compile");
+//! let (result, stats) = physical.execute_mut_with_stats(MapBindings::default());
+//! assert!(result.is_ok());
+//!
+//! let explain = Explain::new(&parsed.ast, &logical, &physical).with_stats(&stats);
+//! let json = explain.to_json();
+//! assert!(json.contains(r#""op": "Filter""#));
+//! ```
+
+use crate::ast::ast;
+use crate::ast::visit::{Traverse, Visit, Visitor};
+use crate::eval::eval::{EvalPlan, EvalStats};
+use crate::logical::{BindingsOp, LogicalPlan};
+use serde::Serialize;
+
+/// The current version of the [`Explain`] document schema.
+pub const EXPLAIN_VERSION: u32 = 1;
+
+/// A structured explanation of a query's plans and, optionally, of its evaluation.
+#[derive(Debug, Clone, Serialize)]
+pub struct Explain {
+    /// The version of the document schema; see [`EXPLAIN_VERSION`].
+    pub version: u32,
+    pub ast: AstSummary,
+    pub logical: PlanSummary,
+    pub physical: PlanSummary,
+    /// Runtime statistics, if the document was created [`with_stats`](Explain::with_stats).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<StatsSummary>,
+}
+
+/// Counts of the constructs of a query's AST.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AstSummary {
+    /// The number of AST nodes.
+    pub nodes: usize,
+    /// The number of `SELECT` clauses, including those of subqueries.
+    pub selects: usize,
+    pub joins: usize,
+    /// The number of (possibly nested) subqueries.
+    pub subqueries: usize,
+}
+
+/// The operators of a plan and the flows between them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlanSummary {
+    pub operators: Vec<OperatorSummary>,
+    pub flows: Vec<FlowSummary>,
+}
+
+/// An operator of a plan.
+#[derive(Debug, Clone, Serialize)]
+pub struct OperatorSummary {
+    pub id: usize,
+    /// The name of the operator, e.g., `Scan` or `Filter`.
+    pub op: String,
+    /// A rendering of the operator and its arguments, e.g., `Filter(Gt(x.a, 1))`.
+    pub detail: String,
+}
+
+/// A flow of values from one operator of a plan to another.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlowSummary {
+    pub src: usize,
+    pub dst: usize,
+    /// The input of the destination operator the flow feeds (e.g., the right side of a join).
+    pub branch: u8,
+}
+
+/// Runtime statistics of an evaluation.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsSummary {
+    pub elapsed_micros: u128,
+    pub operators: Vec<OperatorStatsSummary>,
+}
+
+/// Runtime statistics of a physical operator.
+#[derive(Debug, Clone, Serialize)]
+pub struct OperatorStatsSummary {
+    /// The id of the operator within the physical plan.
+    pub id: usize,
+    pub op: String,
+    pub elapsed_micros: u128,
+    /// The number of values output by the operator, if it output a collection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<usize>,
+}
+
+impl Explain {
+    /// Explains the query `ast`, planned as `logical` and compiled into `physical`.
+    pub fn new(
+        ast: &ast::AstNode<ast::TopLevelQuery>,
+        logical: &LogicalPlan<BindingsOp>,
+        physical: &EvalPlan,
+    ) -> Self {
+        Explain {
+            version: EXPLAIN_VERSION,
+            ast: AstSummary::new(ast),
+            logical: logical_summary(logical),
+            physical: physical_summary(physical),
+            stats: None,
+        }
+    }
+
+    /// Adds the runtime statistics of an evaluation of the explained physical plan.
+    pub fn with_stats(mut self, stats: &EvalStats) -> Self {
+        self.stats = Some(StatsSummary {
+            elapsed_micros: stats.elapsed.as_micros(),
+            operators: stats
+                .operators
+                .iter()
+                .map(|op| OperatorStatsSummary {
+                    id: op.id,
+                    op: op.operator.to_string(),
+                    elapsed_micros: op.elapsed.as_micros(),
+                    rows: op.rows,
+                })
+                .collect(),
+        });
+        self
+    }
+
+    /// Serializes the explanation as a (pretty-printed) JSON document.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Explain serializes to JSON")
+    }
+}
+
+impl AstSummary {
+    fn new(ast: &ast::AstNode<ast::TopLevelQuery>) -> Self {
+        let mut counter = AstCounter::default();
+        ast.visit(&mut counter);
+        counter.summary
+    }
+}
+
+#[derive(Default)]
+struct AstCounter {
+    query_depth: usize,
+    summary: AstSummary,
+}
+
+impl<'ast> Visitor<'ast> for AstCounter {
+    fn enter_ast_node(&mut self, _id: ast::NodeId) -> Traverse {
+        self.summary.nodes += 1;
+        Traverse::Continue
+    }
+
+    fn enter_query(&mut self, _query: &'ast ast::Query) -> Traverse {
+        if self.query_depth > 0 {
+            self.summary.subqueries += 1;
+        }
+        self.query_depth += 1;
+        Traverse::Continue
+    }
+
+    fn exit_query(&mut self, _query: &'ast ast::Query) -> Traverse {
+        self.query_depth -= 1;
+        Traverse::Continue
+    }
+
+    fn enter_select(&mut self, _select: &'ast ast::Select) -> Traverse {
+        self.summary.selects += 1;
+        Traverse::Continue
+    }
+
+    fn enter_join(&mut self, _join: &'ast ast::Join) -> Traverse {
+        self.summary.joins += 1;
+        Traverse::Continue
+    }
+}
+
+fn logical_summary(plan: &LogicalPlan<BindingsOp>) -> PlanSummary {
+    let operators = plan
+        .operators_by_id()
+        .map(|(id, op)| {
+            let name = match op {
+                BindingsOp::Scan(_) => "Scan",
+                BindingsOp::Pivot(_) => "Pivot",
+                BindingsOp::Unpivot(_) => "Unpivot",
+                BindingsOp::Let(_) => "Let",
+                BindingsOp::Filter(_) => "Filter",
+                BindingsOp::OrderBy(_) => "OrderBy",
+                BindingsOp::LimitOffset(_) => "LimitOffset",
+                BindingsOp::Join(_) => "Join",
+                BindingsOp::BagOp(_) => "BagOp",
+                BindingsOp::Project(_) => "Project",
+                BindingsOp::ProjectAll => "ProjectAll",
+                BindingsOp::ProjectValue(_) => "ProjectValue",
+                BindingsOp::ExprQuery(_) => "ExprQuery",
+                BindingsOp::Distinct => "Distinct",
+                BindingsOp::GroupBy(_) => "GroupBy",
+                BindingsOp::Having(_) => "Having",
+                BindingsOp::Window(_) => "Window",
+                BindingsOp::Sink => "Sink",
+            };
+            OperatorSummary {
+                id: id.index(),
+                op: name.to_string(),
+                detail: op.to_string(),
+            }
+        })
+        .collect();
+    let flows = plan
+        .flows()
+        .iter()
+        .map(|(src, dst, branch)| FlowSummary {
+            src: src.index(),
+            dst: dst.index(),
+            branch: *branch,
+        })
+        .collect();
+    PlanSummary { operators, flows }
+}
+
+fn physical_summary(plan: &EvalPlan) -> PlanSummary {
+    let operators = plan
+        .operators()
+        .map(|(id, op)| OperatorSummary {
+            id,
+            op: op.name().to_string(),
+            detail: plan.operator_detail(id).unwrap_or_default().to_string(),
+        })
+        .collect();
+    let flows = plan
+        .flows()
+        .map(|(src, dst, branch)| FlowSummary { src, dst, branch })
+        .collect();
+    PlanSummary { operators, flows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::env::basic::MapBindings;
+    use crate::eval::plan::{EvaluationMode, EvaluatorPlanner};
+    use crate::logical_planner::LogicalPlanner;
+
+    #[test]
+    fn explain_json() {
+        let catalog = crate::catalog::PartiqlCatalog::default();
+        let arena = crate::ast::arena::Arena::new();
+        let parsed = crate::parser::Parser::default()
+            .parse(
+                "SELECT x.a FROM << {'a': 1}, {'a': 2}, {'a': 3} >> AS x \
+                 WHERE x.a IN (SELECT VALUE y FROM [2, 3] AS y)",
+                &arena,
+            )
+            .expect("parse");
+        let logical = LogicalPlanner::new(&catalog).lower(&parsed).expect("lower");
+        let mut physical = EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+            .compile(&logical)
+            .expect("compile");
+        let (result, stats) = physical.execute_mut_with_stats(MapBindings::default());
+        assert!(result.is_ok());
+
+        let explain = Explain::new(&parsed.ast, &logical, &physical).with_stats(&stats);
+        assert_eq!(explain.ast.selects, 2);
+        assert_eq!(explain.ast.subqueries, 1);
+        assert_eq!(explain.ast.joins, 0);
+
+        let mut ops: Vec<_> = explain.logical.operators.iter().map(|op| &op.op).collect();
+        ops.sort();
+        assert_eq!(ops, vec!["Filter", "Project", "Scan", "Sink"]);
+        assert_eq!(explain.logical.flows.len(), 3);
+        assert_eq!(explain.physical.operators.len(), 4);
+        let filter = explain
+            .logical
+            .operators
+            .iter()
+            .find(|op| op.op == "Filter")
+            .expect("logical filter");
+        assert!(filter.detail.starts_with("Filter(In(x.a, "));
+        let details: Vec<_> = explain
+            .physical
+            .operators
+            .iter()
+            .map(|op| op.detail.as_str())
+            .collect();
+        assert!(details.contains(&"Scan(<<{ a: 1 }, { a: 2 }, { a: 3 }>> AS x)"));
+        assert!(details.contains(&"Sink"));
+
+        let stats = explain.stats.as_ref().expect("stats");
+        let scan = stats
+            .operators
+            .iter()
+            .find(|op| op.op == "EvalScan")
+            .expect("scan stats");
+        assert_eq!(scan.rows, Some(3));
+        let filter = stats
+            .operators
+            .iter()
+            .find(|op| op.op == "EvalFilter")
+            .expect("filter stats");
+        assert_eq!(filter.rows, Some(2));
+
+        let json: serde_json::Value = serde_json::from_str(&explain.to_json()).expect("json");
+        assert_eq!(json["version"], EXPLAIN_VERSION);
+        assert_eq!(
+            json["logical"]["operators"].as_array().map(Vec::len),
+            Some(4)
+        );
+        assert_eq!(json["stats"]["operators"].as_array().map(Vec::len), Some(4));
+    }
+
+    #[test]
+    fn explain_without_stats() {
+        let catalog = crate::catalog::PartiqlCatalog::default();
+        let arena = crate::ast::arena::Arena::new();
+        let parsed = crate::parser::Parser::default()
+            .parse("SELECT * FROM a, b", &arena)
+            .expect("parse");
+        let logical = LogicalPlanner::new(&catalog).lower(&parsed).expect("lower");
+        let physical = EvaluatorPlanner::new(EvaluationMode::Permissive, &catalog)
+            .compile(&logical)
+            .expect("compile");
+
+        let explain = Explain::new(&parsed.ast, &logical, &physical);
+        assert_eq!(explain.ast.joins, 1);
+        let json: serde_json::Value = serde_json::from_str(&explain.to_json()).expect("json");
+        assert!(json.get("stats").is_none());
+    }
+}
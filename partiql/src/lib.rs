@@ -1,3 +1,39 @@
+//! PartiQL in Rust.
+//!
+//! This crate re-exports the PartiQL crates enabled by its feature flags, allowing embedders to
+//! compile only what they need:
+//!
+//! - `lexer`: lexing of PartiQL text (i.e., the lexer of [`parser`] only)
+//! - `parser`: parsing of PartiQL text into an AST (i.e., [`ast`] and [`parser`])
+//! - `planner`: lowering of ASTs into logical plans (adds [`value`], [`catalog`], [`logical`] and
+//!   [`logical_planner`])
+//! - `eval`: evaluation of logical plans (adds [`eval`])
+//! - `explain`: structured (e.g., JSON) explanations of query plans & evaluations (adds
+//!   [`explain`])
+//! - `ion`: support for embedded Ion literals
+//! - `serde`: [serde](https://serde.rs) support for ASTs, values and plans
+//! - `full` (default): `eval`, `explain` and `ion`
+
+#[cfg(feature = "parser")]
+pub use partiql_ast as ast;
+#[cfg(feature = "lexer")]
+pub use partiql_parser as parser;
+
+#[cfg(feature = "planner")]
+pub use partiql_catalog as catalog;
+#[cfg(feature = "planner")]
+pub use partiql_logical as logical;
+#[cfg(feature = "planner")]
+pub use partiql_logical_planner as logical_planner;
+#[cfg(feature = "planner")]
+pub use partiql_value as value;
+
+#[cfg(feature = "eval")]
+pub use partiql_eval as eval;
+
+#[cfg(feature = "explain")]
+pub mod explain;
+
 #[cfg(test)]
 mod tests {
     #[test]